@@ -76,6 +76,12 @@ let g:clap_enable_icon = get(g:, 'clap_enable_icon', exists('g:loaded_webdevicon
 let g:clap_popup_border = get(g:, 'clap_popup_border', has('nvim') ? 'single' : 'rounded')
 let g:clap_preview_size = get(g:, 'clap_preview_size', 5)
 let g:clap_preview_direction = get(g:, 'clap_preview_direction', 'AUTO')
+let g:clap_preview_highlight_query = get(g:, 'clap_preview_highlight_query', v:true)
+let g:clap_copy_formats = get(g:, 'clap_copy_formats',
+      \ { '*': ['absolute-path', 'relative-path', 'path-lnum', 'markdown-link'] })
+let g:clap_copy_register = get(g:, 'clap_copy_register', has('clipboard') ? '+' : '"')
+let g:clap_copy_osc52 = get(g:, 'clap_copy_osc52', 'auto')
+let g:clap_file_info_columns = get(g:, 'clap_file_info_columns', v:false)
 let g:clap_insert_mode_only = get(g:, 'clap_insert_mode_only', v:false)
 let g:clap_background_shadow_blend = get(g:, 'clap_background_shadow_blend', 50)
 let g:clap_providers_relaunch_code = get(g:, 'clap_providers_relaunch_code', '@@')
@@ -153,9 +159,21 @@ function! clap#_init() abort
   " Ensure the filetype is empty on init.
   " Each provider can set its own syntax for the highlight purpose.
   call g:clap.display.setbufvar('&filetype', '')
+
+  " The truncation widths are derived from the display window, which can be resized or
+  " reflowed by a new font in GUI Neovim during the session.
+  augroup ClapDisplayMetrics
+    autocmd!
+    autocmd VimResized * call clap#client#notify_provider('display_metrics_changed')
+    autocmd OptionSet guifont,ambiwidth call clap#client#notify_provider('display_metrics_changed', {'force': v:true})
+    if exists('##WinResized')
+      autocmd WinResized * call clap#client#notify_provider('display_metrics_changed')
+    endif
+  augroup END
 endfunction
 
 function! clap#_exit() abort
+  silent! autocmd! ClapDisplayMetrics
   call g:clap.provider.jobstop()
   call clap#maple#clean_up()
 
@@ -207,6 +225,65 @@ function! clap#register(provider_id, provider_info) abort
   let g:clap.registrar[a:provider_id] = a:provider_info
 endfunction
 
+let s:user_provider_specs = get(s:, 'user_provider_specs', {})
+
+" Registers a provider at runtime, e.g., lazily from a plugin manager.
+"
+" spec: Dict with the fields of `g:clap_provider_foo`, plus the optional
+" `preview` ('file', 'grep', 'url', 'none' or {'command': 'cmd {}'}), `preview_position`
+" ('center', 'top' or {'offset': n}) and `action` Dict whose names are reported
+" to maple along with the source.
+function! clap#register_provider(provider_id, spec) abort
+  let provider_info = copy(a:spec)
+  let preview = has_key(provider_info, 'preview') ? remove(provider_info, 'preview') : 'none'
+  let preview_position = has_key(provider_info, 'preview_position')
+        \ ? remove(provider_info, 'preview_position') : v:null
+
+  if !s:inject_default_impl_is_ok(provider_info) || !s:validate_provider(provider_info)
+    return
+  endif
+
+  if preview isnot# 'none' && !has_key(provider_info, 'on_move_async')
+    let provider_info.on_move_async = function('clap#impl#on_move#async')
+  endif
+
+  let g:clap.registrar[a:provider_id] = provider_info
+
+  let backend_spec = {
+        \ 'id': a:provider_id,
+        \ 'preview': preview,
+        \ 'actions': keys(get(provider_info, 'action', {})),
+        \ }
+  if preview_position isnot v:null
+    let backend_spec.preview_position = preview_position
+  endif
+  " Funcref source is evaluated by Vim on demand.
+  let source_ty = type(get(provider_info, 'source', v:null))
+  if source_ty == v:t_string || source_ty == v:t_list
+    let backend_spec.source = provider_info.source
+  endif
+  let s:user_provider_specs[a:provider_id] = backend_spec
+
+  call clap#client#request_async('provider/register', v:null, backend_spec)
+endfunction
+
+function! clap#unregister_provider(provider_id) abort
+  if has_key(g:clap.registrar, a:provider_id)
+    call remove(g:clap.registrar, a:provider_id)
+  endif
+  if has_key(s:user_provider_specs, a:provider_id)
+    call remove(s:user_provider_specs, a:provider_id)
+    call clap#client#request_async('provider/unregister', v:null, {'id': a:provider_id})
+  endif
+endfunction
+
+" Sends the providers registered before maple is (re)started.
+function! clap#sync_user_providers() abort
+  for backend_spec in values(s:user_provider_specs)
+    call clap#client#request_async('provider/register', v:null, backend_spec)
+  endfor
+endfunction
+
 function! s:validate_provider(registration_info) abort
   " Every provider should specify the sink option.
   if !has_key(a:registration_info, 'sink') && !has_key(get(a:registration_info, 'mappings', {}), "<CR>")
@@ -8,7 +8,7 @@ let s:ACTIONS_TITLE_KEY = 'title'
 
 " `confirm()` based action menu, this is deprecated now.
 function! s:invoke_action() abort
-  let provider_action = g:clap.provider._().action
+  let provider_action = clap#action#provider_actions()
   if has_key(provider_action, s:ACTIONS_TITLE_KEY)
     let title = provider_action[s:ACTIONS_TITLE_KEY]()
   else
@@ -29,8 +29,16 @@ function! s:invoke_action() abort
   endif
 endfunction
 
+" Returns the provider actions along with the builtin copy and sort actions.
+function! clap#action#provider_actions() abort
+  let actions = copy(get(g:clap.provider._(), 'action', {}))
+  call extend(actions, clap#copy#actions(), 'keep')
+  call extend(actions, clap#pinned#actions(), 'keep')
+  return extend(actions, clap#file_info#actions(), 'keep')
+endfunction
+
 function! clap#action#invoke() abort
-  if !has_key(g:clap.provider._(), 'action')
+  if empty(clap#action#provider_actions())
     call clap#helper#echo_warn('action not implemented in provider '.g:clap.provider.id)
     return ''
   endif
@@ -17,6 +17,30 @@ function! clap#api#get_origin_line_at(lnum) abort
   endif
 endfunction
 
+" Returns the lines of the loaded buffer for `path` if it's modified or not
+" backed by a file, e.g., `fugitive://`, v:null otherwise.
+function! clap#api#buffer_content(path) abort
+  let buffer = clap#api#buffer_changedtick(a:path)
+  return buffer is v:null ? v:null : getbufline(buffer[0], 1, '$')
+endfunction
+
+" Returns `[bufnr, changedtick]` of the loaded buffer for `path` if it's
+" modified or not backed by a file, e.g., `fugitive://`, v:null otherwise.
+function! clap#api#buffer_changedtick(path) abort
+  for info in getbufinfo({'bufloaded': 1})
+    if empty(info.name)
+      continue
+    endif
+    if info.name ==# a:path || fnamemodify(info.name, ':p') ==# a:path
+      if info.changed || info.name =~# '^\a[a-zA-Z0-9+.-]\+://'
+        return [info.bufnr, info.changedtick]
+      endif
+      return v:null
+    endif
+  endfor
+  return v:null
+endfunction
+
 if exists('*win_execute')
   function! clap#api#win_execute(winid, command) abort
     return win_execute(a:winid, a:command)
@@ -79,6 +103,20 @@ function! s:api.display_getcurline() abort
   return [g:clap.display.getcurline(), get(g:, '__clap_icon_added_by_maple', v:false)]
 endfunction
 
+" The leading icons of the lines are stripped by maple.
+function! s:api.display_get_origin_lines(start, end) abort
+  let end = min([a:end, g:clap.display.line_count()])
+  let lines = map(range(a:start, end), 'clap#api#get_origin_line_at(v:val)')
+  return [lines, get(g:, '__clap_icon_added_by_maple', v:false)]
+endfunction
+
+function! s:api.display_set_curlnum(lnum) abort
+  let lnum = min([a:lnum, g:clap.display.line_count()])
+  call g:clap.display.set_cursor(lnum, 1)
+  let g:__clap_display_curlnum = lnum
+  call clap#sign#toggle_cursorline()
+endfunction
+
 function! s:api.display_set_lines(lines) abort
   call g:clap.display.set_lines(a:lines)
 endfunction
@@ -158,22 +196,44 @@ function! s:api.verbose(cmd) abort
   return l:output
 endfunction
 
-function! s:api.set_initial_query(query) abort
-  if a:query ==# '@visual'
-    let query = clap#util#get_visual_selection()
+function! s:visual_selection() abort
+  let [lnum1, col1] = getpos("'<")[1:2]
+  let [lnum2, col2] = getpos("'>")[1:2]
+  let lines = getline(lnum1, lnum2)
+  if empty(lines)
+    return ''
+  endif
+  if visualmode() !=# 'V'
+    let lines[-1] = lines[-1][: col2 - (&selection ==# 'inclusive' ? 1 : 2)]
+    let lines[0] = lines[0][col1 - 1:]
+  endif
+  return join(lines, "\n")
+endfunction
+
+" Returns the raw text of the initial query source in the start window.
+"
+" source: '@visual', '<cword>' or '<cWORD>'.
+function! s:api.initial_query_source(source) abort
+  let s:initial_query_source = ''
+  if a:source ==# '@visual'
+    call win_execute(g:clap.start.winid, 'let s:initial_query_source = s:visual_selection()')
   else
-    let query = clap#util#expand(a:query)
+    call win_execute(g:clap.start.winid, 'let s:initial_query_source = expand(a:source)')
   endif
+  return s:initial_query_source
+endfunction
 
+" The query has been expanded by the backend.
+function! s:api.set_initial_query(query) abort
   if s:is_nvim
-    call feedkeys(query)
+    call feedkeys(a:query)
   else
-    call g:clap.input.set(query)
+    call g:clap.input.set(a:query)
     " Move the cursor to the end.
     call feedkeys("\<C-E>", 'xt')
   endif
 
-  return query
+  return a:query
 endfunction
 
 function! clap#api#call(method, args) abort
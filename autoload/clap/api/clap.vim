@@ -429,11 +429,55 @@ function! s:inject_base_api(dict) abort
   let dict.setbufvar_batch = function('s:_setbufvar_batch')
 endfunction
 
-function! s:matchaddpos(lnum) abort
+function! s:matchaddpos(lnum, ...) abort
   if exists('w:clap_preview_hi_id')
     call matchdelete(w:clap_preview_hi_id)
   endif
-  let w:clap_preview_hi_id = matchaddpos('Search', [[a:lnum]])
+  let w:clap_preview_hi_id = matchaddpos(a:0 > 0 ? a:1 : 'Search', [[a:lnum]])
+endfunction
+
+" `pos` is `[line_idx, start, length]` of the match, the 0-based line index and
+" the byte span, or empty to clear the previous match.
+function! s:matchaddpos_match(pos) abort
+  if exists('w:clap_preview_match_hi_id')
+    silent! call matchdelete(w:clap_preview_match_hi_id)
+    unlet w:clap_preview_match_hi_id
+  endif
+  if !empty(a:pos)
+    let [line_idx, start, length] = a:pos
+    let w:clap_preview_match_hi_id = matchaddpos('ClapPreviewMatch', [[line_idx + 1, start + 1, length]], 12)
+  endif
+endfunction
+
+function! s:matchadd_query_terms(pattern) abort
+  if exists('w:clap_preview_query_hi_id')
+    silent! call matchdelete(w:clap_preview_query_hi_id)
+    unlet w:clap_preview_query_hi_id
+  endif
+  if !empty(a:pattern)
+    let w:clap_preview_query_hi_id = matchadd('ClapPreviewQueryTerm', a:pattern, 11)
+  endif
+endfunction
+
+" Highlights the line ranges with their own syntax included on top of the
+" syntax of the preview buffer, e.g., the markdown cells of a notebook.
+function! s:add_syntax_regions(regions) abort
+  let included = {}
+  for region in a:regions
+    let cluster = 'ClapPreviewSyntax_'.substitute(region.syntax, '\W', '_', 'g')
+    if !has_key(included, cluster)
+      let current_syntax = get(b:, 'current_syntax', '')
+      unlet! b:current_syntax
+      try
+        execute 'silent! syntax include @'.cluster 'syntax/'.region.syntax.'.vim'
+      finally
+        let b:current_syntax = current_syntax
+      endtry
+      let included[cluster] = 1
+    endif
+    execute printf('syntax region %sRegion start=/\%%%dl/ end=/\%%%dl$/ keepend contains=@%s',
+          \ cluster, region.start, region.end, cluster)
+  endfor
 endfunction
 
 function! clap#api#clap#init() abort
@@ -468,13 +512,43 @@ function! clap#api#clap#init() abort
   endfunction
 
   if exists('*win_execute')
-    function! g:clap.preview.add_highlight(lnum) abort
-      call win_execute(g:clap.preview.winid, 'noautocmd call s:matchaddpos(a:lnum)')
+    function! g:clap.preview.add_highlight(lnum, ...) abort
+      call win_execute(g:clap.preview.winid, 'noautocmd call call("s:matchaddpos", [a:lnum] + a:000)')
+    endfunction
+
+    function! g:clap.preview.highlight_query_terms(pattern) abort
+      call win_execute(g:clap.preview.winid, 'noautocmd call s:matchadd_query_terms(a:pattern)')
+    endfunction
+
+    function! g:clap.preview.highlight_match(pos) abort
+      call win_execute(g:clap.preview.winid, 'noautocmd call s:matchaddpos_match(a:pos)')
+    endfunction
+
+    function! g:clap.preview.add_syntax_regions(regions) abort
+      call win_execute(g:clap.preview.winid, 'noautocmd call s:add_syntax_regions(a:regions)')
     endfunction
   else
-    function! g:clap.preview.add_highlight(lnum) abort
+    function! g:clap.preview.add_highlight(lnum, ...) abort
+      noautocmd call win_gotoid(g:clap.preview.winid)
+      call call('s:matchaddpos', [a:lnum] + a:000)
+      noautocmd call win_gotoid(g:clap.input.winid)
+    endfunction
+
+    function! g:clap.preview.highlight_query_terms(pattern) abort
+      noautocmd call win_gotoid(g:clap.preview.winid)
+      call s:matchadd_query_terms(a:pattern)
+      noautocmd call win_gotoid(g:clap.input.winid)
+    endfunction
+
+    function! g:clap.preview.highlight_match(pos) abort
+      noautocmd call win_gotoid(g:clap.preview.winid)
+      call s:matchaddpos_match(a:pos)
+      noautocmd call win_gotoid(g:clap.input.winid)
+    endfunction
+
+    function! g:clap.preview.add_syntax_regions(regions) abort
       noautocmd call win_gotoid(g:clap.preview.winid)
-      call s:matchaddpos(a:lnum)
+      call s:add_syntax_regions(a:regions)
       noautocmd call win_gotoid(g:clap.input.winid)
     endfunction
   endif
@@ -8,6 +8,10 @@ let s:req_id = get(s:, 'req_id', 0)
 let s:callbacks = get(s:, 'callbacks', {})
 let s:session_id = get(s:, 'session_id', 0)
 
+" Bumped whenever the RPC messages change incompatibly, see `stdio_server/handshake.rs`.
+let s:protocol_version = 1
+let s:min_backend_protocol_version = 1
+
 function! clap#client#handle(msg) abort
   let decoded = json_decode(a:msg)
 
@@ -87,6 +91,34 @@ function! clap#client#request_async(method, callback, ...) abort
   endif
 endfunction
 
+function! s:on_sync_response(req_id, result, error) abort
+  let s:sync_results[a:req_id] = a:error is v:null ? a:result : v:null
+endfunction
+
+" Blocks until the response is received, returns v:null on error or timeout.
+function! clap#client#request_sync(method, params, timeout) abort
+  if !clap#job#daemon#is_running()
+    return v:null
+  endif
+  let s:sync_results = get(s:, 'sync_results', {})
+  call s:request_async(a:method, a:params)
+  let req_id = s:req_id
+  let s:callbacks[req_id] = function('s:on_sync_response', [req_id])
+  if has('nvim')
+    call wait(a:timeout, {-> has_key(s:sync_results, req_id)}, 5)
+  else
+    let elapsed = 0
+    while !has_key(s:sync_results, req_id) && elapsed < a:timeout
+      sleep 5m
+      let elapsed += 5
+    endwhile
+  endif
+  if has_key(s:callbacks, req_id)
+    call remove(s:callbacks, req_id)
+  endif
+  return has_key(s:sync_results, req_id) ? remove(s:sync_results, req_id) : v:null
+endfunction
+
 function! clap#client#notify_on_init(...) abort
   if g:clap.display.winid < 0
     return
@@ -116,6 +148,22 @@ function! clap#client#notify_on_init(...) abort
   call s:notify_provider('new_provider', params)
 endfunction
 
+" Called by the backend on startup, returns the protocol version of the plugin.
+function! clap#client#handshake(backend_info) abort
+  let s:backend_info = a:backend_info
+  if get(a:backend_info, 'protocol_version', 0) < s:min_backend_protocol_version
+    call clap#helper#echo_warn(printf(
+          \ 'maple %s is outdated: protocol v%d is required but found v%d, please run `:Clap install-binary!` or rebuild it',
+          \ a:backend_info.version, s:min_backend_protocol_version, get(a:backend_info, 'protocol_version', 0)))
+  endif
+  return s:protocol_version
+endfunction
+
+" Returns the info reported by the backend in the handshake, empty if not started.
+function! clap#client#backend_info() abort
+  return get(s:, 'backend_info', {})
+endfunction
+
 """"""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""
 ""  Deprecated and unused in clap repo, but keep them to not break the users using old version.
 """"""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""""
@@ -4,22 +4,25 @@
 let s:save_cpo = &cpoptions
 set cpoptions&vim
 
-" Derived from fzf.vim
-function! s:get_history_list(type) abort
-  let max  = histnr(a:type)
-  let fmt  = ' %'.len(string(max)).'d '
-  let list = filter(map(range(1, max), 'histget("'. a:type .'", - v:val)'), '!empty(v:val)')
-  return list
+" Returns the history entries, the most recent first, which are deduplicated and
+" ranked by maple.
+function! clap#common_history#entries(type) abort
+  return filter(map(range(1, histnr(a:type)), 'histget(a:type, - v:val)'), '!empty(v:val)')
 endfunction
 
-function! clap#common_history#source(type) abort
-  let hist_list = s:get_history_list(a:type)
-  let hist_len = len(hist_list)
-  return map(hist_list, 'printf("%4d", hist_len - v:key)."  ".v:val')
+" Deletes the latest occurrence of entry from the history, returns 1 if deleted.
+function! clap#common_history#delete(type, entry) abort
+  for idx in range(histnr(a:type), 1, -1)
+    if histget(a:type, idx) ==# a:entry
+      return histdel(a:type, idx)
+    endif
+  endfor
+  return 0
 endfunction
 
 function! clap#common_history#sink(type, selected) abort
   let item = matchstr(a:selected, '\d\+\s\+\zs\(.*\)')
+  call clap#client#notify('__note_history', [a:type, item])
   if a:type ==# ':'
     call histadd(':', item)
     call execute(item, '')
@@ -29,5 +32,9 @@ function! clap#common_history#sink(type, selected) abort
   endif
 endfunction
 
+function! clap#common_history#delete_action() abort
+  call clap#client#notify_provider('provider_action', {'action': 'delete'})
+endfunction
+
 let &cpoptions = s:save_cpo
 unlet s:save_cpo
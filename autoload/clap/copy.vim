@@ -0,0 +1,96 @@
+" Author: liuchengxu <xuliuchengxlc@gmail.com>
+" Description: Copy the structured data about the current item to a register.
+
+let s:save_cpo = &cpoptions
+set cpoptions&vim
+
+let s:format_actions = {
+      \ 'absolute-path': 'Copy &absolute path',
+      \ 'relative-path': 'Copy &relative path',
+      \ 'path-lnum': 'Copy path:&lnum',
+      \ 'markdown-link': 'Copy &markdown link',
+      \ }
+
+function! s:get_option(option, provider_id, default) abort
+  if type(a:option) != v:t_dict
+    return a:option
+  elseif has_key(a:option, a:provider_id)
+    return a:option[a:provider_id]
+  else
+    return get(a:option, '*', a:default)
+  endif
+endfunction
+
+function! s:register() abort
+  let register = s:get_option(g:clap_copy_register, g:clap.provider.id, '"')
+  " Fallback to the unnamed register if the clipboard is unavailable.
+  if register =~# '^[+*]$' && !has('clipboard')
+    return '"'
+  endif
+  return register
+endfunction
+
+" OSC 52 is used if there is no shared system clipboard, e.g., in SSH sessions.
+function! s:osc52_enabled() abort
+  let osc52 = s:get_option(g:clap_copy_osc52, g:clap.provider.id, 'auto')
+  if osc52 is# 'auto'
+    return !has('clipboard') && (!empty($SSH_TTY) || !empty($SSH_CONNECTION))
+  endif
+  return osc52 ? v:true : v:false
+endfunction
+
+if has('nvim')
+  function! s:write_to_terminal(sequence) abort
+    call chansend(v:stderr, a:sequence)
+  endfunction
+else
+  function! s:write_to_terminal(sequence) abort
+    if exists('*echoraw')
+      call echoraw(a:sequence)
+    else
+      call writefile([a:sequence], '/dev/tty', 'b')
+    endif
+  endfunction
+endif
+
+function! clap#copy#formats() abort
+  let formats = s:get_option(g:clap_copy_formats, g:clap.provider.id, [])
+  return filter(copy(formats), 'has_key(s:format_actions, v:val)')
+endfunction
+
+" Returns the copy actions of the current provider, which are merged into the
+" provider actions.
+function! clap#copy#actions() abort
+  let actions = {}
+  for format in clap#copy#formats()
+    let actions[s:format_actions[format]] = function('clap#copy#selection', [format])
+  endfor
+  return actions
+endfunction
+
+" The current item is resolved on the Rust side, which calls back
+" clap#copy#set_register() with the payload.
+function! clap#copy#selection(format) abort
+  call clap#client#notify_provider('copy_selection', {
+        \ 'format': a:format,
+        \ 'osc52': s:osc52_enabled(),
+        \ 'tmux': !empty($TMUX),
+        \ })
+endfunction
+
+" The OSC 52 escape sequence of the payload is passed if enabled, which is
+" written to the terminal to set the local clipboard.
+function! clap#copy#set_register(payload, ...) abort
+  let register = s:register()
+  " Always characterwise, no trailing newline is pasted.
+  call setreg(register, a:payload, 'v')
+  if a:0 > 0
+    call s:write_to_terminal(a:1)
+    call clap#helper#echo_info('Copied to register '.register.' and the terminal clipboard: '.a:payload)
+  else
+    call clap#helper#echo_info('Copied to register '.register.': '.a:payload)
+  endif
+endfunction
+
+let &cpoptions = s:save_cpo
+unlet s:save_cpo
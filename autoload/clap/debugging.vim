@@ -77,6 +77,15 @@ function! clap#debugging#info() abort
     endif
   endif
 
+  let backend_info = clap#client#backend_info()
+  if !empty(backend_info)
+    echohl Type   | echo '      running backend: ' | echohl NONE
+    echohl Normal | echon printf('%s (protocol v%d), tree-sitter: %s, watcher: %s',
+          \ backend_info.version, backend_info.protocol_version,
+          \ join(backend_info.features.tree_sitter_languages, ','), backend_info.features.watcher_backend)
+          \ | echohl NONE
+  endif
+
   if executable('rustc')
     echohl Type   | echo '        rustc version: '  | echohl NONE
     echohl Normal | echon system('rustc --version') | echohl NONE
@@ -0,0 +1,168 @@
+" Author: liuchengxu <xuliuchengxlc@gmail.com>
+" Description: Size and mtime columns of the file paths in the display window.
+
+let s:save_cpo = &cpoptions
+set cpoptions&vim
+
+let s:supported_providers = ['files', 'recent_files']
+
+let s:update_delay = 100
+let s:update_timer = -1
+let s:request_id = 0
+
+" File info of each display line, v:null if unavailable.
+let s:file_info = []
+
+function! clap#file_info#enabled() abort
+  if !has('nvim') && !has('patch-9.0.0121')
+    return v:false
+  endif
+  if index(s:supported_providers, g:clap.provider.id) == -1
+    return v:false
+  endif
+  if type(g:clap_file_info_columns) == v:t_list
+    return index(g:clap_file_info_columns, g:clap.provider.id) != -1
+  endif
+  return g:clap_file_info_columns
+endfunction
+
+if has('nvim')
+  let s:file_info_ns_id = nvim_create_namespace('clap_file_info')
+
+  function! s:clear() abort
+    call nvim_buf_clear_namespace(g:clap.display.bufnr, s:file_info_ns_id, 0, -1)
+  endfunction
+
+  function! s:add_column(lnum, text) abort
+    let opts = { 'virt_text': [[a:text, 'ClapFileInfo']], 'virt_text_pos': 'right_align' }
+    call nvim_buf_set_extmark(g:clap.display.bufnr, s:file_info_ns_id, a:lnum - 1, 0, opts)
+  endfunction
+else
+  function! s:clear() abort
+    if !empty(prop_type_get('ClapFileInfo'))
+      call prop_remove({'type': 'ClapFileInfo', 'bufnr': g:clap.display.bufnr, 'all': v:true})
+    endif
+  endfunction
+
+  function! s:add_column(lnum, text) abort
+    if empty(prop_type_get('ClapFileInfo'))
+      call prop_type_add('ClapFileInfo', {'highlight': 'ClapFileInfo'})
+    endif
+    call prop_add(a:lnum, 0, {
+          \ 'type': 'ClapFileInfo',
+          \ 'bufnr': g:clap.display.bufnr,
+          \ 'text': a:text,
+          \ 'text_align': 'right',
+          \ })
+  endfunction
+endif
+
+function! s:render(request_id, result, error) abort
+  " Discard the outdated response.
+  if a:request_id != s:request_id || a:error isnot v:null || !g:clap.display.win_is_valid()
+    return
+  endif
+  let s:file_info = a:result
+  call s:clear()
+  let lnum = 1
+  for info in s:file_info
+    if info isnot v:null
+      call s:add_column(lnum, '  '.info.text)
+    endif
+    let lnum += 1
+  endfor
+endfunction
+
+function! s:origin_lines() abort
+  let lines = map(range(1, g:clap.display.line_count()), 'clap#api#get_origin_line_at(v:val)')
+  if get(g:, '__clap_icon_added_by_maple', v:false)
+    call map(lines, 'strcharpart(v:val, 2)')
+  endif
+  return lines
+endfunction
+
+" The columns are computed asynchronously by maple once the display lines are
+" updated.
+function! clap#file_info#update() abort
+  if !clap#file_info#enabled() || g:clap.display.is_empty()
+    return
+  endif
+  let s:request_id += 1
+  let s:file_info = []
+  call clap#client#request_async('file_info', function('s:render', [s:request_id]), {
+        \ 'cwd': clap#rooter#working_dir(),
+        \ 'paths': s:origin_lines(),
+        \ })
+endfunction
+
+function! clap#file_info#update_with_delay() abort
+  if !clap#file_info#enabled()
+    return
+  endif
+  if s:update_timer != -1
+    call timer_stop(s:update_timer)
+  endif
+  let s:update_timer = timer_start(s:update_delay, { -> clap#file_info#update() })
+endfunction
+
+" Sorts the display lines by the column `key` in descending order, the lines
+" without the file info are put at the end.
+function! clap#file_info#sort_by(key) abort
+  let line_count = g:clap.display.line_count()
+  if len(s:file_info) != line_count
+    call clap#helper#echo_warn('file info is not ready yet')
+    return
+  endif
+
+  let [hl_lines, item_hl_lines] = clap#highlighter#display_highlights()
+  let entries = []
+  for idx in range(line_count)
+    let lnum = idx + 1
+    call add(entries, {
+          \ 'line': get(getbufline(g:clap.display.bufnr, lnum), 0, ''),
+          \ 'origin': get(get(g:, '__clap_lines_truncated_map', {}), lnum, v:null),
+          \ 'info': s:file_info[idx],
+          \ 'indices': get(hl_lines, idx, []),
+          \ 'highlights': get(item_hl_lines, idx, []),
+          \ })
+  endfor
+
+  let key = a:key
+  call sort(entries, { a, b ->
+        \ (b.info is v:null ? -1 : b.info[key]) - (a.info is v:null ? -1 : a.info[key]) })
+
+  let truncated_map = {}
+  let lnum = 1
+  for entry in entries
+    if entry.origin isnot v:null
+      let truncated_map[lnum] = entry.origin
+    endif
+    let lnum += 1
+  endfor
+
+  call g:clap.display.set_lines(map(copy(entries), 'v:val.line'))
+  call g:clap.display.clear_highlight()
+  call clap#highlighter#add_highlights(map(copy(entries), 'v:val.indices'))
+  call clap#highlighter#add_item_highlights(map(copy(entries), 'v:val.highlights'))
+  if empty(truncated_map)
+    silent! unlet g:__clap_lines_truncated_map
+  else
+    let g:__clap_lines_truncated_map = truncated_map
+  endif
+
+  call s:render(s:request_id, map(copy(entries), 'v:val.info'), v:null)
+  call clap#preview#update_with_delay()
+endfunction
+
+function! clap#file_info#actions() abort
+  if !clap#file_info#enabled()
+    return {}
+  endif
+  return {
+        \ 'Sort by &size': function('clap#file_info#sort_by', ['size']),
+        \ 'Sort by m&time': function('clap#file_info#sort_by', ['mtime']),
+        \ }
+endfunction
+
+let &cpoptions = s:save_cpo
+unlet s:save_cpo
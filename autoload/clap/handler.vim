@@ -59,7 +59,7 @@ endfunction
 function! s:provider_sink() abort
   " This could be more robust by checking the exact matches count, but this should also be enough.
   if empty(g:clap.display.getcurline())
-        \ || g:clap.display.get_lines() == [g:clap_no_matches_msg]
+        \ || clap#state#is_no_matches()
     call s:handle_no_matches()
     return
   endif
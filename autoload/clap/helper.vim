@@ -26,8 +26,48 @@ function! s:relativize(ArgLead, abs_dirs) abort
   endif
 endfunction
 
+function! s:provider_names() abort
+  let registered = exists('g:clap') ? keys(g:clap.registrar) : []
+  let registered += ['install-binary', 'install-binary!', 'debug', 'debug+']
+  if !exists('s:autoload_providers')
+    let s:autoload_providers = map(split(globpath(&runtimepath, 'autoload/clap/provider/*.vim'), "\n"), 'fnamemodify(v:val, ":t:r")')
+  endif
+  if !exists('s:user_providers')
+    let s:user_providers = map(clap#provider#providers#get_user_defined(), 'split(v:val, ":")[0]')
+  endif
+  return uniq(sort(s:autoload_providers + s:user_providers + keys(g:clap#provider_alias) + registered))
+endfunction
+
+" The last candidates of the backend, the cmdline completion can not wait for the
+" reply, hence they are requested asynchronously and used on the next completion of
+" the same cmdline.
+let s:backend_candidates = {'key': '', 'candidates': []}
+
+function! s:on_backend_candidates(key, result, error) abort
+  if a:error is v:null && type(a:result) == v:t_list
+    let s:backend_candidates = {'key': a:key, 'candidates': a:result}
+  endif
+endfunction
+
 function! clap#helper#complete(ArgLead, CmdLine, P) abort
-  " TODO: Proper completion help
+  let cwd = getcwd()
+  let key = cwd."\n".strpart(a:CmdLine, 0, a:P)
+
+  " The candidates are refreshed even if cached, e.g., the directories may be changed.
+  if clap#job#daemon#is_running()
+    call clap#client#request_async('complete/clap', function('s:on_backend_candidates', [key]), {
+          \ 'cmdline': a:CmdLine,
+          \ 'cursor_pos': a:P,
+          \ 'cwd': cwd,
+          \ 'providers': s:provider_names(),
+          \ })
+  endif
+
+  if s:backend_candidates.key ==# key
+    return s:backend_candidates.candidates
+  endif
+
+  " Fallback until the backend replies.
   if a:CmdLine =~# '^Clap \(files\|grep\|filer\)'
     if a:ArgLead =~# '\(/\|\\\)$' || isdirectory(expand(a:ArgLead))
       let parent_dir = fnamemodify(resolve(expand(a:ArgLead)), ':p')
@@ -43,15 +83,7 @@ function! clap#helper#complete(ArgLead, CmdLine, P) abort
       endif
     endif
   endif
-  let registered = exists('g:clap') ? keys(g:clap.registrar) : []
-  let registered += ['install-binary', 'install-binary!', 'debug', 'debug+']
-  if !exists('s:autoload_providers')
-    let s:autoload_providers = map(split(globpath(&runtimepath, 'autoload/clap/provider/*.vim'), "\n"), 'fnamemodify(v:val, ":t:r")')
-  endif
-  if !exists('s:user_providers')
-    let s:user_providers = map(clap#provider#providers#get_user_defined(), 'split(v:val, ":")[0]')
-  endif
-  return filter(uniq(sort(s:autoload_providers + s:user_providers + keys(g:clap#provider_alias) + registered)), 'v:val =~# "^".a:ArgLead')
+  return filter(s:provider_names(), 'v:val =~# "^".a:ArgLead')
 endfunction
 
 function! clap#helper#complete_actions(A, L, P) abort
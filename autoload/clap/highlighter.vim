@@ -118,7 +118,29 @@ else
   endfunction
 endif
 
+" The highlights of the current display lines, which are discarded once the
+" display buffer is changed.
+let s:display_highlights = { 'changedtick': -1, 'indices': [], 'items': [] }
+
+function! s:remember_highlights(key, hl_lines) abort
+  let changedtick = getbufvar(g:clap.display.bufnr, 'changedtick')
+  if s:display_highlights.changedtick != changedtick
+    let s:display_highlights = { 'changedtick': changedtick, 'indices': [], 'items': [] }
+  endif
+  let s:display_highlights[a:key] = a:hl_lines
+endfunction
+
+" Returns the fuzzy matched indices and the item highlights of the current
+" display lines.
+function! clap#highlighter#display_highlights() abort
+  if s:display_highlights.changedtick != getbufvar(g:clap.display.bufnr, 'changedtick')
+    return [[], []]
+  endif
+  return [s:display_highlights.indices, s:display_highlights.items]
+endfunction
+
 function! clap#highlighter#add_highlights(hl_lines) abort
+  call s:remember_highlights('indices', a:hl_lines)
   try
     call s:add_display_highlights(a:hl_lines)
   catch
@@ -126,6 +148,58 @@ function! clap#highlighter#add_highlights(hl_lines) abort
   endtry
 endfunction
 
+" Highlights carried by the items, added with a lower priority than the fuzzy matches.
+"
+" hl_lines: List of [start, length, group] per line, start is a 0-based byte index.
+if has('nvim')
+  function! s:add_item_highlights_inner(hl_lines) abort
+    let w:clap_match_ids = get(w:, 'clap_match_ids', [])
+    let lnum = 1
+    for highlights in a:hl_lines
+      for [start, length, group] in highlights
+        call add(w:clap_match_ids, matchaddpos(group, [[lnum, start+1, length]], s:default_priority - 1))
+      endfor
+      let lnum += 1
+    endfor
+  endfunction
+
+  function! s:add_item_highlights(hl_lines) abort
+    call win_execute(g:clap.display.winid, 'call s:add_item_highlights_inner(a:hl_lines)')
+  endfunction
+else
+  let s:item_types = []
+
+  function! s:add_item_highlights(hl_lines) abort
+    if !bufexists(g:clap.display.bufnr)
+      return
+    endif
+    let lnum = 0
+    for highlights in a:hl_lines
+      for [start, length, group] in highlights
+        let prop_type = 'ClapItem_'.group
+        if index(s:item_types, prop_type) == -1
+          call add(s:item_types, prop_type)
+          call prop_type_add(prop_type, {'highlight': group, 'priority': -1})
+        endif
+        call s:add_highlight_at(g:clap.display.bufnr, lnum, start, length, prop_type)
+      endfor
+      let lnum += 1
+    endfor
+  endfunction
+endif
+
+function! clap#highlighter#add_item_highlights(hl_lines) abort
+  call s:remember_highlights('items', a:hl_lines)
+  if empty(a:hl_lines)
+    return
+  endif
+  try
+    call s:add_item_highlights(a:hl_lines)
+  catch
+    return
+  endtry
+endfunction
+
 function! s:create_token_highlight_group(token_highlight) abort
   execute printf(
         \ 'highlight %s ctermfg=%s guifg=%s cterm=%s gui=%s',
@@ -160,6 +234,25 @@ function! clap#highlighter#add_sublime_highlights(bufnr, line_highlights) abort
   endfor
 endfunction
 
+" Highlight the lines using the highlights converted from the ANSI escape
+" sequences, the highlight groups are created on demand.
+function! clap#highlighter#add_ansi_highlights(bufnr, line_highlights) abort
+  for [lnum, line_highlight] in a:line_highlights
+    for hl in line_highlight
+      if !hlexists(hl.group_name)
+        execute printf(
+              \ 'highlight %s ctermfg=%s ctermbg=%s guifg=%s guibg=%s cterm=%s gui=%s',
+              \ hl.group_name, hl.ctermfg, hl.ctermbg, hl.guifg, hl.guibg, hl.attr, hl.attr,
+              \ )
+        if !has('nvim')
+          call prop_type_add(hl.group_name, {'highlight': hl.group_name})
+        endif
+      endif
+      call s:add_highlight_at(a:bufnr, lnum - 1, hl.col_start, hl.length, hl.group_name)
+    endfor
+  endfor
+endfunction
+
 function! clap#highlighter#disable_tree_sitter(bufnr) abort
   if has('nvim')
     call nvim_buf_clear_namespace(a:bufnr, s:tree_sitter_ns_id, 0, -1)
@@ -136,6 +136,7 @@ endfunction
 function! clap#job#daemon#start() abort
   let s:MessageHandler = function('clap#client#handle')
   call s:start_service_job(clap#maple#build_cmd('rpc'))
+  call clap#sync_user_providers()
 endfunction
 
 let &cpoptions = s:save_cpo
@@ -23,7 +23,10 @@ endif
 
 if s:maple_bin isnot v:null
   function! clap#maple#clean_up() abort
-    call clap#client#notify_provider('exit_provider')
+    call clap#client#notify_provider('exit_provider', {
+          \ 'query': g:clap.input.get(),
+          \ 'lnum': g:clap.display.getcurlnum(),
+          \ })
   endfunction
 else
   function! clap#maple#clean_up() abort
@@ -60,10 +60,43 @@ function! clap#path#find_nearest_dir(bufnr, dir) abort
 endfunction
 
 function! clap#path#get_git_root() abort
-  let root = split(system('git rev-parse --show-toplevel'), '\n')[0]
+  let root = split(system(clap#path#git_cmd().' rev-parse --show-toplevel'), '\n')[0]
   return v:shell_error ? '' : root
 endfunction
 
+let s:external_git_dirs = {}
+
+" Returns [git_dir, work_tree] if a:path is managed by a git dir outside of the work
+" tree, e.g., the bare repo managing the dotfiles, v:null otherwise.
+"
+" It's resolved by the backend from `git-dir` of `project-tools` in the config file,
+" or $GIT_DIR and $GIT_WORK_TREE.
+function! s:external_git_dir(path) abort
+  let path = empty(a:path) ? getcwd() : a:path
+  if !has_key(s:external_git_dirs, path)
+    if !clap#job#daemon#is_running()
+      return v:null
+    endif
+    let external = clap#client#request_sync('git/external_dir', {'path': path}, 200)
+    " v:null on timeout is not cached, the backend is asked again next time.
+    if external is v:null
+      return v:null
+    endif
+    let s:external_git_dirs[path] = empty(external) ? v:null : external
+  endif
+  return s:external_git_dirs[path]
+endfunction
+
+" Returns the git command, the external git dir is specified explicitly if the
+" start buffer is not in a conventional repo.
+function! clap#path#git_cmd() abort
+  let external = s:external_git_dir(expand('#'.g:clap.start.bufnr.':p'))
+  if external is v:null
+    return 'git'
+  endif
+  return printf('git --git-dir=%s --work-tree=%s', shellescape(external[0]), shellescape(external[1]))
+endfunction
+
 function! s:find_root_dir(bufnr, root_markers) abort
   let start_dir = expand('#'.a:bufnr.':p')
 
@@ -80,7 +113,14 @@ endfunction
 " This is faster than clap#path#get_git_root() which uses the system call.
 function! clap#path#find_git_root(bufnr) abort
   " git submodule uses .git instead of .git/. Ref #164
-  return s:find_root_dir(a:bufnr, ['.git', '.git/'])
+  let git_root = s:find_root_dir(a:bufnr, ['.git', '.git/'])
+  if empty(git_root)
+    let external = s:external_git_dir(expand('#'.a:bufnr.':p'))
+    if external isnot v:null
+      return external[1]
+    endif
+  endif
+  return git_root
 endfunction
 
 function! clap#path#git_root_or_default(bufnr) abort
@@ -0,0 +1,22 @@
+" Author: liuchengxu <xuliuchengxlc@gmail.com>
+" Description: Pin the items per provider, which are listed first on empty query.
+
+let s:save_cpo = &cpoptions
+set cpoptions&vim
+
+" Returns the pin actions, which are merged into the provider actions.
+function! clap#pinned#actions() abort
+  return {
+        \ '&Pin item': function('clap#pinned#pin', [v:true]),
+        \ '&Unpin item': function('clap#pinned#pin', [v:false]),
+        \ }
+endfunction
+
+" The current item is pinned on the Rust side, which refreshes the display
+" window if the query is empty.
+function! clap#pinned#pin(pin) abort
+  call clap#client#notify_provider('pin_item', {'pin': a:pin})
+endfunction
+
+let &cpoptions = s:save_cpo
+unlet s:save_cpo
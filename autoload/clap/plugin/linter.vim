@@ -5,14 +5,6 @@ scriptencoding utf-8
 let s:save_cpo = &cpoptions
 set cpoptions&vim
 
-let s:severity_icons = {
-      \ 'error': '  ',
-      \ 'warning': '  ',
-      \ 'hint': '  ',
-      \ 'info': '  ',
-      \ 'other': '  ',
-      \ }
-
 hi ClapDiagnosticUnderline cterm=underline,bold gui=undercurl,italic,bold ctermfg=173 guifg=#e18254
 
 hi DiagnosticWarn ctermfg=136 guifg=#b1951d
@@ -29,7 +21,8 @@ function! s:convert_diagnostics_to_lines(current_diagnostics) abort
 
     let highlights = []
 
-    let severity_icon = get(s:severity_icons, diagnostic.severity, s:severity_icons.other)
+    " The severity icon is attached by the linter plugin.
+    let severity_icon = ' '.diagnostic.icon.' '
     let severity_len = strlen(severity_icon)
 
     call add(highlights, ['DiagnosticError', 0, severity_len])
@@ -32,7 +32,7 @@ function! s:highlight_shortcut() abort
 endfunction
 
 function! clap#popup#action#invoke() abort
-  let s:provider_action = g:clap.provider._().action
+  let s:provider_action = clap#action#provider_actions()
   if has_key(s:provider_action, 'title')
     let title = s:provider_action['title']()
   else
@@ -94,6 +94,10 @@ function! s:move_manager.shift_down(_winwid) abort
   call clap#client#notify_provider('shift-down')
 endfunction
 
+function! s:move_manager.shift_cr(_winwid) abort
+  call clap#client#notify_provider('accept_at_preview')
+endfunction
+
 function! s:move_manager.ctrl_u(_winid) abort
   if empty(s:input)
     return 1
@@ -198,6 +202,8 @@ let s:move_manager["\<A-U>"] = { _winid -> clap#handler#handle_mapping("\<A-U\>"
 let s:move_manager["\<S-TAB>"] = { _winid -> clap#action#invoke() }
 let s:move_manager["\<S-Up>"] = s:move_manager.shift_up
 let s:move_manager["\<S-Down>"] = s:move_manager.shift_down
+let s:move_manager["\<S-CR>"] = s:move_manager.shift_cr
+let s:move_manager["\<A-P>"] = { _winid -> clap#client#notify_provider('alt-p') }
 let s:move_manager["\<PageUp>"] = s:move_manager.scroll_up
 let s:move_manager["\<PageDown>"] = s:move_manager.scroll_down
 let s:move_manager["\<LeftMouse>"] = s:move_manager["\<Tab>"]
@@ -101,6 +101,7 @@ function! clap#preview#file_at(fpath, lnum) abort
   call g:clap.preview.set_syntax(clap#ext#into_filetype(a:fpath))
   call g:clap.preview.add_highlight(hi_lnum+1)
   call clap#preview#highlight_header()
+  call clap#preview#highlight_query_terms()
 endfunction
 
 " Given the origin lnum and the size of range, return
@@ -129,6 +130,38 @@ function! clap#preview#show_lines(lines, syntax, hi_lnum) abort
   if a:hi_lnum > 0
     call g:clap.preview.add_highlight(a:hi_lnum)
   endif
+  call clap#preview#highlight_query_terms()
+endfunction
+
+" Returns the pattern matching the terms of `query` the matched items must contain,
+" e.g., `foo` and `bar` of `'foo ^bar !baz`, empty if there is no such term.
+function! clap#preview#query_terms_pattern(query) abort
+  let terms = []
+  for term in split(a:query)
+    " Inverse terms are absent in the matched items.
+    if term[0] ==# '!'
+      continue
+    endif
+    let term = substitute(substitute(term, '^[''^]', '', ''), '\$$', '', '')
+    if !empty(term)
+      call add(terms, escape(term, '\'))
+    endif
+  endfor
+  if empty(terms)
+    return ''
+  endif
+  " Smart case as the matcher.
+  let case = a:query =~# '\u' ? '\C' : '\c'
+  return case.'\V\('.join(terms, '\|').'\)'
+endfunction
+
+" Highlights the occurrences of the query terms in the preview window, which helps
+" to tell why the current item matches.
+function! clap#preview#highlight_query_terms() abort
+  if !g:clap_preview_highlight_query || !g:clap.preview.win_is_valid()
+    return
+  endif
+  call g:clap.preview.highlight_query_terms(clap#preview#query_terms_pattern(g:clap.input.get()))
 endfunction
 
 let s:preview_timer = -1
@@ -161,6 +194,21 @@ function! clap#preview#update_with_delay() abort
   let s:preview_timer = timer_start(s:preview_delay, { -> clap#impl#on_move#invoke_async()})
 endfunction
 
+" Scrolls the preview by `lines`, negative to scroll up. Returns an empty string
+" so that it can be used in the insert mode mappings via <C-R>=.
+function! clap#preview#scroll(lines) abort
+  call clap#client#notify_provider('preview/scroll', {'lines': a:lines})
+  return ''
+endfunction
+
+" Returns the number of lines in a page of the preview window.
+function! clap#preview#page_size() abort
+  if has_key(g:clap.preview, 'winid')
+    return max([1, winheight(g:clap.preview.winid) - 1])
+  endif
+  return 10
+endfunction
+
 function! clap#preview#maple_opts(extra) abort
   let opts = {
         \ 'fpath': fnamemodify(fnameescape(g:clap.display.getcurline()), ':p'),
@@ -12,8 +12,11 @@ endfunction
 
 function! s:into_git_diff_cmd(line) abort
   let rev = clap#provider#commits#parse_rev(a:line)
+  if clap#provider#commits#vcs() !=# 'git'
+    return clap#provider#commits#show_cmd(rev).' '.shellescape(bufname(g:clap.start.bufnr))
+  endif
   let prev = s:find_prev(rev)
-  return printf('git diff --color=never %s %s -- %s', rev, prev, bufname(g:clap.start.bufnr))
+  return printf('%s diff --color=never %s %s -- %s', clap#path#git_cmd(), rev, prev, bufname(g:clap.start.bufnr))
 endfunction
 
 function! s:bcommits.on_move() abort
@@ -33,7 +36,7 @@ endfunction
 
 function! s:find_prev(cur_rev) abort
   if !exists('s:shas')
-    let s:shas = systemlist('git log --format=format:%h')
+    let s:shas = systemlist(clap#path#git_cmd().' log --format=format:%h')
     let s:shas_len = len(s:shas)
   endif
   let idx = 0
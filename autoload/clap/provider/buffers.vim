@@ -6,46 +6,40 @@ set cpoptions&vim
 
 let s:cur_tab_only = get(g:, 'clap_provider_buffers_cur_tab_only', v:false)
 
-function! s:padding(origin, target_width) abort
-  let width = strdisplaywidth(a:origin)
-  if width < a:target_width
-    return a:origin.repeat(' ', a:target_width - width)
-  else
-    return a:origin
-  endif
-endfunction
-
-function! s:format_buffer(b) abort
+function! s:fullpath(b) abort
   let buffer_name = bufname(a:b)
-  let fullpath = empty(buffer_name) ? '[No Name]' : fnamemodify(buffer_name, ':p:~:.')
-  let filename = empty(fullpath) ? '[No Name]' : fnamemodify(fullpath, ':t')
-  let flag = a:b == bufnr('')  ? '%' : (a:b == bufnr('#') ? '#' : ' ')
-  let modified = getbufvar(a:b, '&modified') ? ' [+]' : ''
-  let readonly = getbufvar(a:b, '&modifiable') ? '' : ' [RO]'
-
-  let filename = s:padding(filename, 25)
-  let bp = s:padding('['.a:b.']', 5)
-  let fsize = s:padding(clap#util#getfsize(fullpath), 6)
-  let icon = g:clap_enable_icon ? s:padding(clap#icon#for(fullpath), 3) : ''
-  let extra = join(filter([modified, readonly], '!empty(v:val)'), '')
-  let line = s:padding(get(s:line_info, a:b, ''), 10)
+  return empty(buffer_name) ? '[No Name]' : fnamemodify(buffer_name, ':p:~:.')
+endfunction
 
-  return trim(printf('%s %s %s %s %s %s %s %s', bp, filename, fsize, icon, line, fullpath, flag, extra))
+function! s:buffer_info(b, line_info) abort
+  let fullpath = s:fullpath(a:b)
+  let bufname = bufname(a:b)
+  return {
+        \ 'bufnr': a:b,
+        \ 'name': fnamemodify(fullpath, ':t'),
+        \ 'short_path': fullpath,
+        \ 'path': empty(bufname) ? '' : fnamemodify(bufname, ':p'),
+        \ 'lnum': str2nr(matchstr(get(a:line_info, a:b, ''), '\d\+')),
+        \ 'fsize': clap#util#getfsize(fullpath),
+        \ 'icon': g:clap_enable_icon ? clap#icon#for(fullpath) : '',
+        \ 'flag': a:b == bufnr('') ? '%' : (a:b == bufnr('#') ? '#' : ' '),
+        \ 'extra': (getbufvar(a:b, '&modified') ? ' [+]' : '').(getbufvar(a:b, '&modifiable') ? '' : ' [RO]'),
+        \ }
 endfunction
 
-function! s:buffers() abort
-  let l:buffers = execute('buffers')
-  let s:line_info = {}
-  for line in split(l:buffers, "\n")
+" Returns the info of the listed buffers, which are formatted and filtered by maple.
+function! clap#provider#buffers#buffer_list() abort
+  let line_info = {}
+  for line in split(execute('buffers'), "\n")
     let bufnr = str2nr(trim(matchstr(line, '^\s*\d\+')))
-    let lnum = matchstr(line, '\s\+\zsline.*$')
-    let s:line_info[bufnr] = lnum
+    let line_info[bufnr] = matchstr(line, '\s\+\zsline.*$')
   endfor
-  let bufs = map(clap#util#buflisted_sorted(s:cur_tab_only), 's:format_buffer(str2nr(v:val))')
-  if empty(bufs)
+  let buflisted = clap#util#buflisted_sorted(s:cur_tab_only)
+  let buffers = map(buflisted, 's:buffer_info(str2nr(v:val), line_info)')
+  if empty(buffers)
     return []
   else
-    return bufs[1:] + [bufs[0]]
+    return buffers[1:] + [buffers[0]]
   endif
 endfunction
 
@@ -61,61 +55,25 @@ function! s:buffers_sink(selected) abort
   execute 'buffer' b
 endfunction
 
-function! s:buffers_on_move() abort
-  let curline = g:clap.display.getcurline()
-  if empty(curline)
-    return
-  endif
-  let bufnr = str2nr(s:extract_bufnr(curline))
-  if !has_key(s:line_info, bufnr)
-    return
-  endif
-  let lnum = str2nr(matchstr(s:line_info[bufnr], '\d\+'))
-  let [start, end, hi_lnum] = clap#preview#get_range(lnum)
-  let lines = getbufline(bufnr, start+1, end+1)
-  call insert(lines, bufname(bufnr))
-  call g:clap.preview.show(lines)
-  call g:clap.preview.setbufvar('&syntax', getbufvar(bufnr, '&syntax'))
-  call clap#preview#highlight_header()
-endfunction
-
-function! clap#provider#buffers#preview_target() abort
-  let curline = g:clap.display.getcurline()
-  if empty(curline)
-    return []
-  endif
-  let bufnr = str2nr(s:extract_bufnr(curline))
-  if !has_key(s:line_info, bufnr)
-    return []
-  endif
-  let lnum = matchstr(s:line_info[bufnr], '\d\+')
-  return [expand('#'.bufnr.':p'), lnum]
-endfunction
-
-function! s:action_delete() abort
-  let current_matches = g:clap.display.line_count()
-  execute 'bdelete' s:current_bufnr
-  call g:clap.display.deletecurline()
-  call clap#indicator#update_on_deletecurline()
-  call g:clap.preview.hide()
-  call g:clap#display_win.shrink_if_undersize()
+function! s:action(action) abort
+  call clap#client#notify_provider('provider_action', {'action': a:action})
 endfunction
 
 function! s:actions_title() abort
-  let s:current_bufnr = s:extract_bufnr(g:clap.display.getcurline())
-  return 'Choose action for buffer '.s:current_bufnr.':'
+  return 'Choose action for buffer '.s:extract_bufnr(g:clap.display.getcurline()).':'
 endfunction
 
 let s:buffers = {}
+let s:buffers.init = { -> clap#client#notify_on_init() }
 let s:buffers.sink = function('s:buffers_sink')
-let s:buffers.source = function('s:buffers')
-let s:buffers.on_move = function('s:buffers_on_move')
+let s:buffers.on_typed = { -> clap#client#notify_provider('on_typed') }
 let s:buffers.on_move_async = { -> clap#client#notify_provider('on_move') }
 let s:buffers.syntax = 'clap_buffers'
 let s:buffers.support_open_action = v:true
 let s:buffers.action = {
       \ 'title': function('s:actions_title'),
-      \ '&Delete': function('s:action_delete'),
+      \ '&Delete': { -> s:action('delete') },
+      \ '&Wipeout': { -> s:action('wipeout') },
       \ 'OpenInNew&Tab': { -> clap#selection#try_open('ctrl-t') },
       \ 'Open&Vertically': { -> clap#selection#try_open('ctrl-v') },
       \ }
@@ -0,0 +1,27 @@
+" Author: liuchengxu <xuliuchengxlc@gmail.com>
+" Description: List the buffers having unsaved changes, previewed as a diff against the disk.
+
+let s:save_cpo = &cpoptions
+set cpoptions&vim
+
+let s:changed_buffers = {}
+
+function! s:changed_buffers_source() abort
+  let changed = filter(getbufinfo({'buflisted': 1, 'bufmodified': 1}), '!empty(v:val.name)')
+  return map(changed, 'fnamemodify(v:val.name, ":~:.")')
+endfunction
+
+function! s:changed_buffers_sink(selected) abort
+  call clap#sink#edit_with_open_action(a:selected)
+endfunction
+
+let s:changed_buffers.syntax = 'clap_files'
+let s:changed_buffers.sink = function('s:changed_buffers_sink')
+let s:changed_buffers.on_move_async = function('clap#impl#on_move#async')
+let s:changed_buffers.source = function('s:changed_buffers_source')
+let s:changed_buffers.support_open_action = v:true
+
+let g:clap#provider#changed_buffers# = s:changed_buffers
+
+let &cpoptions = s:save_cpo
+unlet s:save_cpo
@@ -4,20 +4,19 @@
 let s:save_cpo = &cpoptions
 set cpoptions&vim
 
-" Derived from fzf.vim
-function! s:command_history_source() abort
-  return clap#common_history#source(':')
-endfunction
-
 function! s:command_history_sink(selected) abort
   call clap#common_history#sink(':', a:selected)
 endfunction
 
 let s:command_history = {}
 let s:command_history.sink = function('s:command_history_sink')
-let s:command_history.source = function('s:command_history_source')
+let s:command_history.init = { -> clap#client#notify_on_init() }
+let s:command_history.on_typed = { -> clap#client#notify_provider('on_typed') }
+let s:command_history.on_move_async = { -> clap#client#notify_provider('on_move') }
 let s:command_history.syntax = 'clap_command_history'
-let s:command_history.mode = 'quick_pick'
+let s:command_history.action = {
+      \ '&Delete from history': function('clap#common_history#delete_action'),
+      \ }
 
 let g:clap#provider#command_history# = s:command_history
 
@@ -14,14 +14,15 @@ function! clap#provider#commits#source_common(buffer_local) abort
     return ['Not in git repository']
   endif
 
-  let source = "git log '--color=never' '--date=short' '--format=%cd %h%d %s (%an)'"
+  let git = clap#path#git_cmd()
+  let source = git." log '--color=never' '--date=short' '--format=%cd %h%d %s (%an)'"
 
   let current = bufname(g:clap.start.bufnr)
   if empty(current)
     return ['buffer name is empty']
   endif
 
-  call system('git show '.current.' 2> '.(has('win32') ? 'nul' : '/dev/null'))
+  call system(git.' show '.current.' 2> '.(has('win32') ? 'nul' : '/dev/null'))
 
   if v:shell_error
     return ['The current buffer is not in the working tree']
@@ -45,14 +46,43 @@ function! clap#provider#commits#on_move_common(cmd) abort
   call clap#preview#highlight_header()
 endfunction
 
+" Returns the VCS of the nearest working copy containing the start buffer, the Rust
+" backend provides the commits of Mercurial and Subversion.
+function! clap#provider#commits#vcs() abort
+  let dir = expand('#'.g:clap.start.bufnr.':p:h')
+  while v:true
+    for [marker, vcs] in [['.git', 'git'], ['.hg', 'hg'], ['.svn', 'svn']]
+      if !empty(glob(dir.'/'.marker, v:true))
+        return vcs
+      endif
+    endfor
+    let parent = fnamemodify(dir, ':h')
+    if parent ==# dir
+      return 'git'
+    endif
+    let dir = parent
+  endwhile
+endfunction
+
+function! clap#provider#commits#show_cmd(rev) abort
+  let vcs = clap#provider#commits#vcs()
+  if vcs ==# 'hg'
+    return 'hg log --patch -r '.a:rev
+  elseif vcs ==# 'svn'
+    return 'svn diff -c '.substitute(a:rev, '^r', '', '')
+  endif
+  return clap#path#git_cmd().' show '.a:rev
+endfunction
+
+" The hash of git and hg, or the revision of svn, e.g., r42.
 function! clap#provider#commits#parse_rev(line) abort
-  return matchstr(a:line, s:begin.'\zs[a-f0-9]\+')
+  return matchstr(a:line, s:begin.'\zs\%(r[0-9]\+\|[a-f0-9]\+\)\ze\s')
 endfunction
 
 function! s:commits.on_move() abort
   let cur_line = g:clap.display.getcurline()
   let rev = clap#provider#commits#parse_rev(cur_line)
-  call clap#provider#commits#on_move_common('git show '.rev)
+  call clap#provider#commits#on_move_common(clap#provider#commits#show_cmd(rev))
 endfunction
 
 function! clap#provider#commits#on_move_callback(result, error) abort
@@ -77,7 +107,7 @@ endfunction
 
 function! s:commits.sink(line) abort
   let rev = clap#provider#commits#parse_rev(a:line)
-  call clap#provider#commits#sink_inner('!git show '.rev)
+  call clap#provider#commits#sink_inner('!'.clap#provider#commits#show_cmd(rev))
 endfunction
 
 let s:commits.syntax = 'clap_diff'
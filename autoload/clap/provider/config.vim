@@ -0,0 +1,33 @@
+" Author: liuchengxu <xuliuchengxlc@gmail.com>
+" Description: List the effective config keys and edit their values.
+
+let s:save_cpo = &cpoptions
+set cpoptions&vim
+
+let s:config = {}
+
+function! s:config.sink(selected) abort
+  let matched = matchlist(a:selected, '^\[\a\+\] \(.\{-}\) = \(.*\)$')
+  if empty(matched)
+    return
+  endif
+  let [key, value] = matched[1:2]
+  let new_value = input('Set '.key.' = ', value ==# '<unset>' ? '' : value)
+  if !empty(new_value) && new_value !=# value
+    call clap#client#notify('__set-config', [key, new_value])
+  endif
+endfunction
+
+function! s:config.on_enter() abort
+  syntax match ClapConfigSource /^\[\a\+\]/
+  hi default link ClapConfigSource Type
+endfunction
+
+let s:config.on_typed = { -> clap#client#notify_provider('on_typed') }
+let s:config.on_move_async = { -> clap#client#notify_provider('on_move') }
+let s:config.source_type = g:__t_rpc
+
+let g:clap#provider#config# = s:config
+
+let &cpoptions = s:save_cpo
+unlet s:save_cpo
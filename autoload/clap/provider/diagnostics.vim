@@ -0,0 +1,63 @@
+" Author: liuchengxu <xuliuchengxlc@gmail.com>
+" Description: List the diagnostics collected by the linter plugin.
+
+let s:save_cpo = &cpoptions
+set cpoptions&vim
+
+let s:diagnostics = {}
+
+function! s:extract_position(line) abort
+  let line = get(g:, '__clap_icon_added_by_maple', v:false) ? strcharpart(a:line, 2) : a:line
+  let matched = matchlist(line, '^\(.\{-}\):\(\d\+\):\(\d\+\) ')
+  return [matched[1], str2nr(matched[2]), str2nr(matched[3])]
+endfunction
+
+function! s:diagnostics.sink(selected) abort
+  let [fpath, lnum, col] = s:extract_position(a:selected)
+  call clap#sink#open_file(fpath, lnum, col)
+endfunction
+
+" Applies the first code action available at the diagnostic position, which is
+" provided by the builtin LSP client of Neovim or coc.nvim.
+function! clap#provider#diagnostics#apply_code_action(fpath, lnum, col) abort
+  call clap#exit()
+  call clap#sink#open_file(a:fpath, a:lnum, a:col)
+  if has('nvim-0.8') && !empty(v:lua.vim.lsp.get_active_clients({'bufnr': bufnr('')}))
+    " Only the first action is kept, which is then applied without prompting.
+    lua << EOF_LUA
+    local first = true
+    vim.lsp.buf.code_action({
+      apply = true,
+      filter = function()
+        local is_first = first
+        first = false
+        return is_first
+      end,
+    })
+EOF_LUA
+  elseif exists('*CocAction') && get(g:, 'coc_service_initialized', 0)
+    call CocActionAsync('doQuickfix')
+  else
+    call clap#helper#echo_warn('No language server available for the code actions')
+  endif
+endfunction
+
+function! s:action(action) abort
+  call clap#client#notify_provider('provider_action', {'action': a:action})
+endfunction
+
+let s:diagnostics.init = { -> clap#client#notify_on_init() }
+let s:diagnostics.on_typed = { -> clap#client#notify_provider('on_typed') }
+let s:diagnostics.on_move_async = { -> clap#client#notify_provider('on_move') }
+let s:diagnostics.syntax = 'clap_diagnostics'
+let s:diagnostics.icon = 'File'
+let s:diagnostics.enable_rooter = v:true
+let s:diagnostics.support_open_action = v:true
+let s:diagnostics.action = {
+      \ 'Apply first &code action': { -> s:action('code_action') },
+      \ }
+
+let g:clap#provider#diagnostics# = s:diagnostics
+
+let &cpoptions = s:save_cpo
+unlet s:save_cpo
@@ -0,0 +1,66 @@
+" Author: liuchengxu <xuliuchengxlc@gmail.com>
+" Description: List the git branches, the list is produced by maple.
+
+let s:save_cpo = &cpoptions
+set cpoptions&vim
+
+let s:git_branches = {}
+
+function! s:extract_branch(line) abort
+  return matchstr(a:line, '^. \zs\S\+')
+endfunction
+
+function! s:on_checkout(result, error) abort
+  if a:error isnot v:null
+    call clap#helper#echo_error(string(a:error))
+  elseif has_key(a:result, 'error')
+    call clap#helper#echo_error(a:result.error)
+  else
+    checktime
+  endif
+endfunction
+
+" The checkout is done by maple, the same as the checkout action.
+function! s:git_branches.sink(selected) abort
+  call clap#client#request_async('git/checkout', function('s:on_checkout'), {
+        \ 'cwd': getcwd(),
+        \ 'branch': s:extract_branch(a:selected),
+        \ })
+endfunction
+
+function! s:action(action, ...) abort
+  let params = {'action': a:action}
+  if a:0
+    let params.name = a:1
+  endif
+  call clap#client#notify_provider('provider_action', params)
+endfunction
+
+function! s:create_from() abort
+  let branch = s:extract_branch(g:clap.display.getcurline())
+  let name = trim(input('Create branch from '.branch.': '))
+  if !empty(name)
+    call s:action('create', name)
+  endif
+endfunction
+
+function! s:actions_title() abort
+  return 'Choose action for branch '.s:extract_branch(g:clap.display.getcurline()).':'
+endfunction
+
+let s:git_branches.init = { -> clap#client#notify_on_init() }
+let s:git_branches.on_typed = { -> clap#client#notify_provider('on_typed') }
+let s:git_branches.on_move_async = { -> clap#client#notify_provider('on_move') }
+let s:git_branches.syntax = 'clap_git_branches'
+let s:git_branches.enable_rooter = v:true
+let s:git_branches.action = {
+      \ 'title': function('s:actions_title'),
+      \ '&Checkout': { -> s:action('checkout') },
+      \ '&Delete': { -> s:action('delete') },
+      \ 'C&reate from': function('s:create_from'),
+      \ }
+
+let g:clap#provider#git_branches# = s:git_branches
+
+let &cpoptions = s:save_cpo
+unlet s:save_cpo
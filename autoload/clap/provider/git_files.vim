@@ -6,11 +6,12 @@ set cpoptions&vim
 
 let s:git_files = {}
 
-if executable('git')
-  let s:git_files.source = 'git ls-files '.(has('win32') ? '' : ' | uniq')
-else
-  let s:git_files.source = ['git executable not found']
-endif
+function! s:git_files.source() abort
+  if !executable('git')
+    return ['git executable not found']
+  endif
+  return clap#path#git_cmd().' ls-files '.(has('win32') ? '' : ' | uniq')
+endfunction
 
 let s:git_files.sink = function('clap#provider#files#sink_impl')
 let s:git_files['sink*'] = function('clap#provider#files#sink_star_impl')
@@ -0,0 +1,44 @@
+" Author: liuchengxu <xuliuchengxlc@gmail.com>
+" Description: List the hunks of the uncommitted changes, the list is produced by maple.
+
+let s:save_cpo = &cpoptions
+set cpoptions&vim
+
+let s:git_hunks = {}
+
+function! s:extract_position(line) abort
+  let matched = matchlist(a:line, '^\(.\{-}\):\(\d\+\) +\d\+ -\d\+')
+  return [matched[1], str2nr(matched[2])]
+endfunction
+
+function! s:git_hunks.sink(selected) abort
+  let [fpath, lnum] = s:extract_position(a:selected)
+  call clap#sink#open_file(fpath, lnum, 1)
+endfunction
+
+function! s:into_qf_item(line) abort
+  let [fpath, lnum] = s:extract_position(a:line)
+  let text = matchstr(a:line, '^.\{-}:\d\+ \zs.*')
+  return {'filename': fpath, 'lnum': lnum, 'text': text}
+endfunction
+
+function! s:git_hunks_sink_star(lines) abort
+  if len(a:lines) == 1
+    call s:git_hunks.sink(a:lines[0])
+    return
+  endif
+  call clap#sink#open_quickfix(map(a:lines, 's:into_qf_item(v:val)'))
+endfunction
+
+let s:git_hunks['sink*'] = function('s:git_hunks_sink_star')
+let s:git_hunks.init = { -> clap#client#notify_on_init() }
+let s:git_hunks.on_typed = { -> clap#client#notify_provider('on_typed') }
+let s:git_hunks.on_move_async = { -> clap#client#notify_provider('on_move') }
+let s:git_hunks.syntax = 'clap_git_hunks'
+let s:git_hunks.enable_rooter = v:true
+let s:git_hunks.support_open_action = v:true
+
+let g:clap#provider#git_hunks# = s:git_hunks
+
+let &cpoptions = s:save_cpo
+unlet s:save_cpo
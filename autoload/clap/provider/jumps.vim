@@ -6,41 +6,36 @@ set cpoptions&vim
 
 let s:jumps = {}
 
-function! s:jumps.source() abort
-  let cout = clap#api#win_execute(g:clap.start.winid, 'jumps')
-  let s:jumplist = split(cout, '\n')
-  return [s:jumplist[0]] + reverse(s:jumplist[1:])
+" The most recent jump comes first, keyed by the count relative to the current
+" position in the jump list, e.g., `-2` for `2<C-O>`.
+function! clap#provider#jumps#entries() abort
+  let [jumplist, current] = getjumplist(g:clap.start.winid)
+  let entries = []
+  for idx in range(len(jumplist))
+    let jump = jumplist[idx]
+    let delta = idx - current
+    let key = delta > 0 ? '+'.delta : string(delta)
+    " The column of the jump list is 0-based.
+    call add(entries, clap#provider#marks#into_entry(key, jump.bufnr, jump.lnum, jump.col + 1))
+  endfor
+  return reverse(entries)
 endfunction
 
 function! s:jumps.sink(line) abort
-  if empty(a:line)
-    return
+  let line = get(g:, '__clap_icon_added_by_maple', v:false) ? strcharpart(a:line, 2) : a:line
+  let delta = str2nr(matchstr(line, '^\s*\zs[-+]\?\d\+'))
+  if delta < 0
+    execute 'normal!' abs(delta)."\<C-O>"
+  elseif delta > 0
+    execute 'normal!' delta."\<C-I>"
   endif
-  let idx = index(s:jumplist, a:line)
-  if idx == -1
-    return
-  endif
-  let pointer = match(s:jumplist, '\v^\s*\>')
-  if pointer ==# a:line
-    return
-  endif
-  let delta = idx - pointer
-  let cmd = delta < 0 ? abs(delta)."\<C-O>" : delta."\<C-I>"
-  execute 'normal!' cmd
   normal! zz
 endfunction
 
-function! s:jumps.on_move() abort
-  let curline = g:clap.display.getcurline()
-  let matched = matchlist(curline, '^\s\+\(\d\+\)\s\+\(\d\+\)\s\+\(\d\+\)\s\+\(.*\)$')
-  if len(matched) < 5
-    return
-  endif
-  call clap#provider#marks#preview_impl(matched[2], matched[3], matched[4])
-endfunction
-
-" TODO: add on_move_async()
-
+let s:jumps.init = { -> clap#client#notify_on_init() }
+let s:jumps.on_typed = { -> clap#client#notify_provider('on_typed') }
+let s:jumps.on_move_async = { -> clap#client#notify_provider('on_move') }
+let s:jumps.icon = 'File'
 let s:jumps.syntax = 'clap_jumps'
 let g:clap#provider#jumps# = s:jumps
 
@@ -126,13 +126,23 @@ function! s:into_abs_path(fpath) abort
   return a:fpath
 endfunction
 
+" The matches under the extra search roots of grep are displayed as `[label]/path`.
+function! s:expand_root_label(fpath) abort
+  for [label, root] in items(get(g:, '__clap_grep_roots', {}))
+    if stridx(a:fpath, label.s:PATH_SEPARATOR) == 0
+      return root.a:fpath[len(label):]
+    endif
+  endfor
+  return a:fpath
+endfunction
+
 function! s:grep_sink(selected) abort
   call s:grep_exit()
   let line = a:selected
 
   let pattern = '\(.\{-}\):\(\d\+\):\(\d\+\):'
   let matched = s:strip_icon_and_match(line, pattern)
-  let [fpath, linenr, column] = [matched[1], str2nr(matched[2]), str2nr(matched[3])]
+  let [fpath, linenr, column] = [s:expand_root_label(matched[1]), str2nr(matched[2]), str2nr(matched[3])]
   call clap#sink#open_file(fpath, linenr, column)
   " Try to adjust the column offset as Rust grep searcher strips the leading
   " whitespaces.
@@ -149,7 +159,7 @@ endfunction
 
 function! s:into_qf_item(line, pattern) abort
   let matched = s:strip_icon_and_match(a:line, a:pattern)
-  let [fpath, linenr, column, text] = [matched[1], str2nr(matched[2]), str2nr(matched[3]), matched[4]]
+  let [fpath, linenr, column, text] = [s:expand_root_label(matched[1]), str2nr(matched[2]), str2nr(matched[3]), matched[4]]
   return {'filename': fpath, 'lnum': linenr, 'col': column, 'text': text}
 endfunction
 
@@ -80,6 +80,7 @@ function! s:loclist.on_move() abort
   call clap#preview#highlight_header()
 endfunction
 
+let s:loclist.on_move_async = { -> clap#client#notify_provider('on_move') }
 let s:loclist.syntax = 'qf'
 let g:clap#provider#loclist# = s:loclist
 
@@ -0,0 +1,21 @@
+" Author: liuchengxu <xuliuchengxlc@gmail.com>
+" Description: Symbols from the language servers only, use `--document` for the symbols of the current buffer.
+
+let s:save_cpo = &cpoptions
+set cpoptions&vim
+
+let s:lsp_symbols = {}
+
+let s:lsp_symbols.sink = function('clap#provider#symbols#sink_impl')
+let s:lsp_symbols.init = { -> clap#client#notify_on_init() }
+let s:lsp_symbols.on_typed = { -> clap#client#notify_provider('on_typed') }
+let s:lsp_symbols.on_move_async = { -> clap#client#notify_provider('on_move') }
+let s:lsp_symbols.enable_rooter = v:true
+let s:lsp_symbols.support_open_action = v:true
+let s:lsp_symbols.icon = 'ProjTags'
+let s:lsp_symbols.syntax = 'clap_proj_tags'
+
+let g:clap#provider#lsp_symbols# = s:lsp_symbols
+
+let &cpoptions = s:save_cpo
+unlet s:save_cpo
@@ -0,0 +1,28 @@
+" Author: liuchengxu <xuliuchengxlc@gmail.com>
+" Description: List the man pages, previewed with the rendered manual.
+
+let s:save_cpo = &cpoptions
+set cpoptions&vim
+
+let s:man = {}
+
+function! s:man_sink(selected) abort
+  let matched = matchlist(a:selected, '^\s*\([^ ,(]\+\)\s*(\([^)]\+\))')
+  if empty(matched)
+    return
+  endif
+  let [page, section] = matched[1:2]
+  if !exists(':Man')
+    runtime ftplugin/man.vim
+  endif
+  execute 'Man' section page
+endfunction
+
+let s:man.source = 'man -k .'
+let s:man.sink = function('s:man_sink')
+let s:man.on_move_async = function('clap#impl#on_move#async')
+
+let g:clap#provider#man# = s:man
+
+let &cpoptions = s:save_cpo
+unlet s:save_cpo
@@ -6,97 +6,46 @@ set cpoptions&vim
 
 let s:marks = {}
 
-function! s:format_mark(line) abort
-  return substitute(a:line, '\S', '\=submatch(0)', '')
-endfunction
-
-function! s:marks.source() abort
-  let cout = clap#api#win_execute(g:clap.start.winid, 'marks')
-  let list = split(cout, "\n")
-  return extend(list[0:0], map(list[1:], 's:format_mark(v:val)'))
-endfunction
-
-function! s:marks.sink(line) abort
-  execute 'normal! `'.matchstr(a:line, '\S').'zz'
-endfunction
-
-function! clap#provider#marks#preview_impl(line, col, file_text) abort
-  let [line, col, file_text] = [a:line, a:col, a:file_text]
-
-  let origin_line = getbufline(g:clap.start.bufnr, line)
-
-  let [start, end, hi_lnum] = clap#preview#get_range(line)
-
-  let should_add_hi = v:true
-
-  " file_text is the origin line with leading white spaces trimmed.
-  if !empty(origin_line)
-        \ && clap#util#trim_leading(origin_line[0]) == file_text
-    let lines = getbufline(g:clap.start.bufnr, start, end)
-    call insert(lines, bufname(g:clap.start.bufnr))
-    let l:preview_header_added = 1
-    let hi_lnum += 1
-    let origin_bufnr = g:clap.start.bufnr
+" Converts the position to the entry consumed by maple, `key` is the mark name
+" or the relative count of the jump.
+function! clap#provider#marks#into_entry(key, bufnr, lnum, col) abort
+  let bufname = bufname(a:bufnr)
+  if a:bufnr == g:clap.start.bufnr
+    let text = trim(get(getbufline(a:bufnr, a:lnum), 0, ''))
   else
-    " TODO try cwd + file_text
-    if filereadable(expand(file_text))
-      let lines = readfile(expand(file_text), '', end)[start :]
-      call insert(lines, file_text)
-      let l:preview_header_added = 1
-    else
-      let lines = [file_text]
-      let should_add_hi = v:false
-    endif
-  endif
-
-  if empty(lines)
-    return
-  endif
-
-  call g:clap.preview.show(lines)
-
-  if should_add_hi
-    if exists('l:origin_bufnr')
-      let ft = getbufvar(l:origin_bufnr, '&filetype')
-      if empty(ft)
-        let ft = fnamemodify(expand(bufname(origin_bufnr)), ':e')
-      endif
-    else
-      let ft = clap#ext#into_filetype(file_text)
-    endif
-    if !empty(ft)
-      call g:clap.preview.set_syntax(ft)
-    endif
-    if exists('l:preview_header_added')
-      let hi_lnum += 1
-      call clap#preview#highlight_header()
-    endif
-    call g:clap.preview.add_highlight(hi_lnum)
+    let text = empty(bufname) ? '[No Name]' : fnamemodify(bufname, ':~:.')
   endif
+  return {
+        \ 'key': a:key,
+        \ 'lnum': a:lnum,
+        \ 'col': a:col,
+        \ 'path': empty(bufname) ? '' : fnamemodify(bufname, ':p'),
+        \ 'text': text,
+        \ }
 endfunction
 
-function! s:marks.on_move() abort
-  let curline = g:clap.display.getcurline()
-
-  if 'mark line  col file/text' ==# curline
-    return
+function! clap#provider#marks#entries() abort
+  if !exists('*getmarklist')
+    call clap#helper#echo_warn('getmarklist() is unavailable, please upgrade Vim to 8.2.2233+ or Neovim to 0.6+')
+    return []
   endif
+  let bufnr = g:clap.start.bufnr
+  let marks = filter(getmarklist(bufnr) + getmarklist(), 'v:val.pos[1] > 0')
+  " The local marks have no buffer number in the position.
+  return map(marks, 'clap#provider#marks#into_entry('
+        \ .'v:val.mark[1:], v:val.pos[0] ? v:val.pos[0] : bufnr, v:val.pos[1], v:val.pos[2])')
+endfunction
 
-  let matched = matchlist(curline, '^.*\([a-zA-Z0-9[`''"\^\]\.]\)\s\+\(\d\+\)\s\+\(\d\+\)\s\+\(.*\)$')
-
-  if len(matched) < 5
-    return
-  endif
-
-  let line = matched[2]
-  let col = matched[3]
-  let file_text = matched[4]
-
-  call clap#provider#marks#preview_impl(line, col, file_text)
+function! s:marks.sink(line) abort
+  let line = get(g:, '__clap_icon_added_by_maple', v:false) ? strcharpart(a:line, 2) : a:line
+  execute 'normal! `'.matchstr(line, '\S').'zz'
 endfunction
 
+let s:marks.init = { -> clap#client#notify_on_init() }
+let s:marks.on_typed = { -> clap#client#notify_provider('on_typed') }
+let s:marks.on_move_async = { -> clap#client#notify_provider('on_move') }
+let s:marks.icon = 'File'
 let s:marks.syntax = 'clap_marks'
-let s:marks.source_type = g:__t_func_list
 
 let g:clap#provider#marks# = s:marks
 
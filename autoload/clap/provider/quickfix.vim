@@ -53,15 +53,7 @@ function! s:quickfix.on_move() abort
   endif
 endfunction
 
-function! s:quickfix.on_move_async() abort
-  call clap#client#request_async('quickfix', function('clap#impl#on_move#handler'), {
-        \ 'curline': g:clap.display.getcurline(),
-        \ 'cwd': clap#rooter#working_dir(),
-        \ 'winwidth': winwidth(g:clap.display.winid),
-        \ 'winheight': winheight(g:clap.display.winid),
-        \ })
-endfunction
-
+let s:quickfix.on_move_async = { -> clap#client#notify_provider('on_move') }
 let s:quickfix.syntax = 'qf'
 let g:clap#provider#quickfix# = s:quickfix
 
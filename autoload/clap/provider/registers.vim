@@ -7,50 +7,34 @@ set cpoptions&vim
 let s:registers = {}
 
 " Credit: https://github.com/junegunn/vim-peekaboo
-function! s:append_group(title, regs) abort
-  call add(s:lines, a:title.':')
-  for r in a:regs
-    let val = eval('@'.r)[:&columns]
-    if !empty(val)
-      call add(s:lines, printf(' %s: %s', r, val))
+let s:register_names = ['"', '*', '+', '-', '/', '.', ':']
+      \ + map(range(0, 9), 'string(v:val)')
+      \ + map(range(97, 97 + 25), 'nr2char(v:val)')
+
+" Returns the non-empty registers, which are formatted and filtered by maple.
+function! clap#provider#registers#entries() abort
+  let entries = []
+  for name in s:register_names
+    let content = getreg(name)
+    if !empty(content)
+      call add(entries, {'name': name, 'content': content})
     endif
   endfor
-endfunction
-
-function! s:registers.source() abort
-  let s:lines = []
-  call s:append_group('Special', ['"', '*', '+', '-'])
-  call add(s:lines, '')
-  call s:append_group('Last-search-pattern', ['/'])
-  call add(s:lines, '')
-  call s:append_group('Read-only', ['.', ':'])
-  call add(s:lines, '')
-  call s:append_group('Numbered', map(range(0, 9), 'string(v:val)'))
-  call add(s:lines, '')
-  call s:append_group('Named', map(range(97, 97 + 25), 'nr2char(v:val)'))
-  return s:lines
+  return entries
 endfunction
 
 function! s:extract_reg(line) abort
   return matchstr(a:line, '^\s*\zs\(.\)\ze: ')
 endfunction
 
-function! s:registers.on_move() abort
-  let curline = g:clap.display.getcurline()
-  let reg = s:extract_reg(curline)
-  if !empty(reg)
-    let lines = split(eval('@'.reg), "\n")
-    let preview_title = 'Content for register ['.s:extract_reg(curline).']:'
-    call g:clap.preview.show([preview_title] + lines)
-    call clap#preview#highlight_header()
-  endif
-endfunction
-
 function! s:registers.sink(selected) abort
   let reg = s:extract_reg(a:selected)
   execute 'normal!' '"'.reg.'p'
 endfunction
 
+let s:registers.init = { -> clap#client#notify_on_init() }
+let s:registers.on_typed = { -> clap#client#notify_provider('on_typed') }
+let s:registers.on_move_async = { -> clap#client#notify_provider('on_move') }
 let s:registers.syntax = 'clap_registers'
 
 let g:clap#provider#registers# = s:registers
@@ -4,20 +4,19 @@
 let s:save_cpo = &cpoptions
 set cpoptions&vim
 
-" Derived from fzf.vim
-function! s:search_history_source() abort
-  return clap#common_history#source('/')
-endfunction
-
 function! s:search_history_sink(selected) abort
   call clap#common_history#sink('/', a:selected)
 endfunction
 
 let s:search_history = {}
 let s:search_history.sink = function('s:search_history_sink')
-let s:search_history.source = function('s:search_history_source')
+let s:search_history.init = { -> clap#client#notify_on_init() }
+let s:search_history.on_typed = { -> clap#client#notify_provider('on_typed') }
+let s:search_history.on_move_async = { -> clap#client#notify_provider('on_move') }
 let s:search_history.syntax = 'clap_command_history'
-let s:search_history.mode = 'quick_pick'
+let s:search_history.action = {
+      \ '&Delete from history': function('clap#common_history#delete_action'),
+      \ }
 
 let g:clap#provider#search_history# = s:search_history
 
@@ -0,0 +1,28 @@
+" Author: liuchengxu <xuliuchengxlc@gmail.com>
+" Description: Local usage statistics of the providers, caches and recent files.
+
+let s:save_cpo = &cpoptions
+set cpoptions&vim
+
+let s:stats = {}
+
+function! s:stats.sink(selected) abort
+  let path = matchstr(a:selected, '^\[file\]\s\+\d\+ visits  \zs.*')
+  if !empty(path)
+    call clap#sink#edit_with_open_action(path)
+  endif
+endfunction
+
+function! s:stats.on_enter() abort
+  syntax match ClapStatsSection /^\[\a\+\]/
+  hi default link ClapStatsSection Type
+endfunction
+
+let s:stats.on_typed = { -> clap#client#notify_provider('on_typed') }
+let s:stats.source_type = g:__t_rpc
+let s:stats.support_open_action = v:true
+
+let g:clap#provider#stats# = s:stats
+
+let &cpoptions = s:save_cpo
+unlet s:save_cpo
@@ -0,0 +1,99 @@
+" Author: liuchengxu <xuliuchengxlc@gmail.com>
+" Description: Workspace symbols from LSP, ctags and tree-sitter
+
+let s:save_cpo = &cpoptions
+set cpoptions&vim
+
+let s:symbols = {}
+
+function! s:extract(symbol_row) abort
+  let lnum = matchstr(a:symbol_row, '^.*:\zs\(\d\+\)')
+  let path = matchstr(a:symbol_row, '\[.*@\zs\(\f*\)\ze\]')
+  return [lnum, path]
+endfunction
+
+function! s:symbol_info(symbol) abort
+  let location = has_key(a:symbol, 'location') ? a:symbol.location : a:symbol
+  if !has_key(location, 'uri') || !has_key(location, 'range')
+    return {}
+  endif
+  return {
+        \ 'name': a:symbol.name,
+        \ 'kind': a:symbol.kind,
+        \ 'path': substitute(location.uri, '^file://', '', ''),
+        \ 'lnum': location.range.start.line + 1,
+        \ }
+endfunction
+
+" Flattens the hierarchical `DocumentSymbol`s which have no uri.
+function! s:flatten_document_symbols(symbols, path) abort
+  let flattened = []
+  for symbol in a:symbols
+    if has_key(symbol, 'location') || has_key(symbol, 'uri')
+      call add(flattened, symbol)
+      continue
+    endif
+    let range = get(symbol, 'selectionRange', get(symbol, 'range', {}))
+    if !empty(range)
+      call add(flattened, {'name': symbol.name, 'kind': symbol.kind, 'uri': 'file://'.a:path, 'range': range})
+    endif
+    call extend(flattened, s:flatten_document_symbols(get(symbol, 'children', []), a:path))
+  endfor
+  return flattened
+endfunction
+
+" Returns the symbols from the language servers attached to the start buffer.
+"
+" scope: 'workspace' (default) or 'document' for the symbols of the start buffer.
+function! clap#provider#symbols#lsp_symbols(...) abort
+  let scope = get(a:000, 0, 'workspace')
+  let bufnr = g:clap.start.bufnr
+  let path = fnamemodify(bufname(bufnr), ':p')
+  let symbols = []
+  try
+    if has('nvim-0.8') && !empty(v:lua.vim.lsp.get_active_clients({'bufnr': bufnr}))
+      if scope ==# 'document'
+        let method = 'textDocument/documentSymbol'
+        let params = {'textDocument': {'uri': v:lua.vim.uri_from_bufnr(bufnr)}}
+      else
+        let method = 'workspace/symbol'
+        let params = {'query': ''}
+      endif
+      let responses = v:lua.vim.lsp.buf_request_sync(bufnr, method, params, 1000)
+      for response in type(responses) == v:t_dict ? values(responses) : responses
+        call extend(symbols, get(response, 'result', []))
+      endfor
+      if scope ==# 'document'
+        let symbols = s:flatten_document_symbols(symbols, path)
+      endif
+    elseif exists('*CocAction') && get(g:, 'coc_service_initialized', 0)
+      if scope ==# 'document'
+        return map(CocAction('documentSymbols', bufnr),
+              \ '{"name": v:val.text, "kind": v:val.kind, "path": path, "lnum": v:val.lnum}')
+      endif
+      let symbols = CocAction('getWorkspaceSymbols', '')
+    endif
+  catch
+    return []
+  endtry
+  return filter(map(symbols, 's:symbol_info(v:val)'), '!empty(v:val)')
+endfunction
+
+function! clap#provider#symbols#sink_impl(selected) abort
+  let [lnum, path] = s:extract(a:selected)
+  call clap#sink#open_file(path, lnum, 1)
+endfunction
+
+let s:symbols.sink = function('clap#provider#symbols#sink_impl')
+let s:symbols.init = { -> clap#client#notify_on_init() }
+let s:symbols.on_move_async = function('clap#impl#on_move#async')
+let s:symbols.on_typed = { -> clap#client#notify_provider('on_typed') }
+let s:symbols.enable_rooter = v:true
+let s:symbols.support_open_action = v:true
+let s:symbols.icon = 'ProjTags'
+let s:symbols.syntax = 'clap_proj_tags'
+
+let g:clap#provider#symbols# = s:symbols
+
+let &cpoptions = s:save_cpo
+unlet s:save_cpo
@@ -72,7 +72,7 @@ endfunction
 " Apply the open action specified by `g:clap_open_action` given the (selected) lines.
 function! clap#selection#try_open(action) abort
   if !has_key(g:clap_open_action, a:action)
-        \ || g:clap.display.get_lines() == [g:clap_no_matches_msg]
+        \ || clap#state#is_no_matches()
     return
   endif
 
@@ -35,12 +35,18 @@ function! clap#state#process_filter_message(decoded_msg, ensure_sign_exists) abo
     endif
   endif
 
+  if has_key(decoded, 'no_matches_hints') && empty(get(decoded, 'lines', []))
+    call clap#state#render_no_matches(decoded.no_matches_hints)
+    return
+  endif
+
   if has_key(decoded, 'lines')
     call g:clap.display.set_lines(decoded.lines)
     if empty(decoded.lines)
       call g:clap.preview.clear()
       return
     endif
+    call clap#file_info#update_with_delay()
   endif
 
   if exists('g:__clap_lines_truncated_map')
@@ -74,23 +80,45 @@ function! clap#state#process_filter_message(decoded_msg, ensure_sign_exists) abo
   endif
 endfunction
 
+" Displays the no matches message followed by the hints explaining what may
+" have filtered out the results.
+" Each hint is a dict of `{'kind', 'text', 'count'}`, `count` is optional.
+function! clap#state#render_no_matches(hints) abort
+  let hint_lines = map(copy(a:hints), '"  - ".v:val.text')
+  call g:clap.display.set_lines([g:clap_no_matches_msg] + hint_lines)
+  call g:clap.preview.clear()
+  call clap#highlighter#add_item_highlights(
+        \ [[]] + map(copy(hint_lines), '[[0, strlen(v:val), "ClapNoMatchesHint"]]'))
+endfunction
+
+" Returns v:true if the display window shows the no matches message.
+function! clap#state#is_no_matches() abort
+  return get(g:clap.display.get_lines(), 0, '') ==# g:clap_no_matches_msg
+endfunction
+
 function! clap#state#process_progress(matched, processed) abort
   call clap#indicator#update(a:matched, a:processed)
 endfunction
 
-function! clap#state#process_progress_full(display_lines, matched, processed) abort
+" The hints of the empty results are passed as the optional argument.
+function! clap#state#process_progress_full(display_lines, matched, processed, ...) abort
   if !g:clap.display.win_is_valid()
     return
   endif
   call clap#indicator#update(a:matched, a:processed)
-  if a:matched == 0
+  if a:matched == 0 && a:0 > 0
+    call clap#state#render_no_matches(a:1)
+    return
+  elseif a:matched == 0
     call g:clap.display.set_lines([g:clap_no_matches_msg])
     call g:clap.preview.clear()
   else
     call g:clap.display.set_lines(a:display_lines.lines)
   endif
   call clap#highlighter#add_highlights(a:display_lines.indices)
+  call clap#highlighter#add_item_highlights(get(a:display_lines, 'highlights', []))
   call clap#preview#update_with_delay()
+  call clap#file_info#update_with_delay()
   if a:matched > 0
     call clap#sign#ensure_exists()
   endif
@@ -102,11 +130,39 @@ function! clap#state#process_progress_full(display_lines, matched, processed) ab
   endif
 endfunction
 
+" Applies the changed lines of the preview in place, returns v:false if the
+" preview buffer is not the one the patch is based on.
+function! s:apply_preview_patch(patch) abort
+  let bufnr = g:clap.preview.bufnr
+  if len(getbufline(bufnr, 1, '$')) != a:patch.base_len
+    return v:false
+  endif
+  for [start, end, lines] in reverse(copy(a:patch.hunks))
+    if has('nvim')
+      call nvim_buf_set_lines(bufnr, start, end, v:true, lines)
+    else
+      if end > start
+        silent call deletebufline(bufnr, start + 1, end)
+      endif
+      if !empty(lines)
+        call appendbufline(bufnr, start, lines)
+      endif
+    endif
+  endfor
+  return v:true
+endfunction
+
 function! clap#state#render_preview(preview) abort
   if !g:clap.display.win_is_valid()
     return
   endif
-  if has_key(a:preview, 'lines')
+  if has_key(a:preview, 'patch')
+    if !s:apply_preview_patch(a:preview.patch)
+      " Request the full preview as the preview buffer has been changed elsewhere.
+      call clap#client#notify_provider('on_move')
+      return
+    endif
+  elseif has_key(a:preview, 'lines')
     try
       call g:clap.preview.show(a:preview.lines)
     catch
@@ -114,35 +170,56 @@ function! clap#state#render_preview(preview) abort
       call g:clap.preview.show(['Error occurred while showing the preview:', v:exception, '', string(a:preview.lines)])
       return
     endtry
-    if has_key(a:preview, 'sublime_syntax_highlights')
-      for [lnum, line_highlight] in a:preview.sublime_syntax_highlights
-        try
-          call clap#highlighter#highlight_line(g:clap.preview.bufnr, lnum, line_highlight)
-        catch
-          " Ignore any potential errors as the line might be truncated.
-        endtry
-      endfor
-    elseif has_key(a:preview, 'tree_sitter_highlights')
-      call clap#highlighter#add_ts_highlights(g:clap.preview.bufnr, [], a:preview.tree_sitter_highlights)
-    elseif has_key(a:preview, 'vim_syntax_info')
-      let vim_syntax_info = a:preview.vim_syntax_info
-      if !empty(vim_syntax_info.syntax)
-        call g:clap.preview.set_syntax(vim_syntax_info.syntax)
-      elseif !empty(vim_syntax_info.fname)
-        call g:clap.preview.set_syntax(clap#ext#into_filetype(vim_syntax_info.fname))
-      endif
-    endif
-    call clap#preview#highlight_header()
+  else
+    return
+  endif
 
-    if has_key(a:preview, 'hi_lnum')
-      call g:clap.preview.add_highlight(a:preview.hi_lnum+1)
+  if has_key(a:preview, 'sublime_syntax_highlights')
+    for [lnum, line_highlight] in a:preview.sublime_syntax_highlights
+      try
+        call clap#highlighter#highlight_line(g:clap.preview.bufnr, lnum, line_highlight)
+      catch
+        " Ignore any potential errors as the line might be truncated.
+      endtry
+    endfor
+  elseif has_key(a:preview, 'tree_sitter_highlights')
+    " The rendered previews, e.g., markdown, reset the syntax of the raw file.
+    if has_key(a:preview, 'vim_syntax_info') && !empty(a:preview.vim_syntax_info.syntax)
+      call g:clap.preview.set_syntax(a:preview.vim_syntax_info.syntax)
     endif
-
-    if has_key(a:preview, 'scrollbar')
-      let [top_position, length] = a:preview.scrollbar
-      call clap#floating_win#show_preview_scrollbar(top_position, length)
+    let to_replace_line_ranges = has_key(a:preview, 'patch') ? a:preview.patch.ranges : []
+    call clap#highlighter#add_ts_highlights(g:clap.preview.bufnr, to_replace_line_ranges, a:preview.tree_sitter_highlights)
+  elseif has_key(a:preview, 'ansi_highlights')
+    " The colors of the external tools take the place of the syntax.
+    call g:clap.preview.set_syntax('')
+    try
+      call clap#highlighter#add_ansi_highlights(g:clap.preview.bufnr, a:preview.ansi_highlights)
+    catch
+      " Ignore any potential errors as the line might be truncated.
+    endtry
+  elseif has_key(a:preview, 'vim_syntax_info')
+    let vim_syntax_info = a:preview.vim_syntax_info
+    if !empty(vim_syntax_info.syntax)
+      call g:clap.preview.set_syntax(vim_syntax_info.syntax)
+    elseif !empty(vim_syntax_info.fname)
+      call g:clap.preview.set_syntax(clap#ext#into_filetype(vim_syntax_info.fname))
     endif
   endif
+  if has_key(a:preview, 'syntax_regions')
+    call g:clap.preview.add_syntax_regions(a:preview.syntax_regions)
+  endif
+  call clap#preview#highlight_header()
+
+  if has_key(a:preview, 'hi_lnum')
+    call g:clap.preview.add_highlight(a:preview.hi_lnum+1)
+  endif
+  call g:clap.preview.highlight_match(get(a:preview, 'match_highlight', []))
+  call clap#preview#highlight_query_terms()
+
+  if has_key(a:preview, 'scrollbar')
+    let [top_position, length] = a:preview.scrollbar
+    call clap#floating_win#show_preview_scrollbar(top_position, length)
+  endif
 endfunction
 
 function! clap#state#process_response_on_typed(result) abort
@@ -176,8 +253,10 @@ function! clap#state#process_response_on_typed(result) abort
 
   call g:clap.display.set_lines(a:result.lines)
   call clap#highlighter#add_highlights(a:result.indices)
+  call clap#highlighter#add_item_highlights(get(a:result, 'highlights', []))
   call clap#sign#ensure_exists()
   call clap#preview#update_with_delay()
+  call clap#file_info#update_with_delay()
 
   if has_key(a:result, 'preview')
     if !empty(a:result.preview)
@@ -201,13 +280,44 @@ function! clap#state#clear_preview() abort
   call g:clap.preview.clear()
 endfunction
 
-function! clap#state#init_display(lines, truncated_map, icon_added, using_cache) abort
+" Render the error of previewing the current item in the preview window.
+function! clap#state#render_preview_error(error) abort
+  if !g:clap.display.win_is_valid()
+    return
+  endif
+  let lines = ['Preview error: '.a:error.message]
+  if has_key(a:error, 'item')
+    call add(lines, 'Item: '.a:error.item)
+  endif
+  if has_key(a:error, 'hint')
+    call extend(lines, ['', 'Hint: '.a:error.hint])
+  endif
+  call g:clap.preview.show(lines)
+  call g:clap.preview.set_syntax('')
+  call g:clap.preview.add_highlight(1, 'ClapPreviewError')
+endfunction
+
+function! clap#state#hide_preview() abort
+  call g:clap.preview.clear()
+  call g:clap.preview.hide()
+endfunction
+
+" Updates the forerunner status in the prompt, e.g., `done` once the stale source from the
+" cache is refreshed.
+function! clap#state#set_forerunner_status(status) abort
+  let g:__clap_current_forerunner_status = g:clap_forerunner_status_sign[a:status]
+  call clap#spinner#refresh()
+endfunction
+
+" Optional argument: the item highlights of the lines.
+function! clap#state#init_display(lines, truncated_map, icon_added, using_cache, ...) abort
   if !g:clap.display.win_is_valid()
     return
   endif
   if empty(g:clap.input.get())
     call g:clap.display.set_lines_lazy(a:lines)
     call g:clap#display_win.shrink_if_undersize()
+    call clap#highlighter#add_item_highlights(get(a:000, 0, []))
   endif
 
   if a:using_cache
@@ -227,9 +337,11 @@ function! clap#state#init_display(lines, truncated_map, icon_added, using_cache)
   call clap#sign#ensure_exists()
   call clap#spinner#refresh()
   call clap#preview#update_with_delay()
+  call clap#file_info#update_with_delay()
 endfunction
 
-function! clap#state#update_on_empty_query(lines, truncated_map, icon_added) abort
+" Optional argument: the item highlights of the lines.
+function! clap#state#update_on_empty_query(lines, truncated_map, icon_added, ...) abort
   if !g:clap.display.win_is_valid()
     return
   endif
@@ -243,8 +355,10 @@ function! clap#state#update_on_empty_query(lines, truncated_map, icon_added) abo
   let g:__clap_icon_added_by_maple = a:icon_added
   call clap#sign#ensure_exists()
   call g:clap.display.clear_highlight()
+  call clap#highlighter#add_item_highlights(get(a:000, 0, []))
   call clap#indicator#update_matched(0)
   call clap#preview#update_with_delay()
+  call clap#file_info#update_with_delay()
 endfunction
 
 function! clap#state#set_input(new) abort
@@ -253,6 +367,53 @@ function! clap#state#set_input(new) abort
   call clap#api#win_execute(g:clap.input.winid, 'call cursor(1, 1000)')
 endfunction
 
+" Truncates the ghost text to the room left by the input in the input window.
+function! s:fit_ghost_text(text, padding) abort
+  let room = winwidth(g:clap.input.winid) - strdisplaywidth(g:clap.input.get()) - a:padding
+  if room <= 0
+    return ''
+  endif
+  " Each char takes at least one cell.
+  let text = strcharpart(a:text, 0, room)
+  while strdisplaywidth(text) > room
+    let text = strcharpart(text, 0, strchars(text) - 1)
+  endwhile
+  return text
+endfunction
+
+if has('nvim')
+  let s:ghost_text_ns_id = nvim_create_namespace('clap_ghost_text')
+
+  function! clap#state#set_ghost_text(text) abort
+    call nvim_buf_clear_namespace(g:clap.input.bufnr, s:ghost_text_ns_id, 0, -1)
+    " The eol virtual text starts one cell after the end of line.
+    let text = s:fit_ghost_text(a:text, 1)
+    if !empty(text)
+      let opts = { 'virt_text': [[text, 'ClapGhostText']], 'virt_text_pos': 'eol' }
+      call nvim_buf_set_extmark(g:clap.input.bufnr, s:ghost_text_ns_id, 0, 0, opts)
+    endif
+  endfunction
+else
+  function! clap#state#set_ghost_text(text) abort
+    if !has('patch-9.0.0067')
+      return
+    endif
+    if empty(prop_type_get('ClapGhostText'))
+      call prop_type_add('ClapGhostText', {'highlight': 'ClapGhostText'})
+    endif
+    call prop_remove({'type': 'ClapGhostText', 'bufnr': g:clap.input.bufnr, 'all': v:true})
+    let text = s:fit_ghost_text(a:text, 2)
+    if !empty(text)
+      call prop_add(1, 0, {'type': 'ClapGhostText', 'bufnr': g:clap.input.bufnr, 'text': '  '.text})
+    endif
+  endfunction
+endif
+
+" The input buffer is reused, the ghost text of the last search must be cleared.
+function! clap#state#clear_ghost_text() abort
+  call clap#state#set_ghost_text('')
+endfunction
+
 function! s:unlet_vars(vars) abort
   for var in a:vars
     if exists(var)
@@ -297,6 +458,8 @@ function! clap#state#clear_post() abort
 
   call map(g:clap.tmps, 'delete(v:val)')
   let g:clap.tmps = []
+
+  call clap#state#clear_ghost_text()
 endfunction
 
 let &cpoptions = s:save_cpo
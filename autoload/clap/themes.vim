@@ -135,6 +135,22 @@ function! s:apply_default_theme() abort
   execute 'hi default link ClapInput' s:input_default_hi_group
   execute 'hi default link ClapDisplay' s:display_default_hi_group
   hi default link ClapIndicator ClapInput
+  hi default link ClapGhostText Comment
+  hi default link ClapPreviewError ErrorMsg
+  hi default link ClapPreviewQueryTerm IncSearch
+  hi default link ClapPreviewMatch IncSearch
+  hi default link ClapDiffWordAdd DiffAdd
+  hi default link ClapDiffWordDelete DiffDelete
+  hi default link ClapFileInfo Comment
+  hi default link ClapPinned Special
+  hi default link ClapSymbolSource Comment
+  hi default link ClapTagSignature Comment
+  hi default link ClapReplacePreview DiffChange
+  hi default link ClapMarkdownHeading Title
+  hi default link ClapMarkdownListMarker Special
+  hi default link ClapMarkdownCode String
+  hi default link ClapMarkdownLink Underlined
+  hi default link ClapMarkdownQuote Comment
 endfunction
 
 function! s:make_display_EndOfBuffer_invisible() abort
@@ -207,6 +223,7 @@ endfunction
 function! clap#themes#init() abort
   hi default link ClapMatches        Search
   hi default link ClapNoMatchesFound ErrorMsg
+  hi default link ClapNoMatchesHint Comment
   hi default link ClapPopupCursor    Type
 
   if exists('g:clap_theme')
@@ -3,6 +3,7 @@ use anyhow::Result;
 use clap::Parser;
 use filter::FilterContext;
 use icon::Icon;
+use maple_core::config::StartupConfig;
 use types::CaseMatching;
 
 #[derive(Parser, Debug)]
@@ -73,8 +74,8 @@ pub struct Args {
 
     /// Specify the number of threads used in the rayon global thread pool.
     ///
-    /// By default, the number of physical cores will be used if the environment variable
-    /// `RAYON_NUM_THREADS` also does not exist.
+    /// By default, `thread-pool.filter-threads` in the config or the number of physical
+    /// cores will be used if the environment variable `RAYON_NUM_THREADS` also does not exist.
     #[clap(long)]
     pub rayon_num_threads: Option<usize>,
 
@@ -88,6 +89,22 @@ pub struct Args {
 }
 
 impl Args {
+    /// Loads the config file specified by `--config-file` or the default one.
+    pub fn load_config(&self) -> StartupConfig {
+        StartupConfig::load(self.config_file.clone())
+    }
+
+    /// Builds the async runtime with the number of worker threads in the config.
+    pub fn build_runtime(
+        &self,
+        startup_config: &StartupConfig,
+    ) -> std::io::Result<tokio::runtime::Runtime> {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(startup_config.thread_pool().async_threads())
+            .enable_all()
+            .build()
+    }
+
     pub fn into_filter_context(self) -> FilterContext {
         FilterContext::default()
             .icon(self.icon)
@@ -97,7 +114,9 @@ impl Args {
 }
 
 impl RunCmd {
-    pub async fn run(self, args: Args) -> Result<()> {
+    pub async fn run(self, args: Args, startup_config: StartupConfig) -> Result<()> {
+        startup_config.initialize_dirs();
+
         // Set the global thread pool to use the number of physical cores if `RAYON_NUM_THREADS`
         // does not exist.
         //
@@ -112,7 +131,7 @@ impl RunCmd {
             std::env::var("RAYON_NUM_THREADS")
                 .ok()
                 .and_then(|s| s.parse().ok())
-                .unwrap_or_else(num_cpus::get_physical)
+                .unwrap_or_else(|| startup_config.thread_pool().filter_threads())
         });
         rayon::ThreadPoolBuilder::new()
             .num_threads(num_threads)
@@ -131,7 +150,7 @@ impl RunCmd {
             Self::Gtags(gtags) => gtags.run(args),
             Self::Helptags(helptags) => helptags.run(),
             Self::RipGrepForerunner(rip_grep_forerunner) => rip_grep_forerunner.run(args),
-            Self::Rpc(rpc) => rpc.run(args).await,
+            Self::Rpc(rpc) => rpc.run(args, startup_config).await,
         }
     }
 }
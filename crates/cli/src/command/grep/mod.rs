@@ -80,7 +80,7 @@ impl Grep {
 
         if self.par_run {
             let par_source = if let Some(cache) = maybe_usable_cache {
-                ParallelSource::File(cache)
+                ParallelSource::CachedFile(cache)
             } else if let Some(ref tempfile) = self.input {
                 ParallelSource::File(tempfile.clone())
             } else if let Some(ref dir) = self.cmd_dir {
@@ -97,7 +97,7 @@ impl Grep {
         } else {
             let source: SequentialSource<std::iter::Empty<_>> =
                 if let Some(cache) = maybe_usable_cache {
-                    SequentialSource::File(cache)
+                    SequentialSource::CachedFile(cache)
                 } else if let Some(ref tempfile) = self.input {
                     SequentialSource::File(tempfile.clone())
                 } else if let Some(ref dir) = self.cmd_dir {
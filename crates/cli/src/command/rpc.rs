@@ -1,6 +1,7 @@
 use crate::app::Args;
 use anyhow::{anyhow, Result};
 use clap::Parser;
+use maple_core::config::StartupConfig;
 use maple_core::stdio_server::ConfigError;
 use std::io::IsTerminal;
 use tracing_subscriber::filter::EnvFilter;
@@ -10,9 +11,8 @@ use tracing_subscriber::filter::EnvFilter;
 pub struct Rpc;
 
 impl Rpc {
-    pub async fn run(&self, args: Args) -> Result<()> {
-        let (config, maybe_toml_err) =
-            maple_core::config::load_config_on_startup(args.config_file.clone());
+    pub async fn run(&self, args: Args, startup_config: StartupConfig) -> Result<()> {
+        let (config, maybe_toml_err) = startup_config.into_global();
 
         let maybe_log = if let Some(log_path) = args.log {
             Some(log_path)
@@ -25,6 +25,15 @@ impl Rpc {
         };
 
         if let Some(log_path) = maybe_log {
+            // The relative log file is placed in the log directory.
+            let log_path = if log_path.is_relative() {
+                let log_dir = maple_core::dirs::Dirs::log_dir();
+                std::fs::create_dir_all(log_dir)?;
+                log_dir.join(log_path)
+            } else {
+                log_path
+            };
+
             if let Ok(metadata) = std::fs::metadata(&log_path) {
                 // Remove the old log file automatically if its size exceeds 8MiB.
                 if log_path.is_file() && metadata.len() > 8 * 1024 * 1024 {
@@ -3,6 +3,8 @@ pub mod command;
 
 /// Re-exports.
 pub use app::{Args, RunCmd};
+pub use maple_core::config::StartupConfig;
+pub use maple_core::stdio_server::set_backend_version;
 
 use icon::Icon;
 use printer::{println_json, println_json_with_length};
@@ -1,13 +1,24 @@
 use directories::{BaseDirs, ProjectDirs};
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
+/// Directories specified by the user in the config.
+#[derive(Debug, Clone, Default)]
+pub struct DirOverrides {
+    pub cache_dir: Option<PathBuf>,
+    pub data_dir: Option<PathBuf>,
+    pub log_dir: Option<PathBuf>,
+}
+
+static OVERRIDES: OnceLock<DirOverrides> = OnceLock::new();
+
 pub struct Dirs;
 
 impl Dirs {
     /// Project directory specifically for Vim Clap.
     ///
-    /// All the files created by vim-clap are stored there.
+    /// All the files created by vim-clap are stored there unless overridden.
     pub fn project() -> &'static ProjectDirs {
         static CELL: OnceLock<ProjectDirs> = OnceLock::new();
 
@@ -24,10 +35,215 @@ impl Dirs {
         CELL.get_or_init(|| BaseDirs::new().expect("Failed to construct BaseDirs"))
     }
 
+    /// Sets the directories specified in the config.
+    ///
+    /// Must be called before any of the directories is accessed, no-op if called again.
+    pub fn set_overrides(overrides: DirOverrides) {
+        let _ = OVERRIDES.set(overrides);
+    }
+
+    /// Returns the directory specified by the environment variable `env_var`, then the
+    /// one in the config, or `default`.
+    fn resolve(
+        env_var: &str,
+        overridden: impl FnOnce(&DirOverrides) -> Option<&PathBuf>,
+        default: impl FnOnce() -> PathBuf,
+    ) -> PathBuf {
+        resolve_dir(
+            std::env::var_os(env_var),
+            OVERRIDES.get().and_then(overridden),
+            default,
+        )
+    }
+
+    /// Directory for the caches, e.g., the output of the commands.
+    ///
+    /// `$VIM_CLAP_CACHE_DIR` > `cache-dir` in the config > platform default:
+    ///
+    /// Linux: $XDG_CACHE_HOME/vimclap or ~/.cache/vimclap
+    /// macOS: ~/Library/Caches/org.vim.Vim-Clap
+    /// Windows: ~\AppData\Local\Vim\Vim Clap\cache
+    pub fn cache_dir() -> &'static Path {
+        static CELL: OnceLock<PathBuf> = OnceLock::new();
+
+        CELL.get_or_init(|| {
+            Self::resolve(
+                "VIM_CLAP_CACHE_DIR",
+                |overrides| overrides.cache_dir.as_ref(),
+                || Self::project().cache_dir().to_path_buf(),
+            )
+        })
+    }
+
+    /// Directory for the persistent data, e.g., recent files and the cache metadata.
+    ///
+    /// `$VIM_CLAP_DATA_DIR` > `data-dir` in the config > platform default:
+    ///
+    /// Linux: $XDG_DATA_HOME/vimclap or ~/.local/share/vimclap
+    /// macOS: ~/Library/Application Support/org.vim.Vim-Clap
+    /// Windows: ~\AppData\Roaming\Vim\Vim Clap\data
+    pub fn data_dir() -> &'static Path {
+        static CELL: OnceLock<PathBuf> = OnceLock::new();
+
+        CELL.get_or_init(|| {
+            Self::resolve(
+                "VIM_CLAP_DATA_DIR",
+                |overrides| overrides.data_dir.as_ref(),
+                || Self::project().data_dir().to_path_buf(),
+            )
+        })
+    }
+
+    /// Directory for the log files.
+    ///
+    /// `$VIM_CLAP_LOG_DIR` > `log-dir` in the config > platform default:
+    ///
+    /// Linux: $XDG_STATE_HOME/vimclap or ~/.local/state/vimclap
+    /// Others: `logs` under [`Self::data_dir`]
+    pub fn log_dir() -> &'static Path {
+        static CELL: OnceLock<PathBuf> = OnceLock::new();
+
+        CELL.get_or_init(|| {
+            Self::resolve(
+                "VIM_CLAP_LOG_DIR",
+                |overrides| overrides.log_dir.as_ref(),
+                || {
+                    Self::project()
+                        .state_dir()
+                        .map(Path::to_path_buf)
+                        .unwrap_or_else(|| Self::data_dir().join("logs"))
+                },
+            )
+        })
+    }
+
     /// Cache directory for Vim Clap project.
     pub fn clap_cache_dir() -> std::io::Result<PathBuf> {
-        let cache_dir = Self::project().cache_dir();
+        let cache_dir = Self::cache_dir();
         std::fs::create_dir_all(cache_dir)?;
         Ok(cache_dir.to_path_buf())
     }
+
+    /// Moves the existing cache and data directories at the platform default location to
+    /// the overridden location on the first run with it.
+    ///
+    /// Returns the pairs of the source and destination directories migrated.
+    pub fn migrate_to_overridden_dirs() -> std::io::Result<Vec<(PathBuf, PathBuf)>> {
+        let mut migrated = Vec::new();
+
+        for (default, current) in [
+            (Self::project().cache_dir(), Self::cache_dir()),
+            (Self::project().data_dir(), Self::data_dir()),
+        ] {
+            if current.starts_with(default)
+                || default.starts_with(current)
+                || !default.is_dir()
+                || !is_empty_dir(current)
+            {
+                continue;
+            }
+            move_dir(default, current)?;
+            migrated.push((default.to_path_buf(), current.to_path_buf()));
+        }
+
+        Ok(migrated)
+    }
+}
+
+/// Returns the directory of the non-empty `env_value`, then `overridden`, or `default`.
+fn resolve_dir(
+    env_value: Option<OsString>,
+    overridden: Option<&PathBuf>,
+    default: impl FnOnce() -> PathBuf,
+) -> PathBuf {
+    env_value
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| overridden.cloned())
+        .unwrap_or_else(default)
+}
+
+/// Returns `true` if `dir` does not exist or contains nothing.
+fn is_empty_dir(dir: &Path) -> bool {
+    std::fs::read_dir(dir).map_or(true, |mut entries| entries.next().is_none())
+}
+
+fn move_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Rename fails if the destination is an existing directory on some platforms.
+    let _ = std::fs::remove_dir(to);
+
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    // Fallback to copying when moving across the file systems.
+    copy_dir(from, to)?;
+    std::fs::remove_dir_all(from)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let dest = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &dest)?;
+        } else {
+            std::fs::copy(entry.path(), dest)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_dir() {
+        let overridden = PathBuf::from("/data/clap");
+        let default = || PathBuf::from("/home/user/.cache/vimclap");
+
+        assert_eq!(
+            resolve_dir(Some("/tmp/clap".into()), Some(&overridden), default),
+            PathBuf::from("/tmp/clap")
+        );
+        // The empty environment variable is ignored.
+        assert_eq!(
+            resolve_dir(Some("".into()), Some(&overridden), default),
+            overridden
+        );
+        assert_eq!(resolve_dir(None, None, default), default());
+    }
+
+    #[test]
+    fn test_move_dir() {
+        let root = std::env::temp_dir().join(format!("clap_dirs_{}", std::process::id()));
+        let from = root.join("from");
+        let to = root.join("nested").join("to");
+        std::fs::create_dir_all(from.join("sub")).unwrap();
+        std::fs::write(from.join("sub").join("file"), "content").unwrap();
+        assert!(!is_empty_dir(&from));
+        assert!(is_empty_dir(&to));
+
+        move_dir(&from, &to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(
+            std::fs::read_to_string(to.join("sub").join("file")).unwrap(),
+            "content"
+        );
+
+        let copied = root.join("copied");
+        copy_dir(&to, &copied).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(copied.join("sub").join("file")).unwrap(),
+            "content"
+        );
+        assert!(to.join("sub").join("file").exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
 }
@@ -0,0 +1,148 @@
+//! Comment detection by the comment syntax of the file extension.
+//!
+//! It's a line based approximation, the lines inside a block comment are recognized only
+//! if they start with the delimiter or the conventional leading `*`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Comment syntax of a language.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommentSyntax {
+    /// Prefixes of the line comment, e.g., `//`.
+    pub line: Vec<String>,
+    /// Start and end delimiters of the block comment, e.g., `["/*", "*/"]`.
+    pub block: Vec<(String, String)>,
+}
+
+impl CommentSyntax {
+    /// Returns `true` if `line` starts with a comment.
+    pub fn is_comment_line(&self, line: &str) -> bool {
+        let line = line.trim_start();
+
+        if line.is_empty() {
+            return false;
+        }
+
+        self.line
+            .iter()
+            .any(|prefix| line.starts_with(prefix.as_str()))
+            || self.block.iter().any(|(start, end)| {
+                line.starts_with(start.as_str())
+                    || line.starts_with(end.as_str())
+                    // ` * foo` inside `/* ... */`.
+                    || (start == "/*" && (line == "*" || line.starts_with("* ")))
+            })
+    }
+
+    fn extend(&mut self, other: &Self) {
+        for prefix in &other.line {
+            if !self.line.contains(prefix) {
+                self.line.push(prefix.clone());
+            }
+        }
+        for delimiters in &other.block {
+            if !self.block.contains(delimiters) {
+                self.block.push(delimiters.clone());
+            }
+        }
+    }
+}
+
+type CommentSyntaxTable = HashMap<String, Arc<CommentSyntax>>;
+
+fn builtin_table() -> &'static CommentSyntaxTable {
+    static BUILTIN_TABLE: OnceLock<CommentSyntaxTable> = OnceLock::new();
+
+    BUILTIN_TABLE.get_or_init(|| {
+        serde_json::from_str::<HashMap<String, CommentSyntax>>(include_str!(
+            "../../../scripts/dumb_jump/comments_map.json"
+        ))
+        .expect("Wrong path for comments_map.json")
+        .into_iter()
+        .map(|(ext, syntax)| (ext, Arc::new(syntax)))
+        .collect()
+    })
+}
+
+/// Builtin comment syntax extended by the user.
+static USER_TABLE: RwLock<Option<CommentSyntaxTable>> = RwLock::new(None);
+
+/// Extends the builtin comment syntax with the syntax specified by the user, keyed by
+/// the file extension.
+///
+/// The previous user syntax is replaced, which allows to apply the reloaded config.
+pub fn set_user_comment_syntax(user_syntax: &HashMap<String, CommentSyntax>) {
+    let table = user_syntax
+        .iter()
+        .map(|(ext, syntax)| {
+            let mut merged = builtin_table()
+                .get(ext)
+                .map(|builtin| builtin.as_ref().clone())
+                .unwrap_or_default();
+            merged.extend(syntax);
+            (ext.clone(), Arc::new(merged))
+        })
+        .collect();
+
+    *USER_TABLE.write().unwrap_or_else(|e| e.into_inner()) = Some(table);
+}
+
+/// Returns the comment syntax of a source file.
+///
+/// # Argument
+///
+/// - `ext`: the extension of a file, e.g., `rs`.
+pub fn get_comment_syntax(ext: &str) -> Arc<CommentSyntax> {
+    let user_table = USER_TABLE.read().unwrap_or_else(|e| e.into_inner());
+
+    if let Some(syntax) = user_table.as_ref().and_then(|table| table.get(ext)) {
+        return syntax.clone();
+    }
+
+    let table = builtin_table();
+    table
+        .get(ext)
+        .unwrap_or_else(|| table.get("*").expect("`*` entry exists; qed"))
+        .clone()
+}
+
+/// Return `true` if the line is a comment.
+pub fn is_comment(line: &str, file_ext: &str) -> bool {
+    get_comment_syntax(file_ext).is_comment_line(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_comment() {
+        assert!(is_comment("  // foo", "rs"));
+        assert!(is_comment("/// foo", "rs"));
+        assert!(is_comment(" * foo", "rs"));
+        assert!(is_comment(" */", "c"));
+        assert!(!is_comment("*ptr = 1;", "c"));
+        assert!(!is_comment("let x = 1; // foo", "rs"));
+        assert!(is_comment("# foo", "py"));
+        assert!(is_comment("-- foo", "lua"));
+        assert!(is_comment("<!-- foo -->", "html"));
+        assert!(is_comment("\" foo", "vim"));
+        assert!(!is_comment("# foo", "unknown"));
+
+        let user_syntax = HashMap::from([(
+            "py".to_string(),
+            CommentSyntax {
+                line: Vec::new(),
+                block: vec![("\"\"\"".into(), "\"\"\"".into())],
+            },
+        )]);
+        set_user_comment_syntax(&user_syntax);
+        assert!(is_comment("# foo", "py"));
+        assert!(is_comment("\"\"\" foo", "py"));
+        set_user_comment_syntax(&HashMap::new());
+        assert!(!is_comment("\"\"\" foo", "py"));
+    }
+}
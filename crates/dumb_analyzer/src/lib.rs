@@ -1,11 +1,12 @@
 //! Poor man's language analyzer.
 
 use keywords::KeywordPriority;
-use std::collections::HashMap;
-use std::sync::OnceLock;
 
+mod comment;
 mod keywords;
 
+pub use self::comment::{get_comment_syntax, is_comment, set_user_comment_syntax, CommentSyntax};
+
 const LOWEST_PRIORITY: usize = 1000usize;
 
 /// Display priority of the line.
@@ -32,31 +33,6 @@ impl From<usize> for Priority {
     }
 }
 
-/// Returns a list of comment prefix for a source file.
-///
-/// # Argument
-///
-/// - `ext`: the extension of a file, e.g., `rs`.
-pub fn get_comment_syntax(ext: &str) -> &[&str] {
-    static LANGUAGE_COMMENT_TABLE: OnceLock<HashMap<&str, Vec<&str>>> = OnceLock::new();
-
-    let table = LANGUAGE_COMMENT_TABLE.get_or_init(|| {
-        serde_json::from_str(include_str!("../../../scripts/dumb_jump/comments_map.json"))
-            .expect("Wrong path for comments_map.json")
-    });
-
-    table
-        .get(ext)
-        .unwrap_or_else(|| table.get("*").expect("`*` entry exists; qed"))
-}
-
-/// Return `true` if the line is a comment.
-pub fn is_comment(line: &str, file_ext: &str) -> bool {
-    get_comment_syntax(file_ext)
-        .iter()
-        .any(|comment_syntax| line.trim_start().starts_with(comment_syntax))
-}
-
 // TODO: More general precise reference resolution, tree-sitter?
 /// Returns a tuple of (ref_kind, kind_weight) given the pattern and source file extension.
 pub fn resolve_reference_kind(pattern: impl AsRef<str>, file_ext: &str) -> (&'static str, usize) {
@@ -6,6 +6,7 @@
 //! 2. sort the all lines with a match result.
 //! 3. print the top rated filtered lines to stdout.
 
+mod mmap_source;
 mod parallel_worker;
 mod sequential_source;
 mod sequential_worker;
@@ -13,9 +14,11 @@ mod sequential_worker;
 use icon::Icon;
 use matcher::{Bonus, MatchScope, Matcher, MatcherBuilder};
 use rayon::prelude::*;
+use std::collections::BinaryHeap;
 use std::sync::Arc;
 use types::{ClapItem, FileNameItem, GrepItem};
 
+pub use self::mmap_source::{count_cached_lines, mmap_lines, write_interned_grep_lines, MmapLines};
 pub use self::parallel_worker::{
     par_dyn_run, par_dyn_run_inprocess, par_dyn_run_list, BestItems, ParallelSource,
     StdioProgressor,
@@ -46,11 +49,76 @@ impl MatchedItems {
         Self(items)
     }
 
+    /// Returns the top `k` items, the item with highest score first.
+    ///
+    /// Cheaper than [`Self::par_sort`] when only the first page of results is needed.
+    pub fn top_k(self, k: usize) -> Vec<MatchedItem> {
+        let mut ranked_items = RankedItems::from(self.0);
+        ranked_items.top(k);
+        ranked_items.ranked
+    }
+
     pub fn inner(self) -> Vec<MatchedItem> {
         self.0
     }
 }
 
+/// Matched items ranked lazily by a priority queue.
+///
+/// Building the heap is O(n) and each of the best items is popped in O(log n) only
+/// when it's requested, e.g., on scrolling down the results, which avoids the full
+/// O(n log n) sort on each keystroke for a huge source.
+#[derive(Debug, Default)]
+pub struct RankedItems {
+    /// Items popped from `heap` so far, the item with highest score first.
+    ranked: Vec<MatchedItem>,
+    heap: BinaryHeap<MatchedItem>,
+}
+
+impl From<Vec<MatchedItem>> for RankedItems {
+    fn from(items: Vec<MatchedItem>) -> Self {
+        Self {
+            ranked: Vec::new(),
+            heap: BinaryHeap::from(items),
+        }
+    }
+}
+
+impl RankedItems {
+    /// Constructs from the items already in the display order, e.g., the unfiltered list.
+    pub fn from_sorted(items: Vec<MatchedItem>) -> Self {
+        Self {
+            ranked: items,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    /// Returns the total number of items.
+    pub fn len(&self) -> usize {
+        self.ranked.len() + self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the top `n` items, the item with highest score first.
+    pub fn top(&mut self, n: usize) -> &[MatchedItem] {
+        while self.ranked.len() < n {
+            match self.heap.pop() {
+                Some(matched_item) => self.ranked.push(matched_item),
+                None => break,
+            }
+        }
+        &self.ranked[..n.min(self.ranked.len())]
+    }
+
+    /// Returns the item at the 0-based rank `index`.
+    pub fn get(&mut self, index: usize) -> Option<&MatchedItem> {
+        self.top(index + 1).get(index)
+    }
+}
+
 impl From<Vec<MatchedItem>> for MatchedItems {
     fn from(items: Vec<MatchedItem>) -> Self {
         Self(items)
@@ -136,6 +204,18 @@ pub fn par_filter(
     matched_items.par_sort().inner()
 }
 
+/// Performs the filtering like [`par_filter_items`], but the matched items are ranked lazily.
+pub fn par_filter_items_ranked(
+    source_items: &[Arc<dyn ClapItem>],
+    fuzzy_matcher: &Matcher,
+) -> RankedItems {
+    source_items
+        .into_par_iter()
+        .filter_map(|item| fuzzy_matcher.match_item(item.clone()))
+        .collect::<Vec<_>>()
+        .into()
+}
+
 /// Performs the synchorous filtering on a small scale of source in parallel.
 pub fn par_filter_items(
     source_items: &[Arc<dyn ClapItem>],
@@ -148,3 +228,34 @@ pub fn par_filter_items(
         .into();
     matched_items.par_sort().inner()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matched_item(score: i32) -> MatchedItem {
+        let item: Arc<dyn ClapItem> = Arc::new(SourceItem::from(score.to_string()));
+        MatchedItem::new(item, [score, 0, 0, 0], Vec::new())
+    }
+
+    #[test]
+    fn test_ranked_items() {
+        let scores = [3, 9, 1, 7, 5, 8];
+        let items = scores.iter().copied().map(matched_item).collect::<Vec<_>>();
+
+        let mut ranked_items = RankedItems::from(items.clone());
+        assert_eq!(ranked_items.len(), scores.len());
+        let top = |items: &[MatchedItem]| items.iter().map(|i| i.rank[0]).collect::<Vec<_>>();
+        assert_eq!(top(ranked_items.top(3)), vec![9, 8, 7]);
+        assert_eq!(ranked_items.get(4).map(|i| i.rank[0]), Some(3));
+        assert_eq!(top(ranked_items.top(10)), vec![9, 8, 7, 5, 3, 1]);
+        assert!(ranked_items.get(6).is_none());
+
+        // The order is kept regardless of the scores.
+        let mut ranked_items = RankedItems::from_sorted(items.clone());
+        assert_eq!(top(ranked_items.top(10)), scores);
+
+        let sorted = MatchedItems::from(items.clone()).par_sort().inner();
+        assert_eq!(top(&MatchedItems::from(items).top_k(4)), top(&sorted[..4]));
+    }
+}
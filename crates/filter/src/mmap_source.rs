@@ -0,0 +1,322 @@
+//! Lines of a file accessed through the memory map.
+//!
+//! The cached sources can be gigabytes, only the offsets of the lines are kept in memory and
+//! each line is materialized when it's being matched.
+//!
+//! Only the cache files written by maple are mapped, a file truncated in place while it's
+//! mapped, e.g., a rotated log, raises SIGBUS on access.
+//!
+//! The grep caches repeat the long path of a file on each matched line, they are written
+//! with the paths interned by [`write_interned_grep_lines`], each path is stored once and
+//! expanded when the line is materialized.
+
+use memmap2::Mmap;
+use parking_lot::Mutex;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufRead, Result, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// First line of the grep cache with the interned paths.
+const INTERNED_GREP_HEADER: &[u8] = b"\x1eclap-interned-grep-v1\n";
+
+/// Leads the path shared by the following lines in the interned grep cache.
+const PATH_MARKER: u8 = 0x1e;
+
+/// Leads the line stored verbatim in the interned grep cache, which is not a grep line.
+const VERBATIM_MARKER: u8 = 0x1f;
+
+/// Writes the grep lines `path:lnum:col:text` read from `reader` to `writer` with the
+/// paths interned, returns the number of lines.
+///
+/// The consecutive lines of the same file share a single path line, the lines are
+/// expanded on reading by [`MmapLines`].
+pub fn write_interned_grep_lines(
+    mut reader: impl BufRead,
+    mut writer: impl Write,
+) -> Result<usize> {
+    writer.write_all(INTERNED_GREP_HEADER)?;
+
+    let mut last_path = Vec::new();
+    let mut buf = Vec::new();
+    let mut total = 0;
+
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        let line = buf.strip_suffix(b"\n").unwrap_or(&buf);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+        match std::str::from_utf8(line)
+            .ok()
+            .and_then(pattern::parse_grep_item)
+        {
+            Some((end_of_path, _)) => {
+                let (path, rest) = line.split_at(end_of_path);
+                if path != last_path.as_slice() {
+                    writer.write_all(&[PATH_MARKER])?;
+                    writer.write_all(path)?;
+                    writer.write_all(b"\n")?;
+                    last_path.clear();
+                    last_path.extend_from_slice(path);
+                }
+                // Skip the `:` after the path, the rest starts with the line number.
+                writer.write_all(&rest[1..])?;
+            }
+            None => {
+                writer.write_all(&[VERBATIM_MARKER])?;
+                writer.write_all(line)?;
+            }
+        }
+        writer.write_all(b"\n")?;
+        total += 1;
+    }
+
+    writer.flush()?;
+
+    Ok(total)
+}
+
+/// Interned path shared by the lines from `first_line`.
+#[derive(Debug)]
+struct InternedPath {
+    first_line: usize,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug)]
+pub struct MmapLines {
+    /// `None` for an empty file which can not be mapped.
+    mmap: Option<Mmap>,
+    /// Start offset of each line.
+    line_starts: Vec<usize>,
+    /// Paths of the lines in the interned grep cache, sorted by the first line.
+    paths: Option<Vec<InternedPath>>,
+}
+
+impl MmapLines {
+    /// Maps the file and builds the offset index of the lines.
+    ///
+    /// `path` must never be modified in place, see the module doc.
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+
+        if file.metadata()?.len() == 0 {
+            return Ok(Self {
+                mmap: None,
+                line_starts: Vec::new(),
+                paths: None,
+            });
+        }
+
+        // SAFETY: The cache files are written atomically by renaming, the mapped file is
+        // never modified in place.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        if mmap.starts_with(INTERNED_GREP_HEADER) {
+            return Ok(Self::index_interned_grep_lines(mmap));
+        }
+
+        let mut line_starts = Vec::with_capacity(mmap.len() / 64);
+        line_starts.push(0);
+        line_starts.extend(
+            memchr::memchr_iter(b'\n', &mmap)
+                .map(|pos| pos + 1)
+                .filter(|&start| start < mmap.len()),
+        );
+
+        Ok(Self {
+            mmap: Some(mmap),
+            line_starts,
+            paths: None,
+        })
+    }
+
+    fn index_interned_grep_lines(mmap: Mmap) -> Self {
+        let mut line_starts = Vec::with_capacity(mmap.len() / 32);
+        let mut paths = Vec::new();
+
+        let mut start = INTERNED_GREP_HEADER.len();
+        while start < mmap.len() {
+            let end = memchr::memchr(b'\n', &mmap[start..]).map_or(mmap.len(), |pos| start + pos);
+            if mmap[start] == PATH_MARKER {
+                paths.push(InternedPath {
+                    first_line: line_starts.len(),
+                    start: start + 1,
+                    end,
+                });
+            } else {
+                line_starts.push(start);
+            }
+            start = end + 1;
+        }
+
+        Self {
+            mmap: Some(mmap),
+            line_starts,
+            paths: Some(paths),
+        }
+    }
+
+    /// Returns the number of lines.
+    pub fn len(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.line_starts.is_empty()
+    }
+
+    /// Returns the bytes of line `idx`, without the line terminator.
+    pub fn line(&self, idx: usize) -> Cow<'_, [u8]> {
+        let Some(mmap) = &self.mmap else {
+            return Cow::Borrowed(&[]);
+        };
+        let start = self.line_starts[idx];
+        let end = memchr::memchr(b'\n', &mmap[start..]).map_or(mmap.len(), |pos| start + pos);
+        let line = &mmap[start..end];
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+        let Some(paths) = &self.paths else {
+            return Cow::Borrowed(line);
+        };
+
+        if let Some(verbatim) = line.strip_prefix(&[VERBATIM_MARKER]) {
+            return Cow::Borrowed(verbatim);
+        }
+
+        let path = match paths.partition_point(|path| path.first_line <= idx) {
+            0 => &[][..],
+            n => &mmap[paths[n - 1].start..paths[n - 1].end],
+        };
+        let mut expanded = Vec::with_capacity(path.len() + 1 + line.len());
+        expanded.extend_from_slice(path);
+        expanded.push(b':');
+        expanded.extend_from_slice(line);
+        Cow::Owned(expanded)
+    }
+
+    /// Returns the line `idx` as String, the invalid UTF-8 sequences are replaced.
+    pub fn line_string(&self, idx: usize) -> String {
+        String::from_utf8_lossy(&self.line(idx)).into_owned()
+    }
+}
+
+/// Returns the number of lines in the cache file at `path`.
+pub fn count_cached_lines(path: &Path) -> Result<usize> {
+    let mut file = File::open(path)?;
+    let mut header = [0u8; INTERNED_GREP_HEADER.len()];
+    let is_interned =
+        std::io::Read::read_exact(&mut file, &mut header).is_ok() && header == INTERNED_GREP_HEADER;
+    if is_interned {
+        Ok(mmap_lines(path)?.len())
+    } else {
+        utils::count_lines(File::open(path)?)
+    }
+}
+
+#[derive(Debug)]
+struct IndexedFile {
+    path: PathBuf,
+    len: u64,
+    modified: Option<SystemTime>,
+    lines: Arc<MmapLines>,
+}
+
+/// The last indexed file, the same source is usually filtered repeatedly on typing.
+static LAST_INDEXED: Mutex<Option<IndexedFile>> = parking_lot::const_mutex(None);
+
+/// Returns the lines of `path`, reusing the offset index if the file is unchanged since
+/// last time.
+pub fn mmap_lines(path: &Path) -> Result<Arc<MmapLines>> {
+    let metadata = std::fs::metadata(path)?;
+    let len = metadata.len();
+    let modified = metadata.modified().ok();
+
+    let mut last_indexed = LAST_INDEXED.lock();
+
+    if let Some(indexed) = last_indexed.as_ref() {
+        if indexed.path == path && indexed.len == len && indexed.modified == modified {
+            return Ok(indexed.lines.clone());
+        }
+    }
+
+    let lines = Arc::new(MmapLines::open(path)?);
+    last_indexed.replace(IndexedFile {
+        path: path.to_path_buf(),
+        len,
+        modified,
+        lines: lines.clone(),
+    });
+
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mmap_lines() {
+        let path =
+            std::env::temp_dir().join(format!("clap_test_mmap_lines_{}", std::process::id()));
+
+        std::fs::write(&path, "foo\r\nbar\n\nbaz").unwrap();
+        let lines = MmapLines::open(&path).unwrap();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(&*lines.line(0), b"foo");
+        assert_eq!(&*lines.line(2), b"");
+        assert_eq!(lines.line_string(3), "baz");
+
+        std::fs::write(&path, b"foo\n\xffbar\n").unwrap();
+        let lines = mmap_lines(&path).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines.line_string(1), "\u{fffd}bar");
+        assert!(Arc::ptr_eq(&lines, &mmap_lines(&path).unwrap()));
+
+        std::fs::write(&path, "").unwrap();
+        assert!(MmapLines::open(&path).unwrap().is_empty());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_interned_grep_lines() {
+        let path = std::env::temp_dir().join(format!("test_interned_grep_{}", std::process::id()));
+
+        let grep_lines = [
+            "crates/foo/src/lib.rs:1:1:fn main() {}",
+            "crates/foo/src/lib.rs:12:5:let x = 1;",
+            "README.md:3:1:# Title",
+            "not a grep line",
+            "crates/foo/src/lib.rs:20:1:}",
+        ];
+        let mut content = Vec::new();
+        let total =
+            write_interned_grep_lines(grep_lines.join("\n").as_bytes(), &mut content).unwrap();
+        assert_eq!(total, grep_lines.len());
+        // The path of the second line is interned.
+        assert_eq!(
+            content
+                .windows(b"crates/foo/src/lib.rs".len())
+                .filter(|w| w == b"crates/foo/src/lib.rs")
+                .count(),
+            2
+        );
+
+        std::fs::write(&path, &content).unwrap();
+        let lines = MmapLines::open(&path).unwrap();
+        assert_eq!(lines.len(), grep_lines.len());
+        for (idx, expected) in grep_lines.iter().enumerate() {
+            assert_eq!(lines.line_string(idx), *expected);
+        }
+        assert_eq!(count_cached_lines(&path).unwrap(), grep_lines.len());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
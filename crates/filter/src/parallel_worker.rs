@@ -1,5 +1,6 @@
 //! Convert the source item stream to a parallel iterator and run the filtering in parallel.
 
+use crate::mmap_source::{mmap_lines, MmapLines};
 use crate::{to_clap_item, FilterContext};
 use parking_lot::Mutex;
 use printer::{println_json_with_length, DisplayLines, Printer};
@@ -18,7 +19,12 @@ use types::{ClapItem, MatchedItem, Query};
 #[derive(Debug)]
 pub enum ParallelSource {
     File(PathBuf),
+    /// Cache file owned by maple, which is memory-mapped as it's only replaced by
+    /// renaming and never truncated in place.
+    CachedFile(PathBuf),
     Exec(Box<Exec>),
+    /// Stdout of a process spawned by the caller, which is in charge of its lifetime.
+    Stdout(std::process::ChildStdout),
 }
 
 /// Returns the ranked results after applying fuzzy filter given the query string and a list of candidates.
@@ -40,6 +46,13 @@ pub fn par_dyn_run(
                 ParSourceInner::Lines(std::fs::File::open(file)?),
             )?;
         }
+        ParallelSource::CachedFile(file) => {
+            par_dyn_run_inner::<Empty<_>, std::io::Empty>(
+                query,
+                filter_context,
+                ParSourceInner::Mmap(mmap_lines(&file)?),
+            )?;
+        }
         ParallelSource::Exec(exec) => {
             par_dyn_run_inner::<Empty<_>, _>(
                 query,
@@ -47,6 +60,9 @@ pub fn par_dyn_run(
                 ParSourceInner::Lines(exec.stream_stdout()?),
             )?;
         }
+        ParallelSource::Stdout(stdout) => {
+            par_dyn_run_inner::<Empty<_>, _>(query, filter_context, ParSourceInner::Lines(stdout))?;
+        }
     }
 
     Ok(())
@@ -185,6 +201,7 @@ impl SearchProgressUpdate<DisplayLines> for StdioProgressor {
             indices,
             truncated_map,
             icon_added,
+            ..
         } = display_lines;
 
         if truncated_map.is_empty() {
@@ -220,6 +237,7 @@ impl SearchProgressUpdate<DisplayLines> for StdioProgressor {
             indices,
             truncated_map,
             icon_added,
+            ..
         } = display_lines;
 
         #[allow(non_upper_case_globals)]
@@ -239,6 +257,7 @@ impl SearchProgressUpdate<DisplayLines> for StdioProgressor {
 enum ParSourceInner<I: IntoParallelIterator<Item = Arc<dyn ClapItem>>, R: Read + Send> {
     Items(I),
     Lines(R),
+    Mmap(Arc<MmapLines>),
 }
 
 /// Perform the matching on a stream of [`Source::File`] and `[Source::Exec]` in parallel.
@@ -304,6 +323,14 @@ where
                     }
                 });
         }
+        ParSourceInner::Mmap(lines) => {
+            (0..lines.len()).into_par_iter().for_each(|idx| {
+                let processed = processed_count.fetch_add(1, Ordering::SeqCst);
+                if let Some(item) = to_clap_item(matcher.match_scope(), lines.line_string(idx)) {
+                    process_item(item, processed);
+                }
+            });
+        }
     }
 
     let total_matched = matched_count.into_inner();
@@ -372,35 +399,57 @@ where
         }
     };
 
-    let read: Box<dyn std::io::Read + Send> = match par_source {
-        ParallelSource::File(file) => Box::new(std::fs::File::open(file)?),
-        ParallelSource::Exec(exec) => Box::new(
-            exec.detached()
-                .stream_stdout()
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?,
-        ), // TODO: kill the exec command ASAP/ Run the exec command in another blocking task.
+    let try_process_line = |line: String| {
+        if stop_signal.load(Ordering::SeqCst) {
+            tracing::debug!(?matcher, "[par_dyn_run_inprocess] stop signal received");
+            // Note that even the stop signal has been received, the thread created by
+            // rayon does not exit actually, it just tries to stop the work ASAP.
+            Err(())
+        } else {
+            let processed = processed_count.fetch_add(1, Ordering::SeqCst);
+            if let Some(item) = to_clap_item(matcher.match_scope(), line) {
+                process_item(item, processed);
+            }
+            Ok(())
+        }
     };
 
-    // To avoid Err(Custom { kind: InvalidData, error: "stream did not contain valid UTF-8" })
-    // The line stream can contain invalid UTF-8 data.
-    let res = std::io::BufReader::new(read)
-        .lines()
-        .map_while(Result::ok)
-        .par_bridge()
-        .try_for_each(|line: String| {
-            if stop_signal.load(Ordering::SeqCst) {
-                tracing::debug!(?matcher, "[par_dyn_run_inprocess] stop signal received");
-                // Note that even the stop signal has been received, the thread created by
-                // rayon does not exit actually, it just tries to stop the work ASAP.
-                Err(())
-            } else {
-                let processed = processed_count.fetch_add(1, Ordering::SeqCst);
-                if let Some(item) = to_clap_item(matcher.match_scope(), line) {
-                    process_item(item, processed);
-                }
-                Ok(())
-            }
-        });
+    let res = match par_source {
+        // The lines are materialized on demand instead of reading the entire file.
+        ParallelSource::CachedFile(file) => {
+            let lines = mmap_lines(&file)?;
+            (0..lines.len())
+                .into_par_iter()
+                .try_for_each(|idx| try_process_line(lines.line_string(idx)))
+        }
+        // The user files may be truncated in place while being read, which is fatal
+        // for a memory map.
+        ParallelSource::File(file) => std::io::BufReader::new(std::fs::File::open(file)?)
+            .lines()
+            .map_while(Result::ok)
+            .par_bridge()
+            .try_for_each(try_process_line),
+        ParallelSource::Exec(exec) => {
+            // TODO: kill the exec command ASAP/ Run the exec command in another blocking task.
+            let read = exec
+                .detached()
+                .stream_stdout()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+
+            // To avoid Err(Custom { kind: InvalidData, error: "stream did not contain valid UTF-8" })
+            // The line stream can contain invalid UTF-8 data.
+            std::io::BufReader::new(read)
+                .lines()
+                .map_while(Result::ok)
+                .par_bridge()
+                .try_for_each(try_process_line)
+        }
+        ParallelSource::Stdout(stdout) => std::io::BufReader::new(stdout)
+            .lines()
+            .map_while(Result::ok)
+            .par_bridge()
+            .try_for_each(try_process_line),
+    };
 
     let total_matched = matched_count.into_inner();
     let total_processed = processed_count.into_inner();
@@ -13,6 +13,8 @@ pub enum SequentialSource<I: Iterator<Item = Arc<dyn ClapItem>>> {
     List(I),
     Stdin,
     File(PathBuf),
+    /// Cache file written by maple, see [`crate::MmapLines`].
+    CachedFile(PathBuf),
     Exec(Box<Exec>),
 }
 
@@ -47,6 +49,12 @@ pub fn filter_sequential<I: Iterator<Item = Arc<dyn ClapItem>>>(
                 .map_while(Result::ok)
                 .map(|line| Arc::new(SourceItem::from(line)) as Arc<dyn ClapItem>),
         ),
+        SequentialSource::CachedFile(path) => {
+            let lines = crate::mmap_lines(&path)?;
+            Box::new((0..lines.len()).map(move |idx| {
+                Arc::new(SourceItem::from(lines.line_string(idx))) as Arc<dyn ClapItem>
+            }))
+        }
         SequentialSource::Exec(exec) => Box::new(
             std::io::BufReader::new(exec.stream_stdout()?)
                 .lines()
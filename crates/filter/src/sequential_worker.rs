@@ -310,6 +310,7 @@ fn print_on_dyn_run_finished(display_lines: DisplayLines, total_matched: usize)
         indices,
         truncated_map,
         icon_added,
+        ..
     } = display_lines;
 
     #[allow(non_upper_case_globals)]
@@ -355,6 +356,14 @@ pub fn dyn_run<I: Iterator<Item = Arc<dyn ClapItem>>>(
                 .map_while(Result::ok)
                 .filter_map(|line| to_clap_item(matcher.match_scope(), line)),
         ),
+        SequentialSource::CachedFile(path) => {
+            let lines = crate::mmap_lines(&path)?;
+            let match_scope = matcher.match_scope();
+            Box::new(
+                (0..lines.len())
+                    .filter_map(move |idx| to_clap_item(match_scope, lines.line_string(idx))),
+            )
+        }
         SequentialSource::Exec(exec) => Box::new(
             std::io::BufReader::new(exec.stream_stdout()?)
                 .lines()
@@ -367,8 +376,7 @@ pub fn dyn_run<I: Iterator<Item = Arc<dyn ClapItem>>>(
 
     if let Some(number) = number {
         let (total_matched, matched_items) = dyn_collect_number(matched_item_stream, number, icon);
-        let mut matched_items = MatchedItems::from(matched_items).par_sort().inner();
-        matched_items.truncate(number);
+        let matched_items = MatchedItems::from(matched_items).top_k(number);
 
         let printer = Printer::new(winwidth.unwrap_or(100), icon);
         let display_lines = printer.to_display_lines(matched_items);
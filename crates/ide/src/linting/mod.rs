@@ -26,6 +26,19 @@ pub enum Severity {
     Unknown,
 }
 
+impl Severity {
+    /// Returns the icon displayed in front of the diagnostic message.
+    pub fn icon(&self) -> char {
+        match self {
+            Self::Error => '\u{ea87}',
+            Self::Warning => '\u{ea6c}',
+            Self::Hint => '\u{ea61}',
+            Self::Info => '\u{f449}',
+            _ => '\u{ea74}',
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
 pub struct DiagnosticSpan {
     /// 1-based.
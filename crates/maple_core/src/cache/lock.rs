@@ -0,0 +1,211 @@
+//! Coordinates the cache writes among the concurrently running instances.
+
+use std::future::Future;
+use std::io::{ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+/// A lock file older than this is considered left over by a crashed instance.
+const STALE_LOCK_AGE: Duration = Duration::from_secs(10 * 60);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+/// Returns an id unique among the processes and threads.
+fn unique_id() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{}.{n}", std::process::id())
+}
+
+/// Returns a temporary path next to `path`, unique among the processes and threads.
+fn temp_path(path: &Path) -> PathBuf {
+    with_suffix(path, &format!(".{}.tmp", unique_id()))
+}
+
+fn rename_or_cleanup<T>(result: Result<T>, temp: &Path, path: &Path) -> Result<T> {
+    match result.and_then(|ret| std::fs::rename(temp, path).map(|()| ret)) {
+        Ok(ret) => Ok(ret),
+        Err(err) => {
+            let _ = std::fs::remove_file(temp);
+            Err(err)
+        }
+    }
+}
+
+/// Writes the file at `path` atomically.
+///
+/// `write` writes the contents to the given temporary file, which is renamed to `path` on
+/// success, so that the readers never see a partially written file.
+pub fn write_atomically<T>(path: &Path, write: impl FnOnce(&Path) -> Result<T>) -> Result<T> {
+    let temp = temp_path(path);
+    rename_or_cleanup(write(&temp), &temp, path)
+}
+
+/// Async version of [`write_atomically`].
+pub async fn write_atomically_async<T, F, Fut>(path: &Path, write: F) -> Result<T>
+where
+    F: FnOnce(PathBuf) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let temp = temp_path(path);
+    let result = write(temp.clone()).await;
+    rename_or_cleanup(result, &temp, path)
+}
+
+fn is_stale(lock_path: &Path) -> bool {
+    std::fs::metadata(lock_path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .map_or(false, |age| age > STALE_LOCK_AGE)
+}
+
+/// Exclusive lock on a cache file shared by all the instances, released on drop.
+#[derive(Debug)]
+pub struct CacheLock {
+    lock_path: PathBuf,
+    /// Written into the lock file to tell whether the lock is still owned by this instance.
+    token: String,
+}
+
+impl CacheLock {
+    fn lock_path(path: &Path) -> PathBuf {
+        with_suffix(path, ".lock")
+    }
+
+    fn create(lock_path: &Path, token: &str) -> Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(lock_path)?;
+        if let Err(err) = write!(file, "{token}") {
+            let _ = std::fs::remove_file(lock_path);
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Moves the stale lock out of the way, returns `true` if it has been taken over.
+    ///
+    /// The lock is renamed to a unique path rather than removed, so that only one of the
+    /// instances racing for it wins and a lock freshly created by another one is never lost.
+    fn take_over_stale(lock_path: &Path) -> bool {
+        let renamed = temp_path(lock_path);
+        if std::fs::rename(lock_path, &renamed).is_err() {
+            return false;
+        }
+
+        if is_stale(&renamed) {
+            tracing::debug!(?lock_path, "Removed the stale cache lock");
+            let _ = std::fs::remove_file(&renamed);
+            true
+        } else {
+            // Another instance has just replaced the stale lock, put it back.
+            let _ = std::fs::hard_link(&renamed, lock_path);
+            let _ = std::fs::remove_file(&renamed);
+            false
+        }
+    }
+
+    /// Acquires the lock of the cache file `path`, returns `None` if another instance holds it.
+    pub fn try_acquire(path: &Path) -> Option<Self> {
+        let lock_path = Self::lock_path(path);
+        let token = unique_id();
+
+        // Retry once after taking over the stale lock.
+        for _ in 0..2 {
+            match Self::create(&lock_path, &token) {
+                Ok(()) => return Some(Self { lock_path, token }),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists && is_stale(&lock_path) => {
+                    if !Self::take_over_stale(&lock_path) {
+                        return None;
+                    }
+                }
+                Err(_) => return None,
+            }
+        }
+
+        None
+    }
+
+    /// Returns `true` if the cache file `path` is being written by another instance.
+    pub fn is_locked(path: &Path) -> bool {
+        let lock_path = Self::lock_path(path);
+        lock_path.exists() && !is_stale(&lock_path)
+    }
+
+    /// Blocks until the lock of `path` is released or `timeout` elapses.
+    pub fn wait_released(path: &Path, timeout: Duration) {
+        let start = Instant::now();
+        while Self::is_locked(path) && start.elapsed() < timeout {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Async version of [`Self::wait_released`].
+    pub async fn wait_released_async(path: &Path, timeout: Duration) {
+        let start = Instant::now();
+        while Self::is_locked(path) && start.elapsed() < timeout {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+impl Drop for CacheLock {
+    fn drop(&mut self) {
+        // The lock may have been taken over by another instance after it was judged stale.
+        match std::fs::read_to_string(&self.lock_path) {
+            Ok(token) if token == self.token => {
+                let _ = std::fs::remove_file(&self.lock_path);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_lock() {
+        let path =
+            std::env::temp_dir().join(format!("clap_test_cache_lock_{}", std::process::id()));
+
+        let lock = CacheLock::try_acquire(&path).unwrap();
+        assert!(CacheLock::is_locked(&path));
+        assert!(CacheLock::try_acquire(&path).is_none());
+
+        write_atomically(&path, |temp| std::fs::write(temp, "foo")).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "foo");
+
+        // The original file is intact if the write fails.
+        assert!(write_atomically(&path, |temp| {
+            std::fs::write(temp, "bar")?;
+            Err::<(), _>(std::io::Error::new(ErrorKind::Other, "interrupted"))
+        })
+        .is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "foo");
+
+        drop(lock);
+        assert!(!CacheLock::is_locked(&path));
+        assert!(CacheLock::try_acquire(&path).is_some());
+
+        // The lock taken over by another instance is left as is.
+        let lock = CacheLock::try_acquire(&path).unwrap();
+        std::fs::write(&lock.lock_path, "other").unwrap();
+        let lock_path = lock.lock_path.clone();
+        drop(lock);
+        assert!(CacheLock::is_locked(&path));
+        std::fs::remove_file(lock_path).unwrap();
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
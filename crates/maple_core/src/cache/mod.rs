@@ -1,10 +1,61 @@
+mod lock;
+
 use crate::datastore::CACHE_INFO_IN_MEMORY;
 use crate::process::ShellCommand;
 use crate::UtcTime;
 use chrono::prelude::*;
-use std::path::PathBuf;
+use std::future::Future;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+
+pub use self::lock::{write_atomically, write_atomically_async, CacheLock};
+
+/// Version of the cache format.
+///
+/// The digests created by a different version are not usable and the caches will be rebuilt.
+pub const CACHE_VERSION: u32 = 1;
+
+/// Name of the directory under the cache directory for the corrupted cache files.
+const QUARANTINE_DIR: &str = "quarantine";
+
+/// Size and CRC32 checksum of a cache file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CacheChecksum {
+    /// Size of the file in bytes.
+    pub size: u64,
+    pub crc32: u32,
+}
+
+impl CacheChecksum {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self {
+            size: bytes.len() as u64,
+            crc32: crc32fast::hash(bytes),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> std::io::Result<Self> {
+        let mut file = std::fs::File::open(path)?;
+        let mut hasher = crc32fast::Hasher::new();
+        let mut buffer = vec![0u8; 64 * 1024];
+        let mut size = 0u64;
+        loop {
+            let n = file.read(&mut buffer)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buffer[..n]);
+            size += n as u64;
+        }
+        Ok(Self {
+            size,
+            crc32: hasher.finalize(),
+        })
+    }
+}
 
 /// Digest of a cached command execution.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -24,18 +75,31 @@ pub struct Digest {
     pub total_executions: usize,
     /// File persistent on the disk for caching the results.
     pub cached_path: PathBuf,
+    /// Version of the cache format, 0 for the digests created before the versioning.
+    #[serde(default)]
+    pub version: u32,
+    /// Checksum of the cache file at the time of creation.
+    #[serde(default)]
+    pub checksum: Option<CacheChecksum>,
 }
 
 impl Digest {
     const EXECUTION_EXPIRATION_DAYS: i64 = 3;
 
     /// Creates an instance of [`Digest`].
-    pub fn new(shell_cmd: ShellCommand, total: usize, cached_path: PathBuf) -> Self {
+    pub fn new(
+        shell_cmd: ShellCommand,
+        total: usize,
+        cached_path: PathBuf,
+        checksum: CacheChecksum,
+    ) -> Self {
         let now = Utc::now();
         Self {
             shell_cmd,
             total,
             cached_path,
+            version: CACHE_VERSION,
+            checksum: Some(checksum),
             last_visit: now,
             total_visits: 1,
             total_executions: 1,
@@ -67,7 +131,45 @@ impl Digest {
 
         // TODO: when the preview content mismatches the line, the cache is outdated and should be updated.
 
-        self.cached_path.exists()
+        self.version == CACHE_VERSION && self.cached_path.exists() && !self.is_corrupted(false)
+    }
+
+    /// Returns `true` if the cache file no longer matches the checksum recorded on creation.
+    ///
+    /// Only the file size is compared unless `thorough` is true, which is cheap enough for
+    /// every lookup and catches the files truncated by a crash.
+    pub fn is_corrupted(&self, thorough: bool) -> bool {
+        let Some(expected) = self.checksum else {
+            return true;
+        };
+
+        if thorough {
+            CacheChecksum::from_file(&self.cached_path).map_or(true, |c| c != expected)
+        } else {
+            std::fs::metadata(&self.cached_path).map_or(true, |m| m.len() != expected.size)
+        }
+    }
+}
+
+/// Moves the corrupted cache file aside for inspection, the cache will be rebuilt on the next run.
+///
+/// The quarantined file is overwritten by the next corrupted cache of the same command.
+fn quarantine(cached_path: &Path) {
+    let Some(file_name) = cached_path.file_name() else {
+        return;
+    };
+
+    let quarantined = crate::datastore::generate_cache_file_path(QUARANTINE_DIR).and_then(|dir| {
+        std::fs::create_dir_all(&dir)?;
+        std::fs::rename(cached_path, dir.join(file_name))
+    });
+
+    match quarantined {
+        Ok(()) => tracing::warn!(?cached_path, "Quarantined the corrupted cache file"),
+        Err(err) => {
+            tracing::debug!(?err, ?cached_path, "Failed to quarantine the cache file");
+            let _ = std::fs::remove_file(cached_path);
+        }
     }
 }
 
@@ -96,20 +198,31 @@ impl CacheInfo {
     /// Remove the entries whose `cwd` no longer exists.
     ///
     /// The original directory for the cache can be deleted or moved to another place.
+    ///
+    /// Only the metadata of the cache files is checked, see [`remove_corrupted_entries`]
+    /// for verifying the checksums.
     pub fn remove_invalid_and_old_entries(&mut self) {
         let now = Utc::now();
 
         const MAX_DAYS: i64 = 30;
 
-        self.digests.retain(|digest| {
+        self.digests.retain_mut(|digest| {
+            // The cache directory may have been migrated to another place.
+            if !digest.cached_path.exists() {
+                if let Some(moved) = digest
+                    .cached_path
+                    .file_name()
+                    .map(|file_name| dirs::Dirs::cache_dir().join(file_name))
+                    .filter(|moved| moved.exists())
+                {
+                    digest.cached_path = moved;
+                }
+            }
+
             if digest.shell_cmd.dir.exists()
                 && digest.cached_path.exists()
+                && digest.version == CACHE_VERSION
                 && now.signed_duration_since(digest.last_visit).num_days() < MAX_DAYS
-                // In case the cache was not created completely.
-                && std::fs::File::open(&digest.cached_path)
-                    .and_then(utils::count_lines)
-                    .map(|total| total == digest.total)
-                    .unwrap_or(false)
             {
                 true
             } else {
@@ -136,6 +249,10 @@ impl CacheInfo {
 
                     Some(d.clone())
                 } else {
+                    if d.version == CACHE_VERSION && d.cached_path.exists() && d.is_corrupted(false)
+                    {
+                        quarantine(&d.cached_path);
+                    }
                     if let Err(err) = self.prune_stale(index) {
                         tracing::error!(?err, "Failed to prune the stale cache digest");
                     }
@@ -184,6 +301,53 @@ impl CacheInfo {
         crate::datastore::store_cache_info(self)
     }
 
+    /// Merges the digests added to `on_disk` by the other instances, the digest executed
+    /// later wins if both have the same command.
+    fn merge(&mut self, on_disk: CacheInfo) {
+        for digest in on_disk.digests {
+            if !digest.cached_path.exists() {
+                continue;
+            }
+            match self.find_digest(&digest.shell_cmd) {
+                Some(index) => {
+                    if digest.execution_time > self.digests[index].execution_time {
+                        self.digests[index] = digest;
+                    }
+                }
+                None => self.digests.push(digest),
+            }
+        }
+
+        if self.digests.len() > Self::MAX_DIGESTS {
+            self.digests.sort_unstable_by_key(|k| k.stale_score());
+            self.digests.truncate(Self::MAX_DIGESTS);
+        }
+    }
+
+    /// Removes the digests whose command was executed in a directory containing `changed_path`.
+    ///
+    /// Returns `true` if any digest has been removed.
+    pub fn invalidate_outdated(&mut self, changed_path: &Path) -> std::io::Result<bool> {
+        let total_digests = self.digests.len();
+
+        self.digests.retain(|digest| {
+            if changed_path.starts_with(&digest.shell_cmd.dir) {
+                let _ = std::fs::remove_file(&digest.cached_path);
+                false
+            } else {
+                true
+            }
+        });
+
+        if self.digests.len() == total_digests {
+            return Ok(false);
+        }
+
+        crate::datastore::store_cache_info(self)?;
+
+        Ok(true)
+    }
+
     /// Prunes the stale digest at index of `stale_index`.
     pub fn prune_stale(&mut self, stale_index: usize) -> std::io::Result<()> {
         self.digests.swap_remove(stale_index);
@@ -195,6 +359,39 @@ impl CacheInfo {
     }
 }
 
+/// Verifies the checksums of the cache files in [`CACHE_INFO_IN_MEMORY`], in case a cache
+/// was not created completely or has been damaged since then. The corrupted ones are
+/// quarantined.
+///
+/// The lock is not held while reading the whole cache files.
+pub fn remove_corrupted_entries() {
+    let digests = CACHE_INFO_IN_MEMORY.lock().to_digests();
+    let corrupted = digests
+        .into_iter()
+        .filter(|digest| digest.is_corrupted(true))
+        .collect::<Vec<_>>();
+
+    if corrupted.is_empty() {
+        return;
+    }
+
+    let mut cache_info = CACHE_INFO_IN_MEMORY.lock();
+    cache_info.digests.retain(|digest| {
+        // The cache may have been rebuilt since the verification.
+        let is_corrupted = corrupted.iter().any(|corrupted| {
+            corrupted.cached_path == digest.cached_path && corrupted.checksum == digest.checksum
+        });
+        if is_corrupted {
+            quarantine(&digest.cached_path);
+        }
+        !is_corrupted
+    });
+
+    if let Err(err) = crate::datastore::store_cache_info(&cache_info) {
+        tracing::error!(?err, "Failed to store the cache info");
+    }
+}
+
 /// Pushes the digest of the results of new fresh run to [`CACHE_INFO_IN_MEMORY`].
 pub fn push_cache_digest(digest: Digest) {
     let cache_info = CACHE_INFO_IN_MEMORY.clone();
@@ -211,17 +408,133 @@ pub fn store_cache_digest(
     shell_cmd: ShellCommand,
     new_created_cache: PathBuf,
 ) -> std::io::Result<Digest> {
-    let total = utils::count_lines(std::fs::File::open(&new_created_cache)?)?;
+    let total = filter::count_cached_lines(&new_created_cache)?;
+    let checksum = CacheChecksum::from_file(&new_created_cache)?;
+
+    let digest = Digest::new(shell_cmd, total, new_created_cache, checksum);
 
-    let digest = Digest::new(shell_cmd, total, new_created_cache);
+    // Another instance may have added its digests to the metadata file since we loaded it,
+    // which must not be dropped by writing our in-memory cache info back.
+    let _lock = lock_cache_metadata();
 
     let cache_info = crate::datastore::CACHE_INFO_IN_MEMORY.clone();
     let mut cache_info = cache_info.lock();
+    if let Some(on_disk) = crate::datastore::load_cache_info() {
+        cache_info.merge(on_disk);
+    }
     cache_info.limited_push(digest.clone())?;
 
     Ok(digest)
 }
 
+/// Maximum time of waiting for another instance creating the same cache.
+const LOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Maximum time of waiting for another instance updating the cache metadata.
+const METADATA_LOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Acquires the lock of the cache metadata file, returns `None` if it's still held by
+/// another instance after [`METADATA_LOCK_WAIT_TIMEOUT`].
+fn lock_cache_metadata() -> Option<CacheLock> {
+    let path = crate::datastore::cache_metadata_path()?;
+    CacheLock::try_acquire(path).or_else(|| {
+        CacheLock::wait_released(path, METADATA_LOCK_WAIT_TIMEOUT);
+        CacheLock::try_acquire(path)
+    })
+}
+
+/// Returns the digest of `shell_cmd` stored by another instance since `since`.
+fn reload_fresh_digest(shell_cmd: &ShellCommand, since: UtcTime) -> Option<Digest> {
+    let on_disk = crate::datastore::load_cache_info()?;
+    let digest = on_disk
+        .find_digest(shell_cmd)
+        .map(|index| on_disk.digests[index].clone())
+        .filter(|digest| digest.execution_time >= since && digest.is_usable())?;
+
+    let mut cache_info = CACHE_INFO_IN_MEMORY.lock();
+    if let Err(err) = cache_info.limited_push(digest.clone()) {
+        tracing::error!(?err, "Failed to push the reloaded cache digest");
+    }
+
+    Some(digest)
+}
+
+/// Returns the existing digest of `shell_cmd` when its cache file is still locked by another
+/// instance, which must not be raced by writing the cache file without the lock.
+fn existing_digest(shell_cmd: &ShellCommand) -> std::io::Result<Digest> {
+    reload_fresh_digest(shell_cmd, UtcTime::MIN_UTC).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::WouldBlock,
+            format!(
+                "cache of `{}` is being created by another instance",
+                shell_cmd.command
+            ),
+        )
+    })
+}
+
+/// Creates the cache of `shell_cmd`, `write` writes the results to the given file.
+///
+/// The cache file is written atomically while holding its lock. If another instance is
+/// creating the same cache, waits for it to finish and reuses its fresh digest. If the lock
+/// is still held after the wait, the existing digest is returned instead.
+pub fn create_cache(
+    shell_cmd: ShellCommand,
+    write: impl FnOnce(&Path) -> std::io::Result<()>,
+) -> std::io::Result<Digest> {
+    let cache_file = shell_cmd.cache_file_path()?;
+
+    let _lock = match CacheLock::try_acquire(&cache_file) {
+        Some(lock) => lock,
+        None => {
+            let since = Utc::now();
+            CacheLock::wait_released(&cache_file, LOCK_WAIT_TIMEOUT);
+            if let Some(digest) = reload_fresh_digest(&shell_cmd, since) {
+                return Ok(digest);
+            }
+            match CacheLock::try_acquire(&cache_file) {
+                Some(lock) => lock,
+                None => return existing_digest(&shell_cmd),
+            }
+        }
+    };
+
+    write_atomically(&cache_file, write)?;
+
+    store_cache_digest(shell_cmd, cache_file)
+}
+
+/// Async version of [`create_cache`].
+pub async fn create_cache_async<F, Fut>(
+    shell_cmd: ShellCommand,
+    write: F,
+) -> std::io::Result<Digest>
+where
+    F: FnOnce(PathBuf) -> Fut,
+    Fut: Future<Output = std::io::Result<()>>,
+{
+    let cache_file = shell_cmd.cache_file_path()?;
+
+    let _lock = match CacheLock::try_acquire(&cache_file) {
+        Some(lock) => lock,
+        None => {
+            let since = Utc::now();
+            CacheLock::wait_released_async(&cache_file, LOCK_WAIT_TIMEOUT).await;
+            if let Some(digest) = reload_fresh_digest(&shell_cmd, since) {
+                return Ok(digest);
+            }
+            match CacheLock::try_acquire(&cache_file) {
+                Some(lock) => lock,
+                None => return existing_digest(&shell_cmd),
+            }
+        }
+    };
+
+    write_atomically_async(&cache_file, write).await?;
+
+    store_cache_digest(shell_cmd, cache_file)
+}
+
 /// For benchmarking purpose.
 pub fn find_largest_cache_digest() -> Option<Digest> {
     let cache_info = CACHE_INFO_IN_MEMORY.lock();
@@ -229,3 +542,85 @@ pub fn find_largest_cache_digest() -> Option<Digest> {
     digests.sort_unstable_by_key(|digest| digest.total);
     digests.last().cloned()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_corrupted_cache() {
+        let cached_path = std::env::temp_dir().join(format!(
+            "clap_test_detect_corrupted_cache_{}",
+            std::process::id()
+        ));
+        std::fs::write(&cached_path, "foo\nbar\nbaz\n").unwrap();
+
+        let checksum = CacheChecksum::from_file(&cached_path).unwrap();
+        assert_eq!(checksum, CacheChecksum::from_bytes(b"foo\nbar\nbaz\n"));
+
+        let shell_cmd = ShellCommand::new("rg".into(), std::env::temp_dir());
+        let digest = Digest::new(shell_cmd, 3, cached_path.clone(), checksum);
+        assert!(digest.is_usable());
+        assert!(!digest.is_corrupted(true));
+
+        // Same size, only the thorough check can tell.
+        std::fs::write(&cached_path, "foo\nbaz\nbar\n").unwrap();
+        assert!(!digest.is_corrupted(false));
+        assert!(digest.is_corrupted(true));
+
+        // Truncated.
+        std::fs::write(&cached_path, "foo\nbar\n").unwrap();
+        assert!(digest.is_corrupted(false));
+        assert!(!digest.is_usable());
+
+        // The digests created before the versioning are not usable.
+        let legacy = Digest {
+            version: 0,
+            checksum: None,
+            ..digest
+        };
+        assert!(!legacy.is_usable());
+
+        std::fs::remove_file(cached_path).unwrap();
+    }
+
+    #[test]
+    fn test_merge_cache_info() {
+        let digest = |cmd: &str| {
+            let cached_path = std::env::temp_dir().join(format!("test_merge_cache_info_{cmd}"));
+            std::fs::write(&cached_path, "foo\n").unwrap();
+            let checksum = CacheChecksum::from_file(&cached_path).unwrap();
+            let shell_cmd = ShellCommand::new(cmd.into(), std::env::temp_dir());
+            Digest::new(shell_cmd, 1, cached_path, checksum)
+        };
+
+        let mut in_memory = CacheInfo::new();
+        in_memory.digests.push(digest("rg"));
+        let stale_fd = digest("fd");
+        in_memory.digests.push(stale_fd.clone());
+
+        // Another instance has added `git ls-files` and refreshed `fd`.
+        let mut on_disk = CacheInfo::new();
+        on_disk.digests.push(digest("git ls-files"));
+        let fresh_fd = Digest {
+            execution_time: stale_fd.execution_time + chrono::Duration::seconds(1),
+            total: 2,
+            ..stale_fd
+        };
+        on_disk.digests.push(fresh_fd.clone());
+
+        in_memory.merge(on_disk);
+
+        let cmds = in_memory
+            .digests
+            .iter()
+            .map(|digest| digest.shell_cmd.command.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(cmds, ["rg", "fd", "git ls-files"]);
+        assert_eq!(in_memory.digests[1].total, fresh_fd.total);
+
+        for digest in in_memory.digests {
+            std::fs::remove_file(digest.cached_path).unwrap();
+        }
+    }
+}
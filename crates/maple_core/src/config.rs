@@ -1,61 +1,167 @@
-use dirs::Dirs;
+use dirs::{DirOverrides, Dirs};
+use dumb_analyzer::CommentSyntax;
+use maple_derive::ConfigSchema;
 use once_cell::sync::OnceCell;
+use parking_lot::RwLock;
 use paths::AbsPathBuf;
+use printer::Layout;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use types::RankCriterion;
 
 static CONFIG_FILE: OnceCell<PathBuf> = OnceCell::new();
-// TODO: reload-config
-static CONFIG: OnceCell<Config> = OnceCell::new();
-
-pub fn load_config_on_startup(
-    specified_config_file: Option<PathBuf>,
-) -> (&'static Config, Option<toml::de::Error>) {
-    let config_file = specified_config_file.unwrap_or_else(|| {
-        // Linux: ~/.config/vimclap/config.toml
-        // macOS: ~/Library/Application\ Support/org.vim.Vim-Clap/config.toml
-        // Windows: ~\AppData\Roaming\Vim\Vim Clap\config\config.toml
-        let config_file_path = Dirs::project().config_dir().join("config.toml");
-
-        if !config_file_path.exists() {
-            std::fs::create_dir_all(&config_file_path).ok();
+/// Current config, which is replaced as a whole on reloading.
+static CONFIG: RwLock<Option<Arc<Config>>> = parking_lot::const_rwlock(None);
+/// Bumped on replacing the config.
+static CONFIG_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// Config cached in each thread along with its generation, so that the lock is only
+    /// taken once the config has been replaced.
+    static CACHED_CONFIG: RefCell<Option<(usize, Arc<Config>)>> = RefCell::new(None);
+}
+
+/// Linux: ~/.config/vimclap/config.toml
+/// macOS: ~/Library/Application\ Support/org.vim.Vim-Clap/config.toml
+/// Windows: ~\AppData\Roaming\Vim\Vim Clap\config\config.toml
+fn default_config_file() -> PathBuf {
+    Dirs::project().config_dir().join("config.toml")
+}
+
+/// Config file read and parsed once on startup, before the thread pools and the
+/// directories are initialized.
+#[derive(Debug)]
+pub struct StartupConfig {
+    config_file: PathBuf,
+    config: Config,
+    maybe_toml_err: Option<toml::de::Error>,
+}
+
+impl StartupConfig {
+    /// Loads the config file, the defaults are used if the file is absent or invalid.
+    pub fn load(specified_config_file: Option<PathBuf>) -> Self {
+        let config_file = specified_config_file.unwrap_or_else(default_config_file);
+
+        let (config, maybe_toml_err) = match std::fs::read_to_string(&config_file) {
+            Ok(contents) => match toml::from_str(&contents) {
+                Ok(config) => (config, None),
+                Err(err) => (Config::default(), Some(err)),
+            },
+            Err(_) => (Config::default(), None),
+        };
+
+        Self {
+            config_file,
+            config,
+            maybe_toml_err,
         }
+    }
 
-        config_file_path
-    });
+    /// Thread pool config, which has to be known before starting the service.
+    pub fn thread_pool(&self) -> &ThreadPoolConfig {
+        &self.config.thread_pool
+    }
 
-    let mut maybe_config_err = None;
-    let loaded_config = std::fs::read_to_string(&config_file)
-        .and_then(|contents| {
-            toml::from_str(&contents).map_err(|err| {
-                maybe_config_err.replace(err);
-                std::io::Error::new(std::io::ErrorKind::Other, "Error occurred in config.toml")
-            })
-        })
-        .unwrap_or_default();
+    /// Initializes the cache, data and log directories specified in the config, before the
+    /// directories are accessed.
+    ///
+    /// The existing caches and data are moved to the specified directories on the first run.
+    pub fn initialize_dirs(&self) {
+        let datastore_config = &self.config.datastore;
+        let expand = |dir: &Option<String>| dir.clone().map(paths::expand_tilde);
 
-    CONFIG_FILE
-        .set(config_file)
-        .expect("Failed to initialize Config file");
+        Dirs::set_overrides(DirOverrides {
+            cache_dir: expand(&datastore_config.cache_dir),
+            data_dir: expand(&datastore_config.data_dir),
+            log_dir: expand(&datastore_config.log_dir),
+        });
+
+        // The old location is simply abandoned on failure, the caches will be rebuilt.
+        let _ = Dirs::migrate_to_overridden_dirs();
+    }
+
+    /// Initializes the global config, returns it along with the error in the config file
+    /// if any, which is reported once the service is started.
+    pub fn into_global(self) -> (Arc<Config>, Option<toml::de::Error>) {
+        if let Some(config_dir) = self.config_file.parent().filter(|dir| !dir.exists()) {
+            std::fs::create_dir_all(config_dir).ok();
+        }
+
+        CONFIG_FILE
+            .set(self.config_file)
+            .expect("Failed to initialize Config file");
+
+        (set_config(self.config), self.maybe_toml_err)
+    }
+}
+
+fn set_config(new_config: Config) -> Arc<Config> {
+    dumb_analyzer::set_user_comment_syntax(&new_config.comment_syntax);
+    let new_config = Arc::new(new_config);
+    let mut config = CONFIG.write();
+    config.replace(new_config.clone());
+    CONFIG_GENERATION.fetch_add(1, Ordering::Release);
+    new_config
+}
+
+/// Reloads the config from the config file, the current config is kept on error.
+///
+/// Note that some options only take effect on startup, e.g., `thread-pool` and `plugin`.
+pub fn reload_config() -> Result<Arc<Config>, toml::de::Error> {
+    let contents = std::fs::read_to_string(config_file()).unwrap_or_default();
+    let new_config = toml::from_str(&contents)?;
+    Ok(set_config(new_config))
+}
+
+/// Initializes the global config from the toml `contents` in tests, no-op if it has
+/// been initialized.
+#[cfg(any(test, feature = "testing"))]
+pub fn initialize_config_for_test(contents: &str) -> Arc<Config> {
+    let mut config = CONFIG.write();
+    let config = config
+        .get_or_insert_with(|| Arc::new(toml::from_str(contents).expect("Invalid test config")))
+        .clone();
+    CONFIG_GENERATION.fetch_add(1, Ordering::Release);
+    config
+}
 
-    CONFIG
-        .set(loaded_config)
-        .expect("Failed to initialize Config");
+/// Returns the generation of the current config, which is bumped on replacing the config.
+pub fn config_generation() -> usize {
+    CONFIG_GENERATION.load(Ordering::Acquire)
+}
 
-    (config(), maybe_config_err)
+pub fn config() -> Arc<Config> {
+    try_config().expect("Config must be initialized")
 }
 
-pub fn config() -> &'static Config {
-    CONFIG.get().expect("Config must be initialized")
+/// Returns the config if initialized, which is not the case in the CLI commands.
+pub fn try_config() -> Option<Arc<Config>> {
+    let generation = CONFIG_GENERATION.load(Ordering::Acquire);
+    CACHED_CONFIG.with(|cached_config| {
+        let mut cached_config = cached_config.borrow_mut();
+        match cached_config.as_ref() {
+            Some((cached_generation, config)) if *cached_generation == generation => {
+                Some(config.clone())
+            }
+            _ => {
+                let config = CONFIG.read().clone()?;
+                cached_config.replace((generation, config.clone()));
+                Some(config)
+            }
+        }
+    })
 }
 
 pub fn config_file() -> &'static PathBuf {
     CONFIG_FILE.get().expect("Config file uninitialized")
 }
 
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, ConfigSchema)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct MatcherConfig {
     pub tiebreak: String,
@@ -78,7 +184,7 @@ impl MatcherConfig {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, ConfigSchema)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct LogConfig {
     pub log_file: Option<String>,
@@ -96,7 +202,29 @@ impl Default for LogConfig {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct DatastoreConfig {
+    /// Directory for the caches.
+    ///
+    /// Defaults to `$XDG_CACHE_HOME/vimclap` on Linux, the environment variable
+    /// `VIM_CLAP_CACHE_DIR` takes precedence.
+    pub cache_dir: Option<String>,
+
+    /// Directory for the persistent data, e.g., recent files and the cache metadata.
+    ///
+    /// Defaults to `$XDG_DATA_HOME/vimclap` on Linux, the environment variable
+    /// `VIM_CLAP_DATA_DIR` takes precedence.
+    pub data_dir: Option<String>,
+
+    /// Directory for the log file when `log.log-file` is a relative path.
+    ///
+    /// Defaults to `$XDG_STATE_HOME/vimclap` on Linux, the environment variable
+    /// `VIM_CLAP_LOG_DIR` takes precedence.
+    pub log_dir: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, ConfigSchema)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct CursorWordConfig {
     /// Whether to enable this plugin.
@@ -117,21 +245,21 @@ impl Default for CursorWordConfig {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct MarkdownPluginConfig {
     /// Whether to enable this plugin.
     pub enable: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct CtagsPluginConfig {
     /// Whether to enable this plugin.
     pub enable: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, ConfigSchema)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct GitPluginConfig {
     /// Whether to enable this plugin.
@@ -152,21 +280,21 @@ impl Default for GitPluginConfig {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct ColorizerPluginConfig {
     /// Whether to enable this plugin.
     pub enable: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct LinterPluginConfig {
     /// Whether to enable this plugin.
     pub enable: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct PluginConfig {
     pub colorizer: ColorizerPluginConfig,
@@ -177,7 +305,7 @@ pub struct PluginConfig {
     pub markdown: MarkdownPluginConfig,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct IgnoreConfig {
     /// Whether to ignore the comment line when it's possible.
@@ -190,7 +318,49 @@ pub struct IgnoreConfig {
     pub ignore_file_path_pattern: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq, ConfigSchema)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct ToolCommandConfig {
+    /// Path of the executable used instead of the default one, e.g., a wrapper script.
+    pub path: Option<String>,
+    /// Extra arguments passed before the default arguments.
+    pub args: Vec<String>,
+}
+
+impl ToolCommandConfig {
+    /// Returns the program to execute, `default` if not overridden.
+    pub fn program<'a>(&'a self, default: &'a str) -> &'a str {
+        self.path.as_deref().unwrap_or(default)
+    }
+
+    /// Returns the shell command string of `default_cmd` with the program and the
+    /// extra arguments overridden.
+    pub fn shell_cmd(&self, default_cmd: &str) -> String {
+        let (default_program, default_args) =
+            default_cmd.split_once(' ').unwrap_or((default_cmd, ""));
+        std::iter::once(self.program(default_program))
+            .chain(self.args.iter().map(String::as_str))
+            .chain((!default_args.is_empty()).then_some(default_args))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct ProjectToolsConfig {
+    /// Command of ripgrep used by the grep providers and the grep cache.
+    pub rg: ToolCommandConfig,
+    /// Command of ctags used for the tags of the project and the buffers.
+    pub ctags: ToolCommandConfig,
+    /// Git directory of the project when it's not `.git` in the project root, e.g.,
+    /// the bare repo managing the dotfiles in the home directory.
+    ///
+    /// Used by the git providers, the git plugin and the previews.
+    pub git_dir: Option<AbsPathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct ProviderConfig {
     /// Delay in milliseconds before the user query will be handled actually.
@@ -209,6 +379,27 @@ pub struct ProviderConfig {
     /// ```
     pub debounce: HashMap<String, u64>,
 
+    /// Default arguments per provider, the arguments specified in `:Clap` take precedence.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider.default-args]
+    /// grep = ["--hidden", "--type=rust"]
+    /// ```
+    pub default_args: HashMap<String, Vec<String>>,
+
+    /// Providers without the preview by default, which can be useful on the slow
+    /// filesystems. The preview can still be toggled with `<A-p>` at runtime.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider]
+    /// disable-preview = ["grep", "files"]
+    /// ```
+    pub disable_preview: Vec<String>,
+
     /// Ignore configuration per provider.
     ///
     /// Priorities of the ignore config:
@@ -221,6 +412,19 @@ pub struct ProviderConfig {
     /// Specify the syntax highlight engine for the provider preview.
     pub preview_highlight_engine: HighlightEngine,
 
+    /// Specify the syntax highlight engine per filetype, overriding `preview-highlight-engine`.
+    ///
+    /// The key can be either the filetype or the file extension.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider.highlight-engine-overrides]
+    /// vue = "sublime-syntax"
+    /// rust = "tree-sitter"
+    /// ```
+    pub highlight_engine_overrides: HashMap<String, HighlightEngine>,
+
     /// Specify the theme for the highlight engine.
     ///
     /// If the theme is not found and the engine is [`HighlightEngine::SublimeSyntax`],
@@ -229,23 +433,626 @@ pub struct ProviderConfig {
 
     /// Whether to share the input history of each provider.
     pub share_input_history: bool,
+
+    /// Providers restoring the last query and cursor position when reopened in the same project.
+    ///
+    /// The query specified by `--query` takes precedence.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider]
+    /// sticky-query = ["grep", "files"]
+    /// ```
+    pub sticky_query: Vec<String>,
+
+    /// Whether to show the top match as the ghost text next to the input.
+    ///
+    /// Currently only for the providers `files` and `grep`, which is handy when the
+    /// preview window is disabled.
+    pub top_match_ghost_text: bool,
+
+    /// Display widths below which the items are formatted more compactly instead of
+    /// being truncated blindly.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider.responsive-layout]
+    /// # Hide the icons and the secondary columns, e.g., the file size of the buffers,
+    /// # if the display is narrower than 60 columns.
+    /// compact-width = 60
+    /// # Also shorten the file paths if the display is narrower than 40 columns.
+    /// narrow-width = 40
+    /// ```
+    pub responsive_layout: ResponsiveLayoutConfig,
+
+    /// Providers displaying the file paths as the shortest suffixes which tell them from
+    /// the other paths in the results, e.g., `foo/lib.rs` and `bar/lib.rs`.
+    ///
+    /// Currently only for the providers `files`, `recent_files` and `buffers`.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider]
+    /// unique-path-suffix = ["files", "recent_files"]
+    /// ```
+    pub unique_path_suffix: Vec<String>,
+
+    /// External tool to pretty print the diff in the previews of `commits` and
+    /// `bcommits`, the plain `git show` is used if the tool is unavailable or fails.
+    ///
+    /// Either `delta`, `difftastic` or a custom command reading the output of `git show`
+    /// from stdin, the ANSI colors in the output are kept.
+    ///
+    /// Only the commit previews are piped through it, the previews of `git_hunks` show
+    /// the files in the work tree instead of the diff.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider]
+    /// diff-pager = "delta"
+    /// # Or a custom command, `git-diff-tool` is a deprecated alias.
+    /// # diff-pager = ["delta", "--color=never"]
+    /// ```
+    #[serde(alias = "git-diff-tool")]
+    pub diff_pager: Option<DiffPager>,
+
+    /// Whether to align the columns of the CSV/TSV files in the preview.
+    ///
+    /// The column widths are computed over the previewed rows only, the header row is
+    /// pinned at the top when previewing the rows further down.
+    pub align_csv_preview: bool,
+
+    /// Whether to ignore the language servers attached in Vim, i.e., the `symbols` provider
+    /// only lists the symbols from ctags and tree-sitter and `lsp_symbols` lists nothing.
+    pub disable_lsp: bool,
+
+    /// Whether to render the markdown files in the preview, i.e., the headings, lists,
+    /// code blocks and links are displayed in a prettified plain-text layout instead of
+    /// the raw markup.
+    ///
+    /// The highlight groups `ClapMarkdownHeading`, `ClapMarkdownListMarker`,
+    /// `ClapMarkdownCode`, `ClapMarkdownLink` and `ClapMarkdownQuote` are used.
+    pub render_markdown: bool,
+
+    /// Whether to show the last commit of the file, i.e., the hash, author and date, in
+    /// the footer of the file preview.
+    ///
+    /// The commit is looked up in the background, the footer is added once it's ready.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider]
+    /// git-footer = true
+    /// ```
+    pub git_footer: bool,
+
+    /// How to display the preview lines wider than the preview window.
+    ///
+    /// The lines are always cut at twice the width of the display window, as the awfully
+    /// long lines can freeze Vim.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider]
+    /// # Soft-wrap the long lines across multiple rows.
+    /// long-preview-lines = "wrap"
+    /// ```
+    pub long_preview_lines: LongPreviewLines,
+
+    /// Where to place the target line in the file preview per provider, the target line
+    /// is vertically centered by default.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider.preview-position]
+    /// # Show the target line at the top of the preview window.
+    /// dumb_jump = "top"
+    /// # Keep 3 lines above the target line.
+    /// grep = { offset = 3 }
+    /// ```
+    pub preview_position: HashMap<String, PreviewPosition>,
+
+    /// External commands extracting the text of the documents per file extension for
+    /// the preview, `{}` in the arguments is replaced with the file path.
+    ///
+    /// The PDF files are extracted by `pdftotext` and the docx files by the built-in
+    /// extractor by default.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider.document-extractors]
+    /// pdf = ["mutool", "draw", "-F", "txt", "{}"]
+    /// odt = ["odt2txt", "{}"]
+    /// ```
+    pub document_extractors: HashMap<String, Vec<String>>,
+
+    /// Extensions of the files showing the context lines in the preview, i.e., the
+    /// context tag or the key path of the config files.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider.context-tag]
+    /// # Never show the context lines for these extensions, `ignore-extensions` is an alias.
+    /// blacklist = ["log", "txt"]
+    /// # Give up the context lines if ctags takes longer than 500ms.
+    /// timeout-ms = 500
+    /// # Only show the context lines for these extensions, takes precedence over the blacklist.
+    /// whitelist = ["rs", "py", "go"]
+    /// ```
+    pub context_tag: ContextTagConfig,
+
+    /// Maximum number of the nested scopes displayed as the context lines above the
+    /// preview, e.g., the module, impl and function enclosing the line, 1 by default,
+    /// which shows the innermost function/method only.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider]
+    /// preview-context-depth = 3
+    /// ```
+    pub preview_context_depth: Option<usize>,
+
+    /// Number of the items above and below the cursor line whose previews are
+    /// prefetched in the background, 0 (disabled) by default.
+    ///
+    /// The prefetching starts once the cursor stays still for a while and is cancelled
+    /// on moving the cursor or changing the query.
+    pub preview_prefetch: usize,
+
+    /// Worker pool generating the previews, which is separate from the filtering.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider.preview-pool]
+    /// # Generate the previews on 4 worker threads.
+    /// workers = 4
+    /// # Display a placeholder if the preview is not ready in 500ms.
+    /// timeout = 500
+    /// ```
+    pub preview_pool: PreviewPoolConfig,
+
+    /// In-memory cache of the generated previews.
+    ///
+    /// The cached preview of a file is dropped once the file is modified.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider.preview-cache]
+    /// # Keep at most 128 previews, the least recently used one is evicted first.
+    /// max-entries = 128
+    /// # Regenerate the previews cached over 60 seconds ago.
+    /// ttl = 60
+    /// ```
+    pub preview_cache: PreviewCacheConfig,
+
+    /// Command whose stdout is previewed for the items of the providers registered with
+    /// `preview = {'command': ...}`.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider.preview-command]
+    /// # Kill the preview command if it's still running after 300ms.
+    /// timeout = 300
+    /// # Read at most 16KiB of the output.
+    /// max-output = 16384
+    /// ```
+    pub preview_command: PreviewCommandConfig,
+
+    /// Preview of the items which are http(s) URLs, e.g., in the bookmarks.
+    ///
+    /// Disabled by default as the resources are fetched on moving the cursor. Only the
+    /// items of the providers listed in `providers` and the registered providers whose
+    /// `preview` is `'url'` are previewed as URLs.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider.url-preview]
+    /// enable = true
+    /// providers = ["bookmarks"]
+    /// # Give up if the resource is not fetched in 2 seconds.
+    /// timeout = 2000
+    /// # Fetch at most the first 32KiB of the resource.
+    /// max-bytes = 32768
+    /// ```
+    pub url_preview: UrlPreviewConfig,
+
+    /// Preview of the directories, e.g., in the `files` and `filer` providers.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [provider.directory-preview]
+    /// # Show the nested entries as a tree instead of the flat listing, the files
+    /// # ignored by `.gitignore` are excluded.
+    /// tree = true
+    /// # Descend at most 2 levels.
+    /// max-depth = 2
+    /// ```
+    pub directory_preview: DirectoryPreviewConfig,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub enum LongPreviewLines {
+    /// Cut the long lines with `……`, leaving the rest to the nowrap preview window.
+    #[default]
+    Truncate,
+    /// Cut the long lines with the marker `…>` to tell the truncation apart.
+    Marker,
+    /// Soft-wrap the long lines at the width of the preview window.
+    Wrap,
+}
+
+/// Position of the target line in the file preview.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub enum PreviewPosition {
+    /// Vertically center the target line.
+    #[default]
+    Center,
+    /// Show the target line right below the header line.
+    Top,
+    /// Keep the given number of lines above the target line.
+    Offset(usize),
+}
+
+impl PreviewPosition {
+    /// Returns the 1-based row of the target line among the `winheight` preview lines
+    /// following the header line, if the file has enough lines above the target.
+    pub fn anchor(self, winheight: usize) -> usize {
+        match self {
+            Self::Center => winheight / 2,
+            Self::Top => 1,
+            Self::Offset(offset) => (offset + 1).min(winheight.max(1)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq, ConfigSchema)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct ContextTagConfig {
+    /// Extensions without the context lines, `["log", "txt", "lock", "mod", "conf"]`
+    /// by default.
+    #[serde(alias = "ignore-extensions")]
+    pub blacklist: Option<Vec<String>>,
+
+    /// Extensions with the context lines, all the extensions not in the blacklist are
+    /// allowed if empty.
+    pub whitelist: Vec<String>,
+
+    /// Milliseconds to wait for ctags before giving up the context lines, 300 by default.
+    pub timeout_ms: Option<u64>,
+}
+
+impl ContextTagConfig {
+    const DEFAULT_BLACKLIST: &'static [&'static str] = &["log", "txt", "lock", "mod", "conf"];
+
+    /// Returns `true` if the context lines are enabled for the file of `extension`.
+    pub fn is_enabled(&self, extension: &str) -> bool {
+        if !self.whitelist.is_empty() {
+            return self.whitelist.iter().any(|ext| ext == extension);
+        }
+        match &self.blacklist {
+            Some(blacklist) => !blacklist.iter().any(|ext| ext == extension),
+            None => !Self::DEFAULT_BLACKLIST.contains(&extension),
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.unwrap_or(300))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct PreviewCacheConfig {
+    /// Maximum number of the cached previews, 256 by default.
+    pub max_entries: Option<usize>,
+
+    /// Seconds for which a cached preview stays valid, 600 by default.
+    pub ttl: Option<u64>,
+}
+
+impl PreviewCacheConfig {
+    pub fn max_entries(&self) -> usize {
+        self.max_entries.unwrap_or(256).max(1)
+    }
+
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl.unwrap_or(600))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct PreviewCommandConfig {
+    /// Milliseconds after which the preview command is killed, 500 by default.
+    pub timeout: Option<u64>,
+
+    /// Maximum bytes of the output read from the preview command, 64KiB by default.
+    pub max_output: Option<usize>,
+}
+
+impl PreviewCommandConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout.unwrap_or(500))
+    }
+
+    pub fn max_output(&self) -> usize {
+        self.max_output.unwrap_or(64 * 1024).max(1)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct UrlPreviewConfig {
+    /// Whether to fetch the URLs for the preview.
+    pub enable: bool,
+
+    /// Providers whose URL items are previewed as URLs.
+    pub providers: Vec<String>,
+
+    /// Milliseconds after which the request is abandoned, 3000 by default.
+    pub timeout: Option<u64>,
+
+    /// Maximum bytes fetched from the beginning of the resource, 64KiB by default.
+    pub max_bytes: Option<usize>,
+}
+
+impl UrlPreviewConfig {
+    /// Returns `true` if the URL items of `provider_id` are previewed as URLs.
+    pub fn is_enabled_for(&self, provider_id: &str) -> bool {
+        self.providers.iter().any(|id| id == provider_id)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout.unwrap_or(3000))
+    }
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes.unwrap_or(64 * 1024).max(1)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct DirectoryPreviewConfig {
+    /// Whether to show the directory as a tree recursively.
+    pub tree: bool,
+
+    /// Maximum depth of the tree, 3 by default.
+    pub max_depth: Option<usize>,
+}
+
+impl DirectoryPreviewConfig {
+    pub fn max_depth(&self) -> usize {
+        self.max_depth.unwrap_or(3).max(1)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct PreviewPoolConfig {
+    /// Number of the worker threads, 2 by default.
+    pub workers: Option<usize>,
+
+    /// Milliseconds to wait for a preview before displaying the timed out placeholder,
+    /// 1000 by default. The preview is still displayed once it's ready.
+    pub timeout: Option<u64>,
+}
+
+impl PreviewPoolConfig {
+    pub fn workers(&self) -> usize {
+        self.workers.unwrap_or(2).max(1)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout.unwrap_or(1000))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct ResponsiveLayoutConfig {
+    /// Display width below which the icons and the secondary columns are hidden, 50 by
+    /// default.
+    pub compact_width: Option<usize>,
+
+    /// Display width below which the file paths are also shortened, 35 by default.
+    pub narrow_width: Option<usize>,
+}
+
+impl ResponsiveLayoutConfig {
+    /// Providers whose items start with a file path.
+    const PATH_PROVIDERS: &'static [&'static str] =
+        &["files", "git_files", "recent_files", "grep", "live_grep"];
+
+    /// Returns the layout of provider `provider_id` for the display of `line_width`.
+    pub fn layout(&self, provider_id: &str, line_width: usize) -> Layout {
+        let layout = Layout::negotiate(
+            line_width,
+            self.compact_width.unwrap_or(50),
+            self.narrow_width.unwrap_or(35),
+        );
+        if layout.shorten_paths() && !Self::PATH_PROVIDERS.contains(&provider_id) {
+            Layout::Compact
+        } else {
+            layout
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, Eq, PartialEq)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub enum HighlightEngine {
+    #[serde(alias = "sublime")]
     SublimeSyntax,
     TreeSitter,
     #[default]
     Vim,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub enum DiffPager {
+    /// <https://github.com/dandavison/delta>
+    Delta,
+    /// <https://github.com/Wilfred/difftastic>
+    #[serde(alias = "difft")]
+    Difftastic,
+    /// Custom command reading the output of `git show` from stdin.
+    #[serde(untagged)]
+    Command(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct WatcherConfig {
+    /// Whether to watch the project directory for changes and invalidate the outdated caches.
+    ///
+    /// The previews of the changed files are purged and the cached source of the active
+    /// provider is refreshed as well.
+    pub enable: bool,
+
+    /// Specify the backend used for watching the file system.
+    pub backend: WatcherBackend,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub enum WatcherBackend {
+    /// Use the native watcher of the OS (inotify, FSEvents, etc).
+    ///
+    /// Falls back to watchman if installed when the OS watch limit is reached.
+    #[default]
+    Native,
+    /// Use [watchman](https://facebook.github.io/watchman/), recommended for very large repositories.
+    ///
+    /// Falls back to the native watcher if watchman is unavailable.
+    Watchman,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq, ConfigSchema)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct IdleSchedulerConfig {
+    /// Whether to run the maintenance tasks in the background when the user is idle.
+    pub enable: bool,
+
+    /// Seconds without any interaction before the maintenance tasks start, 60 by default.
+    ///
+    /// The running task is paused as soon as the user interacts again.
+    pub idle_seconds: Option<u64>,
+}
+
+impl IdleSchedulerConfig {
+    pub fn idle_seconds(&self) -> u64 {
+        self.idle_seconds.unwrap_or(60).max(1)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq, ConfigSchema)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct ProcessLimitsConfig {
+    /// Maximum CPU time in seconds of the spawned tools, e.g., rg, ctags and git.
+    ///
+    /// Only supported on Unix.
+    pub max_cpu_time: Option<u64>,
+
+    /// Maximum virtual memory in MiB of the spawned tools.
+    ///
+    /// Note that some tools like rg reserve much more virtual memory than they use
+    /// by mmap, don't set it too low. Only supported on Unix.
+    pub max_memory: Option<u64>,
+
+    /// Maximum wall-clock time in seconds of the commands creating the provider
+    /// source, the command is killed along with its children on timeout.
+    pub timeout: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Eq, PartialEq, ConfigSchema)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct ThreadPoolConfig {
+    /// Number of threads for the CPU-intensive interactive work, e.g., filtering.
+    ///
+    /// Defaults to the number of physical cores. The command line option
+    /// `--rayon-num-threads` and the environment variable `RAYON_NUM_THREADS`
+    /// take precedence.
+    pub filter_threads: Option<usize>,
+
+    /// Number of the async worker threads handling the messages from Vim.
+    ///
+    /// Defaults to the number of logical cores.
+    pub async_threads: Option<usize>,
+
+    /// Number of threads for the background jobs, e.g., refreshing the caches.
+    ///
+    /// Defaults to half of the physical cores.
+    pub background_threads: Option<usize>,
+
+    /// Niceness of the background threads, the higher the lower priority so that
+    /// the background jobs won't slow down the interactive filtering.
+    ///
+    /// Only supported on Linux. Defaults to 10.
+    pub background_niceness: Option<i32>,
+}
+
+impl ThreadPoolConfig {
+    pub fn filter_threads(&self) -> usize {
+        self.filter_threads
+            .unwrap_or_else(num_cpus::get_physical)
+            .max(1)
+    }
+
+    pub fn async_threads(&self) -> usize {
+        self.async_threads.unwrap_or_else(num_cpus::get).max(1)
+    }
+
+    pub fn background_threads(&self) -> usize {
+        self.background_threads
+            .unwrap_or_else(|| num_cpus::get_physical() / 2)
+            .max(1)
+    }
+
+    pub fn background_niceness(&self) -> i32 {
+        self.background_niceness.unwrap_or(10)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, ConfigSchema)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct Config {
     /// Log configuration.
+    ///
+    /// The log file is placed in `datastore.log-dir` if specified as a relative path.
     pub log: LogConfig,
 
+    /// Locations of the caches, data and logs.
+    ///
+    /// The existing caches and data are moved to the new location on the first run.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [datastore]
+    /// cache-dir = "~/.vim/cache/vimclap"
+    /// log-dir = "/tmp/vimclap"
+    /// ```
+    pub datastore: DatastoreConfig,
+
     /// Matcher configuration.
     pub matcher: MatcherConfig,
 
@@ -262,9 +1069,135 @@ pub struct Config {
     ///
     /// The project path must be specified as absolute path or a path relative to the home directory.
     pub project_ignore: HashMap<AbsPathBuf, IgnoreConfig>,
+
+    /// Tool overrides per project, applied to the files in the project directory.
+    ///
+    /// The project path must be specified as absolute path or a path relative to the home directory.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [project-tools."~/src/github.com/vim/vim"]
+    /// rg = { path = "/usr/local/bin/rg-wrapper", args = ["--hidden"] }
+    /// ctags = { path = "/opt/ctags/bin/ctags" }
+    ///
+    /// [project-tools."~"]
+    /// git-dir = "~/.dotfiles"
+    /// ```
+    pub project_tools: HashMap<AbsPathBuf, ProjectToolsConfig>,
+
+    /// Comment syntax per file extension, extending the builtin syntax used to detect the
+    /// comment lines, e.g., to skip the context tag in the preview or the comment hits of
+    /// dumb_jump.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [comment-syntax.py]
+    /// block = [['"""', '"""']]
+    ///
+    /// [comment-syntax.nix]
+    /// line = ["#"]
+    /// block = [["/*", "*/"]]
+    /// ```
+    pub comment_syntax: HashMap<String, CommentSyntax>,
+
+    /// File watcher configuration.
+    pub watcher: WatcherConfig,
+
+    /// Scheduler running the maintenance tasks when the user is idle, e.g., refreshing
+    /// the grep and tags caches and removing the outdated caches.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [idle-scheduler]
+    /// enable = true
+    /// idle-seconds = 120
+    /// ```
+    pub idle_scheduler: IdleSchedulerConfig,
+
+    /// Resource limits of the spawned processes.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [process-limits]
+    /// max-cpu-time = 60
+    /// max-memory = 4096
+    /// timeout = 120
+    /// ```
+    pub process_limits: ProcessLimitsConfig,
+
+    /// Thread pool configuration.
+    ///
+    /// # Config example
+    ///
+    /// ```toml
+    /// [thread-pool]
+    /// filter-threads = 4
+    /// background-threads = 1
+    /// ```
+    pub thread_pool: ThreadPoolConfig,
+}
+
+impl ProviderConfig {
+    /// Returns `true` if the preview of `provider_id` is disabled by default.
+    pub fn preview_disabled(&self, provider_id: &str) -> bool {
+        self.disable_preview.iter().any(|id| id == provider_id)
+    }
+
+    pub fn preview_context_depth(&self) -> usize {
+        self.preview_context_depth.unwrap_or(1).max(1)
+    }
+
+    /// Returns the preview highlight engine for the file of given `filetype` and `extension`.
+    pub fn highlight_engine_for(
+        &self,
+        filetype: Option<&str>,
+        extension: Option<&str>,
+    ) -> HighlightEngine {
+        filetype
+            .into_iter()
+            .chain(extension)
+            .find_map(|key| self.highlight_engine_overrides.get(key))
+            .copied()
+            .unwrap_or(self.preview_highlight_engine)
+    }
+
+    /// Returns the position of the target line in the file preview of `provider_id` if
+    /// it's configured.
+    pub fn preview_position_of(&self, provider_id: &str) -> Option<PreviewPosition> {
+        self.preview_position.get(provider_id).copied()
+    }
 }
 
 impl Config {
+    /// Returns the dotted keys of all the config options, e.g., `provider.debounce`.
+    pub fn keys() -> Vec<String> {
+        fn collect_keys(prefix: &str, value: &serde_json::Value, keys: &mut Vec<String>) {
+            match value {
+                serde_json::Value::Object(map) if !map.is_empty() => {
+                    for (key, value) in map {
+                        let key = if prefix.is_empty() {
+                            key.clone()
+                        } else {
+                            format!("{prefix}.{key}")
+                        };
+                        collect_keys(&key, value, keys);
+                    }
+                }
+                _ => keys.push(prefix.to_string()),
+            }
+        }
+
+        let mut keys = Vec::new();
+        if let Ok(value) = serde_json::to_value(Self::default()) {
+            collect_keys("", &value, &mut keys);
+        }
+        keys
+    }
+
     pub fn ignore_config(&self, provider_id: &str, project_dir: &AbsPathBuf) -> &IgnoreConfig {
         self.provider.ignore.get(provider_id).unwrap_or_else(|| {
             self.project_ignore
@@ -273,6 +1206,29 @@ impl Config {
         })
     }
 
+    /// Returns the innermost project containing `path` and its tool overrides.
+    pub fn project_tools(&self, path: &Path) -> Option<(&AbsPathBuf, &ProjectToolsConfig)> {
+        self.project_tools
+            .iter()
+            .filter(|(project_dir, _)| path.starts_with(project_dir.as_path()))
+            .max_by_key(|(project_dir, _)| project_dir.components().count())
+    }
+
+    /// Returns the pair of (git_dir, work_tree) of the innermost project containing `path`
+    /// whose `git-dir` is specified.
+    pub fn project_git_dir(&self, path: &Path) -> Option<(PathBuf, PathBuf)> {
+        self.project_tools
+            .iter()
+            .filter(|(project_dir, _)| path.starts_with(project_dir.as_path()))
+            .filter_map(|(project_dir, tools)| {
+                tools
+                    .git_dir
+                    .as_ref()
+                    .map(|git_dir| (git_dir.to_path_buf(), project_dir.to_path_buf()))
+            })
+            .max_by_key(|(_, work_tree)| work_tree.components().count())
+    }
+
     pub fn provider_debounce(&self, provider_id: &str) -> u64 {
         const DEFAULT_DEBOUNCE: u64 = 200;
 
@@ -294,6 +1250,208 @@ impl Config {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_load_startup_config() {
+        let config_file =
+            std::env::temp_dir().join(format!("clap_thread_pool_{}.toml", std::process::id()));
+        std::fs::write(
+            &config_file,
+            "[thread-pool]\nbackground-threads = 0\nbackground-niceness = 5\n",
+        )
+        .unwrap();
+
+        let startup_config = StartupConfig::load(Some(config_file.clone()));
+        assert!(startup_config.maybe_toml_err.is_none());
+        let thread_pool_config = startup_config.thread_pool();
+        // At least one thread is used.
+        assert_eq!(thread_pool_config.background_threads(), 1);
+        assert_eq!(thread_pool_config.background_niceness(), 5);
+        assert!(thread_pool_config.filter_threads() >= 1);
+
+        // The invalid config is reported on startup, the defaults are used meanwhile.
+        std::fs::write(&config_file, "[thread-pool]\nunknown-threads = 1\n").unwrap();
+        let startup_config = StartupConfig::load(Some(config_file.clone()));
+        assert!(startup_config.maybe_toml_err.is_some());
+        assert_eq!(startup_config.thread_pool(), &ThreadPoolConfig::default());
+        assert_eq!(ThreadPoolConfig::default().background_niceness(), 10);
+
+        std::fs::remove_file(&config_file).unwrap();
+        let startup_config = StartupConfig::load(Some(config_file));
+        assert!(startup_config.maybe_toml_err.is_none());
+        assert_eq!(startup_config.thread_pool(), &ThreadPoolConfig::default());
+    }
+
+    #[test]
+    fn test_context_tag_extensions() {
+        let context_tag = |toml_content: &str| -> ContextTagConfig {
+            toml::from_str(toml_content).expect("Invalid context-tag config")
+        };
+
+        let default = ContextTagConfig::default();
+        assert!(default.is_enabled("toml"));
+        assert!(!default.is_enabled("log"));
+
+        // The blacklist replaces the default one.
+        let blacklist = context_tag(r#"blacklist = ["yaml"]"#);
+        assert!(blacklist.is_enabled("log"));
+        assert!(!blacklist.is_enabled("yaml"));
+        assert!(!context_tag(r#"ignore-extensions = ["yaml"]"#).is_enabled("yaml"));
+        assert!(context_tag("blacklist = []").is_enabled("log"));
+
+        // The whitelist takes precedence over the blacklist.
+        let whitelist = context_tag("whitelist = [\"rs\", \"yaml\"]\nblacklist = [\"yaml\"]");
+        assert!(whitelist.is_enabled("rs"));
+        assert!(whitelist.is_enabled("yaml"));
+        assert!(!whitelist.is_enabled("py"));
+    }
+
+    #[test]
+    fn test_context_tag_timeout() {
+        assert_eq!(
+            ContextTagConfig::default().timeout(),
+            Duration::from_millis(300)
+        );
+
+        let config: Config = toml::from_str(
+            r#"
+          [provider.context-tag]
+          timeout-ms = 50
+"#,
+        )
+        .expect("Invalid context-tag config");
+        assert_eq!(
+            config.provider.context_tag.timeout(),
+            Duration::from_millis(50)
+        );
+    }
+
+    #[test]
+    fn test_project_tools() {
+        let config: Config = toml::from_str(
+            r#"
+          [project-tools."/home/user"]
+          git-dir = "/home/user/.dotfiles"
+
+          [project-tools."/home/user/src/vim"]
+          rg = { path = "/usr/local/bin/rg-wrapper", args = ["--hidden"] }
+"#,
+        )
+        .expect("Invalid project-tools config");
+        let (project_dir, tools) = config
+            .project_tools(Path::new("/home/user/src/vim/src/main.c"))
+            .expect("Tools of the innermost project");
+        assert_eq!(project_dir.as_path(), Path::new("/home/user/src/vim"));
+        assert_eq!(
+            tools.rg.shell_cmd("rg --column ."),
+            "/usr/local/bin/rg-wrapper --hidden --column ."
+        );
+        assert_eq!(tools.ctags.shell_cmd("ctags -R"), "ctags -R");
+        assert!(tools.git_dir.is_none());
+        assert_eq!(
+            config.project_git_dir(Path::new("/home/user/src/vim/src/main.c")),
+            Some((
+                PathBuf::from("/home/user/.dotfiles"),
+                PathBuf::from("/home/user")
+            ))
+        );
+        assert!(config.project_tools(Path::new("/tmp")).is_none());
+    }
+
+    #[test]
+    fn test_tool_command() {
+        let tool_command = |toml_content: &str| -> ToolCommandConfig {
+            toml::from_str(toml_content).expect("Invalid tool command config")
+        };
+
+        let default = ToolCommandConfig::default();
+        assert_eq!(default.program("rg"), "rg");
+        assert_eq!(default.shell_cmd("rg --column ."), "rg --column .");
+
+        let overridden = tool_command(r#"path = "/opt/ctags/bin/ctags""#);
+        assert_eq!(overridden.program("ctags"), "/opt/ctags/bin/ctags");
+        assert_eq!(overridden.shell_cmd("ctags"), "/opt/ctags/bin/ctags");
+
+        let with_args = tool_command(r#"args = ["--hidden", "--no-ignore-vcs"]"#);
+        assert_eq!(
+            with_args.shell_cmd("rg --column ."),
+            "rg --hidden --no-ignore-vcs --column ."
+        );
+    }
+
+    #[test]
+    fn test_preview_disabled() {
+        let provider_config: ProviderConfig =
+            toml::from_str(r#"disable-preview = ["grep", "files"]"#)
+                .expect("Failed to deserialize provider config");
+        assert!(provider_config.preview_disabled("grep"));
+        assert!(provider_config.preview_disabled("files"));
+        assert!(!provider_config.preview_disabled("blines"));
+        assert!(!ProviderConfig::default().preview_disabled("grep"));
+    }
+
+    #[test]
+    fn test_highlight_engine_for() {
+        let provider_config: ProviderConfig = toml::from_str(
+            r#"
+            preview-highlight-engine = "tree-sitter"
+
+            [highlight-engine-overrides]
+            markdown = "vim"
+            md = "sublime"
+            vue = "sublime"
+            "#,
+        )
+        .expect("Failed to deserialize provider config");
+
+        // The filetype takes precedence over the extension.
+        assert_eq!(
+            provider_config.highlight_engine_for(Some("markdown"), Some("md")),
+            HighlightEngine::Vim
+        );
+        assert_eq!(
+            provider_config.highlight_engine_for(Some("text"), Some("md")),
+            HighlightEngine::SublimeSyntax
+        );
+        assert_eq!(
+            provider_config.highlight_engine_for(None, Some("vue")),
+            HighlightEngine::SublimeSyntax
+        );
+        // Falls back to the default engine.
+        assert_eq!(
+            provider_config.highlight_engine_for(Some("rust"), Some("rs")),
+            HighlightEngine::TreeSitter
+        );
+        assert_eq!(
+            provider_config.highlight_engine_for(None, None),
+            HighlightEngine::TreeSitter
+        );
+    }
+
+    #[test]
+    fn test_diff_pager() {
+        let provider_config = |toml_content: &str| -> ProviderConfig {
+            toml::from_str(toml_content).expect("Failed to deserialize provider config")
+        };
+        assert_eq!(
+            provider_config(r#"diff-pager = "difft""#).diff_pager,
+            Some(DiffPager::Difftastic)
+        );
+        assert_eq!(
+            provider_config(r#"diff-pager = ["delta", "--color=never"]"#).diff_pager,
+            Some(DiffPager::Command(vec![
+                "delta".to_string(),
+                "--color=never".to_string()
+            ]))
+        );
+        assert_eq!(
+            provider_config(r#"git-diff-tool = ["difft", "--color=always"]"#).diff_pager,
+            Some(DiffPager::Command(vec![
+                "difft".to_string(),
+                "--color=always".to_string()
+            ]))
+        );
+    }
+
     #[test]
     fn test_load_config() {
         let toml_content = r#"
@@ -301,6 +1459,9 @@ mod tests {
           max-level = "trace"
           log-file = "/tmp/clap.log"
 
+          [datastore]
+          cache-dir = "~/.vim/cache/vimclap"
+
           [matcher]
           tiebreak = "score,-begin,-end,-length"
 
@@ -311,6 +1472,13 @@ mod tests {
           "*" = 200
           "files" = 100
 
+          [provider.default-args]
+          grep = ["--hidden"]
+
+          [provider.highlight-engine-overrides]
+          vue = "sublime"
+          rust = "tree-sitter"
+
           [global-ignore]
           ignore-file-path-pattern = ["test", "build"]
 
@@ -319,6 +1487,17 @@ mod tests {
 
           [provider.ignore.dumb_jump]
           ignore-comments = true
+
+          [provider.preview-position]
+          dumb_jump = "top"
+          grep = { offset = 3 }
+
+          [thread-pool]
+          background-threads = 1
+
+          [watcher]
+          enable = true
+          backend = "watchman"
 "#;
         let user_config: Config =
             toml::from_str(toml_content).expect("Failed to deserialize config");
@@ -331,6 +1510,10 @@ mod tests {
                     max_level: "trace".to_string(),
                     ..Default::default()
                 },
+                datastore: DatastoreConfig {
+                    cache_dir: Some("~/.vim/cache/vimclap".to_string()),
+                    ..Default::default()
+                },
                 matcher: MatcherConfig {
                     tiebreak: "score,-begin,-end,-length".to_string()
                 },
@@ -346,6 +1529,14 @@ mod tests {
                         ("*".to_string(), 200),
                         ("files".to_string(), 100)
                     ]),
+                    default_args: HashMap::from([(
+                        "grep".to_string(),
+                        vec!["--hidden".to_string()]
+                    )]),
+                    highlight_engine_overrides: HashMap::from([
+                        ("vue".to_string(), HighlightEngine::SublimeSyntax),
+                        ("rust".to_string(), HighlightEngine::TreeSitter)
+                    ]),
                     ignore: HashMap::from([(
                         "dumb_jump".to_string(),
                         IgnoreConfig {
@@ -353,14 +1544,40 @@ mod tests {
                             ..Default::default()
                         }
                     )]),
+                    preview_position: HashMap::from([
+                        ("dumb_jump".to_string(), PreviewPosition::Top),
+                        ("grep".to_string(), PreviewPosition::Offset(3))
+                    ]),
                     ..Default::default()
                 },
                 global_ignore: IgnoreConfig {
                     ignore_file_path_pattern: vec!["test".to_string(), "build".to_string()],
                     ..Default::default()
                 },
+                watcher: WatcherConfig {
+                    enable: true,
+                    backend: WatcherBackend::Watchman,
+                },
+                thread_pool: ThreadPoolConfig {
+                    background_threads: Some(1),
+                    ..Default::default()
+                },
                 ..Default::default()
             }
         );
+
+        let provider_config = &user_config.provider;
+        assert_eq!(
+            provider_config.highlight_engine_for(Some("rust"), Some("rs")),
+            HighlightEngine::TreeSitter
+        );
+        assert_eq!(
+            provider_config.highlight_engine_for(None, Some("vue")),
+            HighlightEngine::SublimeSyntax
+        );
+        assert_eq!(
+            provider_config.highlight_engine_for(Some("python"), Some("py")),
+            HighlightEngine::Vim
+        );
     }
 }
@@ -0,0 +1,464 @@
+//! Lists and edits the config keys for the `config` provider.
+//!
+//! The doc text of each key is the doc comment of the config field collected by
+//! `#[derive(ConfigSchema)]`, so that the docs are always in sync with the code.
+
+use crate::config::{config, config_file, Config};
+use once_cell::sync::Lazy;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigEditError {
+    #[error("unknown config key `{0}`")]
+    UnknownKey(String),
+    #[error("invalid config: {0}")]
+    InvalidConfig(#[from] toml::de::Error),
+    #[error("failed to parse the config file: {0}")]
+    BadConfigFile(#[from] toml_edit::TomlError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Field of a config struct.
+#[derive(Debug)]
+pub struct SchemaField {
+    pub name: &'static str,
+    pub doc: &'static str,
+    pub kind: FieldKind,
+}
+
+#[derive(Debug)]
+pub enum FieldKind {
+    /// Single value, e.g., a number, a list or an enum.
+    Value,
+    /// Nested config struct.
+    Struct(Vec<SchemaField>),
+    /// Map of the config structs, e.g., `project-ignore`.
+    Map(Vec<SchemaField>),
+    /// Map of the single values, e.g., `provider.debounce`.
+    ValueMap,
+}
+
+/// Config struct listing its fields, implemented by `#[derive(ConfigSchema)]`.
+pub trait ConfigSchema {
+    fn schema() -> Vec<SchemaField>;
+}
+
+/// Resolves the [`FieldKind`] of a field by its type in the derived [`ConfigSchema`].
+///
+/// The method `field_kind()` is called on `&&Probe<T>`, the impls on `&Probe<T>` are
+/// picked first if `T` is a config struct or a map of them, then the impl for the other
+/// maps on `&&Probe<T>` and the impl on `Probe<T>` for the rest.
+#[doc(hidden)]
+pub mod schema_probe {
+    use super::{ConfigSchema, FieldKind};
+    use std::collections::HashMap;
+    use std::marker::PhantomData;
+
+    pub struct Probe<T>(PhantomData<T>);
+
+    impl<T> Probe<T> {
+        pub fn new() -> Self {
+            Self(PhantomData)
+        }
+    }
+
+    pub trait StructKind {
+        fn field_kind(&self) -> FieldKind;
+    }
+
+    impl<T: ConfigSchema> StructKind for &Probe<T> {
+        fn field_kind(&self) -> FieldKind {
+            FieldKind::Struct(T::schema())
+        }
+    }
+
+    pub trait MapKind {
+        fn field_kind(&self) -> FieldKind;
+    }
+
+    impl<K, V: ConfigSchema> MapKind for &Probe<HashMap<K, V>> {
+        fn field_kind(&self) -> FieldKind {
+            FieldKind::Map(V::schema())
+        }
+    }
+
+    pub trait ValueMapKind {
+        fn field_kind(&self) -> FieldKind;
+    }
+
+    impl<K, V> ValueMapKind for &&Probe<HashMap<K, V>> {
+        fn field_kind(&self) -> FieldKind {
+            FieldKind::ValueMap
+        }
+    }
+
+    pub trait ValueKind {
+        fn field_kind(&self) -> FieldKind;
+    }
+
+    impl<T> ValueKind for Probe<T> {
+        fn field_kind(&self) -> FieldKind {
+            FieldKind::Value
+        }
+    }
+}
+
+/// Fields of [`Config`].
+static CONFIG_SCHEMA: Lazy<Vec<SchemaField>> = Lazy::new(Config::schema);
+
+fn find_field<'a>(fields: &'a [SchemaField], key: &str) -> Option<&'a SchemaField> {
+    fields
+        .iter()
+        .find(|field| field.name.replace('_', "-") == key)
+}
+
+/// Finds the field of the key `path`, the segments for the keys of the maps included.
+fn lookup_field(path: &[String]) -> Option<&'static SchemaField> {
+    let mut fields = CONFIG_SCHEMA.as_slice();
+    let mut segments = path.iter();
+
+    loop {
+        let field = find_field(fields, segments.next()?)?;
+
+        match &field.kind {
+            FieldKind::Struct(nested) => fields = nested,
+            FieldKind::Map(nested) => {
+                // Skip the map key.
+                segments.next()?;
+                fields = nested;
+            }
+            FieldKind::ValueMap => {
+                // Either the whole map or a single entry.
+                let _map_key = segments.next();
+                return segments.next().is_none().then_some(field);
+            }
+            FieldKind::Value => return segments.next().is_none().then_some(field),
+        }
+    }
+}
+
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn join_key(prefix: &str, key: &str) -> String {
+    let key = if is_bare_key(key) {
+        key.to_string()
+    } else {
+        format!("{key:?}")
+    };
+    if prefix.is_empty() {
+        key
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+/// Splits the dotted key into segments, e.g., `project-ignore."~/src".ignore-comments`.
+fn split_key(key: &str) -> Option<Vec<String>> {
+    let mut segments = Vec::new();
+    let mut rest = key.trim();
+
+    while !rest.is_empty() {
+        let segment = if let Some(quoted) = rest.strip_prefix('"') {
+            let (segment, remaining) = quoted.split_once('"')?;
+            rest = remaining;
+            segment
+        } else {
+            let end = rest.find('.').unwrap_or(rest.len());
+            let (segment, remaining) = rest.split_at(end);
+            rest = remaining;
+            segment
+        };
+        segments.push(segment.to_string());
+
+        rest = match rest.strip_prefix('.') {
+            Some(remaining) if !remaining.is_empty() => remaining,
+            None if rest.is_empty() => rest,
+            _ => return None,
+        };
+    }
+
+    (!segments.is_empty()).then_some(segments)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySource {
+    /// Not specified in the config file.
+    Default,
+    /// Specified in the config file.
+    Global,
+    /// Specified in the config file for a project.
+    Project,
+}
+
+impl KeySource {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Global => "global",
+            Self::Project => "project",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigKey {
+    pub key: String,
+    /// Effective value in TOML, `None` if unset.
+    pub value: Option<String>,
+    pub source: KeySource,
+}
+
+impl ConfigKey {
+    pub fn display_line(&self) -> String {
+        format!(
+            "[{}] {} = {}",
+            self.source.as_str(),
+            self.key,
+            self.value.as_deref().unwrap_or("<unset>")
+        )
+    }
+}
+
+/// Formats `value` in a single line.
+fn inline_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::Table(table) => {
+            let entries = table
+                .iter()
+                .map(|(k, v)| format!("{} = {}", join_key("", k), inline_value(v)))
+                .collect::<Vec<_>>();
+            format!("{{ {} }}", entries.join(", "))
+        }
+        toml::Value::Array(array) => {
+            let items = array.iter().map(inline_value).collect::<Vec<_>>();
+            format!("[{}]", items.join(", "))
+        }
+        value => value.to_string(),
+    }
+}
+
+fn collect_keys(
+    fields: &[SchemaField],
+    prefix: &str,
+    effective: Option<&toml::Value>,
+    user: Option<&toml::Value>,
+    keys: &mut Vec<ConfigKey>,
+) {
+    for field in fields {
+        let name = field.name.replace('_', "-");
+        let key = join_key(prefix, &name);
+        let effective = effective.and_then(|v| v.get(&name));
+        let user = user.and_then(|v| v.get(&name));
+
+        match &field.kind {
+            FieldKind::Map(nested) => {
+                if let Some(table) = effective.and_then(|v| v.as_table()) {
+                    for (map_key, map_value) in table {
+                        collect_keys(
+                            nested,
+                            &join_key(&key, map_key),
+                            Some(map_value),
+                            user.and_then(|v| v.get(map_key)),
+                            keys,
+                        );
+                    }
+                }
+            }
+            FieldKind::Struct(nested) => collect_keys(nested, &key, effective, user, keys),
+            FieldKind::Value | FieldKind::ValueMap => {
+                let source = match user {
+                    Some(_) if key.starts_with("project-") => KeySource::Project,
+                    Some(_) => KeySource::Global,
+                    None => KeySource::Default,
+                };
+                keys.push(ConfigKey {
+                    key,
+                    value: effective.map(inline_value),
+                    source,
+                });
+            }
+        }
+    }
+}
+
+fn config_keys(effective: &Config, user_config: &str) -> Vec<ConfigKey> {
+    let effective = toml::Value::try_from(effective).ok();
+    let user = toml::from_str::<toml::Value>(user_config).ok();
+
+    let mut keys = Vec::new();
+    collect_keys(
+        &CONFIG_SCHEMA,
+        "",
+        effective.as_ref(),
+        user.as_ref(),
+        &mut keys,
+    );
+    keys
+}
+
+/// Returns the lines of all the effective config keys.
+pub fn config_lines() -> Vec<String> {
+    let user_config = std::fs::read_to_string(config_file()).unwrap_or_default();
+    config_keys(&config(), &user_config)
+        .iter()
+        .map(ConfigKey::display_line)
+        .collect()
+}
+
+/// Extracts the key from the line of [`config_lines`].
+pub fn extract_config_key(line: &str) -> Option<&str> {
+    let (_source, key_value) = line.split_once("] ")?;
+    key_value.split_once(" = ").map(|(key, _value)| key)
+}
+
+/// Returns the doc text of `key`.
+pub fn key_doc(key: &str) -> Option<&'static str> {
+    split_key(key)
+        .as_deref()
+        .and_then(lookup_field)
+        .map(|field| field.doc)
+}
+
+/// Sets `key` to `value` in the config `contents`, returns the new contents.
+///
+/// `value` is used as a string if it's not a valid TOML value.
+fn set_value_in(contents: &str, key: &str, value: &str) -> Result<String, ConfigEditError> {
+    let path = split_key(key)
+        .filter(|path| lookup_field(path).is_some())
+        .ok_or_else(|| ConfigEditError::UnknownKey(key.to_string()))?;
+
+    let mut document = contents.parse::<toml_edit::Document>()?;
+
+    let (last, parents) = path.split_last().expect("Path must not be empty");
+    let mut table: &mut dyn toml_edit::TableLike = document.as_table_mut();
+    for segment in parents {
+        let item = table.entry(segment).or_insert_with(|| {
+            let mut table = toml_edit::Table::new();
+            table.set_implicit(true);
+            toml_edit::Item::Table(table)
+        });
+        table = item
+            .as_table_like_mut()
+            .ok_or_else(|| ConfigEditError::UnknownKey(key.to_string()))?;
+    }
+
+    let new_value = value
+        .trim()
+        .parse::<toml_edit::Value>()
+        .unwrap_or_else(|_| value.into());
+    table.insert(last, toml_edit::value(new_value));
+
+    let new_contents = document.to_string();
+
+    // Validate the entire config.
+    toml::from_str::<Config>(&new_contents)?;
+
+    Ok(new_contents)
+}
+
+/// Sets `key` to `value` in the config file and reloads the config.
+pub fn set_config_value(key: &str, value: &str) -> Result<(), ConfigEditError> {
+    let config_file = config_file();
+    let contents = std::fs::read_to_string(config_file).unwrap_or_default();
+
+    let new_contents = set_value_in(&contents, key, value)?;
+
+    crate::cache::write_atomically(config_file, |temp| std::fs::write(temp, new_contents))?;
+
+    crate::config::reload_config()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_schema() {
+        let path = split_key("plugin.cursorword.enable").unwrap();
+        assert_eq!(lookup_field(&path).unwrap().name, "enable");
+
+        let path = split_key(r#"project-ignore."~/src/vim".ignore-comments"#).unwrap();
+        assert_eq!(path[1], "~/src/vim");
+        assert_eq!(lookup_field(&path).unwrap().name, "ignore_comments");
+
+        let path = split_key("provider.debounce.files").unwrap();
+        assert_eq!(lookup_field(&path).unwrap().name, "debounce");
+        let path = split_key("provider.debounce").unwrap();
+        assert!(matches!(
+            lookup_field(&path).unwrap().kind,
+            FieldKind::ValueMap
+        ));
+
+        // The kinds are resolved by the field types.
+        let provider = find_field(&CONFIG_SCHEMA, "provider").unwrap();
+        let FieldKind::Struct(provider_fields) = &provider.kind else {
+            panic!("provider must be a struct");
+        };
+        assert!(matches!(
+            find_field(provider_fields, "ignore").unwrap().kind,
+            FieldKind::Map(_)
+        ));
+        assert!(matches!(
+            find_field(provider_fields, "diff-pager").unwrap().kind,
+            FieldKind::Value
+        ));
+
+        assert!(lookup_field(&split_key("matcher.unknown").unwrap()).is_none());
+        assert!(split_key("matcher.").is_none());
+        assert!(key_doc("idle-scheduler").is_none());
+        assert!(key_doc("datastore.cache-dir")
+            .unwrap()
+            .starts_with("Directory for the caches."));
+    }
+
+    #[test]
+    fn test_config_keys() {
+        let user_config = r#"
+          [matcher]
+          tiebreak = "score"
+
+          [project-ignore."/tmp"]
+          ignore-comments = true
+"#;
+        let effective: Config = toml::from_str(user_config).unwrap();
+        let keys = config_keys(&effective, user_config);
+
+        let find = |key: &str| keys.iter().find(|k| k.key == key).unwrap();
+        assert_eq!(find("matcher.tiebreak").source, KeySource::Global);
+        assert_eq!(find("log.max-level").source, KeySource::Default);
+        assert_eq!(find("log.log-file").value, None);
+        let ignore_comments = find(r#"project-ignore."/tmp".ignore-comments"#);
+        assert_eq!(ignore_comments.source, KeySource::Project);
+        assert_eq!(
+            extract_config_key(&ignore_comments.display_line()),
+            Some(r#"project-ignore."/tmp".ignore-comments"#)
+        );
+    }
+
+    #[test]
+    fn test_set_value_in() {
+        let contents = "# My config\n[log]\nmax-level = \"debug\"\n";
+
+        let new_contents = set_value_in(contents, "log.max-level", "trace").unwrap();
+        assert_eq!(new_contents, "# My config\n[log]\nmax-level = \"trace\"\n");
+
+        let new_contents = set_value_in(contents, "plugin.cursorword.enable", "true").unwrap();
+        assert!(new_contents.contains("[plugin.cursorword]\nenable = true\n"));
+
+        assert!(matches!(
+            set_value_in(contents, "plugin.cursorword.enable", "\"yes\""),
+            Err(ConfigEditError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            set_value_in(contents, "log.unknown", "1"),
+            Err(ConfigEditError::UnknownKey(_))
+        ));
+    }
+}
@@ -1,8 +1,11 @@
 //! This module provides the feature of persistent data store via file system.
 
 use crate::cache::CacheInfo;
+use crate::history_frecency::HistoryFrecency;
+use crate::pinned_items::PinnedItems;
 use crate::recent_files::SortedRecentFiles;
-use crate::stdio_server::InputHistory;
+use crate::stdio_server::{InputHistory, StickyQueries};
+use crate::usage_stats::UsageStats;
 use dirs::Dirs;
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
@@ -19,12 +22,36 @@ static CACHE_METADATA_PATH: Lazy<Option<PathBuf>> =
     Lazy::new(|| generate_data_file_path(CACHE_FILENAME).ok());
 
 pub static CACHE_INFO_IN_MEMORY: Lazy<Arc<Mutex<CacheInfo>>> = Lazy::new(|| {
-    let mut maybe_persistent =
-        load_json::<CacheInfo, _>(CACHE_METADATA_PATH.as_deref()).unwrap_or_else(CacheInfo::new);
+    // `CacheInfo::new()` is not equivalent to `CacheInfo::default()` as it's idle initially.
+    #[allow(clippy::unwrap_or_default)]
+    let mut maybe_persistent = load_cache_info().unwrap_or_else(CacheInfo::new);
     maybe_persistent.remove_invalid_and_old_entries();
+    // Reading all the cache files would block the first access.
+    std::thread::spawn(crate::cache::remove_corrupted_entries);
     Arc::new(Mutex::new(maybe_persistent))
 });
 
+/// Loads the cache info from the disk.
+///
+/// The corrupted metadata file is moved aside as `cache.json.corrupted` and all the caches
+/// will be rebuilt.
+pub(crate) fn load_cache_info() -> Option<CacheInfo> {
+    let path = CACHE_METADATA_PATH.as_deref()?;
+
+    if !path.exists() {
+        return None;
+    }
+
+    match read_json_as::<_, CacheInfo>(path) {
+        Ok(cache_info) => Some(cache_info),
+        Err(err) => {
+            tracing::warn!(?err, "Cache metadata is corrupted, moving it aside");
+            let _ = std::fs::rename(path, path.with_extension("json.corrupted"));
+            None
+        }
+    }
+}
+
 /// Linux: ~/.local/share/vimclap/recent_files.json
 const RECENT_FILES_FILENAME: &str = "recent_files.json";
 
@@ -38,9 +65,59 @@ pub static RECENT_FILES_IN_MEMORY: Lazy<Mutex<SortedRecentFiles>> = Lazy::new(||
     Mutex::new(maybe_persistent)
 });
 
+/// Linux: ~/.local/share/vimclap/input_history.json
+const INPUT_HISTORY_FILENAME: &str = "input_history.json";
+
+static INPUT_HISTORY_JSON_PATH: Lazy<Option<PathBuf>> =
+    Lazy::new(|| generate_data_file_path(INPUT_HISTORY_FILENAME).ok());
+
 pub static INPUT_HISTORY_IN_MEMORY: Lazy<Arc<Mutex<InputHistory>>> = Lazy::new(|| {
-    // TODO: make input history persistent?
-    Arc::new(Mutex::new(InputHistory::new()))
+    let maybe_persistent = load_json(INPUT_HISTORY_JSON_PATH.as_deref()).unwrap_or_default();
+    Arc::new(Mutex::new(maybe_persistent))
+});
+
+/// Linux: ~/.local/share/vimclap/sticky_queries.json
+const STICKY_QUERIES_FILENAME: &str = "sticky_queries.json";
+
+static STICKY_QUERIES_JSON_PATH: Lazy<Option<PathBuf>> =
+    Lazy::new(|| generate_data_file_path(STICKY_QUERIES_FILENAME).ok());
+
+pub static STICKY_QUERIES_IN_MEMORY: Lazy<Mutex<StickyQueries>> = Lazy::new(|| {
+    let maybe_persistent = load_json(STICKY_QUERIES_JSON_PATH.as_deref()).unwrap_or_default();
+    Mutex::new(maybe_persistent)
+});
+
+/// Linux: ~/.local/share/vimclap/usage_stats.json
+const USAGE_STATS_FILENAME: &str = "usage_stats.json";
+
+static USAGE_STATS_JSON_PATH: Lazy<Option<PathBuf>> =
+    Lazy::new(|| generate_data_file_path(USAGE_STATS_FILENAME).ok());
+
+pub static USAGE_STATS_IN_MEMORY: Lazy<Mutex<UsageStats>> = Lazy::new(|| {
+    let maybe_persistent = load_json(USAGE_STATS_JSON_PATH.as_deref()).unwrap_or_default();
+    Mutex::new(maybe_persistent)
+});
+
+/// Linux: ~/.local/share/vimclap/pinned_items.json
+const PINNED_ITEMS_FILENAME: &str = "pinned_items.json";
+
+static PINNED_ITEMS_JSON_PATH: Lazy<Option<PathBuf>> =
+    Lazy::new(|| generate_data_file_path(PINNED_ITEMS_FILENAME).ok());
+
+pub static PINNED_ITEMS_IN_MEMORY: Lazy<Mutex<PinnedItems>> = Lazy::new(|| {
+    let maybe_persistent = load_json(PINNED_ITEMS_JSON_PATH.as_deref()).unwrap_or_default();
+    Mutex::new(maybe_persistent)
+});
+
+/// Linux: ~/.local/share/vimclap/history_frecency.json
+const HISTORY_FRECENCY_FILENAME: &str = "history_frecency.json";
+
+static HISTORY_FRECENCY_JSON_PATH: Lazy<Option<PathBuf>> =
+    Lazy::new(|| generate_data_file_path(HISTORY_FRECENCY_FILENAME).ok());
+
+pub static HISTORY_FRECENCY_IN_MEMORY: Lazy<Mutex<HistoryFrecency>> = Lazy::new(|| {
+    let maybe_persistent = load_json(HISTORY_FRECENCY_JSON_PATH.as_deref()).unwrap_or_default();
+    Mutex::new(maybe_persistent)
 });
 
 pub fn store_cache_info(cache_info: &CacheInfo) -> std::io::Result<()> {
@@ -51,20 +128,40 @@ pub fn store_recent_files(recent_files: &SortedRecentFiles) -> std::io::Result<(
     write_json(recent_files, RECENT_FILES_JSON_PATH.as_ref())
 }
 
+pub fn store_input_history(input_history: &InputHistory) -> std::io::Result<()> {
+    write_json(input_history, INPUT_HISTORY_JSON_PATH.as_ref())
+}
+
+pub fn store_sticky_queries(sticky_queries: &StickyQueries) -> std::io::Result<()> {
+    write_json(sticky_queries, STICKY_QUERIES_JSON_PATH.as_ref())
+}
+
+pub fn store_usage_stats(usage_stats: &UsageStats) -> std::io::Result<()> {
+    write_json(usage_stats, USAGE_STATS_JSON_PATH.as_ref())
+}
+
+pub fn store_pinned_items(pinned_items: &PinnedItems) -> std::io::Result<()> {
+    write_json(pinned_items, PINNED_ITEMS_JSON_PATH.as_ref())
+}
+
+pub fn store_history_frecency(history_frecency: &HistoryFrecency) -> std::io::Result<()> {
+    write_json(history_frecency, HISTORY_FRECENCY_JSON_PATH.as_ref())
+}
+
 pub fn cache_metadata_path() -> Option<&'static PathBuf> {
     CACHE_METADATA_PATH.as_ref()
 }
 
 /// Returns a `PathBuf` using given file name under the project data directory.
 pub fn generate_data_file_path(filename: &str) -> std::io::Result<PathBuf> {
-    let data_dir = Dirs::project().data_dir();
+    let data_dir = Dirs::data_dir();
     std::fs::create_dir_all(data_dir)?;
     Ok(data_dir.join(filename))
 }
 
 /// Returns a `PathBuf` using given file name under the project cache directory.
 pub fn generate_cache_file_path(filename: impl AsRef<Path>) -> std::io::Result<PathBuf> {
-    let cache_dir = Dirs::project().cache_dir();
+    let cache_dir = Dirs::cache_dir();
     std::fs::create_dir_all(cache_dir)?;
     Ok(cache_dir.join(filename))
 }
@@ -94,7 +191,12 @@ fn load_json<T: DeserializeOwned, P: AsRef<Path>>(path: Option<P>) -> Option<T>
 
 fn write_json<T: Serialize, P: AsRef<Path>>(obj: T, path: Option<P>) -> std::io::Result<()> {
     if let Some(json_path) = path.as_ref() {
-        utils::create_or_overwrite(json_path, serde_json::to_string(&obj)?.as_bytes())?;
+        let json_path = json_path.as_ref();
+        let contents = serde_json::to_string(&obj)?;
+        // Another instance may be reading the file at the same time.
+        crate::cache::write_atomically(json_path, |temp| {
+            utils::create_or_overwrite(temp, contents.as_bytes())
+        })?;
     }
 
     Ok(())
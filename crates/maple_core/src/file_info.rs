@@ -0,0 +1,122 @@
+//! Size and modification time columns of the file paths in the display window.
+
+use serde::Serialize;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileInfo {
+    /// File size in bytes.
+    pub size: u64,
+    /// Seconds since the unix epoch.
+    pub mtime: u64,
+    /// Formatted columns, e.g., `  1.2K  3d ago`.
+    pub text: String,
+}
+
+/// Formats `bytes` in the binary units, e.g., `512B`, `1.5K`, `23M`.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["K", "M", "G", "T", "P"];
+
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next_unit in &UNITS[1..] {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next_unit;
+    }
+
+    if size < 10.0 {
+        format!("{size:.1}{unit}")
+    } else {
+        format!("{size:.0}{unit}")
+    }
+}
+
+/// Formats the time elapsed from `mtime` to `now`, e.g., `5m ago`, `3d ago`.
+pub fn relative_time(mtime: u64, now: u64) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+    const WEEK: u64 = 7 * DAY;
+    const MONTH: u64 = 30 * DAY;
+    const YEAR: u64 = 365 * DAY;
+
+    let elapsed = now.saturating_sub(mtime);
+
+    match elapsed {
+        e if e < MINUTE => "now".to_string(),
+        e if e < HOUR => format!("{}m ago", e / MINUTE),
+        e if e < DAY => format!("{}h ago", e / HOUR),
+        e if e < WEEK => format!("{}d ago", e / DAY),
+        e if e < MONTH => format!("{}w ago", e / WEEK),
+        e if e < YEAR => format!("{}mo ago", e / MONTH),
+        e => format!("{}y ago", e / YEAR),
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Returns the [`FileInfo`] of `path`, `None` if the metadata is unavailable.
+pub fn file_info(path: &Path, now: SystemTime) -> Option<FileInfo> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let size = metadata.len();
+    let mtime = metadata.modified().map(unix_secs).unwrap_or_default();
+    let text = format!(
+        "{:>6} {:>8}",
+        human_size(size),
+        relative_time(mtime, unix_secs(now))
+    );
+    Some(FileInfo { size, mtime, text })
+}
+
+/// Returns the [`FileInfo`] of each path, the relative paths are resolved from `cwd`.
+pub fn collect_file_info(cwd: &Path, paths: &[String]) -> Vec<Option<FileInfo>> {
+    let now = SystemTime::now();
+    paths
+        .iter()
+        .map(|path| file_info(&cwd.join(paths::expand_tilde(path)), now))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_info_columns() {
+        assert_eq!(human_size(0), "0B");
+        assert_eq!(human_size(1023), "1023B");
+        assert_eq!(human_size(1536), "1.5K");
+        assert_eq!(human_size(20 * 1024 * 1024), "20M");
+        assert_eq!(human_size(3 * 1024 * 1024 * 1024), "3.0G");
+
+        let now = 100 * 365 * 24 * 3600;
+        assert_eq!(relative_time(now - 30, now), "now");
+        assert_eq!(relative_time(now - 5 * 60, now), "5m ago");
+        assert_eq!(relative_time(now - 3 * 3600, now), "3h ago");
+        assert_eq!(relative_time(now - 2 * 24 * 3600, now), "2d ago");
+        assert_eq!(relative_time(now - 15 * 24 * 3600, now), "2w ago");
+        assert_eq!(relative_time(now - 90 * 24 * 3600, now), "3mo ago");
+        assert_eq!(relative_time(now - 800 * 24 * 3600, now), "2y ago");
+        // The file modified in the future.
+        assert_eq!(relative_time(now + 10, now), "now");
+
+        let info = collect_file_info(
+            Path::new(env!("CARGO_MANIFEST_DIR")),
+            &["Cargo.toml".into()],
+        );
+        assert!(info[0].as_ref().unwrap().size > 0);
+        assert!(collect_file_info(Path::new("/"), &["not/exist".into()])[0].is_none());
+    }
+}
@@ -1,5 +1,6 @@
 use super::executable_searcher::LanguageRegexSearcher;
 use crate::tools::rg::{Match, Word};
+use dumb_analyzer::CommentSyntax;
 use itertools::Itertools;
 use once_cell::sync::Lazy;
 use rayon::prelude::*;
@@ -102,8 +103,8 @@ pub(super) fn build_full_regexp(lang: &str, kind: &DefinitionKind, word: &Word)
 
 /// Returns true if the ripgrep match is a comment line.
 #[inline]
-pub(super) fn is_comment(mat: &Match, comments: &[&str]) -> bool {
-    comments.iter().any(|c| mat.line_starts_with(c))
+pub(super) fn is_comment(mat: &Match, comments: &CommentSyntax) -> bool {
+    comments.is_comment_line(&mat.lines.text())
 }
 
 /// Search results of a specific definition kind.
@@ -169,7 +170,7 @@ impl Occurrences {
 
 pub(super) fn find_definitions_and_references(
     lang_regex_searcher: LanguageRegexSearcher,
-    comments: &[&str],
+    comments: &CommentSyntax,
 ) -> std::io::Result<HashMap<MatchKind, Vec<Match>>> {
     let (definitions, mut occurrences) = lang_regex_searcher.all(comments);
 
@@ -2,8 +2,9 @@ use super::definition::{
     build_full_regexp, get_definition_rules, is_comment, DefinitionKind, DefinitionSearchResult,
     Definitions, Occurrences,
 };
+use crate::process::limits::LimitResources;
 use crate::tools::rg::{Match, Word, RG_EXISTS};
-use dumb_analyzer::get_comment_syntax;
+use dumb_analyzer::{get_comment_syntax, CommentSyntax};
 use rayon::prelude::*;
 use std::convert::TryFrom;
 use std::io::{Error, ErrorKind, Result};
@@ -17,7 +18,7 @@ pub struct ExecutableSearcher {
 }
 
 impl ExecutableSearcher {
-    fn new(command: Command) -> Result<Self> {
+    fn new(mut command: Command) -> Result<Self> {
         if !*RG_EXISTS {
             return Err(Error::new(
                 ErrorKind::NotFound,
@@ -25,13 +26,15 @@ impl ExecutableSearcher {
             ));
         }
 
+        command.limit_resources().killable();
+
         Ok(Self { command })
     }
 
     /// Executes `command` as a child process.
     ///
     /// Convert the entire output into a stream of ripgrep `Match`.
-    fn search(self, maybe_comments: Option<&[&str]>) -> Result<Vec<Match>> {
+    fn search(self, maybe_comments: Option<&CommentSyntax>) -> Result<Vec<Match>> {
         let mut cmd = self.command;
 
         let cmd_output = cmd.output()?;
@@ -73,11 +76,8 @@ pub(super) fn word_regex_search_with_extension(
     if let Some(ref dir) = maybe_dir {
         command.current_dir(dir);
     }
-    ExecutableSearcher::new(command)?.search(if ignore_comment {
-        Some(get_comment_syntax(file_extension))
-    } else {
-        None
-    })
+    let maybe_comments = ignore_comment.then(|| get_comment_syntax(file_extension));
+    ExecutableSearcher::new(command)?.search(maybe_comments.as_deref())
 }
 
 /// [`LanguageRegexSearcher`] with a known language type.
@@ -97,7 +97,7 @@ impl LanguageRegexSearcher {
     }
 
     /// Finds the occurrences and all definitions concurrently.
-    pub fn all(&self, comments: &[&str]) -> (Definitions, Occurrences) {
+    pub fn all(&self, comments: &CommentSyntax) -> (Definitions, Occurrences) {
         (
             Definitions {
                 defs: self.definitions().unwrap_or_default(),
@@ -124,7 +124,7 @@ impl LanguageRegexSearcher {
     /// Finds all the occurrences of `word`.
     ///
     /// Basically the occurrences are composed of definitions and usages.
-    fn occurrences(&self, comments: &[&str]) -> Result<Vec<Match>> {
+    fn occurrences(&self, comments: &CommentSyntax) -> Result<Vec<Match>> {
         let mut command = Command::new("rg");
         command
             .arg("--json")
@@ -138,7 +138,7 @@ impl LanguageRegexSearcher {
         ExecutableSearcher::new(command)?.search(Some(comments))
     }
 
-    pub(super) fn regexp_search(&self, comments: &[&str]) -> Result<Vec<Match>> {
+    pub(super) fn regexp_search(&self, comments: &CommentSyntax) -> Result<Vec<Match>> {
         let mut command = Command::new("rg");
         command
             .arg("--json")
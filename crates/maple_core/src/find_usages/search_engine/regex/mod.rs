@@ -15,7 +15,7 @@ use self::definition::{find_definitions_and_references, DefinitionSearchResult,
 use self::executable_searcher::{word_regex_search_with_extension, LanguageRegexSearcher};
 use crate::find_usages::{AddressableUsage, Usage, UsageMatcher, Usages};
 use crate::tools::rg::{get_language, Match, Word};
-use dumb_analyzer::{get_comment_syntax, resolve_reference_kind, Priority};
+use dumb_analyzer::{get_comment_syntax, resolve_reference_kind, CommentSyntax, Priority};
 use rayon::prelude::*;
 use std::collections::HashMap;
 use std::io::{Error, ErrorKind, Result};
@@ -140,7 +140,7 @@ impl RegexSearcher {
 
         // render the results in group.
         if classify {
-            let res = find_definitions_and_references(lang_regex_searcher, comments)?;
+            let res = find_definitions_and_references(lang_regex_searcher, &comments)?;
 
             let _usages = res
                 .into_iter()
@@ -151,7 +151,7 @@ impl RegexSearcher {
             unimplemented!("Classify regex search")
             // Ok(usages.into())
         } else {
-            self.regex_search(lang_regex_searcher, comments, usage_matcher)
+            self.regex_search(lang_regex_searcher, &comments, usage_matcher)
         }
     }
 
@@ -161,7 +161,7 @@ impl RegexSearcher {
     fn regex_search(
         &self,
         lang_regex_searcher: LanguageRegexSearcher,
-        comments: &[&str],
+        comments: &CommentSyntax,
         usage_matcher: &UsageMatcher,
     ) -> Result<Vec<AddressableUsage>> {
         let (definitions, occurrences) = lang_regex_searcher.all(comments);
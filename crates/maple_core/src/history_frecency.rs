@@ -0,0 +1,113 @@
+//! Frecency of the command-line and search history entries picked in the
+//! `command_history` and `search_history` providers.
+//!
+//! Vim keeps the history in the order of recency only, the entries picked frequently
+//! are ranked higher on top of that.
+
+use crate::recent_files::frecent_score;
+use crate::UtcTime;
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Maximum number of entries tracked for each history type.
+const MAX_ENTRIES: usize = 1_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntryStats {
+    /// Number of times the entry was picked.
+    pub visits: u64,
+    /// Time of the last pick.
+    pub last_visit: UtcTime,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryFrecency {
+    /// History type, i.e., `:` or `/`, to the stats of the entries.
+    histories: HashMap<String, HashMap<String, HistoryEntryStats>>,
+}
+
+impl HistoryFrecency {
+    /// Records a pick of `entry` in the history `history_type`.
+    pub fn record(&mut self, history_type: &str, entry: &str) {
+        let history = self.histories.entry(history_type.to_string()).or_default();
+
+        history
+            .entry(entry.to_string())
+            .and_modify(|stats| {
+                stats.visits += 1;
+                stats.last_visit = Utc::now();
+            })
+            .or_insert_with(|| HistoryEntryStats {
+                visits: 1,
+                last_visit: Utc::now(),
+            });
+
+        if history.len() > MAX_ENTRIES {
+            let now = Utc::now();
+            if let Some(least_frecent) = history
+                .iter()
+                .min_by_key(|(_, stats)| {
+                    (
+                        frecent_score(stats.visits, stats.last_visit, now),
+                        stats.last_visit,
+                    )
+                })
+                .map(|(entry, _)| entry.clone())
+            {
+                history.remove(&least_frecent);
+            }
+        }
+    }
+
+    /// Forgets `entry` once it's deleted from the history.
+    pub fn remove(&mut self, history_type: &str, entry: &str) {
+        if let Some(history) = self.histories.get_mut(history_type) {
+            history.remove(entry);
+        }
+    }
+
+    pub fn stats(&self, history_type: &str, entry: &str) -> Option<&HistoryEntryStats> {
+        self.histories.get(history_type)?.get(entry)
+    }
+
+    /// Returns the deduplicated `entries` ranked by frecency, `entries` are in the order
+    /// of recency, which is kept for the entries of the same score.
+    pub fn rank(&self, history_type: &str, entries: Vec<String>) -> Vec<String> {
+        let now = Utc::now();
+        let mut seen = HashSet::new();
+        let mut entries = entries
+            .into_iter()
+            .filter(|entry| seen.insert(entry.clone()))
+            .map(|entry| {
+                let score = self.stats(history_type, &entry).map_or(0, |stats| {
+                    frecent_score(stats.visits, stats.last_visit, now)
+                });
+                (score, entry)
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| b.0.cmp(&a.0));
+        entries.into_iter().map(|(_, entry)| entry).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_history_entries() {
+        let mut frecency = HistoryFrecency::default();
+        frecency.record(":", "make");
+        frecency.record(":", "make");
+        frecency.record(":", "w");
+        frecency.record("/", "foo");
+
+        let entries = ["ls", "w", "make", "ls", "foo"].map(String::from).to_vec();
+        assert_eq!(frecency.rank(":", entries), ["make", "w", "ls", "foo"]);
+
+        frecency.remove(":", "make");
+        assert!(frecency.stats(":", "make").is_none());
+        assert_eq!(frecency.stats("/", "foo").unwrap().visits, 1);
+    }
+}
@@ -1,14 +1,22 @@
 mod cache;
 pub mod config;
+mod config_editor;
 pub mod datastore;
+mod file_info;
 pub mod find_usages;
 pub mod helptags;
+mod history_frecency;
+mod pinned_items;
 mod previewer;
 pub mod process;
 mod recent_files;
 pub mod searcher;
 pub mod stdio_server;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod tools;
+mod usage_stats;
+pub mod vfs;
 
 /// For benchmarks.
 pub use self::cache::find_largest_cache_digest;
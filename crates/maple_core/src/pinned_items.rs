@@ -0,0 +1,119 @@
+//! Items pinned by the user per provider.
+//!
+//! The pinned items are listed first when the query is empty and get a bonus in the
+//! matching otherwise.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use types::{ClapItem, ItemHighlight, MatchedItem};
+
+/// Highlight group of the pinned items in the display window.
+const PINNED_HIGHLIGHT_GROUP: &str = "ClapPinned";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PinnedItems {
+    providers: HashMap<String, Vec<String>>,
+}
+
+impl PinnedItems {
+    /// Returns the items pinned in `provider_id`, the latest pinned first.
+    pub fn items(&self, provider_id: &str) -> &[String] {
+        self.providers
+            .get(provider_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn is_pinned(&self, provider_id: &str, item: &str) -> bool {
+        self.items(provider_id).iter().any(|i| i == item)
+    }
+
+    /// Pins `item` in `provider_id`, returns `false` if it has been pinned.
+    pub fn pin(&mut self, provider_id: &str, item: String) -> bool {
+        if self.is_pinned(provider_id, &item) {
+            return false;
+        }
+        self.providers
+            .entry(provider_id.to_string())
+            .or_default()
+            .insert(0, item);
+        true
+    }
+
+    /// Unpins `item` in `provider_id`, returns `false` if it's not pinned.
+    pub fn unpin(&mut self, provider_id: &str, item: &str) -> bool {
+        let Some(items) = self.providers.get_mut(provider_id) else {
+            return false;
+        };
+        let len = items.len();
+        items.retain(|i| i != item);
+        let removed = items.len() != len;
+        if items.is_empty() {
+            self.providers.remove(provider_id);
+        }
+        removed
+    }
+}
+
+/// Pinned item displayed in the pinned section.
+#[derive(Debug)]
+struct PinnedItem(String);
+
+impl ClapItem for PinnedItem {
+    fn raw_text(&self) -> &str {
+        &self.0
+    }
+
+    fn highlights(&self) -> Vec<ItemHighlight> {
+        vec![ItemHighlight {
+            start: 0,
+            length: self.0.len(),
+            group: PINNED_HIGHLIGHT_GROUP,
+        }]
+    }
+}
+
+/// Returns the items displayed on empty query, the pinned items are placed at the top and
+/// removed from the rest of `items`.
+pub fn with_pinned_section(pinned: &[String], items: Vec<MatchedItem>) -> Vec<MatchedItem> {
+    pinned
+        .iter()
+        .map(|item| MatchedItem::from(Arc::new(PinnedItem(item.clone())) as Arc<dyn ClapItem>))
+        .chain(
+            items
+                .into_iter()
+                .filter(|matched| !pinned.iter().any(|p| p == matched.item.raw_text())),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pinned_items() {
+        let mut pinned_items = PinnedItems::default();
+        assert!(pinned_items.pin("files", "src/lib.rs".into()));
+        assert!(pinned_items.pin("files", "Cargo.toml".into()));
+        assert!(!pinned_items.pin("files", "src/lib.rs".into()));
+        assert_eq!(pinned_items.items("files"), ["Cargo.toml", "src/lib.rs"]);
+        assert!(pinned_items.items("grep").is_empty());
+
+        let items = ["README.md", "src/lib.rs"]
+            .into_iter()
+            .map(|item| MatchedItem::from(Arc::new(item.to_string()) as Arc<dyn ClapItem>))
+            .collect();
+        let items = with_pinned_section(pinned_items.items("files"), items)
+            .into_iter()
+            .map(|matched| matched.item.raw_text().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(items, ["Cargo.toml", "src/lib.rs", "README.md"]);
+
+        assert!(pinned_items.unpin("files", "src/lib.rs"));
+        assert!(!pinned_items.unpin("files", "src/lib.rs"));
+        assert!(pinned_items.unpin("files", "Cargo.toml"));
+        assert!(pinned_items.providers.is_empty());
+    }
+}
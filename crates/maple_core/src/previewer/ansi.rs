@@ -0,0 +1,318 @@
+//! Converts the ANSI escape sequences in the output of the external tools, e.g., delta,
+//! into the highlights in Vim.
+
+use rgb2ansi256::rgb_to_ansi256;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Color {
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    fn cterm(self) -> String {
+        match self {
+            Self::Indexed(idx) => idx.to_string(),
+            Self::Rgb(r, g, b) => rgb_to_ansi256(r, g, b).to_string(),
+        }
+    }
+
+    fn gui(self) -> String {
+        let (r, g, b) = match self {
+            Self::Indexed(idx) => indexed_rgb(idx),
+            Self::Rgb(r, g, b) => (r, g, b),
+        };
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+/// Returns the RGB of the color in the xterm 256 color palette.
+fn indexed_rgb(idx: u8) -> (u8, u8, u8) {
+    const BASIC: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0x80, 0x00, 0x00),
+        (0x00, 0x80, 0x00),
+        (0x80, 0x80, 0x00),
+        (0x00, 0x00, 0x80),
+        (0x80, 0x00, 0x80),
+        (0x00, 0x80, 0x80),
+        (0xc0, 0xc0, 0xc0),
+        (0x80, 0x80, 0x80),
+        (0xff, 0x00, 0x00),
+        (0x00, 0xff, 0x00),
+        (0xff, 0xff, 0x00),
+        (0x00, 0x00, 0xff),
+        (0xff, 0x00, 0xff),
+        (0x00, 0xff, 0xff),
+        (0xff, 0xff, 0xff),
+    ];
+
+    match idx {
+        0..=15 => BASIC[idx as usize],
+        16..=231 => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+            let idx = idx - 16;
+            (level(idx / 36), level(idx / 6 % 6), level(idx % 6))
+        }
+        _ => {
+            let gray = 8 + (idx - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Style {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+}
+
+impl Style {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Applies the SGR parameters, e.g., `1;38;5;10`.
+    fn apply_sgr(&mut self, params: &str) {
+        let mut params = params
+            .split(|c| c == ';' || c == ':')
+            .map(|p| p.parse::<u8>().unwrap_or(0));
+
+        let extended_color = |params: &mut dyn Iterator<Item = u8>| match params.next() {
+            Some(5) => params.next().map(Color::Indexed),
+            Some(2) => Some(Color::Rgb(params.next()?, params.next()?, params.next()?)),
+            _ => None,
+        };
+
+        while let Some(param) = params.next() {
+            match param {
+                0 => *self = Self::default(),
+                1 => self.bold = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => self.bold = false,
+                23 => self.italic = false,
+                24 => self.underline = false,
+                30..=37 => self.fg = Some(Color::Indexed(param - 30)),
+                38 => self.fg = extended_color(&mut params),
+                39 => self.fg = None,
+                40..=47 => self.bg = Some(Color::Indexed(param - 40)),
+                48 => self.bg = extended_color(&mut params),
+                49 => self.bg = None,
+                90..=97 => self.fg = Some(Color::Indexed(param - 90 + 8)),
+                100..=107 => self.bg = Some(Color::Indexed(param - 100 + 8)),
+                _ => {}
+            }
+        }
+    }
+
+    fn attr(&self) -> String {
+        let attrs = [
+            (self.bold, "bold"),
+            (self.italic, "italic"),
+            (self.underline, "underline"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, attr)| enabled.then_some(attr))
+        .collect::<Vec<_>>();
+        if attrs.is_empty() {
+            "NONE".to_string()
+        } else {
+            attrs.join(",")
+        }
+    }
+
+    fn into_highlight(self, col_start: usize, length: usize) -> AnsiHighlight {
+        let (ctermfg, guifg) = self
+            .fg
+            .map_or(("NONE".into(), "NONE".into()), |c| (c.cterm(), c.gui()));
+        let (ctermbg, guibg) = self
+            .bg
+            .map_or(("NONE".into(), "NONE".into()), |c| (c.cterm(), c.gui()));
+        let attr = self.attr();
+        let group_name = format!(
+            "ClapAnsi_{}_{}_{}",
+            guifg.trim_start_matches('#'),
+            guibg.trim_start_matches('#'),
+            attr.replace(',', "_")
+        );
+        AnsiHighlight {
+            group_name,
+            ctermfg,
+            ctermbg,
+            guifg,
+            guibg,
+            attr,
+            col_start,
+            length,
+        }
+    }
+}
+
+/// Highlight converted from the ANSI escape sequences, the highlight group named after
+/// the style is created on the Vim side on demand.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnsiHighlight {
+    pub group_name: String,
+    pub ctermfg: String,
+    pub ctermbg: String,
+    pub guifg: String,
+    pub guibg: String,
+    /// `:h attr-list`
+    pub attr: String,
+    /// Start of the highlight in bytes.
+    pub col_start: usize,
+    /// Length of the highlight in bytes.
+    pub length: usize,
+}
+
+/// Returns `true` if `line` contains any escape sequence.
+pub fn has_escape_sequence(line: &str) -> bool {
+    line.contains('\x1b')
+}
+
+/// Strips the escape sequences from `lines`, returns the plain text and the highlights of
+/// each line.
+///
+/// Only the SGR sequences are converted to the highlights, the others are dropped. The
+/// style that is not reset at the end of a line carries over to the next one like in a
+/// terminal, e.g., in the logs with the multi-line colored messages.
+pub fn parse_ansi_lines<'a>(
+    lines: impl IntoIterator<Item = &'a str>,
+) -> Vec<(String, Vec<AnsiHighlight>)> {
+    let mut style = Style::default();
+    lines
+        .into_iter()
+        .map(|line| parse_line_with_style(line, &mut style))
+        .collect()
+}
+
+fn parse_line_with_style(line: &str, style: &mut Style) -> (String, Vec<AnsiHighlight>) {
+    let mut text = String::with_capacity(line.len());
+    let mut highlights = Vec::new();
+
+    let mut start = 0;
+
+    let flush = |style: Style, start: usize, end: usize, highlights: &mut Vec<_>| {
+        if end > start && !style.is_default() {
+            highlights.push(style.into_highlight(start, end - start));
+        }
+    };
+
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // CSI
+                Some('[') => {
+                    let mut params = String::new();
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            if c == 'm' {
+                                let mut new_style = *style;
+                                new_style.apply_sgr(&params);
+                                if new_style != *style {
+                                    flush(*style, start, text.len(), &mut highlights);
+                                    *style = new_style;
+                                    start = text.len();
+                                }
+                            }
+                            break;
+                        }
+                        params.push(c);
+                    }
+                }
+                // OSC, e.g., the hyperlinks, terminated by BEL or ST.
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            c if c.is_control() && c != '\t' => {}
+            c => text.push(c),
+        }
+    }
+
+    flush(*style, start, text.len(), &mut highlights);
+
+    (text, highlights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_ansi_line(line: &str) -> (String, Vec<AnsiHighlight>) {
+        parse_ansi_lines([line]).pop().unwrap()
+    }
+
+    #[test]
+    fn test_parse_ansi_line() {
+        let line = "\x1b[1;33mcommit\x1b[0m abc \x1b[38;2;255;0;0;48;5;22m-x\x1b[39m+\x1b[m\r";
+        let (text, highlights) = parse_ansi_line(line);
+        assert_eq!(text, "commit abc -x+");
+
+        let spans = highlights
+            .iter()
+            .map(|hl| {
+                (
+                    hl.col_start,
+                    hl.length,
+                    hl.guifg.as_str(),
+                    hl.guibg.as_str(),
+                    hl.attr.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            vec![
+                (0, 6, "#808000", "NONE", "bold"),
+                (11, 2, "#ff0000", "#005f00", "NONE"),
+                (13, 1, "NONE", "#005f00", "NONE"),
+            ]
+        );
+        assert_eq!(highlights[0].group_name, "ClapAnsi_808000_NONE_bold");
+        assert_eq!(highlights[1].ctermbg, "22");
+
+        // The hyperlinks are stripped.
+        let (text, highlights) =
+            parse_ansi_line("\x1b]8;;file:///a.rs\x1b\\a.rs\x1b]8;;\x1b\\ \x1b[2Kend");
+        assert_eq!(text, "a.rs end");
+        assert!(highlights.is_empty());
+    }
+
+    #[test]
+    fn test_parse_ansi_lines() {
+        let lines = ["\x1b[31mERROR multi-line", "message\x1b[0m", "plain"];
+        assert!(lines.iter().any(|line| has_escape_sequence(line)));
+
+        let parsed = parse_ansi_lines(lines);
+        let spans = parsed
+            .iter()
+            .map(|(text, highlights)| {
+                let spans = highlights
+                    .iter()
+                    .map(|h| (h.col_start, h.length, h.guifg.as_str()))
+                    .collect::<Vec<_>>();
+                (text.as_str(), spans)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            vec![
+                ("ERROR multi-line", vec![(0, 16, "#800000")]),
+                ("message", vec![(0, 7, "#800000")]),
+                ("plain", vec![]),
+            ]
+        );
+    }
+}
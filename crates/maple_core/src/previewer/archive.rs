@@ -0,0 +1,538 @@
+//! Entries of the archive files, e.g., `.zip`, `.jar` and `.tar.gz`.
+//!
+//! Only the metadata of the archives is parsed, the zip archive is listed from the central
+//! directory at the end of the file and the gzipped tarball is decompressed as a stream,
+//! which stops once enough entries are listed.
+
+use flate2::read::{DeflateDecoder, GzDecoder};
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    Zip,
+    TarGz,
+}
+
+fn archive_kind(path: &Path) -> Option<ArchiveKind> {
+    let file_name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    if file_name.ends_with(".zip") || file_name.ends_with(".jar") {
+        Some(ArchiveKind::Zip)
+    } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+        Some(ArchiveKind::TarGz)
+    } else {
+        None
+    }
+}
+
+/// Returns `true` if `path` is an archive whose entries can be listed.
+pub fn is_archive(path: &Path) -> bool {
+    archive_kind(path).is_some()
+}
+
+/// Entries of an archive.
+#[derive(Debug)]
+pub struct ArchiveEntries {
+    /// Paths of the first entries.
+    pub entries: Vec<String>,
+    /// Total number of the entries, `None` if it's unknown as the archive is not read
+    /// through, i.e., there are more entries than `entries`.
+    pub total: Option<usize>,
+}
+
+/// Returns at most `max_entries` entries of the archive `path` and the total number.
+pub fn list_entries(path: &Path, max_entries: usize) -> Result<ArchiveEntries> {
+    match archive_kind(path) {
+        Some(ArchiveKind::Zip) => list_zip_entries(File::open(path)?, max_entries),
+        Some(ArchiveKind::TarGz) => {
+            list_tar_entries(GzDecoder::new(File::open(path)?), max_entries)
+        }
+        None => Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("{} is not a supported archive", path.display()),
+        )),
+    }
+}
+
+fn invalid_data(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+fn u16_at(buf: &[u8], offset: usize) -> usize {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]]) as usize
+}
+
+fn u32_at(buf: &[u8], offset: usize) -> u64 {
+    u32::from_le_bytes([
+        buf[offset],
+        buf[offset + 1],
+        buf[offset + 2],
+        buf[offset + 3],
+    ]) as u64
+}
+
+fn u64_at(buf: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&buf[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+const CENTRAL_HEADER_LEN: usize = 46;
+
+/// Value of the 32-bit fields which are stored in the zip64 extra field instead.
+const ZIP64_U32_MAX: u64 = 0xFFFF_FFFF;
+
+/// Value of the 16-bit entry count which is stored in the zip64 record instead.
+const ZIP64_U16_MAX: u64 = 0xFFFF;
+
+/// Entry in the central directory of a zip archive.
+struct ZipCentralHeader<'a> {
+    name: &'a [u8],
+    compression_method: usize,
+    compressed_size: u64,
+    local_header_offset: u64,
+}
+
+/// Returns the central directory of the zip archive and the total number of the entries.
+fn read_zip_central_directory<R: Read + Seek>(reader: &mut R) -> Result<(Vec<u8>, usize)> {
+    const EOCD_SIGNATURE: &[u8] = b"PK\x05\x06";
+    const EOCD_LEN: u64 = 22;
+
+    // The end of central directory record is followed by a comment of at most 64KiB.
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    let tail_len = file_len.min(EOCD_LEN + u16::MAX as u64);
+    reader.seek(SeekFrom::Start(file_len - tail_len))?;
+    let mut tail = Vec::with_capacity(tail_len as usize);
+    reader.by_ref().take(tail_len).read_to_end(&mut tail)?;
+
+    let eocd_pos = tail
+        .windows(EOCD_SIGNATURE.len())
+        .rposition(|w| w == EOCD_SIGNATURE)
+        .filter(|&pos| tail.len() - pos >= EOCD_LEN as usize)
+        .ok_or_else(|| invalid_data("end of central directory not found"))?;
+    let eocd = &tail[eocd_pos..];
+
+    let mut total = u16_at(eocd, 10) as u64;
+    let mut central_dir_size = u32_at(eocd, 12);
+    let mut central_dir_offset = u32_at(eocd, 16);
+
+    if total == ZIP64_U16_MAX
+        || central_dir_size == ZIP64_U32_MAX
+        || central_dir_offset == ZIP64_U32_MAX
+    {
+        let eocd_offset = file_len - tail_len + eocd_pos as u64;
+        (total, central_dir_size, central_dir_offset) = read_zip64_eocd(reader, eocd_offset)?;
+    }
+
+    // The size read from the disk can not be trusted.
+    let central_dir_size = central_dir_size.min(file_len.saturating_sub(central_dir_offset));
+    let total = usize::try_from(total).unwrap_or(usize::MAX);
+
+    reader.seek(SeekFrom::Start(central_dir_offset))?;
+    let mut central_dir = Vec::with_capacity(central_dir_size as usize);
+    reader
+        .take(central_dir_size)
+        .read_to_end(&mut central_dir)?;
+
+    Ok((central_dir, total))
+}
+
+/// Returns the total number of the entries, the size and the offset of the central
+/// directory in the zip64 end of central directory record, which is located by the
+/// locator right before the end of central directory record at `eocd_offset`.
+fn read_zip64_eocd<R: Read + Seek>(reader: &mut R, eocd_offset: u64) -> Result<(u64, u64, u64)> {
+    const LOCATOR_SIGNATURE: &[u8] = b"PK\x06\x07";
+    const LOCATOR_LEN: u64 = 20;
+    const RECORD_SIGNATURE: &[u8] = b"PK\x06\x06";
+    const RECORD_LEN: usize = 56;
+
+    let locator_offset = eocd_offset
+        .checked_sub(LOCATOR_LEN)
+        .ok_or_else(|| invalid_data("zip64 end of central directory locator not found"))?;
+    let mut locator = [0u8; LOCATOR_LEN as usize];
+    reader.seek(SeekFrom::Start(locator_offset))?;
+    reader.read_exact(&mut locator)?;
+    if !locator.starts_with(LOCATOR_SIGNATURE) {
+        return Err(invalid_data(
+            "zip64 end of central directory locator not found",
+        ));
+    }
+
+    let mut record = [0u8; RECORD_LEN];
+    reader.seek(SeekFrom::Start(u64_at(&locator, 8)))?;
+    reader.read_exact(&mut record)?;
+    if !record.starts_with(RECORD_SIGNATURE) {
+        return Err(invalid_data("corrupted zip64 end of central directory"));
+    }
+
+    Ok((
+        u64_at(&record, 32),
+        u64_at(&record, 40),
+        u64_at(&record, 48),
+    ))
+}
+
+/// Returns the data of the zip64 extended information extra field in `extra`.
+fn zip64_extra_field(mut extra: &[u8]) -> Option<&[u8]> {
+    const ZIP64_EXTRA_ID: usize = 0x0001;
+
+    while extra.len() >= 4 {
+        let id = u16_at(extra, 0);
+        let len = u16_at(extra, 2);
+        let data = extra.get(4..4 + len)?;
+        if id == ZIP64_EXTRA_ID {
+            return Some(data);
+        }
+        extra = &extra[4 + len..];
+    }
+
+    None
+}
+
+/// Parses the central directory header at the start of `header`, returns the header
+/// and its length.
+fn parse_zip_central_header(header: &[u8]) -> Result<(ZipCentralHeader<'_>, usize)> {
+    const CENTRAL_HEADER_SIGNATURE: &[u8] = b"PK\x01\x02";
+
+    if header.len() < CENTRAL_HEADER_LEN || !header.starts_with(CENTRAL_HEADER_SIGNATURE) {
+        return Err(invalid_data("corrupted central directory"));
+    }
+    let name_len = u16_at(header, 28);
+    let extra_len = u16_at(header, 30);
+    let comment_len = u16_at(header, 32);
+    let name = header
+        .get(CENTRAL_HEADER_LEN..CENTRAL_HEADER_LEN + name_len)
+        .ok_or_else(|| invalid_data("truncated central directory"))?;
+
+    let mut compressed_size = u32_at(header, 20);
+    let mut local_header_offset = u32_at(header, 42);
+
+    // The overflowed fields are stored in the zip64 extra field in a fixed order.
+    let uncompressed_size = u32_at(header, 24);
+    if [uncompressed_size, compressed_size, local_header_offset].contains(&ZIP64_U32_MAX) {
+        let extra_start = CENTRAL_HEADER_LEN + name_len;
+        let mut fields = header
+            .get(extra_start..extra_start + extra_len)
+            .and_then(zip64_extra_field)
+            .ok_or_else(|| invalid_data("zip64 extra field not found"))?
+            .chunks_exact(8)
+            .map(|field| u64_at(field, 0));
+        let mut next_field = || {
+            fields
+                .next()
+                .ok_or_else(|| invalid_data("truncated zip64 extra field"))
+        };
+        if uncompressed_size == ZIP64_U32_MAX {
+            next_field()?;
+        }
+        if compressed_size == ZIP64_U32_MAX {
+            compressed_size = next_field()?;
+        }
+        if local_header_offset == ZIP64_U32_MAX {
+            local_header_offset = next_field()?;
+        }
+    }
+
+    Ok((
+        ZipCentralHeader {
+            name,
+            compression_method: u16_at(header, 10),
+            compressed_size,
+            local_header_offset,
+        },
+        CENTRAL_HEADER_LEN + name_len + extra_len + comment_len,
+    ))
+}
+
+fn list_zip_entries<R: Read + Seek>(mut reader: R, max_entries: usize) -> Result<ArchiveEntries> {
+    let (central_dir, total) = read_zip_central_directory(&mut reader)?;
+
+    let max_entries = total.min(max_entries);
+    let mut entries = Vec::with_capacity(max_entries);
+    let mut pos = 0;
+    while entries.len() < max_entries && pos + CENTRAL_HEADER_LEN <= central_dir.len() {
+        let (header, header_len) = parse_zip_central_header(&central_dir[pos..])?;
+        entries.push(String::from_utf8_lossy(header.name).into_owned());
+        pos += header_len;
+    }
+
+    Ok(ArchiveEntries {
+        entries,
+        total: Some(total),
+    })
+}
+
+/// Returns at most `max_len` bytes of the decompressed entry `name` in the zip archive.
+pub fn read_zip_entry(path: &Path, name: &str, max_len: u64) -> Result<Vec<u8>> {
+    read_zip_entry_from(File::open(path)?, name, max_len)
+}
+
+fn read_zip_entry_from<R: Read + Seek>(mut reader: R, name: &str, max_len: u64) -> Result<Vec<u8>> {
+    const LOCAL_HEADER_LEN: u64 = 30;
+    const STORED: usize = 0;
+    const DEFLATED: usize = 8;
+
+    let (central_dir, _total) = read_zip_central_directory(&mut reader)?;
+
+    let mut pos = 0;
+    let header = loop {
+        if pos + CENTRAL_HEADER_LEN > central_dir.len() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("{name} not found in the zip archive"),
+            ));
+        }
+        let (header, header_len) = parse_zip_central_header(&central_dir[pos..])?;
+        if header.name == name.as_bytes() {
+            break header;
+        }
+        pos += header_len;
+    };
+
+    // The lengths of the name and extra field in the local header may differ from
+    // those in the central directory.
+    let mut local_header = [0u8; LOCAL_HEADER_LEN as usize];
+    reader.seek(SeekFrom::Start(header.local_header_offset))?;
+    reader.read_exact(&mut local_header)?;
+    if !local_header.starts_with(b"PK\x03\x04") {
+        return Err(invalid_data("corrupted local file header"));
+    }
+    let data_offset = header.local_header_offset
+        + LOCAL_HEADER_LEN
+        + (u16_at(&local_header, 26) + u16_at(&local_header, 28)) as u64;
+    reader.seek(SeekFrom::Start(data_offset))?;
+
+    let compressed = reader.take(header.compressed_size);
+    let mut data = Vec::new();
+    match header.compression_method {
+        STORED => compressed.take(max_len).read_to_end(&mut data)?,
+        DEFLATED => DeflateDecoder::new(compressed)
+            .take(max_len)
+            .read_to_end(&mut data)?,
+        method => {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("unsupported compression method {method}"),
+            ))
+        }
+    };
+
+    Ok(data)
+}
+
+fn list_tar_entries<R: Read>(mut reader: R, max_entries: usize) -> Result<ArchiveEntries> {
+    const BLOCK_LEN: u64 = 512;
+
+    let mut entries = Vec::new();
+    let mut long_name = None;
+    let mut header = [0u8; BLOCK_LEN as usize];
+
+    loop {
+        if let Err(e) = reader.read_exact(&mut header) {
+            // Some archivers omit the trailing zero blocks.
+            if e.kind() == ErrorKind::UnexpectedEof {
+                break;
+            }
+            return Err(e);
+        }
+
+        // End of archive.
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+
+        let size = parse_octal(&header[124..136])
+            .ok_or_else(|| invalid_data("invalid size in tar header"))?;
+        let data_len = size.div_ceil(BLOCK_LEN) * BLOCK_LEN;
+
+        match header[156] {
+            // GNU long name of the next entry.
+            b'L' => {
+                let mut name = Vec::with_capacity(size as usize);
+                reader.by_ref().take(size).read_to_end(&mut name)?;
+                skip(&mut reader, data_len - size)?;
+                let name = name.split(|&b| b == 0).next().unwrap_or_default();
+                long_name.replace(String::from_utf8_lossy(name).into_owned());
+                continue;
+            }
+            // PAX extended headers.
+            b'x' | b'g' => {
+                skip(&mut reader, data_len)?;
+                continue;
+            }
+            _ => {}
+        }
+
+        // Only whether there are more entries is checked after `max_entries`.
+        if entries.len() == max_entries {
+            return Ok(ArchiveEntries {
+                entries,
+                total: None,
+            });
+        }
+
+        let name = long_name.take().unwrap_or_else(|| {
+            let name = c_str(&header[0..100]);
+            // The path is split into the prefix and name in the ustar format.
+            match c_str(&header[345..500]) {
+                prefix if header[257..262] == *b"ustar" && !prefix.is_empty() => {
+                    format!("{prefix}/{name}")
+                }
+                _ => name,
+            }
+        });
+        entries.push(name);
+
+        skip(&mut reader, data_len)?;
+    }
+
+    let total = Some(entries.len());
+    Ok(ArchiveEntries { entries, total })
+}
+
+fn c_str(bytes: &[u8]) -> String {
+    let bytes = bytes.split(|&b| b == 0).next().unwrap_or_default();
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn parse_octal(bytes: &[u8]) -> Option<u64> {
+    let s = std::str::from_utf8(bytes).ok()?;
+    let s = s.trim_matches(|c: char| c == '\0' || c == ' ');
+    if s.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(s, 8).ok()
+}
+
+fn skip<R: Read>(reader: &mut R, len: u64) -> Result<()> {
+    let skipped = std::io::copy(&mut reader.by_ref().take(len), &mut std::io::sink())?;
+    if skipped < len {
+        return Err(invalid_data("truncated tar archive"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn tar_header(name: &str, size: usize, typeflag: u8) -> Vec<u8> {
+        let mut header = vec![0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        let size = format!("{size:011o}\0");
+        header[124..136].copy_from_slice(size.as_bytes());
+        header[156] = typeflag;
+        header[257..262].copy_from_slice(b"ustar");
+        header
+    }
+
+    #[test]
+    fn test_list_tar_entries() {
+        let mut tar = tar_header("src/", 0, b'5');
+        tar.extend(tar_header("src/lib.rs", 600, b'0'));
+        tar.extend(vec![b'x'; 1024]);
+        tar.extend(tar_header("README.md", 0, b'0'));
+        tar.extend(vec![0u8; 1024]);
+
+        let ArchiveEntries { entries, total } = list_tar_entries(Cursor::new(&tar), 2).unwrap();
+        assert_eq!(entries, ["src/", "src/lib.rs"]);
+        assert_eq!(total, None);
+
+        let ArchiveEntries { entries, total } = list_tar_entries(Cursor::new(&tar), 3).unwrap();
+        assert_eq!(entries, ["src/", "src/lib.rs", "README.md"]);
+        assert_eq!(total, Some(3));
+    }
+
+    #[test]
+    fn test_list_zip_entries() {
+        let central_header = |name: &str| {
+            let mut header = b"PK\x01\x02".to_vec();
+            header.extend([0u8; 24]);
+            header.extend((name.len() as u16).to_le_bytes());
+            header.extend([0u8; 16]);
+            header.extend(name.as_bytes());
+            header
+        };
+
+        let mut zip = b"local file headers".to_vec();
+        let central_dir_offset = zip.len() as u32;
+        let central_dir = [central_header("META-INF/"), central_header("Main.class")].concat();
+        zip.extend(&central_dir);
+        zip.extend(b"PK\x05\x06");
+        zip.extend([0u8; 4]);
+        zip.extend(2u16.to_le_bytes());
+        zip.extend(2u16.to_le_bytes());
+        zip.extend((central_dir.len() as u32).to_le_bytes());
+        zip.extend(central_dir_offset.to_le_bytes());
+        zip.extend(0u16.to_le_bytes());
+
+        let ArchiveEntries { entries, total } = list_zip_entries(Cursor::new(&zip), 10).unwrap();
+        assert_eq!(entries, ["META-INF/", "Main.class"]);
+        assert_eq!(total, Some(2));
+        assert_eq!(
+            read_zip_entry_from(Cursor::new(&zip), "Other.class", 10)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::NotFound
+        );
+
+        assert!(is_archive(Path::new("foo.tar.gz")));
+        assert!(is_archive(Path::new("foo.JAR")));
+        assert!(!is_archive(Path::new("foo.gz")));
+    }
+
+    #[test]
+    fn test_zip64() {
+        let name = b"a.txt";
+
+        let mut zip = b"PK\x03\x04".to_vec();
+        zip.extend([0u8; 22]);
+        zip.extend((name.len() as u16).to_le_bytes());
+        zip.extend(0u16.to_le_bytes());
+        zip.extend(name);
+        zip.extend(b"hello");
+
+        let central_dir_offset = zip.len() as u64;
+        let mut header = vec![0u8; CENTRAL_HEADER_LEN];
+        header[..4].copy_from_slice(b"PK\x01\x02");
+        header[20..28].fill(0xFF);
+        header[28..30].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        header[30..32].copy_from_slice(&28u16.to_le_bytes());
+        header[42..46].fill(0xFF);
+        header.extend(name);
+        header.extend(1u16.to_le_bytes());
+        header.extend(24u16.to_le_bytes());
+        header.extend(5u64.to_le_bytes());
+        header.extend(5u64.to_le_bytes());
+        header.extend(0u64.to_le_bytes());
+        zip.extend(&header);
+
+        let record_offset = zip.len() as u64;
+        let mut record = vec![0u8; 56];
+        record[..4].copy_from_slice(b"PK\x06\x06");
+        record[32..40].copy_from_slice(&1u64.to_le_bytes());
+        record[40..48].copy_from_slice(&(header.len() as u64).to_le_bytes());
+        record[48..56].copy_from_slice(&central_dir_offset.to_le_bytes());
+        zip.extend(record);
+
+        zip.extend(b"PK\x06\x07");
+        zip.extend([0u8; 4]);
+        zip.extend(record_offset.to_le_bytes());
+        zip.extend(1u32.to_le_bytes());
+
+        zip.extend(b"PK\x05\x06");
+        zip.extend([0u8; 4]);
+        zip.extend([0xFF; 12]);
+        zip.extend(0u16.to_le_bytes());
+
+        let ArchiveEntries { entries, total } = list_zip_entries(Cursor::new(&zip), 10).unwrap();
+        assert_eq!(entries, ["a.txt"]);
+        assert_eq!(total, Some(1));
+        assert_eq!(
+            read_zip_entry_from(Cursor::new(&zip), "a.txt", 10).unwrap(),
+            b"hello"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Output of the arbitrary shell commands for the preview, e.g., `systemctl status <unit>`.
+
+use std::io::Result;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+
+/// Placeholder of the item in the command template.
+const ITEM_PLACEHOLDER: &str = "{}";
+
+/// Output of a preview command.
+#[derive(Debug, Default)]
+pub struct CommandOutput {
+    /// Stdout of the command, stderr is used instead if stdout is empty.
+    pub output: Vec<u8>,
+    /// Whether the output exceeds the size limit.
+    pub truncated: bool,
+    /// Whether the command is killed due to the timeout.
+    pub timed_out: bool,
+}
+
+/// Returns the command for previewing `item` by substituting the `{}` in `template`,
+/// the item is passed as a single shell word.
+pub fn render_template(template: &str, item: &str) -> String {
+    let quoted = if cfg!(target_os = "windows") {
+        format!("\"{}\"", item.replace('"', "\"\""))
+    } else {
+        format!("'{}'", item.replace('\'', r"'\''"))
+    };
+    template.replace(ITEM_PLACEHOLDER, &quoted)
+}
+
+/// Runs the shell `command` in `cwd`, the command is killed once `timeout` elapses or
+/// the output exceeds `max_output` bytes.
+pub async fn run(
+    command: &str,
+    cwd: &Path,
+    timeout: Duration,
+    max_output: usize,
+) -> Result<CommandOutput> {
+    let mut child = crate::process::tokio::shell_command(command)
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let mut stdout = child.stdout.take().expect("stdout is piped; qed");
+    let mut stderr = child.stderr.take().expect("stderr is piped; qed");
+
+    // Read one more byte to tell whether the output is truncated.
+    let limit = max_output as u64 + 1;
+    let mut stdout_buf = Vec::new();
+    let mut stderr_buf = Vec::new();
+
+    let read_stdout = async {
+        let n = (&mut stdout)
+            .take(limit)
+            .read_to_end(&mut stdout_buf)
+            .await?;
+        // Stop the command which is still writing once the output exceeds the limit.
+        if n as u64 == limit {
+            let _ = child.start_kill();
+        }
+        Ok::<_, std::io::Error>(())
+    };
+
+    // Stderr is drained along with stdout, otherwise the command can be blocked forever
+    // on the full stderr pipe.
+    let read_stderr = async {
+        (&mut stderr)
+            .take(limit)
+            .read_to_end(&mut stderr_buf)
+            .await?;
+        tokio::io::copy(&mut stderr, &mut tokio::io::sink()).await?;
+        Ok::<_, std::io::Error>(())
+    };
+
+    let read_output = async {
+        tokio::try_join!(read_stdout, read_stderr)?;
+        Ok::<_, std::io::Error>(())
+    };
+
+    let timed_out = match tokio::time::timeout(timeout, read_output).await {
+        Ok(read) => {
+            read?;
+            false
+        }
+        Err(_) => true,
+    };
+
+    // The command may be still running if the output is truncated.
+    let _ = child.start_kill();
+
+    let mut output = if stdout_buf.is_empty() {
+        stderr_buf
+    } else {
+        stdout_buf
+    };
+    let truncated = output.len() > max_output;
+    output.truncate(max_output);
+
+    Ok(CommandOutput {
+        output,
+        truncated,
+        timed_out,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_render_template() {
+        assert_eq!(
+            render_template("systemctl status {}", "ssh.service"),
+            "systemctl status 'ssh.service'"
+        );
+        assert_eq!(
+            render_template("echo {} {}", "it's"),
+            r"echo 'it'\''s' 'it'\''s'"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_command() {
+        let cwd = std::env::current_dir().unwrap();
+        let timeout = Duration::from_secs(5);
+
+        let output = run("echo hello", &cwd, timeout, 1024).await.unwrap();
+        assert_eq!(output.output, b"hello\n");
+        assert!(!output.truncated && !output.timed_out);
+
+        let output = run("echo oops >&2", &cwd, timeout, 1024).await.unwrap();
+        assert_eq!(output.output, b"oops\n");
+
+        let output = run("yes", &cwd, timeout, 8).await.unwrap();
+        assert_eq!(output.output, b"y\ny\ny\ny\n");
+        assert!(output.truncated);
+
+        let output = run("sleep 5", &cwd, Duration::from_millis(50), 1024)
+            .await
+            .unwrap();
+        assert!(output.timed_out);
+
+        // Stderr exceeding the pipe capacity must not block the command.
+        let output = run(
+            "head -c 200000 /dev/zero >&2; echo done",
+            &cwd,
+            timeout,
+            1024,
+        )
+        .await
+        .unwrap();
+        assert_eq!(output.output, b"done\n");
+        assert!(!output.timed_out);
+    }
+}
@@ -0,0 +1,100 @@
+//! Aligns the columns of the CSV/TSV files for previewing.
+
+use std::path::Path;
+use unicode_width::UnicodeWidthStr;
+
+/// Columns wider than this do not widen the others further.
+const MAX_COLUMN_WIDTH: usize = 40;
+
+/// Separator between the aligned columns.
+const COLUMN_SEPARATOR: &str = "  ";
+
+/// Returns the field delimiter if `path` is a CSV/TSV file.
+pub fn delimiter(path: &Path) -> Option<char> {
+    match path.extension()?.to_str()? {
+        "csv" => Some(','),
+        "tsv" => Some('\t'),
+        _ => None,
+    }
+}
+
+/// Splits the row into fields, the delimiters in the double quoted fields are kept.
+fn split_fields(row: &str, delimiter: char) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (idx, c) in row.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == delimiter && !in_quotes {
+            fields.push(row[start..idx].trim());
+            start = idx + c.len_utf8();
+        }
+    }
+    fields.push(row[start..].trim());
+    fields
+}
+
+/// Aligns the columns of `rows`, the column widths are computed over these rows only.
+pub fn align_rows(rows: &[String], delimiter: char) -> Vec<String> {
+    let rows = rows
+        .iter()
+        .map(|row| split_fields(row, delimiter))
+        .collect::<Vec<_>>();
+
+    let mut widths = Vec::new();
+    for fields in &rows {
+        for (idx, field) in fields.iter().enumerate() {
+            let width = field.width().min(MAX_COLUMN_WIDTH);
+            match widths.get_mut(idx) {
+                Some(max_width) => *max_width = width.max(*max_width),
+                None => widths.push(width),
+            }
+        }
+    }
+
+    rows.into_iter()
+        .map(|fields| {
+            let last = fields.len() - 1;
+            let mut row = String::new();
+            for (idx, field) in fields.into_iter().enumerate() {
+                row.push_str(field);
+                if idx != last {
+                    let padding = widths[idx].saturating_sub(field.width());
+                    row.extend(std::iter::repeat(' ').take(padding));
+                    row.push_str(COLUMN_SEPARATOR);
+                }
+            }
+            row
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_align_rows() {
+        assert_eq!(delimiter(Path::new("data.csv")), Some(','));
+        assert_eq!(delimiter(Path::new("data.tsv")), Some('\t'));
+        assert_eq!(delimiter(Path::new("data.txt")), None);
+
+        let rows = [
+            "name,age,city".to_string(),
+            "Alice,30,\"Paris, France\"".to_string(),
+            "李雷,7".to_string(),
+        ];
+        assert_eq!(
+            align_rows(&rows, ','),
+            vec![
+                "name   age  city",
+                "Alice  30   \"Paris, France\"",
+                "李雷   7",
+            ]
+        );
+
+        let rows = ["a\tbb".to_string(), "ccc\td".to_string()];
+        assert_eq!(align_rows(&rows, '\t'), vec!["a    bb", "ccc  d"]);
+    }
+}
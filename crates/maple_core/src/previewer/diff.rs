@@ -0,0 +1,551 @@
+//! Line-based unified diff, e.g., between the file on disk and the modified buffer,
+//! and the word-level highlights of the changed lines in a diff.
+
+use std::ops::{Index, IndexMut, Range};
+use std::time::{Duration, Instant};
+
+/// Number of the unchanged lines around the changes in a hunk.
+const CONTEXT_LINES: usize = 3;
+
+/// Maximum number of the differing lines, beyond which they are simply replaced as a
+/// whole instead of finding the shortest edit script.
+const MAX_DIFF_LINES: usize = 20_000;
+
+/// Time limit of finding the shortest edit script, a less minimal one is returned once
+/// it's exceeded.
+const DIFF_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Furthest reaching x of each diagonal `k` in the search, indexed by `k` from `-max_d`
+/// to `max_d`.
+struct V {
+    offset: isize,
+    v: Vec<usize>,
+}
+
+impl V {
+    fn new(max_d: usize) -> Self {
+        Self {
+            offset: max_d as isize,
+            v: vec![0; 2 * max_d + 1],
+        }
+    }
+}
+
+impl Index<isize> for V {
+    type Output = usize;
+
+    fn index(&self, k: isize) -> &usize {
+        &self.v[(k + self.offset) as usize]
+    }
+}
+
+impl IndexMut<isize> for V {
+    fn index_mut(&mut self, k: isize) -> &mut usize {
+        &mut self.v[(k + self.offset) as usize]
+    }
+}
+
+/// Upper bound of the edit distance searched from both ends of `n` and `m` items.
+fn max_d(n: usize, m: usize) -> usize {
+    (n + m + 1) / 2 + 1
+}
+
+fn common_prefix_len<T: PartialEq>(old: &[T], new: &[T]) -> usize {
+    old.iter().zip(new).take_while(|(a, b)| a == b).count()
+}
+
+fn common_suffix_len<T: PartialEq>(old: &[T], new: &[T]) -> usize {
+    old.iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+/// Searches the forward and backward paths simultaneously, returns the start `(x, y)`
+/// of the middle snake splitting the shortest edit script in halves, `None` if it's not
+/// found before `deadline`.
+fn find_middle_snake<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    vf: &mut V,
+    vb: &mut V,
+    deadline: Instant,
+) -> Option<(usize, usize)> {
+    let n = old.len();
+    let m = new.len();
+    let delta = n as isize - m as isize;
+    let odd = delta & 1 == 1;
+
+    vf[1] = 0;
+    vb[1] = 0;
+
+    for d in 0..max_d(n, m) as isize {
+        if Instant::now() > deadline {
+            return None;
+        }
+
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && vf[k - 1] < vf[k + 1]) {
+                vf[k + 1]
+            } else {
+                vf[k - 1] + 1
+            };
+            let y = (x as isize - k) as usize;
+            let (x0, y0) = (x, y);
+            if x < n && y < m {
+                x += common_prefix_len(&old[x..], &new[y..]);
+            }
+            vf[k] = x;
+            if odd && (k - delta).abs() < d && vf[k] + vb[-(k - delta)] >= n {
+                return Some((x0, y0));
+            }
+        }
+
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && vb[k - 1] < vb[k + 1]) {
+                vb[k + 1]
+            } else {
+                vb[k - 1] + 1
+            };
+            let mut y = (x as isize - k) as usize;
+            if x < n && y < m {
+                let advance = common_suffix_len(&old[..n - x], &new[..m - y]);
+                x += advance;
+                y += advance;
+            }
+            vb[k] = x;
+            if !odd && (k - delta).abs() <= d && vb[k] + vf[-(k - delta)] >= n {
+                return Some((n - x, m - y));
+            }
+        }
+    }
+
+    None
+}
+
+/// State of the linear-space Myers' algorithm, the edit script is appended to `edits`.
+struct Myers<'a, T> {
+    old: &'a [T],
+    new: &'a [T],
+    vf: V,
+    vb: V,
+    deadline: Instant,
+    edits: Vec<Edit>,
+}
+
+impl<T: PartialEq> Myers<'_, T> {
+    fn push(&mut self, edit: Edit, count: usize) {
+        self.edits.extend(std::iter::repeat(edit).take(count));
+    }
+
+    /// Appends the edit script from `old[old_range]` to `new[new_range]` by splitting the
+    /// ranges at the middle snakes recursively.
+    fn conquer(&mut self, mut old_range: Range<usize>, mut new_range: Range<usize>) {
+        let prefix = common_prefix_len(&self.old[old_range.clone()], &self.new[new_range.clone()]);
+        self.push(Edit::Equal, prefix);
+        old_range.start += prefix;
+        new_range.start += prefix;
+
+        let suffix = common_suffix_len(&self.old[old_range.clone()], &self.new[new_range.clone()]);
+        old_range.end -= suffix;
+        new_range.end -= suffix;
+
+        let middle_snake = if old_range.is_empty() || new_range.is_empty() {
+            None
+        } else {
+            find_middle_snake(
+                &self.old[old_range.clone()],
+                &self.new[new_range.clone()],
+                &mut self.vf,
+                &mut self.vb,
+                self.deadline,
+            )
+        };
+
+        match middle_snake {
+            Some((x, y)) => {
+                let (old_mid, new_mid) = (old_range.start + x, new_range.start + y);
+                self.conquer(old_range.start..old_mid, new_range.start..new_mid);
+                self.conquer(old_mid..old_range.end, new_mid..new_range.end);
+            }
+            None => {
+                // Either range is empty or the search timed out, the rest is replaced
+                // as a whole.
+                self.push(Edit::Delete, old_range.len());
+                self.push(Edit::Insert, new_range.len());
+            }
+        }
+
+        self.push(Edit::Equal, suffix);
+    }
+}
+
+/// Returns the edit script from `old` to `new` using the linear-space variant of the
+/// Myers' algorithm.
+fn myers_diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    if old.len() + new.len() > MAX_DIFF_LINES {
+        return std::iter::repeat(Edit::Delete)
+            .take(old.len())
+            .chain(std::iter::repeat(Edit::Insert).take(new.len()))
+            .collect();
+    }
+
+    let max_d = max_d(old.len(), new.len());
+    let mut myers = Myers {
+        old,
+        new,
+        vf: V::new(max_d),
+        vb: V::new(max_d),
+        deadline: Instant::now() + DIFF_TIMEOUT,
+        edits: Vec::with_capacity(old.len() + new.len()),
+    };
+    myers.conquer(0..old.len(), 0..new.len());
+    let mut edits = myers.edits;
+
+    // The deletions go before the insertions in each run of the changes, as in `diff`.
+    for run in edits.split_mut(|edit| *edit == Edit::Equal) {
+        let deleted = run.iter().filter(|edit| **edit == Edit::Delete).count();
+        run[..deleted].fill(Edit::Delete);
+        run[deleted..].fill(Edit::Insert);
+    }
+
+    edits
+}
+
+/// Returns the edit script of the lines, the common prefix and suffix are excluded
+/// from the search of the shortest edit script.
+fn diff_lines<T: AsRef<str>, U: AsRef<str>>(old: &[T], new: &[U]) -> Vec<Edit> {
+    let old = old.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+    let new = new.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut edits = vec![Edit::Equal; prefix];
+    edits.extend(myers_diff(
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    ));
+    edits.extend(std::iter::repeat(Edit::Equal).take(suffix));
+    edits
+}
+
+/// Returns the hunks of the unified diff from `old` to `new` without the file headers,
+/// empty if they are identical.
+pub fn unified_diff<T: AsRef<str>, U: AsRef<str>>(old: &[T], new: &[U]) -> Vec<String> {
+    let edits = diff_lines(old, new);
+
+    // (edit, index in old, index in new) of each line.
+    let mut ops = Vec::with_capacity(edits.len());
+    let (mut old_idx, mut new_idx) = (0, 0);
+    for edit in edits {
+        ops.push((edit, old_idx, new_idx));
+        match edit {
+            Edit::Equal => {
+                old_idx += 1;
+                new_idx += 1;
+            }
+            Edit::Delete => old_idx += 1,
+            Edit::Insert => new_idx += 1,
+        }
+    }
+
+    // Ranges of the ops in each hunk, the nearby changes are merged into one hunk.
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (idx, _) in ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (edit, _, _))| *edit != Edit::Equal)
+    {
+        let start = idx.saturating_sub(CONTEXT_LINES);
+        let end = (idx + 1 + CONTEXT_LINES).min(ops.len());
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let range = |start: usize, len: usize| {
+        if len == 0 {
+            format!("{start},0")
+        } else {
+            format!("{},{len}", start + 1)
+        }
+    };
+
+    let mut lines = Vec::new();
+    for (start, end) in hunks {
+        let hunk_ops = &ops[start..end];
+        let old_len = hunk_ops.iter().filter(|op| op.0 != Edit::Insert).count();
+        let new_len = hunk_ops.iter().filter(|op| op.0 != Edit::Delete).count();
+        let (_, old_start, new_start) = hunk_ops[0];
+        lines.push(format!(
+            "@@ -{} +{} @@",
+            range(old_start, old_len),
+            range(new_start, new_len)
+        ));
+        lines.extend(hunk_ops.iter().map(|&(edit, old_idx, new_idx)| match edit {
+            Edit::Equal => format!(" {}", old[old_idx].as_ref()),
+            Edit::Delete => format!("-{}", old[old_idx].as_ref()),
+            Edit::Insert => format!("+{}", new[new_idx].as_ref()),
+        }));
+    }
+
+    lines
+}
+
+/// Highlight of the removed words in the paired `-` line.
+const WORD_DELETE_GROUP: &str = "ClapDiffWordDelete";
+
+/// Highlight of the added words in the paired `+` line.
+const WORD_ADD_GROUP: &str = "ClapDiffWordAdd";
+
+/// Maximum ratio of the changed bytes in a line pair, beyond which the line is considered
+/// rewritten and no words are highlighted.
+const MAX_CHANGED_RATIO: f32 = 0.6;
+
+/// Byte spans `(start, length, group)` of the highlights on a line.
+type WordHighlights = Vec<(usize, usize, &'static str)>;
+
+/// Splits `s` into the words, the runs of whitespaces and the single other chars.
+fn tokenize(s: &str) -> Vec<&str> {
+    #[derive(PartialEq)]
+    enum Class {
+        Word,
+        Space,
+        Other,
+    }
+
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            Class::Word
+        } else if c.is_whitespace() {
+            Class::Space
+        } else {
+            Class::Other
+        }
+    };
+
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut chars = s.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        let is_boundary = match chars.peek() {
+            Some(&(_, next)) => class(c) == Class::Other || class(c) != class(next),
+            None => true,
+        };
+        if is_boundary {
+            let end = idx + c.len_utf8();
+            tokens.push(&s[start..end]);
+            start = end;
+        }
+    }
+    tokens
+}
+
+/// Returns the highlights of the changed words in the removed line `old` and the added
+/// line `new`, `None` if they are too different to be compared word by word.
+fn word_diff(old: &str, new: &str) -> Option<(WordHighlights, WordHighlights)> {
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+
+    let mut old_highlights: WordHighlights = Vec::new();
+    let mut new_highlights: WordHighlights = Vec::new();
+
+    // Adjacent changed tokens are merged into one span.
+    let push = |highlights: &mut WordHighlights, start: usize, len: usize, group| match highlights
+        .last_mut()
+    {
+        Some((last_start, last_len, _)) if *last_start + *last_len == start => *last_len += len,
+        _ => highlights.push((start, len, group)),
+    };
+
+    let (mut old_offset, mut new_offset) = (0, 0);
+    let (mut old_idx, mut new_idx) = (0, 0);
+    for edit in diff_lines(&old_tokens, &new_tokens) {
+        match edit {
+            Edit::Equal => {
+                old_offset += old_tokens[old_idx].len();
+                new_offset += new_tokens[new_idx].len();
+                old_idx += 1;
+                new_idx += 1;
+            }
+            Edit::Delete => {
+                let len = old_tokens[old_idx].len();
+                push(&mut old_highlights, old_offset, len, WORD_DELETE_GROUP);
+                old_offset += len;
+                old_idx += 1;
+            }
+            Edit::Insert => {
+                let len = new_tokens[new_idx].len();
+                push(&mut new_highlights, new_offset, len, WORD_ADD_GROUP);
+                new_offset += len;
+                new_idx += 1;
+            }
+        }
+    }
+
+    let changed_ratio = |highlights: &WordHighlights, line: &str| {
+        highlights.iter().map(|(_, len, _)| len).sum::<usize>() as f32 / line.len().max(1) as f32
+    };
+
+    if changed_ratio(&old_highlights, old) > MAX_CHANGED_RATIO
+        || changed_ratio(&new_highlights, new) > MAX_CHANGED_RATIO
+    {
+        return None;
+    }
+
+    Some((old_highlights, new_highlights))
+}
+
+/// Returns the word-level highlights `(line_idx, spans)` of the unified diff `lines`, e.g.,
+/// the output of `git show`.
+///
+/// The removed lines are paired with the added lines right after them in order, the
+/// changed words of each pair are highlighted.
+pub fn word_diff_highlights(lines: &[String]) -> Vec<(usize, WordHighlights)> {
+    let mut highlights = Vec::new();
+
+    let mut in_hunk = false;
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+
+    let mut flush = |removed: &mut Vec<usize>, added: &mut Vec<usize>| {
+        for (&old_idx, &new_idx) in removed.iter().zip(added.iter()) {
+            // Skip the leading `-` and `+`.
+            if let Some((old_spans, new_spans)) =
+                word_diff(&lines[old_idx][1..], &lines[new_idx][1..])
+            {
+                let shift = |spans: WordHighlights| {
+                    spans
+                        .into_iter()
+                        .map(|(start, len, group)| (start + 1, len, group))
+                        .collect::<Vec<_>>()
+                };
+                if !old_spans.is_empty() {
+                    highlights.push((old_idx, shift(old_spans)));
+                }
+                if !new_spans.is_empty() {
+                    highlights.push((new_idx, shift(new_spans)));
+                }
+            }
+        }
+        removed.clear();
+        added.clear();
+    };
+
+    for (idx, line) in lines.iter().enumerate() {
+        if line.starts_with("@@") {
+            flush(&mut removed, &mut added);
+            in_hunk = true;
+        } else if !in_hunk {
+            continue;
+        } else if line.starts_with('-') {
+            // A removed line after the added lines starts a new group.
+            if !added.is_empty() {
+                flush(&mut removed, &mut added);
+            }
+            removed.push(idx);
+        } else if line.starts_with('+') {
+            added.push(idx);
+        } else {
+            flush(&mut removed, &mut added);
+            // The next file starts with `diff --git`.
+            in_hunk = line.starts_with(' ') || line.is_empty() || line.starts_with('\\');
+        }
+    }
+    flush(&mut removed, &mut added);
+
+    highlights.sort_by_key(|(idx, _)| *idx);
+    highlights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"];
+        let new = ["a", "B", "c", "d", "e", "f", "g", "h", "i", "j", "k"];
+        assert_eq!(
+            unified_diff(&old, &new),
+            [
+                "@@ -1,5 +1,5 @@",
+                " a",
+                "-b",
+                "+B",
+                " c",
+                " d",
+                " e",
+                "@@ -8,3 +8,4 @@",
+                " h",
+                " i",
+                " j",
+                "+k",
+            ]
+        );
+
+        assert_eq!(
+            unified_diff(&[] as &[&str], &["x"]),
+            ["@@ -0,0 +1,1 @@", "+x"]
+        );
+        assert!(unified_diff(&old, &old).is_empty());
+
+        let old = "abcabba".chars().collect::<Vec<_>>();
+        let new = "cbabac".chars().collect::<Vec<_>>();
+        let edits = myers_diff(&old, &new);
+        // The shortest edit script of the classic example is 5 edits.
+        assert_eq!(edits.iter().filter(|e| **e != Edit::Equal).count(), 5);
+        let kept = edits.iter().filter(|e| **e != Edit::Insert).count();
+        assert_eq!(kept, old.len());
+
+        // Too many differing lines are replaced as a whole.
+        let old = (0..MAX_DIFF_LINES)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>();
+        let new = (0..MAX_DIFF_LINES)
+            .map(|i| format!("{i}!"))
+            .collect::<Vec<_>>();
+        let edits = diff_lines(&old, &new);
+        assert_eq!(edits.len(), 2 * MAX_DIFF_LINES);
+        assert!(edits[..MAX_DIFF_LINES].iter().all(|e| *e == Edit::Delete));
+    }
+
+    #[test]
+    fn test_word_diff_highlights() {
+        let lines = [
+            "commit abc",
+            "--- a/src/lib.rs",
+            "+++ b/src/lib.rs",
+            "@@ -1,3 +1,3 @@",
+            " fn main() {",
+            "-    let total = count + 1;",
+            "+    let total = count + 2;",
+            "-    old_completely_different_line();",
+            "+    x",
+            " }",
+        ]
+        .map(String::from);
+        assert_eq!(
+            word_diff_highlights(&lines),
+            vec![
+                (5, vec![(25, 1, WORD_DELETE_GROUP)]),
+                (6, vec![(25, 1, WORD_ADD_GROUP)]),
+            ]
+        );
+    }
+}
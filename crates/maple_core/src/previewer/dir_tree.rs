@@ -0,0 +1,177 @@
+//! `tree`-like rendering of a directory for the preview.
+
+use icon::{icon_or_default, FOLDER_ICON};
+use ignore::WalkBuilder;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
+#[derive(Debug)]
+struct TreeEntry {
+    /// 1 for the direct children of the root.
+    depth: usize,
+    name: String,
+    is_dir: bool,
+}
+
+/// Returns the lines of the tree of `dir` at most `max_depth` levels deep, the ignored
+/// files of `.gitignore` and the hidden files are excluded.
+///
+/// The directories are listed before the files on each level. At most `max_lines` lines
+/// are returned, the walk stops once it's exceeded and the last line tells there are
+/// more entries. The unreadable entries are skipped.
+pub fn render_tree(
+    dir: &Path,
+    max_depth: usize,
+    max_lines: usize,
+    enable_icon: bool,
+) -> Result<Vec<String>> {
+    if !dir.is_dir() {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("{} is not a directory", dir.display()),
+        ));
+    }
+
+    // The sort compares each entry multiple times, the file type is queried only once.
+    let is_dir_cache = Mutex::new(HashMap::<PathBuf, bool>::new());
+    let is_dir = move |path: &Path| {
+        *is_dir_cache
+            .lock()
+            .entry(path.to_path_buf())
+            .or_insert_with(|| path.is_dir())
+    };
+
+    let walker = WalkBuilder::new(dir)
+        .max_depth(Some(max_depth.max(1)))
+        // Not in a git repo, e.g., the extracted tarball.
+        .require_git(false)
+        .sort_by_file_path(move |a, b| is_dir(b).cmp(&is_dir(a)).then_with(|| a.cmp(b)))
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    let mut entries = Vec::new();
+    let mut truncated = false;
+
+    // The root itself is skipped.
+    for entry in walker.flatten().filter(|entry| entry.depth() > 0) {
+        if entries.len() >= max_lines {
+            truncated = true;
+            break;
+        }
+        entries.push(TreeEntry {
+            depth: entry.depth(),
+            name: entry.file_name().to_string_lossy().into_owned(),
+            is_dir: entry
+                .file_type()
+                .map_or(false, |file_type| file_type.is_dir()),
+        });
+    }
+
+    // Keep the line count within `max_lines`.
+    if truncated {
+        entries.truncate(max_lines.saturating_sub(1));
+    }
+
+    let mut lines = tree_lines(&entries, truncated, |entry| {
+        match (entry.is_dir, enable_icon) {
+            (true, true) => format!("{FOLDER_ICON} {}{MAIN_SEPARATOR}", entry.name),
+            (true, false) => format!("{}{MAIN_SEPARATOR}", entry.name),
+            (false, true) => format!("{} {}", icon_or_default(Path::new(&entry.name)), entry.name),
+            (false, false) => entry.name.clone(),
+        }
+    });
+
+    if truncated {
+        lines.push("… more".to_string());
+    }
+
+    Ok(lines)
+}
+
+/// Prefixes the entries in the depth-first order with the tree branches.
+///
+/// The entries of the `truncated` tree are assumed to be followed by their siblings
+/// unless told otherwise, as the walk stopped before seeing them.
+fn tree_lines(
+    entries: &[TreeEntry],
+    truncated: bool,
+    display: impl Fn(&TreeEntry) -> String,
+) -> Vec<String> {
+    // An entry is the last one of its siblings if no sibling follows before the walk
+    // returns to the parent level.
+    let mut is_last = vec![false; entries.len()];
+    let max_depth = entries.iter().map(|entry| entry.depth).max().unwrap_or(0);
+    let mut sibling_follows = vec![truncated; max_depth + 1];
+    for (idx, depth) in entries.iter().map(|entry| entry.depth).enumerate().rev() {
+        // The deeper levels following this entry are its own subtree.
+        sibling_follows.resize(depth + 1, false);
+        sibling_follows.truncate(depth + 1);
+        is_last[idx] = !sibling_follows[depth];
+        sibling_follows[depth] = true;
+    }
+
+    // Whether the ancestor at each level is the last one, which decides the `│` guide.
+    let mut ancestors_last = Vec::<bool>::new();
+    entries
+        .iter()
+        .zip(is_last)
+        .map(|(entry, is_last)| {
+            ancestors_last.truncate(entry.depth - 1);
+            let mut line = ancestors_last
+                .iter()
+                .map(|&last| if last { "    " } else { "│   " })
+                .collect::<String>();
+            line.push_str(if is_last { "└── " } else { "├── " });
+            line.push_str(&display(entry));
+            ancestors_last.push(is_last);
+            line
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_tree() {
+        let dir = std::env::temp_dir().join(format!("clap_dir_tree_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src/bin")).unwrap();
+        std::fs::create_dir_all(dir.join("target/debug")).unwrap();
+        std::fs::write(dir.join(".gitignore"), "target\n").unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "").unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "").unwrap();
+        std::fs::write(dir.join("src/bin/main.rs"), "").unwrap();
+
+        let sep = MAIN_SEPARATOR;
+        assert_eq!(
+            render_tree(&dir, 3, 10, false).unwrap(),
+            [
+                format!("├── src{sep}"),
+                format!("│   ├── bin{sep}"),
+                "│   │   └── main.rs".to_string(),
+                "│   └── lib.rs".to_string(),
+                "└── Cargo.toml".to_string(),
+            ]
+        );
+
+        assert_eq!(
+            render_tree(&dir, 1, 10, false).unwrap(),
+            [format!("├── src{sep}"), "└── Cargo.toml".to_string()]
+        );
+
+        assert_eq!(
+            render_tree(&dir, 3, 3, false).unwrap(),
+            [
+                format!("├── src{sep}"),
+                format!("│   ├── bin{sep}"),
+                "… more".to_string(),
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
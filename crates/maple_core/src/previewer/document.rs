@@ -0,0 +1,184 @@
+//! Plain text extracted from the document files, e.g., PDF and docx.
+//!
+//! Each file extension maps to a [`DocumentExtractor`], which is either an external
+//! command configured in `provider.document-extractors` or a built-in one.
+
+use crate::previewer::archive::read_zip_entry;
+use crate::process::limits::{self, output_with_timeout};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+/// Placeholder of the file path in the arguments of the external extractor.
+const PATH_PLACEHOLDER: &str = "{}";
+
+/// Timeout of the external extractor unless `process-limits.timeout` is configured.
+const EXTRACTOR_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Upper bound of the decompressed `word/document.xml` read for the preview.
+const MAX_DOCX_XML_LEN: u64 = 4 * 1024 * 1024;
+
+/// Extracts the text of a document for the preview.
+pub trait DocumentExtractor {
+    /// Returns at most `max_lines` lines of the text in `path`.
+    fn extract(&self, path: &Path, max_lines: usize) -> Result<Vec<String>>;
+}
+
+/// External command printing the text of the document to stdout, e.g.,
+/// `["pdftotext", "-l", "10", "-layout", "{}", "-"]`.
+#[derive(Debug)]
+pub struct CommandExtractor {
+    args: Vec<String>,
+}
+
+impl DocumentExtractor for CommandExtractor {
+    fn extract(&self, path: &Path, max_lines: usize) -> Result<Vec<String>> {
+        let (program, args) = self
+            .args
+            .split_first()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "empty extractor command"))?;
+
+        let path = path.to_string_lossy();
+        let args = args
+            .iter()
+            .map(|arg| arg.replace(PATH_PLACEHOLDER, &path))
+            .collect::<Vec<_>>();
+
+        let output = output_with_timeout(
+            Command::new(program).args(args),
+            Some(limits::timeout().unwrap_or(EXTRACTOR_TIMEOUT)),
+        )?;
+
+        if !output.status.success() {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "{program} exited with {}: {}",
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .take(max_lines)
+            .map(Into::into)
+            .collect())
+    }
+}
+
+/// Built-in extractor of the paragraphs in the docx file.
+#[derive(Debug)]
+pub struct DocxExtractor;
+
+impl DocumentExtractor for DocxExtractor {
+    fn extract(&self, path: &Path, max_lines: usize) -> Result<Vec<String>> {
+        let xml = read_zip_entry(path, "word/document.xml", MAX_DOCX_XML_LEN)?;
+        Ok(docx_xml_to_text(&String::from_utf8_lossy(&xml))
+            .lines()
+            .take(max_lines)
+            .map(Into::into)
+            .collect())
+    }
+}
+
+/// Returns the extractor of `path` if it's a document, `extractors` configured by the
+/// user take precedence over the built-in ones.
+pub fn extractor(
+    path: &Path,
+    extractors: &HashMap<String, Vec<String>>,
+) -> Option<Box<dyn DocumentExtractor>> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+
+    if let Some(args) = extractors.get(&ext) {
+        return Some(Box::new(CommandExtractor { args: args.clone() }));
+    }
+
+    match ext.as_str() {
+        "pdf" => Some(Box::new(CommandExtractor {
+            args: ["pdftotext", "-l", "10", "-layout", PATH_PLACEHOLDER, "-"]
+                .map(Into::into)
+                .to_vec(),
+        })),
+        "docx" => Some(Box::new(DocxExtractor)),
+        _ => None,
+    }
+}
+
+/// Converts the WordprocessingML to the plain text, one line per paragraph.
+fn docx_xml_to_text(xml: &str) -> String {
+    let mut text = String::new();
+    let mut in_text_run = false;
+    let mut rest = xml;
+
+    while let Some(tag_start) = rest.find('<') {
+        if in_text_run {
+            push_unescaped(&mut text, &rest[..tag_start]);
+        }
+        let Some(tag_len) = rest[tag_start..].find('>') else {
+            break;
+        };
+        let tag = &rest[tag_start + 1..tag_start + tag_len];
+        let tag_name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .find(|s| !s.is_empty())
+            .unwrap_or_default();
+
+        match (tag.starts_with('/'), tag_name) {
+            (false, "w:t") => in_text_run = !tag.ends_with('/'),
+            (true, "w:t") => in_text_run = false,
+            (true, "w:p") => text.push('\n'),
+            (false, "w:p") if tag.ends_with('/') => text.push('\n'),
+            (false, "w:tab") => text.push('\t'),
+            (false, "w:br" | "w:cr") => text.push('\n'),
+            _ => {}
+        }
+
+        rest = &rest[tag_start + tag_len + 1..];
+    }
+
+    text
+}
+
+fn push_unescaped(buf: &mut String, s: &str) {
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        buf.push_str(&rest[..amp]);
+        let entity = &rest[amp..];
+        let (unescaped, len) = [
+            ("&amp;", '&'),
+            ("&lt;", '<'),
+            ("&gt;", '>'),
+            ("&quot;", '"'),
+            ("&apos;", '\''),
+        ]
+        .iter()
+        .find(|(escaped, _)| entity.starts_with(escaped))
+        .map_or(('&', 1), |(escaped, c)| (*c, escaped.len()));
+        buf.push(unescaped);
+        rest = &entity[len..];
+    }
+    buf.push_str(rest);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_docx_xml_to_text() {
+        let xml = r#"<?xml version="1.0"?><w:document><w:body>
+<w:p><w:pPr><w:pStyle w:val="Title"/></w:pPr><w:r><w:t>Report</w:t></w:r></w:p>
+<w:p><w:r><w:t xml:space="preserve">Tom &amp; Jerry </w:t></w:r><w:r><w:tab/><w:t>&lt;1&gt;</w:t></w:r></w:p>
+<w:p/></w:body></w:document>"#;
+        assert_eq!(docx_xml_to_text(xml), "Report\nTom & Jerry \t<1>\n\n");
+
+        let extractors = HashMap::from([("pdf".to_string(), vec!["mutool".to_string()])]);
+        assert!(extractor(Path::new("a.PDF"), &extractors).is_some());
+        assert!(extractor(Path::new("a.docx"), &HashMap::new()).is_some());
+        assert!(extractor(Path::new("a.txt"), &HashMap::new()).is_none());
+    }
+}
@@ -0,0 +1,273 @@
+//! Detection of the legacy encodings, the files in which are transcoded to UTF-8 for the
+//! preview instead of being rendered as mojibake.
+//!
+//! The detection is a rough heuristic in the spirit of chardet: the byte order mark
+//! and the UTF-16 without BOM are recognized first, then the leading bytes are decoded
+//! with each candidate encoding and the one yielding the most plausible text wins.
+
+use encoding_rs::{
+    Encoding, BIG5, EUC_JP, EUC_KR, GBK, SHIFT_JIS, UTF_16BE, UTF_16LE, WINDOWS_1252,
+};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::io::{Read, Result};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Number of leading bytes inspected to detect the encoding.
+const SNIFF_LEN: usize = 8192;
+
+/// Maximum number of the detected encodings kept in memory.
+const MAX_DETECTED_FILES: usize = 64;
+
+/// Most frequent characters in the simplified Chinese text.
+const FREQUENT_HANS: &str = "的一是不了在人有我他这个们中来上大为和国地到以说时要就出会可也你对生能而子那得于着下自之年过发后作里用道行所然家种事成方多经么去法学如都同现当没动面起看定天分还进好小部其些主样理心她本前开但因只从想实";
+
+/// Most frequent characters in the traditional Chinese text.
+const FREQUENT_HANT: &str = "的一是不了在人有我他這個們中來上大為和國地到以說時要就出會可也你對生能而子那得於著下自之年過發後作裡用道行所然家種事成方多經麼去法學如都同現當沒動面起看定天分還進好小部其些主樣理心她本前開但因只從想實";
+
+/// Most frequent syllables in the Korean text.
+const FREQUENT_HANGUL: &str =
+    "이다의는에을하고가한지로기서사리자도를으대어인들수나그아정시해일보것있없게";
+
+/// Returns the encoding of `head`, the leading bytes of a file, or `None` if it's UTF-8
+/// or not text at all.
+pub fn detect(head: &[u8]) -> Option<&'static Encoding> {
+    let head = &head[..head.len().min(SNIFF_LEN)];
+
+    if let Some((encoding, _bom_len)) = Encoding::for_bom(head) {
+        return (encoding != encoding_rs::UTF_8).then_some(encoding);
+    }
+
+    if let Some(utf16) = detect_utf16_without_bom(head) {
+        return Some(utf16);
+    }
+
+    if head.contains(&0) {
+        return None;
+    }
+
+    match std::str::from_utf8(head) {
+        Ok(_) => return None,
+        // Only a multi-byte character is cut off at the end.
+        Err(e) if e.error_len().is_none() => return None,
+        Err(_) => {}
+    }
+
+    // Stop at the last ASCII byte to not cut off a multi-byte character.
+    let end = head
+        .iter()
+        .rposition(|b| b.is_ascii())
+        .map_or(0, |pos| pos + 1);
+    let head = if end > 0 { &head[..end] } else { head };
+
+    // Candidates of the legacy encodings, the former wins on a tie.
+    [GBK, SHIFT_JIS, EUC_JP, EUC_KR, BIG5, WINDOWS_1252]
+        .into_iter()
+        .filter_map(|encoding| {
+            let text = encoding.decode_without_bom_handling_and_without_replacement(head)?;
+            Some((encoding, plausibility(encoding, &text)))
+        })
+        .fold(
+            None,
+            |best: Option<(&'static Encoding, i64)>, (encoding, score)| match best {
+                Some((_, best_score)) if best_score >= score => best,
+                _ => Some((encoding, score)),
+            },
+        )
+        .filter(|(_, score)| *score > 0)
+        .map(|(encoding, _)| encoding)
+}
+
+#[derive(Debug)]
+struct DetectedFile {
+    path: PathBuf,
+    len: u64,
+    modified: Option<SystemTime>,
+    encoding: Option<&'static Encoding>,
+}
+
+/// Encodings of the recently detected files, the most recently used one comes first.
+static DETECTED_FILES: Lazy<Mutex<Vec<DetectedFile>>> = Lazy::new(Default::default);
+
+/// Returns the encoding of the file at `path`, see [`detect`].
+///
+/// The result is cached until the file is modified as it's asked for several times on
+/// each preview of the file.
+pub fn detect_file(path: &Path) -> Result<Option<&'static Encoding>> {
+    let file = std::fs::File::open(path)?;
+    let metadata = file.metadata()?;
+    let (len, modified) = (metadata.len(), metadata.modified().ok());
+
+    {
+        let mut detected_files = DETECTED_FILES.lock();
+        if let Some(pos) = detected_files.iter().position(|detected| {
+            detected.path == path && detected.len == len && detected.modified == modified
+        }) {
+            let detected = detected_files.remove(pos);
+            let encoding = detected.encoding;
+            detected_files.insert(0, detected);
+            return Ok(encoding);
+        }
+    }
+
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    file.take(SNIFF_LEN as u64).read_to_end(&mut head)?;
+    let encoding = detect(&head);
+
+    let mut detected_files = DETECTED_FILES.lock();
+    detected_files.retain(|detected| detected.path != path);
+    detected_files.insert(
+        0,
+        DetectedFile {
+            path: path.to_path_buf(),
+            len,
+            modified,
+            encoding,
+        },
+    );
+    detected_files.truncate(MAX_DETECTED_FILES);
+
+    Ok(encoding)
+}
+
+/// Returns `content` decoded from `encoding`, the BOM is removed.
+pub fn transcode(content: &[u8], encoding: &'static Encoding) -> String {
+    let (text, _had_errors) = encoding.decode_with_bom_removal(content);
+    text.into_owned()
+}
+
+/// Recognizes the UTF-16 text without BOM by the NUL bytes of the ASCII characters, which
+/// are all at either the odd or the even offsets.
+fn detect_utf16_without_bom(head: &[u8]) -> Option<&'static Encoding> {
+    if head.len() < 4 {
+        return None;
+    }
+
+    let pairs = head.len() / 2;
+    let (even_nuls, odd_nuls) =
+        head.chunks_exact(2)
+            .fold((0, 0), |(even, odd), pair| match (pair[0], pair[1]) {
+                (0, 0) => (even, odd),
+                (0, _) => (even + 1, odd),
+                (_, 0) => (even, odd + 1),
+                _ => (even, odd),
+            });
+
+    let mostly = |count: usize| count * 10 >= pairs * 4;
+    let rarely = |count: usize| count * 20 <= pairs;
+
+    if mostly(odd_nuls) && rarely(even_nuls) {
+        Some(UTF_16LE)
+    } else if mostly(even_nuls) && rarely(odd_nuls) {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Returns how plausible `text` decoded from `encoding` is as human-written text.
+fn plausibility(encoding: &'static Encoding, text: &str) -> i64 {
+    if encoding == WINDOWS_1252 {
+        return latin_plausibility(text);
+    }
+
+    let frequent = if encoding == GBK {
+        FREQUENT_HANS
+    } else if encoding == BIG5 {
+        FREQUENT_HANT
+    } else if encoding == EUC_KR {
+        FREQUENT_HANGUL
+    } else {
+        ""
+    };
+
+    let japanese = encoding == SHIFT_JIS || encoding == EUC_JP;
+
+    text.chars()
+        .filter(|c| !c.is_ascii())
+        .map(|c| match c {
+            _ if frequent.contains(c) => 3,
+            // Hiragana is distinctive of the Japanese text.
+            '\u{3040}'..='\u{309f}' if japanese => 3,
+            // Katakana.
+            '\u{30a0}'..='\u{30ff}' if japanese => 2,
+            // CJK unified ideographs, Hangul syllables and CJK punctuations.
+            '\u{4e00}'..='\u{9fff}' | '\u{ac00}'..='\u{d7af}' | '\u{3000}'..='\u{303f}' => 1,
+            // Full-width forms.
+            '\u{ff01}'..='\u{ff5e}' => 1,
+            _ => -1,
+        })
+        .sum()
+}
+
+/// Latin letters with diacritics are plausible only next to the ASCII letters, the runs
+/// of them are usually the CJK text decoded wrongly.
+fn latin_plausibility(text: &str) -> i64 {
+    let chars = text.chars().collect::<Vec<_>>();
+    chars
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| !c.is_ascii())
+        .map(|(idx, c)| {
+            let next_to_ascii_letter = idx
+                .checked_sub(1)
+                .and_then(|prev| chars.get(prev))
+                .into_iter()
+                .chain(chars.get(idx + 1))
+                .any(|c| c.is_ascii_alphabetic());
+            if c.is_alphabetic() && next_to_ascii_letter {
+                2
+            } else if matches!(c, '€' | '–' | '—' | '‘' | '’' | '“' | '”' | '…' | '°' | '©')
+            {
+                1
+            } else {
+                -1
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(encoding: &'static Encoding, text: &str) -> Vec<u8> {
+        encoding.encode(text).0.into_owned()
+    }
+
+    #[test]
+    fn test_detect_encoding() {
+        assert_eq!(detect(b"fn main() {}\n"), None);
+        assert_eq!(detect("日本語のテキスト".as_bytes()), None);
+        assert_eq!(detect(b"\x7fELF\x02\x01\x01\x00\x00"), None);
+
+        let chinese = "这是一个中文的文件，我们在预览里显示它。\n";
+        assert_eq!(detect(&encode(GBK, chinese)), Some(GBK));
+
+        let traditional = "這是一個中文的檔案，我們在預覽裡顯示它。\n";
+        assert_eq!(detect(&encode(BIG5, traditional)), Some(BIG5));
+
+        let japanese = "これは日本語のファイルです。プレビューに表示します。\n";
+        assert_eq!(detect(&encode(SHIFT_JIS, japanese)), Some(SHIFT_JIS));
+        assert_eq!(detect(&encode(EUC_JP, japanese)), Some(EUC_JP));
+
+        let korean = "이것은 한국어 파일입니다. 미리보기에 표시합니다.\n";
+        assert_eq!(detect(&encode(EUC_KR, korean)), Some(EUC_KR));
+
+        let latin = "Le café est très bon à Paris, n'est-ce pas?\n";
+        assert_eq!(detect(&encode(WINDOWS_1252, latin)), Some(WINDOWS_1252));
+
+        let utf16le = "hello\nworld\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        assert_eq!(detect(&utf16le), Some(UTF_16LE));
+        assert_eq!(transcode(&utf16le, UTF_16LE), "hello\nworld\n");
+
+        let mut with_bom = vec![0xfe, 0xff];
+        with_bom.extend("hi".encode_utf16().flat_map(u16::to_be_bytes));
+        assert_eq!(detect(&with_bom), Some(UTF_16BE));
+        assert_eq!(transcode(&with_bom, UTF_16BE), "hi");
+    }
+}
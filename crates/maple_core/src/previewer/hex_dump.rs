@@ -0,0 +1,185 @@
+//! Hex dump preview of the binary files in the style of `xxd`.
+
+use std::fmt::Write;
+use std::io::Read;
+use std::path::Path;
+
+/// Number of bytes in each line of the hex dump.
+const BYTES_PER_LINE: usize = 16;
+
+/// Number of leading bytes inspected to tell whether the file is binary.
+const SNIFF_LEN: usize = 8192;
+
+/// Returns `true` if `head`, the leading bytes of a file, looks like binary content.
+///
+/// The content containing NUL is binary, otherwise it's binary if it's not valid UTF-8
+/// and consists of too many control characters to be text in a legacy encoding.
+pub fn is_binary(head: &[u8]) -> bool {
+    let head = &head[..head.len().min(SNIFF_LEN)];
+
+    if head.contains(&0) {
+        return true;
+    }
+
+    match std::str::from_utf8(head) {
+        Ok(_) => false,
+        // Only a multi-byte character is cut off at the end.
+        Err(e) if e.error_len().is_none() => false,
+        Err(_) => {
+            let controls = head
+                .iter()
+                .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b))
+                .count();
+            controls * 10 > head.len()
+        }
+    }
+}
+
+/// Returns the MIME type detected from the magic number of `head`.
+pub fn mime_type(head: &[u8]) -> &'static str {
+    const MAGIC_NUMBERS: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"BM", "image/bmp"),
+        (b"\x00\x00\x01\x00", "image/x-icon"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"BZh", "application/x-bzip2"),
+        (b"\xfd7zXZ\x00", "application/x-xz"),
+        (b"\x28\xb5\x2f\xfd", "application/zstd"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"\x7fELF", "application/x-elf"),
+        (b"MZ", "application/vnd.microsoft.portable-executable"),
+        (b"\xcf\xfa\xed\xfe", "application/x-mach-binary"),
+        (b"\xca\xfe\xba\xbe", "application/java-vm"),
+        (b"\x00asm", "application/wasm"),
+        (b"SQLite format 3\x00", "application/vnd.sqlite3"),
+        (b"OggS", "audio/ogg"),
+        (b"fLaC", "audio/flac"),
+        (b"ID3", "audio/mpeg"),
+    ];
+
+    if let Some((_, mime)) = MAGIC_NUMBERS
+        .iter()
+        .find(|(magic, _)| head.starts_with(magic))
+    {
+        return mime;
+    }
+
+    if head.starts_with(b"RIFF") && head.len() >= 12 {
+        match &head[8..12] {
+            b"WEBP" => return "image/webp",
+            b"WAVE" => return "audio/wav",
+            b"AVI " => return "video/x-msvideo",
+            _ => {}
+        }
+    }
+
+    if head.len() >= 12 && &head[4..8] == b"ftyp" {
+        return "video/mp4";
+    }
+
+    if head.len() >= 262 && &head[257..262] == b"ustar" {
+        return "application/x-tar";
+    }
+
+    "application/octet-stream"
+}
+
+/// Formats `bytes` starting at `offset` as the lines of `xxd`, e.g.,
+/// `00000000: 7f45 4c46 0201 0100 0000 0000 0000 0000  .ELF............`.
+pub fn hex_dump_lines(bytes: &[u8], offset: usize) -> Vec<String> {
+    bytes
+        .chunks(BYTES_PER_LINE)
+        .enumerate()
+        .map(|(idx, chunk)| {
+            let mut hex = String::with_capacity(40);
+            for (i, b) in chunk.iter().enumerate() {
+                if i > 0 && i % 2 == 0 {
+                    hex.push(' ');
+                }
+                let _ = write!(hex, "{b:02x}");
+            }
+            let ascii = chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            // Each full line has 8 groups of 4 hex digits separated by a space.
+            format!("{:08x}: {hex:<39}  {ascii}", offset + idx * BYTES_PER_LINE)
+        })
+        .collect()
+}
+
+/// Hex dump of the head of a binary file.
+#[derive(Debug)]
+pub struct HexDump {
+    /// File size in bytes.
+    pub size: u64,
+    pub mime_type: &'static str,
+    pub lines: Vec<String>,
+}
+
+/// Returns the hex dump of at most `max_lines` lines if `path` is a binary file on disk.
+pub fn hex_dump(path: &Path, max_lines: usize) -> std::io::Result<Option<HexDump>> {
+    let file = std::fs::File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut head = Vec::with_capacity(SNIFF_LEN.max(max_lines * BYTES_PER_LINE));
+    file.take(SNIFF_LEN.max(max_lines * BYTES_PER_LINE) as u64)
+        .read_to_end(&mut head)?;
+
+    // The UTF-16 text looks binary due to the NUL bytes.
+    if !is_binary(&head) || super::encoding::detect(&head).is_some() {
+        return Ok(None);
+    }
+
+    head.truncate(max_lines * BYTES_PER_LINE);
+
+    Ok(Some(HexDump {
+        size,
+        mime_type: mime_type(&head),
+        lines: hex_dump_lines(&head, 0),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_binary() {
+        assert!(is_binary(b"\x7fELF\x02\x01\x01\x00"));
+        assert!(!is_binary("fn main() {}\n// 日本語".as_bytes()));
+        // Multi-byte character cut off at the end.
+        assert!(!is_binary(&"日本語".as_bytes()[..7]));
+        assert!(!is_binary(b"caf\xe9 au lait"));
+        assert!(is_binary(b"\x01\x02\x03\xff\xfe\x04\x05"));
+    }
+
+    #[test]
+    fn test_hex_dump_lines() {
+        let lines = hex_dump_lines(
+            b"\x7fELF\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00abc",
+            0,
+        );
+        assert_eq!(
+            lines,
+            [
+                "00000000: 7f45 4c46 0201 0100 0000 0000 0000 0000  .ELF............",
+                "00000010: 6162 63                                  abc",
+            ]
+        );
+        assert_eq!(mime_type(b"\x7fELF\x02\x01"), "application/x-elf");
+        assert_eq!(mime_type(b"RIFF\x00\x00\x00\x00WEBPVP8 "), "image/webp");
+        assert_eq!(mime_type(b"\x01\x02"), "application/octet-stream");
+    }
+}
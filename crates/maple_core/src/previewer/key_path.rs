@@ -0,0 +1,162 @@
+//! Key path of the line in the config files, e.g., `services.web.environment[3]`,
+//! displayed as the context line of the preview.
+
+use std::path::Path;
+
+/// Returns `true` if the key path of the file at `path` can be computed.
+pub fn is_supported(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("json" | "toml" | "yaml" | "yml")
+    )
+}
+
+/// Returns the tree-sitter language of the file at `path` if its key path is computed from
+/// the syntax tree, i.e., JSON and TOML.
+pub fn tree_sitter_language(path: &Path) -> Option<tree_sitter::Language> {
+    match path.extension().and_then(|ext| ext.to_str())? {
+        "json" => Some(tree_sitter::Language::Json),
+        "toml" => Some(tree_sitter::Language::Toml),
+        _ => None,
+    }
+}
+
+/// Returns the key path of the 1-based line `lnum` in the file `source` at `path`.
+pub fn key_path(path: &Path, source: &[u8], lnum: usize) -> Option<String> {
+    let row = lnum.checked_sub(1)?;
+    match tree_sitter_language(path) {
+        Some(language) => {
+            let tree = tree_sitter::parse(language, source)?;
+            tree_sitter::key_path(&tree, source, row)
+        }
+        None if is_supported(path) => yaml_key_path(&String::from_utf8_lossy(source), row),
+        None => None,
+    }
+}
+
+enum YamlNode {
+    Key(String),
+    /// Index of the item in the sequence.
+    Index(usize),
+}
+
+/// Returns the mapping key of the YAML line content, e.g., `image` of `image: nginx`.
+fn yaml_key(content: &str) -> Option<&str> {
+    let key = content
+        .strip_suffix(':')
+        .or_else(|| content.split_once(": ").map(|(key, _)| key))?;
+    // A colon in the quoted scalar, e.g., `- "80:80"`, does not make a key.
+    if key.is_empty() || (key.starts_with(['{', '[', '#', '\'', '"']) && !is_quoted(key)) {
+        return None;
+    }
+    Some(key.trim_matches(|c| c == '"' || c == '\''))
+}
+
+fn is_quoted(s: &str) -> bool {
+    s.len() > 1
+        && ((s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\'')))
+}
+
+/// Computes the YAML key path from the indentation as no tree-sitter grammar of YAML
+/// is available, the flow collections and the multi-line keys are not supported.
+fn yaml_key_path(source: &str, row: usize) -> Option<String> {
+    // Indentation of each node on the path.
+    let mut stack: Vec<(usize, YamlNode)> = Vec::new();
+
+    for line in source.lines().take(row + 1) {
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') || content.starts_with("---") {
+            continue;
+        }
+        let indent = line.len() - content.len();
+
+        if let Some(item) = content
+            .strip_prefix("- ")
+            .or((content == "-").then_some(""))
+        {
+            // The sequence may be at the same indentation as its parent key.
+            while stack.last().map_or(false, |(i, _)| *i > indent) {
+                stack.pop();
+            }
+            match stack.last_mut() {
+                Some((i, YamlNode::Index(index))) if *i == indent => *index += 1,
+                _ => stack.push((indent, YamlNode::Index(0))),
+            }
+            let item_indent = indent + content.len() - item.trim_start().len();
+            if let Some(key) = yaml_key(item.trim_start()) {
+                stack.push((item_indent, YamlNode::Key(key.to_string())));
+            }
+        } else if let Some(key) = yaml_key(content) {
+            while stack.last().map_or(false, |(i, _)| *i >= indent) {
+                stack.pop();
+            }
+            stack.push((indent, YamlNode::Key(key.to_string())));
+        }
+    }
+
+    if stack.is_empty() {
+        return None;
+    }
+
+    let mut key_path = String::new();
+    for (_, node) in stack {
+        match node {
+            YamlNode::Key(key) => {
+                if !key_path.is_empty() {
+                    key_path.push('.');
+                }
+                key_path.push_str(&key);
+            }
+            YamlNode::Index(index) => key_path.push_str(&format!("[{index}]")),
+        }
+    }
+
+    Some(key_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yaml_key_path() {
+        let yaml = r#"# compose
+services:
+  web:
+    image: "nginx:latest"
+    environment:
+      - A=1
+      - B=2
+      - name: C
+        value: 3
+    ports:
+    - "80:80"
+  db:
+    image: postgres
+"#;
+        let path = Path::new("compose.yaml");
+        let cases = [
+            (1, None),
+            (2, Some("services")),
+            (4, Some("services.web.image")),
+            (6, Some("services.web.environment[0]")),
+            (7, Some("services.web.environment[1]")),
+            (8, Some("services.web.environment[2].name")),
+            (9, Some("services.web.environment[2].value")),
+            (11, Some("services.web.ports[0]")),
+            (13, Some("services.db.image")),
+        ];
+        for (lnum, expected) in cases {
+            assert_eq!(
+                key_path(path, yaml.as_bytes(), lnum).as_deref(),
+                expected,
+                "lnum: {lnum}"
+            );
+        }
+
+        assert_eq!(
+            key_path(Path::new("a.json"), b"{\n  \"a\": {\"b\": 1}\n}", 2).as_deref(),
+            Some("a")
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Man pages rendered by `man -P cat` for the preview.
+
+use crate::process::limits::{self, output_with_timeout};
+use std::io::{Error, ErrorKind, Result};
+use std::process::Command;
+use std::time::Duration;
+
+/// Timeout of `man` unless `process-limits.timeout` is configured.
+const MAN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Extracts the page and section from a line of `man -k .`.
+///
+/// Both `ls (1) - list directory contents` of man-db and `ls(1) - list directory contents`
+/// of BSD are supported, only the first page is taken if multiple pages share the line.
+pub fn parse_apropos_line(line: &str) -> Option<(&str, Option<&str>)> {
+    let head = line.split(" - ").next()?;
+    let first = head.split(", ").next()?.trim();
+
+    match first.split_once('(') {
+        Some((page, rest)) => {
+            let section = rest.split_once(')').map(|(section, _)| section.trim())?;
+            let page = page.trim();
+            (!page.is_empty()).then_some((page, Some(section).filter(|s| !s.is_empty())))
+        }
+        None => {
+            let page = first.split_whitespace().next()?;
+            Some((page, None))
+        }
+    }
+}
+
+/// Removes the overstrike sequences of the bold and underlined text, e.g., `N\x08N`.
+fn strip_overstrike(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    for c in line.chars() {
+        if c == '\x08' {
+            stripped.pop();
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// Returns at most `max_lines` lines of the man page, formatted to fit into `width`.
+pub fn render(
+    page: &str,
+    section: Option<&str>,
+    width: usize,
+    max_lines: usize,
+) -> Result<Vec<String>> {
+    let mut cmd = Command::new("man");
+    cmd.args(["-P", "cat"]);
+    if let Some(section) = section {
+        cmd.arg(section);
+    }
+    cmd.arg(page)
+        .env("MANWIDTH", width.max(20).to_string())
+        // Use the overstrike instead of the SGR escape sequences, which are easier to strip.
+        .env("GROFF_NO_SGR", "1")
+        .env_remove("MAN_KEEP_FORMATTING");
+    let output = output_with_timeout(&mut cmd, Some(limits::timeout().unwrap_or(MAN_TIMEOUT)))?;
+
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "man exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .take(max_lines)
+        .map(strip_overstrike)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_apropos_line() {
+        assert_eq!(
+            parse_apropos_line("ls (1)               - list directory contents"),
+            Some(("ls", Some("1")))
+        );
+        assert_eq!(
+            parse_apropos_line("cal(1), ncal(1) - displays a calendar"),
+            Some(("cal", Some("1")))
+        );
+        assert_eq!(
+            parse_apropos_line("SSL_read (3ssl) - read bytes from a TLS/SSL connection"),
+            Some(("SSL_read", Some("3ssl")))
+        );
+        assert_eq!(parse_apropos_line("printf"), Some(("printf", None)));
+        assert_eq!(parse_apropos_line("(1) - broken"), None);
+        assert_eq!(strip_overstrike("N\x08NA\x08AM\x08ME\x08E"), "NAME");
+        assert_eq!(strip_overstrike("_\x08f_\x08o_\x08o"), "foo");
+    }
+}
@@ -0,0 +1,265 @@
+//! Plain-text rendering of the markdown files in the preview.
+//!
+//! The markup is replaced with a prettified layout, e.g., the headings are underlined and
+//! the links display only the text, the spans of each element are highlighted by the
+//! dedicated highlight groups.
+
+/// `(start, length, highlight_group)`, start is the byte offset in the rendered line.
+pub type Span = (usize, usize, &'static str);
+
+const HEADING: &str = "ClapMarkdownHeading";
+const LIST_MARKER: &str = "ClapMarkdownListMarker";
+const CODE: &str = "ClapMarkdownCode";
+const LINK: &str = "ClapMarkdownLink";
+const QUOTE: &str = "ClapMarkdownQuote";
+
+#[derive(Debug, Default)]
+pub struct RenderedMarkdown {
+    pub lines: Vec<String>,
+    /// `(line_index, spans)`, line_index is 0-based in `lines`.
+    pub highlights: Vec<(usize, Vec<Span>)>,
+}
+
+impl RenderedMarkdown {
+    fn push(&mut self, line: String, spans: Vec<Span>) {
+        if !spans.is_empty() {
+            self.highlights.push((self.lines.len(), spans));
+        }
+        self.lines.push(line);
+    }
+}
+
+/// Returns `true` if `path` is a markdown file.
+pub fn is_markdown(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map_or(false, |ext| {
+            matches!(ext.to_ascii_lowercase().as_str(), "md" | "markdown")
+        })
+}
+
+/// Renders the first lines of markdown `source` until `max_lines` lines are rendered.
+pub fn render_markdown<'a>(
+    source: impl Iterator<Item = &'a str>,
+    max_lines: usize,
+) -> RenderedMarkdown {
+    let mut rendered = RenderedMarkdown::default();
+    let mut in_code_fence = None::<String>;
+
+    for line in source {
+        if rendered.lines.len() >= max_lines {
+            break;
+        }
+
+        let trimmed = line.trim_start();
+
+        if let Some(fence) = &in_code_fence {
+            if trimmed.starts_with(fence.as_str()) && trimmed.trim_end() == fence {
+                in_code_fence.take();
+            } else {
+                let code = format!("  {line}");
+                let len = code.len();
+                rendered.push(code, vec![(0, len, CODE)]);
+            }
+            continue;
+        }
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            let fence_char = trimmed.as_bytes()[0] as char;
+            let fence_len = trimmed.chars().take_while(|&c| c == fence_char).count();
+            in_code_fence.replace(fence_char.to_string().repeat(fence_len));
+            let lang = trimmed[fence_len..].trim();
+            if !lang.is_empty() {
+                let label = format!("  [{lang}]");
+                let len = label.len();
+                rendered.push(label, vec![(0, len, CODE)]);
+            }
+            continue;
+        }
+
+        if let Some((level, text)) = heading(trimmed) {
+            let (text, _) = render_inline(text);
+            let width = text.chars().count();
+            let len = text.len();
+            rendered.push(text, vec![(0, len, HEADING)]);
+            let underline = match level {
+                1 => "═",
+                2 => "─",
+                _ => continue,
+            };
+            let underline = underline.repeat(width);
+            let len = underline.len();
+            rendered.push(underline, vec![(0, len, HEADING)]);
+            continue;
+        }
+
+        if is_thematic_break(trimmed) {
+            rendered.push("─".repeat(40), Vec::new());
+            continue;
+        }
+
+        let indent = &line[..line.len() - trimmed.len()];
+
+        if let Some(quoted) = trimmed.strip_prefix('>') {
+            let (text, mut spans) = render_inline(quoted.strip_prefix(' ').unwrap_or(quoted));
+            let prefix = format!("{indent}│ ");
+            shift_spans(&mut spans, prefix.len());
+            spans.insert(0, (0, prefix.len() + text.len(), QUOTE));
+            rendered.push(format!("{prefix}{text}"), spans);
+            continue;
+        }
+
+        if let Some((marker, item)) = list_item(trimmed) {
+            let (text, mut spans) = render_inline(item);
+            let marker_start = indent.len();
+            let prefix = format!("{indent}{marker} ");
+            shift_spans(&mut spans, prefix.len());
+            spans.insert(0, (marker_start, marker.len(), LIST_MARKER));
+            rendered.push(format!("{prefix}{text}"), spans);
+            continue;
+        }
+
+        let (text, spans) = render_inline(line);
+        rendered.push(text, spans);
+    }
+
+    rendered
+}
+
+/// Returns the level and text of an ATX heading, e.g., `## Usage`.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+fn is_thematic_break(line: &str) -> bool {
+    let line = line.trim_end();
+    ['-', '*', '_'].iter().any(|&c| {
+        line.chars().filter(|&x| x == c).count() >= 3 && line.chars().all(|x| x == c || x == ' ')
+    })
+}
+
+/// Returns the rendered marker and the item text of a list item.
+fn list_item(line: &str) -> Option<(String, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(bullet) {
+            let marker = match item.strip_prefix("[ ] ") {
+                Some(_) => "☐",
+                None if item.starts_with("[x] ") || item.starts_with("[X] ") => "☑",
+                None => "•",
+            };
+            let item = if marker == "•" { item } else { &item[4..] };
+            return Some((marker.to_string(), item));
+        }
+    }
+
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && digits < 10 {
+        let rest = &line[digits..];
+        if let Some(item) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return Some((format!("{}.", &line[..digits]), item));
+        }
+    }
+
+    None
+}
+
+fn shift_spans(spans: &mut [Span], offset: usize) {
+    spans.iter_mut().for_each(|(start, _, _)| *start += offset);
+}
+
+/// Renders the inline code and links, returns the rendered text and the highlights.
+fn render_inline(text: &str) -> (String, Vec<Span>) {
+    let mut rendered = String::with_capacity(text.len());
+    let mut spans = Vec::new();
+    let mut rest = text;
+
+    while let Some(pos) = rest.find(['`', '[']) {
+        rendered.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+
+        if let Some(code) = tail.strip_prefix('`') {
+            if let Some(end) = code.find('`') {
+                spans.push((rendered.len(), end, CODE));
+                rendered.push_str(&code[..end]);
+                rest = &code[end + 1..];
+                continue;
+            }
+        } else if let Some((link_text, consumed)) = inline_link(tail) {
+            spans.push((rendered.len(), link_text.len(), LINK));
+            rendered.push_str(link_text);
+            rest = &tail[consumed..];
+            continue;
+        }
+
+        rendered.push_str(&tail[..1]);
+        rest = &tail[1..];
+    }
+
+    rendered.push_str(rest);
+
+    (rendered, spans)
+}
+
+/// Parses `[text](url)` at the start of `s`, returns the text and the consumed length.
+fn inline_link(s: &str) -> Option<(&str, usize)> {
+    let text_end = s.find("](")?;
+    let text = &s[1..text_end];
+    if text.contains(['[', ']']) {
+        return None;
+    }
+    let url_len = s[text_end + 2..].find(')')?;
+    Some((text, text_end + 2 + url_len + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_markdown() {
+        let source = "\
+# vim-clap
+
+See [the docs](https://example.com) and `:Clap`.
+
+- [x] done
+  1. nested
+> quoted
+
+```rust
+fn main() {}
+```
+---";
+        let RenderedMarkdown { lines, highlights } = render_markdown(source.lines(), 100);
+        assert_eq!(
+            lines,
+            [
+                "vim-clap",
+                "════════",
+                "",
+                "See the docs and :Clap.",
+                "",
+                "☑ done",
+                "  1. nested",
+                "│ quoted",
+                "",
+                "  [rust]",
+                "  fn main() {}",
+                &"─".repeat(40),
+            ]
+        );
+        assert_eq!(highlights[0], (0, vec![(0, 8, HEADING)]));
+        assert_eq!(highlights[2], (3, vec![(4, 8, LINK), (17, 5, CODE)]));
+        assert_eq!(highlights[4], (6, vec![(2, 2, LIST_MARKER)]));
+
+        assert_eq!(render_markdown(source.lines(), 2).lines.len(), 2);
+    }
+}
@@ -1,11 +1,25 @@
+pub mod ansi;
+pub mod archive;
+pub mod command_output;
+pub mod csv;
+pub mod diff;
+pub mod dir_tree;
+pub mod document;
+pub mod encoding;
+pub mod hex_dump;
+pub mod key_path;
+pub mod man_page;
+pub mod markdown;
+pub mod notebook;
+pub mod special_file;
+pub mod symlink;
+pub mod url;
 pub mod vim_help;
 
-use paths::truncate_absolute_path;
-use std::fs::File;
-use std::io::Read;
+use crate::config::{LongPreviewLines, PreviewPosition};
+use crate::vfs::Vfs;
 use std::path::Path;
-use utils::bytelines::ByteLines;
-use utils::read_first_lines;
+use unicode_width::UnicodeWidthChar;
 
 /// Preview of a file.
 #[derive(Clone, Debug)]
@@ -24,23 +38,25 @@ pub struct FilePreview {
 
 /// Returns the lines that can fit into the preview window given its window height.
 ///
-/// Center the line at `target_line_number` in the preview window if possible.
-/// (`target_line` - `size`, `target_line` - `size`).
+/// The line at `target_line_number` is placed at `position` in the preview window if
+/// possible, i.e., unless it's too close to the beginning of the file.
 pub fn get_file_preview<P: AsRef<Path>>(
+    vfs: &dyn Vfs,
     path: P,
     target_line_number: usize,
     winheight: usize,
+    position: PreviewPosition,
 ) -> std::io::Result<FilePreview> {
-    let mid = winheight / 2;
-    let (start, end, highlight_lnum) = if target_line_number > mid {
-        (target_line_number - mid, target_line_number + mid, mid)
+    let anchor = position.anchor(winheight);
+    let (start, end, highlight_lnum) = if target_line_number > anchor {
+        let start = target_line_number - anchor;
+        (start, start + winheight, anchor)
     } else {
         (0, winheight, target_line_number)
     };
 
-    let total = utils::count_lines(std::fs::File::open(path.as_ref())?)?;
-
-    let lines = read_preview_lines(path, start, end)?;
+    // Only the lines in the window are read from the large file.
+    let (lines, total) = vfs.read_lines(path.as_ref(), start, end)?;
     let end = end.min(total);
 
     Ok(FilePreview {
@@ -52,50 +68,8 @@ pub fn get_file_preview<P: AsRef<Path>>(
     })
 }
 
-// Copypasted from stdlib.
-/// Indicates how large a buffer to pre-allocate before reading the entire file.
-fn initial_buffer_size(file: &File) -> usize {
-    // Allocate one extra byte so the buffer doesn't need to grow before the
-    // final `read` call at the end of the file.  Don't worry about `usize`
-    // overflow because reading will fail regardless in that case.
-    file.metadata().map(|m| m.len() as usize + 1).unwrap_or(0)
-}
-
-fn read_preview_lines<P: AsRef<Path>>(
-    path: P,
-    start: usize,
-    end: usize,
-) -> std::io::Result<Vec<String>> {
-    let mut filebuf: Vec<u8> = Vec::new();
-
-    File::open(path)
-        .and_then(|mut file| {
-            // XXX: is megabyte enough for any text file?
-            const MEGABYTE: usize = 32 * 1_048_576;
-
-            let filesize = initial_buffer_size(&file);
-            if filesize > MEGABYTE {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "maximum preview file buffer size reached",
-                ));
-            }
-
-            filebuf.reserve_exact(filesize);
-            file.read_to_end(&mut filebuf)
-        })
-        .map(|_| {
-            ByteLines::new(&filebuf)
-                .skip(start)
-                .take(end - start)
-                // trim_end() to get rid of ^M on Windows.
-                .map(|l| l.trim_end().to_string())
-                .collect()
-        })
-}
-
 #[inline]
-fn as_absolute_path<P: AsRef<Path>>(path: P) -> std::io::Result<String> {
+pub fn as_absolute_path<P: AsRef<Path>>(path: P) -> std::io::Result<String> {
     if path.as_ref().is_absolute() {
         Ok(path.as_ref().to_string_lossy().into())
     } else {
@@ -116,15 +90,13 @@ pub fn truncate_lines(
     lines: impl Iterator<Item = String>,
     max_width: usize,
 ) -> impl Iterator<Item = String> {
+    let marker = truncation_marker();
     lines.map(move |line| {
         if line.len() > max_width {
             let mut line = line;
             // https://github.com/liuchengxu/vim-clap/pull/544#discussion_r506281014
-            let replace_start = (0..max_width + 1)
-                .rev()
-                .find(|idx| line.is_char_boundary(*idx))
-                .unwrap_or_default(); // truncate to 0
-            line.replace_range(replace_start.., "……");
+            let replace_start = utils::truncate_at_grapheme_boundary(&line, max_width).len();
+            line.replace_range(replace_start.., marker);
             line
         } else {
             line
@@ -132,19 +104,70 @@ pub fn truncate_lines(
     })
 }
 
+/// Returns the marker appended to the truncated lines.
+fn truncation_marker() -> &'static str {
+    match crate::config::try_config().map(|config| config.provider.long_preview_lines) {
+        Some(LongPreviewLines::Marker) => "…>",
+        _ => "……",
+    }
+}
+
+/// Returns the byte offsets of the rows in `line` soft-wrapped at `width` display
+/// columns, the first row always starts at 0.
+pub fn wrap_offsets(line: &str, width: usize) -> Vec<usize> {
+    let mut offsets = vec![0];
+    let mut row_width = 0;
+    for (idx, c) in line.char_indices() {
+        let char_width = c.width().unwrap_or(0);
+        if row_width + char_width > width && row_width > 0 {
+            offsets.push(idx);
+            row_width = 0;
+        }
+        row_width += char_width;
+    }
+    offsets
+}
+
+/// Returns the byte span `(start, length)` of the position at 1-based byte `column` in
+/// `line`, i.e., the rest of the word from the column or the single char otherwise.
+///
+/// Only used for the items pointing to a position without the matcher of the query, e.g.,
+/// the quickfix entries, the grep matches are highlighted by the matcher instead.
+pub fn match_span(line: &str, column: usize) -> Option<(usize, usize)> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    let offset = column
+        .checked_sub(1)
+        .filter(|&offset| offset < line.len())?;
+    // The column in the middle of a char is moved to the start of the char.
+    let start = (0..=offset).rev().find(|&idx| line.is_char_boundary(idx))?;
+    let first = line[start..].chars().next()?;
+    let end = if is_word_char(first) {
+        line[start..]
+            .char_indices()
+            .find(|(_, c)| !is_word_char(*c))
+            .map_or(line.len(), |(idx, _)| start + idx)
+    } else {
+        start + first.len_utf8()
+    };
+
+    Some((start, end - start))
+}
+
 pub fn preview_file<P: AsRef<Path>>(
+    vfs: &dyn Vfs,
     path: P,
     size: usize,
     max_width: usize,
 ) -> std::io::Result<(Vec<String>, String)> {
-    if !path.as_ref().is_file() {
+    if !vfs.is_file(path.as_ref()) {
         return Err(std::io::Error::new(
             std::io::ErrorKind::Other,
             "Can not preview if the object is not a file",
         ));
     }
     let abs_path = as_absolute_path(path.as_ref())?;
-    let lines_iter = read_first_lines(path.as_ref(), size)?;
+    let lines_iter = vfs.read_first_lines(path.as_ref(), size)?.into_iter();
     let lines = std::iter::once(abs_path.clone())
         .chain(truncate_lines(lines_iter, max_width))
         .collect::<Vec<_>>();
@@ -152,47 +175,43 @@ pub fn preview_file<P: AsRef<Path>>(
     Ok((lines, abs_path))
 }
 
-pub fn preview_file_with_truncated_title<P: AsRef<Path>>(
-    path: P,
-    size: usize,
-    max_line_width: usize,
-    max_title_width: usize,
-) -> std::io::Result<(Vec<String>, String)> {
-    let abs_path = as_absolute_path(path.as_ref())?;
-    let truncated_abs_path = truncate_absolute_path(&abs_path, max_title_width).into_owned();
-    let lines_iter = read_first_lines(path.as_ref(), size)?;
-    let lines = std::iter::once(truncated_abs_path.clone())
-        .chain(truncate_lines(lines_iter, max_line_width))
-        .collect::<Vec<_>>();
-
-    Ok((lines, truncated_abs_path))
-}
-
-pub fn preview_file_at<P: AsRef<Path>>(
-    path: P,
-    winheight: usize,
-    max_width: usize,
-    lnum: usize,
-) -> std::io::Result<(Vec<String>, usize)> {
-    tracing::debug!(path = %path.as_ref().display(), lnum, "Previewing file");
-
-    let FilePreview {
-        lines,
-        highlight_lnum,
-        ..
-    } = get_file_preview(path.as_ref(), lnum, winheight)?;
-
-    let lines = std::iter::once(format!("{}:{lnum}", path.as_ref().display()))
-        .chain(truncate_lines(lines.into_iter(), max_width))
-        .collect::<Vec<_>>();
-
-    Ok((lines, highlight_lnum))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_truncate_lines() {
+        let lines = vec![
+            "short".to_string(),
+            "中文字符".to_string(),
+            "ok👍🏽👍🏽".to_string(),
+        ];
+        assert_eq!(
+            truncate_lines(lines.into_iter(), 8).collect::<Vec<_>>(),
+            ["short", "中文……", "ok……"]
+        );
+    }
+
+    #[test]
+    fn test_wrap_offsets() {
+        assert_eq!(wrap_offsets("", 4), [0]);
+        assert_eq!(wrap_offsets("abcdefghij", 4), [0, 4, 8]);
+        // Wide characters are not split across the rows.
+        assert_eq!(wrap_offsets("ab中文字", 3), [0, 2, 5, 8]);
+    }
+
+    #[test]
+    fn test_match_span() {
+        assert_eq!(match_span("let foo_bar = 1;", 5), Some((4, 7)));
+        assert_eq!(match_span("a = 中文;", 5), Some((4, 6)));
+        // The column of rg counts the bytes, 中 and 文 are 3 bytes each.
+        assert_eq!(match_span("中文 = foo;", 10), Some((9, 3)));
+        assert_eq!(match_span("中文 = foo;", 2), Some((0, 6)));
+        assert_eq!(match_span("x == y", 3), Some((2, 1)));
+        assert_eq!(match_span("short", 9), None);
+        assert_eq!(match_span("short", 0), None);
+    }
+
     #[test]
     fn test_file_preview_contains_multi_byte() {
         let test_txt = std::env::current_dir()
@@ -204,16 +223,63 @@ mod tests {
             .join("test")
             .join("testdata")
             .join("test_673.txt");
-        let FilePreview { lines, .. } = get_file_preview(test_txt, 2, 10).unwrap();
+        let FilePreview { lines, .. } = get_file_preview(
+            &crate::vfs::DiskFs,
+            test_txt,
+            2,
+            10,
+            PreviewPosition::Center,
+        )
+        .unwrap();
         assert_eq!(
             lines,
             [
                 "test_ddd",
-                "test_ddd    //1����ˤ��ϡ�����1",
-                "test_ddd    //2����ˤ��ϡ�����2",
-                "test_ddd    //3����ˤ��ϡ�����3",
+                "test_ddd    //1こんにちは、世界1",
+                "test_ddd    //2こんにちは、世界2",
+                "test_ddd    //3こんにちは、世界3",
                 "test_ddd    //hello"
             ]
         );
     }
+
+    #[test]
+    fn test_file_preview_position() {
+        let vfs = crate::vfs::BufferFs::default();
+        let path = Path::new("position.txt");
+        vfs.insert(path.into(), (1..=100).map(|n| n.to_string()).collect());
+
+        let preview = |lnum, position| {
+            let FilePreview {
+                start,
+                end,
+                highlight_lnum,
+                lines,
+                ..
+            } = get_file_preview(&vfs, path, lnum, 10, position).unwrap();
+            // `highlight_lnum` is 1-based in `lines`.
+            (
+                start,
+                end,
+                highlight_lnum,
+                lines[highlight_lnum - 1].clone(),
+            )
+        };
+
+        assert_eq!(
+            preview(50, PreviewPosition::Center),
+            (45, 55, 5, "50".into())
+        );
+        assert_eq!(preview(50, PreviewPosition::Top), (49, 59, 1, "50".into()));
+        assert_eq!(
+            preview(50, PreviewPosition::Offset(3)),
+            (46, 56, 4, "50".into())
+        );
+        // Not enough lines above the target line.
+        assert_eq!(
+            preview(2, PreviewPosition::Offset(3)),
+            (0, 10, 2, "2".into())
+        );
+        assert_eq!(preview(98, PreviewPosition::Top), (97, 100, 1, "98".into()));
+    }
 }
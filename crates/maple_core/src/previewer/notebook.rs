@@ -0,0 +1,421 @@
+//! Renders the Jupyter notebooks in the [percent format](https://jupytext.readthedocs.io/en/latest/formats-scripts.html)
+//! for previewing instead of the raw JSON.
+//!
+//! ```text
+//! # %% [markdown]
+//! # Title
+//!
+//! # %% In [1]
+//! print("hello")
+//! # >> hello
+//! ```
+//!
+//! The cells whose language differs from the kernel language, e.g., the markdown cells
+//! or the code cells starting with a cell magic like `%%bash`, carry their own syntax.
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// Maximum number of lines displayed for each output.
+const MAX_OUTPUT_LINES: usize = 3;
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum MultilineText {
+    Single(String),
+    Lines(Vec<String>),
+}
+
+impl Default for MultilineText {
+    fn default() -> Self {
+        Self::Single(String::new())
+    }
+}
+
+impl MultilineText {
+    fn lines(&self) -> Vec<&str> {
+        match self {
+            Self::Single(text) => text.lines().collect(),
+            Self::Lines(lines) => lines.iter().flat_map(|line| line.lines()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Cell {
+    cell_type: String,
+    #[serde(default)]
+    metadata: Value,
+    #[serde(default)]
+    source: MultilineText,
+    #[serde(default)]
+    execution_count: Option<u64>,
+    #[serde(default)]
+    outputs: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Notebook {
+    cells: Vec<Cell>,
+    #[serde(default)]
+    metadata: Value,
+}
+
+/// Returns `true` if `path` is a Jupyter notebook.
+pub fn is_notebook(path: &Path) -> bool {
+    path.extension().map_or(false, |ext| ext == "ipynb")
+}
+
+/// Returns the line comment prefix and the Vim filetype of the kernel language.
+fn language_syntax(language: &str) -> (&'static str, String) {
+    let comment = match language {
+        "rust" | "javascript" | "typescript" | "go" | "c" | "c++" | "cpp" | "java" | "scala"
+        | "kotlin" | "csharp" | "c#" | "swift" => "//",
+        "haskell" | "lua" | "sql" => "--",
+        "matlab" | "octave" => "%",
+        _ => "#",
+    };
+    let filetype = match language {
+        "c++" => "cpp",
+        "c#" => "cs",
+        "bash" => "sh",
+        "octave" => "matlab",
+        lang => lang,
+    };
+    (comment, filetype.to_string())
+}
+
+/// Returns the Vim syntax of the code cell if it's written in a language other than the
+/// kernel language.
+fn code_cell_syntax(cell: &Cell, source_lines: &[&str]) -> Option<String> {
+    // Polyglot notebooks, e.g., .NET Interactive, record the language per cell.
+    if let Some(language) = cell.metadata["vscode"]["languageId"].as_str() {
+        return Some(language_syntax(&language.to_lowercase()).1);
+    }
+
+    let magic = source_lines.first()?.strip_prefix("%%")?;
+    let syntax = match magic.split_whitespace().next()? {
+        "bash" | "sh" | "script" => "sh",
+        "html" => "html",
+        "javascript" | "js" => "javascript",
+        "latex" => "tex",
+        "markdown" => "markdown",
+        "perl" => "perl",
+        "ruby" => "ruby",
+        "sql" => "sql",
+        "svg" => "svg",
+        _ => return None,
+    };
+    Some(syntax.to_string())
+}
+
+/// Source lines of a cell highlighted with a syntax other than the kernel language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellSyntax {
+    /// 1-based line number of the first source line in the rendered lines.
+    pub start: usize,
+    /// 1-based line number of the last source line in the rendered lines, inclusive.
+    pub end: usize,
+    /// Vim syntax of the cell.
+    pub syntax: String,
+}
+
+/// Notebook rendered in the percent format.
+#[derive(Debug, Clone)]
+pub struct RenderedNotebook {
+    pub lines: Vec<String>,
+    /// Vim filetype of the kernel language.
+    pub filetype: String,
+    /// Cells in a language other than the kernel language, in the order of `lines`.
+    pub cell_syntaxes: Vec<CellSyntax>,
+    /// Sorted pairs of the 1-based line number in the raw JSON and in `lines`.
+    line_map: Vec<(usize, usize)>,
+}
+
+impl RenderedNotebook {
+    /// Converts the line number in the raw JSON, e.g., the grep matched line, to the
+    /// line number in the rendered lines.
+    ///
+    /// The line without an exact counterpart is mapped to the nearest line above it.
+    pub fn rendered_lnum(&self, raw_lnum: usize) -> usize {
+        match self
+            .line_map
+            .binary_search_by_key(&raw_lnum, |(raw, _)| *raw)
+        {
+            Ok(idx) => self.line_map[idx].1,
+            Err(0) => 1,
+            Err(idx) => self.line_map[idx - 1].1,
+        }
+    }
+}
+
+fn summarize_output(output: &Value) -> Vec<String> {
+    let text_lines = |value: Option<&Value>| {
+        value
+            .cloned()
+            .and_then(|v| serde_json::from_value::<MultilineText>(v).ok())
+            .map(|text| {
+                text.lines()
+                    .into_iter()
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+    };
+
+    match output["output_type"].as_str() {
+        Some("stream") => text_lines(output.get("text")).unwrap_or_default(),
+        Some("execute_result" | "display_data") => {
+            let data = &output["data"];
+            text_lines(data.get("text/plain")).unwrap_or_else(|| {
+                data.as_object()
+                    .map(|data| data.keys().map(|mime| format!("<{mime}>")).collect())
+                    .unwrap_or_default()
+            })
+        }
+        Some("error") => vec![format!(
+            "{}: {}",
+            output["ename"].as_str().unwrap_or_default(),
+            output["evalue"].as_str().unwrap_or_default()
+        )],
+        _ => Vec::new(),
+    }
+}
+
+/// Parses the JSON string literal on the raw line, e.g., `"import os\n",`.
+fn parse_source_line(raw_line: &str) -> Option<String> {
+    let literal = raw_line.trim().trim_end_matches(',');
+    if !literal.starts_with('"') {
+        return None;
+    }
+    let text = serde_json::from_str::<String>(literal).ok()?;
+    Some(text.trim_end_matches('\n').to_string())
+}
+
+/// Renders the notebook `content` in the percent format.
+pub fn render_notebook(content: &[u8]) -> serde_json::Result<RenderedNotebook> {
+    let notebook: Notebook = serde_json::from_slice(content)?;
+
+    let language = notebook.metadata["kernelspec"]["language"]
+        .as_str()
+        .or_else(|| notebook.metadata["language_info"]["name"].as_str())
+        .unwrap_or("python")
+        .to_lowercase();
+    let (comment, filetype) = language_syntax(&language);
+
+    let mut lines = Vec::new();
+    let mut cell_syntaxes = Vec::new();
+    // Line number in `lines` of each cell header.
+    let mut cell_headers = Vec::new();
+    // Source lines of the cells and their line numbers in `lines`.
+    let mut source_lines = Vec::new();
+
+    for (cell_idx, cell) in notebook.cells.iter().enumerate() {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        let header = match (cell.cell_type.as_str(), cell.execution_count) {
+            ("code", Some(count)) => format!("{comment} %% In [{count}]"),
+            ("code", None) => format!("{comment} %%"),
+            (cell_type, _) => format!("{comment} %% [{cell_type}]"),
+        };
+        lines.push(header);
+        cell_headers.push(lines.len());
+
+        let cell_source = cell.source.lines();
+        let cell_syntax = match cell.cell_type.as_str() {
+            "code" => code_cell_syntax(cell, &cell_source).filter(|syntax| *syntax != filetype),
+            "markdown" => Some("markdown".to_string()),
+            _ => None,
+        };
+
+        // The markdown cells are highlighted as markdown, only the raw cells are
+        // commented out.
+        let is_commented = cell.cell_type == "raw";
+        let source_start = lines.len() + 1;
+        for line in &cell_source {
+            if !is_commented {
+                lines.push(line.to_string());
+            } else if line.is_empty() {
+                lines.push(comment.to_string());
+            } else {
+                lines.push(format!("{comment} {line}"));
+            }
+            source_lines.push((cell_idx, line.to_string(), lines.len()));
+        }
+
+        if let Some(syntax) = cell_syntax {
+            if !cell_source.is_empty() {
+                cell_syntaxes.push(CellSyntax {
+                    start: source_start,
+                    end: lines.len(),
+                    syntax,
+                });
+            }
+        }
+
+        for output in &cell.outputs {
+            let output_lines = summarize_output(output);
+            lines.extend(
+                output_lines
+                    .iter()
+                    .take(MAX_OUTPUT_LINES)
+                    .map(|line| format!("{comment} >> {line}")),
+            );
+            if output_lines.len() > MAX_OUTPUT_LINES {
+                lines.push(format!(
+                    "{comment} >> ... {} more lines",
+                    output_lines.len() - MAX_OUTPUT_LINES
+                ));
+            }
+        }
+    }
+
+    // The cells and their sources appear in the same order in the raw JSON.
+    let mut line_map = Vec::new();
+    let mut cell_headers = cell_headers.into_iter().enumerate();
+    let mut source_lines = source_lines.into_iter().peekable();
+    for (idx, raw_line) in String::from_utf8_lossy(content).lines().enumerate() {
+        let raw_lnum = idx + 1;
+        if raw_line.trim_start().starts_with("\"cell_type\"") {
+            if let Some((cell_idx, rendered_lnum)) = cell_headers.next() {
+                line_map.push((raw_lnum, rendered_lnum));
+                // The unmatched lines of the previous cells, e.g., the source is a
+                // single string, are skipped.
+                while source_lines.next_if(|(idx, ..)| *idx < cell_idx).is_some() {}
+            }
+        } else if let Some((_, expected, rendered_lnum)) = source_lines.peek() {
+            if parse_source_line(raw_line).as_ref() == Some(expected) {
+                line_map.push((raw_lnum, *rendered_lnum));
+                source_lines.next();
+            }
+        }
+    }
+
+    Ok(RenderedNotebook {
+        lines,
+        filetype,
+        cell_syntaxes,
+        line_map,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_notebook() {
+        let content = r##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": [
+    "# Title\n",
+    "\n",
+    "Some text"
+   ]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 1,
+   "metadata": {},
+   "outputs": [
+    {
+     "name": "stdout",
+     "output_type": "stream",
+     "text": ["1\n", "2\n", "3\n", "4\n"]
+    },
+    {
+     "data": {"image/png": "iVBORw0KGgo="},
+     "output_type": "display_data"
+    },
+    {
+     "ename": "ZeroDivisionError",
+     "evalue": "division by zero",
+     "output_type": "error",
+     "traceback": []
+    }
+   ],
+   "source": [
+    "for i in range(4):\n",
+    "    print(i)"
+   ]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": null,
+   "metadata": {},
+   "outputs": [],
+   "source": "%%bash\nls -l"
+  },
+  {
+   "cell_type": "raw",
+   "metadata": {},
+   "source": "raw text"
+  }
+ ],
+ "metadata": {
+  "kernelspec": {"language": "python", "name": "python3"}
+ },
+ "nbformat": 4,
+ "nbformat_minor": 5
+}"##;
+
+        let notebook = render_notebook(content.as_bytes()).unwrap();
+        assert_eq!(notebook.filetype, "python");
+        assert_eq!(
+            notebook.lines,
+            vec![
+                "# %% [markdown]",
+                "# Title",
+                "",
+                "Some text",
+                "",
+                "# %% In [1]",
+                "for i in range(4):",
+                "    print(i)",
+                "# >> 1",
+                "# >> 2",
+                "# >> 3",
+                "# >> ... 1 more lines",
+                "# >> <image/png>",
+                "# >> ZeroDivisionError: division by zero",
+                "",
+                "# %%",
+                "%%bash",
+                "ls -l",
+                "",
+                "# %% [raw]",
+                "# raw text",
+            ]
+        );
+        assert_eq!(
+            notebook.cell_syntaxes,
+            vec![
+                CellSyntax {
+                    start: 2,
+                    end: 4,
+                    syntax: "markdown".into()
+                },
+                CellSyntax {
+                    start: 17,
+                    end: 18,
+                    syntax: "sh".into()
+                },
+            ]
+        );
+
+        // Header of the second cell.
+        assert_eq!(notebook.rendered_lnum(13), 6);
+        // `    "    print(i)"`
+        assert_eq!(notebook.rendered_lnum(35), 8);
+        // Within the outputs, mapped to the cell header above.
+        assert_eq!(notebook.rendered_lnum(20), 6);
+        assert_eq!(notebook.rendered_lnum(1), 1);
+        // `    "\n",` of the markdown cell.
+        assert_eq!(notebook.rendered_lnum(8), 3);
+        assert_eq!(notebook.rendered_lnum(9), 4);
+    }
+}
@@ -0,0 +1,138 @@
+//! Previews of the paths whose content can't be read as text, e.g., FIFOs, sockets,
+//! device files and the files without read permission.
+//!
+//! These paths are recognized by `stat` before opening them, since opening a FIFO
+//! blocks until a writer shows up.
+
+use std::path::Path;
+
+/// Returns a single-line description of `path` if it's a special file or not readable,
+/// `None` if the path is a directory, a readable regular file or does not exist.
+pub fn special_file_preview(path: &Path) -> Option<String> {
+    let metadata = std::fs::metadata(path).ok()?;
+
+    if metadata.is_dir() {
+        return None;
+    }
+
+    let kind = match special_file_kind(&metadata) {
+        Some(kind) => kind,
+        None => match std::fs::File::open(path) {
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                "permission denied, regular file"
+            }
+            _ => return None,
+        },
+    };
+
+    Some(format!(
+        "{}: {kind}{}",
+        path.display(),
+        file_attributes(&metadata)
+    ))
+}
+
+#[cfg(unix)]
+fn special_file_kind(metadata: &std::fs::Metadata) -> Option<&'static str> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let file_type = metadata.file_type();
+    if file_type.is_fifo() {
+        Some("named pipe (FIFO)")
+    } else if file_type.is_socket() {
+        Some("socket")
+    } else if file_type.is_block_device() {
+        Some("block device")
+    } else if file_type.is_char_device() {
+        Some("character device")
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn special_file_kind(_metadata: &std::fs::Metadata) -> Option<&'static str> {
+    None
+}
+
+/// Returns the permissions and owner like `, prw-r--r-- alice`.
+#[cfg(unix)]
+fn file_attributes(metadata: &std::fs::Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+
+    let owner = user_name(metadata.uid()).unwrap_or_else(|| metadata.uid().to_string());
+    format!(", {} {owner}", format_mode(metadata.mode()))
+}
+
+#[cfg(not(unix))]
+fn file_attributes(_metadata: &std::fs::Metadata) -> String {
+    String::new()
+}
+
+/// Formats the file mode in the style of `ls -l`.
+#[cfg(unix)]
+fn format_mode(mode: u32) -> String {
+    // The `S_IFMT` bits, whose values are the same on all the Unix platforms.
+    let file_type = match mode & 0o170000 {
+        0o010000 => 'p',
+        0o140000 => 's',
+        0o060000 => 'b',
+        0o020000 => 'c',
+        0o040000 => 'd',
+        0o120000 => 'l',
+        _ => '-',
+    };
+
+    std::iter::once(file_type)
+        .chain((0..9).rev().map(|bit| {
+            if mode & (1 << bit) == 0 {
+                '-'
+            } else {
+                ['x', 'w', 'r'][bit % 3]
+            }
+        }))
+        .collect()
+}
+
+#[cfg(unix)]
+fn user_name(uid: u32) -> Option<String> {
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 1024];
+    let mut result = std::ptr::null_mut();
+
+    let ret =
+        unsafe { libc::getpwuid_r(uid, &mut passwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+
+    if ret != 0 || result.is_null() {
+        return None;
+    }
+
+    let name = unsafe { std::ffi::CStr::from_ptr(passwd.pw_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_special_file_preview() {
+        assert_eq!(format_mode(0o100644), "-rw-r--r--");
+        assert_eq!(format_mode(0o010600), "prw-------");
+
+        let fifo = std::env::temp_dir().join(format!("clap_test_fifo_{}", std::process::id()));
+        let c_path = std::ffi::CString::new(fifo.to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+
+        let preview = special_file_preview(&fifo).unwrap();
+        std::fs::remove_file(&fifo).unwrap();
+        assert!(preview.starts_with(&format!(
+            "{}: named pipe (FIFO), prw-------",
+            fifo.display()
+        )));
+
+        let regular_file = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+        assert!(special_file_preview(Path::new(regular_file)).is_none());
+    }
+}
@@ -0,0 +1,106 @@
+//! Symbolic links resolved safely for the preview.
+
+use std::collections::HashSet;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+/// Maximum number of the symlinks followed, on par with `MAXSYMLINKS` of Linux.
+const MAX_SYMLINK_DEPTH: usize = 40;
+
+/// Returns the target of `path` as stored in the link if it's a symlink.
+pub fn link_target(path: &Path) -> Option<PathBuf> {
+    std::fs::read_link(path).ok()
+}
+
+/// Follows the chain of symlinks starting from `path`, returns the final target or `None`
+/// if `path` is not a symlink.
+///
+/// The cyclic chain, the chain deeper than [`MAX_SYMLINK_DEPTH`] and the dangling link
+/// are errors.
+pub fn resolve(path: &Path) -> Result<Option<PathBuf>> {
+    let mut current = path.to_path_buf();
+    let mut visited = HashSet::new();
+
+    for depth in 0..MAX_SYMLINK_DEPTH {
+        let Ok(target) = std::fs::read_link(&current) else {
+            if depth == 0 {
+                return Ok(None);
+            }
+            if std::fs::symlink_metadata(&current).is_err() {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!(
+                        "{} is a dangling symlink to {}",
+                        path.display(),
+                        current.display()
+                    ),
+                ));
+            }
+            return Ok(Some(current));
+        };
+
+        if !visited.insert(current.clone()) {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("{} is a cyclic symlink", path.display()),
+            ));
+        }
+
+        current = match current.parent() {
+            Some(parent) if target.is_relative() => parent.join(target),
+            _ => target,
+        };
+    }
+
+    Err(Error::new(
+        ErrorKind::Other,
+        format!(
+            "{} is a chain of over {MAX_SYMLINK_DEPTH} symlinks",
+            path.display()
+        ),
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn test_resolve_symlink() {
+        let dir = std::env::temp_dir().join(format!("clap_symlink_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub/file"), "content").unwrap();
+
+        let file = dir.join("sub/file");
+        assert_eq!(resolve(&file).unwrap(), None);
+
+        // Relative targets are resolved against the directory of the link.
+        symlink("sub/file", dir.join("link")).unwrap();
+        symlink("link", dir.join("link_to_link")).unwrap();
+        assert_eq!(resolve(&dir.join("link_to_link")).unwrap(), Some(file));
+        assert_eq!(
+            link_target(&dir.join("link_to_link")),
+            Some(PathBuf::from("link"))
+        );
+
+        symlink("sub", dir.join("dir_link")).unwrap();
+        assert_eq!(
+            resolve(&dir.join("dir_link")).unwrap(),
+            Some(dir.join("sub"))
+        );
+
+        symlink("b", dir.join("a")).unwrap();
+        symlink("a", dir.join("b")).unwrap();
+        assert!(resolve(&dir.join("a")).is_err());
+
+        symlink("missing", dir.join("dangling")).unwrap();
+        assert_eq!(
+            resolve(&dir.join("dangling")).unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+//! Leading content of the remote http(s) resources for the preview.
+
+use crate::previewer::hex_dump::{is_binary, mime_type};
+use once_cell::sync::Lazy;
+use std::io::{Error, ErrorKind, Result};
+use std::time::Duration;
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .user_agent(concat!("vim-clap/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .unwrap_or_default()
+});
+
+/// Returns `true` if the whole `item` is an http(s) URL.
+pub fn is_url(item: &str) -> bool {
+    (item.starts_with("https://") || item.starts_with("http://"))
+        && !item.contains(char::is_whitespace)
+}
+
+/// Leading bytes of a remote resource.
+#[derive(Debug)]
+pub struct RemoteContent {
+    pub status: u16,
+    /// Value of the `Content-Type` header without the parameters like charset.
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+    /// Whether the body is cut off at the size limit.
+    pub truncated: bool,
+}
+
+impl RemoteContent {
+    /// Returns `true` if the body is text, the content type is trusted unless it's absent
+    /// or generic, in which case the body is sniffed.
+    pub fn is_text(&self) -> bool {
+        match self.content_type.as_deref() {
+            Some(content_type) if content_type.starts_with("text/") => true,
+            Some(content_type) if is_textual_application(content_type) => true,
+            None | Some("application/octet-stream") => !is_binary(&self.body),
+            Some(_) => false,
+        }
+    }
+
+    /// Returns the content type, sniffed from the body if it's not declared.
+    pub fn mime_type(&self) -> &str {
+        self.content_type
+            .as_deref()
+            .unwrap_or_else(|| mime_type(&self.body))
+    }
+
+    /// Returns the Vim syntax of the text body.
+    pub fn syntax(&self) -> Option<&'static str> {
+        let content_type = self.content_type.as_deref()?;
+        let subtype = content_type.rsplit('/').next()?;
+        let subtype = subtype.rsplit('+').next().unwrap_or(subtype);
+        let syntax = match subtype {
+            "html" => "html",
+            "xml" => "xml",
+            "json" => "json",
+            "javascript" | "ecmascript" => "javascript",
+            "css" => "css",
+            "markdown" | "x-markdown" => "markdown",
+            "yaml" | "x-yaml" => "yaml",
+            "toml" => "toml",
+            "csv" => "csv",
+            _ => return None,
+        };
+        Some(syntax)
+    }
+}
+
+fn is_textual_application(content_type: &str) -> bool {
+    const TEXTUAL: &[&str] = &["json", "xml", "javascript", "ecmascript", "yaml", "toml"];
+    content_type.starts_with("application/")
+        && TEXTUAL.iter().any(|textual| content_type.contains(textual))
+}
+
+fn into_io_error(err: reqwest::Error) -> Error {
+    let kind = if err.is_timeout() {
+        ErrorKind::TimedOut
+    } else {
+        ErrorKind::Other
+    };
+    Error::new(kind, err)
+}
+
+/// Fetches at most `max_bytes` bytes of `url`, the request fails once `timeout` elapses.
+pub async fn fetch(url: &str, timeout: Duration, max_bytes: usize) -> Result<RemoteContent> {
+    let mut response = CLIENT
+        .get(url)
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(into_io_error)?;
+
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty());
+
+    let mut body = Vec::new();
+    let mut truncated = false;
+    while let Some(chunk) = response.chunk().await.map_err(into_io_error)? {
+        body.extend_from_slice(&chunk);
+        if body.len() >= max_bytes {
+            truncated = body.len() > max_bytes;
+            body.truncate(max_bytes);
+            break;
+        }
+    }
+
+    Ok(RemoteContent {
+        status,
+        content_type,
+        body,
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_content() {
+        assert!(is_url("https://github.com/liuchengxu/vim-clap"));
+        assert!(!is_url("https://github.com/liuchengxu/vim-clap is great"));
+        assert!(!is_url("ftp://example.com/file"));
+
+        let remote = |content_type: Option<&str>, body: &[u8]| RemoteContent {
+            status: 200,
+            content_type: content_type.map(Into::into),
+            body: body.to_vec(),
+            truncated: false,
+        };
+
+        let html = remote(Some("text/html"), b"<html></html>");
+        assert!(html.is_text());
+        assert_eq!(html.syntax(), Some("html"));
+
+        let feed = remote(Some("application/atom+xml"), b"<feed/>");
+        assert!(feed.is_text());
+        assert_eq!(feed.syntax(), Some("xml"));
+
+        let png = remote(Some("image/png"), b"\x89PNG\r\n\x1a\n\0\0");
+        assert!(!png.is_text());
+        assert_eq!(png.mime_type(), "image/png");
+
+        let unknown_text = remote(None, b"plain text");
+        assert!(unknown_text.is_text());
+        assert_eq!(unknown_text.syntax(), None);
+
+        let unknown_binary = remote(Some("application/octet-stream"), b"\x89PNG\r\n\x1a\n\0\0");
+        assert!(!unknown_binary.is_text());
+    }
+}
@@ -0,0 +1,321 @@
+//! Resource limits of the spawned external tools, e.g., rg, ctags and git, so that a
+//! runaway command can not freeze the machine.
+//!
+//! The CPU time and memory are limited by `setrlimit` in the child process, which
+//! is also inherited by the grandchildren of a shell command. The wall-clock time is
+//! enforced by polling the child process, the whole process group is killed once
+//! the timeout is reached.
+
+use crate::config::ProcessLimitsConfig;
+use std::io::Read;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::time::{Duration, Instant};
+
+fn process_limits() -> Option<ProcessLimitsConfig> {
+    crate::config::try_config().map(|config| config.process_limits.clone())
+}
+
+/// Returns the wall-clock timeout of the commands creating the provider source.
+pub fn timeout() -> Option<Duration> {
+    process_limits()
+        .and_then(|limits| limits.timeout)
+        .map(Duration::from_secs)
+}
+
+#[cfg(unix)]
+fn apply_in_child(max_cpu_time: Option<u64>, max_memory: Option<u64>) -> std::io::Result<()> {
+    if let Some(secs) = max_cpu_time {
+        // SIGXCPU is sent on the soft limit and SIGKILL on the hard limit.
+        let rlimit = libc::rlimit {
+            rlim_cur: secs as libc::rlim_t,
+            rlim_max: (secs + 1) as libc::rlim_t,
+        };
+        if unsafe { libc::setrlimit(libc::RLIMIT_CPU, &rlimit) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+
+    if let Some(mib) = max_memory {
+        let bytes = (mib * 1024 * 1024) as libc::rlim_t;
+        let rlimit = libc::rlimit {
+            rlim_cur: bytes,
+            rlim_max: bytes,
+        };
+        if unsafe { libc::setrlimit(libc::RLIMIT_AS, &rlimit) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// Extension of the process builders for applying the configured resource limits.
+pub trait LimitResources {
+    /// Applies `process-limits` in the config to the process to spawn.
+    ///
+    /// No-op on the non-Unix platforms.
+    fn limit_resources(&mut self) -> &mut Self;
+
+    /// Puts the process in its own process group so that it can be killed along with
+    /// its children by [`kill_process_group`], e.g., the search jobs killed on timeout.
+    ///
+    /// No-op on the non-Unix platforms.
+    fn killable(&mut self) -> &mut Self;
+}
+
+impl LimitResources for std::process::Command {
+    #[cfg(unix)]
+    fn limit_resources(&mut self) -> &mut Self {
+        use std::os::unix::process::CommandExt;
+
+        if let Some(ProcessLimitsConfig {
+            max_cpu_time,
+            max_memory,
+            ..
+        }) = process_limits()
+        {
+            // SAFETY: only the async-signal-safe functions are called in the child.
+            unsafe {
+                self.pre_exec(move || apply_in_child(max_cpu_time, max_memory));
+            }
+        }
+        self
+    }
+
+    #[cfg(not(unix))]
+    fn limit_resources(&mut self) -> &mut Self {
+        self
+    }
+
+    #[cfg(unix)]
+    fn killable(&mut self) -> &mut Self {
+        use std::os::unix::process::CommandExt;
+
+        self.process_group(0)
+    }
+
+    #[cfg(not(unix))]
+    fn killable(&mut self) -> &mut Self {
+        self
+    }
+}
+
+impl LimitResources for tokio::process::Command {
+    #[cfg(unix)]
+    fn limit_resources(&mut self) -> &mut Self {
+        if let Some(ProcessLimitsConfig {
+            max_cpu_time,
+            max_memory,
+            ..
+        }) = process_limits()
+        {
+            // SAFETY: only the async-signal-safe functions are called in the child.
+            unsafe {
+                self.pre_exec(move || apply_in_child(max_cpu_time, max_memory));
+            }
+        }
+        self
+    }
+
+    #[cfg(not(unix))]
+    fn limit_resources(&mut self) -> &mut Self {
+        self
+    }
+
+    #[cfg(unix)]
+    fn killable(&mut self) -> &mut Self {
+        // `tokio::process::Command::process_group` is still unstable.
+        // SAFETY: only the async-signal-safe functions are called in the child.
+        unsafe {
+            self.pre_exec(|| {
+                if libc::setpgid(0, 0) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        self
+    }
+
+    #[cfg(not(unix))]
+    fn killable(&mut self) -> &mut Self {
+        self
+    }
+}
+
+/// Kills the process group of `pid` created by [`LimitResources::killable`].
+#[cfg(unix)]
+pub fn kill_process_group(pid: u32) {
+    unsafe {
+        libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn kill_process_group(_pid: u32) {}
+
+pub(crate) fn timed_out_error(timeout: Duration) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::TimedOut,
+        format!("Command killed after timeout of {}s", timeout.as_secs()),
+    )
+}
+
+/// Waits for the child process, which will be killed if `timeout` is reached.
+pub fn wait_with_timeout(
+    child: &mut Child,
+    timeout: Option<Duration>,
+) -> std::io::Result<ExitStatus> {
+    if timeout.is_none() {
+        return child.wait();
+    }
+    wait_interruptible(child, timeout, || false)
+}
+
+/// Waits for the child process like [`wait_with_timeout`], the process is killed as
+/// well once `interrupted` returns `true`.
+pub fn wait_interruptible(
+    child: &mut Child,
+    timeout: Option<Duration>,
+    interrupted: impl Fn() -> bool,
+) -> std::io::Result<ExitStatus> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+    loop {
+        if let Some(exit_status) = child.try_wait()? {
+            return Ok(exit_status);
+        }
+
+        let timed_out = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if timed_out || interrupted() {
+            kill_process_group(child.id());
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(match timeout.filter(|_| timed_out) {
+                Some(timeout) => timed_out_error(timeout),
+                None => std::io::Error::new(
+                    std::io::ErrorKind::Interrupted,
+                    "Command killed on interruption",
+                ),
+            });
+        }
+
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Executes `cmd` like [`Command::output`], the process is killed if `timeout` is reached.
+///
+/// The resource limits are applied and both pipes are drained on their own threads so
+/// that the child can't block on a full pipe.
+pub fn output_with_timeout(
+    cmd: &mut Command,
+    timeout: Option<Duration>,
+) -> std::io::Result<Output> {
+    output_interruptible(cmd, timeout, || false)
+}
+
+/// Executes `cmd` like [`output_with_timeout`], the process is killed as well once
+/// `interrupted` returns `true`.
+pub fn output_interruptible(
+    cmd: &mut Command,
+    timeout: Option<Duration>,
+    interrupted: impl Fn() -> bool,
+) -> std::io::Result<Output> {
+    let mut child = cmd
+        .limit_resources()
+        .killable()
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    fn drain(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<Vec<u8>> {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    }
+
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let status = wait_interruptible(&mut child, timeout, interrupted)?;
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Async version of [`wait_with_timeout`].
+pub async fn wait_with_timeout_async(
+    child: &mut tokio::process::Child,
+    timeout: Option<Duration>,
+) -> std::io::Result<ExitStatus> {
+    let Some(timeout) = timeout else {
+        return child.wait().await;
+    };
+
+    match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(exit_status) => exit_status,
+        Err(_) => {
+            if let Some(pid) = child.id() {
+                kill_process_group(pid);
+            }
+            let _ = child.kill().await;
+            Err(timed_out_error(timeout))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wait_with_timeout() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("5")
+            .spawn()
+            .unwrap();
+        let err = wait_with_timeout(&mut child, Some(Duration::from_millis(100))).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        assert!(wait_with_timeout(&mut child, Some(Duration::from_secs(5)))
+            .unwrap()
+            .success());
+
+        let mut child = std::process::Command::new("sleep")
+            .arg("5")
+            .spawn()
+            .unwrap();
+        let err = wait_interruptible(&mut child, None, || true).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Interrupted);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output_with_timeout() {
+        let output = output_with_timeout(
+            Command::new("sh").args(["-c", "echo out; echo err >&2"]),
+            Some(Duration::from_secs(5)),
+        )
+        .unwrap();
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+
+        let err = output_with_timeout(
+            Command::new("sleep").arg("5"),
+            Some(Duration::from_millis(100)),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    }
+}
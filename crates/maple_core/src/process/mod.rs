@@ -1,8 +1,10 @@
+pub mod limits;
 pub mod subprocess;
 pub mod tokio;
 
-use crate::cache::{push_cache_digest, Digest};
+use crate::cache::{push_cache_digest, write_atomically, CacheChecksum, Digest};
 use crate::datastore::{generate_cache_file_path, CACHE_INFO_IN_MEMORY};
+use crate::process::limits::LimitResources;
 use icon::Icon;
 use printer::println_json;
 use rayon::prelude::*;
@@ -17,7 +19,7 @@ use utils::{count_lines, read_first_lines};
 ///
 /// This can work with the piped command, e.g., `git ls-files | uniq`.
 pub fn shell_command(shell_cmd: &str) -> Command {
-    if cfg!(target_os = "windows") {
+    let mut cmd = if cfg!(target_os = "windows") {
         let mut cmd = Command::new("cmd");
         cmd.args(["/C", shell_cmd]);
         cmd
@@ -25,7 +27,9 @@ pub fn shell_command(shell_cmd: &str) -> Command {
         let mut cmd = Command::new("bash");
         cmd.arg("-c").arg(shell_cmd);
         cmd
-    }
+    };
+    cmd.limit_resources().killable();
+    cmd
 }
 
 /// Executes the command and redirects the output to a file.
@@ -39,7 +43,8 @@ pub fn write_stdout_to_file<P: AsRef<Path>>(
         .truncate(true)
         .open(output_file)?;
 
-    let exit_status = cmd.stdout(file).spawn()?.wait()?;
+    let mut child = cmd.stdout(file).spawn()?;
+    let exit_status = limits::wait_with_timeout(&mut child, limits::timeout())?;
 
     if exit_status.success() {
         Ok(())
@@ -128,9 +133,12 @@ impl ShellCommand {
         let cache_filename = utils::calculate_hash(&self);
         let cache_file = generate_cache_file_path(cache_filename.to_string())?;
 
-        std::fs::File::create(&cache_file)?.write_all(cmd_stdout)?;
+        write_atomically(&cache_file, |temp| {
+            std::fs::File::create(temp)?.write_all(cmd_stdout)
+        })?;
 
-        let digest = Digest::new(self, total, cache_file.clone());
+        let checksum = CacheChecksum::from_bytes(cmd_stdout);
+        let digest = Digest::new(self, total, cache_file.clone(), checksum);
 
         push_cache_digest(digest);
 
@@ -256,7 +264,9 @@ impl<'a> CacheableCommand<'a> {
     pub fn execute(&mut self) -> std::io::Result<ExecInfo> {
         let cache_file_path = self.shell_cmd.cache_file_path()?;
 
-        write_stdout_to_file(self.std_cmd, &cache_file_path)?;
+        write_atomically(&cache_file_path, |temp| {
+            write_stdout_to_file(self.std_cmd, temp)
+        })?;
 
         let lines_iter = read_first_lines(&cache_file_path, 100)?;
         let lines = if let Some(icon_kind) = self.icon.icon_kind() {
@@ -270,7 +280,13 @@ impl<'a> CacheableCommand<'a> {
         // Store the cache file if the total number of items exceeds the threshold, so that the
         // cache can be reused if the identical command is executed again.
         if total > self.output_threshold {
-            let digest = Digest::new(self.shell_cmd.clone(), total, cache_file_path.clone());
+            let checksum = CacheChecksum::from_file(&cache_file_path)?;
+            let digest = Digest::new(
+                self.shell_cmd.clone(),
+                total,
+                cache_file_path.clone(),
+                checksum,
+            );
 
             {
                 let cache_info = crate::datastore::CACHE_INFO_IN_MEMORY.clone();
@@ -1,5 +1,6 @@
 //! Wrapper of [`tokio::process::Command`].
 
+use crate::process::limits::{self, LimitResources};
 use crate::process::process_output;
 use std::path::Path;
 use tokio::process::Command;
@@ -15,7 +16,8 @@ pub async fn write_stdout_to_file<P: AsRef<Path>>(
         .truncate(true)
         .open(output_file)?;
 
-    let exit_status = cmd.stdout(file).spawn()?.wait().await?;
+    let mut child = cmd.stdout(file).spawn()?;
+    let exit_status = limits::wait_with_timeout_async(&mut child, limits::timeout()).await?;
 
     if exit_status.success() {
         Ok(())
@@ -34,7 +36,7 @@ pub async fn write_stdout_to_file<P: AsRef<Path>>(
 ///
 /// This can work with the piped command, e.g., `git ls-files | uniq`.
 pub fn shell_command(shell_cmd: impl AsRef<str>) -> Command {
-    if cfg!(target_os = "windows") {
+    let mut cmd = if cfg!(target_os = "windows") {
         let mut cmd = Command::new("cmd");
         cmd.args(["/C", shell_cmd.as_ref()]);
         cmd
@@ -42,7 +44,9 @@ pub fn shell_command(shell_cmd: impl AsRef<str>) -> Command {
         let mut cmd = Command::new("bash");
         cmd.arg("-c").arg(shell_cmd.as_ref());
         cmd
-    }
+    };
+    cmd.limit_resources().killable();
+    cmd
 }
 
 /// Unit type wrapper for [`tokio::process::Command`].
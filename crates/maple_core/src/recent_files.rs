@@ -4,6 +4,7 @@ use matcher::{Bonus, MatcherBuilder};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::path::Path;
 
 // 3600 seconds
@@ -15,6 +16,24 @@ const MONTH: i64 = DAY * 30;
 /// Maximum number of recent files.
 const MAX_ENTRIES: u64 = 10_000;
 
+/// Returns the frecent score of an entry visited `visits` times, the score decays as
+/// the time since `last_visit` grows.
+pub fn frecent_score(visits: u64, last_visit: UtcTime, now: UtcTime) -> u64 {
+    let duration = now.signed_duration_since(last_visit).num_seconds();
+
+    if duration < HOUR {
+        visits * 4
+    } else if duration < DAY {
+        visits * 2
+    } else if duration < WEEK {
+        visits * 3 / 2
+    } else if duration < MONTH {
+        visits / 2
+    } else {
+        visits / 4
+    }
+}
+
 /// Preference for sorting the recent files.
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub enum SortPreference {
@@ -41,7 +60,7 @@ pub struct FrecentEntry {
 
 impl PartialEq for FrecentEntry {
     fn eq(&self, other: &Self) -> bool {
-        self.fpath == other.fpath
+        paths::path_case_key(&self.fpath) == paths::path_case_key(&other.fpath)
     }
 }
 
@@ -85,20 +104,7 @@ impl FrecentEntry {
     /// Updates the frecent score.
     pub fn update_frecent(&mut self, at: Option<UtcTime>) {
         let now = at.unwrap_or_else(Utc::now);
-
-        let duration = now.signed_duration_since(self.last_visit).num_seconds();
-
-        self.frecent_score = if duration < HOUR {
-            self.visits * 4
-        } else if duration < DAY {
-            self.visits * 2
-        } else if duration < WEEK {
-            self.visits * 3 / 2
-        } else if duration < MONTH {
-            self.visits / 2
-        } else {
-            self.visits / 4
-        };
+        self.frecent_score = frecent_score(self.visits, self.last_visit, now);
     }
 
     /// Add a bonus score based on cwd.
@@ -133,17 +139,21 @@ impl Default for SortedRecentFiles {
 }
 
 impl SortedRecentFiles {
-    /// Deletes the invalid ones from current entries.
+    /// Deletes the invalid and duplicate ones from current entries, the first one of
+    /// the entries for the same file is kept.
     ///
     /// Used when loading from the disk.
     pub fn remove_invalid_entries(self) -> Self {
+        let mut seen = HashSet::new();
         Self {
             entries: self
                 .entries
                 .into_iter()
                 .filter(|entry| {
                     let path = Path::new(&entry.fpath);
-                    path.exists() && path.is_file()
+                    path.exists()
+                        && path.is_file()
+                        && seen.insert(paths::path_case_key(&entry.fpath).into_owned())
                 })
                 .collect(),
             ..self
@@ -188,12 +198,21 @@ impl SortedRecentFiles {
         filter::par_filter(source_items, &matcher)
     }
 
+    /// Recomputes the frecent scores as the time goes by and sorts the entries again.
+    pub fn decay_frecency(&mut self) {
+        let now = Utc::now();
+        for entry in self.entries.iter_mut() {
+            entry.update_frecent(Some(now));
+        }
+        self.entries.sort_unstable_by(|a, b| b.cmp(a));
+    }
+
     /// Updates or inserts a new entry in a sorted way.
     pub fn upsert(&mut self, file: String) {
         match self
             .entries
             .iter()
-            .position(|entry| entry.fpath.as_str() == file.as_str())
+            .position(|entry| paths::path_case_key(&entry.fpath) == paths::path_case_key(&file))
         {
             Some(pos) => FrecentEntry::refresh_now(&mut self.entries[pos]),
             None => {
@@ -249,4 +268,14 @@ mod tests {
             ]
         );
     }
+    #[test]
+    fn test_upsert_path_case() {
+        let mut sorted_recent_files = SortedRecentFiles::default();
+
+        sorted_recent_files.upsert("/home/xlc/src/Foo.rs".to_string());
+        sorted_recent_files.upsert("/home/xlc/src/foo.rs".to_string());
+
+        let expected_len = if paths::CASE_INSENSITIVE_PATHS { 1 } else { 2 };
+        assert_eq!(sorted_recent_files.len(), expected_len);
+    }
 }
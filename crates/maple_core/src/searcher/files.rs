@@ -74,10 +74,15 @@ pub async fn search(query: String, hidden: bool, matcher: Matcher, search_contex
         line_width,
         stop_signal,
         item_pool_size,
+        layout,
+        unique_path_suffix,
+        no_matches_hints,
     } = search_context;
 
     let number = item_pool_size;
-    let progressor = VimProgressor::new(vim, stop_signal.clone());
+    let progressor = VimProgressor::new(vim, stop_signal.clone())
+        .with_no_matches_hints(no_matches_hints.with_roots(paths.clone()))
+        .with_top_match_ghost_text();
 
     let (sender, mut receiver) = unbounded_channel();
 
@@ -92,7 +97,9 @@ pub async fn search(query: String, hidden: bool, matcher: Matcher, search_contex
     let mut total_matched = 0usize;
     let mut total_processed = 0usize;
 
-    let printer = Printer::new(line_width, icon);
+    let printer = Printer::new(line_width, icon)
+        .with_layout(layout)
+        .with_path_suffix(unique_path_suffix.then(Default::default));
     let mut best_items = BestItems::new(printer, number, progressor, Duration::from_millis(200));
 
     let now = std::time::Instant::now();
@@ -107,6 +114,11 @@ pub async fn search(query: String, hidden: bool, matcher: Matcher, search_contex
                 total_matched += 1;
                 total_processed += 1;
 
+                // The index is not shared until the search is finished.
+                if let Some(index) = best_items.printer.path_suffix.as_mut() {
+                    Arc::make_mut(index).insert(matched_item.item.raw_text());
+                }
+
                 best_items.on_new_match(matched_item, total_matched, total_processed);
             }
             None => {
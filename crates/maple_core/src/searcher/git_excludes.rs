@@ -0,0 +1,237 @@
+//! Excludes of git not handled by [`ignore::WalkBuilder`], which are resolved per
+//! repository, including the nested ones like submodules:
+//!
+//! - `core.excludesFile`, the repository local config takes precedence over the global one.
+//! - `info/exclude` of a submodule, whose `.git` is a file pointing to the actual git dir.
+
+use dirs::Dirs;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Returns the git directory of the repository `repo_root`.
+///
+/// `.git` is a file instead of a directory in a submodule or a linked worktree,
+/// which contains the path of the actual git directory.
+fn resolve_git_dir(repo_root: &Path) -> Option<PathBuf> {
+    let dot_git = repo_root.join(".git");
+
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+
+    let contents = std::fs::read_to_string(&dot_git).ok()?;
+    let git_dir = contents.lines().next()?.strip_prefix("gitdir: ")?.trim();
+
+    Some(repo_root.join(git_dir))
+}
+
+/// Parses the value of `core.excludesFile` from the contents of a git config file.
+fn parse_excludes_file(git_config: &str) -> Option<PathBuf> {
+    let mut in_core = false;
+    let mut excludes_file = None;
+
+    for line in git_config.lines() {
+        let mut line = line.trim();
+
+        if let Some(section) = line.strip_prefix('[') {
+            let Some((name, rest)) = section.split_once(']') else {
+                continue;
+            };
+            in_core = name.trim().eq_ignore_ascii_case("core");
+            // The variable can follow the section header on the same line.
+            line = rest.trim();
+        }
+
+        if !in_core {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if !key.trim().eq_ignore_ascii_case("excludesfile") {
+            continue;
+        }
+
+        let value = value.trim();
+        let value = match value.strip_prefix('"') {
+            Some(quoted) => quoted.split('"').next().unwrap_or_default(),
+            None => value.split(['#', ';']).next().unwrap_or_default().trim(),
+        };
+        // The last one wins, as git does.
+        excludes_file = Some(paths::expand_tilde(value));
+    }
+
+    excludes_file
+}
+
+fn read_excludes_file(git_config: &Path) -> Option<PathBuf> {
+    parse_excludes_file(&std::fs::read_to_string(git_config).ok()?)
+}
+
+/// Returns the global `core.excludesFile`, which is `$XDG_CONFIG_HOME/git/ignore` if not set.
+fn global_excludes_file() -> PathBuf {
+    let xdg_config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Dirs::base().home_dir().join(".config"));
+
+    let configured = match std::env::var_os("GIT_CONFIG_GLOBAL") {
+        Some(git_config) => read_excludes_file(Path::new(&git_config)),
+        // `~/.gitconfig` is read after the XDG one, hence takes precedence.
+        None => read_excludes_file(&Dirs::base().home_dir().join(".gitconfig"))
+            .or_else(|| read_excludes_file(&xdg_config_home.join("git").join("config"))),
+    };
+
+    configured.unwrap_or_else(|| xdg_config_home.join("git").join("ignore"))
+}
+
+/// Matcher of the excludes, shared by the threads of a parallel walk.
+#[derive(Debug)]
+pub(super) struct GitExcludes {
+    /// Global `core.excludesFile`, `None` if `core.excludesFile` is not respected.
+    global_excludes_file: Option<PathBuf>,
+    /// Whether to respect `info/exclude` of the submodules.
+    info_exclude: bool,
+    /// Matcher of the repository containing each visited directory, `None` if the
+    /// directory is not in a repository or nothing is excluded in the repository.
+    dir_matchers: Mutex<HashMap<PathBuf, Option<Arc<Gitignore>>>>,
+}
+
+impl GitExcludes {
+    pub fn new(core_excludes_file: bool, info_exclude: bool) -> Self {
+        Self {
+            global_excludes_file: core_excludes_file.then(global_excludes_file),
+            info_exclude,
+            dir_matchers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns `true` if the entry `path` of the walk is excluded.
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        // The repository root is an entry of the parent repository.
+        path.parent()
+            .and_then(|dir| self.dir_matcher(dir))
+            .map_or(false, |matcher| {
+                matcher
+                    .matched_path_or_any_parents(path, is_dir)
+                    .is_ignore()
+            })
+    }
+
+    fn dir_matcher(&self, dir: &Path) -> Option<Arc<Gitignore>> {
+        if let Some(matcher) = self.dir_matchers.lock().get(dir) {
+            return matcher.clone();
+        }
+
+        let matcher = if dir.join(".git").exists() {
+            self.build_repo_matcher(dir)
+        } else {
+            dir.parent().and_then(|parent| self.dir_matcher(parent))
+        };
+
+        self.dir_matchers
+            .lock()
+            .insert(dir.to_path_buf(), matcher.clone());
+
+        matcher
+    }
+
+    fn build_repo_matcher(&self, repo_root: &Path) -> Option<Arc<Gitignore>> {
+        let git_dir = resolve_git_dir(repo_root);
+        let mut builder = GitignoreBuilder::new(repo_root);
+
+        if let Some(global_excludes_file) = &self.global_excludes_file {
+            let local_excludes_file = git_dir
+                .as_ref()
+                .and_then(|git_dir| read_excludes_file(&git_dir.join("config")))
+                // Relative path is relative to the working tree root, as git does.
+                .map(|excludes_file| repo_root.join(excludes_file));
+            let excludes_file = local_excludes_file.unwrap_or_else(|| global_excludes_file.clone());
+            // The excludes file does not have to exist.
+            let _ = builder.add(excludes_file);
+        }
+
+        if self.info_exclude && repo_root.join(".git").is_file() {
+            if let Some(git_dir) = &git_dir {
+                let _ = builder.add(git_dir.join("info").join("exclude"));
+            }
+        }
+
+        builder
+            .build()
+            .ok()
+            .filter(|matcher| !matcher.is_empty())
+            .map(Arc::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_excludes_file() {
+        let git_config = r#"
+[core]
+	repositoryformatversion = 0
+	excludesFile = /tmp/clap_ignore
+"#;
+        assert_eq!(
+            parse_excludes_file(git_config),
+            Some(PathBuf::from("/tmp/clap_ignore"))
+        );
+
+        let git_config = r#"
+[core]
+	excludesfile = "relative/ignore" ; comment
+"#;
+        assert_eq!(
+            parse_excludes_file(git_config),
+            Some(PathBuf::from("relative/ignore"))
+        );
+
+        let git_config = r#"
+[core]
+	excludesFile = /tmp/first # comment
+[alias]
+	excludesFile = /tmp/alias
+[core] excludesFile = /tmp/last
+"#;
+        assert_eq!(
+            parse_excludes_file(git_config),
+            Some(PathBuf::from("/tmp/last"))
+        );
+
+        assert_eq!(parse_excludes_file("[user]\n\texcludesFile = x"), None);
+        assert_eq!(parse_excludes_file("[core]\n\tbare = false"), None);
+    }
+
+    #[test]
+    fn test_submodule_excludes() {
+        let root = std::env::temp_dir().join(format!("clap_git_excludes_{}", std::process::id()));
+        let submodule = root.join("sub");
+        let git_dir = root.join(".git").join("modules").join("sub");
+        std::fs::create_dir_all(git_dir.join("info")).unwrap();
+        std::fs::create_dir_all(&submodule).unwrap();
+        std::fs::write(submodule.join(".git"), "gitdir: ../.git/modules/sub\n").unwrap();
+        std::fs::write(git_dir.join("info").join("exclude"), "*.log\n").unwrap();
+        std::fs::write(git_dir.join("config"), "[core]\n\texcludesFile = ignore\n").unwrap();
+        std::fs::write(submodule.join("ignore"), "*.tmp\n").unwrap();
+
+        let git_excludes = GitExcludes::new(true, true);
+        let excluded = |path: &str| git_excludes.is_excluded(&root.join(path), false);
+        let results = [
+            excluded("sub/a.log"),
+            excluded("sub/a.tmp"),
+            excluded("sub/a.rs"),
+            excluded("a.log"),
+        ];
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(results, [true, true, false, false]);
+    }
+}
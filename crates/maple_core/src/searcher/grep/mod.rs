@@ -1,7 +1,10 @@
+mod replace;
 mod stoppable_searcher;
 
-pub use self::stoppable_searcher::search;
+pub use self::replace::Replacement;
+pub use self::stoppable_searcher::{resolve_display_path, search, ExtraRoot, LineMatcher};
 use self::stoppable_searcher::{FileResult, SearcherMessage, StoppableSearchImpl, UPDATE_INTERVAL};
+use crate::searcher::WalkConfig;
 use matcher::Matcher;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
@@ -20,10 +23,21 @@ pub async fn cli_search(paths: Vec<PathBuf>, matcher: Matcher) -> SearchResult {
     let (sender, mut receiver) = unbounded_channel();
 
     let stop_signal = Arc::new(AtomicBool::new(false));
+    let search_root = paths[0].clone();
 
     std::thread::Builder::new()
         .name("searcher-worker".into())
-        .spawn(move || StoppableSearchImpl::new(paths, matcher, sender, stop_signal).run())
+        .spawn(move || {
+            StoppableSearchImpl::new(
+                paths,
+                search_root,
+                WalkConfig::default(),
+                matcher.into(),
+                sender,
+                stop_signal,
+            )
+            .run()
+        })
         .expect("Failed to spawn searcher worker thread");
 
     let mut matches = Vec::new();
@@ -0,0 +1,134 @@
+use regex::{Regex, RegexBuilder};
+use std::borrow::Cow;
+use types::{ClapItem, ItemHighlight, Query};
+
+/// Highlight group of the replaced text in the display window.
+const REPLACE_HIGHLIGHT_GROUP: &str = "ClapReplacePreview";
+
+/// Substitution previewed in the grep results, nothing is written to disk.
+#[derive(Debug, Clone)]
+pub struct Replacement {
+    regex: Regex,
+    replacement: String,
+}
+
+impl Replacement {
+    /// Constructs a new instance replacing the first positive term in `query` with
+    /// `replacement`, e.g., `foo` of `'foo !bar`.
+    ///
+    /// The pattern is matched literally in smart case, i.e., case insensitive unless
+    /// the pattern contains an uppercase character.
+    pub fn new(query: &str, replacement: String) -> Option<Self> {
+        let Query {
+            word_terms,
+            exact_terms,
+            fuzzy_terms,
+            ..
+        } = Query::from(query);
+
+        let pattern = word_terms
+            .into_iter()
+            .map(|term| term.text)
+            .chain(exact_terms.into_iter().map(|term| term.text))
+            .chain(fuzzy_terms.into_iter().map(|term| term.text))
+            .find(|text| !text.is_empty())?;
+
+        let regex = RegexBuilder::new(&regex::escape(&pattern))
+            .case_insensitive(!pattern.chars().any(char::is_uppercase))
+            .build()
+            .ok()?;
+
+        Some(Self { regex, replacement })
+    }
+
+    /// Constructs a new instance replacing the matches of `regex`, used by the raw query.
+    pub fn from_regex(regex: Regex, replacement: String) -> Self {
+        Self { regex, replacement }
+    }
+
+    /// Returns the line with all the occurrences of the pattern replaced and the byte
+    /// ranges `(start, length)` of the replaced text in the new line, `None` if the
+    /// pattern does not occur in `line`.
+    pub fn apply(&self, line: &str) -> Option<(String, Vec<(usize, usize)>)> {
+        let mut new_line = String::with_capacity(line.len());
+        let mut ranges = Vec::new();
+        let mut last_end = 0;
+        let mut matched = false;
+
+        for m in self.regex.find_iter(line) {
+            matched = true;
+            new_line.push_str(&line[last_end..m.start()]);
+            if !self.replacement.is_empty() {
+                ranges.push((new_line.len(), self.replacement.len()));
+            }
+            new_line.push_str(&self.replacement);
+            last_end = m.end();
+        }
+
+        if !matched {
+            return None;
+        }
+
+        new_line.push_str(&line[last_end..]);
+
+        Some((new_line, ranges))
+    }
+}
+
+/// Grep line whose text is displayed with the replacement applied.
+#[derive(Debug)]
+pub(super) struct ReplacedLine {
+    pub output_text: String,
+    /// Byte ranges of the replaced text in `output_text`.
+    pub replaced: Vec<(usize, usize)>,
+}
+
+impl ClapItem for ReplacedLine {
+    fn raw_text(&self) -> &str {
+        &self.output_text
+    }
+
+    fn output_text(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.output_text)
+    }
+
+    fn highlights(&self) -> Vec<ItemHighlight> {
+        self.replaced
+            .iter()
+            .map(|&(start, length)| ItemHighlight {
+                start,
+                length,
+                group: REPLACE_HIGHLIGHT_GROUP,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replacement() {
+        let replacement = Replacement::new("'foo !bar", "quux".into()).unwrap();
+        let (line, ranges) = replacement.apply("let Foo = foo(1);").unwrap();
+        assert_eq!(line, "let quux = quux(1);");
+        assert_eq!(ranges, vec![(4, 4), (11, 4)]);
+        assert!(replacement.apply("let baz = 1;").is_none());
+
+        // Smart case.
+        let replacement = Replacement::new("Foo", "Bar".into()).unwrap();
+        let (line, ranges) = replacement.apply("foo Foo").unwrap();
+        assert_eq!(line, "foo Bar");
+        assert_eq!(ranges, vec![(4, 3)]);
+
+        // Deleting the pattern.
+        let replacement = Replacement::new("foo", String::new()).unwrap();
+        assert_eq!(
+            replacement.apply("foo bar"),
+            Some((" bar".to_string(), vec![]))
+        );
+
+        assert!(Replacement::new("!foo", "bar".into()).is_none());
+    }
+}
@@ -1,3 +1,4 @@
+use super::replace::{ReplacedLine, Replacement};
 use crate::searcher::{walk_parallel, SearchContext, WalkConfig};
 use crate::stdio_server::VimProgressor;
 use filter::MatchedItem;
@@ -5,7 +6,10 @@ use grep_searcher::{sinks, BinaryDetection, SearcherBuilder};
 use icon::Icon;
 use ignore::{DirEntry, WalkState};
 use matcher::Matcher;
-use std::path::PathBuf;
+use paths::{InternedPath, PathInterner};
+use std::borrow::Cow;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -37,10 +41,60 @@ impl grep_matcher::Matcher for MatchEverything {
     }
 }
 
+/// How the lines are matched in the search.
+#[derive(Debug, Clone)]
+pub enum LineMatcher {
+    /// Fuzzy match the path and line with the query.
+    Fuzzy(Matcher),
+    /// Match the line with the regex verbatim, like `rg PATTERN`.
+    Raw(regex::Regex),
+}
+
+impl From<Matcher> for LineMatcher {
+    fn from(matcher: Matcher) -> Self {
+        Self::Fuzzy(matcher)
+    }
+}
+
+impl LineMatcher {
+    /// Returns the rank, the indices in path and the indices in line if `line` matches.
+    fn match_line(&self, path: &Path, line: &str) -> Option<(Rank, Vec<usize>, Vec<usize>)> {
+        match self {
+            Self::Fuzzy(matcher) => matcher
+                .match_file_result(path, line)
+                .map(|matched| (matched.rank, matched.exact_indices, matched.fuzzy_indices)),
+            Self::Raw(regex) => regex.find(line).map(|m| {
+                let start = line[..m.start()].chars().count();
+                // The empty match still needs an index for the column.
+                let len = m.as_str().chars().count().max(1);
+                (Rank::default(), Vec::new(), (start..start + len).collect())
+            }),
+        }
+    }
+
+    /// Returns the byte range of the match in `line`, from the first matched char to the
+    /// end of the last one, `None` if `line` does not match.
+    pub fn match_range(&self, path: &Path, line: &str) -> Option<Range<usize>> {
+        match self {
+            Self::Raw(regex) => regex
+                .find(line)
+                .filter(|m| !m.is_empty())
+                .map(|m| m.range()),
+            Self::Fuzzy(_) => {
+                let (_rank, _indices_in_path, indices_in_line) = self.match_line(path, line)?;
+                let (start, _) = line.char_indices().nth(*indices_in_line.first()?)?;
+                let (last, last_char) = line.char_indices().nth(*indices_in_line.last()?)?;
+                Some(start..last + last_char.len_utf8())
+            }
+        }
+    }
+}
+
 /// Represents an matched item by searching a file.
 #[derive(Debug, Clone)]
 pub struct FileResult {
-    pub path: PathBuf,
+    /// The directory is shared by all the results, expanded when the result is displayed.
+    pub path: InternedPath,
     pub line_number: u64,
     pub line: String,
     pub rank: Rank,
@@ -48,10 +102,96 @@ pub struct FileResult {
     pub indices_in_line: Vec<usize>,
 }
 
+/// Search root of grep apart from the current working directory.
+///
+/// The matches under it are displayed relative to the root, prefixed with the label of
+/// root, e.g., `[serde]/src/lib.rs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtraRoot {
+    pub label: String,
+    pub path: PathBuf,
+}
+
+impl ExtraRoot {
+    /// Returns the labelled roots of `paths` except for `search_root`.
+    ///
+    /// The root is labelled by its directory name, the duplicate names are numbered.
+    pub fn from_paths(paths: &[PathBuf], search_root: &Path) -> Vec<Self> {
+        let mut extra_roots: Vec<Self> = Vec::new();
+
+        for path in paths.iter().filter(|path| *path != search_root) {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string());
+            let mut label = format!("[{name}]");
+            let mut n = 1;
+            while extra_roots.iter().any(|root| root.label == label) {
+                n += 1;
+                label = format!("[{name}#{n}]");
+            }
+            extra_roots.push(Self {
+                label,
+                path: path.clone(),
+            });
+        }
+
+        extra_roots
+    }
+}
+
+/// Returns the path displayed in the search result.
+///
+/// The paths under `search_root` are displayed as relative paths, the ones from the extra
+/// roots are relative to their own root and prefixed with the label of root. The displayed
+/// path can be resolved by [`resolve_display_path`].
+pub(super) fn display_path<'a>(
+    path: &'a Path,
+    search_root: &Path,
+    extra_roots: &[ExtraRoot],
+) -> Cow<'a, Path> {
+    if let Ok(relative_path) = path.strip_prefix(search_root) {
+        return relative_path.into();
+    }
+
+    if let Some((root, relative_path)) = extra_roots
+        .iter()
+        .find_map(|root| Some((root, path.strip_prefix(&root.path).ok()?)))
+    {
+        return Path::new(&root.label).join(relative_path).into();
+    }
+
+    if let Ok(relative_home_path) = path.strip_prefix(dirs::Dirs::base().home_dir()) {
+        return Path::new("~").join(relative_home_path).into();
+    }
+
+    path.into()
+}
+
+/// Returns the absolute path of `fpath` displayed by [`display_path`].
+pub fn resolve_display_path(fpath: &str, search_root: &Path, extra_roots: &[ExtraRoot]) -> PathBuf {
+    let fpath = fpath.strip_prefix("./").unwrap_or(fpath);
+
+    if let Some((root, relative_path)) = extra_roots
+        .iter()
+        .find_map(|root| Some((root, Path::new(fpath).strip_prefix(&root.label).ok()?)))
+    {
+        return root.path.join(relative_path);
+    }
+
+    if fpath.starts_with('~') {
+        paths::expand_tilde(fpath)
+    } else {
+        search_root.join(fpath)
+    }
+}
+
 #[derive(Debug)]
 pub(super) struct StoppableSearchImpl {
     paths: Vec<PathBuf>,
-    matcher: Matcher,
+    search_root: PathBuf,
+    walk_config: WalkConfig,
+    matcher: LineMatcher,
     sender: UnboundedSender<SearcherMessage>,
     stop_signal: Arc<AtomicBool>,
 }
@@ -59,12 +199,16 @@ pub(super) struct StoppableSearchImpl {
 impl StoppableSearchImpl {
     pub(super) fn new(
         paths: Vec<PathBuf>,
-        matcher: Matcher,
+        search_root: PathBuf,
+        walk_config: WalkConfig,
+        matcher: LineMatcher,
         sender: UnboundedSender<SearcherMessage>,
         stop_signal: Arc<AtomicBool>,
     ) -> Self {
         Self {
             paths,
+            search_root,
+            walk_config,
             matcher,
             sender,
             stop_signal,
@@ -74,6 +218,8 @@ impl StoppableSearchImpl {
     pub(super) fn run(self) {
         let Self {
             paths,
+            search_root,
+            walk_config,
             matcher,
             sender,
             stop_signal,
@@ -83,14 +229,17 @@ impl StoppableSearchImpl {
             .binary_detection(BinaryDetection::quit(b'\x00'))
             .build();
 
-        let search_root = paths[0].clone();
+        let path_interner = Arc::new(PathInterner::default());
+        let extra_roots = Arc::new(ExtraRoot::from_paths(&paths, &search_root));
 
-        walk_parallel(paths, WalkConfig::default()).run(|| {
+        walk_parallel(paths, walk_config).run(|| {
             let mut searcher = searcher.clone();
             let matcher = matcher.clone();
             let sender = sender.clone();
             let stop_signal = stop_signal.clone();
             let search_root = search_root.clone();
+            let extra_roots = extra_roots.clone();
+            let path_interner = path_interner.clone();
             Box::new(move |entry: Result<DirEntry, ignore::Error>| -> WalkState {
                 if stop_signal.load(Ordering::SeqCst) {
                     return WalkState::Quit;
@@ -109,6 +258,9 @@ impl StoppableSearchImpl {
                     _ => return WalkState::Continue,
                 };
 
+                // Interned lazily as most of the files have no match at all.
+                let mut interned_path = None;
+
                 let result = searcher.search_path(
                     &MatchEverything,
                     entry.path(),
@@ -118,22 +270,20 @@ impl StoppableSearchImpl {
                             return Ok(sender.send(SearcherMessage::ProcessedOne).is_ok());
                         }
 
-                        let path = entry
-                            .path()
-                            .strip_prefix(&search_root)
-                            .unwrap_or_else(|_| entry.path());
+                        let path = display_path(entry.path(), &search_root, &extra_roots);
                         let line = line.trim();
-                        let maybe_file_result =
-                            matcher
-                                .match_file_result(path, line)
-                                .map(|matched| FileResult {
-                                    path: entry.path().to_path_buf(),
-                                    line_number,
-                                    line: line.to_string(),
-                                    rank: matched.rank,
-                                    indices_in_path: matched.exact_indices,
-                                    indices_in_line: matched.fuzzy_indices,
-                                });
+                        let maybe_file_result = matcher.match_line(&path, line).map(
+                            |(rank, indices_in_path, indices_in_line)| FileResult {
+                                path: interned_path
+                                    .get_or_insert_with(|| path_interner.intern(entry.path()))
+                                    .clone(),
+                                line_number,
+                                line: line.to_string(),
+                                rank,
+                                indices_in_path,
+                                indices_in_line,
+                            },
+                        );
 
                         let searcher_message = if let Some(file_result) = maybe_file_result {
                             SearcherMessage::Match(file_result)
@@ -182,7 +332,17 @@ impl BestFileResults {
     }
 }
 
-pub async fn search(query: String, matcher: Matcher, search_context: SearchContext) {
+/// Searches the paths in `search_context`, the paths in the results are displayed relative to `cwd`.
+///
+/// If `replacement` is given, the matched lines are displayed with the replacement applied.
+pub async fn search(
+    query: String,
+    cwd: PathBuf,
+    walk_config: WalkConfig,
+    matcher: LineMatcher,
+    search_context: SearchContext,
+    replacement: Option<Replacement>,
+) {
     let SearchContext {
         icon,
         line_width,
@@ -190,11 +350,17 @@ pub async fn search(query: String, matcher: Matcher, search_context: SearchConte
         paths,
         stop_signal,
         item_pool_size,
+        layout,
+        unique_path_suffix: _,
+        no_matches_hints,
     } = search_context;
 
-    let progressor = VimProgressor::new(vim, stop_signal.clone());
+    let progressor = VimProgressor::new(vim, stop_signal.clone())
+        .with_no_matches_hints(no_matches_hints.with_roots(paths.clone()))
+        .with_top_match_ghost_text();
     let number = item_pool_size;
-    let search_root = paths[0].clone();
+    let search_root = cwd;
+    let extra_roots = ExtraRoot::from_paths(&paths, &search_root);
 
     let mut best_results = BestFileResults::new(number);
 
@@ -204,7 +370,18 @@ pub async fn search(query: String, matcher: Matcher, search_context: SearchConte
         .name("grep-worker".into())
         .spawn({
             let stop_signal = stop_signal.clone();
-            move || StoppableSearchImpl::new(paths, matcher, sender, stop_signal).run()
+            let search_root = search_root.clone();
+            move || {
+                StoppableSearchImpl::new(
+                    paths,
+                    search_root,
+                    walk_config,
+                    matcher,
+                    sender,
+                    stop_signal,
+                )
+                .run()
+            }
         })
         .expect("Failed to spawn grep-worker thread");
 
@@ -228,26 +405,35 @@ pub async fn search(query: String, matcher: Matcher, search_context: SearchConte
 
                 if let Some(mut column) = maybe_column.copied() {
                     column += 1;
-                    let mut fmt_line = if let Ok(relative_path) = path.strip_prefix(&search_root) {
-                        format!("{}:{line_number}:{column}:", relative_path.display())
-                    } else {
-                        format!("{}:{line_number}:{column}:", path.display())
-                    };
+                    let path = path.to_path_buf();
+                    let path = display_path(&path, &search_root, &extra_roots);
+                    let mut fmt_line = format!("{}:{line_number}:{column}:", path.display());
                     let offset = fmt_line.len();
-                    fmt_line.push_str(line);
 
                     let mut indices = indices_in_path.clone();
-                    indices.extend(indices_in_line.iter().map(|x| *x + offset));
 
-                    let matched_item = MatchedItem::new(Arc::new(fmt_line), *rank, indices);
+                    let replaced = replacement.as_ref().and_then(|r| r.apply(line));
+                    let matched_item = if let Some((new_line, replaced)) = replaced {
+                        // The indices in the original line are meaningless after the replacement.
+                        fmt_line.push_str(&new_line);
+                        let item = ReplacedLine {
+                            output_text: fmt_line,
+                            replaced: replaced
+                                .into_iter()
+                                .map(|(start, length)| (start + offset, length))
+                                .collect(),
+                        };
+                        MatchedItem::new(Arc::new(item), *rank, indices)
+                    } else {
+                        fmt_line.push_str(line);
+                        indices.extend(indices_in_line.iter().map(|x| *x + offset));
+                        MatchedItem::new(Arc::new(fmt_line), *rank, indices)
+                    };
 
                     let line_number = *line_number as usize;
                     Some(printer::GrepResult {
                         matched_item,
-                        path: path
-                            .strip_prefix(&search_root)
-                            .unwrap_or(path)
-                            .to_path_buf(),
+                        path: path.into_owned(),
                         line_number,
                         column,
                         column_end: offset,
@@ -257,7 +443,7 @@ pub async fn search(query: String, matcher: Matcher, search_context: SearchConte
                 }
             })
             .collect();
-        printer::grep_results_to_display_lines(grep_results, line_width, icon)
+        printer::grep_results_to_display_lines(grep_results, line_width, icon, layout)
     };
 
     let now = std::time::Instant::now();
@@ -349,3 +535,69 @@ pub async fn search(query: String, matcher: Matcher, search_context: SearchConte
         "Searching is complete in {elapsed:?}ms"
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_path() {
+        let search_root = Path::new("/opt/project");
+        let extra_roots = ExtraRoot::from_paths(
+            &[
+                search_root.to_path_buf(),
+                "/opt/dependency".into(),
+                "/vendor/dependency".into(),
+            ],
+            search_root,
+        );
+        assert_eq!(extra_roots[0].label, "[dependency]");
+        assert_eq!(extra_roots[1].label, "[dependency#2]");
+
+        let display = |path: &str| display_path(Path::new(path), search_root, &extra_roots);
+        let resolve = |fpath: &Path| {
+            resolve_display_path(&fpath.to_string_lossy(), search_root, &extra_roots)
+        };
+
+        for (path, displayed) in [
+            ("/opt/project/src/lib.rs", "src/lib.rs"),
+            ("/opt/dependency/src/lib.rs", "[dependency]/src/lib.rs"),
+            ("/vendor/dependency/src/lib.rs", "[dependency#2]/src/lib.rs"),
+            ("/opt/other/src/lib.rs", "/opt/other/src/lib.rs"),
+        ] {
+            assert_eq!(display(path), Path::new(displayed));
+            assert_eq!(resolve(&display(path)), Path::new(path));
+        }
+
+        let home_dir = dirs::Dirs::base().home_dir();
+        let home_path = home_dir.join("other/src/lib.rs");
+        let displayed = display_path(&home_path, search_root, &extra_roots);
+        assert_eq!(displayed, Path::new("~/other/src/lib.rs"));
+        assert_eq!(resolve(&displayed), home_path);
+    }
+
+    #[test]
+    fn test_match_range() {
+        let path = Path::new("src/lib.rs");
+        let regex = |pattern| LineMatcher::Raw(regex::Regex::new(pattern).unwrap());
+
+        // The match across the word boundary.
+        let line = "let foo = bar;";
+        assert_eq!(regex(r"foo = b").match_range(path, line), Some(4..11));
+        // The match ending in the middle of a word.
+        assert_eq!(regex("fo").match_range(path, line), Some(4..6));
+        assert_eq!(regex("baz").match_range(path, line), None);
+
+        let fuzzy = |query| {
+            LineMatcher::Fuzzy(
+                matcher::MatcherBuilder::new()
+                    .match_scope(matcher::MatchScope::Full)
+                    .build(types::Query::from(query)),
+            )
+        };
+        // Multiple words of the query.
+        assert_eq!(fuzzy("foo bar").match_range(path, line), Some(4..13));
+        assert_eq!(fuzzy("'fo").match_range(path, line), Some(4..6));
+        assert_eq!(fuzzy("café").match_range(path, "let café = 1;"), Some(4..9));
+    }
+}
@@ -3,9 +3,14 @@ pub mod files;
 pub mod grep;
 pub mod tagfiles;
 
-use crate::stdio_server::Vim;
+mod git_excludes;
+
+use self::git_excludes::GitExcludes;
+use crate::stdio_server::{NoMatchesHints, Vim};
 use icon::Icon;
+use ignore::types::TypesBuilder;
 use ignore::{WalkBuilder, WalkParallel};
+use printer::Layout;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
@@ -26,6 +31,11 @@ pub struct SearchContext {
     pub vim: Vim,
     pub stop_signal: Arc<AtomicBool>,
     pub item_pool_size: usize,
+    pub layout: Layout,
+    /// Whether to display the file paths as their unique suffixes among the results.
+    pub unique_path_suffix: bool,
+    /// Hints displayed below the no matches message.
+    pub no_matches_hints: NoMatchesHints,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -46,6 +56,7 @@ pub struct WalkConfig {
     /// Whether to hide files listed in .gitignore in file picker and global search results. Defaults to true.
     pub git_ignore: bool,
     /// Enables reading global .gitignore, whose path is specified in git's config: `core.excludefile` option.
+    /// It's resolved per repository, including the submodules, the repository local config takes precedence.
     /// Whether to hide files listed in global .gitignore in file picker and global search results. Defaults to true.
     pub git_global: bool,
     /// Enables reading `.git/info/exclude` files.
@@ -54,6 +65,8 @@ pub struct WalkConfig {
     /// WalkBuilder options
     /// Maximum Depth to recurse directories in file picker and global search. Defaults to `None`.
     pub max_depth: Option<usize>,
+    /// Only walk the files matching these file types, e.g., `rust`. Defaults to empty (all files).
+    pub file_types: Vec<String>,
 }
 
 impl Default for WalkConfig {
@@ -67,27 +80,49 @@ impl Default for WalkConfig {
             git_global: true,
             git_exclude: true,
             max_depth: None,
+            file_types: Vec::new(),
         }
     }
 }
 
 fn walk_parallel(paths: Vec<PathBuf>, walk_config: WalkConfig) -> WalkParallel {
+    let git_excludes = GitExcludes::new(walk_config.git_global, walk_config.git_exclude);
     let mut builder = WalkBuilder::new(&paths[0]);
     for path in &paths[1..] {
         builder.add(path);
     }
+    if !walk_config.file_types.is_empty() {
+        let mut types_builder = TypesBuilder::new();
+        types_builder.add_defaults();
+        for file_type in &walk_config.file_types {
+            types_builder.select(file_type);
+        }
+        match types_builder.build() {
+            Ok(types) => {
+                builder.types(types);
+            }
+            Err(err) => tracing::error!(?err, "Failed to build the file types"),
+        }
+    }
     builder
         .hidden(walk_config.hidden)
         .parents(walk_config.parents)
         .ignore(walk_config.ignore)
         .follow_links(walk_config.follow_symlinks)
         .git_ignore(walk_config.git_ignore)
-        .git_global(walk_config.git_global)
+        // The global excludes file is resolved per repository by `GitExcludes`.
+        .git_global(false)
         .git_exclude(walk_config.git_exclude)
         .max_depth(walk_config.max_depth)
         // We always want to ignore the .git directory, otherwise if
         // `ignore` is turned off above, we end up with a lot of noise
         // in our picker.
-        .filter_entry(|entry| entry.file_name() != ".git")
+        .filter_entry(move |entry| {
+            entry.file_name() != ".git"
+                && !git_excludes.is_excluded(
+                    entry.path(),
+                    entry.file_type().map_or(false, |ty| ty.is_dir()),
+                )
+        })
         .build_parallel()
 }
@@ -297,15 +297,21 @@ pub async fn search(query: String, cwd: PathBuf, matcher: Matcher, search_contex
         vim,
         stop_signal,
         item_pool_size,
+        layout: _,
+        unique_path_suffix: _,
+        no_matches_hints,
     } = search_context;
 
     let printer = Printer {
         line_width,
         icon,
         truncate_text: false,
+        layout: printer::Layout::Full,
+        path_suffix: None,
     };
     let number = item_pool_size;
-    let progressor = VimProgressor::new(vim, stop_signal.clone());
+    let progressor =
+        VimProgressor::new(vim, stop_signal.clone()).with_no_matches_hints(no_matches_hints);
 
     let mut best_items = BestItems::new(printer, number, progressor, Duration::from_millis(200));
 
@@ -0,0 +1,186 @@
+//! This module provides the completion of `:Clap` command line, using the same
+//! argument metadata the providers parse their arguments with.
+
+use crate::config::Config;
+use crate::stdio_server::provider::provider_args_command;
+use clap::ValueHint;
+use std::path::Path;
+
+/// Pseudo provider whose arguments are the config keys.
+const CONFIG_PROVIDER: &str = "config";
+
+/// Returns the completion candidates of the argument at the end of `cmdline`.
+///
+/// `providers` are the provider names known to Vim, which may include the
+/// providers not backed by the Rust backend.
+pub fn complete_clap_command(cmdline: &str, cwd: &Path, providers: &[String]) -> Vec<String> {
+    let mut args = cmdline.split_whitespace().collect::<Vec<_>>();
+
+    let arg_lead = if cmdline.ends_with(char::is_whitespace) || args.len() < 2 {
+        ""
+    } else {
+        args.pop().unwrap_or_default()
+    };
+
+    // Skip the command name `Clap`.
+    let mut args = args.into_iter().skip(1).skip_while(|arg| *arg == "!");
+
+    let Some(provider_id) = args.next() else {
+        return filter_candidates(providers.iter().map(|s| s.to_string()), arg_lead);
+    };
+
+    if provider_id == CONFIG_PROVIDER {
+        return filter_candidates(Config::keys().into_iter(), arg_lead);
+    }
+
+    let command = provider_args_command(provider_id);
+
+    if let Some((flag, value_lead)) = arg_lead.split_once('=') {
+        let flag_prefix = format!("{flag}=");
+        return complete_flag_value(&command, flag, value_lead, cwd)
+            .into_iter()
+            .map(|value| format!("{flag_prefix}{value}"))
+            .collect();
+    }
+
+    if arg_lead.starts_with('-') {
+        let flags = command
+            .get_arguments()
+            .filter_map(|arg| arg.get_long())
+            .chain(["help"])
+            .map(|long| format!("--{long}"));
+        return filter_candidates(flags, arg_lead);
+    }
+
+    // The value of the preceding flag, e.g., `--path src/`.
+    if let Some(prev_flag) = args.last().filter(|arg| arg.starts_with("--")) {
+        if !prev_flag.contains('=') && takes_value(&command, prev_flag) {
+            return complete_flag_value(&command, prev_flag, arg_lead, cwd);
+        }
+    }
+
+    // The directory argument of filer and igrep is handled on the Vim side.
+    if command.get_positionals().next().is_some() || matches!(provider_id, "filer" | "igrep") {
+        return complete_dirs(arg_lead, cwd);
+    }
+
+    Vec::new()
+}
+
+fn filter_candidates(candidates: impl Iterator<Item = String>, arg_lead: &str) -> Vec<String> {
+    let mut candidates = candidates
+        .filter(|candidate| candidate.starts_with(arg_lead))
+        .collect::<Vec<_>>();
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+fn find_arg<'a>(command: &'a clap::Command, flag: &str) -> Option<&'a clap::Arg> {
+    let long = flag.strip_prefix("--")?;
+    command
+        .get_arguments()
+        .find(|arg| arg.get_long() == Some(long))
+}
+
+fn takes_value(command: &clap::Command, flag: &str) -> bool {
+    find_arg(command, flag)
+        .map(|arg| arg.get_action().takes_values())
+        .unwrap_or(false)
+}
+
+fn complete_flag_value(
+    command: &clap::Command,
+    flag: &str,
+    value_lead: &str,
+    cwd: &Path,
+) -> Vec<String> {
+    let Some(arg) = find_arg(command, flag) else {
+        return Vec::new();
+    };
+
+    if arg.get_long() == Some("type") {
+        let mut types_builder = ignore::types::TypesBuilder::new();
+        types_builder.add_defaults();
+        let file_types = types_builder
+            .definitions()
+            .into_iter()
+            .map(|def| def.name().to_string());
+        return filter_candidates(file_types, value_lead);
+    }
+
+    match arg.get_value_hint() {
+        ValueHint::AnyPath | ValueHint::DirPath => complete_dirs(value_lead, cwd),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns the directories matching `arg_lead`, which can be relative to `cwd`.
+fn complete_dirs(arg_lead: &str, cwd: &Path) -> Vec<String> {
+    let (parent, name_prefix) = match arg_lead.rfind(['/', std::path::MAIN_SEPARATOR]) {
+        Some(idx) => arg_lead.split_at(idx + 1),
+        None => ("", arg_lead),
+    };
+
+    let parent_dir = if parent.is_empty() {
+        cwd.to_path_buf()
+    } else {
+        cwd.join(paths::expand_tilde(parent))
+    };
+
+    let Ok(entries) = std::fs::read_dir(parent_dir) else {
+        return Vec::new();
+    };
+
+    let dirs = entries.filter_map(|entry| {
+        let entry = entry.ok()?;
+        let name = entry.file_name().into_string().ok()?;
+        if name.starts_with('.') && !name_prefix.starts_with('.') {
+            return None;
+        }
+        entry
+            .path()
+            .is_dir()
+            .then(|| format!("{parent}{name}{}", std::path::MAIN_SEPARATOR))
+    });
+
+    filter_candidates(dirs, arg_lead)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_clap_command() {
+        let cwd = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let providers = vec!["files".to_string(), "grep".to_string()];
+
+        assert_eq!(complete_clap_command("Clap ", cwd, &providers), providers);
+        assert_eq!(
+            complete_clap_command("Clap gr", cwd, &providers),
+            vec!["grep"]
+        );
+        assert_eq!(
+            complete_clap_command("Clap grep --hi", cwd, &providers),
+            vec!["--hidden"]
+        );
+        assert_eq!(
+            complete_clap_command("Clap grep --type=rus", cwd, &providers),
+            vec!["--type=rust"]
+        );
+        assert_eq!(
+            complete_clap_command("Clap grep --path sr", cwd, &providers),
+            vec!["src/"]
+        );
+        assert_eq!(
+            complete_clap_command("Clap grep sr", cwd, &providers),
+            vec!["src/"]
+        );
+        assert!(complete_clap_command("Clap blines sr", cwd, &providers).is_empty());
+        assert!(
+            complete_clap_command("Clap config provider.", cwd, &providers)
+                .contains(&"provider.debounce".to_string())
+        );
+    }
+}
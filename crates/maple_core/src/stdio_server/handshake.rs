@@ -0,0 +1,117 @@
+//! Version negotiation between the Vim plugin and the backend on startup.
+//!
+//! Both sides declare a protocol version which is bumped whenever the RPC messages change
+//! incompatibly, the backend version alone can't tell the compatibility as the plugin is
+//! often updated separately from the prebuilt binary.
+
+use crate::config::WatcherBackend;
+use serde::Serialize;
+use std::sync::OnceLock;
+
+/// Protocol version of the backend.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Minimum protocol version of the Vim plugin the backend can work with.
+pub const MIN_PLUGIN_PROTOCOL_VERSION: u32 = 1;
+
+static BACKEND_VERSION: OnceLock<String> = OnceLock::new();
+
+/// Sets the version of the executable, e.g., `0.1.49 (git v0.49-3-gabcdef)`.
+pub fn set_backend_version(version: String) {
+    let _ = BACKEND_VERSION.set(version);
+}
+
+fn backend_version() -> &'static str {
+    BACKEND_VERSION
+        .get()
+        .map(String::as_str)
+        .unwrap_or(env!("CARGO_PKG_VERSION"))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Features {
+    /// Languages highlighted by tree-sitter.
+    pub tree_sitter_languages: Vec<&'static str>,
+    pub watcher_backend: WatcherBackend,
+    /// Whether the symbols from the LSP clients in Vim are supported.
+    pub lsp: bool,
+}
+
+/// Message sent to the Vim plugin on startup.
+#[derive(Debug, Clone, Serialize)]
+pub struct Handshake {
+    pub version: &'static str,
+    pub protocol_version: u32,
+    pub min_plugin_protocol_version: u32,
+    pub features: Features,
+}
+
+impl Handshake {
+    pub fn new() -> Self {
+        let config = crate::config::config();
+        Self {
+            version: backend_version(),
+            protocol_version: PROTOCOL_VERSION,
+            min_plugin_protocol_version: MIN_PLUGIN_PROTOCOL_VERSION,
+            features: Features {
+                tree_sitter_languages: tree_sitter::Language::ALL
+                    .iter()
+                    .map(|language| language.name())
+                    .collect(),
+                watcher_backend: config.watcher.backend,
+                lsp: !config.provider.disable_lsp,
+            },
+        }
+    }
+
+    /// Returns the one-line summary displayed in the log and `:Clap debug`.
+    pub fn banner(&self) -> String {
+        format!(
+            "maple {} (protocol v{}), tree-sitter: {}, watcher: {:?}, lsp: {}",
+            self.version,
+            self.protocol_version,
+            self.features.tree_sitter_languages.join(","),
+            self.features.watcher_backend,
+            self.features.lsp
+        )
+    }
+
+    /// Returns the warning to display if the plugin of `plugin_protocol_version` is
+    /// incompatible with the backend.
+    ///
+    /// `None` for the plugin version means the plugin predates the handshake.
+    pub fn check_plugin(&self, plugin_protocol_version: Option<u32>) -> Option<String> {
+        match plugin_protocol_version {
+            Some(v) if v >= self.min_plugin_protocol_version => None,
+            _ => Some(format!(
+                "vim-clap plugin is outdated for maple {}: protocol v{} is required but found {}, \
+                 please update the plugin or run `:Clap install-binary!` with the version of the plugin",
+                self.version,
+                self.min_plugin_protocol_version,
+                plugin_protocol_version.map_or_else(|| "none".to_string(), |v| format!("v{v}"))
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_plugin_version() {
+        let handshake = Handshake {
+            version: "0.1.49",
+            protocol_version: PROTOCOL_VERSION,
+            min_plugin_protocol_version: MIN_PLUGIN_PROTOCOL_VERSION,
+            features: Features {
+                tree_sitter_languages: vec!["rust"],
+                watcher_backend: WatcherBackend::Native,
+                lsp: true,
+            },
+        };
+        assert!(handshake.check_plugin(Some(PROTOCOL_VERSION)).is_none());
+        assert!(handshake.check_plugin(None).unwrap().contains("found none"));
+        assert!(handshake.banner().contains("rust"));
+    }
+}
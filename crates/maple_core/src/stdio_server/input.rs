@@ -1,9 +1,12 @@
 use crate::stdio_server::plugin::PluginId;
 use crate::stdio_server::provider::ProviderId;
 use crate::stdio_server::service::ProviderSessionId;
+use crate::stdio_server::watcher::FileChange;
 use crate::stdio_server::Error;
 use rpc::{Params, RpcNotification};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use tokio::sync::mpsc::UnboundedSender;
 
 pub use types::AutocmdEventType;
@@ -39,8 +42,21 @@ impl PluginEvent {
 pub enum ProviderEvent {
     OnMove(Params),
     OnTyped(Params),
-    Exit,
+    /// Carries the final query and cursor position of the session.
+    Exit(Params),
     Key(KeyEvent),
+    /// Copies the current item to the register.
+    Copy(Params),
+    /// Pins or unpins the current item.
+    Pin(Params),
+    /// Opens the current item at the position of the scrolled preview.
+    AcceptAtPreview,
+    /// Scrolls the preview by the given number of lines.
+    ScrollPreview(Params),
+    /// Performs a provider specific action on the current item, e.g., deleting a buffer.
+    Action(Params),
+    /// The geometry of the display window may have changed.
+    DisplayMetricsChanged(Params),
     /// Signal fired internally.
     Internal(InternalProviderEvent),
 }
@@ -49,6 +65,8 @@ pub enum ProviderEvent {
 pub enum InternalProviderEvent {
     Initialize,
     InitialQuery(String),
+    /// Files in the working directory have been changed on the disk.
+    FilesChanged(Vec<FileChange>),
     Terminate,
 }
 
@@ -69,6 +87,8 @@ pub enum KeyEventType {
     CtrlN,
     // <C-P>
     CtrlP,
+    // <A-P>
+    AltP,
 }
 
 pub type ActionEvent = (PluginId, ActionRequest);
@@ -111,13 +131,31 @@ impl Event {
 
         match notification.method.as_str() {
             "new_provider" => Ok(Self::NewProvider(notification.params)),
-            "exit_provider" => Ok(Self::ProviderWorker(ProviderEvent::Exit)),
+            "exit_provider" => Ok(Self::ProviderWorker(ProviderEvent::Exit(
+                notification.params,
+            ))),
             "on_move" => Ok(Self::ProviderWorker(ProviderEvent::OnMove(
                 notification.params,
             ))),
             "on_typed" => Ok(Self::ProviderWorker(ProviderEvent::OnTyped(
                 notification.params,
             ))),
+            "copy_selection" => Ok(Self::ProviderWorker(ProviderEvent::Copy(
+                notification.params,
+            ))),
+            "pin_item" => Ok(Self::ProviderWorker(ProviderEvent::Pin(
+                notification.params,
+            ))),
+            "accept_at_preview" => Ok(Self::ProviderWorker(ProviderEvent::AcceptAtPreview)),
+            "preview/scroll" => Ok(Self::ProviderWorker(ProviderEvent::ScrollPreview(
+                notification.params,
+            ))),
+            "provider_action" => Ok(Self::ProviderWorker(ProviderEvent::Action(
+                notification.params,
+            ))),
+            "display_metrics_changed" => Ok(Self::ProviderWorker(
+                ProviderEvent::DisplayMetricsChanged(notification.params),
+            )),
             "cr" => Ok(Self::Key((CarriageReturn, notification.params))),
             "tab" => Ok(Self::Key((Tab, notification.params))),
             "ctrl-n" => Ok(Self::Key((CtrlN, notification.params))),
@@ -125,6 +163,7 @@ impl Event {
             "shift-up" => Ok(Self::Key((ShiftUp, notification.params))),
             "shift-down" => Ok(Self::Key((ShiftDown, notification.params))),
             "backspace" => Ok(Self::Key((Backspace, notification.params))),
+            "alt-p" => Ok(Self::Key((AltP, notification.params))),
             "CursorMoved" => Ok(Self::Autocmd((CursorMoved, notification.params))),
             "InsertEnter" => Ok(Self::Autocmd((InsertEnter, notification.params))),
             "BufEnter" => Ok(Self::Autocmd((BufEnter, notification.params))),
@@ -167,8 +206,8 @@ impl ProviderEventSender {
     }
 }
 
-/// Input history of all providers.
-#[derive(Debug, Clone, Default)]
+/// Input history of all providers, the oldest input first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct InputHistory(HashMap<ProviderId, VecDeque<String>>);
 
 impl InputHistory {
@@ -181,18 +220,63 @@ impl InputHistory {
     }
 
     pub fn all_inputs(&self) -> VecDeque<String> {
-        // HashSet guarantees no duplicated elements.
-        self.0
-            .values()
-            .flatten()
-            .cloned()
-            .collect::<HashSet<_>>()
+        let mut seen = HashSet::new();
+        let mut provider_ids = self.0.keys().collect::<Vec<_>>();
+        // Keep the order stable across the sessions.
+        provider_ids.sort_by_key(|provider_id| provider_id.as_str());
+        provider_ids
             .into_iter()
+            .flat_map(|provider_id| &self.0[provider_id])
+            .filter(|input| seen.insert(input.as_str()))
+            .cloned()
             .collect()
     }
 
     pub fn update_inputs(&mut self, provider_id: ProviderId, new_value: VecDeque<String>) {
-        self.0.insert(provider_id, new_value);
+        if new_value.is_empty() {
+            self.0.remove(&provider_id);
+        } else {
+            self.0.insert(provider_id, new_value);
+        }
+    }
+}
+
+/// Last query of a provider in a project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StickyQuery {
+    pub project: PathBuf,
+    pub query: String,
+    /// Line number of the cursor in the display window.
+    pub lnum: usize,
+}
+
+/// Last queries of the providers with `provider.sticky-query` enabled, the most recent first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StickyQueries(HashMap<ProviderId, VecDeque<StickyQuery>>);
+
+impl StickyQueries {
+    /// Maximum number of projects remembered per provider.
+    const MAX_PROJECTS: usize = 50;
+
+    pub fn get(&self, provider_id: &ProviderId, project: &Path) -> Option<&StickyQuery> {
+        self.0
+            .get(provider_id)?
+            .iter()
+            .find(|sticky_query| sticky_query.project == project)
+    }
+
+    pub fn insert(&mut self, provider_id: ProviderId, sticky_query: StickyQuery) {
+        let queries = self.0.entry(provider_id).or_default();
+        queries.retain(|q| q.project != sticky_query.project);
+        queries.push_front(sticky_query);
+        queries.truncate(Self::MAX_PROJECTS);
+    }
+
+    /// Forgets the last query of `provider_id` in `project`, e.g., on exiting with an empty query.
+    pub fn remove(&mut self, provider_id: &ProviderId, project: &Path) {
+        if let Some(queries) = self.0.get_mut(provider_id) {
+            queries.retain(|q| q.project != project);
+        }
     }
 }
 
@@ -207,11 +291,13 @@ impl InputRecorder {
     /// Maximum size of inputs per provider.
     const MAX_INPUTS: usize = 20;
 
+    /// Creates a recorder with the inputs of the previous sessions, the navigation starts
+    /// from the most recent one.
     pub fn new(inputs: VecDeque<String>) -> Self {
         Self {
+            current_index: inputs.len(),
             inputs,
             last_input: Default::default(),
-            current_index: 0usize,
         }
     }
 
@@ -222,7 +308,17 @@ impl InputRecorder {
     pub fn try_record(&mut self, new: String) {
         let new = new.trim();
 
-        if new.is_empty() || self.inputs.iter().any(|s| s == new) {
+        if new.is_empty() {
+            return;
+        }
+
+        // The re-typed input becomes the most recent one.
+        if let Some(pos) = self.inputs.iter().position(|s| s == new) {
+            if let Some(input) = self.inputs.remove(pos) {
+                self.inputs.push_back(input);
+            }
+            self.last_input = new.to_string();
+            self.current_index = self.inputs.len() - 1;
             return;
         }
 
@@ -246,15 +342,14 @@ impl InputRecorder {
             }
         }
 
-        if !self.inputs.is_empty() {
-            self.current_index += 1;
-        }
         self.inputs.push_back(new.to_string());
         self.last_input = new.to_string();
 
         if self.inputs.len() > Self::MAX_INPUTS {
             self.inputs.pop_front();
         }
+
+        self.current_index = self.inputs.len() - 1;
     }
 
     /// Returns the next input if inputs are not empty.
@@ -264,7 +359,11 @@ impl InputRecorder {
         if self.inputs.is_empty() {
             return None;
         }
-        self.current_index = (self.current_index + 1) % self.inputs.len();
+        self.current_index = if self.current_index + 1 >= self.inputs.len() {
+            0
+        } else {
+            self.current_index + 1
+        };
         self.inputs.get(self.current_index).map(AsRef::as_ref)
     }
 
@@ -282,3 +381,87 @@ impl InputRecorder {
         self.inputs.get(self.current_index).map(AsRef::as_ref)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_recorder() {
+        let mut recorder = InputRecorder::new(["foo", "bar"].map(String::from).into());
+        assert_eq!(recorder.move_to_prev(), Some("bar"));
+        assert_eq!(recorder.move_to_prev(), Some("foo"));
+        assert_eq!(recorder.move_to_next(), Some("bar"));
+
+        let mut recorder = InputRecorder::new(["foo", "bar"].map(String::from).into());
+        assert_eq!(recorder.move_to_next(), Some("foo"));
+
+        let mut recorder = InputRecorder::new(["foo"].map(String::from).into());
+        for input in ["b", "ba", "baz", "foo", "fo"] {
+            recorder.try_record(input.to_string());
+        }
+        assert_eq!(recorder.inputs, ["baz", "foo"]);
+        assert_eq!(recorder.move_to_prev(), Some("baz"));
+        assert_eq!(recorder.move_to_prev(), Some("foo"));
+
+        // The re-typed input becomes the most recent one.
+        recorder.try_record("baz".to_string());
+        assert_eq!(recorder.inputs, ["foo", "baz"]);
+        assert_eq!(recorder.move_to_next(), Some("foo"));
+        assert_eq!(recorder.move_to_prev(), Some("baz"));
+    }
+
+    #[test]
+    fn test_shared_input_history() {
+        let mut history = InputHistory::new();
+        history.update_inputs("grep".into(), ["foo", "bar"].map(String::from).into());
+        history.update_inputs("files".into(), ["bar", "baz"].map(String::from).into());
+        assert_eq!(history.all_inputs(), ["bar", "baz", "foo"]);
+        history.update_inputs("files".into(), VecDeque::new());
+        assert_eq!(history.all_inputs(), ["foo", "bar"]);
+    }
+
+    #[test]
+    fn test_parse_toggle_preview_key() {
+        let notification = RpcNotification {
+            method: "alt-p".to_string(),
+            params: Params::None,
+        };
+        let event = Event::parse_notification(notification, |_| unreachable!()).unwrap();
+        assert!(matches!(
+            event,
+            Event::Key((KeyEventType::AltP, Params::None))
+        ));
+    }
+
+    #[test]
+    fn test_sticky_queries() {
+        let sticky_query = |project: &str, query: &str| StickyQuery {
+            project: PathBuf::from(project),
+            query: query.to_string(),
+            lnum: 3,
+        };
+        let grep = ProviderId::from("grep");
+
+        let mut sticky_queries = StickyQueries::default();
+        sticky_queries.insert(grep.clone(), sticky_query("/a", "foo"));
+        sticky_queries.insert(grep.clone(), sticky_query("/b", "bar"));
+        sticky_queries.insert(grep.clone(), sticky_query("/a", "baz"));
+        assert_eq!(
+            sticky_queries.get(&grep, Path::new("/a")),
+            Some(&sticky_query("/a", "baz"))
+        );
+        assert_eq!(sticky_queries.get(&"files".into(), Path::new("/a")), None);
+
+        sticky_queries.remove(&grep, Path::new("/a"));
+        assert_eq!(sticky_queries.get(&grep, Path::new("/a")), None);
+        assert!(sticky_queries.get(&grep, Path::new("/b")).is_some());
+
+        // The least recent projects are forgotten once full.
+        for i in 0..StickyQueries::MAX_PROJECTS {
+            sticky_queries.insert(grep.clone(), sticky_query(&format!("/{i}"), "foo"));
+        }
+        assert_eq!(sticky_queries.get(&grep, Path::new("/b")), None);
+        assert!(sticky_queries.get(&grep, Path::new("/0")).is_some());
+    }
+}
@@ -1,4 +1,7 @@
 //! This module ensures the process of same command won't be spawned multiple times simultaneously.
+//!
+//! The blocking background jobs are run in a dedicated thread pool with a lower
+//! priority, leaving the other threads to the interactive work.
 
 use futures::Future;
 use once_cell::sync::Lazy;
@@ -20,6 +23,36 @@ pub fn try_start(job_future: impl Future<Output = ()> + Send + Sync + 'static, j
     }
 }
 
+static BACKGROUND_POOL: Lazy<rayon::ThreadPool> = Lazy::new(|| {
+    let thread_pool_config = &crate::config::config().thread_pool;
+    let niceness = thread_pool_config.background_niceness();
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(thread_pool_config.background_threads())
+        .thread_name(|index| format!("clap-background-{index}"))
+        .start_handler(move |_| lower_thread_priority(niceness))
+        .build()
+        .expect("Failed to build the background thread pool")
+});
+
+/// Runs the blocking `job` in the background thread pool.
+pub fn spawn_background(job: impl FnOnce() + Send + 'static) {
+    BACKGROUND_POOL.spawn(job);
+}
+
+#[cfg(target_os = "linux")]
+fn lower_thread_priority(niceness: i32) {
+    // The niceness is per-thread on Linux, `0` refers to the calling thread.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, niceness) } != 0 {
+        tracing::debug!(
+            err = ?std::io::Error::last_os_error(),
+            "Failed to lower the priority of background thread"
+        );
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn lower_thread_priority(_niceness: i32) {}
+
 pub fn reserve(job_id: u64) -> bool {
     let mut jobs = JOBS.lock();
     if jobs.contains(&job_id) {
@@ -1,15 +1,23 @@
+mod completion;
+mod handshake;
 mod input;
 mod job;
 mod plugin;
 mod provider;
 mod request_handler;
+mod scheduler;
 mod service;
 mod vim;
+mod watcher;
 
-pub use self::input::InputHistory;
+pub use self::handshake::set_backend_version;
+use self::handshake::Handshake;
 use self::input::{ActionEvent, Event, ProviderEvent};
+pub use self::input::{InputHistory, StickyQueries, StickyQuery};
 use self::plugin::PluginId;
-use self::provider::{create_provider, Context, ProviderError};
+pub(crate) use self::provider::Context;
+pub use self::provider::NoMatchesHints;
+use self::provider::{create_provider, ProviderError};
 use self::service::ServiceManager;
 use self::vim::{initialize_filetype_map, VimError, VimResult};
 pub use self::vim::{Vim, VimProgressor};
@@ -49,6 +57,18 @@ pub enum Error {
 async fn initialize_client(vim: Vim, actions: Vec<&str>, config_err: ConfigError) -> VimResult<()> {
     config_err.notify_error(&vim)?;
 
+    let handshake = Handshake::new();
+    tracing::info!("{}", handshake.banner());
+
+    // The plugin predating the handshake has no such function.
+    let plugin_protocol_version: Option<u32> = vim
+        .call("clap#client#handshake", json!([handshake]))
+        .await
+        .ok();
+    if let Some(warning) = handshake.check_plugin(plugin_protocol_version) {
+        vim.echo_warn(warning)?;
+    }
+
     // The output of `autocmd filetypedetect` could be incomplete as the
     // filetype won't be instantly initialized, thus the current workaround
     // is to introduce some delay.
@@ -139,6 +159,11 @@ fn initialize_service(vim: Vim) -> InitializedService {
         register_plugin(Box::new(CursorwordPlugin::new(vim)), None);
     }
 
+    let idle_scheduler_config = &crate::config::config().idle_scheduler;
+    if idle_scheduler_config.enable {
+        tokio::spawn(scheduler::IdleScheduler::new(idle_scheduler_config).run());
+    }
+
     InitializedService {
         callable_actions,
         plugin_actions,
@@ -237,6 +262,7 @@ impl Backend {
                 maybe_call = rx.recv() => {
                     match maybe_call {
                         Some(call) => {
+                            scheduler::touch();
                             match call {
                                 VimMessage::Request(rpc_request) => self.process_request(rpc_request),
                                 VimMessage::Notification(notification) => {
@@ -322,9 +348,11 @@ impl Backend {
                     .new_provider(session_id, provider, ctx);
             }
             Event::ProviderWorker(provider_event) => match provider_event {
-                ProviderEvent::Exit => {
+                ProviderEvent::Exit(params) => {
                     let session_id = maybe_session_id.ok_or(Error::MissingSessionId)?;
-                    self.service_manager.lock().notify_provider_exit(session_id);
+                    self.service_manager
+                        .lock()
+                        .notify_provider_exit(session_id, params);
                 }
                 to_send => {
                     let session_id = maybe_session_id.ok_or(Error::MissingSessionId)?;
@@ -390,7 +418,13 @@ impl Backend {
 
         let value = match msg.method.as_str() {
             "preview/file" => Some(request_handler::preview_file(msg).await?),
-            "quickfix" => Some(request_handler::preview_quickfix(msg).await?),
+            "file_info" => Some(request_handler::file_info(msg).await?),
+            "complete/clap" => Some(request_handler::complete_clap_command(msg)?),
+            "git/external_dir" => Some(request_handler::external_git_dir(msg)?),
+            "git/checkout" => Some(request_handler::git_checkout(msg).await?),
+            "provider/register" => Some(request_handler::register_provider(msg)?),
+            "provider/unregister" => Some(request_handler::unregister_provider(msg)?),
+            "handshake" => Some(json!(Handshake::new())),
             _ => Some(json!({
                 "error": format!("Unknown request: {}", msg.method)
             })),
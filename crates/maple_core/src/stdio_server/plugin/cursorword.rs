@@ -132,8 +132,8 @@ pub struct Cursorword {
     vim: Vim,
     bufs: HashMap<usize, PathBuf>,
     cursor_highlights: Option<CursorHighlights>,
-    ignore_extensions: Vec<&'static str>,
-    ignore_file_names: Vec<&'static str>,
+    ignore_extensions: Vec<String>,
+    ignore_file_names: Vec<String>,
 }
 
 impl Cursorword {
@@ -143,6 +143,7 @@ impl Cursorword {
             .cursorword
             .ignore_files
             .split(',')
+            .map(String::from)
             .partition(|s| s.starts_with("*."));
 
         tokio::spawn({
@@ -274,7 +275,7 @@ impl Cursorword {
             .ignore_extensions
             .iter()
             .any(|s| &s[2..] == file_extension)
-            || self.ignore_file_names.contains(&file_name)
+            || self.ignore_file_names.iter().any(|s| s == file_name)
         {
             return Ok(());
         }
@@ -1,3 +1,4 @@
+use crate::process::limits::LimitResources;
 use crate::stdio_server::input::{AutocmdEvent, AutocmdEventType};
 use crate::stdio_server::plugin::{ActionRequest, ClapPlugin, PluginError, Toggle};
 use crate::stdio_server::vim::Vim;
@@ -25,8 +26,7 @@ struct GitRepo {
 
 impl GitRepo {
     fn init(git_root: PathBuf) -> Result<Self, GitError> {
-        let output = std::process::Command::new("git")
-            .current_dir(&git_root)
+        let output = crate::tools::git::git_command(&git_root)
             .arg("config")
             .arg("user.name")
             .stderr(Stdio::null())
@@ -40,9 +40,13 @@ impl GitRepo {
         })
     }
 
+    fn git_command(&self) -> std::process::Command {
+        crate::tools::git::git_command(&self.repo)
+    }
+
     fn is_tracked(&self, file: &Path) -> std::io::Result<bool> {
-        let output = std::process::Command::new("git")
-            .current_dir(&self.repo)
+        let output = self
+            .git_command()
             .arg("ls-files")
             .arg("--error-unmatch")
             .arg(file)
@@ -51,8 +55,8 @@ impl GitRepo {
     }
 
     fn fetch_rev_parse(&self, arg: &str) -> Result<String, GitError> {
-        let output = std::process::Command::new("git")
-            .current_dir(&self.repo)
+        let output = self
+            .git_command()
             .arg("rev-parse")
             .arg(arg)
             .stderr(Stdio::null())
@@ -63,8 +67,8 @@ impl GitRepo {
 
     #[allow(unused)]
     fn fetch_user_name(&self) -> Result<String, GitError> {
-        let output = std::process::Command::new("git")
-            .current_dir(&self.repo)
+        let output = self
+            .git_command()
             .arg("config")
             .arg("user.name")
             .stderr(Stdio::null())
@@ -74,8 +78,8 @@ impl GitRepo {
     }
 
     fn fetch_origin_url(&self) -> Result<String, GitError> {
-        let output = std::process::Command::new("git")
-            .current_dir(&self.repo)
+        let output = self
+            .git_command()
             .arg("config")
             .arg("--get")
             .arg("remote.origin.url")
@@ -86,8 +90,9 @@ impl GitRepo {
     }
 
     fn fetch_blame_output(&self, relative_path: &Path, lnum: usize) -> std::io::Result<Vec<u8>> {
-        let output = std::process::Command::new("git")
-            .current_dir(&self.repo)
+        let output = self
+            .git_command()
+            .limit_resources()
             .arg("blame")
             .arg("--porcelain")
             .arg("--incremental")
@@ -121,8 +126,9 @@ impl GitRepo {
         lnum: usize,
         lines: Vec<String>,
     ) -> std::io::Result<Vec<u8>> {
-        let mut p = std::process::Command::new("git")
-            .current_dir(&self.repo)
+        let mut p = self
+            .git_command()
+            .limit_resources()
             .arg("blame")
             .arg("--contents")
             .arg("-")
@@ -239,10 +245,10 @@ fn parse_blame_info(stdout: Vec<u8>) -> Option<BlameInfo> {
     None
 }
 
-fn in_git_repo(filepath: &Path) -> Option<&Path> {
+fn in_git_repo(filepath: &Path) -> Option<PathBuf> {
     filepath
         .exists()
-        .then(|| paths::find_git_root(filepath))
+        .then(|| crate::tools::git::find_work_tree(filepath))
         .flatten()
 }
 
@@ -273,7 +279,7 @@ impl Git {
         let filepath = PathBuf::from(buf_path);
 
         if let Some(git_root) = in_git_repo(&filepath) {
-            let git = GitRepo::init(git_root.to_path_buf())?;
+            let git = GitRepo::init(git_root)?;
             if git.is_tracked(&filepath)? {
                 self.bufs.insert(bufnr, (filepath, git));
                 return Ok(());
@@ -335,7 +341,7 @@ impl Git {
         };
 
         if let Ok(Some(blame_info)) = self
-            .cursor_line_blame_info(&GitRepo::init(git_root.to_path_buf())?, &filepath)
+            .cursor_line_blame_info(&GitRepo::init(git_root)?, &filepath)
             .await
         {
             self.vim.echo_info(blame_info)?;
@@ -352,7 +358,7 @@ impl Git {
             return Ok(());
         };
 
-        let git = GitRepo::init(git_root.to_path_buf())?;
+        let git = GitRepo::init(git_root)?;
 
         let relative_path = filepath.strip_prefix(&git.repo)?;
 
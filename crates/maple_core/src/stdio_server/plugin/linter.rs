@@ -2,6 +2,7 @@ use crate::stdio_server::input::{AutocmdEvent, AutocmdEventType};
 use crate::stdio_server::plugin::{ActionRequest, ClapPlugin, PluginError, Toggle};
 use crate::stdio_server::vim::{Vim, VimResult};
 use ide::linting::{Diagnostic, DiagnosticSpan};
+use once_cell::sync::Lazy;
 use parking_lot::{Mutex, RwLock};
 use serde::Serialize;
 use std::cmp::Ordering as CmpOrdering;
@@ -17,6 +18,23 @@ struct Count {
     warn: usize,
 }
 
+/// Diagnostic along with its severity icon, displayed on the top right corner.
+#[derive(Serialize)]
+struct DisplayDiagnostic<'a> {
+    #[serde(flatten)]
+    diagnostic: &'a Diagnostic,
+    icon: char,
+}
+
+impl<'a> From<&'a Diagnostic> for DisplayDiagnostic<'a> {
+    fn from(diagnostic: &'a Diagnostic) -> Self {
+        Self {
+            diagnostic,
+            icon: diagnostic.severity.icon(),
+        }
+    }
+}
+
 enum Direction {
     Next,
     Prev,
@@ -215,11 +233,41 @@ impl BufferLinterInfo {
     }
 }
 
+/// Linted buffers keyed by bufnr, which is also read by the `diagnostics` provider.
+static LINTED_BUFFERS: Lazy<RwLock<HashMap<usize, BufferLinterInfo>>> = Lazy::new(Default::default);
+
+/// Returns the info of a linted buffer, cloned so that no lock is held across `.await`.
+fn linted_buffer(bufnr: usize) -> Option<BufferLinterInfo> {
+    LINTED_BUFFERS.read().get(&bufnr).cloned()
+}
+
+/// Returns the diagnostics along with the path of the buffer, only the diagnostics of
+/// `bufnr` are returned if specified.
+pub fn collect_diagnostics(bufnr: Option<usize>) -> Vec<(PathBuf, Diagnostic)> {
+    let linted_buffers = LINTED_BUFFERS.read();
+    let mut diagnostics = linted_buffers
+        .iter()
+        .filter(|(linted_bufnr, _)| bufnr.map_or(true, |bufnr| bufnr == **linted_bufnr))
+        .flat_map(|(_, buf_linter_info)| {
+            let path = &buf_linter_info.source_file;
+            buf_linter_info
+                .diagnostics
+                .inner
+                .read()
+                .iter()
+                .map(|diagnostic| (path.clone(), diagnostic.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    // The diagnostics of each buffer are sorted already.
+    diagnostics.sort_by(|a, b| a.0.cmp(&b.0));
+    diagnostics
+}
+
 #[derive(Debug, Clone, maple_derive::ClapPlugin)]
 #[clap_plugin(id = "linter", actions = ["lint", "format", "next-error", "prev-error", "next-warn", "prev-warn", "debug", "toggle"])]
 pub struct Linter {
     vim: Vim,
-    bufs: HashMap<usize, BufferLinterInfo>,
     toggle: Toggle,
 }
 
@@ -227,7 +275,6 @@ impl Linter {
     pub fn new(vim: Vim) -> Self {
         Self {
             vim,
-            bufs: HashMap::new(),
             toggle: Toggle::On,
         }
     }
@@ -244,7 +291,7 @@ impl Linter {
 
         let buf_linter_info = BufferLinterInfo::new(filetype, workspace.to_path_buf(), source_file);
         self.lint_buffer(bufnr, &buf_linter_info);
-        self.bufs.insert(bufnr, buf_linter_info);
+        LINTED_BUFFERS.write().insert(bufnr, buf_linter_info);
 
         Ok(())
     }
@@ -278,7 +325,7 @@ impl Linter {
         direction: Direction,
     ) -> VimResult<()> {
         let bufnr = self.vim.bufnr("").await?;
-        if let Some(buf_linter_info) = self.bufs.get(&bufnr) {
+        if let Some(buf_linter_info) = linted_buffer(bufnr) {
             let lnum = self.vim.line(".").await?;
             if let Some((lnum, col)) = buf_linter_info
                 .diagnostics
@@ -291,7 +338,7 @@ impl Linter {
     }
 
     async fn on_cursor_moved(&self, bufnr: usize) -> VimResult<()> {
-        if let Some(buf_linter_info) = self.bufs.get(&bufnr) {
+        if let Some(buf_linter_info) = linted_buffer(bufnr) {
             let lnum = self.vim.line(".").await?;
             let col = self.vim.col(".").await?;
 
@@ -316,17 +363,21 @@ impl Linter {
 
                 // Display the specific diagnostic if the cursor is on it, otherwise display all
                 // the diagnostics in this line.
-                if diagnostic_at_cursor.is_empty() {
-                    self.vim.exec(
-                        "clap#plugin#linter#display_top_right",
-                        [current_diagnostics],
-                    )?;
+                let display_diagnostics = if diagnostic_at_cursor.is_empty() {
+                    current_diagnostics
+                        .into_iter()
+                        .map(DisplayDiagnostic::from)
+                        .collect::<Vec<_>>()
                 } else {
-                    self.vim.exec(
-                        "clap#plugin#linter#display_top_right",
-                        [diagnostic_at_cursor],
-                    )?;
-                }
+                    diagnostic_at_cursor
+                        .into_iter()
+                        .map(|d| DisplayDiagnostic::from(*d))
+                        .collect::<Vec<_>>()
+                };
+                self.vim.exec(
+                    "clap#plugin#linter#display_top_right",
+                    [display_diagnostics],
+                )?;
             }
         }
 
@@ -351,12 +402,12 @@ impl ClapPlugin for Linter {
         match autocmd_event_type {
             BufEnter => self.on_buf_enter(bufnr).await?,
             BufWritePost => {
-                if let Some(buf_linter_info) = self.bufs.get(&bufnr) {
-                    self.lint_buffer(bufnr, buf_linter_info);
+                if let Some(buf_linter_info) = linted_buffer(bufnr) {
+                    self.lint_buffer(bufnr, &buf_linter_info);
                 }
             }
             BufDelete => {
-                self.bufs.remove(&bufnr);
+                LINTED_BUFFERS.write().remove(&bufnr);
             }
             CursorMoved => {
                 self.on_cursor_moved(bufnr).await?;
@@ -373,7 +424,9 @@ impl ClapPlugin for Linter {
             LinterAction::Toggle => {
                 match self.toggle {
                     Toggle::On => {
-                        for bufnr in self.bufs.keys() {
+                        let linted_bufnrs =
+                            LINTED_BUFFERS.read().keys().copied().collect::<Vec<_>>();
+                        for bufnr in linted_bufnrs {
                             self.vim.exec("clap#plugin#linter#toggle_off", [bufnr])?;
                         }
                     }
@@ -387,7 +440,7 @@ impl ClapPlugin for Linter {
             LinterAction::Lint => {
                 let bufnr = self.vim.bufnr("").await?;
 
-                if let Some(buf_linter_info) = self.bufs.get(&bufnr) {
+                if let Some(buf_linter_info) = linted_buffer(bufnr) {
                     let lnum = self.vim.line(".").await?;
                     let diagnostics = buf_linter_info.diagnostics.inner.read();
                     let current_diagnostics = diagnostics
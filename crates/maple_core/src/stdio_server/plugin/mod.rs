@@ -15,7 +15,7 @@ pub use self::colorizer::ColorizerPlugin;
 pub use self::ctags::CtagsPlugin;
 pub use self::cursorword::Cursorword as CursorwordPlugin;
 pub use self::git::Git as GitPlugin;
-pub use self::linter::Linter as LinterPlugin;
+pub use self::linter::{collect_diagnostics, Linter as LinterPlugin};
 pub use self::markdown::Markdown as MarkdownPlugin;
 pub use self::syntax::Syntax as SyntaxPlugin;
 pub use self::system::System as SystemPlugin;
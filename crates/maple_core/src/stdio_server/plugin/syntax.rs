@@ -23,7 +23,7 @@ struct SyntaxProps {
     highlight_group: &'static str,
 }
 
-type RawTsHighlights = BTreeMap<usize, Vec<tree_sitter::HighlightItem>>;
+pub type RawTsHighlights = BTreeMap<usize, Vec<tree_sitter::HighlightItem>>;
 
 #[derive(Debug, Clone)]
 struct BufferHighlights(RawTsHighlights);
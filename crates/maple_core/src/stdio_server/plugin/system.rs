@@ -1,4 +1,4 @@
-use crate::datastore::RECENT_FILES_IN_MEMORY;
+use crate::datastore::{HISTORY_FRECENCY_IN_MEMORY, RECENT_FILES_IN_MEMORY};
 use crate::stdio_server::input::ActionRequest;
 use crate::stdio_server::plugin::{ClapPlugin, PluginError, PluginResult};
 use crate::stdio_server::vim::Vim;
@@ -8,7 +8,7 @@ use regex::Regex;
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, maple_derive::ClapPlugin)]
-#[clap_plugin(id = "system", actions = ["__note_recent_files", "__copy-to-clipboard", "__configure-vim-which-key", "open-config", "list-plugins"])]
+#[clap_plugin(id = "system", actions = ["__note_recent_files", "__note_history", "__copy-to-clipboard", "__configure-vim-which-key", "__set-config", "open-config", "list-plugins"])]
 pub struct System {
     vim: Vim,
 }
@@ -75,6 +75,15 @@ fn note_recent_file(file_path: String) {
     recent_files.upsert(file_path);
 }
 
+/// Records the entry picked in the `command_history` or `search_history` provider.
+fn note_history(history_type: &str, entry: &str) {
+    let mut history_frecency = HISTORY_FRECENCY_IN_MEMORY.lock();
+    history_frecency.record(history_type, entry);
+    if let Err(err) = crate::datastore::store_history_frecency(&history_frecency) {
+        tracing::error!(?err, "Failed to store the history frecency");
+    }
+}
+
 #[async_trait::async_trait]
 impl ClapPlugin for System {
     async fn handle_action(&mut self, action: ActionRequest) -> Result<(), PluginError> {
@@ -90,6 +99,10 @@ impl ClapPlugin for System {
 
                 note_recent_file(file_path);
             }
+            SystemAction::__NoteHistory => {
+                let (history_type, entry): (String, String) = params.parse()?;
+                note_history(&history_type, &entry);
+            }
             SystemAction::__CopyToClipboard => {
                 let content: Vec<String> = params.parse()?;
                 let content = content.into_iter().next().ok_or_else(|| {
@@ -112,6 +125,17 @@ impl ClapPlugin for System {
                 self.configure_vim_which_key_map(&args[0], &args[1..])
                     .await?;
             }
+            SystemAction::__SetConfig => {
+                let (key, value): (String, String) = params.parse()?;
+                match crate::config_editor::set_config_value(&key, &value) {
+                    Ok(()) => {
+                        self.vim.echo_info(format!("{key} is set to {value}"))?;
+                    }
+                    Err(e) => {
+                        self.vim.echo_warn(format!("failed to set {key}: {e}"))?;
+                    }
+                }
+            }
             SystemAction::OpenConfig => {
                 let config_file = crate::config::config_file();
                 self.vim
@@ -0,0 +1,151 @@
+//! Golden tests of the previews across providers.
+//!
+//! Each case builds a `Context` against [`MockVim`], previews a recorded provider
+//! item and compares the resulting [`Preview`] payload with the snapshot in
+//! `test/testdata/golden_previews`.
+//!
+//! Run with `UPDATE_GOLDEN=1` to regenerate the snapshots after an intended change.
+
+use super::on_move::{CachedPreviewImpl, Preview};
+use crate::testing::{initialize_config, MockVim, PREVIEW_WINID};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+const PREVIEW_HEIGHT: usize = 10;
+
+/// Bufnr of the start buffer in [`MockVim::context`].
+const START_BUFNR: usize = 1;
+
+const TEST_CONFIG: &str = r#"
+[provider]
+preview-highlight-engine = "tree-sitter"
+"#;
+
+fn testdata_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../test/testdata/golden_previews")
+        .canonicalize()
+        .expect("Golden previews directory must exist")
+}
+
+fn read_lines(path: &Path) -> Vec<String> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(Into::into)
+        .collect()
+}
+
+/// Returns the pretty JSON of the preview of `item`, or the error info if it fails.
+async fn preview_snapshot(provider_id: &str, item: &str, is_nvim: bool) -> String {
+    initialize_config(TEST_CONFIG);
+
+    let testdata_dir = testdata_dir();
+    let fixture = testdata_dir.join("fixture.rs");
+
+    // The start buffer is not modified.
+    let mock_vim = MockVim::new()
+        .with_winsize(PREVIEW_WINID, 200, 20)
+        .with_response("clap#preview#direction", json!("LR"))
+        .with_buffer(START_BUFNR, read_lines(&fixture));
+    let mock_vim = if is_nvim { mock_vim.nvim() } else { mock_vim };
+
+    let ctx = mock_vim.context(provider_id, &testdata_dir, &fixture).await;
+
+    let result = match CachedPreviewImpl::new(item.to_string(), PREVIEW_HEIGHT, &ctx) {
+        Ok(preview_impl) => preview_impl.get_preview().await,
+        Err(err) => Err(err),
+    };
+
+    let value = match result {
+        Ok((_preview_target, preview)) => serde_json::to_value::<Preview>(preview).unwrap(),
+        Err(err) => err.to_vim_error_info(),
+    };
+
+    // The absolute paths vary across the machines.
+    serde_json::to_string_pretty(&value)
+        .unwrap()
+        .replace(testdata_dir.to_str().unwrap(), "$TESTDATA")
+}
+
+fn assert_golden(name: &str, actual: String) {
+    let golden_file = testdata_dir().join(format!("{name}.json"));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&golden_file, actual + "\n").unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&golden_file).unwrap_or_default();
+    assert_eq!(
+        expected.trim_end(),
+        actual,
+        "Preview of {name} mismatches the golden file {}, rerun with UPDATE_GOLDEN=1 if it's intended",
+        golden_file.display()
+    );
+}
+
+#[tokio::test]
+async fn test_golden_previews() {
+    // (name, provider_id, item, is_nvim)
+    let cases = [
+        (
+            "grep",
+            "grep",
+            "fixture.rs:5:5:    scores.insert(\"中文字符\", 1);",
+            false,
+        ),
+        (
+            "grep_nvim",
+            "grep",
+            "./fixture.rs:9:9:        println!",
+            true,
+        ),
+        ("files", "files", "fixture.rs", false),
+        ("blines", "blines", "12     let long_line", false),
+        (
+            "tags",
+            "tags",
+            "new:22 [method@Point]  fn new(x: i32, y: i32) -> Self {",
+            false,
+        ),
+        ("grep_invalid_item", "grep", "invalid grep item", false),
+        ("unknown_provider", "unknown", "fixture.rs", false),
+    ];
+
+    for (name, provider_id, item, is_nvim) in cases {
+        assert_golden(name, preview_snapshot(provider_id, item, is_nvim).await);
+    }
+}
+
+#[tokio::test]
+async fn test_preview_unsaved_start_buffer() {
+    initialize_config(TEST_CONFIG);
+
+    let testdata_dir = testdata_dir();
+    let mut lines = read_lines(&testdata_dir.join("fixture.rs"));
+    lines[11] = "    let unsaved_line = 1;".to_string();
+
+    for start_buffer_path in [testdata_dir.join("fixture.rs"), PathBuf::new()] {
+        let mock_vim = MockVim::new().with_buffer(START_BUFNR, lines.clone());
+        let ctx = mock_vim
+            .context("blines", &testdata_dir, &start_buffer_path)
+            .await;
+
+        for _ in 0..2 {
+            let preview_impl =
+                CachedPreviewImpl::new("12     let long_line".into(), PREVIEW_HEIGHT, &ctx)
+                    .unwrap();
+            let (_preview_target, preview) = preview_impl.get_preview().await.unwrap();
+            assert!(preview.lines.contains(&lines[11]));
+        }
+
+        // The unchanged buffer is fetched only once.
+        let fetches = mock_vim
+            .calls()
+            .into_iter()
+            .filter(|call| call.method == "getbufline")
+            .count();
+        assert_eq!(fetches, 1);
+    }
+}
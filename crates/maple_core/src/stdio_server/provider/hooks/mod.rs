@@ -1,5 +1,14 @@
+#[cfg(test)]
+mod golden_previews;
+mod on_copy;
+mod on_initial_query;
 mod on_initialize;
 mod on_move;
 
-pub use self::on_initialize::initialize_provider;
-pub use self::on_move::{CachedPreviewImpl, Preview, PreviewTarget};
+pub use self::on_copy::copy_selection;
+pub use self::on_initial_query::resolve_initial_query;
+pub use self::on_initialize::{initialize_provider, refresh_command_source};
+pub use self::on_move::{
+    CachedPreviewImpl, GitFooterCache, Preview, PreviewError, PreviewPatch, PreviewTarget,
+    SyntaxTreeCache, TsHighlightsCache,
+};
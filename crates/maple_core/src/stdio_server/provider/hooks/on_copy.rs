@@ -0,0 +1,216 @@
+use super::on_move::{parse_preview_target, PreviewTarget};
+use crate::stdio_server::provider::{Context, ProviderError, ProviderResult};
+use rpc::Params;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Structured data about the current item copied to the register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CopyFormat {
+    /// `/home/user/project/src/lib.rs`
+    AbsolutePath,
+    /// `src/lib.rs`, the path outside the project is kept as is.
+    RelativePath,
+    /// `src/lib.rs:10`
+    PathLnum,
+    /// `[src/lib.rs:10](src/lib.rs#L10)`
+    MarkdownLink,
+}
+
+/// Replaces the control chars with a space so that the payload can be pasted as a
+/// single line safely.
+fn clipboard_safe(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn escape_markdown_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '[' | ']' | '*' | '_' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn encode_link_destination(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            ' ' => encoded.push_str("%20"),
+            '(' => encoded.push_str("%28"),
+            ')' => encoded.push_str("%29"),
+            '<' => encoded.push_str("%3C"),
+            '>' => encoded.push_str("%3E"),
+            c => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// Returns the payload of `preview_target` in `format`.
+///
+/// The targets without a path, e.g., the git commit, are always copied as the
+/// identifier of the item.
+pub fn copy_payload(preview_target: &PreviewTarget, format: CopyFormat, cwd: &Path) -> String {
+    let (path, line_number) = match preview_target {
+        PreviewTarget::File(path)
+        | PreviewTarget::Directory(path)
+        | PreviewTarget::Archive(path)
+        | PreviewTarget::BufferDiff { path, .. } => (path, None),
+        PreviewTarget::LineInFile {
+            path, line_number, ..
+        } => (path, Some(*line_number)),
+        PreviewTarget::GitCommit(rev) | PreviewTarget::GitRef(rev) => return clipboard_safe(rev),
+        PreviewTarget::HelpTags { subject, .. } => return clipboard_safe(subject),
+        PreviewTarget::ConfigKey(key) | PreviewTarget::Command(key) | PreviewTarget::Url(key) => {
+            return clipboard_safe(key)
+        }
+        PreviewTarget::Text(lines) => return clipboard_safe(&lines.join(" ")),
+        PreviewTarget::ManPage { page, section } => {
+            return match section {
+                Some(section) => clipboard_safe(&format!("{page}({section})")),
+                None => clipboard_safe(page),
+            }
+        }
+    };
+
+    let relative_path = path.strip_prefix(cwd).unwrap_or(path).display().to_string();
+    let with_lnum = |path: &str| match line_number {
+        Some(lnum) => format!("{path}:{lnum}"),
+        None => path.to_string(),
+    };
+
+    let payload = match format {
+        CopyFormat::AbsolutePath => path.display().to_string(),
+        CopyFormat::RelativePath => relative_path,
+        CopyFormat::PathLnum => with_lnum(&relative_path),
+        CopyFormat::MarkdownLink => {
+            let anchor = line_number
+                .map(|lnum| format!("#L{lnum}"))
+                .unwrap_or_default();
+            format!(
+                "[{}]({}{anchor})",
+                escape_markdown_text(&with_lnum(&relative_path)),
+                encode_link_destination(&relative_path)
+            )
+        }
+    };
+
+    clipboard_safe(&payload)
+}
+
+/// Returns the OSC 52 escape sequence which sets the system clipboard of the terminal
+/// to `payload`, it's wrapped in the passthrough sequence if running in tmux.
+pub fn osc52_sequence(payload: &str, tmux: bool) -> String {
+    let osc52 = format!("\x1b]52;c;{}\x07", base64::encode(payload));
+    if tmux {
+        format!("\x1bPtmux;{}\x1b\\", osc52.replace('\x1b', "\x1b\x1b"))
+    } else {
+        osc52
+    }
+}
+
+/// Copies the current item in the format specified in `params` to the register
+/// configured on the Vim side.
+///
+/// The OSC 52 sequence of the payload is also sent back if requested, so that the
+/// item can be copied to the local clipboard in the remote session.
+pub async fn copy_selection(ctx: &Context, params: Params) -> ProviderResult<()> {
+    #[derive(Deserialize)]
+    struct CopyParams {
+        format: CopyFormat,
+        #[serde(default)]
+        osc52: bool,
+        #[serde(default)]
+        tmux: bool,
+    }
+
+    let CopyParams {
+        format,
+        osc52,
+        tmux,
+    } = params
+        .parse()
+        .map_err(|err| ProviderError::Other(err.to_string()))?;
+
+    let curline = ctx.vim.display_getcurline().await?;
+
+    if curline.is_empty() {
+        ctx.vim.echo_warn("Nothing to copy")?;
+        return Ok(());
+    }
+
+    let (preview_target, _line_content) = parse_preview_target(curline, ctx)?;
+
+    let payload = copy_payload(&preview_target, format, &ctx.cwd);
+
+    if osc52 {
+        let sequence = osc52_sequence(&payload, tmux);
+        ctx.vim
+            .exec("clap#copy#set_register", [payload, sequence])?;
+    } else {
+        ctx.vim.exec("clap#copy#set_register", [payload])?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_copy_payload() {
+        let cwd = Path::new("/project");
+        let target = PreviewTarget::LineInFile {
+            path: PathBuf::from("/project/src/my (old)_lib.rs"),
+            line_number: 10,
+            column: None,
+        };
+        let payload = |format| copy_payload(&target, format, cwd);
+
+        assert_eq!(
+            payload(CopyFormat::AbsolutePath),
+            "/project/src/my (old)_lib.rs"
+        );
+        assert_eq!(payload(CopyFormat::RelativePath), "src/my (old)_lib.rs");
+        assert_eq!(payload(CopyFormat::PathLnum), "src/my (old)_lib.rs:10");
+        assert_eq!(
+            payload(CopyFormat::MarkdownLink),
+            "[src/my (old)\\_lib.rs:10](src/my%20%28old%29_lib.rs#L10)"
+        );
+
+        let target = PreviewTarget::File(PathBuf::from("/elsewhere/a\nb.rs"));
+        assert_eq!(
+            copy_payload(&target, CopyFormat::PathLnum, cwd),
+            "/elsewhere/a b.rs"
+        );
+
+        let target = PreviewTarget::GitCommit("abc1234".into());
+        assert_eq!(
+            copy_payload(&target, CopyFormat::MarkdownLink, cwd),
+            "abc1234"
+        );
+    }
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(
+            osc52_sequence("src/lib.rs:10", false),
+            "\x1b]52;c;c3JjL2xpYi5yczoxMA==\x07"
+        );
+        assert_eq!(
+            osc52_sequence("a", true),
+            "\x1bPtmux;\x1b\x1b]52;c;YQ==\x07\x1b\\"
+        );
+    }
+}
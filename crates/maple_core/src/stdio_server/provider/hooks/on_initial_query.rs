@@ -0,0 +1,91 @@
+use crate::stdio_server::provider::Context;
+use crate::stdio_server::vim::VimResult;
+use itertools::Itertools;
+use serde_json::json;
+use std::borrow::Cow;
+
+/// How the initial query is interpreted by the provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryMode {
+    /// The query is parsed into the fuzzy search terms.
+    Fuzzy,
+    /// The first term of query is used as a regex, e.g., the keyword of dumb_jump.
+    Regex,
+}
+
+impl QueryMode {
+    pub fn for_provider(provider_id: &str) -> Self {
+        match provider_id {
+            "dumb_jump" => Self::Regex,
+            _ => Self::Fuzzy,
+        }
+    }
+}
+
+/// Returns the initial query specified by `--query`, which can be:
+///
+/// - `@visual`: the last visual selection in the start window.
+/// - `<cword>` or `<cWORD>`: the word under cursor in the start window.
+/// - otherwise the query is used as is.
+///
+/// The text from the start window is escaped according to the [`QueryMode`] of provider.
+pub async fn resolve_initial_query(ctx: &Context, query: String) -> VimResult<String> {
+    if !matches!(query.as_str(), "@visual" | "<cword>" | "<cWORD>") {
+        return Ok(query);
+    }
+
+    let text: String = ctx.vim.call("initial_query_source", json!([query])).await?;
+
+    Ok(build_initial_query(
+        &text,
+        QueryMode::for_provider(ctx.provider_id()),
+    ))
+}
+
+/// Converts the raw `text` into a query of `mode` matching `text` literally.
+///
+/// The lines of multiline text are joined as the whitespaces are the separators of
+/// search terms anyway.
+fn build_initial_query(text: &str, mode: QueryMode) -> String {
+    match mode {
+        QueryMode::Fuzzy => text.split_whitespace().map(escape_search_term).join(" "),
+        QueryMode::Regex => text
+            .split_whitespace()
+            .next()
+            .map(|keyword| escape_search_term(&regex::escape(keyword)).into_owned())
+            .unwrap_or_default(),
+    }
+}
+
+/// Escapes the term which will be parsed as a special search term otherwise,
+/// e.g., `^foo` is a prefix-exact term, by turning it into an exact term.
+fn escape_search_term(term: &str) -> Cow<'_, str> {
+    if term.starts_with(['\'', '^', '!', '"']) || term.ends_with('$') {
+        format!("'{term}").into()
+    } else {
+        term.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_initial_query() {
+        assert_eq!(
+            build_initial_query("fn foo(\n    bar: &str,\n)", QueryMode::Fuzzy),
+            "fn foo( bar: &str, )"
+        );
+        assert_eq!(
+            build_initial_query("!ready ^start end$", QueryMode::Fuzzy),
+            "'!ready '^start 'end$"
+        );
+        assert_eq!(
+            build_initial_query("foo.bar(baz)", QueryMode::Regex),
+            "foo\\.bar\\(baz\\)"
+        );
+        assert_eq!(build_initial_query("$var", QueryMode::Regex), "\\$var");
+        assert_eq!(build_initial_query("", QueryMode::Regex), "");
+    }
+}
@@ -1,29 +1,34 @@
 use crate::process::ShellCommand;
+use crate::stdio_server::provider::registry::{self, SourceSpec};
 use crate::stdio_server::provider::{Context, ProviderResult as Result, ProviderSource};
+use crate::stdio_server::watcher;
 use crate::tools::ctags::ProjectCtagsCommand;
+use crate::tools::rg::RgTokioCommand;
+use crate::tools::symbols::{collect_workspace_symbols, LspSymbol};
+use crate::tools::vcs::{self, VcsKind};
 use filter::SourceItem;
-use printer::{DisplayLines, Printer};
+use printer::DisplayLines;
 use serde_json::{json, Value};
+use std::future::Future;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Duration;
 use types::ClapItem;
 use utils::count_lines;
 
-async fn execute_and_write_cache(
-    cmd: &str,
-    cache_file: std::path::PathBuf,
-) -> std::io::Result<ProviderSource> {
+async fn execute_and_write_cache(shell_cmd: ShellCommand) -> std::io::Result<ProviderSource> {
     // Can not use subprocess::Exec::shell here.
     //
     // Must use TokioCommand otherwise the timeout may not work.
 
-    let mut tokio_cmd = crate::process::tokio::shell_command(cmd);
-    crate::process::tokio::write_stdout_to_file(&mut tokio_cmd, &cache_file).await?;
-    let total = count_lines(std::fs::File::open(&cache_file)?)?;
+    let mut tokio_cmd = crate::process::tokio::shell_command(&shell_cmd.command);
+    let digest = crate::cache::create_cache_async(shell_cmd, |cache_file| async move {
+        crate::process::tokio::write_stdout_to_file(&mut tokio_cmd, cache_file).await
+    })
+    .await?;
     Ok(ProviderSource::CachedFile {
-        total,
-        path: cache_file,
+        total: digest.total,
+        path: digest.cached_path,
         refreshed: true,
     })
 }
@@ -37,41 +42,66 @@ fn to_small_provider_source(lines: Vec<String>) -> ProviderSource {
     ProviderSource::Small { total, items }
 }
 
-#[allow(unused)]
-async fn init_proj_tags(ctx: &Context) -> std::io::Result<ProviderSource> {
-    let ctags_cmd = ProjectCtagsCommand::with_cwd(ctx.cwd.to_path_buf());
-    let provider_source = if true {
-        let lines = ctags_cmd.execute_and_write_cache().await?;
-        to_small_provider_source(lines)
+/// Creates the source from the output of shell command `command`, reusing the cache if any.
+///
+/// The shell command is also returned if the cache is stale, i.e., it's created before the
+/// directory is watched, which needs to be refreshed.
+async fn command_source(
+    ctx: &Context,
+    command: String,
+) -> Result<(ProviderSource, Option<ShellCommand>)> {
+    let shell_cmd = ShellCommand::new(command, ctx.cwd.to_path_buf());
+    ctx.source_command.lock().replace(shell_cmd.clone());
+
+    const DIRECT_CREATE_NEW_SOURCE: &[&str] = &["files"];
+
+    let create_new_source_directly = DIRECT_CREATE_NEW_SOURCE.contains(&ctx.provider_id());
+
+    let (provider_source, stale_cmd) = if create_new_source_directly || ctx.env.no_cache {
+        (execute_and_write_cache(shell_cmd).await?, None)
     } else {
-        match ctags_cmd.ctags_cache() {
-            Some((total, path)) => ProviderSource::CachedFile {
-                total,
-                path,
-                refreshed: false,
-            },
-            None => {
-                let lines = ctags_cmd.execute_and_write_cache().await?;
-                to_small_provider_source(lines)
+        match shell_cmd.cache_digest() {
+            Some(digest) => {
+                let stale = !watcher::is_watched_since(&ctx.cwd, digest.execution_time);
+                (
+                    ProviderSource::CachedFile {
+                        total: digest.total,
+                        path: digest.cached_path,
+                        refreshed: false,
+                    },
+                    stale.then_some(shell_cmd),
+                )
             }
+            None => (execute_and_write_cache(shell_cmd).await?, None),
         }
     };
-    Ok(provider_source)
+
+    if let ProviderSource::CachedFile { path, .. } = &provider_source {
+        ctx.vim.set_var("g:__clap_forerunner_tempfile", path)?;
+        watcher::watch_provider_cache_dir(&ctx.cwd);
+    }
+
+    Ok((provider_source, stale_cmd))
 }
 
 /// Performs the initialization like collecting the source and total number of source items.
-async fn initialize_provider_source(ctx: &Context) -> Result<ProviderSource> {
+///
+/// Returns the shell command to refresh the source in the background as well if the source
+/// is warm-started from the cache.
+async fn initialize_provider_source(
+    ctx: &Context,
+) -> Result<(ProviderSource, Option<ShellCommand>)> {
     // Known providers.
     match ctx.provider_id() {
         "blines" => {
             let total = count_lines(std::fs::File::open(&ctx.env.start_buffer_path)?)?;
             let path = ctx.env.start_buffer_path.clone();
-            return Ok(ProviderSource::File { total, path });
+            return Ok((ProviderSource::File { total, path }, None));
         }
         "tags" => {
             let items = crate::tools::ctags::buffer_tag_items(&ctx.env.start_buffer_path, false)?;
             let total = items.len();
-            return Ok(ProviderSource::Small { total, items });
+            return Ok((ProviderSource::Small { total, items }, None));
         }
         "help_tags" => {
             let helplang: String = ctx.vim.eval("&helplang").await?;
@@ -83,43 +113,38 @@ async fn initialize_provider_source(ctx: &Context) -> Result<ProviderSource> {
                     .map(|lang| format!("/doc/tags-{lang}")),
             );
             let lines = crate::helptags::generate_tag_lines(doc_tags, &runtimepath);
-            return Ok(to_small_provider_source(lines));
+            return Ok((to_small_provider_source(lines), None));
+        }
+        "stats" => {
+            let usage_stats = crate::datastore::USAGE_STATS_IN_MEMORY.lock().clone();
+            let digests = crate::datastore::CACHE_INFO_IN_MEMORY.lock().to_digests();
+            let recent_files = crate::datastore::RECENT_FILES_IN_MEMORY
+                .lock()
+                .entries
+                .clone();
+            let lines = crate::usage_stats::stats_lines(&usage_stats, &digests, &recent_files);
+            return Ok((to_small_provider_source(lines), None));
+        }
+        "config" => {
+            let lines = crate::config_editor::config_lines();
+            return Ok((to_small_provider_source(lines), None));
         }
         _ => {}
     }
 
+    // Providers registered at runtime with a known source don't have to ask Vim.
+    if let Some(source) = registry::get(ctx.provider_id()).and_then(|spec| spec.source) {
+        return match source {
+            SourceSpec::Command(command) => command_source(ctx, command).await,
+            SourceSpec::List(lines) => Ok((to_small_provider_source(lines), None)),
+        };
+    }
+
     let source_cmd: Vec<Value> = ctx.vim.bare_call("provider_source").await?;
     if let Some(value) = source_cmd.into_iter().next() {
         match value {
             // Source is a String: g:__t_string, g:__t_func_string
-            Value::String(command) => {
-                let shell_cmd = ShellCommand::new(command, ctx.cwd.to_path_buf());
-                let cache_file = shell_cmd.cache_file_path()?;
-
-                const DIRECT_CREATE_NEW_SOURCE: &[&str] = &["files"];
-
-                let create_new_source_directly =
-                    DIRECT_CREATE_NEW_SOURCE.contains(&ctx.provider_id());
-
-                let provider_source = if create_new_source_directly || ctx.env.no_cache {
-                    execute_and_write_cache(&shell_cmd.command, cache_file).await?
-                } else {
-                    match shell_cmd.cache_digest() {
-                        Some(digest) => ProviderSource::CachedFile {
-                            total: digest.total,
-                            path: digest.cached_path,
-                            refreshed: false,
-                        },
-                        None => execute_and_write_cache(&shell_cmd.command, cache_file).await?,
-                    }
-                };
-
-                if let ProviderSource::CachedFile { path, .. } = &provider_source {
-                    ctx.vim.set_var("g:__clap_forerunner_tempfile", path)?;
-                }
-
-                return Ok(provider_source);
-            }
+            Value::String(command) => return command_source(ctx, command).await,
             // Source is a List: g:__t_list, g:__t_func_list
             Value::Array(arr) => {
                 let lines = arr
@@ -132,13 +157,13 @@ async fn initialize_provider_source(ctx: &Context) -> Result<ProviderSource> {
                         }
                     })
                     .collect::<Vec<_>>();
-                return Ok(to_small_provider_source(lines));
+                return Ok((to_small_provider_source(lines), None));
             }
             _ => {}
         }
     }
 
-    Ok(ProviderSource::Uninitialized)
+    Ok((ProviderSource::Uninitialized, None))
 }
 
 fn on_initialized_source(
@@ -152,11 +177,12 @@ fn on_initialized_source(
 
     if init_display {
         if let Some(items) = provider_source.try_skim(ctx.provider_id(), 100) {
-            let printer = Printer::new(ctx.env.display_winwidth, ctx.env.icon);
+            let printer = ctx.printer();
             let DisplayLines {
                 lines,
                 icon_added,
                 truncated_map,
+                highlights,
                 ..
             } = printer.to_display_lines(items);
 
@@ -164,7 +190,7 @@ fn on_initialized_source(
 
             ctx.vim.exec(
                 "clap#state#init_display",
-                json!([lines, truncated_map, icon_added, using_cache]),
+                json!([lines, truncated_map, icon_added, using_cache, highlights]),
             )?;
         }
         if ctx.initializing_prompt_echoed.load(Ordering::SeqCst) {
@@ -177,6 +203,91 @@ fn on_initialized_source(
     Ok(())
 }
 
+/// Initializes the display with the `cached` source of previous session and swaps in the fresh
+/// source once `refresh` is done, the forerunner status shows the stale items in between.
+fn warm_start<F>(
+    ctx: &Context,
+    cached: ProviderSource,
+    init_display: bool,
+    refresh: F,
+) -> Result<()>
+where
+    F: Future<Output = std::io::Result<ProviderSource>> + Send + 'static,
+{
+    on_initialized_source(cached, ctx, init_display)?;
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        let fresh = match refresh.await {
+            Ok(fresh) => fresh,
+            Err(err) => {
+                tracing::error!(
+                    ?err,
+                    provider_id = ctx.provider_id(),
+                    "Failed to refresh the source"
+                );
+                return;
+            }
+        };
+
+        if ctx.terminated.load(Ordering::SeqCst) {
+            return;
+        }
+
+        if let Err(err) = on_refreshed_source(fresh, &ctx).await {
+            tracing::error!(?err, "Failed to swap in the refreshed source");
+        }
+    });
+
+    Ok(())
+}
+
+/// Replaces the stale source with `fresh` and re-ranks the current query against it.
+async fn on_refreshed_source(fresh: ProviderSource, ctx: &Context) -> Result<()> {
+    if let Some(total) = fresh.total() {
+        ctx.vim.set_var("g:clap.display.initial_size", total)?;
+    }
+    if let ProviderSource::CachedFile { path, .. } = &fresh {
+        ctx.vim.set_var("g:__clap_forerunner_tempfile", path)?;
+    }
+
+    ctx.set_provider_source(fresh);
+    ctx.vim
+        .exec("clap#state#set_forerunner_status", json!(["done"]))?;
+
+    ctx.refresh_display().await
+}
+
+/// Reruns the command of the cached source in the background and swaps in the fresh source.
+///
+/// Does nothing if the source is not created from a command or it's being refreshed.
+pub fn refresh_command_source(ctx: &Context) {
+    let Some(shell_cmd) = ctx.source_command.lock().take() else {
+        return;
+    };
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        match execute_and_write_cache(shell_cmd.clone()).await {
+            Ok(fresh) => {
+                if !ctx.terminated.load(Ordering::SeqCst) {
+                    if let Err(err) = on_refreshed_source(fresh, &ctx).await {
+                        tracing::error!(?err, "Failed to swap in the refreshed source");
+                    }
+                }
+            }
+            Err(err) => {
+                tracing::error!(
+                    ?err,
+                    provider_id = ctx.provider_id(),
+                    "Failed to refresh the source"
+                );
+            }
+        }
+        ctx.source_command.lock().replace(shell_cmd);
+    });
+}
+
 async fn initialize_list_source(ctx: Context, init_display: bool) -> Result<()> {
     let source_cmd: Vec<Value> = ctx.vim.bare_call("provider_source").await?;
     // Source must be initialized when it is a List: g:__t_list, g:__t_func_list
@@ -200,13 +311,54 @@ async fn initialize_list_source(ctx: Context, init_display: bool) -> Result<()>
 pub async fn initialize_provider(ctx: &Context, init_display: bool) -> Result<()> {
     // Skip the initialization.
     match ctx.provider_id() {
-        "grep" | "live_grep" => return Ok(()),
+        "grep" | "live_grep" => {
+            // The matches are searched on typing, the lines of the ripgrep cache are only
+            // displayed for the empty query.
+            let rg_cmd = RgTokioCommand::new(ctx.cwd.to_path_buf());
+            if let Some(digest) = rg_cmd.cache_digest() {
+                let cached = ProviderSource::CachedFile {
+                    total: digest.total,
+                    path: digest.cached_path,
+                    refreshed: false,
+                };
+                watcher::watch_provider_cache_dir(&ctx.cwd);
+                if watcher::is_watched_since(&ctx.cwd, digest.execution_time) {
+                    on_initialized_source(cached, ctx, init_display)?;
+                } else {
+                    warm_start(ctx, cached, init_display, async move {
+                        let digest = rg_cmd.create_cache().await?;
+                        Ok(ProviderSource::CachedFile {
+                            total: digest.total,
+                            path: digest.cached_path,
+                            refreshed: true,
+                        })
+                    })?;
+                }
+            }
+            return Ok(());
+        }
         "proj_tags" => {
+            let ctags_cmd = ProjectCtagsCommand::with_cwd(ctx.cwd.to_path_buf());
+
+            // Show the tags of previous session while the fresh ones are being collected.
+            if let Some((total, path)) = ctags_cmd.ctags_cache() {
+                let cached = ProviderSource::CachedFile {
+                    total,
+                    path,
+                    refreshed: false,
+                };
+                return warm_start(ctx, cached, init_display, async move {
+                    let lines =
+                        tokio::task::spawn_blocking(move || ctags_cmd.execute_and_write_cache())
+                            .await??;
+                    Ok(to_small_provider_source(lines))
+                });
+            }
+
             ctx.set_provider_source(ProviderSource::Initializing);
             let ctx = ctx.clone();
             std::thread::spawn(move || {
-                let mut ctags_cmd = ProjectCtagsCommand::with_cwd(ctx.cwd.to_path_buf());
-                match ctags_cmd.par_formatted_lines() {
+                match ctags_cmd.execute_and_write_cache() {
                     Ok(lines) => {
                         let provider_source = to_small_provider_source(lines);
                         ctx.set_provider_source(provider_source);
@@ -222,6 +374,82 @@ pub async fn initialize_provider(ctx: &Context, init_display: bool) -> Result<()
             });
             return Ok(());
         }
+        "commits" | "bcommits" | "git_files" => {
+            // The git repo is handled by the source defined in Vim.
+            if let Some(vcs) = vcs::detect(&ctx.cwd).filter(|vcs| vcs.kind() != VcsKind::Git) {
+                ctx.set_provider_source(ProviderSource::Initializing);
+                let ctx = ctx.clone();
+                tokio::task::spawn_blocking(move || {
+                    let lines = match ctx.provider_id() {
+                        "commits" => vcs.log(None),
+                        "bcommits" => vcs.log(Some(&ctx.env.start_buffer_path)),
+                        _ => vcs.files().map(|files| {
+                            files
+                                .into_iter()
+                                .map(|file| {
+                                    let path = vcs.root().join(file);
+                                    path.strip_prefix(&ctx.cwd)
+                                        .map(|p| p.display().to_string())
+                                        .unwrap_or_else(|_| path.display().to_string())
+                                })
+                                .collect()
+                        }),
+                    };
+                    match lines {
+                        Ok(lines) => on_initialized_source(
+                            to_small_provider_source(lines),
+                            &ctx,
+                            init_display,
+                        ),
+                        Err(e) => {
+                            ctx.set_provider_source(ProviderSource::InitializationFailed(
+                                e.to_string(),
+                            ));
+                            Ok(())
+                        }
+                    }
+                });
+                return Ok(());
+            }
+        }
+        "symbols" => {
+            ctx.set_provider_source(ProviderSource::Initializing);
+            let ctx = ctx.clone();
+            tokio::spawn(async move {
+                // The language servers are managed by Vim, e.g., the builtin LSP client of Neovim.
+                let lsp_symbols: Vec<LspSymbol> = if crate::config::config().provider.disable_lsp {
+                    Vec::new()
+                } else {
+                    ctx.vim
+                        .bare_call("clap#provider#symbols#lsp_symbols")
+                        .await
+                        .unwrap_or_else(|err| {
+                            tracing::debug!(?err, "Failed to fetch the LSP workspace symbols");
+                            Vec::new()
+                        })
+                };
+                let cwd = ctx.cwd.to_path_buf();
+                let symbols = tokio::task::spawn_blocking(move || {
+                    collect_workspace_symbols(&cwd, lsp_symbols)
+                })
+                .await;
+                let provider_source = match symbols {
+                    Ok(symbols) => {
+                        let items = symbols
+                            .into_iter()
+                            .map(|symbol| Arc::new(symbol.into_symbol_item()) as Arc<dyn ClapItem>)
+                            .collect::<Vec<_>>();
+                        ProviderSource::Small {
+                            total: items.len(),
+                            items,
+                        }
+                    }
+                    Err(e) => ProviderSource::InitializationFailed(e.to_string()),
+                };
+                ctx.set_provider_source(provider_source);
+            });
+            return Ok(());
+        }
         _ => {}
     }
 
@@ -237,7 +465,15 @@ pub async fn initialize_provider(ctx: &Context, init_display: bool) -> Result<()
     const TIMEOUT: Duration = Duration::from_millis(300);
 
     match tokio::time::timeout(TIMEOUT, initialize_provider_source(ctx)).await {
-        Ok(Ok(provider_source)) => on_initialized_source(provider_source, ctx, init_display)?,
+        Ok(Ok((provider_source, None))) => {
+            on_initialized_source(provider_source, ctx, init_display)?
+        }
+        Ok(Ok((cached, Some(shell_cmd)))) => warm_start(
+            ctx,
+            cached,
+            init_display,
+            execute_and_write_cache(shell_cmd),
+        )?,
         Ok(Err(e)) => tracing::error!(?e, "Error occurred while initializing the provider source"),
         Err(_) => {
             // The initialization was not super fast.
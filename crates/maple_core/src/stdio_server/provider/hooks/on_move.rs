@@ -1,22 +1,38 @@
+use crate::config::{LongPreviewLines, PreviewPosition};
 use crate::previewer;
+use crate::previewer::ansi::{has_escape_sequence, parse_ansi_lines, AnsiHighlight};
+use crate::previewer::archive::ArchiveEntries;
+use crate::previewer::command_output::CommandOutput;
+use crate::previewer::special_file::special_file_preview;
 use crate::previewer::vim_help::HelpTagPreview;
 use crate::previewer::{get_file_preview, FilePreview};
+use crate::stdio_server::input::ProviderEvent;
 use crate::stdio_server::job;
 use crate::stdio_server::plugin::syntax::{
     convert_raw_ts_highlights_to_vim_highlights, sublime_syntax_by_extension,
-    sublime_syntax_highlight, sublime_theme_exists,
+    sublime_syntax_highlight, sublime_theme_exists, RawTsHighlights,
 };
+use crate::stdio_server::provider::preview_pool;
+use crate::stdio_server::provider::registry::{self, PreviewSpec};
 use crate::stdio_server::provider::{read_dir_entries, Context, ProviderSource};
-use crate::stdio_server::vim::{preview_syntax, VimResult};
-use crate::tools::ctags::{current_context_tag_async, BufferTag};
+use crate::stdio_server::vim::{preview_syntax, VimError};
+use crate::tools::ctags::{context_tags_async, BufferTag};
+use crate::tools::diff_pager;
+use crate::tools::git::LastCommit;
+use crate::tools::vcs::VcsKind;
+use crate::vfs::Vfs;
+use encoding_rs::Encoding;
 use paths::{expand_tilde, truncate_absolute_path};
 use pattern::*;
+use rpc::Params;
 use serde::{Deserialize, Serialize};
-use std::io::{Error, ErrorKind, Result};
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Result};
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::Ordering;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use sublime_syntax::TokenHighlight;
 use utils::display_width;
 
@@ -26,6 +42,166 @@ type SublimeHighlights = Vec<(usize, Vec<TokenHighlight>)>;
 type LineHighlights = Vec<(usize, usize, String)>;
 type TsHighlights = Vec<(usize, LineHighlights)>;
 
+/// Tree-sitter highlights of a whole file on disk.
+#[derive(Debug)]
+struct CachedTsHighlights {
+    path: PathBuf,
+    mtime: SystemTime,
+    highlights: Arc<RawTsHighlights>,
+}
+
+/// Tree-sitter highlights of the recently previewed files, the most recent first.
+///
+/// Previewing the lines of the same file, e.g., in `grep` and `blines`, reuses the
+/// highlights instead of parsing the whole file again on each cursor move, until the
+/// file is modified. The highlighter parses the source itself, the parsed trees used by
+/// the other features are cached in [`SyntaxTreeCache`].
+#[derive(Debug, Clone, Default)]
+pub struct TsHighlightsCache(Arc<parking_lot::Mutex<VecDeque<CachedTsHighlights>>>);
+
+impl TsHighlightsCache {
+    const CAPACITY: usize = 8;
+
+    /// Returns the cached highlights of `path` if it's not modified since, otherwise
+    /// highlights `source` read by `read_source` and caches the result.
+    fn get_or_highlight(
+        &self,
+        path: &Path,
+        language: tree_sitter::Language,
+        read_source: impl FnOnce() -> Option<Vec<u8>>,
+    ) -> Option<Arc<RawTsHighlights>> {
+        let highlight = |source: Vec<u8>| tree_sitter::highlight(language, &source).ok();
+
+        let Some(mtime) = std::fs::metadata(path).and_then(|m| m.modified()).ok() else {
+            return highlight(read_source()?).map(Arc::new);
+        };
+
+        {
+            let mut cache = self.0.lock();
+            if let Some(idx) = cache.iter().position(|cached| cached.path == path) {
+                let cached = cache.remove(idx)?;
+                if cached.mtime == mtime {
+                    let highlights = cached.highlights.clone();
+                    cache.push_front(cached);
+                    return Some(highlights);
+                }
+            }
+        }
+
+        let highlights = Arc::new(highlight(read_source()?)?);
+
+        let mut cache = self.0.lock();
+        cache.push_front(CachedTsHighlights {
+            path: path.to_path_buf(),
+            mtime,
+            highlights: highlights.clone(),
+        });
+        cache.truncate(Self::CAPACITY);
+
+        Some(highlights)
+    }
+}
+
+#[derive(Debug)]
+struct CachedGitFooter {
+    path: PathBuf,
+    footer: Option<String>,
+}
+
+/// Git footers of the recently previewed files, the most recent first.
+///
+/// The last commit of a file is looked up once per provider session, `None` is cached
+/// for the untracked file and the failed lookup.
+#[derive(Debug, Clone, Default)]
+pub struct GitFooterCache(Arc<parking_lot::Mutex<VecDeque<CachedGitFooter>>>);
+
+impl GitFooterCache {
+    const CAPACITY: usize = 64;
+
+    /// Returns the cached footer of `path`, `None` if it's not looked up yet.
+    fn get(&self, path: &Path) -> Option<Option<String>> {
+        self.0
+            .lock()
+            .iter()
+            .find_map(|cached| (cached.path == path).then(|| cached.footer.clone()))
+    }
+
+    fn insert(&self, path: PathBuf, footer: Option<String>) {
+        let mut cache = self.0.lock();
+        cache.retain(|cached| cached.path != path);
+        cache.push_front(CachedGitFooter { path, footer });
+        cache.truncate(Self::CAPACITY);
+    }
+}
+
+/// Syntax tree of a whole file on disk.
+#[derive(Debug)]
+struct CachedSyntaxTree {
+    path: PathBuf,
+    mtime: SystemTime,
+    source: Vec<u8>,
+    tree: tree_sitter::Tree,
+}
+
+/// Syntax trees of the recently previewed files, the most recent first.
+///
+/// The comment detection and the key path on each cursor move query the cached tree
+/// instead of reading and parsing the whole file again, until the file is modified.
+#[derive(Debug, Clone, Default)]
+pub struct SyntaxTreeCache(Arc<parking_lot::Mutex<VecDeque<CachedSyntaxTree>>>);
+
+impl SyntaxTreeCache {
+    const CAPACITY: usize = 8;
+
+    /// Files larger than this are never parsed.
+    const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+    /// Calls `f` with the syntax tree and the source of `path`, which is parsed from the
+    /// source read by `read_source` unless it's cached and not modified since.
+    ///
+    /// Returns `None` if the file is too large or can't be parsed.
+    fn with_tree<R>(
+        &self,
+        path: &Path,
+        language: tree_sitter::Language,
+        read_source: impl FnOnce() -> Option<Vec<u8>>,
+        f: impl FnOnce(&tree_sitter::Tree, &[u8]) -> R,
+    ) -> Option<R> {
+        let metadata = std::fs::metadata(path).ok()?;
+        if metadata.len() > Self::MAX_FILE_SIZE {
+            return None;
+        }
+        let mtime = metadata.modified().ok()?;
+
+        {
+            let mut cache = self.0.lock();
+            if let Some(idx) = cache.iter().position(|cached| cached.path == path) {
+                let cached = cache.remove(idx)?;
+                if cached.mtime == mtime {
+                    let output = f(&cached.tree, &cached.source);
+                    cache.push_front(cached);
+                    return Some(output);
+                }
+            }
+        }
+
+        let source = read_source()?;
+        let tree = tree_sitter::parse(language, &source)?;
+        let output = f(&tree, &source);
+
+        let mut cache = self.0.lock();
+        cache.push_front(CachedSyntaxTree {
+            path: path.to_path_buf(),
+            mtime,
+            source,
+            tree,
+        });
+        cache.truncate(Self::CAPACITY);
+
+        Some(output)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct VimSyntaxInfo {
     syntax: String,
@@ -52,6 +228,17 @@ impl VimSyntaxInfo {
     }
 }
 
+/// Lines highlighted with a Vim syntax other than the one of the whole preview, e.g.,
+/// the markdown cells of a notebook.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyntaxRegion {
+    pub syntax: String,
+    /// 1-based line number of the first line.
+    pub start: usize,
+    /// 1-based line number of the last line, inclusive.
+    pub end: usize,
+}
+
 /// Preview content.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Preview {
@@ -63,6 +250,9 @@ pub struct Preview {
     /// Vim will inspect the syntax value from `fname`.
     #[serde(skip_serializing_if = "VimSyntaxInfo::is_empty")]
     pub vim_syntax_info: VimSyntaxInfo,
+    /// Regions highlighted with their own Vim syntax on top of `vim_syntax_info`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub syntax_regions: Vec<SyntaxRegion>,
     /// Highlights from sublime-syntax highlight engine.
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub sublime_syntax_highlights: SublimeHighlights,
@@ -71,8 +261,19 @@ pub struct Preview {
     pub tree_sitter_highlights: TsHighlights,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hi_lnum: Option<usize>,
+    /// `(line_idx, start, length)` of the match on the highlighted line, e.g., the grep
+    /// match, where `line_idx` is 0-based and the span is in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_highlight: Option<(usize, usize, usize)>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub scrollbar: Option<(usize, usize)>,
+    /// Highlights converted from the ANSI escape sequences in the output of the external
+    /// tools, `(lnum, highlights)` where lnum is 1-based.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ansi_highlights: Vec<(usize, Vec<AnsiHighlight>)>,
+    /// Only the changed lines relative to the previous preview, `lines` is ignored if set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch: Option<PreviewPatch>,
 }
 
 impl Preview {
@@ -95,6 +296,419 @@ impl Preview {
             ..Default::default()
         }
     }
+
+    /// Clamps the highlight spans to the length of the (truncated) lines, the spans
+    /// starting beyond the line end or on a nonexistent line are dropped.
+    ///
+    /// Otherwise Vim may throw the `col out of range` error on adding the highlights.
+    fn clamp_highlights(&mut self) {
+        let lines = &self.lines;
+
+        // Returns the clamped length of span `(start, length)` on `line`.
+        let clamp = |line: &str, start: usize, length: usize| {
+            if start >= line.len() {
+                None
+            } else {
+                Some(length.min(line.len() - start))
+            }
+        };
+
+        // The sublime-syntax highlights are 1-based.
+        self.sublime_syntax_highlights
+            .retain_mut(|(lnum, token_highlights)| {
+                let Some(line) = lnum.checked_sub(1).and_then(|idx| lines.get(idx)) else {
+                    tracing::debug!(
+                        lnum,
+                        "Dropping the sublime-syntax highlights on nonexistent line"
+                    );
+                    return false;
+                };
+                token_highlights.retain_mut(|token| {
+                    match clamp(line, token.col_start, token.length) {
+                        Some(length) => {
+                            token.length = length;
+                            true
+                        }
+                        None => {
+                            tracing::debug!(
+                                lnum,
+                                ?token,
+                                "Dropping the out-of-range sublime-syntax highlight"
+                            );
+                            false
+                        }
+                    }
+                });
+                true
+            });
+
+        // The tree-sitter highlights are 0-based.
+        self.tree_sitter_highlights
+            .retain_mut(|(line_idx, line_highlights)| {
+                let Some(line) = lines.get(*line_idx) else {
+                    tracing::debug!(
+                        line_idx,
+                        "Dropping the tree-sitter highlights on nonexistent line"
+                    );
+                    return false;
+                };
+                line_highlights.retain_mut(|(start, length, group)| {
+                    match clamp(line, *start, *length) {
+                        Some(clamped) => {
+                            *length = clamped;
+                            true
+                        }
+                        None => {
+                            tracing::debug!(
+                                line_idx,
+                                start,
+                                length,
+                                group,
+                                "Dropping the out-of-range tree-sitter highlight"
+                            );
+                            false
+                        }
+                    }
+                });
+                true
+            });
+
+        // The ANSI highlights are 1-based.
+        self.ansi_highlights.retain_mut(|(lnum, highlights)| {
+            let Some(line) = lnum.checked_sub(1).and_then(|idx| lines.get(idx)) else {
+                return false;
+            };
+            highlights.retain_mut(|highlight| {
+                match clamp(line, highlight.col_start, highlight.length) {
+                    Some(length) => {
+                        highlight.length = length;
+                        true
+                    }
+                    None => false,
+                }
+            });
+            true
+        });
+
+        self.match_highlight = self.match_highlight.and_then(|(line_idx, start, length)| {
+            let line = lines.get(line_idx)?;
+            clamp(line, start, length).map(|length| (line_idx, start, length))
+        });
+    }
+
+    /// Adds `footer` as the last line, which replaces the last line of the preview if it
+    /// already has `max_lines` lines to keep the footer in the window.
+    ///
+    /// The footer is skipped if it would replace the highlighted line.
+    pub fn push_footer(&mut self, footer: String, max_lines: usize) {
+        if self.lines.len() >= max_lines {
+            let kept = max_lines.saturating_sub(1).max(1);
+            if self.hi_lnum.map_or(false, |hi_lnum| hi_lnum >= kept) {
+                return;
+            }
+            self.lines.truncate(kept);
+        }
+
+        let footer_idx = self.lines.len();
+        self.sublime_syntax_highlights
+            .retain(|(lnum, _)| *lnum != footer_idx + 1);
+        self.tree_sitter_highlights
+            .retain(|(line_idx, _)| *line_idx != footer_idx);
+        self.ansi_highlights
+            .retain(|(lnum, _)| *lnum != footer_idx + 1);
+        self.tree_sitter_highlights
+            .push((footer_idx, vec![(0, footer.len(), "Comment".to_string())]));
+        self.lines.push(footer);
+
+        self.clamp_highlights();
+    }
+
+    /// Soft-wraps the lines wider than `width` across multiple rows, `hi_lnum` and the
+    /// highlights are moved to the rows accordingly.
+    fn wrap_lines(&mut self, width: usize) {
+        let row_offsets = self
+            .lines
+            .iter()
+            .map(|line| previewer::wrap_offsets(line, width))
+            .collect::<Vec<_>>();
+
+        if row_offsets.iter().all(|offsets| offsets.len() == 1) {
+            return;
+        }
+
+        // Index of the first row of each line.
+        let first_rows = row_offsets
+            .iter()
+            .scan(0, |row, offsets| {
+                let first_row = *row;
+                *row += offsets.len();
+                Some(first_row)
+            })
+            .collect::<Vec<_>>();
+
+        // Splits the span `(start, length)` on line `line_idx` into the spans on the rows.
+        let split_span = |line_idx: usize, start: usize, length: usize| {
+            let offsets = &row_offsets[line_idx];
+            let first_row = first_rows[line_idx];
+            offsets
+                .iter()
+                .enumerate()
+                .filter_map(move |(row, &row_start)| {
+                    let row_end = offsets.get(row + 1).copied().unwrap_or(usize::MAX);
+                    let span_start = start.max(row_start);
+                    let span_end = (start + length).min(row_end);
+                    if span_start < span_end {
+                        Some((
+                            first_row + row,
+                            span_start - row_start,
+                            span_end - span_start,
+                        ))
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // `base` is 0 for the 0-based line index and 1 for the 1-based line number.
+        fn wrap_highlights<H: Clone>(
+            highlights: Vec<(usize, Vec<H>)>,
+            base: usize,
+            span: impl Fn(&H) -> (usize, usize),
+            set_span: impl Fn(&mut H, usize, usize),
+            split_span: impl Fn(usize, usize, usize) -> Vec<(usize, usize, usize)>,
+        ) -> Vec<(usize, Vec<H>)> {
+            let mut wrapped = std::collections::BTreeMap::<usize, Vec<H>>::new();
+            for (line, line_highlights) in highlights {
+                let Some(line_idx) = line.checked_sub(base) else {
+                    continue;
+                };
+                for highlight in line_highlights {
+                    let (start, length) = span(&highlight);
+                    for (row, row_start, row_length) in split_span(line_idx, start, length) {
+                        let mut highlight = highlight.clone();
+                        set_span(&mut highlight, row_start, row_length);
+                        wrapped.entry(row + base).or_default().push(highlight);
+                    }
+                }
+            }
+            wrapped.into_iter().collect()
+        }
+
+        let is_valid_line = |line_idx: usize| line_idx < row_offsets.len();
+
+        self.tree_sitter_highlights
+            .retain(|(idx, _)| is_valid_line(*idx));
+        self.tree_sitter_highlights = wrap_highlights(
+            std::mem::take(&mut self.tree_sitter_highlights),
+            0,
+            |(start, length, _)| (*start, *length),
+            |(start, length, _), row_start, row_length| {
+                *start = row_start;
+                *length = row_length;
+            },
+            split_span,
+        );
+
+        self.sublime_syntax_highlights
+            .retain(|(lnum, _)| lnum.checked_sub(1).map_or(false, is_valid_line));
+        self.sublime_syntax_highlights = wrap_highlights(
+            std::mem::take(&mut self.sublime_syntax_highlights),
+            1,
+            |token| (token.col_start, token.length),
+            |token, row_start, row_length| {
+                token.col_start = row_start;
+                token.length = row_length;
+            },
+            split_span,
+        );
+
+        self.ansi_highlights
+            .retain(|(lnum, _)| lnum.checked_sub(1).map_or(false, is_valid_line));
+        self.ansi_highlights = wrap_highlights(
+            std::mem::take(&mut self.ansi_highlights),
+            1,
+            |highlight| (highlight.col_start, highlight.length),
+            |highlight, row_start, row_length| {
+                highlight.col_start = row_start;
+                highlight.length = row_length;
+            },
+            split_span,
+        );
+
+        self.hi_lnum = self
+            .hi_lnum
+            .map(|hi_lnum| first_rows.get(hi_lnum).copied().unwrap_or(hi_lnum));
+
+        self.syntax_regions
+            .retain(|region| region.start <= region.end && is_valid_line(region.end - 1));
+        for region in &mut self.syntax_regions {
+            let end_idx = region.end - 1;
+            region.start = first_rows[region.start - 1] + 1;
+            region.end = first_rows[end_idx] + row_offsets[end_idx].len();
+        }
+
+        // Only the part on the first row is highlighted if the match is wrapped.
+        self.match_highlight = self
+            .match_highlight
+            .filter(|(line_idx, _, _)| is_valid_line(*line_idx))
+            .and_then(|(line_idx, start, length)| {
+                split_span(line_idx, start, length).into_iter().next()
+            });
+
+        self.lines = std::mem::take(&mut self.lines)
+            .into_iter()
+            .zip(&row_offsets)
+            .flat_map(|(line, offsets)| {
+                offsets
+                    .iter()
+                    .enumerate()
+                    .map(|(row, &row_start)| {
+                        let row_end = offsets.get(row + 1).copied().unwrap_or(line.len());
+                        line[row_start..row_end].to_string()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+    }
+
+    /// Converts into a preview carrying the `patch` instead of the full lines.
+    ///
+    /// The highlights of the unchanged lines are kept by Vim, so only those of the
+    /// changed lines are retained.
+    pub fn into_patched(self, patch: PreviewPatch) -> Self {
+        let is_changed = |line_idx: usize| {
+            patch
+                .ranges
+                .iter()
+                .any(|&(start, end)| (start..end).contains(&line_idx))
+        };
+
+        Self {
+            lines: Vec::new(),
+            vim_syntax_info: VimSyntaxInfo::default(),
+            syntax_regions: Vec::new(),
+            sublime_syntax_highlights: self
+                .sublime_syntax_highlights
+                .into_iter()
+                .filter(|(lnum, _)| lnum.checked_sub(1).map_or(false, is_changed))
+                .collect(),
+            tree_sitter_highlights: self
+                .tree_sitter_highlights
+                .into_iter()
+                .filter(|(line_idx, _)| is_changed(*line_idx))
+                .collect(),
+            ansi_highlights: self
+                .ansi_highlights
+                .into_iter()
+                .filter(|(lnum, _)| lnum.checked_sub(1).map_or(false, is_changed))
+                .collect(),
+            hi_lnum: self.hi_lnum,
+            match_highlight: self.match_highlight,
+            scrollbar: self.scrollbar,
+            patch: Some(patch),
+        }
+    }
+}
+
+/// Changed lines of a preview relative to the previously rendered one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreviewPatch {
+    /// Number of lines in the previous preview.
+    pub base_len: usize,
+    /// `(start, end, lines)`: `lines` replaces the lines `[start, end)` of the previous preview.
+    ///
+    /// The hunks are sorted and non-overlapping, they must be applied in reverse order.
+    pub hunks: Vec<(usize, usize, Vec<String>)>,
+    /// Line ranges `[start, end)` of the changed lines in the new preview.
+    pub ranges: Vec<(usize, usize)>,
+}
+
+impl PreviewPatch {
+    /// Computes the patch turning `old` into `new`.
+    ///
+    /// Returns `None` if the line count differs or most of the lines are changed,
+    /// in which case sending the full preview is preferred.
+    pub fn new(old: &[String], new: &[String]) -> Option<Self> {
+        if old.len() != new.len() {
+            return None;
+        }
+
+        // The lines shared by both previews when moving within a file are mostly contiguous.
+        let (old_start, new_start, len) = longest_common_block(old, new);
+
+        if len == 0 {
+            return None;
+        }
+
+        let mut hunks = Vec::new();
+        let mut ranges = Vec::new();
+
+        for (old_range, new_range) in [
+            (0..old_start, 0..new_start),
+            (old_start + len..old.len(), new_start + len..new.len()),
+        ] {
+            let old_lines = &old[old_range.clone()];
+            let new_lines = &new[new_range.clone()];
+
+            let prefix = old_lines
+                .iter()
+                .zip(new_lines)
+                .take_while(|(a, b)| a == b)
+                .count();
+            let suffix = old_lines[prefix..]
+                .iter()
+                .rev()
+                .zip(new_lines[prefix..].iter().rev())
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            let old_range = old_range.start + prefix..old_range.end - suffix;
+            let new_range = new_range.start + prefix..new_range.end - suffix;
+
+            if old_range.is_empty() && new_range.is_empty() {
+                continue;
+            }
+
+            if !new_range.is_empty() {
+                ranges.push((new_range.start, new_range.end));
+            }
+            hunks.push((old_range.start, old_range.end, new[new_range].to_vec()));
+        }
+
+        let changed_lines = ranges.iter().map(|(start, end)| end - start).sum::<usize>();
+
+        if changed_lines * 2 > new.len() {
+            return None;
+        }
+
+        Some(Self {
+            base_len: old.len(),
+            hunks,
+            ranges,
+        })
+    }
+}
+
+/// Returns `(old_start, new_start, len)` of the longest block of lines shared by `old` and `new`.
+fn longest_common_block(old: &[String], new: &[String]) -> (usize, usize, usize) {
+    let mut longest = (0, 0, 0);
+    let mut prev_row = vec![0; new.len() + 1];
+
+    for (i, old_line) in old.iter().enumerate() {
+        let mut row = vec![0; new.len() + 1];
+        for (j, new_line) in new.iter().enumerate() {
+            if old_line == new_line {
+                row[j + 1] = prev_row[j] + 1;
+                if row[j + 1] > longest.2 {
+                    longest = (i + 1 - row[j + 1], j + 1 - row[j + 1], row[j + 1]);
+                }
+            }
+        }
+        prev_row = row;
+    }
+
+    longest
 }
 
 /// Represents various targets for previews in clap provider.
@@ -102,39 +716,160 @@ impl Preview {
 pub enum PreviewTarget {
     /// List the entries under a directory.
     Directory(PathBuf),
+    /// List the entries of an archive file, e.g., `.zip` and `.tar.gz`.
+    Archive(PathBuf),
     /// Start from the beginning of a file.
     File(PathBuf),
     /// Represents a specific location in a file identified by its path and line number.
-    LineInFile { path: PathBuf, line_number: usize },
+    ///
+    /// `column` is the 1-based column of the match, e.g., in the grep results, which is
+    /// highlighted in the preview.
+    LineInFile {
+        path: PathBuf,
+        line_number: usize,
+        column: Option<usize>,
+    },
     /// Represents a Git commit revision specified by its commit hash.
     GitCommit(String),
+    /// Git ref like a branch, previewed as the recent commits of the ref.
+    GitRef(String),
     /// Specifically for the `help_tags` provider.
     HelpTags {
         subject: String,
         doc_filename: String,
         runtimepath: String,
     },
+    /// Specifically for the `config` provider.
+    ConfigKey(String),
+    /// Stdout of a shell command, specified by the user provider.
+    Command(String),
+    /// Remote http(s) resource.
+    Url(String),
+    /// Specifically for the `man` provider, `section` is searched by `man` if not specified.
+    ManPage {
+        page: String,
+        section: Option<String>,
+    },
+    /// Unified diff between the file on disk and the unsaved content of its buffer.
+    ///
+    /// `buffer_lines` is fetched from Vim right before the preview is generated.
+    BufferDiff {
+        path: PathBuf,
+        buffer_lines: Vec<String>,
+    },
+    /// Lines provided by the provider itself, e.g., the content of a register.
+    Text(Vec<String>),
 }
 
 impl PreviewTarget {
     /// Returns the path associated with the enum variant, or `None` if no path exists.
     pub fn path(&self) -> Option<&Path> {
         match self {
-            Self::File(path) | Self::Directory(path) | Self::LineInFile { path, .. } => Some(path),
+            Self::File(path)
+            | Self::Directory(path)
+            | Self::Archive(path)
+            | Self::LineInFile { path, .. }
+            | Self::BufferDiff { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Returns the key of the target in the preview cache, the paths differing only
+    /// in case are the same file on the case-insensitive platforms.
+    pub fn cache_key(&self) -> Self {
+        let fold_case = |path: &PathBuf| paths::path_buf_case_key(path).into_owned();
+        match self {
+            Self::Directory(path) => Self::Directory(fold_case(path)),
+            Self::Archive(path) => Self::Archive(fold_case(path)),
+            Self::File(path) => Self::File(fold_case(path)),
+            Self::LineInFile {
+                path,
+                line_number,
+                column,
+            } => Self::LineInFile {
+                path: fold_case(path),
+                line_number: *line_number,
+                column: *column,
+            },
+            Self::GitCommit(_)
+            | Self::GitRef(_)
+            | Self::HelpTags { .. }
+            | Self::ConfigKey(_)
+            | Self::ManPage { .. }
+            | Self::Command(_)
+            | Self::Url(_)
+            | Self::BufferDiff { .. }
+            | Self::Text(_) => self.clone(),
+        }
+    }
+}
+
+/// Error of previewing the current item, rendered in the preview window by
+/// `clap#state#render_preview_error()`.
+#[derive(Debug, thiserror::Error)]
+pub enum PreviewError {
+    #[error("failed to parse the preview target for provider {provider_id}")]
+    ParsePreviewTarget { provider_id: String, item: String },
+    #[error("no preview implementation for provider {provider_id}")]
+    UnsupportedProvider { provider_id: String, item: String },
+    #[error("{} is not a file", path.display())]
+    NotAFile { provider_id: String, path: PathBuf },
+    #[error(transparent)]
+    Vim(#[from] VimError),
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+}
+
+impl PreviewError {
+    /// Returns the item text which failed to be previewed.
+    pub fn item(&self) -> Option<&str> {
+        match self {
+            Self::ParsePreviewTarget { item, .. } | Self::UnsupportedProvider { item, .. } => {
+                Some(item)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the user-facing hint for resolving the error.
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            Self::ParsePreviewTarget { provider_id, .. } => Some(format!(
+                "The item may be outdated, try refreshing the cache by `:Clap {provider_id} +no-cache`"
+            )),
+            Self::UnsupportedProvider { provider_id, .. } => Some(format!(
+                "Add `{provider_id}` to `provider.disable-preview` in the config if the preview is unnecessary"
+            )),
+            Self::NotAFile { provider_id, .. } => Some(format!(
+                "The file may have been removed, try refreshing the cache by `:Clap {provider_id} +no-cache`"
+            )),
+            Self::IO(e) if e.kind() == ErrorKind::NotFound => {
+                Some("The file may have been removed, try refreshing the cache".to_string())
+            }
             _ => None,
         }
     }
+
+    /// Returns the error info for rendering in Vim.
+    pub fn to_vim_error_info(&self) -> serde_json::Value {
+        let mut info = serde_json::json!({ "message": self.to_string() });
+        if let Some(item) = self.item() {
+            info["item"] = item.into();
+        }
+        if let Some(hint) = self.hint() {
+            info["hint"] = hint.into();
+        }
+        info
+    }
 }
 
-fn parse_preview_target(curline: String, ctx: &Context) -> Result<(PreviewTarget, Option<String>)> {
-    let err = || {
-        Error::new(
-            ErrorKind::Other,
-            format!(
-                "Failed to parse PreviewTarget for provider_id: {} from `{curline}`",
-                ctx.provider_id()
-            ),
-        )
+pub(super) fn parse_preview_target(
+    curline: String,
+    ctx: &Context,
+) -> std::result::Result<(PreviewTarget, Option<String>), PreviewError> {
+    let err = || PreviewError::ParsePreviewTarget {
+        provider_id: ctx.provider_id().to_string(),
+        item: curline.clone(),
     };
 
     // Store the line context we see in the search result, but it may be out-dated due to the
@@ -145,71 +880,328 @@ fn parse_preview_target(curline: String, ctx: &Context) -> Result<(PreviewTarget
     // is always accurate, try to refresh the cache and reload.
     let mut line_content = None;
 
+    // The URL item of the providers opted in, e.g., bookmarks, is previewed as a remote
+    // resource.
+    if crate::config::config()
+        .provider
+        .url_preview
+        .is_enabled_for(ctx.provider_id())
+        && previewer::url::is_url(curline.trim())
+    {
+        return Ok((PreviewTarget::Url(curline.trim().to_string()), line_content));
+    }
+
+    // The results from the extra search roots of grep are displayed with the root label or `~`.
+    let to_path = |fpath: &str| ctx.resolve_grep_path(fpath);
+
     let preview_target = match ctx.provider_id() {
-        "files" | "git_files" => PreviewTarget::File(ctx.cwd.join(&curline)),
-        "recent_files" => PreviewTarget::File(PathBuf::from(&curline)),
+        "files" | "git_files" | "recent_files" => {
+            let path = if ctx.provider_id() == "recent_files" {
+                PathBuf::from(&curline)
+            } else {
+                ctx.cwd.join(&curline)
+            };
+            if previewer::archive::is_archive(&path) {
+                PreviewTarget::Archive(path)
+            } else {
+                PreviewTarget::File(path)
+            }
+        }
         "history" => {
             let path = if curline.starts_with('~') {
-                expand_tilde(curline)
+                expand_tilde(&curline)
             } else {
                 ctx.cwd.join(&curline)
             };
             PreviewTarget::File(path)
         }
-        "coc_location" | "grep" | "live_grep" | "igrep" => {
-            let mut try_extract_file_path = |line: &str| {
-                let (fpath, lnum, _col, cache_line) =
-                    extract_grep_position(line).ok_or_else(err)?;
-
-                line_content.replace(cache_line.into());
-
-                let fpath = fpath.strip_prefix("./").unwrap_or(fpath);
-                let path = ctx.cwd.join(fpath);
-
-                Ok::<_, Error>((path, lnum))
+        "changed_buffers" => {
+            let path = if curline.starts_with('~') {
+                expand_tilde(&curline)
+            } else {
+                ctx.cwd.join(&curline)
             };
-
-            let (path, line_number) = try_extract_file_path(&curline)?;
-
-            PreviewTarget::LineInFile { path, line_number }
+            PreviewTarget::BufferDiff {
+                path,
+                buffer_lines: Vec::new(),
+            }
+        }
+        "coc_location" | "grep" | "live_grep" | "igrep" => {
+            match extract_grep_position(&curline) {
+                Some((fpath, line_number, col, cache_line)) => {
+                    line_content.replace(cache_line.into());
+                    PreviewTarget::LineInFile {
+                        path: to_path(fpath),
+                        line_number,
+                        column: Some(col),
+                    }
+                }
+                None => {
+                    // Best-effort: preview the whole file if the line starts with a file
+                    // path, e.g., the binary file matches message.
+                    let path = extract_grep_path_lossy(&curline)
+                        .map(to_path)
+                        .filter(|path| path.is_file())
+                        .ok_or_else(err)?;
+                    PreviewTarget::File(path)
+                }
+            }
         }
         "dumb_jump" => {
             let (_def_kind, fpath, line_number, _col) =
                 extract_jump_line_info(&curline).ok_or_else(err)?;
             let path = ctx.cwd.join(fpath);
-            PreviewTarget::LineInFile { path, line_number }
+            PreviewTarget::LineInFile {
+                path,
+                line_number,
+                column: None,
+            }
+        }
+        "quickfix" | "loclist" => {
+            let (fpath, line_number, col, _text) =
+                extract_quickfix_position(&curline).ok_or_else(err)?;
+            let path = if fpath.starts_with('~') {
+                expand_tilde(fpath)
+            } else {
+                ctx.cwd.join(fpath)
+            };
+            // The entry without a valid position, e.g., the error message of the compiler.
+            if line_number == 0 {
+                PreviewTarget::File(path)
+            } else {
+                PreviewTarget::LineInFile {
+                    path,
+                    line_number,
+                    column: (col > 0).then_some(col),
+                }
+            }
         }
         "blines" => {
             let line_number = extract_blines_lnum(&curline).ok_or_else(err)?;
-            let path = ctx.env.start_buffer_path.clone();
-            PreviewTarget::LineInFile { path, line_number }
+            let path = ctx.start_buffer_vfs_path();
+            PreviewTarget::LineInFile {
+                path,
+                line_number,
+                column: None,
+            }
         }
         "tags" => {
             let line_number = extract_buf_tags_lnum(&curline).ok_or_else(err)?;
-            let path = ctx.env.start_buffer_path.clone();
-            PreviewTarget::LineInFile { path, line_number }
+            let path = ctx.start_buffer_vfs_path();
+            PreviewTarget::LineInFile {
+                path,
+                line_number,
+                column: None,
+            }
         }
-        "proj_tags" => {
+        "proj_tags" | "symbols" => {
             let (line_number, p) = extract_proj_tags(&curline).ok_or_else(err)?;
             let path = ctx.cwd.join(p);
-            PreviewTarget::LineInFile { path, line_number }
+            PreviewTarget::LineInFile {
+                path,
+                line_number,
+                column: None,
+            }
         }
         "commits" | "bcommits" => {
             let rev = extract_commit_rev(&curline).ok_or_else(err)?;
             PreviewTarget::GitCommit(rev.into())
         }
-        unknown_provider_id => {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!(
-                    "Failed to parse PreviewTarget, you probably forget to \
-                    add an implementation for this provider: {unknown_provider_id}",
-                ),
-            ))
+        "config" => {
+            let key = crate::config_editor::extract_config_key(&curline).ok_or_else(err)?;
+            PreviewTarget::ConfigKey(key.into())
+        }
+        "man" => {
+            let (page, section) =
+                previewer::man_page::parse_apropos_line(&curline).ok_or_else(err)?;
+            PreviewTarget::ManPage {
+                page: page.into(),
+                section: section.map(Into::into),
+            }
         }
+        unknown_provider_id => match registry::get(unknown_provider_id).map(|spec| spec.preview) {
+            Some(PreviewSpec::File) => PreviewTarget::File(ctx.cwd.join(&curline)),
+            Some(PreviewSpec::Grep) => {
+                let (fpath, line_number, col, cache_line) =
+                    extract_grep_position(&curline).ok_or_else(err)?;
+                line_content.replace(cache_line.into());
+                PreviewTarget::LineInFile {
+                    path: to_path(fpath),
+                    line_number,
+                    column: Some(col),
+                }
+            }
+            Some(PreviewSpec::Command(template)) => PreviewTarget::Command(
+                previewer::command_output::render_template(&template, &curline),
+            ),
+            Some(PreviewSpec::Url) => PreviewTarget::Url(curline.trim().to_string()),
+            Some(PreviewSpec::None) | None => {
+                return Err(PreviewError::UnsupportedProvider {
+                    provider_id: unknown_provider_id.to_string(),
+                    item: curline,
+                })
+            }
+        },
+    };
+
+    Ok((classify_symlink(preview_target)?, line_content))
+}
+
+/// Previews the symlink by the file it eventually points to, the link path is kept for
+/// the header line except for the archive, whose kind is told by the file name.
+fn classify_symlink(
+    preview_target: PreviewTarget,
+) -> std::result::Result<PreviewTarget, PreviewError> {
+    let PreviewTarget::File(path) = &preview_target else {
+        return Ok(preview_target);
+    };
+
+    let Some(resolved) = previewer::symlink::resolve(path)? else {
+        return Ok(preview_target);
+    };
+
+    if resolved.is_dir() {
+        Ok(PreviewTarget::Directory(path.clone()))
+    } else if previewer::archive::is_archive(&resolved) {
+        Ok(PreviewTarget::Archive(resolved))
+    } else {
+        Ok(preview_target)
+    }
+}
+
+/// Maximum time to wait for `git log` of the git footer.
+const GIT_FOOTER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Returns the path of the previewed file if the git footer is enabled for the preview,
+/// the cached footer is added to `preview` directly.
+fn attach_cached_git_footer(
+    ctx: &Context,
+    preview_target: &PreviewTarget,
+    preview: &mut Preview,
+    preview_height: usize,
+) -> Option<PathBuf> {
+    if !crate::config::config().provider.git_footer {
+        return None;
+    }
+
+    let (PreviewTarget::File(path) | PreviewTarget::LineInFile { path, .. }) = preview_target
+    else {
+        return None;
     };
 
-    Ok((preview_target, line_content))
+    match ctx.preview_manager.git_footer_cache.get(path) {
+        Some(footer) => {
+            // The header line takes one line.
+            if let Some(footer) = footer {
+                preview.push_footer(footer, preview_height + 1);
+            }
+            None
+        }
+        None => Some(path.clone()),
+    }
+}
+
+/// Looks up the last commit of `path` in the background, the footer is merged into the
+/// cached preview and displayed if the file is still being previewed.
+fn spawn_git_footer(
+    ctx: &Context,
+    path: PathBuf,
+    preview_target: PreviewTarget,
+    preview_height: usize,
+    is_stale: preview_pool::IsStale,
+) {
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        let footer =
+            match tokio::time::timeout(GIT_FOOTER_TIMEOUT, crate::tools::git::last_commit(&path))
+                .await
+            {
+                Ok(Ok(last_commit)) => last_commit.map(|LastCommit { hash, author, date }| {
+                    format!("Last commit {hash} by {author} on {date}")
+                }),
+                Ok(Err(err)) => {
+                    tracing::debug!(?err, ?path, "Failed to find the last commit");
+                    None
+                }
+                Err(_) => {
+                    tracing::debug!(?path, "Timed out finding the last commit");
+                    None
+                }
+            };
+
+        ctx.preview_manager
+            .git_footer_cache
+            .insert(path, footer.clone());
+
+        let Some(footer) = footer else {
+            return;
+        };
+
+        // The header line takes one line.
+        let updated = ctx
+            .preview_manager
+            .update_cached_preview(&preview_target, |preview| {
+                preview.push_footer(footer, preview_height + 1)
+            });
+
+        // Only the preview of the cursor line is displayed again.
+        if updated && !is_stale() && !ctx.terminated.load(Ordering::SeqCst) {
+            let _ = ctx.send_provider_event(ProviderEvent::OnMove(Params::None));
+        }
+    });
+}
+
+/// Appends ` → target` to the header line of the symlink `path`, the target is shortened
+/// from the start to keep the header line within `max_width`.
+fn with_link_target(header: String, path: &Path, max_width: usize) -> String {
+    match previewer::symlink::link_target(path) {
+        Some(target) => append_link_target(header, &target.display().to_string(), max_width),
+        None => header,
+    }
+}
+
+fn append_link_target(header: String, target: &str, max_width: usize) -> String {
+    use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+    const ARROW: &str = " → ";
+    const ELLIPSIS: char = '…';
+
+    let available = max_width.saturating_sub(header.width() + ARROW.width());
+    if target.width() <= available {
+        return format!("{header}{ARROW}{target}");
+    }
+
+    // No room for even one more char of the target than the ellipsis.
+    if available < 2 {
+        return header;
+    }
+
+    let mut tail_width = 0;
+    let tail_start = target
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| {
+            tail_width += c.width().unwrap_or(0);
+            tail_width < available
+        })
+        .last()
+        .map_or(target.len(), |(idx, _)| idx);
+
+    format!("{header}{ARROW}{ELLIPSIS}{}", &target[tail_start..])
+}
+
+/// Appends the badge like ` [GBK]` to the header line if `path` is transcoded from a legacy
+/// encoding for the preview.
+fn with_encoding_badge(header: String, encoding: Option<&'static Encoding>) -> String {
+    match encoding {
+        Some(encoding) => format!("{header} [{}]", encoding.name()),
+        None => header,
+    }
+}
+
+/// Returns the legacy encoding of `path` detected for the badge in the preview header.
+fn detect_encoding(path: &Path) -> Option<&'static Encoding> {
+    previewer::encoding::detect_file(path).ok().flatten()
 }
 
 /// Returns `true` if the file path of preview file should be truncateted relative to cwd.
@@ -222,6 +1214,8 @@ fn should_truncate_cwd_relative(provider_id: &str) -> bool {
         "coc_location",
         "dumb_jump",
         "proj_tags",
+        "symbols",
+        "lsp_symbols",
     ];
     SET.contains(&provider_id)
 }
@@ -240,7 +1234,11 @@ pub struct CachedPreviewImpl<'a> {
 }
 
 impl<'a> CachedPreviewImpl<'a> {
-    pub fn new(curline: String, preview_height: usize, ctx: &'a Context) -> Result<Self> {
+    pub fn new(
+        curline: String,
+        preview_height: usize,
+        ctx: &'a Context,
+    ) -> std::result::Result<Self, PreviewError> {
         let (preview_target, cache_line) = parse_preview_target(curline, ctx)?;
 
         Ok(Self {
@@ -264,48 +1262,423 @@ impl<'a> CachedPreviewImpl<'a> {
         }
     }
 
-    pub async fn get_preview(&self) -> VimResult<(PreviewTarget, Preview)> {
-        if let Some(preview) = self
+    pub async fn get_preview(&self) -> std::result::Result<(PreviewTarget, Preview), PreviewError> {
+        if let PreviewTarget::BufferDiff { path, .. } = &self.preview_target {
+            return self.get_buffer_diff_preview(path).await;
+        }
+
+        // The command output can be changed at any time, hence never cached, so is the
+        // text which is cheap to render anyway. The ref moves on commit or checkout.
+        if let PreviewTarget::Command(_) | PreviewTarget::Text(_) | PreviewTarget::GitRef(_) =
+            &self.preview_target
+        {
+            let preview = self.generate_preview_in_pool(true).await?;
+            return Ok((self.preview_target.clone(), preview));
+        }
+
+        // The buffer content can be changed at any time, hence never cached. The cache is
+        // checked first to avoid the roundtrip to Vim for syncing the buffers.
+        let maybe_buffer_path = match &self.preview_target {
+            PreviewTarget::File(path) | PreviewTarget::LineInFile { path, .. } => Some(path),
+            _ => None,
+        };
+        if !maybe_buffer_path.map_or(false, |path| self.ctx.vfs.buffers().contains(path)) {
+            if let Some(preview) = self
+                .ctx
+                .preview_manager
+                .cached_preview(&self.preview_target)
+            {
+                return Ok((self.preview_target.clone(), preview));
+            }
+        }
+
+        let from_buffer = match maybe_buffer_path {
+            Some(path) => {
+                // The items of these providers are always from the start buffer.
+                if matches!(self.ctx.provider_id(), "blines" | "tags") {
+                    self.ctx.sync_start_buffer_content().await?;
+                } else {
+                    self.ctx.sync_buffer_content(path).await?;
+                }
+                self.ctx.vfs.buffers().contains(path)
+            }
+            None => false,
+        };
+
+        // Special files and the unreadable files are described in a single line.
+        if let PreviewTarget::File(path) | PreviewTarget::LineInFile { path, .. } =
+            &self.preview_target
+        {
+            if let Some(line) = special_file_preview(path).filter(|_| !from_buffer) {
+                return Ok((self.preview_target.clone(), Preview::new(vec![line])));
+            }
+        }
+
+        let preview = self.generate_preview_in_pool(from_buffer).await?;
+
+        Ok((self.preview_target.clone(), preview))
+    }
+
+    /// Computes and caches the preview in the background, so that moving to the item later
+    /// is instant.
+    ///
+    /// Unlike [`Self::get_preview`], no request is sent to Vim and nothing is displayed,
+    /// the previews which are never cached are skipped.
+    pub async fn prefetch_preview(&self) {
+        if matches!(
+            self.preview_target,
+            PreviewTarget::BufferDiff { .. }
+                | PreviewTarget::Command(_)
+                | PreviewTarget::Text(_)
+                | PreviewTarget::GitRef(_)
+        ) {
+            return;
+        }
+
+        if let PreviewTarget::File(path) | PreviewTarget::LineInFile { path, .. } =
+            &self.preview_target
+        {
+            if self.ctx.vfs.buffers().contains(path) || special_file_preview(path).is_some() {
+                return;
+            }
+        }
+
+        if self
             .ctx
             .preview_manager
             .cached_preview(&self.preview_target)
+            .is_some()
         {
+            return;
+        }
+
+        match self.generate_preview().await {
+            Ok(preview) => self
+                .ctx
+                .preview_manager
+                .insert_preview(self.preview_target.clone(), preview),
+            Err(err) => tracing::trace!(?err, "Failed to prefetch the preview"),
+        }
+    }
+
+    /// Fetches the unsaved content of the buffer and previews its diff against the disk,
+    /// which is never cached.
+    async fn get_buffer_diff_preview(
+        &self,
+        path: &Path,
+    ) -> std::result::Result<(PreviewTarget, Preview), PreviewError> {
+        let maybe_lines: Option<Vec<String>> =
+            self.ctx.vim.call("clap#api#buffer_content", [path]).await?;
+
+        let Some(buffer_lines) = maybe_lines else {
+            let preview = Preview::new(vec![format!("{}: no unsaved changes", path.display())]);
             return Ok((self.preview_target.clone(), preview));
+        };
+
+        let preview_target = PreviewTarget::BufferDiff {
+            path: path.to_path_buf(),
+            buffer_lines,
+        };
+        let preview_impl =
+            Self::with_preview_target(preview_target.clone(), self.preview_height, self.ctx);
+        let preview = preview_impl.generate_preview_in_pool(true).await?;
+
+        Ok((preview_target, preview))
+    }
+
+    /// Generates the preview on the preview worker pool.
+    ///
+    /// A placeholder is returned if the preview is not ready in time, the cursor line is
+    /// previewed again once it's done.
+    async fn generate_preview_in_pool(
+        &self,
+        from_buffer: bool,
+    ) -> std::result::Result<Preview, PreviewError> {
+        let ctx = self.ctx.clone();
+        let preview_target = self.preview_target.clone();
+        let preview_height = self.preview_height;
+        let is_stale = self.ctx.preview_manager.staleness_checker();
+        let task_is_stale = is_stale.clone();
+        let task = async move {
+            let preview_impl = CachedPreviewImpl::with_preview_target(
+                preview_target.clone(),
+                preview_height,
+                &ctx,
+            );
+            let mut preview = preview_impl.generate_preview().await?;
+            if !from_buffer {
+                let git_footer_path =
+                    attach_cached_git_footer(&ctx, &preview_target, &mut preview, preview_height);
+                ctx.preview_manager
+                    .insert_preview(preview_target.clone(), preview.clone());
+                // The last commit is only looked up for the preview still being displayed.
+                if let Some(path) = git_footer_path.filter(|_| !task_is_stale()) {
+                    spawn_git_footer(&ctx, path, preview_target, preview_height, task_is_stale);
+                }
+            }
+            Ok::<_, PreviewError>(preview)
+        };
+
+        let Some(mut receiver) = preview_pool::spawn(task, is_stale.clone()) else {
+            return Ok(Preview::new(vec![
+                "Preview skipped as too many previews are pending".to_string(),
+            ]));
+        };
+
+        let timeout = crate::config::config().provider.preview_pool.timeout();
+
+        match tokio::time::timeout(timeout, &mut receiver).await {
+            Ok(Ok(preview)) => preview,
+            // Dropped as the cursor has moved, the outdated preview is never displayed.
+            Ok(Err(_)) => Ok(Preview::new(Vec::new())),
+            Err(_) => {
+                tracing::debug!(preview_target = ?self.preview_target, ?timeout, "Preview timed out");
+
+                // The late preview is cached and displayed on the next OnMove, the buffer
+                // content is never cached and would time out again.
+                if !from_buffer {
+                    let ctx = self.ctx.clone();
+                    tokio::spawn(async move {
+                        if let Ok(Ok(_)) = receiver.await {
+                            if !is_stale() && !ctx.terminated.load(Ordering::SeqCst) {
+                                let _ =
+                                    ctx.send_provider_event(ProviderEvent::OnMove(Params::None));
+                            }
+                        }
+                    });
+                }
+
+                Ok(Preview::new(vec![format!(
+                    "Preview timed out after {}ms",
+                    timeout.as_millis()
+                )]))
+            }
         }
+    }
 
-        let preview = match &self.preview_target {
+    async fn generate_preview(&self) -> std::result::Result<Preview, PreviewError> {
+        let mut preview = match &self.preview_target {
             PreviewTarget::Directory(path) => self.preview_directory(path)?,
+            PreviewTarget::Archive(path) => self.preview_archive(path)?,
             PreviewTarget::File(path) => self.preview_file(path)?,
-            PreviewTarget::LineInFile { path, line_number } => {
+            PreviewTarget::LineInFile {
+                path,
+                line_number,
+                column,
+            } => {
                 let container_width = self.ctx.preview_winwidth().await?;
-                self.preview_file_at(path, *line_number, container_width)
+                self.preview_file_at(path, *line_number, *column, container_width)
                     .await
             }
-            PreviewTarget::GitCommit(rev) => self.preview_commits(rev)?,
+            PreviewTarget::GitCommit(rev) => self.preview_commits(rev).await?,
+            PreviewTarget::GitRef(rev) => {
+                let lines =
+                    crate::tools::git::log_oneline(&self.ctx.cwd, rev, self.preview_height).await?;
+                Preview::new(lines)
+            }
             PreviewTarget::HelpTags {
                 subject,
                 doc_filename,
                 runtimepath,
             } => self.preview_help_subject(subject, doc_filename, runtimepath),
+            PreviewTarget::ConfigKey(key) => self.preview_config_key(key),
+            PreviewTarget::Command(command) => self.preview_command_output(command).await?,
+            PreviewTarget::Url(url) => self.preview_url(url).await?,
+            PreviewTarget::ManPage { page, section } => {
+                let width = self.ctx.preview_winwidth().await?;
+                self.preview_man_page(page, section.as_deref(), width)?
+            }
+            PreviewTarget::BufferDiff { path, buffer_lines } => {
+                self.preview_buffer_diff(path, buffer_lines)?
+            }
+            PreviewTarget::Text(lines) => {
+                Preview::new(self.truncate_preview_lines(lines.iter().cloned()).collect())
+            }
         };
 
-        self.ctx
-            .preview_manager
-            .insert_preview(self.preview_target.clone(), preview.clone());
+        if crate::config::config().provider.long_preview_lines == LongPreviewLines::Wrap {
+            preview.wrap_lines(self.ctx.preview_winwidth().await?);
+        }
 
-        Ok((self.preview_target.clone(), preview))
+        Ok(preview)
     }
 
-    fn preview_commits(&self, rev: &str) -> Result<Preview> {
-        let stdout = self.ctx.exec_cmd(&format!("git show {rev}"))?;
+    async fn preview_commits(&self, rev: &str) -> std::result::Result<Preview, PreviewError> {
+        let vcs = crate::tools::vcs::detect(&self.ctx.cwd);
+        let is_git = vcs.as_ref().map_or(true, |vcs| vcs.kind() == VcsKind::Git);
+
+        let provider_config = &crate::config::config().provider;
+
+        if let Some(pager) = provider_config.diff_pager.as_ref().filter(|_| is_git) {
+            let width = self.ctx.preview_winwidth().await?;
+            match diff_pager::show_commit(pager, rev, &self.ctx.cwd, width).await {
+                Ok(stdout) => return Ok(self.ansi_preview(&stdout)),
+                Err(err) => {
+                    tracing::debug!(
+                        ?err,
+                        ?pager,
+                        "Failed to run the diff pager, fallback to git show"
+                    );
+                }
+            }
+        }
+
+        let (stdout, stat_only) = match vcs.filter(|_| !is_git) {
+            Some(vcs) => {
+                let rev = rev.to_string();
+                let stdout = tokio::task::spawn_blocking(move || vcs.show(&rev))
+                    .await
+                    .map_err(std::io::Error::from)??;
+                (stdout, false)
+            }
+            None => {
+                let shown = crate::tools::git::show_commit(
+                    &self.ctx.cwd,
+                    rev,
+                    crate::tools::git::MAX_COMMIT_DIFF_BYTES,
+                )
+                .await?;
+                (shown.output, shown.stat_only)
+            }
+        };
         let stdout_str = String::from_utf8_lossy(&stdout);
-        let lines = stdout_str
-            .split('\n')
+        let header = stat_only.then(|| {
+            format!(
+                "The diff exceeds {} KiB, only the diffstat is shown",
+                crate::tools::git::MAX_COMMIT_DIFF_BYTES / 1024
+            )
+        });
+        let lines = header
+            .into_iter()
+            .chain(stdout_str.split('\n').map(Into::into))
             .take(self.preview_height)
-            .map(Into::into)
             .collect::<Vec<_>>();
+        let tree_sitter_highlights = previewer::diff::word_diff_highlights(&lines)
+            .into_iter()
+            .map(|(line_idx, spans)| {
+                let spans = spans
+                    .into_iter()
+                    .map(|(start, length, group)| (start, length, group.to_string()))
+                    .collect();
+                (line_idx, spans)
+            })
+            .collect();
+        let mut preview = Preview {
+            lines,
+            vim_syntax_info: VimSyntaxInfo::syntax("diff".into()),
+            tree_sitter_highlights,
+            ..Default::default()
+        };
+        preview.clamp_highlights();
+        Ok(preview)
+    }
+
+    /// Returns the preview of the colored output, the colors are converted to highlights.
+    fn ansi_preview(&self, output: &[u8]) -> Preview {
+        let output = String::from_utf8_lossy(output);
+        let (lines, ansi_highlights): (Vec<_>, Vec<_>) =
+            parse_ansi_lines(output.split('\n').take(self.preview_height))
+                .into_iter()
+                .enumerate()
+                .map(|(idx, (line, highlights))| (line, (idx + 1, highlights)))
+                .unzip();
+        Preview {
+            lines,
+            ansi_highlights: ansi_highlights
+                .into_iter()
+                .filter(|(_lnum, highlights)| !highlights.is_empty())
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn preview_config_key(&self, key: &str) -> Preview {
+        let lines = match crate::config_editor::key_doc(key).filter(|doc| !doc.is_empty()) {
+            Some(doc) => doc.lines().map(Into::into).collect(),
+            None => vec![format!("No documentation for `{key}`")],
+        };
+        let mut preview = Preview::new(lines);
+        preview.vim_syntax_info.syntax = "markdown".to_string();
+        preview
+    }
+
+    async fn preview_command_output(&self, command: &str) -> Result<Preview> {
+        let config = &crate::config::config().provider.preview_command;
+        let CommandOutput {
+            output,
+            truncated,
+            timed_out,
+        } = previewer::command_output::run(
+            command,
+            &self.ctx.cwd,
+            config.timeout(),
+            config.max_output(),
+        )
+        .await?;
+
+        let mut preview = if output.is_empty() {
+            Preview::new(vec![format!("No output of `{command}`")])
+        } else {
+            self.ansi_preview(&output)
+        };
+
+        if timed_out {
+            preview
+                .lines
+                .push(format!("[Killed after {}ms]", config.timeout().as_millis()));
+        } else if truncated {
+            preview.lines.push("[Output truncated]".to_string());
+        }
+
+        Ok(preview)
+    }
+
+    async fn preview_url(&self, url: &str) -> Result<Preview> {
+        let config = &crate::config::config().provider.url_preview;
+
+        if !config.enable {
+            return Ok(Preview::new(vec![
+                url.to_string(),
+                "Set `provider.url-preview.enable = true` in the config to preview the URLs"
+                    .to_string(),
+            ]));
+        }
+
+        let remote = previewer::url::fetch(url, config.timeout(), config.max_bytes()).await?;
+
+        let title = format!("{url} [{} {}]", remote.status, remote.mime_type());
+
+        if !remote.is_text() {
+            return Ok(Preview::new(vec![
+                title,
+                format!("<Binary content, {} bytes fetched>", remote.body.len()),
+            ]));
+        }
+
+        let body = String::from_utf8_lossy(&remote.body);
+        let mut lines = std::iter::once(title)
+            .chain(body.lines().take(self.preview_height).map(Into::into))
+            .collect::<Vec<_>>();
+        if remote.truncated && lines.len() <= self.preview_height {
+            lines.push("[Truncated]".to_string());
+        }
+
+        let mut preview = Preview::new(lines);
+        if let Some(syntax) = remote.syntax() {
+            preview.vim_syntax_info.syntax = syntax.to_string();
+        }
+        Ok(preview)
+    }
+
+    fn preview_man_page(&self, page: &str, section: Option<&str>, width: usize) -> Result<Preview> {
+        let lines = previewer::man_page::render(page, section, width, self.preview_height)?;
+        let lines = if lines.is_empty() {
+            vec![format!("No manual entry for {page}")]
+        } else {
+            lines
+        };
         let mut preview = Preview::new(lines);
-        preview.vim_syntax_info.syntax = "diff".to_string();
+        preview.vim_syntax_info.syntax = "man".to_string();
         Ok(preview)
     }
 
@@ -334,7 +1707,18 @@ impl<'a> CachedPreviewImpl<'a> {
 
     fn preview_directory<P: AsRef<Path>>(&self, path: P) -> Result<Preview> {
         let enable_icon = self.ctx.env.icon.enabled();
-        let lines = read_dir_entries(&path, enable_icon, Some(self.preview_height))?;
+        let config = &crate::config::config().provider.directory_preview;
+        let lines = if config.tree {
+            let lines = previewer::dir_tree::render_tree(
+                path.as_ref(),
+                config.max_depth(),
+                self.preview_height,
+                enable_icon,
+            )?;
+            self.truncate_preview_lines(lines.into_iter()).collect()
+        } else {
+            read_dir_entries(&path, enable_icon, Some(self.preview_height))?
+        };
         let mut lines = if lines.is_empty() {
             vec!["<Empty directory>".to_string()]
         } else {
@@ -345,66 +1729,149 @@ impl<'a> CachedPreviewImpl<'a> {
         if title.ends_with(std::path::MAIN_SEPARATOR) {
             title.pop();
         }
+        // 1 = `:`
+        let max_title_width = self.ctx.display_metrics().line_width.saturating_sub(1);
+        let mut title = with_link_target(title, path.as_ref(), max_title_width);
         title.push(':');
         lines.insert(0, title);
 
         Ok(Preview::new(lines))
     }
 
-    fn preview_file<P: AsRef<Path>>(&self, path: P) -> Result<Preview> {
+    fn preview_archive(&self, path: &Path) -> Result<Preview> {
+        // The title line and the footer line take two lines.
+        let max_entries = self.preview_height.saturating_sub(2).max(1);
+        let ArchiveEntries { entries, total } =
+            previewer::archive::list_entries(path, max_entries)?;
+
+        let footer = match total {
+            Some(total) => match total.saturating_sub(entries.len()) {
+                0 => format!("{total} entries"),
+                rest => format!("… {rest} more, {total} entries in total"),
+            },
+            None => "… more entries".to_string(),
+        };
+
+        let mut lines = Vec::with_capacity(entries.len() + 2);
+        lines.push(format!("{}:", path.display()));
+        if entries.is_empty() {
+            lines.push("<Empty archive>".to_string());
+        }
+        lines.extend(self.truncate_preview_lines(entries.into_iter()));
+        lines.push(footer);
+
+        Ok(Preview::new(lines))
+    }
+
+    fn preview_file<P: AsRef<Path>>(&self, path: P) -> std::result::Result<Preview, PreviewError> {
         let path = path.as_ref();
 
-        if !path.is_file() {
-            return Err(Error::new(
-                ErrorKind::Other,
-                format!("Failed to preview as {} is not a file", path.display()),
-            ));
+        if !self.ctx.vfs.is_file(path) {
+            return Err(PreviewError::NotAFile {
+                provider_id: self.ctx.provider_id().to_string(),
+                path: path.to_path_buf(),
+            });
         }
 
-        let handle_io_error = |e: &Error| {
-            if e.kind() == ErrorKind::NotFound {
-                tracing::debug!(
-                    "TODO: {} not found, the files cache might be invalid, try refreshing the cache",
-                    path.display()
-                );
-            }
+        // Detected once for both the header line and the title.
+        let encoding = once_cell::unsync::OnceCell::new();
+        let encoding = || *encoding.get_or_init(|| detect_encoding(path));
+
+        // Title line of the rendered previews below, only built by the renderer taking the file.
+        let header_line = || {
+            let fname = path.display().to_string();
+            let header = match (self.ctx.env.is_nvim, self.ctx.env.has_nvim_09) {
+                (true, false) => {
+                    truncate_absolute_path(&fname, self.ctx.display_metrics().line_width - 1)
+                        .into_owned()
+                }
+                _ => fname.replacen(self.ctx.cwd.as_str(), ".", 1),
+            };
+            with_encoding_badge(
+                with_link_target(header, path, self.ctx.display_metrics().line_width),
+                encoding(),
+            )
         };
 
-        let (lines, fname) = match (self.ctx.env.is_nvim, self.ctx.env.has_nvim_09) {
+        // The buffer content is always text.
+        if !self.ctx.vfs.buffers().contains(path) {
+            if let Some(preview) = self.preview_document(path, &header_line) {
+                return Ok(preview);
+            }
+
+            if let Some(preview) = self.preview_hex_dump(path, &header_line) {
+                return Ok(preview);
+            }
+        }
+
+        if previewer::notebook::is_notebook(path) {
+            if let Some(preview) = self.preview_notebook(path, None, &header_line) {
+                return Ok(preview);
+            }
+        }
+
+        if let Some(delimiter) = previewer::csv::delimiter(path) {
+            if let Some(preview) = self.preview_csv(path, delimiter, None, &header_line) {
+                return Ok(preview);
+            }
+        }
+
+        if crate::config::config().provider.render_markdown
+            && previewer::markdown::is_markdown(path)
+        {
+            if let Some(preview) = self.preview_markdown(path, &header_line) {
+                return Ok(preview);
+            }
+        }
+
+        let first_lines = self
+            .ctx
+            .vfs
+            .read_first_lines(path, self.preview_height)
+            .map_err(|e| {
+                if e.kind() == ErrorKind::NotFound {
+                    tracing::debug!(
+                        "TODO: {} not found, the files cache might be invalid, try refreshing the cache",
+                        path.display()
+                    );
+                }
+                e
+            })?;
+
+        if let Some(preview) = self.preview_colored_file(&first_lines, &header_line) {
+            return Ok(preview);
+        }
+
+        let abs_path = previewer::as_absolute_path(path)?;
+        let (title, fname) = match (self.ctx.env.is_nvim, self.ctx.env.has_nvim_09) {
             (true, false) => {
                 // Title is not available before nvim 0.9
-                let max_fname_len = self.ctx.env.display_line_width - 1;
-                previewer::preview_file_with_truncated_title(
-                    path,
-                    self.preview_height,
-                    self.max_line_width(),
-                    max_fname_len,
-                )
-                .map_err(|e| {
-                    handle_io_error(&e);
-                    e
-                })?
+                let max_fname_len = self.ctx.display_metrics().line_width - 1;
+                let truncated_abs_path =
+                    truncate_absolute_path(&abs_path, max_fname_len).into_owned();
+                (truncated_abs_path.clone(), truncated_abs_path)
             }
             _ => {
-                let (lines, abs_path) =
-                    previewer::preview_file(path, self.preview_height, self.max_line_width())
-                        .map_err(|e| {
-                            handle_io_error(&e);
-                            e
-                        })?;
                 // cwd is shown via the popup title, no need to include it again.
                 let cwd_relative = abs_path.replacen(self.ctx.cwd.as_str(), ".", 1);
-                let mut lines = lines;
-                lines[0] = cwd_relative;
-                (lines, abs_path)
+                (
+                    with_encoding_badge(
+                        with_link_target(cwd_relative, path, self.ctx.display_metrics().line_width),
+                        encoding(),
+                    ),
+                    abs_path,
+                )
             }
         };
+        let lines = std::iter::once(title)
+            .chain(self.truncate_preview_lines(first_lines.into_iter()))
+            .collect::<Vec<_>>();
 
-        let total = utils::count_lines(std::fs::File::open(path)?)?;
+        let total = self.ctx.vfs.count_lines(path)?;
         let end = lines.len();
 
         let scrollbar = if self.ctx.env.should_add_scrollbar(end) {
-            let preview_winheight = self.ctx.env.display_winheight;
+            let preview_winheight = self.ctx.display_metrics().winheight;
 
             let length = ((end * preview_winheight) as f32 / total as f32) as usize;
 
@@ -425,7 +1892,8 @@ impl<'a> CachedPreviewImpl<'a> {
             None
         };
 
-        if std::fs::metadata(path)?.len() == 0 {
+        // Only the header line for the empty file.
+        if lines.len() == 1 {
             let mut lines = lines;
             lines.push("<Empty file>".to_string());
             Ok(Preview::new_file_preview(
@@ -448,7 +1916,275 @@ impl<'a> CachedPreviewImpl<'a> {
         }
     }
 
-    async fn preview_file_at(&self, path: &Path, lnum: usize, container_width: usize) -> Preview {
+    /// Previews the text extracted from the document, e.g., PDF and docx.
+    ///
+    /// Returns `None` if it's not a document or the extraction fails, it's then previewed
+    /// as the hex dump.
+    fn preview_document(&self, path: &Path, header_line: &dyn Fn() -> String) -> Option<Preview> {
+        let extractor = previewer::document::extractor(
+            path,
+            &crate::config::config().provider.document_extractors,
+        )?;
+
+        let text_lines = extractor
+            .extract(path, self.preview_height)
+            .map_err(|err| tracing::debug!(?path, "Failed to extract the document: {err:?}"))
+            .ok()?;
+
+        let lines = std::iter::once(header_line())
+            .chain(self.truncate_preview_lines(text_lines.into_iter()))
+            .collect::<Vec<_>>();
+
+        Some(Preview {
+            lines,
+            vim_syntax_info: VimSyntaxInfo::syntax("text".into()),
+            ..Default::default()
+        })
+    }
+
+    /// Previews the binary file as the hex dump, returns `None` if it's not binary.
+    fn preview_hex_dump(&self, path: &Path, header_line: &dyn Fn() -> String) -> Option<Preview> {
+        // The header line and the file info line take the first two rows.
+        let hex_dump = previewer::hex_dump::hex_dump(path, self.preview_height.saturating_sub(2))
+            .map_err(|err| tracing::debug!(?path, "Failed to read the file head: {err:?}"))
+            .ok()??;
+
+        let file_info = format!(
+            "{} ({} bytes), {}",
+            crate::file_info::human_size(hex_dump.size),
+            hex_dump.size,
+            hex_dump.mime_type
+        );
+
+        let lines = [header_line(), file_info]
+            .into_iter()
+            .chain(hex_dump.lines)
+            .collect::<Vec<_>>();
+
+        Some(Preview {
+            lines,
+            vim_syntax_info: VimSyntaxInfo::syntax("xxd".into()),
+            ..Default::default()
+        })
+    }
+
+    /// Previews the notebook rendered in the percent format, `lnum` is the line number in
+    /// the raw JSON.
+    ///
+    /// Returns `None` if the notebook can not be rendered, it's then previewed as a
+    /// plain file.
+    fn preview_notebook(
+        &self,
+        path: &Path,
+        lnum: Option<usize>,
+        header_line: &dyn Fn() -> String,
+    ) -> Option<Preview> {
+        let content = self.ctx.vfs.read(path).ok()?;
+        let notebook = previewer::notebook::render_notebook(&content)
+            .map_err(|err| tracing::debug!(?path, "Failed to render the notebook: {err:?}"))
+            .ok()?;
+
+        let (start, hi_lnum) = match lnum.map(|lnum| notebook.rendered_lnum(lnum)) {
+            Some(lnum) => {
+                let start = lnum.saturating_sub(self.preview_height / 2 + 1);
+                // The header line offsets the 1-based line number.
+                (start, Some(lnum - start))
+            }
+            None => (0, None),
+        };
+
+        let lines = std::iter::once(header_line())
+            .chain(
+                self.truncate_preview_lines(
+                    notebook
+                        .lines
+                        .into_iter()
+                        .skip(start)
+                        .take(self.preview_height),
+                ),
+            )
+            .collect::<Vec<_>>();
+
+        // The rendered line `start + 1` is the second line after the header line.
+        let last_lnum = start + self.preview_height;
+        let syntax_regions = notebook
+            .cell_syntaxes
+            .into_iter()
+            .filter(|cell| cell.end > start && cell.start <= last_lnum)
+            .map(|cell| SyntaxRegion {
+                syntax: cell.syntax,
+                start: cell.start.max(start + 1) - start + 1,
+                end: cell.end.min(last_lnum) - start + 1,
+            })
+            .collect();
+
+        Some(Preview {
+            lines,
+            vim_syntax_info: VimSyntaxInfo::syntax(notebook.filetype),
+            syntax_regions,
+            hi_lnum,
+            ..Default::default()
+        })
+    }
+
+    /// Previews the CSV/TSV file with the columns aligned if enabled, the header row is
+    /// pinned below the header line if it's scrolled out of the window.
+    fn preview_csv(
+        &self,
+        path: &Path,
+        delimiter: char,
+        lnum: Option<usize>,
+        header_line: &dyn Fn() -> String,
+    ) -> Option<Preview> {
+        if !crate::config::config().provider.align_csv_preview {
+            return None;
+        }
+
+        let FilePreview {
+            start,
+            highlight_lnum,
+            mut lines,
+            ..
+        } = get_file_preview(
+            &self.ctx.vfs,
+            path,
+            lnum.unwrap_or_default(),
+            self.preview_height,
+            self.preview_position(),
+        )
+        .ok()?;
+
+        let highlight_lnum = if start > 0 {
+            let header_row = self.ctx.vfs.read_first_lines(path, 1).ok()?;
+            let header_row = header_row.into_iter().next()?;
+            pin_header_row(&mut lines, header_row, highlight_lnum, self.preview_height)
+        } else {
+            highlight_lnum
+        };
+
+        let lines =
+            std::iter::once(header_line())
+                .chain(self.truncate_preview_lines(
+                    previewer::csv::align_rows(&lines, delimiter).into_iter(),
+                ))
+                .collect::<Vec<_>>();
+
+        Some(Preview {
+            lines,
+            // The aligned rows no longer match the syntax of the CSV file.
+            vim_syntax_info: VimSyntaxInfo::syntax("text".into()),
+            hi_lnum: lnum.map(|_| highlight_lnum),
+            ..Default::default()
+        })
+    }
+
+    /// Previews the unsaved changes of the buffer as a unified diff against the file on disk.
+    fn preview_buffer_diff(&self, path: &Path, buffer_lines: &[String]) -> Result<Preview> {
+        // The new file which has never been written is diffed against the empty content.
+        let disk_content = match std::fs::read(path) {
+            Ok(content) => String::from_utf8_lossy(&content).into_owned(),
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        let disk_lines = disk_content.lines().collect::<Vec<_>>();
+
+        let hunks = previewer::diff::unified_diff(&disk_lines, buffer_lines);
+
+        let fname = path.display().to_string();
+        let lines = if hunks.is_empty() {
+            vec![format!("{fname}: no unsaved changes")]
+        } else {
+            let headers = [format!("--- {fname}"), format!("+++ {fname} (buffer)")];
+            headers
+                .into_iter()
+                .chain(self.truncate_preview_lines(hunks.into_iter().take(self.preview_height)))
+                .collect()
+        };
+
+        Ok(Preview {
+            lines,
+            vim_syntax_info: VimSyntaxInfo::syntax("diff".into()),
+            ..Default::default()
+        })
+    }
+
+    /// Previews the file containing the ANSI escape sequences, e.g., the colored logs, with
+    /// the sequences converted to the highlights.
+    ///
+    /// Returns `None` if there is no escape sequence in the first lines.
+    fn preview_colored_file(
+        &self,
+        lines: &[String],
+        header_line: &dyn Fn() -> String,
+    ) -> Option<Preview> {
+        if !lines.iter().any(|line| has_escape_sequence(line)) {
+            return None;
+        }
+
+        // The escape sequences are stripped before truncating the lines.
+        let (lines, ansi_highlights): (Vec<_>, Vec<_>) =
+            parse_ansi_lines(lines.iter().map(String::as_str))
+                .into_iter()
+                .enumerate()
+                // The header line shifts the lines by one.
+                .map(|(idx, (line, highlights))| (line, (idx + 2, highlights)))
+                .unzip();
+
+        let mut preview = Preview {
+            lines: std::iter::once(header_line())
+                .chain(self.truncate_preview_lines(lines.into_iter()))
+                .collect(),
+            ansi_highlights: ansi_highlights
+                .into_iter()
+                .filter(|(_lnum, highlights)| !highlights.is_empty())
+                .collect(),
+            ..Default::default()
+        };
+        preview.clamp_highlights();
+        Some(preview)
+    }
+
+    /// Previews the markdown file rendered in the plain-text layout.
+    fn preview_markdown(&self, path: &Path, header_line: &dyn Fn() -> String) -> Option<Preview> {
+        let content = self.ctx.vfs.read_text(path).ok()?;
+        let content = String::from_utf8_lossy(&content);
+        let rendered = previewer::markdown::render_markdown(content.lines(), self.preview_height);
+
+        let lines = std::iter::once(header_line())
+            .chain(self.truncate_preview_lines(rendered.lines.into_iter()))
+            .collect::<Vec<_>>();
+
+        // The header line shifts the rendered lines by one.
+        let tree_sitter_highlights = rendered
+            .highlights
+            .into_iter()
+            .map(|(line_idx, spans)| {
+                let spans = spans
+                    .into_iter()
+                    .map(|(start, length, group)| (start, length, group.to_string()))
+                    .collect();
+                (line_idx + 1, spans)
+            })
+            .collect();
+
+        let mut preview = Preview {
+            lines,
+            vim_syntax_info: VimSyntaxInfo::syntax("text".into()),
+            tree_sitter_highlights,
+            ..Default::default()
+        };
+        preview.clamp_highlights();
+
+        Some(preview)
+    }
+
+    async fn preview_file_at(
+        &self,
+        path: &Path,
+        lnum: usize,
+        column: Option<usize>,
+        container_width: usize,
+    ) -> Preview {
         tracing::debug!(path = ?path.display(), lnum, "Previewing file");
 
         let fname = path.display().to_string();
@@ -460,13 +2196,35 @@ impl<'a> CachedPreviewImpl<'a> {
                 let cwd_relative = fname.replacen(self.ctx.cwd.as_str(), ".", 1);
                 format!("{cwd_relative}:{lnum}")
             } else {
-                let max_fname_len = container_width - 1 - display_width(lnum);
+                let max_fname_len = container_width.saturating_sub(1 + display_width(lnum));
                 let truncated_abs_path = truncate_absolute_path(&fname, max_fname_len);
                 format!("{truncated_abs_path}:{lnum}")
             }
         };
 
-        match get_file_preview(path, lnum, self.preview_height) {
+        if previewer::notebook::is_notebook(path) {
+            if let Some(preview) =
+                self.preview_notebook(path, Some(lnum), &truncated_preview_header)
+            {
+                return preview;
+            }
+        }
+
+        if let Some(delimiter) = previewer::csv::delimiter(path) {
+            if let Some(preview) =
+                self.preview_csv(path, delimiter, Some(lnum), &truncated_preview_header)
+            {
+                return preview;
+            }
+        }
+
+        match get_file_preview(
+            &self.ctx.vfs,
+            path,
+            lnum,
+            self.preview_height,
+            self.preview_position(),
+        ) {
             Ok(FilePreview {
                 start,
                 end,
@@ -474,32 +2232,84 @@ impl<'a> CachedPreviewImpl<'a> {
                 highlight_lnum,
                 lines,
             }) => {
-                let context_lines = fetch_context_lines(
-                    &lines,
-                    highlight_lnum,
-                    lnum,
-                    start,
-                    container_width,
-                    path,
-                    self.ctx.env.is_nvim,
-                )
-                .await;
+                // The escape sequences of the colored logs are converted to the highlights,
+                // the syntax highlights on the raw lines no longer apply.
+                let (lines, ansi_lines) = if lines.iter().any(|line| has_escape_sequence(line)) {
+                    let (lines, highlights): (Vec<_>, Vec<_>) =
+                        parse_ansi_lines(lines.iter().map(String::as_str))
+                            .into_iter()
+                            .unzip();
+                    (lines, Some(highlights))
+                } else {
+                    (lines, None)
+                };
+
+                // The unsaved buffer may differ from the file on disk.
+                let on_disk = !self.ctx.vfs.buffers().contains(path);
+
+                let context_tag_enabled = path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .map_or(false, |ext| {
+                        crate::config::config().provider.context_tag.is_enabled(ext)
+                    });
+                let context_lines = if !context_tag_enabled {
+                    Vec::new()
+                } else if previewer::key_path::is_supported(path) {
+                    key_path_context_lines(
+                        on_disk.then_some(&self.ctx.preview_manager.syntax_tree_cache),
+                        &self.ctx.vfs,
+                        path,
+                        lnum,
+                        container_width,
+                        self.ctx.env.is_nvim,
+                    )
+                } else {
+                    fetch_context_lines(
+                        on_disk.then_some(&self.ctx.preview_manager.syntax_tree_cache),
+                        &lines,
+                        highlight_lnum,
+                        lnum,
+                        start,
+                        container_width,
+                        path,
+                        self.ctx.env.is_nvim,
+                    )
+                    .await
+                };
                 let highlight_lnum = highlight_lnum + context_lines.len();
 
                 let context_lines_is_empty = context_lines.is_empty();
 
                 // 1 (header line) + 1 (1-based line number)
                 let line_number_offset = context_lines.len() + 1 + 1;
-                let sublime_or_ts_highlights = fetch_syntax_highlights(
-                    &lines,
-                    path,
-                    line_number_offset,
-                    self.max_line_width(),
-                    start..end + 1,
-                    context_lines.len(),
-                );
+                let ts_highlights_cache =
+                    on_disk.then_some(&self.ctx.preview_manager.ts_highlights_cache);
+                let escape_stripped = ansi_lines.is_some();
+                let sublime_or_ts_highlights = if escape_stripped {
+                    SublimeOrTreeSitter::Neither
+                } else {
+                    fetch_syntax_highlights(
+                        &self.ctx.vfs,
+                        ts_highlights_cache,
+                        &lines,
+                        path,
+                        line_number_offset,
+                        self.max_line_width(),
+                        start..end + 1,
+                        context_lines.len(),
+                    )
+                };
+                let ansi_highlights = ansi_lines
+                    .into_iter()
+                    .flatten()
+                    .enumerate()
+                    .filter(|(_idx, highlights)| !highlights.is_empty())
+                    .map(|(idx, highlights)| (idx + line_number_offset, highlights))
+                    .collect::<Vec<_>>();
 
-                let header_line = truncated_preview_header();
+                let header_line =
+                    with_encoding_badge(truncated_preview_header(), detect_encoding(path));
                 let lines = std::iter::once(header_line)
                     .chain(context_lines.into_iter())
                     .chain(self.truncate_preview_lines(lines.into_iter()))
@@ -511,7 +2321,7 @@ impl<'a> CachedPreviewImpl<'a> {
                     } else {
                         start
                     };
-                    let preview_winheight = self.ctx.env.display_winheight;
+                    let preview_winheight = self.ctx.display_metrics().winheight;
                     let length =
                         (((end - start) * preview_winheight) as f32 / total as f32) as usize;
                     let top_position = (start * preview_winheight) as f32 / total as f32;
@@ -534,10 +2344,27 @@ impl<'a> CachedPreviewImpl<'a> {
                     None
                 };
 
+                // The column of the match is no longer valid once the escape sequences
+                // are stripped.
+                let match_highlight = column.filter(|_| !escape_stripped).and_then(|column| {
+                    let line = lines.get(highlight_lnum)?;
+                    let (start, length) = match self.ctx.preview_manager.line_matcher() {
+                        Some(line_matcher) => {
+                            let path = path.strip_prefix(&self.ctx.cwd).unwrap_or(path);
+                            let range = line_matcher.match_range(path, line)?;
+                            (range.start, range.len())
+                        }
+                        None => previewer::match_span(line, column)?,
+                    };
+                    Some((highlight_lnum, start, length))
+                });
+
                 let mut preview = Preview {
                     lines,
                     hi_lnum: Some(highlight_lnum),
+                    match_highlight,
                     scrollbar,
+                    ansi_highlights,
                     ..Default::default()
                 };
 
@@ -557,6 +2384,8 @@ impl<'a> CachedPreviewImpl<'a> {
                     }
                 }
 
+                preview.clamp_highlights();
+
                 preview
             }
             Err(err) => {
@@ -594,7 +2423,7 @@ impl<'a> CachedPreviewImpl<'a> {
                         let ctx = self.ctx.clone();
 
                         // TODO: Refresh with a timeout.
-                        tokio::task::spawn_blocking(move || {
+                        job::spawn_background(move || {
                             tracing::debug!(cwd = ?ctx.cwd, "Refreshing grep cache");
                             let new_digest = match crate::tools::rg::refresh_cache(&ctx.cwd) {
                                 Ok(digest) => {
@@ -640,26 +2469,210 @@ impl<'a> CachedPreviewImpl<'a> {
         previewer::truncate_lines(lines, self.max_line_width())
     }
 
+    /// Returns the position of the target line in the file preview, the user config takes
+    /// precedence over the one requested by the registered provider.
+    fn preview_position(&self) -> PreviewPosition {
+        let provider_id = self.ctx.provider_id();
+        crate::config::config()
+            .provider
+            .preview_position_of(provider_id)
+            .or_else(|| registry::get(provider_id).and_then(|spec| spec.preview_position))
+            .unwrap_or_default()
+    }
+
     /// Returns the maximum line width.
     #[inline]
     fn max_line_width(&self) -> usize {
-        2 * self.ctx.env.display_line_width
+        2 * self.ctx.display_metrics().line_width
     }
 }
 
-async fn context_tag_with_timeout(path: &Path, lnum: usize) -> Option<BufferTag> {
-    const TIMEOUT: Duration = Duration::from_millis(300);
-
-    match tokio::time::timeout(TIMEOUT, current_context_tag_async(path, lnum)).await {
+async fn context_tags_with_timeout(
+    path: &Path,
+    lnum: usize,
+    depth: usize,
+    timeout: Duration,
+) -> Vec<BufferTag> {
+    match tokio::time::timeout(timeout, context_tags_async(path, lnum, depth)).await {
         Ok(res) => res,
         Err(_) => {
-            tracing::debug!(timeout = ?TIMEOUT, ?path, lnum, "⏳ Did not get the context tag in time");
-            None
+            tracing::debug!(
+                ?timeout,
+                ?path,
+                lnum,
+                "⏳ Did not get the context tag in time"
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Returns the scope chain of context tag, e.g., `impl Foo > pub fn bar(&self) {`.
+///
+/// The outermost scopes are elided first if the line exceeds `max_len`, then
+/// the right of tag pattern is truncated.
+fn format_scope_chain(scopes: &[String], pattern: &str, max_len: usize) -> String {
+    use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+    const SEPARATOR: &str = " > ";
+    const ELLIPSIS: &str = "…";
+
+    let chain_width = |scopes: &[String]| {
+        scopes
+            .iter()
+            .map(|scope| scope.width() + SEPARATOR.len())
+            .sum::<usize>()
+    };
+
+    let pattern_width = pattern.width();
+
+    let mut elided = 0;
+    while elided < scopes.len() {
+        let prefix_width = if elided > 0 {
+            ELLIPSIS.width() + SEPARATOR.len()
+        } else {
+            0
+        };
+        if prefix_width + chain_width(&scopes[elided..]) + pattern_width <= max_len {
+            break;
+        }
+        elided += 1;
+    }
+
+    let mut line = String::new();
+    if elided > 0 {
+        line.push_str(ELLIPSIS);
+        line.push_str(SEPARATOR);
+    }
+    for scope in &scopes[elided..] {
+        line.push_str(scope);
+        line.push_str(SEPARATOR);
+    }
+
+    let available = max_len.saturating_sub(line.width());
+    if pattern_width > available {
+        let budget = available.saturating_sub(2);
+        let mut width = 0;
+        line.extend(pattern.chars().take_while(|c| {
+            width += c.width().unwrap_or(0);
+            width <= budget
+        }));
+        line.push_str("..");
+    } else {
+        line.push_str(pattern);
+    }
+
+    line
+}
+
+/// Inserts `header_row` at the top of `lines` which are at most `max_lines`, returns the
+/// new 1-based line number of the highlighted line `highlight_lnum`.
+///
+/// If no room is left, a line on the side away from the highlighted line is dropped so
+/// that the highlighted line is always kept.
+fn pin_header_row(
+    lines: &mut Vec<String>,
+    header_row: String,
+    highlight_lnum: usize,
+    max_lines: usize,
+) -> usize {
+    let is_full = lines.len() >= max_lines;
+    if is_full && highlight_lnum > lines.len() / 2 {
+        lines[0] = header_row;
+        highlight_lnum
+    } else {
+        if is_full {
+            lines.pop();
         }
+        lines.insert(0, header_row);
+        highlight_lnum + 1
     }
 }
 
+fn border_line(container_width: usize, is_nvim: bool) -> String {
+    // Vim has a different border width.
+    "─".repeat(if is_nvim {
+        container_width
+    } else {
+        container_width.saturating_sub(2)
+    })
+}
+
+/// Returns the key path of the line in the config files as the context lines, which
+/// takes the place of the context tag.
+fn key_path_context_lines(
+    syntax_trees: Option<&SyntaxTreeCache>,
+    vfs: &dyn Vfs,
+    path: &Path,
+    lnum: usize,
+    container_width: usize,
+    is_nvim: bool,
+) -> Vec<String> {
+    let key_path = match (
+        syntax_trees,
+        previewer::key_path::tree_sitter_language(path),
+    ) {
+        (Some(syntax_trees), Some(language)) => syntax_trees
+            .with_tree(
+                path,
+                language,
+                || vfs.read(path).ok(),
+                |tree, source| tree_sitter::key_path(tree, source, lnum.checked_sub(1)?),
+            )
+            .flatten(),
+        _ => vfs
+            .read(path)
+            .ok()
+            .and_then(|source| previewer::key_path::key_path(path, &source, lnum)),
+    };
+    let Some(key_path) = key_path else {
+        return Vec::new();
+    };
+
+    // 2 whitespaces + 💡
+    let max_line_len = container_width.saturating_sub(4);
+    let key_path_len = key_path.chars().count();
+    let mut context_line = if key_path_len > max_line_len {
+        // Keep the innermost keys.
+        std::iter::once('…')
+            .chain(key_path.chars().skip(key_path_len - max_line_len + 1))
+            .collect()
+    } else {
+        key_path
+    };
+    context_line.push_str("  💡");
+
+    let border_line = border_line(container_width, is_nvim);
+    vec![border_line.clone(), context_line, border_line]
+}
+
+/// Returns `true` if the line `lnum` of `path` is a comment, detected by the syntax tree if
+/// the language is supported by tree-sitter, otherwise by the comment syntax of `ext`.
+///
+/// `syntax_trees` is `None` if the file on disk may differ from the previewed lines.
+fn is_comment_line(
+    syntax_trees: Option<&SyntaxTreeCache>,
+    path: &Path,
+    ext: &str,
+    line: &str,
+    lnum: usize,
+) -> bool {
+    syntax_trees
+        .zip(tree_sitter::Language::try_from_extension(ext))
+        .and_then(|(syntax_trees, language)| {
+            syntax_trees.with_tree(
+                path,
+                language,
+                || std::fs::read(path).ok(),
+                |tree, _source| tree_sitter::is_comment_line(tree, lnum.saturating_sub(1), line),
+            )
+        })
+        .unwrap_or_else(|| dumb_analyzer::is_comment(line, ext))
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn fetch_context_lines(
+    syntax_trees: Option<&SyntaxTreeCache>,
     lines: &[String],
     highlight_lnum: usize,
     lnum: usize,
@@ -669,7 +2682,7 @@ async fn fetch_context_lines(
     is_nvim: bool,
 ) -> Vec<String> {
     // Some checks against the latest preview line.
-    let Some(line) = lines.get(highlight_lnum - 1) else {
+    let Some(line) = highlight_lnum.checked_sub(1).and_then(|idx| lines.get(idx)) else {
         return Vec::new();
     };
 
@@ -677,48 +2690,45 @@ async fn fetch_context_lines(
         return Vec::new();
     };
 
-    let skip_context_tag = {
-        const BLACK_LIST: &[&str] = &["log", "txt", "lock", "toml", "yaml", "mod", "conf"];
-
-        BLACK_LIST.contains(&ext) || dumb_analyzer::is_comment(line, ext)
-    };
-
-    if skip_context_tag {
+    if is_comment_line(syntax_trees, path, ext, line, lnum) {
         return Vec::new();
-    };
-
-    let generate_border_line = |container_width: usize, is_nvim: bool| -> String {
-        // Vim has a different border width.
-        "─".repeat(if is_nvim {
-            container_width
-        } else {
-            container_width - 2
-        })
-    };
+    }
 
     let mut context_lines = Vec::new();
 
-    match context_tag_with_timeout(path, lnum).await {
-        Some(tag) if tag.line_number < start => {
-            context_lines.reserve_exact(3);
+    let provider_config = &crate::config::config().provider;
+    let context_tags = context_tags_with_timeout(
+        path,
+        lnum,
+        provider_config.preview_context_depth(),
+        provider_config.context_tag.timeout(),
+    )
+    .await;
+
+    match context_tags.last() {
+        Some(innermost) if innermost.line_number < start => {
+            context_lines.reserve_exact(context_tags.len() + 2);
 
-            let border_line = generate_border_line(container_width, is_nvim);
+            let border_line = border_line(container_width, is_nvim);
 
             context_lines.push(border_line.clone());
 
-            // Truncate the right of pattern, 2 whitespaces + 💡
-            let max_pattern_len = container_width - 4;
-            let pattern = tag.trimmed_pattern();
-            let (mut context_line, to_push) = if pattern.len() > max_pattern_len {
-                // Use the chars instead of indexing the str to avoid the char boundary error.
-                let p: String = pattern.chars().take(max_pattern_len - 4 - 2).collect();
-                (p, "..  💡")
-            } else {
-                (String::from(pattern), "  💡")
-            };
-            context_line.reserve(to_push.len());
-            context_line.push_str(to_push);
-            context_lines.push(context_line);
+            // 2 whitespaces + 💡
+            let max_line_len = container_width.saturating_sub(4);
+            // The scopes of the outermost tag not found in the file lead the first line.
+            for (idx, tag) in context_tags.iter().enumerate() {
+                let scopes = if idx == 0 {
+                    tag.scope_chain()
+                } else {
+                    Vec::new()
+                };
+                let mut context_line =
+                    format_scope_chain(&scopes, tag.trimmed_pattern(), max_line_len);
+                if idx == context_tags.len() - 1 {
+                    context_line.push_str("  💡");
+                }
+                context_lines.push(context_line);
+            }
 
             context_lines.push(border_line);
         }
@@ -737,8 +2747,12 @@ enum SublimeOrTreeSitter {
 }
 
 // TODO: this might be slow for larger files (over 100k lines) as tree-sitter will have to
-// parse the whole file to obtain the highlight info. We may make the highlighting async.
+// parse the whole file to obtain the highlight info, which is cached in `ts_highlights_cache`
+// though. We may make the highlighting async.
+#[allow(clippy::too_many_arguments)]
 fn fetch_syntax_highlights(
+    vfs: &dyn Vfs,
+    ts_highlights_cache: Option<&TsHighlightsCache>,
     lines: &[String],
     path: &Path,
     line_number_offset: usize,
@@ -750,7 +2764,12 @@ fn fetch_syntax_highlights(
 
     let provider_config = &crate::config::config().provider;
 
-    match provider_config.preview_highlight_engine {
+    let highlight_engine = provider_config.highlight_engine_for(
+        crate::stdio_server::vim::preview_syntax(path),
+        path.extension().and_then(|s| s.to_str()),
+    );
+
+    match highlight_engine {
         HighlightEngine::SublimeSyntax => {
             const THEME: &str = "Visual Studio Dark+";
 
@@ -776,10 +2795,9 @@ fn fetch_syntax_highlights(
                     //  long and the query is short, the highlights can be enomerous and
                     //  cause the Vim frozen due to the too many highlight works.
                     let max_len = max_line_width;
-                    let lines = lines.iter().map(|s| {
-                        let len = s.len().min(max_len);
-                        &s[..len]
-                    });
+                    let lines = lines
+                        .iter()
+                        .map(|s| utils::truncate_at_grapheme_boundary(s, max_len));
                     sublime_syntax_highlight(syntax, lines, line_number_offset, theme)
                 })
                 .map(SublimeOrTreeSitter::Sublime)
@@ -791,12 +2809,11 @@ fn fetch_syntax_highlights(
                 .and_then(|s| s.to_str())
                 .and_then(tree_sitter::Language::try_from_extension)
                 .and_then(|language| {
-                    let Ok(source_code) = std::fs::read(path) else {
-                        return None;
-                    };
+                    let read_source = || vfs.read_text(path).ok();
 
-                    let Ok(raw_highlights) = tree_sitter::highlight(language, &source_code) else {
-                        return None;
+                    let raw_highlights = match ts_highlights_cache {
+                        Some(cache) => cache.get_or_highlight(path, language, read_source)?,
+                        None => Arc::new(tree_sitter::highlight(language, &read_source()?).ok()?),
                     };
 
                     let line_start = range.start;
@@ -837,3 +2854,315 @@ fn fetch_syntax_highlights(
         HighlightEngine::Vim => SublimeOrTreeSitter::Neither,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ts_highlights_cache() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/lib.rs");
+        let cache = TsHighlightsCache::default();
+        let read_source = || std::fs::read(&path).ok();
+
+        let highlights = cache
+            .get_or_highlight(&path, tree_sitter::Language::Rust, read_source)
+            .unwrap();
+        let cached = cache
+            .get_or_highlight(&path, tree_sitter::Language::Rust, || {
+                unreachable!("Unmodified file must not be parsed again")
+            })
+            .unwrap();
+        assert!(Arc::ptr_eq(&highlights, &cached));
+    }
+
+    #[test]
+    fn test_syntax_tree_cache() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/lib.rs");
+        let cache = SyntaxTreeCache::default();
+        let read_source = || std::fs::read(&path).ok();
+
+        let root_kind = |tree: &tree_sitter::Tree, _: &[u8]| tree.root_node().kind().to_string();
+        assert_eq!(
+            cache.with_tree(&path, tree_sitter::Language::Rust, read_source, root_kind),
+            Some("source_file".to_string())
+        );
+        assert!(cache
+            .with_tree(
+                &path,
+                tree_sitter::Language::Rust,
+                || unreachable!("Unmodified file must not be parsed again"),
+                |_, _| ()
+            )
+            .is_some());
+    }
+
+    #[test]
+    fn test_format_scope_chain() {
+        let scopes = vec!["mod tests".to_string(), "impl Foo".to_string()];
+        let pattern = "fn bar(&self) {";
+
+        assert_eq!(
+            format_scope_chain(&scopes, pattern, 80),
+            "mod tests > impl Foo > fn bar(&self) {"
+        );
+        assert_eq!(
+            format_scope_chain(&scopes, pattern, 32),
+            "… > impl Foo > fn bar(&self) {"
+        );
+        assert_eq!(format_scope_chain(&scopes, pattern, 16), "… > fn bar(&se..");
+        assert_eq!(format_scope_chain(&[], pattern, 10), "fn bar(&..");
+
+        let scopes = vec!["impl 結構体".to_string()];
+        assert_eq!(
+            format_scope_chain(&scopes, "fn 関数() {", 25),
+            "impl 結構体 > fn 関数() {"
+        );
+        assert_eq!(
+            format_scope_chain(&scopes, "fn 関数() {", 20),
+            "… > fn 関数() {"
+        );
+        assert_eq!(format_scope_chain(&[], "fn 関数() {", 8), "fn 関..");
+        assert_eq!(format_scope_chain(&[], "fn 関数() {", 6), "fn ..");
+    }
+
+    #[test]
+    fn test_append_link_target() {
+        let header = "./link".to_string();
+        assert_eq!(
+            append_link_target(header.clone(), "/tmp/target", 80),
+            "./link → /tmp/target"
+        );
+        assert_eq!(
+            append_link_target(header.clone(), "/tmp/target", 16),
+            "./link → …target"
+        );
+        assert_eq!(append_link_target(header.clone(), "/tmp/target", 8), header);
+    }
+
+    #[test]
+    fn test_pin_header_row() {
+        let rows = |rows: &[&str]| rows.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        // The highlighted last line is kept.
+        let mut lines = rows(&["a", "b", "c"]);
+        assert_eq!(pin_header_row(&mut lines, "h".into(), 3, 3), 3);
+        assert_eq!(lines, rows(&["h", "b", "c"]));
+
+        let mut lines = rows(&["a", "b", "c"]);
+        assert_eq!(pin_header_row(&mut lines, "h".into(), 1, 3), 2);
+        assert_eq!(lines, rows(&["h", "a", "b"]));
+
+        // Nothing is dropped at the end of file.
+        let mut lines = rows(&["a", "b"]);
+        assert_eq!(pin_header_row(&mut lines, "h".into(), 2, 3), 3);
+        assert_eq!(lines, rows(&["h", "a", "b"]));
+    }
+
+    #[test]
+    fn test_key_path_context_lines_narrow() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let fs = crate::vfs::DiskFs;
+
+        // `async-trait` in `[dependencies]`.
+        let lines = key_path_context_lines(None, &fs, &path, 16, 20, false);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].chars().count(), 18);
+        assert!(lines[1].chars().count() <= 16 + 3);
+
+        // Narrower than the decorations.
+        let lines = key_path_context_lines(None, &fs, &path, 16, 1, false);
+        assert_eq!(lines, ["", "…  💡", ""]);
+    }
+
+    #[test]
+    fn test_preview_target_cache_key() {
+        let upper = PreviewTarget::LineInFile {
+            path: PathBuf::from("/src/Foo.rs"),
+            line_number: 1,
+            column: None,
+        };
+        let lower = PreviewTarget::LineInFile {
+            path: PathBuf::from("/src/foo.rs"),
+            line_number: 1,
+            column: None,
+        };
+        assert_eq!(
+            upper.cache_key() == lower.cache_key(),
+            paths::CASE_INSENSITIVE_PATHS
+        );
+
+        let commit = PreviewTarget::GitCommit("ABC123".into());
+        assert_eq!(commit.cache_key(), commit);
+    }
+
+    #[test]
+    fn test_preview_error_info() {
+        let err = PreviewError::ParsePreviewTarget {
+            provider_id: "grep".to_string(),
+            item: "invalid grep line".to_string(),
+        };
+        assert_eq!(
+            err.to_vim_error_info(),
+            serde_json::json!({
+                "message": "failed to parse the preview target for provider grep",
+                "item": "invalid grep line",
+                "hint": "The item may be outdated, try refreshing the cache by `:Clap grep +no-cache`",
+            })
+        );
+
+        let err = PreviewError::IO(std::io::Error::new(ErrorKind::PermissionDenied, "denied"));
+        assert_eq!(
+            err.to_vim_error_info(),
+            serde_json::json!({ "message": "denied" })
+        );
+    }
+
+    #[test]
+    fn test_clamp_highlights() {
+        let mut preview = Preview {
+            lines: vec!["header".to_string(), "let x = 1;".to_string()],
+            tree_sitter_highlights: vec![
+                (
+                    1,
+                    vec![
+                        (0, 3, "Keyword".to_string()),
+                        (8, 5, "Number".to_string()),
+                        (12, 1, "Operator".to_string()),
+                    ],
+                ),
+                (2, vec![(0, 1, "Comment".to_string())]),
+            ],
+            ..Default::default()
+        };
+        preview.clamp_highlights();
+        assert_eq!(
+            preview.tree_sitter_highlights,
+            vec![(
+                1,
+                vec![(0, 3, "Keyword".to_string()), (8, 2, "Number".to_string())]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_wrap_lines() {
+        let mut preview = Preview {
+            lines: vec![
+                "header".to_string(),
+                "let long_name = 1;".to_string(),
+                "x".to_string(),
+            ],
+            tree_sitter_highlights: vec![
+                (
+                    1,
+                    vec![
+                        (0, 3, "Keyword".to_string()),
+                        (4, 9, "Variable".to_string()),
+                    ],
+                ),
+                (2, vec![(0, 1, "Variable".to_string())]),
+            ],
+            hi_lnum: Some(2),
+            match_highlight: Some((1, 4, 9)),
+            ..Default::default()
+        };
+        preview.wrap_lines(8);
+        assert_eq!(preview.lines, ["header", "let long", "_name = ", "1;", "x"]);
+        assert_eq!(
+            preview.tree_sitter_highlights,
+            vec![
+                (
+                    1,
+                    vec![
+                        (0, 3, "Keyword".to_string()),
+                        (4, 4, "Variable".to_string())
+                    ]
+                ),
+                (2, vec![(0, 5, "Variable".to_string())]),
+                (4, vec![(0, 1, "Variable".to_string())]),
+            ]
+        );
+        assert_eq!(preview.hi_lnum, Some(4));
+        assert_eq!(preview.match_highlight, Some((1, 4, 4)));
+    }
+
+    #[test]
+    fn test_push_footer() {
+        let mut preview = Preview {
+            lines: vec!["header".to_string(), "a".to_string(), "b".to_string()],
+            tree_sitter_highlights: vec![
+                (1, vec![(0, 1, "Keyword".to_string())]),
+                (2, vec![(0, 1, "Keyword".to_string())]),
+            ],
+            ..Default::default()
+        };
+        // The last line gives way to the footer in a full window.
+        preview.push_footer("footer".to_string(), 3);
+        assert_eq!(preview.lines, ["header", "a", "footer"]);
+        assert_eq!(
+            preview.tree_sitter_highlights,
+            vec![
+                (1, vec![(0, 1, "Keyword".to_string())]),
+                (2, vec![(0, 6, "Comment".to_string())]),
+            ]
+        );
+
+        let mut preview = Preview::new(vec!["header".to_string(), "a".to_string()]);
+        preview.push_footer("footer".to_string(), 10);
+        assert_eq!(preview.lines, ["header", "a", "footer"]);
+
+        // The highlighted line is never replaced by the footer.
+        let mut preview = Preview {
+            lines: vec!["header".to_string(), "a".to_string(), "b".to_string()],
+            hi_lnum: Some(2),
+            ..Default::default()
+        };
+        preview.push_footer("footer".to_string(), 3);
+        assert_eq!(preview.lines, ["header", "a", "b"]);
+    }
+
+    #[test]
+    fn test_git_footer_cache() {
+        let cache = GitFooterCache::default();
+        assert_eq!(cache.get(Path::new("a.rs")), None);
+        cache.insert(PathBuf::from("a.rs"), None);
+        cache.insert(PathBuf::from("b.rs"), Some("footer".to_string()));
+        assert_eq!(cache.get(Path::new("a.rs")), Some(None));
+        assert_eq!(
+            cache.get(Path::new("b.rs")),
+            Some(Some("footer".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_preview_patch() {
+        let to_lines = |lines: &[&str]| lines.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        // Scrolled down by 2 lines.
+        let old = to_lines(&["header", "1", "2", "3", "4", "5", "6", "7"]);
+        let new = to_lines(&["header", "3", "4", "5", "6", "7", "8", "9"]);
+        assert_eq!(
+            PreviewPatch::new(&old, &new),
+            Some(PreviewPatch {
+                base_len: 8,
+                hunks: vec![(1, 3, vec![]), (8, 8, to_lines(&["8", "9"]))],
+                ranges: vec![(6, 8)],
+            })
+        );
+
+        // Only the highlighted line moved.
+        assert_eq!(
+            PreviewPatch::new(&old, &old),
+            Some(PreviewPatch {
+                base_len: 8,
+                hunks: vec![],
+                ranges: vec![],
+            })
+        );
+
+        // Too many changes.
+        let new = to_lines(&["header", "6", "7", "8", "9", "10", "11", "12"]);
+        assert_eq!(PreviewPatch::new(&old, &new), None);
+    }
+}
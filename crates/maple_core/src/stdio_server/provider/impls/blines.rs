@@ -1,8 +1,7 @@
 use crate::stdio_server::provider::hooks::initialize_provider;
 use crate::stdio_server::provider::{
-    BaseArgs, ClapProvider, Context, ProviderResult as Result, SearcherControl,
+    ActiveFilters, BaseArgs, ClapProvider, Context, ProviderResult as Result, SearcherControl,
 };
-use crate::stdio_server::vim::VimResult;
 use matcher::{Bonus, MatchScope};
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
@@ -15,7 +14,7 @@ pub struct BlinesProvider {
 }
 
 impl BlinesProvider {
-    pub async fn new(ctx: &Context) -> VimResult<Self> {
+    pub async fn new(ctx: &Context) -> Result<Self> {
         let args = ctx.parse_provider_args().await?;
         Ok(Self {
             args,
@@ -46,7 +45,9 @@ impl BlinesProvider {
             let stop_signal = Arc::new(AtomicBool::new(false));
 
             let join_handle = {
-                let search_context = ctx.search_context(stop_signal.clone());
+                let mut search_context = ctx.search_context(stop_signal.clone());
+                search_context.no_matches_hints =
+                    ctx.no_matches_hints(&query, ActiveFilters::default());
 
                 tokio::spawn(async move {
                     crate::searcher::blines::search(query, source_file, matcher, search_context)
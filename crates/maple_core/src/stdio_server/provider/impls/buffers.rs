@@ -0,0 +1,303 @@
+use crate::stdio_server::provider::hooks::PreviewTarget;
+use crate::stdio_server::provider::{
+    ActionParams, BaseArgs, ClapProvider, Context, ListItems, ProviderResult as Result,
+};
+use rpc::Params;
+use serde::Deserialize;
+use std::sync::Arc;
+use types::{ClapItem, MatchResult};
+use unicode_width::UnicodeWidthStr;
+
+/// Buffer info returned by `clap#provider#buffers#buffer_list()`.
+#[derive(Debug, Clone, Deserialize)]
+struct BufferInfo {
+    bufnr: usize,
+    /// File name of the buffer, `[No Name]` for the unnamed buffer.
+    name: String,
+    /// Path displayed in the list, relative to the cwd, which is shortened to the unique
+    /// path suffix if enabled.
+    short_path: String,
+    /// Absolute path of the buffer, empty for the unnamed buffer.
+    path: String,
+    /// Line of the cursor when the buffer was last visited.
+    lnum: usize,
+    fsize: String,
+    icon: String,
+    /// `%` for the current buffer, `#` for the alternate buffer.
+    flag: String,
+    /// `[+]` for the modified buffer, `[RO]` for the readonly buffer.
+    extra: String,
+}
+
+fn padding(origin: &str, target_width: usize) -> String {
+    let width = origin.width();
+    if width < target_width {
+        format!("{origin}{}", " ".repeat(target_width - width))
+    } else {
+        origin.to_string()
+    }
+}
+
+#[derive(Debug)]
+struct BufferItem {
+    info: BufferInfo,
+    /// `[bufnr] name fsize icon line short_path flag extra`, the sink parses the
+    /// buffer number from the leading `[bufnr]`. `fsize icon line` are dropped in
+    /// the compact layout.
+    output: String,
+    /// `name short_path`, only the name and path are used for the fuzzy matching.
+    match_text: String,
+    /// Char offsets of `name` and `short_path` in `output`.
+    name_offset: usize,
+    path_offset: usize,
+}
+
+impl BufferItem {
+    fn new(info: BufferInfo, drop_columns: bool) -> Self {
+        let mut output = format!("{} ", padding(&format!("[{}]", info.bufnr), 5));
+        let name_offset = output.chars().count();
+        if drop_columns {
+            output.push_str(&format!("{} ", padding(&info.name, 25)));
+        } else {
+            output.push_str(&format!(
+                "{} {} {} {} ",
+                padding(&info.name, 25),
+                padding(&info.fsize, 6),
+                if info.icon.is_empty() {
+                    String::new()
+                } else {
+                    padding(&info.icon, 3)
+                },
+                padding(&format!("line {}", info.lnum), 10)
+            ));
+        }
+        let path_offset = output.chars().count();
+        output.push_str(&format!("{} {} {}", info.short_path, info.flag, info.extra));
+        let output = output.trim_end().to_string();
+
+        let match_text = format!("{} {}", info.name, info.short_path);
+
+        Self {
+            info,
+            output,
+            match_text,
+            name_offset,
+            path_offset,
+        }
+    }
+}
+
+impl ClapItem for BufferItem {
+    fn raw_text(&self) -> &str {
+        &self.output
+    }
+
+    fn match_text(&self) -> &str {
+        &self.match_text
+    }
+
+    fn match_result_callback(&self, match_result: MatchResult) -> MatchResult {
+        let name_len = self.info.name.chars().count();
+        let mut match_result = match_result;
+        match_result.indices.iter_mut().for_each(|x| {
+            *x = if *x < name_len {
+                self.name_offset + *x
+            } else {
+                // The separator between the name and path is never matched.
+                self.path_offset + (*x - name_len).saturating_sub(1)
+            };
+        });
+        match_result
+    }
+}
+
+/// Lists the buffers, the buffer list is fetched from Vim on initialization and
+/// refreshed after the buffer is deleted.
+#[derive(Debug)]
+pub struct BuffersProvider {
+    args: BaseArgs,
+    buffers: ListItems,
+}
+
+impl BuffersProvider {
+    pub async fn new(ctx: &Context) -> Result<Self> {
+        let args = ctx.parse_provider_args().await?;
+        Ok(Self {
+            args,
+            buffers: ListItems::default(),
+        })
+    }
+
+    async fn load_buffers(&mut self, ctx: &Context) -> Result<()> {
+        let mut buffers: Vec<BufferInfo> = ctx
+            .vim
+            .bare_call("clap#provider#buffers#buffer_list")
+            .await?;
+        if ctx.env.unique_path_suffix
+            || ctx
+                .vim
+                .get_var_bool("clap_provider_buffers_unique_path_suffix")
+                .await?
+        {
+            let starts =
+                printer::unique_suffix_starts(buffers.iter().map(|info| info.short_path.as_str()));
+            for (info, start) in buffers.iter_mut().zip(starts) {
+                info.short_path.replace_range(..start, "");
+            }
+        }
+        let drop_columns = ctx.display_metrics().layout.drop_columns();
+        self.buffers.set_items(
+            buffers
+                .into_iter()
+                .map(|info| Arc::new(BufferItem::new(info, drop_columns)) as Arc<dyn ClapItem>)
+                .collect(),
+        );
+        Ok(())
+    }
+
+    fn buffer_at(&mut self, lnum: usize) -> Option<&BufferInfo> {
+        self.buffers
+            .item_at::<BufferItem>(lnum)
+            .map(|item| &item.info)
+    }
+}
+
+#[async_trait::async_trait]
+impl ClapProvider for BuffersProvider {
+    async fn on_initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        self.load_buffers(ctx).await?;
+        if self.args.query.is_none() {
+            self.buffers.process_query("", ctx)?;
+        }
+        ctx.handle_base_args(&self.args).await
+    }
+
+    async fn on_move(&mut self, ctx: &mut Context) -> Result<()> {
+        if !ctx.preview_enabled() {
+            return Ok(());
+        }
+
+        let lnum = ctx.vim.display_getcurlnum().await?;
+
+        let Some(buffer) = self.buffer_at(lnum) else {
+            return Ok(());
+        };
+
+        let preview_target = if buffer.path.is_empty() {
+            // The unnamed buffer exists in Vim only, hence previewed by the buffer lines.
+            let preview_height = ctx.preview_height().await?;
+            let lines = ctx.vim.getbufline(buffer.bufnr, 1, preview_height).await?;
+            if lines.is_empty() {
+                PreviewTarget::Text(vec!["<Empty buffer>".to_string()])
+            } else {
+                PreviewTarget::Text(lines)
+            }
+        } else {
+            PreviewTarget::LineInFile {
+                path: buffer.path.clone().into(),
+                // `line 0` for the buffer not loaded yet.
+                line_number: buffer.lnum.max(1),
+                column: None,
+            }
+        };
+
+        ctx.preview_manager.reset_scroll();
+        ctx.update_preview(Some(preview_target)).await
+    }
+
+    async fn on_typed(&mut self, ctx: &mut Context) -> Result<()> {
+        let query = ctx.vim.input_get().await?;
+        self.buffers.process_query(&query, ctx)
+    }
+
+    async fn on_action(&mut self, ctx: &mut Context, params: Params) -> Result<()> {
+        let ActionParams { action, .. } = ActionParams::parse(params)?;
+
+        let command = match action.as_str() {
+            "delete" => "bdelete",
+            "wipeout" => "bwipeout",
+            _ => {
+                ctx.vim
+                    .echo_warn(format!("Unknown buffers action: {action}"))?;
+                return Ok(());
+            }
+        };
+
+        let lnum = ctx.vim.display_getcurlnum().await?;
+        let Some(bufnr) = self.buffer_at(lnum).map(|buffer| buffer.bufnr) else {
+            return Ok(());
+        };
+
+        // E.g., E89 if the buffer has been modified.
+        if let Err(err) = ctx
+            .vim
+            .call::<String>("execute", [format!("{command} {bufnr}")])
+            .await
+        {
+            ctx.vim
+                .echo_warn(format!("Failed to {action} buffer {bufnr}: {err}"))?;
+            return Ok(());
+        }
+
+        self.load_buffers(ctx).await?;
+        let query = ctx.vim.input_get().await?;
+        self.buffers.process_query(&query, ctx)?;
+
+        self.on_move(ctx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer_info() -> BufferInfo {
+        BufferInfo {
+            bufnr: 3,
+            name: "lib.rs".into(),
+            short_path: "src/lib.rs".into(),
+            path: "/project/src/lib.rs".into(),
+            lnum: 42,
+            fsize: "1.2K".into(),
+            icon: String::new(),
+            flag: "%".into(),
+            extra: " [+]".into(),
+        }
+    }
+
+    #[test]
+    fn test_buffer_item_match_indices() {
+        let item = BufferItem::new(buffer_info(), false);
+
+        assert_eq!(
+            item.raw_text(),
+            "[3]   lib.rs                    1.2K    line 42    src/lib.rs %  [+]"
+        );
+        assert_eq!(item.match_text(), "lib.rs src/lib.rs");
+
+        // `lib` in the name and `src` in the path.
+        let match_result = item.match_result_callback(MatchResult::new(0, vec![0, 1, 2, 7, 8, 9]));
+        let matched = match_result
+            .indices
+            .iter()
+            .map(|&idx| item.raw_text().chars().nth(idx).unwrap())
+            .collect::<String>();
+        assert_eq!(matched, "libsrc");
+    }
+
+    #[test]
+    fn test_buffer_item_drop_columns() {
+        let item = BufferItem::new(buffer_info(), true);
+        assert_eq!(
+            item.raw_text(),
+            "[3]   lib.rs                    src/lib.rs %  [+]"
+        );
+        let match_result = item.match_result_callback(MatchResult::new(0, vec![0, 7]));
+        let matched = match_result
+            .indices
+            .iter()
+            .map(|&idx| item.raw_text().chars().nth(idx).unwrap())
+            .collect::<String>();
+        assert_eq!(matched, "ls");
+    }
+}
@@ -0,0 +1,221 @@
+use crate::datastore::HISTORY_FRECENCY_IN_MEMORY;
+use crate::stdio_server::provider::hooks::PreviewTarget;
+use crate::stdio_server::provider::{
+    ActionParams, BaseArgs, ClapProvider, Context, ListItems, ProviderResult as Result,
+};
+use rpc::Params;
+use std::collections::HashMap;
+use std::sync::Arc;
+use types::{ClapItem, MatchResult};
+
+#[derive(Debug)]
+struct HistoryItem {
+    entry: String,
+    /// `{index:>4}  {entry}`, the index is the position in the Vim history, the most
+    /// recent one has the largest index.
+    output: String,
+    /// Char offset of the entry in `output`.
+    entry_offset: usize,
+}
+
+impl HistoryItem {
+    fn new(index: usize, entry: String) -> Self {
+        let prefix = format!("{index:>4}  ");
+        let entry_offset = prefix.chars().count();
+        let output = format!("{prefix}{entry}");
+        Self {
+            entry,
+            output,
+            entry_offset,
+        }
+    }
+}
+
+impl ClapItem for HistoryItem {
+    fn raw_text(&self) -> &str {
+        &self.output
+    }
+
+    fn match_text(&self) -> &str {
+        &self.entry
+    }
+
+    fn match_result_callback(&self, match_result: MatchResult) -> MatchResult {
+        let mut match_result = match_result;
+        match_result.indices.iter_mut().for_each(|x| {
+            *x += self.entry_offset;
+        });
+        match_result
+    }
+}
+
+/// Lists the entries of the command-line history for `command_history` or the search
+/// history for `search_history`, the entries picked frequently are ranked higher.
+#[derive(Debug)]
+pub struct CommandHistoryProvider {
+    args: BaseArgs,
+    /// `:` or `/`, same with `{history}` in `:h histget()`.
+    history_type: &'static str,
+    entries: ListItems,
+}
+
+impl CommandHistoryProvider {
+    pub async fn new(ctx: &Context) -> Result<Self> {
+        let args = ctx.parse_provider_args().await?;
+        let history_type = if ctx.provider_id() == "search_history" {
+            "/"
+        } else {
+            ":"
+        };
+        Ok(Self {
+            args,
+            history_type,
+            entries: ListItems::default(),
+        })
+    }
+
+    async fn load_entries(&mut self, ctx: &Context) -> Result<()> {
+        // The most recent entry first.
+        let entries: Vec<String> = ctx
+            .vim
+            .call("clap#common_history#entries", [self.history_type])
+            .await?;
+
+        let total = entries.len();
+        let indices = entries
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| (entry.clone(), total - idx))
+            .rev()
+            .collect::<HashMap<_, _>>();
+
+        let ranked = HISTORY_FRECENCY_IN_MEMORY
+            .lock()
+            .rank(self.history_type, entries);
+
+        self.entries.set_items(
+            ranked
+                .into_iter()
+                .map(|entry| {
+                    let index = indices.get(&entry).copied().unwrap_or_default();
+                    Arc::new(HistoryItem::new(index, entry)) as Arc<dyn ClapItem>
+                })
+                .collect(),
+        );
+
+        Ok(())
+    }
+
+    fn entry_at(&mut self, lnum: usize) -> Option<&str> {
+        self.entries
+            .item_at::<HistoryItem>(lnum)
+            .map(|item| item.entry.as_str())
+    }
+}
+
+#[async_trait::async_trait]
+impl ClapProvider for CommandHistoryProvider {
+    async fn on_initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        self.load_entries(ctx).await?;
+        if self.args.query.is_none() {
+            self.entries.process_query("", ctx)?;
+        }
+        ctx.handle_base_args(&self.args).await
+    }
+
+    async fn on_move(&mut self, ctx: &mut Context) -> Result<()> {
+        if !ctx.preview_enabled() {
+            return Ok(());
+        }
+
+        let lnum = ctx.vim.display_getcurlnum().await?;
+        let history_type = self.history_type;
+
+        let Some(entry) = self.entry_at(lnum) else {
+            return Ok(());
+        };
+
+        // The long command is truncated in the display window, hence shown in full here.
+        let width = ctx.preview_winwidth().await?.max(1);
+        let offsets = crate::previewer::wrap_offsets(entry, width);
+        let mut lines = offsets
+            .iter()
+            .zip(offsets.iter().skip(1).chain(std::iter::once(&entry.len())))
+            .map(|(&start, &end)| entry[start..end].to_string())
+            .collect::<Vec<_>>();
+
+        if let Some(stats) = HISTORY_FRECENCY_IN_MEMORY.lock().stats(history_type, entry) {
+            lines.push(String::new());
+            lines.push(format!(
+                "Picked {} times, last {}",
+                stats.visits,
+                chrono_humanize::HumanTime::from(stats.last_visit)
+            ));
+        }
+
+        ctx.preview_manager.reset_scroll();
+        ctx.update_preview(Some(PreviewTarget::Text(lines))).await
+    }
+
+    async fn on_typed(&mut self, ctx: &mut Context) -> Result<()> {
+        let query = ctx.vim.input_get().await?;
+        self.entries.process_query(&query, ctx)
+    }
+
+    async fn on_action(&mut self, ctx: &mut Context, params: Params) -> Result<()> {
+        let ActionParams { action, .. } = ActionParams::parse(params)?;
+
+        if action != "delete" {
+            ctx.vim
+                .echo_warn(format!("Unknown {} action: {action}", ctx.provider_id()))?;
+            return Ok(());
+        }
+
+        let lnum = ctx.vim.display_getcurlnum().await?;
+        let Some(entry) = self.entry_at(lnum).map(ToString::to_string) else {
+            return Ok(());
+        };
+
+        let deleted: usize = ctx
+            .vim
+            .call("clap#common_history#delete", (self.history_type, &entry))
+            .await?;
+        if deleted == 0 {
+            ctx.vim
+                .echo_warn(format!("Failed to delete `{entry}` from the history"))?;
+            return Ok(());
+        }
+
+        {
+            let mut history_frecency = HISTORY_FRECENCY_IN_MEMORY.lock();
+            history_frecency.remove(self.history_type, &entry);
+            if let Err(err) = crate::datastore::store_history_frecency(&history_frecency) {
+                tracing::error!(?err, "Failed to store the history frecency");
+            }
+        }
+
+        self.load_entries(ctx).await?;
+        let query = ctx.vim.input_get().await?;
+        self.entries.process_query(&query, ctx)?;
+
+        self.on_move(ctx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_item() {
+        let item = HistoryItem::new(12, "echo 'hello'".into());
+        assert_eq!(item.raw_text(), "  12  echo 'hello'");
+        assert_eq!(item.match_text(), "echo 'hello'");
+        let match_result = item.match_result_callback(MatchResult::new(0, vec![0, 1]));
+        assert_eq!(match_result.indices, [6, 7]);
+
+        let item = HistoryItem::new(12345, "w".into());
+        assert_eq!(item.raw_text(), "12345  w");
+        assert_eq!(item.entry_offset, 7);
+    }
+}
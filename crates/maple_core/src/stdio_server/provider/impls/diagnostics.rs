@@ -0,0 +1,205 @@
+use crate::stdio_server::plugin::collect_diagnostics;
+use crate::stdio_server::provider::hooks::PreviewTarget;
+use crate::stdio_server::provider::{
+    ActionParams, BaseArgs, ClapProvider, Context, ListItems, ProviderResult as Result,
+};
+use clap::Parser;
+use icon::{Icon, IconType};
+use ide::linting::{Diagnostic, Severity};
+use rpc::Params;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use types::ClapItem;
+
+#[derive(Debug, Parser, PartialEq, Eq, Default)]
+#[command(name = ":Clap diagnostics")]
+#[command(about = "diagnostics provider", long_about = None)]
+pub(super) struct DiagnosticsArgs {
+    #[clap(flatten)]
+    base: BaseArgs,
+
+    /// Whether to list the diagnostics of the start buffer only.
+    #[clap(long)]
+    buffer: bool,
+}
+
+#[derive(Debug)]
+struct DiagnosticItem {
+    path: PathBuf,
+    lnum: usize,
+    col: usize,
+    severity: Severity,
+    /// `{path}:{lnum}:{col} {message} [{code}]`, the path is relative to the cwd if the
+    /// file is under it.
+    output: String,
+}
+
+impl DiagnosticItem {
+    fn new(path: PathBuf, diagnostic: Diagnostic, cwd: &Path) -> Option<Self> {
+        let span = diagnostic.spans.first()?;
+        let display_path = path.strip_prefix(cwd).unwrap_or(&path);
+        let message = diagnostic.message.split_whitespace().collect::<Vec<_>>();
+        let mut output = format!(
+            "{}:{}:{} {}",
+            display_path.display(),
+            span.line_start,
+            span.column_start,
+            message.join(" ")
+        );
+        if !diagnostic.code.code.is_empty() {
+            output.push_str(&format!(" [{}]", diagnostic.code.code));
+        }
+        Some(Self {
+            lnum: span.line_start,
+            col: span.column_start,
+            severity: diagnostic.severity,
+            path,
+            output,
+        })
+    }
+}
+
+impl ClapItem for DiagnosticItem {
+    fn raw_text(&self) -> &str {
+        &self.output
+    }
+
+    fn icon(&self, icon: Icon) -> Option<IconType> {
+        icon.enabled().then(|| self.severity.icon())
+    }
+}
+
+/// Lists the diagnostics collected by the linter plugin, either of all the linted
+/// buffers or of the start buffer only.
+#[derive(Debug)]
+pub struct DiagnosticsProvider {
+    args: DiagnosticsArgs,
+    diagnostics: ListItems,
+}
+
+impl DiagnosticsProvider {
+    pub async fn new(ctx: &Context) -> Result<Self> {
+        let args: DiagnosticsArgs = ctx.parse_provider_args().await?;
+        let bufnr = args.buffer.then_some(ctx.env.start.bufnr);
+        let diagnostics = collect_diagnostics(bufnr)
+            .into_iter()
+            .filter_map(|(path, diagnostic)| DiagnosticItem::new(path, diagnostic, &ctx.cwd))
+            .map(|item| Arc::new(item) as Arc<dyn ClapItem>)
+            .collect();
+        Ok(Self {
+            args,
+            diagnostics: ListItems::new(diagnostics),
+        })
+    }
+
+    fn diagnostic_at(&mut self, lnum: usize) -> Option<&DiagnosticItem> {
+        self.diagnostics.item_at::<DiagnosticItem>(lnum)
+    }
+}
+
+#[async_trait::async_trait]
+impl ClapProvider for DiagnosticsProvider {
+    async fn on_initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        if self.diagnostics.is_empty() {
+            ctx.vim
+                .echo_info("No diagnostics, the diagnostics are collected by the linter plugin")?;
+        }
+        if self.args.base.query.is_none() {
+            self.diagnostics.process_query("", ctx)?;
+        }
+        ctx.handle_base_args(&self.args.base).await
+    }
+
+    async fn on_move(&mut self, ctx: &mut Context) -> Result<()> {
+        if !ctx.preview_enabled() {
+            return Ok(());
+        }
+
+        let lnum = ctx.vim.display_getcurlnum().await?;
+
+        let Some(diagnostic) = self.diagnostic_at(lnum) else {
+            return Ok(());
+        };
+
+        let preview_target = PreviewTarget::LineInFile {
+            path: diagnostic.path.clone(),
+            line_number: diagnostic.lnum,
+            column: Some(diagnostic.col),
+        };
+
+        ctx.preview_manager.reset_scroll();
+        ctx.update_preview(Some(preview_target)).await
+    }
+
+    async fn on_typed(&mut self, ctx: &mut Context) -> Result<()> {
+        let query = ctx.vim.input_get().await?;
+        self.diagnostics.process_query(&query, ctx)
+    }
+
+    async fn on_action(&mut self, ctx: &mut Context, params: Params) -> Result<()> {
+        let ActionParams { action, .. } = ActionParams::parse(params)?;
+
+        if action != "code_action" {
+            ctx.vim
+                .echo_warn(format!("Unknown diagnostics action: {action}"))?;
+            return Ok(());
+        }
+
+        let lnum = ctx.vim.display_getcurlnum().await?;
+        let Some(diagnostic) = self.diagnostic_at(lnum) else {
+            return Ok(());
+        };
+
+        // The code actions are provided by the language servers managed by Vim.
+        ctx.vim.exec(
+            "clap#provider#diagnostics#apply_code_action",
+            (&diagnostic.path, diagnostic.lnum, diagnostic.col),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ide::linting::{Code, DiagnosticSpan};
+
+    #[test]
+    fn test_diagnostic_item() {
+        let diagnostic = Diagnostic {
+            message: "unused variable: `x`\n  help: prefix it with an underscore".into(),
+            spans: vec![DiagnosticSpan {
+                line_start: 10,
+                line_end: 10,
+                column_start: 9,
+                column_end: 10,
+            }],
+            code: Code {
+                code: "unused_variables".into(),
+            },
+            severity: Severity::Warning,
+        };
+
+        let item = DiagnosticItem::new(
+            PathBuf::from("/project/src/lib.rs"),
+            diagnostic.clone(),
+            Path::new("/project"),
+        )
+        .unwrap();
+        assert_eq!(
+            item.raw_text(),
+            "src/lib.rs:10:9 unused variable: `x` help: prefix it with an underscore [unused_variables]"
+        );
+        assert_eq!(
+            item.icon(Icon::Enabled(icon::IconKind::File)),
+            Some(Severity::Warning.icon())
+        );
+
+        let no_span = Diagnostic {
+            spans: Vec::new(),
+            ..diagnostic
+        };
+        assert!(DiagnosticItem::new(PathBuf::from("/a.rs"), no_span, Path::new("/")).is_none());
+    }
+}
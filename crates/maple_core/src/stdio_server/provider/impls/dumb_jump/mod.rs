@@ -156,7 +156,7 @@ async fn init_gtags(cwd: PathBuf, gtags_regenerated: Arc<AtomicBool>) {
 }
 
 impl DumbJumpProvider {
-    pub async fn new(ctx: &Context) -> VimResult<Self> {
+    pub async fn new(ctx: &Context) -> ProviderResult<Self> {
         let args = ctx.parse_provider_args().await?;
         Ok(Self {
             args,
@@ -317,6 +317,10 @@ impl ClapProvider for DumbJumpProvider {
     }
 
     async fn on_move(&mut self, ctx: &mut Context) -> ProviderResult<()> {
+        if !ctx.preview_enabled() {
+            return Ok(());
+        }
+
         let current_lines = self
             .current_usages
             .as_ref()
@@ -335,10 +339,15 @@ impl ClapProvider for DumbJumpProvider {
         })?;
 
         let preview_height = ctx.preview_height().await?;
-        let (preview_target, preview) =
-            CachedPreviewImpl::new(curline.to_string(), preview_height, ctx)?
-                .get_preview()
-                .await?;
+        let preview_result = match CachedPreviewImpl::new(curline.to_string(), preview_height, ctx)
+        {
+            Ok(preview_impl) => preview_impl.get_preview().await,
+            Err(err) => Err(err),
+        };
+        let (preview_target, preview) = preview_result.map_err(|err| {
+            let _ = ctx.render_preview_error(&err);
+            err
+        })?;
 
         let current_input = ctx.vim.input_get().await?;
         let current_lnum = ctx.vim.display_getcurlnum().await?;
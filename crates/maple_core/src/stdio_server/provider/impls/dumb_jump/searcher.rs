@@ -7,7 +7,7 @@ use rayon::prelude::*;
 use std::collections::HashSet;
 use std::io::Result;
 use std::path::Path;
-use std::process::{Command, Stdio};
+use std::process::Stdio;
 
 /// `dumb_jump` search worker.
 #[derive(Debug, Clone)]
@@ -176,11 +176,12 @@ fn filter_usages(
     cwd: &AbsPathBuf,
     addressable_usages: Vec<AddressableUsage>,
 ) -> Vec<AddressableUsage> {
+    let config = crate::config::config();
     let IgnoreConfig {
         git_tracked_only,
         ignore_file_path_pattern,
         ..
-    } = crate::config::config().ignore_config("dumb_jump", cwd);
+    } = config.ignore_config("dumb_jump", cwd);
 
     let mut addressable_usages = addressable_usages;
 
@@ -211,11 +212,10 @@ fn filter_usages(
 
 fn is_git_tracked(file_path: &str, git_dir: &Path) -> bool {
     // Only the exit status matters.
-    Command::new("git")
+    crate::tools::git::git_command(git_dir)
         .arg("ls-files")
         .arg("--error-unmatch")
         .arg(file_path)
-        .current_dir(git_dir)
         .stderr(Stdio::null())
         .stdout(Stdio::null())
         .status()
@@ -1,5 +1,5 @@
 use crate::stdio_server::input::{KeyEvent, KeyEventType};
-use crate::stdio_server::provider::hooks::{CachedPreviewImpl, Preview, PreviewTarget};
+use crate::stdio_server::provider::hooks::{CachedPreviewImpl, PreviewTarget};
 use crate::stdio_server::provider::{
     ClapProvider, Context, Direction, ProviderError, ProviderResult as Result,
 };
@@ -98,7 +98,7 @@ impl FilerProvider {
     pub async fn new(ctx: &Context) -> Result<Self> {
         let current_dir = ctx.cwd.to_path_buf();
         // icon is handled inside the provider impl.
-        let printer = Printer::new(ctx.env.display_winwidth, icon::Icon::Null);
+        let printer = Printer::new(ctx.display_metrics().winwidth, icon::Icon::Null);
         let icon_enabled = ctx.vim.get_var_bool("clap_enable_icon").await?;
         let winwidth = ctx.vim.winwidth(ctx.env.display.winid).await?;
         Ok(Self {
@@ -208,6 +208,7 @@ impl FilerProvider {
                 mut indices,
                 truncated_map: _,
                 icon_added,
+                ..
             } = self.printer.to_display_lines(
                 current_items
                     .iter()
@@ -236,17 +237,18 @@ impl FilerProvider {
         }
 
         let matcher = ctx.matcher_builder().build(query.into());
-        let mut matched_items = filter::par_filter_items(current_items, &matcher);
-        let matched = matched_items.len();
-
-        matched_items.truncate(200);
+        let mut ranked_items = filter::par_filter_items_ranked(current_items, &matcher);
+        let matched = ranked_items.len();
 
         let printer::DisplayLines {
             lines,
             mut indices,
             truncated_map,
             icon_added,
-        } = self.printer.to_display_lines(matched_items);
+            ..
+        } = self
+            .printer
+            .to_display_lines(ranked_items.top(200).to_vec());
 
         if self.icon_enabled {
             indices.iter_mut().for_each(|v| {
@@ -301,9 +303,7 @@ impl FilerProvider {
 
                 Ok(())
             }
-            Err(err) => ctx
-                .render_preview(Preview::new(vec![err.to_string()]))
-                .map_err(Into::into),
+            Err(err) => ctx.render_preview_error(&err).map_err(Into::into),
         }
     }
 
@@ -397,7 +397,7 @@ impl ClapProvider for FilerProvider {
     }
 
     async fn on_move(&mut self, ctx: &mut Context) -> Result<()> {
-        if !ctx.env.preview_enabled {
+        if !ctx.preview_enabled() {
             return Ok(());
         }
         self.preview_current_entry(ctx).await
@@ -432,6 +432,7 @@ impl ClapProvider for FilerProvider {
             KeyEventType::ShiftDown => ctx.scroll_preview(Direction::Down).await,
             KeyEventType::CtrlN => ctx.next_input().await,
             KeyEventType::CtrlP => ctx.prev_input().await,
+            KeyEventType::AltP => ctx.toggle_preview(),
         }
     }
 }
@@ -1,5 +1,6 @@
 use crate::stdio_server::provider::{
-    BaseArgs, ClapProvider, Context, ProviderError, ProviderResult as Result, SearcherControl,
+    ActiveFilters, BaseArgs, ClapProvider, Context, ProviderError, ProviderResult as Result,
+    SearcherControl,
 };
 use clap::Parser;
 use matcher::{Bonus, MatchScope};
@@ -13,7 +14,7 @@ use types::Query;
 #[derive(Debug, Parser, PartialEq, Eq, Default)]
 #[command(name = ":Clap files")]
 #[command(about = "files provider", long_about = None)]
-struct FilesArgs {
+pub(super) struct FilesArgs {
     #[clap(flatten)]
     base: BaseArgs,
 
@@ -72,7 +73,11 @@ impl FilesProvider {
             } else {
                 MatchScope::Full
             })
-            .bonuses(vec![self.recent_files_bonus.clone()])
+            .bonuses(
+                std::iter::once(self.recent_files_bonus.clone())
+                    .chain(ctx.env.provider_id.pinned_bonus())
+                    .collect(),
+            )
             .build(Query::from(&query));
 
         let new_control = {
@@ -80,6 +85,13 @@ impl FilesProvider {
 
             let join_handle = {
                 let mut search_context = ctx.search_context(stop_signal.clone());
+                search_context.no_matches_hints = ctx.no_matches_hints(
+                    &query,
+                    ActiveFilters {
+                        name_only: self.args.name_only,
+                        ..ActiveFilters::walk(self.args.hidden)
+                    },
+                );
                 if self.args.base.no_cwd {
                     search_context.paths = self.args.paths.clone();
                 } else {
@@ -136,6 +148,10 @@ impl ClapProvider for FilesProvider {
     async fn on_typed(&mut self, ctx: &mut Context) -> Result<()> {
         let query = ctx.vim.input_get().await?;
         if query.is_empty() {
+            // Stop the outdated search from updating the results and the ghost text.
+            if let Some(control) = self.searcher_control.take() {
+                tokio::task::spawn_blocking(move || control.kill());
+            }
             ctx.update_on_empty_query().await?;
         } else {
             self.process_query(query, ctx);
@@ -1,22 +1,28 @@
+use crate::process::{limits, shell_command};
 use crate::stdio_server::provider::hooks::{initialize_provider, CachedPreviewImpl, PreviewTarget};
 use crate::stdio_server::provider::{
-    BaseArgs, ClapProvider, Context, ProviderError, ProviderResult as Result, ProviderSource,
+    raw_query, ActiveFilters, BaseArgs, ClapProvider, Context, ProviderError,
+    ProviderResult as Result, ProviderSource,
 };
 use crate::stdio_server::vim::VimProgressor;
-use filter::{FilterContext, ParallelSource};
+use filter::{FilterContext, ParallelSource, RankedItems};
 use parking_lot::Mutex;
-use printer::{DisplayLines, Printer};
+use printer::DisplayLines;
 use serde_json::json;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::{ChildStdout, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread::JoinHandle;
-use subprocess::Exec;
-use types::MatchedItem;
+use std::time::Duration;
+
+/// Delay before running `git log -S` as the whole history is searched for each term.
+const PICKAXE_DEBOUNCE: Duration = Duration::from_millis(300);
 
 #[derive(Debug)]
 enum DataSource {
     File(PathBuf),
+    CachedFile(PathBuf),
     Command(String),
 }
 
@@ -33,6 +39,30 @@ impl FilterControl {
     }
 }
 
+/// Spawns the source command in its own process group, which is killed along with its
+/// children once the timeout is reached.
+fn spawn_source_command(command: &str, cwd: &Path) -> std::io::Result<ChildStdout> {
+    let mut child = shell_command(command)
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| std::io::Error::other("stdout of the source command is not piped"))?;
+
+    std::thread::spawn(move || {
+        if let Err(err) = limits::wait_with_timeout(&mut child, limits::timeout()) {
+            tracing::debug!(?err, "Failed to wait for the source command");
+        }
+    });
+
+    Ok(stdout)
+}
+
 /// Start the parallel filter in a new thread.
 fn start_filter_parallel(
     query: String,
@@ -46,25 +76,33 @@ fn start_filter_parallel(
         let filter_context = FilterContext::new(
             ctx.env.icon,
             Some(number),
-            Some(ctx.env.display_winwidth),
+            Some(ctx.display_metrics().winwidth),
             ctx.matcher_builder(),
         );
 
         let cwd = ctx.cwd.clone();
-        let vim = ctx.vim.clone();
+        let progressor = VimProgressor::new(ctx.vim.clone(), stop_signal.clone())
+            .with_no_matches_hints(ctx.no_matches_hints(&query, ActiveFilters::default()));
         let stop_signal = stop_signal.clone();
 
         std::thread::spawn(move || {
+            let par_source = match data_source {
+                DataSource::File(path) => ParallelSource::File(path),
+                DataSource::CachedFile(path) => ParallelSource::CachedFile(path),
+                DataSource::Command(command) => match spawn_source_command(&command, &cwd) {
+                    Ok(stdout) => ParallelSource::Stdout(stdout),
+                    Err(e) => {
+                        tracing::error!(error = ?e, command, "Failed to spawn the source command");
+                        return;
+                    }
+                },
+            };
+
             if let Err(e) = filter::par_dyn_run_inprocess(
                 &query,
                 filter_context,
-                match data_source {
-                    DataSource::File(path) => ParallelSource::File(path),
-                    DataSource::Command(command) => {
-                        ParallelSource::Exec(Box::new(Exec::shell(command).cwd(cwd)))
-                    }
-                },
-                VimProgressor::new(vim, stop_signal.clone()),
+                par_source,
+                progressor,
                 stop_signal,
             ) {
                 tracing::error!(error = ?e, "Error occurred when filtering the cache source");
@@ -84,8 +122,10 @@ pub struct GenericProvider {
     args: BaseArgs,
     runtimepath: Option<String>,
     maybe_filter_control: Option<FilterControl>,
-    current_results: Arc<Mutex<Vec<MatchedItem>>>,
+    current_results: Arc<Mutex<RankedItems>>,
     last_filter_control_killed: Arc<AtomicBool>,
+    /// Running search of the commits for the raw query, aborted on the next query.
+    pickaxe_search: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl GenericProvider {
@@ -95,8 +135,9 @@ impl GenericProvider {
             args,
             runtimepath: None,
             maybe_filter_control: None,
-            current_results: Arc::new(Mutex::new(Vec::new())),
+            current_results: Arc::new(Mutex::new(RankedItems::default())),
             last_filter_control_killed: Arc::new(AtomicBool::new(true)),
+            pickaxe_search: None,
         })
     }
 
@@ -109,6 +150,50 @@ impl GenericProvider {
             .map(|r| r.item.output_text().to_string())
     }
 
+    /// Searches the commits changing the occurrences of `term` for the raw query.
+    ///
+    /// The search is debounced and the previous one is aborted, which kills its `git log`,
+    /// the results are discarded if the query has been changed meanwhile.
+    async fn search_commits(&mut self, query: &str, term: &str, ctx: &Context) -> Result<()> {
+        self.abort_pickaxe_search();
+
+        if term.is_empty() {
+            ctx.update_on_empty_query().await?;
+            return Ok(());
+        }
+
+        let ctx = ctx.clone();
+        let query = query.to_string();
+        let term = term.to_string();
+        let path = (ctx.provider_id() == "bcommits").then(|| ctx.env.start_buffer_path.clone());
+        let search = tokio::spawn(async move {
+            tokio::time::sleep(PICKAXE_DEBOUNCE).await;
+
+            let result = crate::tools::git::pickaxe_log(&ctx.cwd, &term, path.as_deref()).await;
+
+            if !matches!(ctx.vim.input_get().await, Ok(input) if input == query) {
+                return;
+            }
+
+            let res = match result {
+                Ok(lines) => ctx.display_raw_lines(lines),
+                Err(err) => ctx.vim.echo_warn(format!("git log -S failed: {err}")),
+            };
+            if let Err(err) = res {
+                tracing::debug!(?err, "Failed to display the commits of git log -S");
+            }
+        });
+        self.pickaxe_search.replace(search);
+
+        Ok(())
+    }
+
+    fn abort_pickaxe_search(&mut self) {
+        if let Some(search) = self.pickaxe_search.take() {
+            search.abort();
+        }
+    }
+
     async fn nontypical_preview_target(
         &mut self,
         curline: &str,
@@ -136,16 +221,6 @@ impl GenericProvider {
                     runtimepath,
                 })
             }
-            "buffers" => {
-                let res: [String; 2] = ctx
-                    .vim
-                    .bare_call("clap#provider#buffers#preview_target")
-                    .await?;
-                let mut iter = res.into_iter();
-                let path = iter.next().expect("Element must exist").into();
-                let line_number = iter.next().expect("Element must exist").parse::<usize>()?;
-                Some(PreviewTarget::LineInFile { path, line_number })
-            }
             _ => None,
         };
 
@@ -163,7 +238,7 @@ impl ClapProvider for GenericProvider {
     }
 
     async fn on_move(&mut self, ctx: &mut Context) -> Result<()> {
-        if !ctx.env.preview_enabled {
+        if !ctx.preview_enabled() {
             return Ok(());
         }
 
@@ -186,15 +261,22 @@ impl ClapProvider for GenericProvider {
                     cache_line: None,
                 }
             } else {
-                CachedPreviewImpl::new(curline, preview_height, ctx)?
+                CachedPreviewImpl::new(curline, preview_height, ctx).map_err(|err| {
+                    let _ = ctx.render_preview_error(&err);
+                    err
+                })?
             };
 
-        let (preview_target, preview) = preview_impl.get_preview().await?;
+        let (preview_target, preview) = preview_impl.get_preview().await.map_err(|err| {
+            let _ = ctx.render_preview_error(&err);
+            err
+        })?;
 
         // Ensure the preview result is not out-dated.
         let curlnum = ctx.vim.display_getcurlnum().await?;
         if curlnum == lnum {
-            ctx.render_preview(preview)?;
+            ctx.preview_manager.reset_scroll();
+            ctx.render_preview_with_target(&preview_target, preview)?;
         }
 
         ctx.preview_manager.set_preview_target(preview_target);
@@ -205,36 +287,50 @@ impl ClapProvider for GenericProvider {
     async fn on_typed(&mut self, ctx: &mut Context) -> Result<()> {
         let query = ctx.vim.input_get().await?;
 
+        if matches!(ctx.provider_id(), "commits" | "bcommits") {
+            match raw_query(&query) {
+                Some(term) => return self.search_commits(&query, term, ctx).await,
+                None => self.abort_pickaxe_search(),
+            }
+        }
+
         let small_list_response =
             if let ProviderSource::Small { ref items, .. } = *ctx.provider_source.read() {
-                let matched_items = filter::par_filter_items(items, &ctx.matcher(&query));
-                let printer = Printer::new(ctx.env.display_winwidth, ctx.env.icon);
+                let mut ranked_items = filter::par_filter_items_ranked(items, &ctx.matcher(&query));
+                let printer = ctx.printer();
                 // Take the first 200 entries and add an icon to each of them.
                 let DisplayLines {
                     lines,
                     indices,
                     truncated_map,
                     icon_added,
-                } = printer.to_display_lines(matched_items.iter().take(200).cloned().collect());
-                let msg = json!({
-                    "total": matched_items.len(),
+                    highlights,
+                } = printer.to_display_lines(ranked_items.top(200).to_vec());
+                let mut msg = json!({
+                    "total": ranked_items.len(),
                     "lines": lines,
                     "indices": indices,
                     "icon_added": icon_added,
                     "truncated_map": truncated_map,
+                    "highlights": highlights,
                 });
-                Some((msg, matched_items))
+                if ranked_items.is_empty() {
+                    msg["no_matches_hints"] = json!(ctx
+                        .no_matches_hints(&query, ActiveFilters::default())
+                        .hints());
+                }
+                Some((msg, ranked_items))
             } else {
                 None
             };
 
-        if let Some((msg, matched_items)) = small_list_response {
+        if let Some((msg, ranked_items)) = small_list_response {
             let new_query = ctx.vim.input_get().await?;
             if new_query == query {
                 ctx.vim
                     .exec("clap#state#process_filter_message", json!([msg, true]))?;
                 let mut current_results = self.current_results.lock();
-                *current_results = matched_items;
+                *current_results = ranked_items;
             }
             return Ok(());
         }
@@ -256,9 +352,8 @@ impl ClapProvider for GenericProvider {
                 ctx.vim.echo_warn(format!("InitializationFailed: {msg}"))?;
                 return Ok(());
             }
-            ProviderSource::CachedFile { ref path, .. } | ProviderSource::File { ref path, .. } => {
-                DataSource::File(path.clone())
-            }
+            ProviderSource::CachedFile { ref path, .. } => DataSource::CachedFile(path.clone()),
+            ProviderSource::File { ref path, .. } => DataSource::File(path.clone()),
             ProviderSource::Command(ref cmd) => DataSource::Command(cmd.to_string()),
         };
 
@@ -282,7 +377,7 @@ impl ClapProvider for GenericProvider {
             });
         }
 
-        let display_winheight = ctx.env.display_winheight;
+        let display_winheight = ctx.display_metrics().winheight;
         let new_control = start_filter_parallel(query, display_winheight, data_source, ctx);
 
         self.maybe_filter_control.replace(new_control);
@@ -295,6 +390,7 @@ impl ClapProvider for GenericProvider {
             // NOTE: The kill operation can not block current task.
             tokio::task::spawn_blocking(move || control.kill());
         }
+        self.abort_pickaxe_search();
         ctx.signify_terminated(session_id);
     }
 }
@@ -0,0 +1,230 @@
+use crate::stdio_server::provider::hooks::PreviewTarget;
+use crate::stdio_server::provider::{
+    ActionParams, BaseArgs, ClapProvider, Context, ListItems, ProviderResult as Result,
+};
+use crate::tools::git::{self, Branch};
+use rpc::Params;
+use std::sync::Arc;
+use types::{ClapItem, MatchResult};
+
+#[derive(Debug)]
+struct BranchItem {
+    branch: Branch,
+    /// `{* } {name} {hash} {subject} ({date})`, the sink parses the branch name from
+    /// the first word after the leading marker. `{hash}` and `({date})` are dropped
+    /// in the compact layout.
+    output: String,
+}
+
+impl BranchItem {
+    /// Char length of the `* ` marker of the current branch.
+    const PREFIX_LEN: usize = 2;
+
+    fn new(branch: Branch, name_width: usize, drop_columns: bool) -> Self {
+        let marker = if branch.is_head { '*' } else { ' ' };
+        let output = if drop_columns {
+            format!("{marker} {:<name_width$} {}", branch.name, branch.subject)
+        } else {
+            format!(
+                "{marker} {:<name_width$} {} {} ({})",
+                branch.name, branch.hash, branch.subject, branch.date,
+            )
+        };
+        Self { branch, output }
+    }
+}
+
+impl ClapItem for BranchItem {
+    fn raw_text(&self) -> &str {
+        &self.output
+    }
+
+    fn match_text(&self) -> &str {
+        &self.branch.name
+    }
+
+    fn match_result_callback(&self, match_result: MatchResult) -> MatchResult {
+        let mut match_result = match_result;
+        match_result.indices.iter_mut().for_each(|x| {
+            *x += Self::PREFIX_LEN;
+        });
+        match_result
+    }
+}
+
+/// Lists the local and remote branches of the git repo in the cwd, the list is
+/// refreshed after the branches are changed by the actions.
+#[derive(Debug)]
+pub struct GitBranchesProvider {
+    args: BaseArgs,
+    branches: ListItems,
+}
+
+impl GitBranchesProvider {
+    pub async fn new(ctx: &Context) -> Result<Self> {
+        let args = ctx.parse_provider_args().await?;
+        Ok(Self {
+            args,
+            branches: ListItems::default(),
+        })
+    }
+
+    async fn load_branches(&mut self, ctx: &Context) -> Result<()> {
+        let branches = git::branches(&ctx.cwd).await?;
+        let name_width = branches
+            .iter()
+            .map(|branch| branch.name.chars().count())
+            .max()
+            .unwrap_or_default();
+        let drop_columns = ctx.display_metrics().layout.drop_columns();
+        self.branches.set_items(
+            branches
+                .into_iter()
+                .map(|branch| {
+                    Arc::new(BranchItem::new(branch, name_width, drop_columns)) as Arc<dyn ClapItem>
+                })
+                .collect(),
+        );
+        Ok(())
+    }
+
+    fn branch_at(&mut self, lnum: usize) -> Option<&Branch> {
+        self.branches
+            .item_at::<BranchItem>(lnum)
+            .map(|item| &item.branch)
+    }
+}
+
+/// Returns the arguments of the git command performing `action` on `branch`, the
+/// checkout is done by [`git::checkout`] shared with the sink.
+fn action_args(action: &str, branch: &Branch, new_branch: Option<&str>) -> Option<Vec<String>> {
+    let args = match action {
+        // Only the remote-tracking branch is deleted, the remote is never touched.
+        "delete" if branch.is_remote => vec!["branch", "--delete", "--remotes", &branch.name],
+        "delete" => vec!["branch", "--delete", &branch.name],
+        "create" => vec!["branch", new_branch?, &branch.name],
+        _ => return None,
+    };
+    Some(args.into_iter().map(Into::into).collect())
+}
+
+#[async_trait::async_trait]
+impl ClapProvider for GitBranchesProvider {
+    async fn on_initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        self.load_branches(ctx).await?;
+        if self.args.query.is_none() {
+            self.branches.process_query("", ctx)?;
+        }
+        ctx.handle_base_args(&self.args).await
+    }
+
+    async fn on_move(&mut self, ctx: &mut Context) -> Result<()> {
+        if !ctx.preview_enabled() {
+            return Ok(());
+        }
+
+        let lnum = ctx.vim.display_getcurlnum().await?;
+
+        let Some(branch) = self.branch_at(lnum) else {
+            return Ok(());
+        };
+
+        let preview_target = PreviewTarget::GitRef(branch.name.clone());
+
+        ctx.preview_manager.reset_scroll();
+        ctx.update_preview(Some(preview_target)).await
+    }
+
+    async fn on_typed(&mut self, ctx: &mut Context) -> Result<()> {
+        let query = ctx.vim.input_get().await?;
+        self.branches.process_query(&query, ctx)
+    }
+
+    async fn on_action(&mut self, ctx: &mut Context, params: Params) -> Result<()> {
+        let ActionParams { action, name } = ActionParams::parse(params)?;
+
+        let lnum = ctx.vim.display_getcurlnum().await?;
+        let Some(branch) = self.branch_at(lnum) else {
+            return Ok(());
+        };
+
+        if action == "checkout" {
+            if let Err(err) = git::checkout(&ctx.cwd, &branch.name).await {
+                ctx.vim
+                    .echo_warn(format!("Failed to checkout {}: {err}", branch.name))?;
+                return Ok(());
+            }
+            // Reload the buffers changed by the checkout.
+            ctx.vim.exec("execute", ["checktime"])?;
+        } else {
+            let Some(args) = action_args(&action, branch, name.as_deref()) else {
+                ctx.vim
+                    .echo_warn(format!("Unknown git_branches action: {action}"))?;
+                return Ok(());
+            };
+
+            if let Err(err) = git::run_git(&ctx.cwd, &args).await {
+                ctx.vim
+                    .echo_warn(format!("Failed to run `git {}`: {err}", args.join(" ")))?;
+                return Ok(());
+            }
+        }
+
+        self.load_branches(ctx).await?;
+        let query = ctx.vim.input_get().await?;
+        self.branches.process_query(&query, ctx)?;
+
+        self.on_move(ctx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn branch(name: &str, is_remote: bool) -> Branch {
+        Branch {
+            name: name.into(),
+            is_remote,
+            is_head: false,
+            hash: "a1b2c3d".into(),
+            date: "2 days ago".into(),
+            subject: "Fix the typo".into(),
+        }
+    }
+
+    #[test]
+    fn test_branch_item() {
+        let item = BranchItem::new(branch("dev", false), 6, false);
+        assert_eq!(
+            item.raw_text(),
+            "  dev    a1b2c3d Fix the typo (2 days ago)"
+        );
+        let item = BranchItem::new(branch("dev", false), 6, true);
+        assert_eq!(item.raw_text(), "  dev    Fix the typo");
+        assert_eq!(item.match_text(), "dev");
+        let match_result = item.match_result_callback(MatchResult::new(0, vec![0, 1]));
+        assert_eq!(match_result.indices, [2, 3]);
+    }
+
+    #[test]
+    fn test_action_args() {
+        let local = branch("dev", false);
+        let remote = branch("origin/dev", true);
+        assert_eq!(
+            action_args("delete", &local, None).unwrap(),
+            ["branch", "--delete", "dev"]
+        );
+        assert_eq!(
+            action_args("delete", &remote, None).unwrap(),
+            ["branch", "--delete", "--remotes", "origin/dev"]
+        );
+        assert_eq!(
+            action_args("create", &remote, Some("feature")).unwrap(),
+            ["branch", "feature", "origin/dev"]
+        );
+        assert_eq!(action_args("create", &local, None), None);
+        assert_eq!(action_args("checkout", &local, None), None);
+        assert_eq!(action_args("merge", &local, None), None);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::stdio_server::provider::hooks::PreviewTarget;
+use crate::stdio_server::provider::{
+    BaseArgs, ClapProvider, Context, ListItems, ProviderResult as Result,
+};
+use crate::tools::git::Hunk;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use types::ClapItem;
+
+#[derive(Debug)]
+struct HunkItem {
+    /// Absolute path of the file.
+    path: PathBuf,
+    lnum: usize,
+    /// `{path}:{lnum} +{added} -{removed} {text}`, the path is relative to the cwd
+    /// if the file is under it.
+    output: String,
+}
+
+impl HunkItem {
+    fn new(hunk: Hunk, work_tree: &Path, cwd: &Path) -> Self {
+        let path = work_tree.join(&hunk.path);
+        let display_path = path.strip_prefix(cwd).unwrap_or(&path);
+        let output = format!(
+            "{}:{} +{} -{} {}",
+            display_path.display(),
+            hunk.lnum,
+            hunk.added,
+            hunk.removed,
+            hunk.text
+        );
+        Self {
+            path,
+            lnum: hunk.lnum,
+            output,
+        }
+    }
+}
+
+impl ClapItem for HunkItem {
+    fn raw_text(&self) -> &str {
+        &self.output
+    }
+}
+
+/// Lists the hunks of the uncommitted changes across the work tree containing the
+/// cwd, in the order of `git diff`.
+#[derive(Debug)]
+pub struct GitHunksProvider {
+    args: BaseArgs,
+    hunks: ListItems,
+}
+
+impl GitHunksProvider {
+    pub async fn new(ctx: &Context) -> Result<Self> {
+        let args = ctx.parse_provider_args().await?;
+        Ok(Self {
+            args,
+            hunks: ListItems::default(),
+        })
+    }
+
+    async fn load_hunks(&mut self, ctx: &Context) -> Result<()> {
+        let Some(work_tree) = crate::tools::git::find_work_tree(&ctx.cwd) else {
+            ctx.vim
+                .echo_warn(format!("{} is not in a git repository", ctx.cwd.display()))?;
+            return Ok(());
+        };
+        self.hunks.set_items(
+            crate::tools::git::diff_hunks(&work_tree)
+                .await?
+                .into_iter()
+                .map(|hunk| {
+                    Arc::new(HunkItem::new(hunk, &work_tree, &ctx.cwd)) as Arc<dyn ClapItem>
+                })
+                .collect(),
+        );
+        Ok(())
+    }
+
+    fn hunk_at(&mut self, lnum: usize) -> Option<&HunkItem> {
+        self.hunks.item_at::<HunkItem>(lnum)
+    }
+}
+
+#[async_trait::async_trait]
+impl ClapProvider for GitHunksProvider {
+    async fn on_initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        self.load_hunks(ctx).await?;
+        if self.args.query.is_none() {
+            self.hunks.process_query("", ctx)?;
+        }
+        ctx.handle_base_args(&self.args).await
+    }
+
+    async fn on_move(&mut self, ctx: &mut Context) -> Result<()> {
+        if !ctx.preview_enabled() {
+            return Ok(());
+        }
+
+        let lnum = ctx.vim.display_getcurlnum().await?;
+
+        let Some(hunk) = self.hunk_at(lnum) else {
+            return Ok(());
+        };
+
+        let preview_target = PreviewTarget::LineInFile {
+            path: hunk.path.clone(),
+            line_number: hunk.lnum,
+            column: None,
+        };
+
+        ctx.preview_manager.reset_scroll();
+        ctx.update_preview(Some(preview_target)).await
+    }
+
+    async fn on_typed(&mut self, ctx: &mut Context) -> Result<()> {
+        let query = ctx.vim.input_get().await?;
+        self.hunks.process_query(&query, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hunk_item() {
+        let hunk = |path: &str| Hunk {
+            path: path.into(),
+            lnum: 3,
+            added: 2,
+            removed: 1,
+            text: "let x = 2;".into(),
+        };
+        let work_tree = Path::new("/project");
+
+        let item = HunkItem::new(
+            hunk("crates/foo/src/lib.rs"),
+            work_tree,
+            Path::new("/project/crates"),
+        );
+        assert_eq!(item.raw_text(), "foo/src/lib.rs:3 +2 -1 let x = 2;");
+        assert_eq!(item.path, Path::new("/project/crates/foo/src/lib.rs"));
+
+        // Outside of the cwd.
+        let item = HunkItem::new(hunk("README.md"), work_tree, Path::new("/project/crates"));
+        assert_eq!(item.raw_text(), "/project/README.md:3 +2 -1 let x = 2;");
+    }
+}
@@ -1,8 +1,13 @@
+use crate::searcher::grep::{ExtraRoot, LineMatcher, Replacement};
+use crate::searcher::WalkConfig;
+use crate::stdio_server::provider::hooks::initialize_provider;
 use crate::stdio_server::provider::{
-    BaseArgs, ClapProvider, Context, ProviderResult as Result, SearcherControl,
+    raw_query, ActiveFilters, BaseArgs, ClapProvider, Context, ProviderResult as Result,
+    SearcherControl,
 };
 use clap::Parser;
 use matcher::MatchScope;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
@@ -11,13 +16,45 @@ use types::Query;
 #[derive(Debug, Parser, PartialEq, Eq, Default)]
 #[command(name = ":Clap grep")]
 #[command(about = "grep provider", long_about = None)]
-struct GrepArgs {
+pub(super) struct GrepArgs {
     #[clap(flatten)]
     base: BaseArgs,
 
     /// Specify additional search paths apart from the current working directory.
     #[clap(long = "path")]
     paths: Vec<PathBuf>,
+
+    /// Same as `--path`, e.g., `:Clap grep foo/ bar/`.
+    #[clap(value_name = "PATH")]
+    positional_paths: Vec<PathBuf>,
+
+    /// Whether to search hidden files and directories.
+    #[clap(long)]
+    hidden: bool,
+
+    /// Only search the files of given type, e.g., `--type=rust`. Can be specified multiple times.
+    #[clap(long = "type", value_parser = parse_file_type)]
+    file_types: Vec<String>,
+
+    /// Preview the replacement of the query in the matched lines, nothing is written to disk.
+    #[clap(long)]
+    replace: Option<String>,
+}
+
+/// Ensures `file_type` is one of the builtin file types of ripgrep.
+fn parse_file_type(file_type: &str) -> std::result::Result<String, String> {
+    let mut types_builder = ignore::types::TypesBuilder::new();
+    types_builder.add_defaults();
+
+    if types_builder
+        .definitions()
+        .iter()
+        .any(|def| def.name() == file_type)
+    {
+        Ok(file_type.to_string())
+    } else {
+        Err(format!("unknown file type `{file_type}`"))
+    }
 }
 
 #[derive(Debug)]
@@ -29,43 +66,99 @@ pub struct GrepProvider {
 impl GrepProvider {
     pub async fn new(ctx: &Context) -> Result<Self> {
         tracing::debug!("================== [grep] ctx: {ctx:?}");
-        let GrepArgs { base, paths } = ctx.parse_provider_args().await?;
+        let GrepArgs {
+            base,
+            mut paths,
+            positional_paths,
+            hidden,
+            file_types,
+            replace,
+        } = ctx.parse_provider_args().await?;
         tracing::debug!("================== [grep] base: {base:?}, paths: {paths:?}");
+        paths.extend(positional_paths);
         Ok(Self {
             args: GrepArgs {
                 base,
                 paths: ctx.expanded_paths(&paths).await?,
+                positional_paths: Vec::new(),
+                hidden,
+                file_types,
+                replace,
             },
             searcher_control: None,
         })
     }
 
-    fn process_query(&mut self, query: String, ctx: &Context) {
+    fn process_query(&mut self, query: String, ctx: &Context) -> Result<()> {
         if let Some(control) = self.searcher_control.take() {
             tokio::task::spawn_blocking(move || {
                 control.kill();
             });
         }
 
-        let matcher = ctx
-            .matcher_builder()
-            .match_scope(MatchScope::Full) // Force using MatchScope::Full.
-            .build(Query::from(&query));
+        let (matcher, replacement) = match raw_query(&query) {
+            Some(pattern) => {
+                let regex = match regex::Regex::new(pattern) {
+                    Ok(regex) => regex,
+                    Err(err) => {
+                        ctx.vim
+                            .echo_warn(format!("Invalid regex `{pattern}`: {err}"))?;
+                        return Ok(());
+                    }
+                };
+                let replacement = self
+                    .args
+                    .replace
+                    .clone()
+                    .map(|replace| Replacement::from_regex(regex.clone(), replace));
+                (LineMatcher::Raw(regex), replacement)
+            }
+            None => {
+                let matcher = ctx
+                    .matcher_builder()
+                    .match_scope(MatchScope::Full) // Force using MatchScope::Full.
+                    .build(Query::from(&query));
+                let replacement = self
+                    .args
+                    .replace
+                    .clone()
+                    .and_then(|replace| Replacement::new(&query, replace));
+                (matcher.into(), replacement)
+            }
+        };
+
+        ctx.preview_manager.set_line_matcher(matcher.clone());
 
         let new_control = {
             let stop_signal = Arc::new(AtomicBool::new(false));
 
             let vim = ctx.vim.clone();
+            let cwd = ctx.cwd.to_path_buf();
             let mut search_context = ctx.search_context(stop_signal.clone());
+            search_context.no_matches_hints =
+                ctx.no_matches_hints(&query, ActiveFilters::walk(self.args.hidden));
             // cwd + extra paths
             if self.args.base.no_cwd {
                 search_context.paths = self.args.paths.clone();
             } else {
                 search_context.paths.extend_from_slice(&self.args.paths);
             }
+            let walk_config = WalkConfig {
+                hidden: !self.args.hidden,
+                file_types: self.args.file_types.clone(),
+                ..Default::default()
+            };
             let join_handle = tokio::spawn(async move {
                 let _ = vim.bare_exec("clap#spinner#set_busy");
-                crate::searcher::grep::search(query, matcher, search_context).await;
+                crate::searcher::grep::search(
+                    query,
+                    cwd,
+                    walk_config,
+                    matcher,
+                    search_context,
+                    replacement,
+                )
+                .await;
                 let _ = vim.bare_exec("clap#spinner#set_idle");
             });
 
@@ -76,21 +169,37 @@ impl GrepProvider {
         };
 
         self.searcher_control.replace(new_control);
+
+        Ok(())
     }
 }
 
 #[async_trait::async_trait]
 impl ClapProvider for GrepProvider {
     async fn on_initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        // The sink resolves the paths displayed with the root label as well.
+        let extra_roots = ExtraRoot::from_paths(&self.args.paths, &ctx.cwd);
+        let root_labels = extra_roots
+            .iter()
+            .map(|root| (root.label.as_str(), root.path.as_path()))
+            .collect::<HashMap<_, _>>();
+        ctx.vim.set_var("g:__clap_grep_roots", root_labels)?;
+        ctx.set_extra_search_roots(extra_roots);
+
+        initialize_provider(ctx, self.args.base.query.is_none()).await?;
         ctx.handle_base_args(&self.args.base).await
     }
 
     async fn on_typed(&mut self, ctx: &mut Context) -> Result<()> {
         let query = ctx.vim.input_get().await?;
-        if query.is_empty() {
+        if query.is_empty() || raw_query(&query).map_or(false, str::is_empty) {
+            // Stop the outdated search from updating the results and the ghost text.
+            if let Some(control) = self.searcher_control.take() {
+                tokio::task::spawn_blocking(move || control.kill());
+            }
             ctx.update_on_empty_query().await?;
         } else {
-            self.process_query(query, ctx);
+            self.process_query(query, ctx)?;
         }
 
         Ok(())
@@ -118,7 +227,8 @@ mod tests {
                     query: Some(String::from("@visual")),
                     ..Default::default()
                 },
-                paths: vec![PathBuf::from("~/.vim/plugged/vim-clap")]
+                paths: vec![PathBuf::from("~/.vim/plugged/vim-clap")],
+                ..Default::default()
             }
         );
 
@@ -129,7 +239,7 @@ mod tests {
                     query: Some(String::from("@visual")),
                     ..Default::default()
                 },
-                paths: vec![]
+                ..Default::default()
             }
         );
 
@@ -137,8 +247,28 @@ mod tests {
             GrepArgs::parse_from([""]),
             GrepArgs {
                 base: BaseArgs::default(),
-                paths: vec![]
+                ..Default::default()
+            }
+        );
+
+        assert_eq!(
+            GrepArgs::parse_from(["", "--hidden", "--type=rust", "--type=vim", "foo/"]),
+            GrepArgs {
+                positional_paths: vec![PathBuf::from("foo/")],
+                hidden: true,
+                file_types: vec![String::from("rust"), String::from("vim")],
+                ..Default::default()
             }
         );
+
+        assert_eq!(
+            GrepArgs::parse_from(["", "--replace=bar"]),
+            GrepArgs {
+                replace: Some(String::from("bar")),
+                ..Default::default()
+            }
+        );
+
+        assert!(GrepArgs::try_parse_from(["", "--type=not-a-type"]).is_err());
     }
 }
@@ -1,6 +1,7 @@
 use super::filer::{read_dir_entries, FilerItem, FilerItemWithoutIcon};
+use crate::searcher::WalkConfig;
 use crate::stdio_server::input::{KeyEvent, KeyEventType};
-use crate::stdio_server::provider::hooks::{CachedPreviewImpl, Preview, PreviewTarget};
+use crate::stdio_server::provider::hooks::{CachedPreviewImpl, PreviewTarget};
 use crate::stdio_server::provider::{
     ClapProvider, Context, Direction, ProviderError, ProviderResult as Result, SearcherControl,
 };
@@ -52,9 +53,17 @@ impl Grepper {
             let stop_signal = Arc::new(AtomicBool::new(false));
 
             let mut search_context = ctx.search_context(stop_signal.clone());
-            search_context.paths = vec![path];
+            search_context.paths = vec![path.clone()];
             let join_handle = tokio::spawn(async move {
-                crate::searcher::grep::search(query, matcher, search_context).await
+                crate::searcher::grep::search(
+                    query,
+                    path,
+                    WalkConfig::default(),
+                    matcher.into(),
+                    search_context,
+                    None,
+                )
+                .await
             });
 
             SearcherControl {
@@ -84,7 +93,7 @@ struct Explorer {
 impl Explorer {
     async fn new(ctx: &Context) -> Result<Self> {
         let current_dir = ctx.cwd.to_path_buf();
-        let printer = Printer::new(ctx.env.display_winwidth, icon::Icon::Null);
+        let printer = Printer::new(ctx.display_metrics().winwidth, icon::Icon::Null);
         let icon_enabled = ctx.vim.get_var_bool("clap_enable_icon").await?;
         let winwidth = ctx.vim.winwidth(ctx.env.display.winid).await?;
         Ok(Self {
@@ -208,6 +217,7 @@ impl Explorer {
             mut indices,
             truncated_map: _,
             icon_added,
+            ..
         } = self.printer.to_display_lines(
             current_items
                 .iter()
@@ -285,7 +295,7 @@ impl Explorer {
                 }
             }
             Err(err) => {
-                ctx.render_preview(Preview::new(vec![err.to_string()]))?;
+                ctx.render_preview_error(&err)?;
             }
         }
         Ok(())
@@ -435,13 +445,14 @@ impl IgrepProvider {
 
     async fn preview_grep_line(&self, ctx: &mut Context) -> Result<()> {
         let curline = ctx.vim.display_getcurline().await?;
-        if let Some((fpath, lnum, _col, _cache_line)) = extract_grep_position(&curline) {
+        if let Some((fpath, lnum, col, _cache_line)) = extract_grep_position(&curline) {
             let fpath = fpath.strip_prefix("./").unwrap_or(fpath);
             let path = self.explorer.current_dir.join(fpath);
 
             let preview_target = PreviewTarget::LineInFile {
                 path,
                 line_number: lnum,
+                column: Some(col),
             };
 
             ctx.update_preview(Some(preview_target)).await?;
@@ -457,7 +468,7 @@ impl ClapProvider for IgrepProvider {
     }
 
     async fn on_move(&mut self, ctx: &mut Context) -> Result<()> {
-        if !ctx.env.preview_enabled {
+        if !ctx.preview_enabled() {
             return Ok(());
         }
         let query: String = ctx.vim.input_get().await?;
@@ -488,6 +499,7 @@ impl ClapProvider for IgrepProvider {
         match key_event_type {
             KeyEventType::CtrlN => ctx.next_input().await,
             KeyEventType::CtrlP => ctx.prev_input().await,
+            KeyEventType::AltP => ctx.toggle_preview(),
             KeyEventType::ShiftUp => ctx.scroll_preview(Direction::Up).await,
             KeyEventType::ShiftDown => ctx.scroll_preview(Direction::Down).await,
             KeyEventType::Tab => self.on_tab(ctx).await,
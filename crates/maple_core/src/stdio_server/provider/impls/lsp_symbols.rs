@@ -0,0 +1,127 @@
+use crate::stdio_server::provider::hooks::PreviewTarget;
+use crate::stdio_server::provider::{
+    BaseArgs, ClapProvider, Context, ListItems, ProviderResult as Result,
+};
+use crate::tools::symbols::{lsp_symbols, LspSymbol, WorkspaceSymbolItem};
+use clap::Parser;
+use std::sync::Arc;
+use types::ClapItem;
+
+#[derive(Debug, Parser, PartialEq, Eq, Default)]
+#[command(name = ":Clap lsp_symbols")]
+#[command(about = "lsp_symbols provider", long_about = None)]
+pub(super) struct LspSymbolsArgs {
+    #[clap(flatten)]
+    base: BaseArgs,
+
+    /// Whether to list the symbols of the start buffer instead of the workspace.
+    #[clap(long)]
+    document: bool,
+}
+
+/// Lists the symbols reported by the language servers attached to the start buffer,
+/// unlike `symbols`, neither ctags nor tree-sitter is involved.
+#[derive(Debug)]
+pub struct LspSymbolsProvider {
+    args: LspSymbolsArgs,
+    symbols: ListItems,
+}
+
+impl LspSymbolsProvider {
+    pub async fn new(ctx: &Context) -> Result<Self> {
+        let args: LspSymbolsArgs = ctx.parse_provider_args().await?;
+        Ok(Self {
+            args,
+            symbols: ListItems::default(),
+        })
+    }
+
+    async fn load_symbols(&mut self, ctx: &Context) -> Result<()> {
+        if crate::config::config().provider.disable_lsp {
+            ctx.vim
+                .echo_warn("LSP is disabled by `provider.disable-lsp` in the config")?;
+            return Ok(());
+        }
+
+        let scope = if self.args.document {
+            "document"
+        } else {
+            "workspace"
+        };
+        // The language servers are managed by Vim, e.g., the builtin LSP client of Neovim.
+        let symbols: Vec<LspSymbol> = ctx
+            .vim
+            .call("clap#provider#symbols#lsp_symbols", [scope])
+            .await?;
+
+        if symbols.is_empty() {
+            ctx.vim.echo_warn(format!(
+                "No {scope} symbols, is any language server attached to the buffer?"
+            ))?;
+        }
+
+        self.symbols.set_items(
+            lsp_symbols(symbols, &ctx.cwd)
+                .into_iter()
+                .map(|symbol| Arc::new(symbol.into_symbol_item()) as Arc<dyn ClapItem>)
+                .collect(),
+        );
+
+        Ok(())
+    }
+
+    fn symbol_at(&mut self, lnum: usize) -> Option<&WorkspaceSymbolItem> {
+        self.symbols.item_at::<WorkspaceSymbolItem>(lnum)
+    }
+}
+
+#[async_trait::async_trait]
+impl ClapProvider for LspSymbolsProvider {
+    async fn on_initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        self.load_symbols(ctx).await?;
+        if self.args.base.query.is_none() {
+            self.symbols.process_query("", ctx)?;
+        }
+        ctx.handle_base_args(&self.args.base).await
+    }
+
+    async fn on_move(&mut self, ctx: &mut Context) -> Result<()> {
+        if !ctx.preview_enabled() {
+            return Ok(());
+        }
+
+        let lnum = ctx.vim.display_getcurlnum().await?;
+
+        let Some(symbol) = self.symbol_at(lnum) else {
+            return Ok(());
+        };
+
+        // The path is absolute if it's outside of the cwd.
+        let preview_target = PreviewTarget::LineInFile {
+            path: ctx.cwd.join(&symbol.path),
+            line_number: symbol.line,
+            column: None,
+        };
+
+        ctx.preview_manager.reset_scroll();
+        ctx.update_preview(Some(preview_target)).await
+    }
+
+    async fn on_typed(&mut self, ctx: &mut Context) -> Result<()> {
+        let query = ctx.vim.input_get().await?;
+        self.symbols.process_query(&query, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lsp_symbols_args() {
+        let args = LspSymbolsArgs::try_parse_from(["", "--document", "--query=foo"]).unwrap();
+        assert!(args.document);
+        assert_eq!(args.base.query.as_deref(), Some("foo"));
+        assert!(!LspSymbolsArgs::default().document);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::stdio_server::provider::hooks::PreviewTarget;
+use crate::stdio_server::provider::{
+    BaseArgs, ClapProvider, Context, ListItems, ProviderResult as Result,
+};
+use icon::{icon_or_default, Icon, IconType};
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Arc;
+use types::ClapItem;
+
+/// Position returned by `clap#provider#marks#entries()` or `clap#provider#jumps#entries()`.
+#[derive(Debug, Clone, Deserialize)]
+struct PositionEntry {
+    /// Mark name, or the count of the jump relative to the current position, e.g., `-3`.
+    key: String,
+    lnum: usize,
+    /// 1-based column.
+    col: usize,
+    /// Absolute path, empty for the unnamed buffer.
+    path: String,
+    /// Line content if the position is in the start buffer, the path otherwise.
+    text: String,
+}
+
+#[derive(Debug)]
+struct PositionItem {
+    entry: PositionEntry,
+    output: String,
+}
+
+impl PositionItem {
+    fn new(entry: PositionEntry) -> Self {
+        let output = format!(
+            "{:<4} {:>6} {:>4}  {}",
+            entry.key, entry.lnum, entry.col, entry.text
+        );
+        Self { entry, output }
+    }
+}
+
+impl ClapItem for PositionItem {
+    fn raw_text(&self) -> &str {
+        &self.output
+    }
+
+    fn icon(&self, icon: Icon) -> Option<IconType> {
+        icon.enabled()
+            .then(|| icon_or_default(Path::new(&self.entry.path)))
+    }
+}
+
+/// Lists the positions of `marks` or `jumps`, which are fetched from Vim on
+/// initialization.
+#[derive(Debug)]
+pub struct MarksProvider {
+    args: BaseArgs,
+    positions: ListItems,
+}
+
+impl MarksProvider {
+    pub async fn new(ctx: &Context) -> Result<Self> {
+        let args = ctx.parse_provider_args().await?;
+        let entries: Vec<PositionEntry> = ctx
+            .vim
+            .bare_call(format!("clap#provider#{}#entries", ctx.provider_id()))
+            .await?;
+        let positions = entries
+            .into_iter()
+            .map(|entry| Arc::new(PositionItem::new(entry)) as Arc<dyn ClapItem>)
+            .collect();
+        Ok(Self {
+            args,
+            positions: ListItems::new(positions),
+        })
+    }
+
+    fn entry_at(&mut self, lnum: usize) -> Option<&PositionEntry> {
+        self.positions
+            .item_at::<PositionItem>(lnum)
+            .map(|item| &item.entry)
+    }
+}
+
+#[async_trait::async_trait]
+impl ClapProvider for MarksProvider {
+    async fn on_initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        if self.args.query.is_none() {
+            self.positions.process_query("", ctx)?;
+        }
+        ctx.handle_base_args(&self.args).await
+    }
+
+    async fn on_move(&mut self, ctx: &mut Context) -> Result<()> {
+        if !ctx.preview_enabled() {
+            return Ok(());
+        }
+
+        let lnum = ctx.vim.display_getcurlnum().await?;
+
+        let Some(entry) = self.entry_at(lnum).filter(|entry| !entry.path.is_empty()) else {
+            return Ok(());
+        };
+
+        let preview_target = PreviewTarget::LineInFile {
+            path: entry.path.clone().into(),
+            line_number: entry.lnum,
+            column: Some(entry.col).filter(|&col| col > 0),
+        };
+
+        ctx.preview_manager.reset_scroll();
+        ctx.update_preview(Some(preview_target)).await
+    }
+
+    async fn on_typed(&mut self, ctx: &mut Context) -> Result<()> {
+        let query = ctx.vim.input_get().await?;
+        self.positions.process_query(&query, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_item() {
+        let item = PositionItem::new(PositionEntry {
+            key: "a".into(),
+            lnum: 128,
+            col: 5,
+            path: "/project/src/main.rs".into(),
+            text: "fn main() {".into(),
+        });
+        assert_eq!(item.raw_text(), "a       128    5  fn main() {");
+        assert_eq!(
+            item.icon(Icon::Enabled(icon::IconKind::File)),
+            Some(icon_or_default(Path::new("main.rs")))
+        );
+        assert_eq!(item.icon(Icon::Null), None);
+    }
+}
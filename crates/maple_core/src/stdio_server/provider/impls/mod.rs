@@ -1,26 +1,56 @@
 mod blines;
+mod buffers;
+mod command_history;
+mod diagnostics;
 mod dumb_jump;
 pub mod filer;
 mod files;
 mod generic_provider;
+mod git_branches;
+mod git_hunks;
 mod grep;
 mod igrep;
+mod lsp_symbols;
+mod marks;
 mod recent_files;
+mod registers;
 mod tagfiles;
 
-use crate::stdio_server::provider::{ClapProvider, Context, ProviderResult};
+use crate::stdio_server::provider::{BaseArgs, ClapProvider, Context, ProviderResult};
+use clap::CommandFactory;
 
 pub async fn create_provider(ctx: &Context) -> ProviderResult<Box<dyn ClapProvider>> {
     let provider: Box<dyn ClapProvider> = match ctx.env.provider_id.as_str() {
         "blines" => Box::new(blines::BlinesProvider::new(ctx).await?),
+        "buffers" => Box::new(buffers::BuffersProvider::new(ctx).await?),
+        "command_history" | "search_history" => {
+            Box::new(command_history::CommandHistoryProvider::new(ctx).await?)
+        }
+        "diagnostics" => Box::new(diagnostics::DiagnosticsProvider::new(ctx).await?),
         "dumb_jump" => Box::new(dumb_jump::DumbJumpProvider::new(ctx).await?),
         "filer" => Box::new(filer::FilerProvider::new(ctx).await?),
         "files" => Box::new(files::FilesProvider::new(ctx).await?),
+        "git_branches" => Box::new(git_branches::GitBranchesProvider::new(ctx).await?),
+        "git_hunks" => Box::new(git_hunks::GitHunksProvider::new(ctx).await?),
         "grep" => Box::new(grep::GrepProvider::new(ctx).await?),
         "igrep" => Box::new(igrep::IgrepProvider::new(ctx).await?),
+        "jumps" | "marks" => Box::new(marks::MarksProvider::new(ctx).await?),
+        "lsp_symbols" => Box::new(lsp_symbols::LspSymbolsProvider::new(ctx).await?),
         "recent_files" => Box::new(recent_files::RecentFilesProvider::new(ctx).await?),
+        "registers" => Box::new(registers::RegistersProvider::new(ctx).await?),
         "tagfiles" => Box::new(tagfiles::TagfilesProvider::new(ctx).await?),
         _ => Box::new(generic_provider::GenericProvider::new(ctx).await?),
     };
     Ok(provider)
 }
+
+/// Returns the [`clap::Command`] describing the arguments accepted by the provider.
+pub fn provider_args_command(provider_id: &str) -> clap::Command {
+    match provider_id {
+        "diagnostics" => diagnostics::DiagnosticsArgs::command(),
+        "files" => files::FilesArgs::command(),
+        "grep" => grep::GrepArgs::command(),
+        "lsp_symbols" => lsp_symbols::LspSymbolsArgs::command(),
+        _ => BaseArgs::command(),
+    }
+}
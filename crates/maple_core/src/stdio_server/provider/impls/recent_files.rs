@@ -3,7 +3,7 @@ use crate::stdio_server::provider::hooks::CachedPreviewImpl;
 use crate::stdio_server::provider::{BaseArgs, ClapProvider, Context, ProviderResult as Result};
 use parking_lot::Mutex;
 use paths::AbsPathBuf;
-use printer::Printer;
+use printer::{PathSuffixIndex, Printer};
 use serde_json::{json, Value};
 use std::sync::Arc;
 use types::{ClapItem, MatchedItem, RankCalculator, Score};
@@ -12,6 +12,7 @@ use types::{ClapItem, MatchedItem, RankCalculator, Score};
 pub struct RecentFilesProvider {
     args: BaseArgs,
     printer: Printer,
+    unique_path_suffix: bool,
     lines: Arc<Mutex<Vec<MatchedItem>>>,
 }
 
@@ -23,10 +24,13 @@ impl RecentFilesProvider {
         } else {
             icon::Icon::Null
         };
-        let printer = Printer::new(ctx.env.display_winwidth, icon);
+        let display_metrics = ctx.display_metrics();
+        let printer =
+            Printer::new(display_metrics.winwidth, icon).with_layout(display_metrics.layout);
         Ok(Self {
             args,
             printer,
+            unique_path_suffix: ctx.env.unique_path_suffix,
             lines: Default::default(),
         })
     }
@@ -74,15 +78,30 @@ impl RecentFilesProvider {
         let processed = recent_files.len();
         let matched = ranked.len();
 
+        let printer = if self.unique_path_suffix {
+            let index = PathSuffixIndex::new(
+                recent_files
+                    .entries
+                    .iter()
+                    .map(|entry| entry.fpath.as_str()),
+            );
+            self.printer.clone().with_path_suffix(Some(Arc::new(index)))
+        } else {
+            self.printer.clone()
+        };
+
         drop(recent_files);
 
         // process the new preview
         let preview = match (preview_size, ranked.get(lnum - 1)) {
             (Some(size), Some(new_entry)) => {
                 let new_curline = new_entry.display_text().to_string();
-                if let Ok((lines, fname)) =
-                    crate::previewer::preview_file(new_curline, size, self.printer.line_width)
-                {
+                if let Ok((lines, fname)) = crate::previewer::preview_file(
+                    &crate::vfs::DiskFs,
+                    new_curline,
+                    size,
+                    self.printer.line_width,
+                ) {
                     Some(json!({ "lines": lines, "fname": fname }))
                 } else {
                     None
@@ -96,9 +115,8 @@ impl RecentFilesProvider {
             indices,
             truncated_map,
             icon_added,
-        } = self
-            .printer
-            .to_display_lines(ranked.iter().take(200).cloned().collect());
+            ..
+        } = printer.to_display_lines(ranked.iter().take(200).cloned().collect());
 
         let mut cwd = cwd;
         cwd.push(std::path::MAIN_SEPARATOR);
@@ -141,7 +159,7 @@ impl RecentFilesProvider {
 impl ClapProvider for RecentFilesProvider {
     async fn on_initialize(&mut self, ctx: &mut Context) -> Result<()> {
         if self.args.query.is_none() {
-            let preview_size = if ctx.env.preview_enabled {
+            let preview_size = if ctx.preview_enabled() {
                 Some(ctx.preview_size().await?)
             } else {
                 None
@@ -161,6 +179,10 @@ impl ClapProvider for RecentFilesProvider {
     }
 
     async fn on_move(&mut self, ctx: &mut Context) -> Result<()> {
+        if !ctx.preview_enabled() {
+            return Ok(());
+        }
+
         let lnum = ctx.vim.display_getcurlnum().await?;
 
         let maybe_curline = self
@@ -171,9 +193,14 @@ impl ClapProvider for RecentFilesProvider {
 
         if let Some(curline) = maybe_curline {
             let preview_height = ctx.preview_height().await?;
-            let (preview_target, preview) = CachedPreviewImpl::new(curline, preview_height, ctx)?
-                .get_preview()
-                .await?;
+            let preview_result = match CachedPreviewImpl::new(curline, preview_height, ctx) {
+                Ok(preview_impl) => preview_impl.get_preview().await,
+                Err(err) => Err(err),
+            };
+            let (preview_target, preview) = preview_result.map_err(|err| {
+                let _ = ctx.render_preview_error(&err);
+                err
+            })?;
             ctx.preview_manager.reset_scroll();
             ctx.render_preview(preview)?;
             ctx.preview_manager.set_preview_target(preview_target);
@@ -190,7 +217,7 @@ impl ClapProvider for RecentFilesProvider {
             let recent_files = self.clone();
 
             let cwd = ctx.cwd.clone();
-            let preview_size = if ctx.env.preview_enabled {
+            let preview_size = if ctx.preview_enabled() {
                 Some(ctx.preview_size().await?)
             } else {
                 None
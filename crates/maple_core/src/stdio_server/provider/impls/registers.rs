@@ -0,0 +1,182 @@
+use crate::stdio_server::provider::hooks::PreviewTarget;
+use crate::stdio_server::provider::{
+    BaseArgs, ClapProvider, Context, ListItems, ProviderResult as Result,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use types::{ClapItem, MatchResult};
+use unicode_width::UnicodeWidthChar;
+
+/// Maximum display width of the register content in the display window, the full content
+/// is available in the preview.
+const MAX_DISPLAY_WIDTH: usize = 256;
+
+/// Register returned by `clap#provider#registers#entries()`.
+#[derive(Debug, Clone, Deserialize)]
+struct Register {
+    name: char,
+    content: String,
+}
+
+/// Returns the content in a single line, the control chars are displayed in the caret
+/// notation like `:registers`, e.g., `^J` for the newline.
+fn flatten_content(content: &str) -> String {
+    let mut flattened = String::new();
+    let mut width = 0;
+    for c in content.chars() {
+        let caret = match c {
+            '\x7f' => Some('?'),
+            c if c.is_ascii_control() => Some((c as u8 ^ 0x40) as char),
+            _ => None,
+        };
+        let c_width = if caret.is_some() {
+            2
+        } else {
+            c.width().unwrap_or(0)
+        };
+        if width + c_width > MAX_DISPLAY_WIDTH {
+            flattened.push('…');
+            break;
+        }
+        width += c_width;
+        match caret {
+            Some(caret) => {
+                flattened.push('^');
+                flattened.push(caret);
+            }
+            None => flattened.push(c),
+        }
+    }
+    flattened
+}
+
+#[derive(Debug)]
+struct RegisterItem {
+    register: Register,
+    /// ` {name}: {flattened content}`.
+    output: String,
+}
+
+impl RegisterItem {
+    /// Char length of the ` {name}: ` prefix.
+    const PREFIX_LEN: usize = 4;
+
+    fn new(register: Register) -> Self {
+        let output = format!(" {}: {}", register.name, flatten_content(&register.content));
+        Self { register, output }
+    }
+}
+
+impl ClapItem for RegisterItem {
+    fn raw_text(&self) -> &str {
+        &self.output
+    }
+
+    fn match_text(&self) -> &str {
+        let prefix_len = self
+            .output
+            .chars()
+            .take(Self::PREFIX_LEN)
+            .map(char::len_utf8)
+            .sum();
+        &self.output[prefix_len..]
+    }
+
+    fn match_result_callback(&self, match_result: MatchResult) -> MatchResult {
+        let mut match_result = match_result;
+        match_result.indices.iter_mut().for_each(|x| {
+            *x += Self::PREFIX_LEN;
+        });
+        match_result
+    }
+}
+
+/// Lists the non-empty registers, which are fetched from Vim on initialization.
+#[derive(Debug)]
+pub struct RegistersProvider {
+    args: BaseArgs,
+    registers: ListItems,
+}
+
+impl RegistersProvider {
+    pub async fn new(ctx: &Context) -> Result<Self> {
+        let args = ctx.parse_provider_args().await?;
+        let registers: Vec<Register> = ctx.vim.bare_call("clap#provider#registers#entries").await?;
+        let registers = registers
+            .into_iter()
+            .map(|register| Arc::new(RegisterItem::new(register)) as Arc<dyn ClapItem>)
+            .collect();
+        Ok(Self {
+            args,
+            registers: ListItems::new(registers),
+        })
+    }
+
+    fn register_at(&mut self, lnum: usize) -> Option<&Register> {
+        self.registers
+            .item_at::<RegisterItem>(lnum)
+            .map(|item| &item.register)
+    }
+}
+
+#[async_trait::async_trait]
+impl ClapProvider for RegistersProvider {
+    async fn on_initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        if self.args.query.is_none() {
+            self.registers.process_query("", ctx)?;
+        }
+        ctx.handle_base_args(&self.args).await
+    }
+
+    async fn on_move(&mut self, ctx: &mut Context) -> Result<()> {
+        if !ctx.preview_enabled() {
+            return Ok(());
+        }
+
+        let lnum = ctx.vim.display_getcurlnum().await?;
+
+        let Some(register) = self.register_at(lnum) else {
+            return Ok(());
+        };
+
+        let lines = std::iter::once(format!("Content for register [{}]:", register.name))
+            .chain(register.content.lines().map(Into::into))
+            .collect();
+
+        ctx.preview_manager.reset_scroll();
+        ctx.update_preview(Some(PreviewTarget::Text(lines))).await
+    }
+
+    async fn on_typed(&mut self, ctx: &mut Context) -> Result<()> {
+        let query = ctx.vim.input_get().await?;
+        self.registers.process_query(&query, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_item() {
+        let item = RegisterItem::new(Register {
+            name: 'a',
+            content: "fn main() {\n\tprintln!();\n}\n".into(),
+        });
+        assert_eq!(item.raw_text(), " a: fn main() {^J^Iprintln!();^J}^J");
+        assert_eq!(item.match_text(), "fn main() {^J^Iprintln!();^J}^J");
+
+        let match_result = item.match_result_callback(MatchResult::new(0, vec![0, 1]));
+        assert_eq!(match_result.indices, [4, 5]);
+
+        let long = RegisterItem::new(Register {
+            name: '"',
+            content: "x".repeat(1000),
+        });
+        assert_eq!(long.raw_text().chars().count(), 4 + MAX_DISPLAY_WIDTH + 1);
+
+        let wide = flatten_content(&"中".repeat(1000));
+        assert_eq!(wide.chars().count(), MAX_DISPLAY_WIDTH / 2 + 1);
+        assert!(wide.ends_with('…'));
+    }
+}
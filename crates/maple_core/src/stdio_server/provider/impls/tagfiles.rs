@@ -1,6 +1,6 @@
 use crate::stdio_server::provider::hooks::initialize_provider;
 use crate::stdio_server::provider::{
-    BaseArgs, ClapProvider, Context, ProviderResult as Result, SearcherControl,
+    ActiveFilters, BaseArgs, ClapProvider, Context, ProviderResult as Result, SearcherControl,
 };
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
@@ -34,7 +34,9 @@ impl TagfilesProvider {
             let stop_signal = Arc::new(AtomicBool::new(false));
 
             let join_handle = {
-                let search_context = ctx.search_context(stop_signal.clone());
+                let mut search_context = ctx.search_context(stop_signal.clone());
+                search_context.no_matches_hints =
+                    ctx.no_matches_hints(&query, ActiveFilters::default());
                 let cwd = ctx.cwd.to_path_buf();
 
                 tokio::spawn(async move {
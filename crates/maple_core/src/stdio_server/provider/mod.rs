@@ -1,34 +1,48 @@
 mod hooks;
 mod impls;
-
-use self::hooks::{initialize_provider, CachedPreviewImpl, Preview, PreviewTarget};
+mod no_matches;
+mod preview_pool;
+pub mod registry;
+
+use self::hooks::{
+    copy_selection, initialize_provider, refresh_command_source, resolve_initial_query,
+    CachedPreviewImpl, GitFooterCache, Preview, PreviewError, PreviewPatch, PreviewTarget,
+    SyntaxTreeCache, TsHighlightsCache,
+};
+use crate::config::PreviewCacheConfig;
+use crate::pinned_items::with_pinned_section;
+use crate::process::ShellCommand;
 use crate::searcher::blines::BlinesItem;
+use crate::searcher::grep::{ExtraRoot, LineMatcher};
 use crate::searcher::SearchContext;
 use crate::stdio_server::input::{
-    InputRecorder, InternalProviderEvent, KeyEvent, KeyEventType, ProviderEvent,
+    InputRecorder, InternalProviderEvent, KeyEvent, KeyEventType, ProviderEvent, StickyQuery,
 };
 use crate::stdio_server::vim::{Vim, VimError, VimResult};
-use filter::Query;
+use crate::stdio_server::watcher;
+use crate::vfs::{OverlayFs, Vfs};
+use filter::{Query, RankedItems};
 use icon::{Icon, IconKind};
 use matcher::{Bonus, MatchScope, Matcher, MatcherBuilder};
 use once_cell::sync::OnceCell;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use paths::AbsPathBuf;
-use printer::Printer;
+use printer::{Layout, PathSuffixIndex, Printer};
 use rpc::Params;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::mpsc::UnboundedSender;
 use types::{ClapItem, MatchedItem};
 
-pub use self::impls::create_provider;
 pub use self::impls::filer::read_dir_entries;
+pub use self::impls::{create_provider, provider_args_command};
+pub use self::no_matches::{ActiveFilters, NoMatchesHints};
 
 #[derive(Debug, thiserror::Error)]
 pub enum ProviderError {
@@ -40,9 +54,13 @@ pub enum ProviderError {
     ExceedingMaxLines(usize, usize),
     #[error("failed to convert {0} to absolute path")]
     ConvertToAbsolutePath(String),
+    #[error("invalid arguments for provider {provider_id}: {reason}")]
+    InvalidProviderArgs { provider_id: String, reason: String },
     #[error("{0}")]
     Other(String),
     #[error(transparent)]
+    Preview(#[from] PreviewError),
+    #[error(transparent)]
     Vim(#[from] VimError),
     #[error(transparent)]
     Join(#[from] tokio::task::JoinError),
@@ -108,6 +126,9 @@ impl PreviewDirection {
     }
 }
 
+/// Maximum number of the items sent to Vim for the display, the rest is out of sight.
+const MAX_DISPLAYED_ITEMS: usize = 200;
+
 /// Provider environment initialized at invoking the provider.
 ///
 /// Immutable once initialized.
@@ -126,11 +147,8 @@ pub struct ProviderEnvironment {
     pub preview_enabled: bool,
     pub preview_border_enabled: bool,
     pub preview_direction: PreviewDirection,
-    pub display_winwidth: usize,
-    pub display_winheight: usize,
-    /// Actual width for displaying the line content due to the sign column is included in
-    /// winwidth.
-    pub display_line_width: usize,
+    /// Whether to display the file paths as their unique suffixes.
+    pub unique_path_suffix: bool,
     pub start_buffer_path: PathBuf,
 }
 
@@ -140,6 +158,46 @@ impl ProviderEnvironment {
     }
 }
 
+/// Geometry of the display window, which can change during the session, e.g., the window
+/// is resized or the font of GUI Neovim changes.
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayMetrics {
+    pub winwidth: usize,
+    pub winheight: usize,
+    /// Actual width for displaying the line content due to the sign column is included in
+    /// winwidth.
+    pub line_width: usize,
+    /// Layout of the display lines negotiated with `line_width`.
+    pub layout: Layout,
+}
+
+impl DisplayMetrics {
+    /// Derives the line width and layout from the size of display window.
+    fn new(provider_id: &str, winwidth: usize, winheight: usize) -> Self {
+        let line_width = display_line_width(provider_id, winwidth);
+        let layout = crate::config::config()
+            .provider
+            .responsive_layout
+            .layout(provider_id, line_width);
+        Self {
+            winwidth,
+            winheight,
+            line_width,
+            layout,
+        }
+    }
+}
+
+/// Returns the actual width for displaying the line content in the display window.
+fn display_line_width(provider_id: &str, display_winwidth: usize) -> usize {
+    // Sign column occupies 2 spaces.
+    let display_line_width = display_winwidth.saturating_sub(2);
+    match provider_id {
+        "grep" => display_line_width.saturating_sub(2),
+        _ => display_line_width,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Direction {
     Down,
@@ -153,60 +211,320 @@ struct ScrollFile {
 }
 
 impl ScrollFile {
-    fn new(line_start: usize, path: &Path) -> std::io::Result<Self> {
+    fn new(line_start: usize, path: &Path, vfs: &dyn Vfs) -> std::io::Result<Self> {
         Ok(Self {
             line_start,
-            total_lines: utils::count_lines(std::fs::File::open(path)?)?,
+            total_lines: vfs.count_lines(path)?,
         })
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
+struct CachedPreview {
+    preview: Preview,
+    inserted_at: Instant,
+    /// Modification time of the previewed file on insertion, `None` if the target has
+    /// no path or the file is unavailable.
+    mtime: Option<SystemTime>,
+    last_used: u64,
+}
+
+/// Previews generated before, the least recently used one is evicted once full.
+///
+/// A cached preview is stale once it's expired or the file has been modified since.
+#[derive(Debug, Default)]
+struct PreviewCache {
+    entries: HashMap<PreviewTarget, CachedPreview>,
+    /// Bumped on each access, the entry of the smallest tick is the least recently used.
+    tick: u64,
+}
+
+impl PreviewCache {
+    fn target_mtime(preview_target: &PreviewTarget) -> Option<SystemTime> {
+        let path = preview_target.path()?;
+        std::fs::metadata(path).ok()?.modified().ok()
+    }
+
+    fn get(&mut self, preview_target: &PreviewTarget, ttl: Duration) -> Option<Preview> {
+        let cached = self.entries.get_mut(preview_target)?;
+
+        if cached.inserted_at.elapsed() > ttl || cached.mtime != Self::target_mtime(preview_target)
+        {
+            self.entries.remove(preview_target);
+            return None;
+        }
+
+        self.tick += 1;
+        cached.last_used = self.tick;
+
+        Some(cached.preview.clone())
+    }
+
+    fn insert(&mut self, preview_target: PreviewTarget, preview: Preview, max_entries: usize) {
+        if !self.entries.contains_key(&preview_target) && self.entries.len() >= max_entries {
+            let least_recently_used = self
+                .entries
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(target, _)| target.clone());
+            if let Some(target) = least_recently_used {
+                self.entries.remove(&target);
+            }
+        }
+
+        self.tick += 1;
+        let mtime = Self::target_mtime(&preview_target);
+        self.entries.insert(
+            preview_target,
+            CachedPreview {
+                preview,
+                inserted_at: Instant::now(),
+                mtime,
+                last_used: self.tick,
+            },
+        );
+    }
+
+    /// Drops the previews of `changed_paths` and of the directories containing them,
+    /// returns `true` if any preview is dropped.
+    fn invalidate(&mut self, changed_paths: &[PathBuf]) -> bool {
+        let total = self.entries.len();
+        self.entries
+            .retain(|target, _| !is_affected_by(target, changed_paths));
+        self.entries.len() != total
+    }
+
+    /// Updates the cached preview of `preview_target` in place, returns `false` if it's
+    /// not cached.
+    fn update(&mut self, preview_target: &PreviewTarget, f: impl FnOnce(&mut Preview)) -> bool {
+        match self.entries.get_mut(preview_target) {
+            Some(cached) => {
+                f(&mut cached.preview);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Returns `true` if the preview of `preview_target` is outdated by the changes of `changed_paths`.
+///
+/// The paths are compared in the case folded form as the cache keys on the case-insensitive
+/// platforms.
+fn is_affected_by(preview_target: &PreviewTarget, changed_paths: &[PathBuf]) -> bool {
+    let Some(path) = preview_target.path() else {
+        return false;
+    };
+    let path = paths::path_buf_case_key(path);
+    changed_paths.iter().any(|changed| {
+        let changed = paths::path_buf_case_key(changed);
+        changed == path || changed.parent() == Some(&*path)
+    })
+}
+
+/// Preview of a line in file last sent to Vim.
+#[derive(Debug)]
+struct RenderedPreview {
+    path: PathBuf,
+    line_number: usize,
+    lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct PreviewManager {
     scroll_file: Option<ScrollFile>,
     scroll_offset: i32,
     current_preview_target: Option<PreviewTarget>,
-    preview_cache: Arc<RwLock<HashMap<PreviewTarget, Preview>>>,
+    /// Target of the preview scrolled from the current item, `None` if not scrolled.
+    scrolled_preview_target: Option<PreviewTarget>,
+    preview_cache: Arc<Mutex<PreviewCache>>,
+    rendered_preview: Arc<Mutex<Option<RenderedPreview>>>,
+    /// Bumped to cancel the outdated prefetching tasks.
+    prefetch_generation: Arc<AtomicUsize>,
+    /// Cursor line of the latest preview, the previews pending for another line are stale.
+    cursor_line: Arc<Mutex<Option<String>>>,
+    ts_highlights_cache: TsHighlightsCache,
+    syntax_tree_cache: SyntaxTreeCache,
+    git_footer_cache: GitFooterCache,
+    /// Matcher of the latest grep query, used to highlight the whole match in the preview.
+    line_matcher: Arc<Mutex<Option<Arc<LineMatcher>>>>,
 }
 
 impl PreviewManager {
     const SCROLL_SIZE: i32 = 10;
 
     pub fn new() -> Self {
-        Self {
-            scroll_file: None,
-            scroll_offset: 0,
-            current_preview_target: None,
-            preview_cache: Arc::new(RwLock::new(HashMap::new())),
-        }
+        Self::default()
+    }
+
+    /// Sets the matcher of the new query, the previews highlighting the match of the
+    /// previous query are dropped.
+    pub fn set_line_matcher(&self, line_matcher: LineMatcher) {
+        self.line_matcher.lock().replace(Arc::new(line_matcher));
+        self.preview_cache.lock().entries.retain(|target, _| {
+            !matches!(
+                target,
+                PreviewTarget::LineInFile {
+                    column: Some(_),
+                    ..
+                }
+            )
+        });
+    }
+
+    fn line_matcher(&self) -> Option<Arc<LineMatcher>> {
+        self.line_matcher.lock().clone()
     }
 
     pub fn cached_preview(&self, preview_target: &PreviewTarget) -> Option<Preview> {
-        let preview_cache = self.preview_cache.read();
+        let ttl = crate::config::try_config()
+            .map(|config| config.provider.preview_cache.ttl())
+            .unwrap_or_else(|| PreviewCacheConfig::default().ttl());
         // TODO: not clone?
-        preview_cache.get(preview_target).cloned()
+        self.preview_cache
+            .lock()
+            .get(&preview_target.cache_key(), ttl)
     }
 
     pub fn insert_preview(&self, preview_target: PreviewTarget, preview: Preview) {
-        let mut preview_cache = self.preview_cache.write();
-        preview_cache.insert(preview_target, preview);
+        let max_entries = crate::config::try_config()
+            .map(|config| config.provider.preview_cache.max_entries())
+            .unwrap_or_else(|| PreviewCacheConfig::default().max_entries());
+        self.preview_cache
+            .lock()
+            .insert(preview_target.cache_key(), preview, max_entries);
+    }
+
+    /// Updates the cached preview of `preview_target`, e.g., with the footer computed in
+    /// the background. Returns `false` if the preview is no longer cached.
+    pub fn update_cached_preview(
+        &self,
+        preview_target: &PreviewTarget,
+        f: impl FnOnce(&mut Preview),
+    ) -> bool {
+        self.preview_cache
+            .lock()
+            .update(&preview_target.cache_key(), f)
+    }
+
+    /// Drops the cached previews outdated by the changes of `changed_paths` on the disk.
+    ///
+    /// Returns `true` if the current preview is outdated.
+    pub fn invalidate_previews(&self, changed_paths: &[PathBuf]) -> bool {
+        self.preview_cache.lock().invalidate(changed_paths);
+
+        let current_outdated = self
+            .current_preview_target
+            .as_ref()
+            .map_or(false, |target| is_affected_by(target, changed_paths));
+
+        if current_outdated {
+            self.reset_rendered();
+        }
+
+        current_outdated
+    }
+
+    /// Drops the cached previews, which are truncated to the outdated window width.
+    pub fn clear_preview_cache(&self) {
+        self.preview_cache.lock().clear();
+        self.reset_rendered();
+    }
+
+    /// Returns the preview to be sent to Vim, which contains only the changed lines
+    /// if the last rendered preview is for a nearby line in the same file.
+    ///
+    /// Rendering the same target again always sends the full preview, which is used
+    /// by Vim to recover from an inconsistent preview buffer.
+    fn diff_with_rendered(&self, preview_target: &PreviewTarget, preview: Preview) -> Preview {
+        let mut rendered_preview = self.rendered_preview.lock();
+
+        let PreviewTarget::LineInFile {
+            path, line_number, ..
+        } = preview_target
+        else {
+            rendered_preview.take();
+            return preview;
+        };
+
+        let maybe_patch = rendered_preview
+            .as_ref()
+            // The syntax regions are bound to the line numbers of the full preview.
+            .filter(|_| preview.syntax_regions.is_empty())
+            .filter(|rendered| {
+                rendered.path == *path
+                    && rendered.line_number != *line_number
+                    && rendered.line_number.abs_diff(*line_number) < preview.lines.len()
+            })
+            .and_then(|rendered| PreviewPatch::new(&rendered.lines, &preview.lines));
+
+        rendered_preview.replace(RenderedPreview {
+            path: path.clone(),
+            line_number: *line_number,
+            lines: preview.lines.clone(),
+        });
+
+        match maybe_patch {
+            Some(patch) => preview.into_patched(patch),
+            None => preview,
+        }
+    }
+
+    /// Cancels the running prefetching tasks, returns the generation of the new ones.
+    pub fn cancel_prefetch(&self) -> usize {
+        self.prefetch_generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    fn is_prefetch_outdated(&self, generation: usize) -> bool {
+        self.prefetch_generation.load(Ordering::SeqCst) != generation
+    }
+
+    fn set_cursor_line(&self, cursor_line: Option<String>) {
+        *self.cursor_line.lock() = cursor_line;
+    }
+
+    /// Returns the closure telling whether the cursor has moved away from the current line.
+    pub fn staleness_checker(&self) -> preview_pool::IsStale {
+        let cursor_line = self.cursor_line.clone();
+        let current = cursor_line.lock().clone();
+        Arc::new(move || *cursor_line.lock() != current)
+    }
+
+    fn reset_rendered(&self) {
+        self.rendered_preview.lock().take();
     }
 
     fn reset_scroll(&mut self) {
         self.scroll_file.take();
         self.scroll_offset = 0;
         self.current_preview_target.take();
+        self.scrolled_preview_target.take();
+    }
+
+    /// Returns the file position of the scrolled preview.
+    fn scrolled_position(&self) -> Option<(&Path, usize)> {
+        match self.scrolled_preview_target.as_ref()? {
+            PreviewTarget::LineInFile {
+                path, line_number, ..
+            } => Some((path, *line_number)),
+            _ => None,
+        }
     }
 
     fn prepare_scroll_file_info(
         &mut self,
         line_start: usize,
         path: PathBuf,
+        vfs: &dyn Vfs,
     ) -> std::io::Result<(ScrollFile, PathBuf)> {
         let scroll_file = match self.scroll_file {
             Some(scroll_file) => scroll_file,
             None => {
-                let scroll_file = ScrollFile::new(line_start, &path)?;
+                let scroll_file = ScrollFile::new(line_start, &path, vfs)?;
                 self.scroll_file.replace(scroll_file);
                 scroll_file
             }
@@ -218,21 +536,19 @@ impl PreviewManager {
         self.current_preview_target.replace(preview_target);
     }
 
-    fn scroll_preview(&mut self, direction: Direction) -> ProviderResult<PreviewTarget> {
-        let new_scroll_offset = match direction {
-            Direction::Up => self.scroll_offset - 1,
-            Direction::Down => self.scroll_offset + 1,
-        };
+    /// Scrolls the preview of the current file by `lines`, negative to scroll up.
+    fn scroll_preview(&mut self, lines: i32, vfs: &dyn Vfs) -> ProviderResult<PreviewTarget> {
+        let new_scroll_offset = self.scroll_offset + lines;
 
         let (scroll_file, path) = match self
             .current_preview_target
             .as_ref()
             .ok_or(ProviderError::PreviewTargetNotFound)?
         {
-            PreviewTarget::LineInFile { path, line_number } => {
-                self.prepare_scroll_file_info(*line_number, path.clone())?
-            }
-            PreviewTarget::File(path) => self.prepare_scroll_file_info(0, path.clone())?,
+            PreviewTarget::LineInFile {
+                path, line_number, ..
+            } => self.prepare_scroll_file_info(*line_number, path.clone(), vfs)?,
+            PreviewTarget::File(path) => self.prepare_scroll_file_info(0, path.clone(), vfs)?,
             _ => return Err(ProviderError::OnlyFilePreviewScrollSupported),
         };
 
@@ -241,16 +557,23 @@ impl PreviewManager {
             total_lines,
         } = scroll_file;
 
-        let new_line_number = line_start as i32 + new_scroll_offset * Self::SCROLL_SIZE;
+        let new_line_number = line_start as i32 + new_scroll_offset;
 
         let new_line_number = if new_line_number < 0 {
             // Reaching the start of file.
+            self.scroll_offset = -(line_start as i32);
             0
         } else if new_line_number as usize > total_lines {
-            return Err(ProviderError::ExceedingMaxLines(
-                new_line_number as usize,
-                total_lines,
-            ));
+            let last_line_offset = total_lines as i32 - line_start as i32;
+            // Scrolling by more lines than left stops at the end of file.
+            if self.scroll_offset >= last_line_offset {
+                return Err(ProviderError::ExceedingMaxLines(
+                    new_line_number as usize,
+                    total_lines,
+                ));
+            }
+            self.scroll_offset = last_line_offset;
+            total_lines as i32
         } else {
             self.scroll_offset = new_scroll_offset;
             new_line_number
@@ -259,8 +582,11 @@ impl PreviewManager {
         let new_target = PreviewTarget::LineInFile {
             path,
             line_number: new_line_number as usize,
+            column: None,
         };
 
+        self.scrolled_preview_target.replace(new_target.clone());
+
         Ok(new_target)
     }
 }
@@ -270,13 +596,37 @@ pub struct Context {
     pub cwd: AbsPathBuf,
     pub vim: Vim,
     pub env: Arc<ProviderEnvironment>,
-    pub maybe_preview_size: Option<usize>,
+    /// Shared by the clones of the context like [`Self::display_metrics`].
+    maybe_preview_size: Arc<Mutex<Option<usize>>>,
     pub initializing_prompt_echoed: Arc<AtomicBool>,
     pub terminated: Arc<AtomicBool>,
+    /// Whether the preview is disabled in this session, toggled by the user at runtime.
+    pub preview_disabled: Arc<AtomicBool>,
     pub input_recorder: InputRecorder,
     pub preview_manager: PreviewManager,
     pub provider_source: Arc<RwLock<ProviderSource>>,
+    /// Shared by the clones of the context so that the spawned tasks see the latest one.
+    display_metrics: Arc<RwLock<DisplayMetrics>>,
     provider_event_sender: OnceCell<UnboundedSender<ProviderEvent>>,
+    /// Cursor position to restore once the sticky query has been handled.
+    pending_sticky_lnum: Arc<Mutex<Option<usize>>>,
+    /// Suffix index of the paths in the provider source, built lazily.
+    path_suffix: Arc<Mutex<Option<Arc<PathSuffixIndex>>>>,
+    /// Filesystem the previews are read from.
+    pub vfs: OverlayFs,
+    /// Changedtick of the start buffer when its lines were loaded into [`Self::vfs`].
+    start_buffer_changedtick: Arc<Mutex<Option<usize>>>,
+    /// Changedtick of the other buffers when their lines were loaded into [`Self::vfs`].
+    buffer_changedticks: Arc<Mutex<HashMap<PathBuf, usize>>>,
+    /// Shell command of the [`ProviderSource::CachedFile`], rerun once the files are changed.
+    ///
+    /// Taken while rerunning so that the refreshes don't pile up.
+    source_command: Arc<Mutex<Option<ShellCommand>>>,
+    /// Subscription to the changes of the files in [`Self::cwd`].
+    files_subscription: Arc<Mutex<Option<watcher::Subscription>>>,
+    /// Search roots of grep apart from [`Self::cwd`], to resolve the paths displayed with
+    /// the label of root.
+    extra_search_roots: Arc<RwLock<Vec<ExtraRoot>>>,
 }
 
 impl Context {
@@ -332,12 +682,18 @@ impl Context {
         };
         let popup_border: String = vim.eval("g:clap_popup_border").await?;
 
-        // Sign column occupies 2 spaces.
-        let display_line_width = display_winwidth - 2;
-        let display_line_width = match provider_id.as_str() {
-            "grep" => display_line_width - 2,
-            _ => display_line_width,
+        let display_metrics =
+            DisplayMetrics::new(provider_id.as_str(), display_winwidth, display_winheight);
+        let icon = if display_metrics.layout.hide_icon() {
+            Icon::Null
+        } else {
+            icon
         };
+        let unique_path_suffix = crate::config::config()
+            .provider
+            .unique_path_suffix
+            .iter()
+            .any(|id| id == provider_id.as_str());
 
         let env = ProviderEnvironment {
             is_nvim: is_nvim == 1,
@@ -352,13 +708,17 @@ impl Context {
             preview_border_enabled: popup_border != "nil",
             preview_direction,
             start_buffer_path,
-            display_winwidth,
-            display_winheight,
-            display_line_width,
+            unique_path_suffix,
             matcher_builder,
             icon,
         };
 
+        let preview_disabled = crate::config::config()
+            .provider
+            .preview_disabled(env.provider_id.as_str())
+            || registry::get(env.provider_id.as_str())
+                .map_or(false, |spec| spec.preview == registry::PreviewSpec::None);
+
         let input_history = crate::datastore::INPUT_HISTORY_IN_MEMORY.lock();
         let inputs = if crate::config::config().provider.share_input_history {
             input_history.all_inputs()
@@ -371,13 +731,23 @@ impl Context {
             cwd,
             vim,
             env: Arc::new(env),
-            maybe_preview_size: None,
+            display_metrics: Arc::new(RwLock::new(display_metrics)),
+            maybe_preview_size: Arc::new(Mutex::new(None)),
             initializing_prompt_echoed: Arc::new(AtomicBool::new(false)),
             terminated: Arc::new(AtomicBool::new(false)),
+            preview_disabled: Arc::new(AtomicBool::new(preview_disabled)),
             input_recorder,
             preview_manager: PreviewManager::new(),
             provider_source: Arc::new(RwLock::new(ProviderSource::Uninitialized)),
             provider_event_sender: OnceCell::new(),
+            pending_sticky_lnum: Arc::new(Mutex::new(None)),
+            path_suffix: Arc::new(Mutex::new(None)),
+            vfs: OverlayFs::default(),
+            start_buffer_changedtick: Arc::new(Mutex::new(None)),
+            buffer_changedticks: Arc::new(Mutex::new(HashMap::new())),
+            source_command: Arc::new(Mutex::new(None)),
+            files_subscription: Arc::new(Mutex::new(None)),
+            extra_search_roots: Arc::new(RwLock::new(Vec::new())),
         })
     }
 
@@ -399,6 +769,29 @@ impl Context {
         self.env.provider_id.as_str()
     }
 
+    /// Returns `true` if the preview should be generated on moving the cursor.
+    pub fn preview_enabled(&self) -> bool {
+        self.env.preview_enabled && !self.preview_disabled.load(Ordering::SeqCst)
+    }
+
+    /// Disables or re-enables the preview for the rest of this session.
+    pub fn toggle_preview(&self) -> ProviderResult<()> {
+        if !self.env.preview_enabled {
+            return Ok(());
+        }
+
+        let was_disabled = self.preview_disabled.fetch_xor(true, Ordering::SeqCst);
+
+        if was_disabled {
+            self.send_provider_event(ProviderEvent::OnMove(Params::None))?;
+        } else {
+            self.preview_manager.reset_rendered();
+            self.vim.bare_exec("clap#state#hide_preview")?;
+        }
+
+        Ok(())
+    }
+
     pub fn provider_debounce(&self) -> u64 {
         crate::config::config().provider_debounce(self.env.provider_id.as_str())
     }
@@ -411,24 +804,88 @@ impl Context {
         self.env.matcher_builder.clone().build(query.into())
     }
 
+    /// Returns the current geometry of the display window.
+    pub fn display_metrics(&self) -> DisplayMetrics {
+        *self.display_metrics.read()
+    }
+
     /// Constructs a [`SearchContext`] for the searching worker.
     pub fn search_context(&self, stop_signal: Arc<AtomicBool>) -> SearchContext {
+        let display_metrics = self.display_metrics();
         SearchContext {
             icon: self.env.icon,
-            line_width: self.env.display_line_width,
+            line_width: display_metrics.line_width,
             paths: vec![self.cwd.to_path_buf()],
             vim: self.vim.clone(),
             stop_signal,
-            item_pool_size: self.env.display_winheight,
+            item_pool_size: display_metrics.winheight,
+            layout: display_metrics.layout,
+            unique_path_suffix: self.env.unique_path_suffix,
+            no_matches_hints: NoMatchesHints::default(),
         }
     }
 
+    /// Returns the hints displayed if nothing is matched for `query`.
+    pub fn no_matches_hints(&self, query: &str, filters: ActiveFilters) -> NoMatchesHints {
+        NoMatchesHints::new(
+            self.provider_id(),
+            query,
+            filters,
+            vec![self.cwd.to_path_buf()],
+        )
+    }
+
     pub fn set_provider_event_sender(&self, provider_event_sender: UnboundedSender<ProviderEvent>) {
         self.provider_event_sender
             .set(provider_event_sender)
             .expect("Failed to initialize provider_event_sender in Context")
     }
 
+    /// Starts watching the files in [`Self::cwd`], [`InternalProviderEvent::FilesChanged`]
+    /// will be sent on the changes.
+    pub fn watch_files(&self) {
+        let Some(sender) = self.provider_event_sender.get().cloned() else {
+            return;
+        };
+
+        // The subscription ends once the provider has exited.
+        let on_change: watcher::OnSubscribedChange =
+            Box::new(move |changes: Vec<watcher::FileChange>| {
+                sender
+                    .send(ProviderEvent::Internal(
+                        InternalProviderEvent::FilesChanged(changes),
+                    ))
+                    .is_ok()
+            });
+
+        if let Some(subscription) = watcher::subscribe(&self.cwd, on_change) {
+            self.files_subscription.lock().replace(subscription);
+        }
+    }
+
+    /// Purges the previews outdated by `changes` and refreshes the cached source if any file
+    /// is created, removed or renamed.
+    ///
+    /// Returns `true` if the current preview is outdated.
+    pub fn on_files_changed(&self, changes: &[watcher::FileChange]) -> bool {
+        tracing::debug!(?changes, "Files changed on the disk");
+
+        if changes.iter().any(|change| change.structural)
+            && matches!(
+                *self.provider_source.read(),
+                ProviderSource::CachedFile { .. }
+            )
+        {
+            refresh_command_source(self);
+        }
+
+        let changed_paths = changes
+            .iter()
+            .map(|change| change.path.clone())
+            .collect::<Vec<_>>();
+        self.preview_manager.invalidate_previews(&changed_paths)
+    }
+
     pub fn send_provider_event(&self, event: ProviderEvent) -> ProviderResult<()> {
         self.provider_event_sender
             .get()
@@ -437,12 +894,7 @@ impl Context {
             .map_err(Into::into)
     }
 
-    /// Executes the command `cmd` and returns the raw bytes of stdout.
-    pub fn exec_cmd(&self, cmd: &str) -> std::io::Result<Vec<u8>> {
-        let out = utils::execute_at(cmd, Some(&self.cwd))?;
-        Ok(out.stdout)
-    }
-
+    /// Returns the extension of the start buffer.
     pub fn start_buffer_extension(&self) -> std::io::Result<&str> {
         self.env
             .start_buffer_path
@@ -459,32 +911,55 @@ impl Context {
             })
     }
 
-    pub async fn parse_provider_args<T: clap::Parser + Default + Debug>(&self) -> VimResult<T> {
+    /// Parses the provider arguments, the default arguments in the config are prepended
+    /// so that they can be overridden by the ones passed to `:Clap`.
+    ///
+    /// The invalid arguments are shown in Vim and fail the provider instead of being
+    /// silently dropped.
+    pub async fn parse_provider_args<T: clap::Parser + Default>(&self) -> ProviderResult<T> {
         let args = self.vim.provider_args().await?;
 
-        let provider_args = if args.is_empty() {
-            T::default()
-        } else {
-            T::try_parse_from(std::iter::once(String::from("")).chain(args.into_iter()))
-                .map_err(|err| {
-                    match err.kind() {
-                        clap::error::ErrorKind::DisplayHelp => {
-                            // Show help in the display window.
-                            let err_msg = err.to_string();
-                            let lines = err_msg.split('\n').collect::<Vec<_>>();
-                            let _ = self.vim.exec("display_set_lines", [lines]);
-                        }
-                        _ => {
-                            let _ = self.vim.echo_warn(format!(
-                                "using default {:?} due to {err}",
-                                T::default()
-                            ));
-                        }
-                    }
+        let args = crate::config::config()
+            .provider
+            .default_args
+            .get(self.provider_id())
+            .into_iter()
+            .flatten()
+            .cloned()
+            .chain(split_provider_args(&args))
+            .collect::<Vec<_>>();
+
+        if args.is_empty() {
+            return Ok(T::default());
+        }
+
+        match try_parse_provider_args(args) {
+            Ok(provider_args) => Ok(provider_args),
+            Err(err) if err.kind() == clap::error::ErrorKind::DisplayHelp => {
+                // Show help in the display window.
+                let err_msg = err.to_string();
+                let lines = err_msg.split('\n').collect::<Vec<_>>();
+                self.vim.exec("display_set_lines", [lines])?;
+                Ok(T::default())
+            }
+            Err(err) => {
+                let err_msg = err.to_string();
+                let reason = err_msg
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .trim_start_matches("error: ")
+                    .to_string();
+                self.vim.echo_warn(format!(
+                    "Invalid arguments for provider {}: {reason}",
+                    self.provider_id()
+                ))?;
+                Err(ProviderError::InvalidProviderArgs {
+                    provider_id: self.provider_id().to_string(),
+                    reason,
                 })
-                .unwrap_or_default()
-        };
-        Ok(provider_args)
+            }
+        }
     }
 
     pub async fn handle_base_args(&self, base: &BaseArgs) -> ProviderResult<()> {
@@ -494,8 +969,81 @@ impl Context {
             self.send_provider_event(ProviderEvent::Internal(
                 InternalProviderEvent::InitialQuery(query.clone()),
             ))?;
+        } else if let Some(sticky_query) = self.sticky_query() {
+            *self.pending_sticky_lnum.lock() = Some(sticky_query.lnum);
+            self.send_provider_event(ProviderEvent::Internal(
+                InternalProviderEvent::InitialQuery(sticky_query.query),
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    fn sticky_query_enabled(&self) -> bool {
+        crate::config::config()
+            .provider
+            .sticky_query
+            .iter()
+            .any(|provider_id| provider_id == self.provider_id())
+    }
+
+    /// Returns the last query of this provider in the current project.
+    fn sticky_query(&self) -> Option<StickyQuery> {
+        if !self.sticky_query_enabled() {
+            return None;
+        }
+        crate::datastore::STICKY_QUERIES_IN_MEMORY
+            .lock()
+            .get(&self.env.provider_id, &self.cwd)
+            .cloned()
+    }
+
+    /// Remembers the final query and cursor position on exit if `provider.sticky-query`
+    /// is enabled for this provider.
+    pub fn remember_sticky_query(&self, params: Params) {
+        #[derive(Deserialize)]
+        struct ExitParams {
+            query: String,
+            lnum: usize,
+        }
+
+        if !self.sticky_query_enabled() {
+            return;
+        }
+
+        let Ok(ExitParams { query, lnum }) = params.parse() else {
+            return;
         };
 
+        let mut sticky_queries = crate::datastore::STICKY_QUERIES_IN_MEMORY.lock();
+
+        if query.is_empty() {
+            sticky_queries.remove(&self.env.provider_id, &self.cwd);
+        } else {
+            sticky_queries.insert(
+                self.env.provider_id.clone(),
+                StickyQuery {
+                    project: self.cwd.to_path_buf(),
+                    query,
+                    lnum,
+                },
+            );
+        }
+
+        if let Err(err) = crate::datastore::store_sticky_queries(&sticky_queries) {
+            tracing::error!(?err, "Failed to store the sticky queries");
+        }
+    }
+
+    /// Moves the cursor in the display window to where it was when the sticky query
+    /// was remembered.
+    ///
+    /// This is best-effort for the async providers as the results may be incomplete.
+    pub async fn restore_sticky_lnum(&self) -> VimResult<()> {
+        let maybe_lnum = self.pending_sticky_lnum.lock().take();
+        if let Some(lnum) = maybe_lnum.filter(|lnum| *lnum > 1) {
+            self.vim.exec("display_set_curlnum", [lnum])?;
+        }
         Ok(())
     }
 
@@ -509,9 +1057,66 @@ impl Context {
         Ok(expanded_paths)
     }
 
+    pub fn set_extra_search_roots(&self, extra_search_roots: Vec<ExtraRoot>) {
+        *self.extra_search_roots.write() = extra_search_roots;
+    }
+
+    /// Returns the absolute path of `fpath` in the grep results.
+    pub fn resolve_grep_path(&self, fpath: &str) -> PathBuf {
+        crate::searcher::grep::resolve_display_path(
+            fpath,
+            &self.cwd,
+            &self.extra_search_roots.read(),
+        )
+    }
+
     pub fn set_provider_source(&self, new: ProviderSource) {
         let mut provider_source = self.provider_source.write();
         *provider_source = new;
+        self.path_suffix.lock().take();
+    }
+
+    /// Returns the suffix index of all the paths in the provider source if the unique
+    /// path suffixes are displayed.
+    fn path_suffix_index(&self) -> Option<Arc<PathSuffixIndex>> {
+        if !self.env.unique_path_suffix {
+            return None;
+        }
+
+        let mut path_suffix = self.path_suffix.lock();
+        if path_suffix.is_none() {
+            let index = match &*self.provider_source.read() {
+                ProviderSource::Small { items, .. } => {
+                    PathSuffixIndex::new(items.iter().map(|item| item.raw_text()))
+                }
+                ProviderSource::File { path, .. } => {
+                    let mut index = PathSuffixIndex::default();
+                    utils::read_lines(path)
+                        .ok()?
+                        .map_while(Result::ok)
+                        .for_each(|line| index.insert(&line));
+                    index
+                }
+                ProviderSource::CachedFile { path, .. } => {
+                    let lines = filter::mmap_lines(path).ok()?;
+                    let mut index = PathSuffixIndex::default();
+                    (0..lines.len()).for_each(|idx| index.insert(&lines.line_string(idx)));
+                    index
+                }
+                _ => return None,
+            };
+            path_suffix.replace(Arc::new(index));
+        }
+
+        path_suffix.clone()
+    }
+
+    /// Constructs a [`Printer`] for the display window.
+    pub fn printer(&self) -> Printer {
+        let display_metrics = self.display_metrics();
+        Printer::new(display_metrics.winwidth, self.env.icon)
+            .with_layout(display_metrics.layout)
+            .with_path_suffix(self.path_suffix_index())
     }
 
     /// Returns a smaller delay for the input debounce if the source is not large.
@@ -530,10 +1135,14 @@ impl Context {
 
     pub fn signify_terminated(&self, session_id: u64) {
         self.terminated.store(true, Ordering::SeqCst);
+        self.files_subscription.lock().take();
         let provider_id = self.env.provider_id.clone();
         tracing::debug!("ProviderSession {session_id:?}-{provider_id} terminated");
         let mut input_history = crate::datastore::INPUT_HISTORY_IN_MEMORY.lock();
         input_history.update_inputs(provider_id, self.input_recorder.clone().into_inputs());
+        if let Err(err) = crate::datastore::store_input_history(&input_history) {
+            tracing::error!(?err, "Failed to store the input history");
+        }
     }
 
     pub async fn record_input(&mut self) -> VimResult<()> {
@@ -568,8 +1177,9 @@ impl Context {
         Ok(())
     }
 
-    pub async fn preview_size(&mut self) -> VimResult<usize> {
-        match self.maybe_preview_size {
+    pub async fn preview_size(&self) -> VimResult<usize> {
+        let maybe_preview_size = *self.maybe_preview_size.lock();
+        match maybe_preview_size {
             Some(size) => Ok(size),
             None => {
                 let preview_winid = self.vim.eval("g:clap.preview.winid").await?;
@@ -577,12 +1187,71 @@ impl Context {
                     .vim
                     .preview_size(&self.env.provider_id, preview_winid)
                     .await?;
-                self.maybe_preview_size.replace(size);
+                self.maybe_preview_size.lock().replace(size);
                 Ok(size)
             }
         }
     }
 
+    /// Re-derives the display metrics from the actual window geometry, which may change
+    /// during the session, e.g., the window is resized or the font of GUI Neovim changes.
+    ///
+    /// The items and preview are re-rendered with the new truncation widths, even if the
+    /// window size is unchanged when `force` is set in `params`, e.g., the width of the
+    /// characters changes with `guifont` or `ambiwidth`.
+    pub async fn update_display_metrics(&self, params: Params) -> ProviderResult<()> {
+        #[derive(Deserialize)]
+        struct DisplayMetricsParams {
+            #[serde(default)]
+            force: bool,
+        }
+
+        let DisplayMetricsParams { force } = params
+            .parse()
+            .map_err(|err| ProviderError::Other(err.to_string()))?;
+
+        let display_winwidth = self.vim.winwidth(self.env.display.winid).await?;
+        let display_winheight = self.vim.winheight(self.env.display.winid).await?;
+
+        let current = self.display_metrics();
+        if !force && display_winwidth == current.winwidth && display_winheight == current.winheight
+        {
+            return Ok(());
+        }
+
+        tracing::debug!(
+            display_winwidth,
+            display_winheight,
+            force,
+            "Display metrics changed"
+        );
+
+        *self.display_metrics.write() = DisplayMetrics::new(
+            self.env.provider_id.as_str(),
+            display_winwidth,
+            display_winheight,
+        );
+        self.maybe_preview_size.lock().take();
+        self.preview_manager.clear_preview_cache();
+
+        self.refresh_display().await?;
+        if self.preview_enabled() {
+            self.send_provider_event(ProviderEvent::OnMove(Params::None))?;
+        }
+
+        Ok(())
+    }
+
+    /// Re-renders the items of the current query in the display window.
+    pub async fn refresh_display(&self) -> ProviderResult<()> {
+        if self.vim.input_get().await?.is_empty() {
+            self.update_on_empty_query().await?;
+        } else {
+            self.send_provider_event(ProviderEvent::OnTyped(Params::None))?;
+        }
+        Ok(())
+    }
+
     pub async fn preview_winwidth(&self) -> VimResult<usize> {
         let preview_winid = self.vim.eval("g:clap.preview.winid").await?;
         let winwidth = self.vim.winwidth(preview_winid).await?;
@@ -593,10 +1262,104 @@ impl Context {
         self.preview_size().await.map(|x| 2 * x)
     }
 
+    /// Copies the structured data about the current item to the register.
+    pub async fn copy_selection(&self, params: Params) -> ProviderResult<()> {
+        copy_selection(self, params).await
+    }
+
+    /// Loads the content of the buffer for `path` into [`Self::vfs`] if the buffer in
+    /// Vim is modified or not backed by a file, so that the preview matches the buffer.
+    ///
+    /// The lines are fetched again only if the buffer has been changed since the last sync.
+    pub async fn sync_buffer_content(&self, path: &Path) -> VimResult<()> {
+        let maybe_buffer: Option<(usize, usize)> =
+            self.vim.call("clap#api#buffer_changedtick", [path]).await?;
+
+        let Some((bufnr, changedtick)) = maybe_buffer else {
+            self.vfs.buffers().remove(path);
+            self.buffer_changedticks.lock().remove(path);
+            return Ok(());
+        };
+
+        if self.buffer_changedticks.lock().get(path) == Some(&changedtick)
+            && self.vfs.buffers().contains(path)
+        {
+            return Ok(());
+        }
+
+        let lines = self.vim.getbufline(bufnr, 1, "$").await?;
+        self.vfs.buffers().insert(path.to_path_buf(), lines);
+        self.buffer_changedticks
+            .lock()
+            .insert(path.to_path_buf(), changedtick);
+
+        Ok(())
+    }
+
+    /// Path of the start buffer in [`Self::vfs`], the unnamed buffer is keyed by its bufnr.
+    pub fn start_buffer_vfs_path(&self) -> PathBuf {
+        if self.env.start_buffer_path.as_os_str().is_empty() {
+            PathBuf::from(format!("buffer://{}", self.env.start.bufnr))
+        } else {
+            self.env.start_buffer_path.clone()
+        }
+    }
+
+    /// Loads the lines of the start buffer into [`Self::vfs`], which are fetched again
+    /// only if the buffer has been changed since the last sync.
+    pub async fn sync_start_buffer_content(&self) -> VimResult<()> {
+        let bufnr = self.env.start.bufnr;
+        let path = self.start_buffer_vfs_path();
+
+        // 0 if the buffer has been wiped out.
+        let changedtick: usize = self
+            .vim
+            .call("getbufvar", (bufnr, "changedtick", 0))
+            .await?;
+
+        if changedtick == 0 {
+            self.vfs.buffers().remove(&path);
+            self.start_buffer_changedtick.lock().take();
+            return Ok(());
+        }
+
+        if *self.start_buffer_changedtick.lock() == Some(changedtick)
+            && self.vfs.buffers().contains(&path)
+        {
+            return Ok(());
+        }
+
+        let lines = self.vim.getbufline(bufnr, 1, "$").await?;
+        self.vfs.buffers().insert(path, lines);
+        *self.start_buffer_changedtick.lock() = Some(changedtick);
+
+        Ok(())
+    }
+
     pub fn render_preview(&self, preview: Preview) -> VimResult<()> {
+        self.preview_manager.reset_rendered();
+        self.vim.exec("clap#state#render_preview", preview)
+    }
+
+    /// Renders the preview of `preview_target`, sending only the changed lines if possible.
+    pub fn render_preview_with_target(
+        &self,
+        preview_target: &PreviewTarget,
+        preview: Preview,
+    ) -> VimResult<()> {
+        let preview = self
+            .preview_manager
+            .diff_with_rendered(preview_target, preview);
         self.vim.exec("clap#state#render_preview", preview)
     }
 
+    /// Renders the error of previewing the current item in the preview window.
+    pub fn render_preview_error(&self, err: &PreviewError) -> VimResult<()> {
+        self.preview_manager.reset_rendered();
+        self.vim
+            .exec("clap#state#render_preview_error", err.to_vim_error_info())
+    }
+
     async fn update_preview(
         &mut self,
         maybe_preview_target: Option<PreviewTarget>,
@@ -607,24 +1370,41 @@ impl Context {
 
         if curline.is_empty() {
             tracing::debug!("Skipping preview as curline is empty");
+            self.preview_manager.set_cursor_line(None);
+            self.preview_manager.reset_rendered();
             self.vim.bare_exec("clap#state#clear_preview")?;
             return Ok(());
         }
 
+        self.preview_manager.set_cursor_line(Some(curline.clone()));
+
         let preview_height = self.preview_height().await?;
 
-        let cached_preview_impl = if let Some(preview_target) = maybe_preview_target {
-            CachedPreviewImpl::with_preview_target(preview_target, preview_height, self)
-        } else {
-            CachedPreviewImpl::new(curline, preview_height, self)?
+        let preview_result = match maybe_preview_target {
+            Some(preview_target) => {
+                CachedPreviewImpl::with_preview_target(preview_target, preview_height, self)
+                    .get_preview()
+                    .await
+            }
+            None => match CachedPreviewImpl::new(curline, preview_height, self) {
+                Ok(cached_preview_impl) => cached_preview_impl.get_preview().await,
+                Err(err) => Err(err),
+            },
         };
 
-        let (preview_target, preview) = cached_preview_impl.get_preview().await?;
+        let (preview_target, preview) = match preview_result {
+            Ok(res) => res,
+            Err(err) => {
+                self.render_preview_error(&err)?;
+                return Err(err.into());
+            }
+        };
 
         // Ensure the preview result is not out-dated.
         let cur_lnum = self.vim.display_getcurlnum().await?;
         if cur_lnum == lnum {
-            self.render_preview(preview)?;
+            self.render_preview_with_target(&preview_target, preview)?;
+            self.prefetch_previews(lnum, preview_height);
         }
 
         self.preview_manager
@@ -634,30 +1414,249 @@ impl Context {
         Ok(())
     }
 
+    /// Prefetches the previews of the items around the cursor line `lnum` in the
+    /// background, so that moving to them is instant.
+    fn prefetch_previews(&self, lnum: usize, preview_height: usize) {
+        /// Delay before prefetching to not compete with the user moving fast.
+        const IDLE_DELAY: Duration = Duration::from_millis(200);
+        /// Maximum number of the previews computed concurrently.
+        const MAX_CONCURRENCY: usize = 2;
+
+        let generation = self.preview_manager.cancel_prefetch();
+
+        let size = crate::config::config().provider.preview_prefetch;
+        if size == 0 {
+            return;
+        }
+
+        let ctx = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(IDLE_DELAY).await;
+            if ctx.preview_manager.is_prefetch_outdated(generation) {
+                return;
+            }
+
+            let first_lnum = lnum.saturating_sub(size).max(1);
+            let Ok(lines) = ctx
+                .vim
+                .display_get_origin_lines(first_lnum, lnum + size)
+                .await
+            else {
+                return;
+            };
+
+            let semaphore = Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENCY));
+            for line in prefetch_order(lines, first_lnum, lnum) {
+                let Ok(permit) = semaphore.clone().acquire_owned().await else {
+                    return;
+                };
+                if ctx.preview_manager.is_prefetch_outdated(generation) {
+                    return;
+                }
+                let ctx = ctx.clone();
+                tokio::spawn(async move {
+                    if let Ok(cached_preview_impl) =
+                        CachedPreviewImpl::new(line, preview_height, &ctx)
+                    {
+                        cached_preview_impl.prefetch_preview().await;
+                    }
+                    drop(permit);
+                });
+            }
+        });
+    }
+
     async fn scroll_preview(&mut self, direction: Direction) -> ProviderResult<()> {
-        if let Ok(new_preview_target) = self.preview_manager.scroll_preview(direction) {
+        let lines = match direction {
+            Direction::Up => -PreviewManager::SCROLL_SIZE,
+            Direction::Down => PreviewManager::SCROLL_SIZE,
+        };
+        self.scroll_preview_lines(lines).await
+    }
+
+    async fn scroll_preview_lines(&mut self, lines: i32) -> ProviderResult<()> {
+        if let Ok(new_preview_target) = self.preview_manager.scroll_preview(lines, &self.vfs) {
             self.update_preview(Some(new_preview_target)).await?;
         }
         Ok(())
     }
 
+    /// Scrolls the preview by the number of lines in `params`, e.g., `{"lines": -20}`
+    /// scrolls up 20 lines.
+    pub async fn scroll_preview_by(&mut self, params: Params) -> ProviderResult<()> {
+        #[derive(Deserialize)]
+        struct ScrollParams {
+            lines: i32,
+        }
+
+        let ScrollParams { lines } = params
+            .parse()
+            .map_err(|err| ProviderError::Other(err.to_string()))?;
+
+        self.scroll_preview_lines(lines).await
+    }
+
+    /// Opens the file at the line of the scrolled preview instead of the line of the
+    /// current item, the current item is accepted as usual if the preview is not scrolled.
+    pub fn accept_at_preview(&self) -> ProviderResult<()> {
+        match self.preview_manager.scrolled_position() {
+            Some((path, line_number)) => self.vim.exec(
+                "clap#handler#sink_with",
+                json!(["clap#sink#open_file", path, line_number.max(1), 1]),
+            )?,
+            None => self.vim.exec("clap#handler#handle_mapping", ["\r"])?,
+        }
+        Ok(())
+    }
+
+    /// Pins or unpins the current item in this provider.
+    pub async fn pin_item(&self, params: Params) -> ProviderResult<()> {
+        #[derive(Deserialize)]
+        struct PinParams {
+            pin: bool,
+        }
+
+        let PinParams { pin } = params
+            .parse()
+            .map_err(|err| ProviderError::Other(err.to_string()))?;
+
+        // The displayed line may be truncated, pin the original item instead.
+        let curlnum = self.vim.display_getcurlnum().await?;
+        let curline = self
+            .vim
+            .display_get_origin_lines(curlnum, curlnum)
+            .await?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        if curline.is_empty() {
+            self.vim.echo_warn("Nothing to pin")?;
+            return Ok(());
+        }
+
+        let changed = {
+            let mut pinned_items = crate::datastore::PINNED_ITEMS_IN_MEMORY.lock();
+            let changed = if pin {
+                pinned_items.pin(self.provider_id(), curline.clone())
+            } else {
+                pinned_items.unpin(self.provider_id(), &curline)
+            };
+            if changed {
+                if let Err(err) = crate::datastore::store_pinned_items(&pinned_items) {
+                    tracing::error!(?err, "Failed to store the pinned items");
+                }
+            }
+            changed
+        };
+
+        match (pin, changed) {
+            (true, true) => self.vim.echo_info(format!("Pinned {curline}"))?,
+            (true, false) => self.vim.echo_info(format!("{curline} has been pinned"))?,
+            (false, true) => self.vim.echo_info(format!("Unpinned {curline}"))?,
+            (false, false) => self.vim.echo_info(format!("{curline} is not pinned"))?,
+        }
+
+        if changed && self.vim.input_get().await?.is_empty() {
+            self.update_on_empty_query().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Displays the output of the underlying tool for the raw query as is.
+    pub fn display_raw_lines(&self, lines: Vec<String>) -> VimResult<()> {
+        let total = lines.len();
+        let items = lines
+            .into_iter()
+            .take(MAX_DISPLAYED_ITEMS)
+            .map(|line| MatchedItem::from(Arc::new(line) as Arc<dyn ClapItem>))
+            .collect();
+        let printer::DisplayLines {
+            lines,
+            indices,
+            truncated_map,
+            icon_added,
+            highlights,
+        } = self.printer().to_display_lines(items);
+        let msg = json!({
+            "total": total,
+            "lines": lines,
+            "indices": indices,
+            "icon_added": icon_added,
+            "truncated_map": truncated_map,
+            "highlights": highlights,
+        });
+        self.vim
+            .exec("clap#state#process_filter_message", json!([msg, true]))
+    }
+
+    /// Displays the items filtered by the provider itself, e.g., from a list fetched
+    /// from Vim, the no matches hints are displayed if nothing matches `query`.
+    pub fn display_matched_items(
+        &self,
+        ranked_items: &mut RankedItems,
+        query: &str,
+    ) -> VimResult<()> {
+        let printer::DisplayLines {
+            lines,
+            indices,
+            truncated_map,
+            icon_added,
+            highlights,
+        } = self
+            .printer()
+            .to_display_lines(ranked_items.top(MAX_DISPLAYED_ITEMS).to_vec());
+        let mut msg = json!({
+            "total": ranked_items.len(),
+            "lines": lines,
+            "indices": indices,
+            "icon_added": icon_added,
+            "truncated_map": truncated_map,
+            "highlights": highlights,
+        });
+        if ranked_items.is_empty() && !query.is_empty() {
+            msg["no_matches_hints"] = json!(self
+                .no_matches_hints(query, ActiveFilters::default())
+                .hints());
+        }
+        self.vim
+            .exec("clap#state#process_filter_message", json!([msg, true]))
+    }
+
     pub async fn update_on_empty_query(&self) -> VimResult<()> {
-        if let Some(items) = self
+        // The top match of the last query is outdated.
+        self.vim.bare_exec("clap#state#clear_ghost_text")?;
+
+        let pinned = crate::datastore::PINNED_ITEMS_IN_MEMORY
+            .lock()
+            .items(self.provider_id())
+            .to_vec();
+
+        let maybe_items = self
             .provider_source
             .read()
-            .try_skim(self.provider_id(), 100)
-        {
-            let printer = Printer::new(self.env.display_winwidth, self.env.icon);
+            .try_skim(self.provider_id(), 100);
+
+        let maybe_items = match maybe_items {
+            Some(items) => Some(with_pinned_section(&pinned, items)),
+            None if !pinned.is_empty() => Some(with_pinned_section(&pinned, Vec::new())),
+            None => None,
+        };
+
+        if let Some(items) = maybe_items {
+            let printer = self.printer();
             let printer::DisplayLines {
                 lines,
                 icon_added,
                 truncated_map,
+                highlights,
                 ..
             } = printer.to_display_lines(items);
 
             self.vim.exec(
                 "clap#state#update_on_empty_query",
-                json!([lines, truncated_map, icon_added]),
+                json!([lines, truncated_map, icon_added, highlights]),
             )
         } else {
             self.vim.bare_exec("clap#state#clear_screen")
@@ -665,6 +1664,53 @@ impl Context {
     }
 }
 
+/// Items of the provider listing a small source on its own, e.g., fetched from Vim
+/// on initialization, and the results of the current query.
+#[derive(Debug, Default)]
+pub struct ListItems {
+    items: Vec<Arc<dyn ClapItem>>,
+    current_results: RankedItems,
+}
+
+impl ListItems {
+    pub fn new(items: Vec<Arc<dyn ClapItem>>) -> Self {
+        Self {
+            items,
+            current_results: RankedItems::default(),
+        }
+    }
+
+    pub fn set_items(&mut self, items: Vec<Arc<dyn ClapItem>>) {
+        self.items = items;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Filters the items with `query` and displays the results, the items are kept in
+    /// the original order if `query` is empty.
+    pub fn process_query(&mut self, query: &str, ctx: &Context) -> ProviderResult<()> {
+        let mut ranked_items = if query.is_empty() {
+            RankedItems::from_sorted(self.items.iter().cloned().map(MatchedItem::from).collect())
+        } else {
+            filter::par_filter_items_ranked(&self.items, &ctx.matcher(query))
+        };
+
+        ctx.display_matched_items(&mut ranked_items, query)?;
+
+        self.current_results = ranked_items;
+
+        Ok(())
+    }
+
+    /// Returns the item displayed at `lnum`, which is 1-based.
+    pub fn item_at<T: ClapItem + 'static>(&mut self, lnum: usize) -> Option<&T> {
+        let matched_item = self.current_results.get(lnum.checked_sub(1)?)?;
+        (*matched_item.item).as_any().downcast_ref::<T>()
+    }
+}
+
 #[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct ProviderId(String);
 
@@ -673,17 +1719,27 @@ impl ProviderId {
         &self.0
     }
 
+    /// Returns the bonus of the items pinned in this provider if any.
+    pub fn pinned_bonus(&self) -> Option<Bonus> {
+        let pinned = crate::datastore::PINNED_ITEMS_IN_MEMORY
+            .lock()
+            .items(self.as_str())
+            .to_vec();
+        (!pinned.is_empty()).then(|| Bonus::Pinned(pinned.into()))
+    }
+
     pub fn matcher_builder(&self) -> MatcherBuilder {
         let match_scope = match self.0.as_str() {
             "grep" | "live_grep" => MatchScope::GrepLine,
-            "tags" | "proj_tags" => MatchScope::TagName,
+            "tags" | "proj_tags" | "symbols" => MatchScope::TagName,
             _ => MatchScope::Full,
         };
 
-        let match_bonuses = match self.0.as_str() {
+        let mut match_bonuses = match self.0.as_str() {
             "files" | "git_files" | "filer" => vec![Bonus::FileName],
             _ => vec![],
         };
+        match_bonuses.extend(self.pinned_bonus());
 
         MatcherBuilder::new()
             .bonuses(match_bonuses)
@@ -759,40 +1815,63 @@ impl ProviderSource {
     }
 
     pub fn try_skim(&self, provider_id: &str, n: usize) -> Option<Vec<MatchedItem>> {
-        match self {
-            Self::Small { ref items, .. } => Some(
-                items
-                    .iter()
-                    .take(n)
-                    .map(|item| MatchedItem::from(item.clone()))
-                    .collect(),
-            ),
-            Self::File { ref path, .. } | Self::CachedFile { ref path, .. } => {
-                let lines_iter = utils::read_first_lines(path, n).ok()?;
-                if provider_id == "blines" {
-                    let items = lines_iter
-                        .enumerate()
-                        .map(|(index, line)| {
-                            let item: Arc<dyn ClapItem> = Arc::new(BlinesItem {
-                                raw: line,
-                                line_number: index + 1,
-                            });
-                            MatchedItem::from(item)
-                        })
-                        .collect();
-                    Some(items)
-                } else {
-                    let items = lines_iter
-                        .map(|line| MatchedItem::from(Arc::new(line) as Arc<dyn ClapItem>))
-                        .collect();
-                    Some(items)
-                }
+        let lines_iter: Box<dyn Iterator<Item = String>> = match self {
+            Self::Small { ref items, .. } => {
+                return Some(
+                    items
+                        .iter()
+                        .take(n)
+                        .map(|item| MatchedItem::from(item.clone()))
+                        .collect(),
+                )
             }
-            _ => None,
+            Self::File { ref path, .. } => Box::new(utils::read_first_lines(path, n).ok()?),
+            // The paths in the grep cache are interned.
+            Self::CachedFile { ref path, .. } => {
+                let lines = filter::mmap_lines(path).ok()?;
+                Box::new((0..lines.len().min(n)).map(move |idx| lines.line_string(idx)))
+            }
+            _ => return None,
+        };
+
+        if provider_id == "blines" {
+            let items = lines_iter
+                .enumerate()
+                .map(|(index, line)| {
+                    let item: Arc<dyn ClapItem> = Arc::new(BlinesItem {
+                        raw: line,
+                        line_number: index + 1,
+                    });
+                    MatchedItem::from(item)
+                })
+                .collect();
+            Some(items)
+        } else {
+            let items = lines_iter
+                .map(|line| MatchedItem::from(Arc::new(line) as Arc<dyn ClapItem>))
+                .collect();
+            Some(items)
         }
     }
 }
 
+/// Parameters of [`ClapProvider::on_action`].
+#[derive(Debug, Deserialize)]
+pub struct ActionParams {
+    /// Name of the action.
+    pub action: String,
+    /// Argument of the action if any, e.g., the name of the branch to create.
+    pub name: Option<String>,
+}
+
+impl ActionParams {
+    pub fn parse(params: Params) -> ProviderResult<Self> {
+        params
+            .parse()
+            .map_err(|err| ProviderError::Other(err.to_string()))
+    }
+}
+
 /// A trait each Clap provider must implement.
 #[async_trait::async_trait]
 pub trait ClapProvider: Debug + Send + Sync + 'static {
@@ -805,6 +1884,7 @@ pub trait ClapProvider: Debug + Send + Sync + 'static {
         ctx: &mut Context,
         initial_query: String,
     ) -> ProviderResult<()> {
+        let initial_query = resolve_initial_query(ctx, initial_query).await?;
         // Mimic the user behavior by setting the user input and sending the signal
         let _ = ctx
             .vim
@@ -814,7 +1894,7 @@ pub trait ClapProvider: Debug + Send + Sync + 'static {
     }
 
     async fn on_move(&mut self, ctx: &mut Context) -> ProviderResult<()> {
-        if !ctx.env.preview_enabled {
+        if !ctx.preview_enabled() {
             return Ok(());
         }
         ctx.preview_manager.reset_scroll();
@@ -837,8 +1917,290 @@ pub trait ClapProvider: Debug + Send + Sync + 'static {
             KeyEventType::ShiftDown => ctx.scroll_preview(Direction::Down).await?,
             KeyEventType::CtrlN => ctx.next_input().await?,
             KeyEventType::CtrlP => ctx.prev_input().await?,
+            KeyEventType::AltP => ctx.toggle_preview()?,
             _ => {}
         }
         Ok(())
     }
+
+    /// Performs the provider specific action on the current item, `params` is parsed as
+    /// [`ActionParams`].
+    async fn on_action(&mut self, ctx: &mut Context, _params: Params) -> ProviderResult<()> {
+        ctx.vim.echo_warn(format!(
+            "Provider {} does not support any action",
+            ctx.provider_id()
+        ))?;
+        Ok(())
+    }
+}
+
+/// Prefix of the query forwarded verbatim to the underlying tool, e.g., `>fn \w+\(` is
+/// searched as the regex by grep and `>foo` is `git log -S foo` for commits.
+pub const RAW_QUERY_PREFIX: char = '>';
+
+/// Returns the query with [`RAW_QUERY_PREFIX`] stripped, `None` if it's not a raw query.
+///
+/// The rest of the query is kept verbatim, e.g., the leading spaces of the search term.
+pub fn raw_query(query: &str) -> Option<&str> {
+    query.strip_prefix(RAW_QUERY_PREFIX)
+}
+
+/// Parses `args`, the later occurrences of an argument override the earlier ones, e.g.,
+/// the default arguments in the config repeated in `:Clap`.
+fn try_parse_provider_args<T: clap::Parser>(args: Vec<String>) -> Result<T, clap::Error> {
+    let matches = T::command()
+        .args_override_self(true)
+        .try_get_matches_from(std::iter::once(String::new()).chain(args))?;
+    T::from_arg_matches(&matches)
+}
+
+/// Splits the provider arguments in a shell-like way.
+///
+/// Vim splits the arguments of `:Clap` on the whitespaces, the quoted argument
+/// containing whitespaces, e.g., `--query="foo bar"`, is joined back here.
+fn split_provider_args(args: &[String]) -> Vec<String> {
+    let raw_args = args.join(" ");
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+    let mut chars = raw_args.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            // Only escape in the double quotes, the backslashes of Windows paths are kept as is.
+            (Some('"'), '\\') => {
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote.replace(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+/// Returns the items to prefetch in the `lines` starting from `first_lnum`, excluding the
+/// current one at `lnum`.
+///
+/// The next items are more likely to be previewed than the previous ones, the nearer the
+/// earlier.
+fn prefetch_order(lines: Vec<String>, first_lnum: usize, lnum: usize) -> Vec<String> {
+    let mut neighbours = lines
+        .into_iter()
+        .enumerate()
+        .map(|(idx, line)| (first_lnum + idx, line))
+        .filter(|(line_number, line)| *line_number != lnum && !line.is_empty())
+        .collect::<Vec<_>>();
+    neighbours.sort_by_key(|(line_number, _)| (*line_number < lnum, line_number.abs_diff(lnum)));
+    neighbours.into_iter().map(|(_, line)| line).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_parse_provider_args() {
+        #[derive(Debug, clap::Parser, PartialEq, Eq, Default)]
+        struct Args {
+            #[clap(long)]
+            hidden: bool,
+            #[clap(long)]
+            max: Option<usize>,
+            #[clap(flatten)]
+            base: BaseArgs,
+        }
+
+        let args = |args: &[&str]| args.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        // The default `--hidden --max=1` from the config is repeated by the user.
+        let parsed: Args =
+            try_parse_provider_args(args(&["--hidden", "--max=1", "--hidden", "--max=2"])).unwrap();
+        assert!(parsed.hidden);
+        assert_eq!(parsed.max, Some(2));
+
+        assert!(try_parse_provider_args::<Args>(args(&["--unknown"])).is_err());
+    }
+
+    #[test]
+    fn test_preview_cache() {
+        let commit = |rev: &str| PreviewTarget::GitCommit(rev.into());
+        let preview = |line: &str| Preview::new(vec![line.into()]);
+        let ttl = Duration::from_secs(60);
+
+        let mut cache = PreviewCache::default();
+        cache.insert(commit("a"), preview("a"), 2);
+        cache.insert(commit("b"), preview("b"), 2);
+        assert!(cache.get(&commit("a"), ttl).is_some());
+
+        // `b` is the least recently used.
+        cache.insert(commit("c"), preview("c"), 2);
+        assert!(cache.get(&commit("b"), ttl).is_none());
+        assert!(cache.get(&commit("a"), ttl).is_some());
+        assert!(cache.get(&commit("c"), Duration::ZERO).is_none());
+
+        // The file created after the insertion makes the preview stale.
+        let path = std::env::temp_dir().join(format!("clap_preview_cache_{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let file = PreviewTarget::File(path.clone());
+        cache.insert(file.clone(), preview("file"), 2);
+        assert!(cache.get(&file, ttl).is_some());
+        std::fs::write(&path, "new").unwrap();
+        assert!(cache.get(&file, ttl).is_none());
+        std::fs::remove_file(&path).unwrap();
+
+        // Previews of the changed file and its directory are purged.
+        let dir = PreviewTarget::Directory("/clap/src".into());
+        let changed = PreviewTarget::File("/clap/src/lib.rs".into());
+        let unchanged = PreviewTarget::File("/clap/src/main.rs".into());
+        cache.clear();
+        cache.insert(dir.clone(), preview("dir"), 4);
+        cache.insert(changed.clone(), preview("changed"), 4);
+        cache.insert(unchanged.clone(), preview("unchanged"), 4);
+        cache.insert(commit("a"), preview("a"), 4);
+        assert!(cache.invalidate(&["/clap/src/lib.rs".into()]));
+        assert!(cache.get(&dir, ttl).is_none());
+        assert!(cache.get(&changed, ttl).is_none());
+        assert!(cache.get(&unchanged, ttl).is_some());
+        assert!(cache.get(&commit("a"), ttl).is_some());
+        assert!(!cache.invalidate(&["/clap/README.md".into()]));
+
+        // The cache keys of the mixed-case paths are folded on the case-insensitive platforms.
+        let mixed_case = PreviewTarget::File("/clap/src/ReadMe.md".into());
+        cache.insert(mixed_case.cache_key(), preview("mixed case"), 4);
+        assert!(cache.invalidate(&["/clap/src/ReadMe.md".into()]));
+        assert!(cache.get(&mixed_case.cache_key(), ttl).is_none());
+    }
+
+    #[test]
+    fn test_prefetch_order() {
+        let lines = ["1", "2", "", "4", "5", "6"].map(String::from).to_vec();
+        assert_eq!(prefetch_order(lines, 1, 4), ["5", "6", "2", "1"]);
+        let lines = ["1", "2", "3"].map(String::from).to_vec();
+        assert_eq!(prefetch_order(lines, 1, 1), ["2", "3"]);
+    }
+
+    #[test]
+    fn test_cancel_prefetch() {
+        let preview_manager = PreviewManager::new();
+        let first = preview_manager.cancel_prefetch();
+        assert!(!preview_manager.is_prefetch_outdated(first));
+        let second = preview_manager.cancel_prefetch();
+        assert!(preview_manager.is_prefetch_outdated(first));
+        assert!(!preview_manager.is_prefetch_outdated(second));
+    }
+
+    #[test]
+    fn test_scroll_preview() {
+        let path = PathBuf::from("/clap/scroll.txt");
+        let vfs = crate::vfs::BufferFs::default();
+        vfs.insert(path.clone(), (1..=30).map(|i| i.to_string()).collect());
+
+        let mut preview_manager = PreviewManager::new();
+        preview_manager.set_preview_target(PreviewTarget::LineInFile {
+            path: path.clone(),
+            line_number: 5,
+            column: None,
+        });
+        let mut scroll = |lines| {
+            preview_manager
+                .scroll_preview(lines, &vfs)
+                .map(|target| match target {
+                    PreviewTarget::LineInFile { line_number, .. } => line_number,
+                    target => panic!("Unexpected preview target: {target:?}"),
+                })
+        };
+
+        assert_eq!(scroll(10).unwrap(), 15);
+        // Stops at the start and the end of file.
+        assert_eq!(scroll(-20).unwrap(), 0);
+        assert_eq!(scroll(100).unwrap(), 30);
+        assert!(scroll(1).is_err());
+        assert_eq!(scroll(-10).unwrap(), 20);
+    }
+
+    #[test]
+    fn test_reset_scroll() {
+        let vfs = crate::vfs::BufferFs::default();
+        let line_in_file = |path: &str, line_number| PreviewTarget::LineInFile {
+            path: path.into(),
+            line_number,
+            column: None,
+        };
+        vfs.insert("/clap/a.txt".into(), vec!["a".into(); 30]);
+        vfs.insert("/clap/b.txt".into(), vec!["b".into(); 30]);
+
+        let mut preview_manager = PreviewManager::new();
+        preview_manager.set_preview_target(line_in_file("/clap/a.txt", 5));
+        preview_manager.scroll_preview(10, &vfs).unwrap();
+        assert_eq!(
+            preview_manager.scrolled_position(),
+            Some((Path::new("/clap/a.txt"), 15))
+        );
+
+        // Moving to another item starts over from its line.
+        preview_manager.reset_scroll();
+        assert_eq!(preview_manager.scroll_offset, 0);
+        assert!(preview_manager.scrolled_position().is_none());
+        preview_manager.set_preview_target(line_in_file("/clap/b.txt", 20));
+        assert_eq!(
+            preview_manager.scroll_preview(1, &vfs).unwrap(),
+            line_in_file("/clap/b.txt", 21)
+        );
+    }
+
+    #[test]
+    fn test_raw_query() {
+        assert_eq!(raw_query(">fn \\w+"), Some("fn \\w+"));
+        assert_eq!(raw_query(">  foo"), Some("  foo"));
+        assert_eq!(raw_query(">"), Some(""));
+        assert_eq!(raw_query("foo>"), None);
+    }
+
+    #[test]
+    fn test_display_line_width() {
+        assert_eq!(display_line_width("files", 80), 78);
+        assert_eq!(display_line_width("grep", 80), 76);
+        assert_eq!(display_line_width("grep", 3), 0);
+    }
+
+    #[test]
+    fn test_split_provider_args() {
+        let to_args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            split_provider_args(&to_args(&["--hidden", "--type=rust", "foo/"])),
+            to_args(&["--hidden", "--type=rust", "foo/"])
+        );
+        assert_eq!(
+            split_provider_args(&to_args(&["--query=\"foo", "bar\"", "--path", "'a", "b'"])),
+            to_args(&["--query=foo bar", "--path", "a b"])
+        );
+        assert_eq!(
+            split_provider_args(&to_args(&["--query", "\"\""])),
+            to_args(&["--query", ""])
+        );
+    }
 }
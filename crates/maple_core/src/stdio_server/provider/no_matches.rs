@@ -0,0 +1,301 @@
+//! Hints displayed below the no matches message, pointing out what may have filtered out
+//! the expected results.
+
+use super::raw_query;
+use ignore::WalkBuilder;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of the files walked when counting the excluded files.
+const MAX_COUNTED_FILES: usize = 10_000;
+
+/// Filters of the provider narrowing the results.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ActiveFilters {
+    /// `Some(false)` if the hidden files are excluded, `None` if not applicable.
+    pub hidden: Option<bool>,
+    /// Whether the files ignored by `.gitignore` are excluded.
+    pub gitignore: bool,
+    /// Whether only the file names are matched.
+    pub name_only: bool,
+}
+
+impl ActiveFilters {
+    /// Filters of the providers walking the files, e.g., `files` and `grep`.
+    pub fn walk(hidden: bool) -> Self {
+        Self {
+            hidden: Some(hidden),
+            gitignore: true,
+            name_only: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HintKind {
+    RawQuery,
+    CaseSensitive,
+    ExactTerms,
+    NameOnly,
+    Hidden,
+    Gitignore,
+}
+
+/// Hint sent to Vim as `{"kind": "hidden", "text": "...", "count": 3}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NoMatchesHint {
+    pub kind: HintKind,
+    pub text: String,
+    /// Number of the files excluded by the filter, if counted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+}
+
+impl NoMatchesHint {
+    fn new(kind: HintKind, text: String) -> Self {
+        Self {
+            kind,
+            text,
+            count: None,
+        }
+    }
+}
+
+/// Number of the files excluded by the walk filters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ExcludedFiles {
+    hidden: usize,
+    gitignored: usize,
+    /// Whether the walk stopped at [`MAX_COUNTED_FILES`].
+    truncated: bool,
+}
+
+/// Counts the hidden and the gitignored files under `roots`, the `.git` directory aside.
+fn count_excluded_files(roots: &[PathBuf]) -> ExcludedFiles {
+    fn walk(root: &Path, git_ignore: bool) -> impl Iterator<Item = ignore::DirEntry> {
+        WalkBuilder::new(root)
+            .hidden(false)
+            .ignore(git_ignore)
+            .git_ignore(git_ignore)
+            .git_global(git_ignore)
+            .git_exclude(git_ignore)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().map_or(false, |ty| ty.is_file()))
+            .take(MAX_COUNTED_FILES)
+    }
+
+    let is_hidden = |root: &Path, path: &Path| {
+        path.strip_prefix(root).map_or(false, |relative| {
+            relative
+                .components()
+                .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+        })
+    };
+
+    let mut excluded = ExcludedFiles::default();
+    for root in roots {
+        let mut not_ignored = 0;
+        for entry in walk(root, true) {
+            not_ignored += 1;
+            if is_hidden(root, entry.path()) {
+                excluded.hidden += 1;
+            }
+        }
+        let total = walk(root, false).count();
+        excluded.gitignored += total.saturating_sub(not_ignored);
+        excluded.truncated |= total == MAX_COUNTED_FILES || not_ignored == MAX_COUNTED_FILES;
+    }
+    excluded
+}
+
+/// Hints for the empty results of a query, computed only once nothing is matched as
+/// the excluded files are counted by walking the directories.
+#[derive(Debug, Clone, Default)]
+pub struct NoMatchesHints {
+    provider_id: String,
+    query: String,
+    filters: ActiveFilters,
+    /// Directories walked by the provider, in which the excluded files are counted.
+    roots: Vec<PathBuf>,
+}
+
+impl NoMatchesHints {
+    pub fn new(
+        provider_id: &str,
+        query: &str,
+        filters: ActiveFilters,
+        roots: Vec<PathBuf>,
+    ) -> Self {
+        Self {
+            provider_id: provider_id.to_string(),
+            query: query.to_string(),
+            filters,
+            roots,
+        }
+    }
+
+    /// Replaces the directories walked by the provider.
+    pub fn with_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.roots = roots;
+        self
+    }
+
+    pub fn hints(&self) -> Vec<NoMatchesHint> {
+        let excluded = if self.filters.hidden == Some(false) || self.filters.gitignore {
+            count_excluded_files(&self.roots)
+        } else {
+            ExcludedFiles::default()
+        };
+        no_matches_hints(&self.provider_id, &self.query, self.filters, excluded)
+    }
+}
+
+fn no_matches_hints(
+    provider_id: &str,
+    query: &str,
+    filters: ActiveFilters,
+    excluded: ExcludedFiles,
+) -> Vec<NoMatchesHint> {
+    let mut hints = Vec::new();
+
+    if let Some(raw_query) = raw_query(query) {
+        hints.push(NoMatchesHint::new(
+            HintKind::RawQuery,
+            format!(
+                "Raw query `{raw_query}` is passed to the underlying tool verbatim, delete the leading `>` for the fuzzy matching"
+            ),
+        ));
+    } else {
+        if query.chars().any(char::is_uppercase) {
+            hints.push(NoMatchesHint::new(
+                HintKind::CaseSensitive,
+                "Case-sensitive as the query contains uppercase letters, type in lowercase to ignore case"
+                    .to_string(),
+            ));
+        }
+
+        let exact_terms = query
+            .split_whitespace()
+            .filter(|term| {
+                term.len() > 1 && (term.starts_with(['\'', '^', '!', '"']) || term.ends_with('$'))
+            })
+            .collect::<Vec<_>>();
+        if !exact_terms.is_empty() {
+            hints.push(NoMatchesHint::new(
+                HintKind::ExactTerms,
+                format!(
+                    "Exact terms: {}, which are not fuzzy matched",
+                    exact_terms.join(" ")
+                ),
+            ));
+        }
+    }
+
+    if filters.name_only {
+        hints.push(NoMatchesHint::new(
+            HintKind::NameOnly,
+            "Only the file names are matched, the directories are ignored".to_string(),
+        ));
+    }
+
+    let plus = if excluded.truncated { "+" } else { "" };
+
+    if filters.hidden == Some(false) && excluded.hidden > 0 {
+        hints.push(NoMatchesHint {
+            kind: HintKind::Hidden,
+            text: format!(
+                "{}{plus} hidden files are excluded, run `:Clap {provider_id} --hidden` to include them",
+                excluded.hidden
+            ),
+            count: Some(excluded.hidden),
+        });
+    }
+
+    if filters.gitignore && excluded.gitignored > 0 {
+        hints.push(NoMatchesHint {
+            kind: HintKind::Gitignore,
+            text: format!(
+                "{}{plus} files ignored by .gitignore are excluded",
+                excluded.gitignored
+            ),
+            count: Some(excluded.gitignored),
+        });
+    }
+
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_matches_hints() {
+        let excluded = ExcludedFiles {
+            hidden: 3,
+            gitignored: 5,
+            truncated: false,
+        };
+        let hints = no_matches_hints("files", "Foo 'bar", ActiveFilters::walk(false), excluded);
+        let kinds = hints.iter().map(|hint| hint.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                HintKind::CaseSensitive,
+                HintKind::ExactTerms,
+                HintKind::Hidden,
+                HintKind::Gitignore
+            ]
+        );
+        assert!(hints[1].text.contains("'bar"));
+        assert_eq!(
+            hints[2].text,
+            "3 hidden files are excluded, run `:Clap files --hidden` to include them"
+        );
+        assert_eq!(hints[3].count, Some(5));
+
+        let hints = no_matches_hints("grep", ">Foo", ActiveFilters::walk(true), excluded);
+        assert_eq!(hints.len(), 2);
+        assert!(hints[0].text.starts_with("Raw query `Foo`"));
+        assert_eq!(hints[1].kind, HintKind::Gitignore);
+
+        // Nothing is excluded actually.
+        let hints = no_matches_hints(
+            "files",
+            "foo",
+            ActiveFilters::walk(false),
+            ExcludedFiles::default(),
+        );
+        assert!(hints.is_empty());
+
+        assert!(no_matches_hints("lines", "foo", ActiveFilters::default(), excluded).is_empty());
+    }
+
+    #[test]
+    fn test_count_excluded_files() {
+        let root = std::env::temp_dir().join(format!("clap_no_matches_{}", std::process::id()));
+        std::fs::create_dir_all(root.join(".hidden")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join(".ignore"), "target/\n").unwrap();
+        std::fs::write(root.join("visible.rs"), "").unwrap();
+        std::fs::write(root.join(".hidden/a.rs"), "").unwrap();
+        std::fs::write(root.join("target/b.rs"), "").unwrap();
+        std::fs::write(root.join("target/c.rs"), "").unwrap();
+
+        let excluded = count_excluded_files(&[root.clone()]);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            excluded,
+            ExcludedFiles {
+                // `.ignore` and `.hidden/a.rs`.
+                hidden: 2,
+                gitignored: 2,
+                truncated: false,
+            }
+        );
+    }
+}
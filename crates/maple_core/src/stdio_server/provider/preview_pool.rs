@@ -0,0 +1,164 @@
+//! Dedicated worker pool generating the previews.
+//!
+//! The previews are generated off the runtime serving the provider events, a slow preview,
+//! e.g., a huge file on a cold network filesystem, can not delay the filtering on typing.
+//!
+//! The pending previews are queued in a bounded channel and dropped once they are stale,
+//! i.e., the cursor has moved to another line, including the ones already running.
+
+use once_cell::sync::Lazy;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::sync::{mpsc, oneshot};
+
+/// Maximum number of the previews waiting for a worker.
+const QUEUE_CAPACITY: usize = 16;
+
+/// Interval of checking whether the running preview is stale.
+const STALE_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Returns `true` once the preview is no longer wanted.
+pub type IsStale = Arc<dyn Fn() -> bool + Send + Sync>;
+
+struct PreviewTask {
+    task: Pin<Box<dyn Future<Output = ()> + Send>>,
+    is_stale: IsStale,
+}
+
+struct PreviewPool {
+    sender: mpsc::Sender<PreviewTask>,
+    _runtime: Runtime,
+}
+
+static PREVIEW_POOL: Lazy<PreviewPool> = Lazy::new(|| {
+    let workers = crate::config::config().provider.preview_pool.workers();
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(workers)
+        .thread_name("clap-preview")
+        .enable_all()
+        .build()
+        .expect("Failed to build the preview worker pool");
+
+    let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+    let receiver = Arc::new(tokio::sync::Mutex::new(receiver));
+    for _ in 0..workers {
+        runtime.spawn(run_worker(receiver.clone()));
+    }
+
+    PreviewPool {
+        sender,
+        _runtime: runtime,
+    }
+});
+
+async fn run_worker(receiver: Arc<tokio::sync::Mutex<mpsc::Receiver<PreviewTask>>>) {
+    loop {
+        let Some(PreviewTask { task, is_stale }) = receiver.lock().await.recv().await else {
+            return;
+        };
+
+        if is_stale() {
+            continue;
+        }
+
+        // Most of the previewers are synchronous, which would otherwise block the worker
+        // thread and starve the other queued previews. The number of the running previews
+        // is still bounded by the workers as each one waits for its blocking task.
+        let handle = tokio::runtime::Handle::current();
+        if let Err(err) = tokio::task::spawn_blocking(move || handle.block_on(task)).await {
+            tracing::error!(?err, "Preview task panicked");
+        }
+    }
+}
+
+/// Resolves once `is_stale` returns `true`.
+async fn until_stale(is_stale: &IsStale) {
+    let mut interval = tokio::time::interval(STALE_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if is_stale() {
+            return;
+        }
+    }
+}
+
+/// Queues `task` generating a preview on the preview worker pool.
+///
+/// The task is cancelled if it's stale or the returned receiver is dropped. Returns `None`
+/// if the queue is full.
+pub fn spawn<F>(task: F, is_stale: IsStale) -> Option<oneshot::Receiver<F::Output>>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let (mut output_sender, output_receiver) = oneshot::channel();
+
+    let task = {
+        let is_stale = is_stale.clone();
+        Box::pin(async move {
+            let output = tokio::select! {
+                output = task => Some(output),
+                _ = output_sender.closed() => None,
+                _ = until_stale(&is_stale) => None,
+            };
+            if let Some(output) = output {
+                let _ = output_sender.send(output);
+            }
+        })
+    };
+
+    match PREVIEW_POOL.sender.try_send(PreviewTask { task, is_stale }) {
+        Ok(()) => Some(output_receiver),
+        Err(err) => {
+            tracing::debug!(?err, "Preview queue is full");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_run_worker_skips_stale_tasks() {
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        let ran = Arc::new(AtomicUsize::new(0));
+
+        for stale in [true, false] {
+            let ran = ran.clone();
+            let task = PreviewTask {
+                task: Box::pin(async move {
+                    ran.fetch_add(1, Ordering::SeqCst);
+                }),
+                is_stale: Arc::new(move || stale),
+            };
+            sender.try_send(task).unwrap();
+        }
+        drop(sender);
+
+        // The worker returns once the queue is closed and drained.
+        run_worker(Arc::new(tokio::sync::Mutex::new(receiver))).await;
+        assert_eq!(ran.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_until_stale() {
+        let stale = Arc::new(AtomicBool::new(false));
+        let is_stale: IsStale = {
+            let stale = stale.clone();
+            Arc::new(move || stale.load(Ordering::SeqCst))
+        };
+
+        let pending = tokio::time::timeout(STALE_CHECK_INTERVAL * 3, until_stale(&is_stale));
+        assert!(pending.await.is_err());
+
+        stale.store(true, Ordering::SeqCst);
+        let resolved = tokio::time::timeout(STALE_CHECK_INTERVAL * 3, until_stale(&is_stale));
+        assert!(resolved.await.is_ok());
+    }
+}
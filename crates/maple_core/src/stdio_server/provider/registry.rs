@@ -0,0 +1,123 @@
+//! Providers registered by the user at runtime via the `provider/register` request,
+//! e.g., lazily by a plugin manager, without restarting maple.
+//!
+//! The Vim side keeps the actions (sink, mappings) of the registered provider as they
+//! are funcrefs, the backend only needs to know where the source items come from and
+//! how to preview them.
+
+use crate::config::PreviewPosition;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+static USER_PROVIDERS: Lazy<RwLock<HashMap<String, UserProviderSpec>>> =
+    Lazy::new(Default::default);
+
+/// Source of the registered provider.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SourceSpec {
+    /// Shell command whose output lines are the source items.
+    Command(String),
+    /// Items of a small source.
+    List(Vec<String>),
+}
+
+/// How to preview the items of the registered provider.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PreviewSpec {
+    /// No preview.
+    #[default]
+    None,
+    /// The item is a file path relative to the cwd.
+    File,
+    /// The item is in the format of grep, i.e., `path:line:column:text`.
+    Grep,
+    /// Stdout of the shell command, `{}` in which is replaced with the item, e.g.,
+    /// `systemctl status {}`.
+    Command(String),
+    /// The item is a http(s) URL, fetched if `provider.url-preview.enable` is set.
+    Url,
+}
+
+/// Registration of a user provider.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserProviderSpec {
+    pub id: String,
+    /// `None` if the source is provided by Vim, e.g., a funcref.
+    #[serde(default)]
+    pub source: Option<SourceSpec>,
+    #[serde(default)]
+    pub preview: PreviewSpec,
+    /// Position of the target line in the file preview, overridden by the user config.
+    #[serde(default)]
+    pub preview_position: Option<PreviewPosition>,
+    /// Names of the actions available on Vim side.
+    #[serde(default)]
+    pub actions: Vec<String>,
+}
+
+/// Registers `spec`, the existing registration of the same id is replaced and returned.
+pub fn register(spec: UserProviderSpec) -> Option<UserProviderSpec> {
+    tracing::debug!(?spec, "Registering user provider");
+    USER_PROVIDERS.write().insert(spec.id.clone(), spec)
+}
+
+/// Unregisters the provider `id`, returns the removed registration.
+pub fn unregister(id: &str) -> Option<UserProviderSpec> {
+    USER_PROVIDERS.write().remove(id)
+}
+
+/// Returns the registration of provider `id`.
+pub fn get(id: &str) -> Option<UserProviderSpec> {
+    USER_PROVIDERS.read().get(id).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_user_provider_registry() {
+        let spec: UserProviderSpec = serde_json::from_value(json!({
+            "id": "test_registry_todo",
+            "source": "rg --vimgrep TODO",
+            "preview": "grep",
+            "actions": ["open", "open-in-split"],
+        }))
+        .unwrap();
+        assert_eq!(
+            spec.source,
+            Some(SourceSpec::Command("rg --vimgrep TODO".into()))
+        );
+        assert_eq!(spec.preview, PreviewSpec::Grep);
+
+        assert!(register(spec.clone()).is_none());
+        assert_eq!(get("test_registry_todo"), Some(spec.clone()));
+
+        let list_spec: UserProviderSpec = serde_json::from_value(json!({
+            "id": "test_registry_todo",
+            "source": ["a", "b"],
+        }))
+        .unwrap();
+        assert_eq!(list_spec.preview, PreviewSpec::None);
+
+        let command_spec: UserProviderSpec = serde_json::from_value(json!({
+            "id": "test_registry_units",
+            "preview": {"command": "systemctl status {}"},
+            "preview_position": "top",
+        }))
+        .unwrap();
+        assert_eq!(command_spec.preview_position, Some(PreviewPosition::Top));
+        assert_eq!(
+            command_spec.preview,
+            PreviewSpec::Command("systemctl status {}".into())
+        );
+        assert_eq!(register(list_spec.clone()), Some(spec));
+        assert_eq!(unregister("test_registry_todo"), Some(list_spec));
+        assert!(get("test_registry_todo").is_none());
+    }
+}
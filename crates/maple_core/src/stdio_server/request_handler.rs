@@ -1,4 +1,5 @@
 use crate::stdio_server::Error;
+use crate::vfs::DiskFs;
 use rpc::RpcRequest;
 use serde::Deserialize;
 use serde_json::{json, Value};
@@ -33,83 +34,111 @@ pub async fn preview_file(msg: RpcRequest) -> Result<Value, Error> {
         (display_height, preview_width.unwrap_or(display_width))
     };
 
-    let (lines, fname) = crate::previewer::preview_file(fpath, preview_height, preview_width)?;
+    let (lines, fname) =
+        crate::previewer::preview_file(&DiskFs, fpath, preview_height, preview_width)?;
 
     let value = json!({"id": msg_id, "result": json!({"lines": lines, "fname": fname})});
 
     Ok(value)
 }
 
-pub async fn preview_quickfix(msg: RpcRequest) -> Result<Value, Error> {
-    use crate::previewer::{preview_file, preview_file_at};
-    use std::path::PathBuf;
-
-    let msg_id = msg.id;
-
+pub fn complete_clap_command(msg: RpcRequest) -> Result<Value, Error> {
     #[derive(Deserialize)]
     struct InnerParams {
+        cmdline: String,
+        cursor_pos: usize,
         cwd: String,
-        curline: String,
-        winwidth: usize,
-        winheight: usize,
+        providers: Vec<String>,
     }
 
     let InnerParams {
+        cmdline,
+        cursor_pos,
         cwd,
-        curline,
-        winwidth,
-        winheight,
+        providers,
     } = msg.params.parse()?;
 
-    let (p, lnum) = parse_quickfix_entry(curline.as_str())?;
+    let cmdline = cmdline.get(..cursor_pos).unwrap_or(&cmdline);
+    let cwd = paths::expand_tilde(cwd);
 
-    let mut fpath: PathBuf = cwd.into();
-    fpath.push(p);
+    let candidates =
+        crate::stdio_server::completion::complete_clap_command(cmdline, &cwd, &providers);
 
-    let result = if lnum == 0 {
-        let size = winheight + 5;
-        let (lines, _) = preview_file(fpath.as_path(), size, winwidth)?;
-        json!({ "event": "on_move", "lines": lines, "fname": fpath })
-    } else {
-        let (lines, hi_lnum) = preview_file_at(fpath.as_path(), winheight, winwidth, lnum)?;
-        json!({ "event": "on_move", "lines": lines, "fname": fpath, "hi_lnum": hi_lnum })
-    };
+    Ok(json!(candidates))
+}
 
-    let value = json!({ "id": msg_id, "provider_id": "quickfix", "result": result });
+pub fn register_provider(msg: RpcRequest) -> Result<Value, Error> {
+    use crate::stdio_server::provider::registry::{self, UserProviderSpec};
 
-    Ok(value)
+    let spec: UserProviderSpec = msg.params.parse()?;
+    let id = spec.id.clone();
+    let replaced = registry::register(spec).is_some();
+
+    Ok(json!({ "id": id, "replaced": replaced }))
 }
 
-fn parse_quickfix_entry(line: &str) -> Result<(&str, usize), Error> {
-    let mut parts = line.split('|');
-    let fpath = parts
-        .next()
-        .ok_or_else(|| Error::Parse(format!("missing fpath in quickfix entry {line}")))?;
+pub fn unregister_provider(msg: RpcRequest) -> Result<Value, Error> {
+    use crate::stdio_server::provider::registry;
+
+    #[derive(Deserialize)]
+    struct InnerParams {
+        id: String,
+    }
+
+    let InnerParams { id } = msg.params.parse()?;
+    let removed = registry::unregister(&id).is_some();
+
+    Ok(json!({ "id": id, "removed": removed }))
+}
+
+/// Returns `[git_dir, work_tree]` if `path` is managed by a git directory outside of the
+/// work tree, `[]` otherwise, which Vim can tell from `null` on timeout.
+pub fn external_git_dir(msg: RpcRequest) -> Result<Value, Error> {
+    #[derive(Deserialize)]
+    struct InnerParams {
+        path: String,
+    }
+
+    let InnerParams { path } = msg.params.parse()?;
+    let path = paths::expand_tilde(path);
 
-    let mut it = parts
-        .next()
-        .ok_or_else(|| Error::Parse(format!("missing lnum and column in quickfix entry {line}")))?
-        .split("col");
+    match crate::tools::git::external_git_dir(&path) {
+        Some((git_dir, work_tree)) => Ok(json!([git_dir, work_tree])),
+        None => Ok(json!([])),
+    }
+}
 
-    let lnum = it
-        .next()
-        .ok_or_else(|| Error::Parse(format!("missing lnum in quickfix entry {line}")))?
-        .trim()
-        .parse::<usize>()?;
+/// Checks out the branch of the `git_branches` provider, returns `{"error": msg}` on failure.
+pub async fn git_checkout(msg: RpcRequest) -> Result<Value, Error> {
+    #[derive(Deserialize)]
+    struct InnerParams {
+        cwd: String,
+        branch: String,
+    }
 
-    Ok((fpath, lnum))
+    let InnerParams { cwd, branch } = msg.params.parse()?;
+    let cwd = paths::expand_tilde(cwd);
+
+    match crate::tools::git::checkout(&cwd, &branch).await {
+        Ok(()) => Ok(json!({})),
+        Err(err) => Ok(json!({ "error": err.to_string() })),
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn parse_quickdix_display_line_works() {
-        let line = "test/bench/python/test_fuzzy_filter.vim|0 col 0| Modified 2月,13 2021 10:58:27 rw-rw-r--";
-        assert_eq!(
-            parse_quickfix_entry(line).unwrap(),
-            ("test/bench/python/test_fuzzy_filter.vim", 0usize)
-        );
+pub async fn file_info(msg: RpcRequest) -> Result<Value, Error> {
+    #[derive(Deserialize)]
+    struct InnerParams {
+        cwd: String,
+        paths: Vec<String>,
     }
+
+    let InnerParams { cwd, paths } = msg.params.parse()?;
+
+    let file_info = tokio::task::spawn_blocking(move || {
+        crate::file_info::collect_file_info(&paths::expand_tilde(cwd), &paths)
+    })
+    .await
+    .map_err(|err| Error::Other(err.to_string()))?;
+
+    Ok(json!(file_info))
 }
@@ -0,0 +1,257 @@
+//! This module runs the heavy maintenance tasks in the background only when the user
+//! is idle, i.e., no message from Vim has been processed for a while.
+//!
+//! A running task pauses as soon as the user interacts again, the external command of
+//! the current step is killed, and it will be resumed on the next idle period.
+
+use crate::config::IdleSchedulerConfig;
+use crate::datastore::{CACHE_INFO_IN_MEMORY, RECENT_FILES_IN_MEMORY};
+use crate::stdio_server::job;
+use crate::tools::ctags::ProjectCtagsCommand;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+static LAST_ACTIVITY: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant::now()));
+
+/// Records the interaction of the user, which pauses the running maintenance task.
+pub fn touch() {
+    *LAST_ACTIVITY.lock() = Instant::now();
+}
+
+fn idle_duration() -> Duration {
+    LAST_ACTIVITY.lock().elapsed()
+}
+
+/// Returns `true` if the user has interacted since `start`.
+fn interrupted_since(start: Instant) -> bool {
+    is_interrupted(*LAST_ACTIVITY.lock(), start)
+}
+
+/// Returns `true` if the last activity is after the task started at `start`.
+fn is_interrupted(last_activity: Instant, start: Instant) -> bool {
+    last_activity > start
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum MaintenanceTask {
+    /// Removes the invalid and old cache digests along with the cache files.
+    CacheGc,
+    /// Recomputes the frecent scores of the recent files as time goes by.
+    FrecencyDecay,
+    /// Refreshes the grep cache of the recently visited projects.
+    IndexRefresh,
+    /// Rebuilds the project tags cache of the recently visited projects.
+    TagsRebuild,
+}
+
+impl MaintenanceTask {
+    const ALL: [Self; 4] = [
+        Self::CacheGc,
+        Self::FrecencyDecay,
+        Self::IndexRefresh,
+        Self::TagsRebuild,
+    ];
+
+    /// Minimum interval between two runs of the task.
+    fn interval(&self) -> Duration {
+        const HOUR: Duration = Duration::from_secs(3600);
+        match self {
+            Self::CacheGc => 6 * HOUR,
+            Self::FrecencyDecay => HOUR,
+            Self::IndexRefresh | Self::TagsRebuild => 2 * HOUR,
+        }
+    }
+
+    /// Runs the task, returns `false` if it's paused by the user interaction.
+    fn run(self, start: Instant) -> bool {
+        match self {
+            // The snapshot is written to the disk after the lock is released.
+            Self::CacheGc => {
+                let cache_info = {
+                    let mut cache_info = CACHE_INFO_IN_MEMORY.lock();
+                    cache_info.remove_invalid_and_old_entries();
+                    cache_info.clone()
+                };
+                if let Err(err) = crate::datastore::store_cache_info(&cache_info) {
+                    tracing::error!(?err, "Failed to store the cache info");
+                }
+                if interrupted_since(start) {
+                    return false;
+                }
+                crate::cache::remove_corrupted_entries();
+                true
+            }
+            Self::FrecencyDecay => {
+                let recent_files = {
+                    let mut recent_files = RECENT_FILES_IN_MEMORY.lock();
+                    recent_files.decay_frecency();
+                    recent_files.clone()
+                };
+                if let Err(err) = crate::datastore::store_recent_files(&recent_files) {
+                    tracing::error!(?err, "Failed to store the recent files");
+                }
+                true
+            }
+            Self::IndexRefresh | Self::TagsRebuild => {
+                /// The caches executed longer ago than this are refreshed.
+                const REFRESH_AGE_HOURS: i64 = 12;
+                /// The caches not visited in this period are left to expire.
+                const MAX_UNVISITED_DAYS: i64 = 3;
+
+                let now = chrono::Utc::now();
+                let digests = CACHE_INFO_IN_MEMORY.lock().to_digests();
+                for digest in digests {
+                    if now.signed_duration_since(digest.execution_time).num_hours()
+                        < REFRESH_AGE_HOURS
+                        || now.signed_duration_since(digest.last_visit).num_days()
+                            > MAX_UNVISITED_DAYS
+                    {
+                        continue;
+                    }
+
+                    if interrupted_since(start) {
+                        return false;
+                    }
+
+                    // The running command is killed as soon as the user interacts again.
+                    let interrupted = || interrupted_since(start);
+                    let dir = digest.shell_cmd.dir.clone();
+                    let result = match self {
+                        Self::IndexRefresh
+                            if digest.shell_cmd == crate::tools::rg::rg_shell_command(&dir) =>
+                        {
+                            crate::tools::rg::refresh_cache_interruptible(&dir, interrupted)
+                                .map(|_| ())
+                        }
+                        Self::TagsRebuild => {
+                            let mut ctags_cmd = ProjectCtagsCommand::with_cwd(dir.clone());
+                            if ctags_cmd.shell_cmd() != &digest.shell_cmd {
+                                continue;
+                            }
+                            ctags_cmd
+                                .par_create_cache_interruptible(interrupted)
+                                .map(|_| ())
+                        }
+                        _ => continue,
+                    };
+                    match result {
+                        Ok(()) => {}
+                        Err(err) if err.kind() == std::io::ErrorKind::Interrupted => return false,
+                        Err(err) => {
+                            tracing::debug!(?err, ?dir, task = ?self, "Failed to refresh the cache");
+                        }
+                    }
+                }
+                true
+            }
+        }
+    }
+}
+
+/// Scheduler running the maintenance tasks in the idle periods.
+#[derive(Debug)]
+pub struct IdleScheduler {
+    idle_timeout: Duration,
+    /// Time of the last complete run of each task.
+    last_runs: HashMap<MaintenanceTask, Instant>,
+}
+
+impl IdleScheduler {
+    pub fn new(config: &IdleSchedulerConfig) -> Self {
+        Self {
+            idle_timeout: Duration::from_secs(config.idle_seconds()),
+            last_runs: HashMap::new(),
+        }
+    }
+
+    /// Returns the task which is due the most at `now`.
+    fn next_due_task(&self, now: Instant) -> Option<MaintenanceTask> {
+        MaintenanceTask::ALL
+            .into_iter()
+            .filter(|task| {
+                self.last_runs.get(task).map_or(true, |last_run| {
+                    now.saturating_duration_since(*last_run) >= task.interval()
+                })
+            })
+            .min_by_key(|task| self.last_runs.get(task).copied())
+    }
+
+    pub async fn run(mut self) {
+        loop {
+            let idle = idle_duration();
+            if idle < self.idle_timeout {
+                tokio::time::sleep(self.idle_timeout - idle).await;
+                continue;
+            }
+
+            let Some(task) = self.next_due_task(Instant::now()) else {
+                tokio::time::sleep(self.idle_timeout).await;
+                continue;
+            };
+
+            let start = Instant::now();
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            job::spawn_background(move || {
+                let _ = tx.send(task.run(start));
+            });
+
+            match rx.await {
+                Ok(true) => {
+                    tracing::debug!(?task, elapsed = ?start.elapsed(), "Finished the maintenance task");
+                    self.last_runs.insert(task, Instant::now());
+                }
+                Ok(false) => {
+                    tracing::debug!(?task, "Paused the maintenance task on user interaction");
+                }
+                Err(_) => {
+                    // The task panicked, it's retried after the interval as if it finished.
+                    tracing::error!(?task, "The maintenance task panicked");
+                    self.last_runs.insert(task, Instant::now());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_due_task() {
+        let now = Instant::now();
+        let mut scheduler = IdleScheduler::new(&IdleSchedulerConfig::default());
+        assert_eq!(scheduler.next_due_task(now), Some(MaintenanceTask::CacheGc));
+
+        for task in MaintenanceTask::ALL {
+            scheduler.last_runs.insert(task, now);
+        }
+        assert_eq!(scheduler.next_due_task(now), None);
+
+        scheduler.last_runs.remove(&MaintenanceTask::IndexRefresh);
+        assert_eq!(
+            scheduler.next_due_task(now),
+            Some(MaintenanceTask::IndexRefresh)
+        );
+
+        // FrecencyDecay has the shortest interval.
+        scheduler
+            .last_runs
+            .insert(MaintenanceTask::IndexRefresh, now);
+        assert_eq!(
+            scheduler.next_due_task(now + MaintenanceTask::FrecencyDecay.interval()),
+            Some(MaintenanceTask::FrecencyDecay)
+        );
+    }
+
+    #[test]
+    fn test_is_interrupted() {
+        let before = Instant::now();
+        let start = before + Duration::from_secs(1);
+        assert!(!is_interrupted(before, start));
+        assert!(!is_interrupted(start, start));
+        assert!(is_interrupted(start + Duration::from_secs(1), start));
+    }
+}
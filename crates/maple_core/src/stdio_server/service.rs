@@ -6,6 +6,7 @@ use crate::stdio_server::input::{
 };
 use crate::stdio_server::plugin::{ActionType, ClapPlugin, PluginId};
 use crate::stdio_server::provider::{ClapProvider, Context, ProviderId};
+use rpc::Params;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::fmt::Debug;
@@ -65,6 +66,13 @@ impl ProviderSession {
             } else {
                 self.run_event_loop_without_debounce().await;
             }
+
+            tokio::task::spawn_blocking(|| {
+                let usage_stats = crate::datastore::USAGE_STATS_IN_MEMORY.lock();
+                if let Err(err) = crate::datastore::store_usage_stats(&usage_stats) {
+                    tracing::error!(?err, "Failed to store the usage stats");
+                }
+            });
         });
     }
 
@@ -114,7 +122,8 @@ impl ProviderSession {
                                         }
                                     }
                                 }
-                                ProviderEvent::Exit => {
+                                ProviderEvent::Exit(params) => {
+                                    self.ctx.remember_sticky_query(params);
                                     self.provider.on_terminate(&mut self.ctx, self.provider_session_id);
                                     break;
                                 }
@@ -126,11 +135,7 @@ impl ProviderSession {
                                     on_typed_dirty = true;
                                     on_typed_timer.as_mut().reset(Instant::now() + on_typed_delay);
                                 }
-                                ProviderEvent::Key(key_event) => {
-                                    if let Err(err) = self.provider.on_key_event(&mut self.ctx, key_event).await {
-                                        tracing::error!(?err, "Failed to process key_event");
-                                    }
-                                }
+                                event => self.handle_event(event).await,
                             }
                           }
                           None => break, // channel has closed.
@@ -148,11 +153,16 @@ impl ProviderSession {
                     on_typed_dirty = false;
                     on_typed_timer.as_mut().reset(Instant::now() + NEVER);
 
+                    self.ctx.preview_manager.cancel_prefetch();
                     let _ = self.ctx.record_input().await;
 
+                    let now = Instant::now();
                     if let Err(err) = self.provider.on_typed(&mut self.ctx).await {
                         tracing::error!(?err, "Failed to process ProviderEvent::OnTyped");
                     }
+                    self.record_typed(now.elapsed());
+
+                    let _ = self.ctx.restore_sticky_lnum().await;
 
                     let _ = self.provider.on_move(&mut self.ctx).await;
                 }
@@ -170,7 +180,8 @@ impl ProviderSession {
                         break;
                     }
                 }
-                ProviderEvent::Exit => {
+                ProviderEvent::Exit(params) => {
+                    self.ctx.remember_sticky_query(params);
                     self.provider
                         .on_terminate(&mut self.ctx, self.provider_session_id);
                     break;
@@ -181,20 +192,73 @@ impl ProviderSession {
                     }
                 }
                 ProviderEvent::OnTyped(_params) => {
+                    self.ctx.preview_manager.cancel_prefetch();
                     let _ = self.ctx.record_input().await;
+                    let now = Instant::now();
                     if let Err(err) = self.provider.on_typed(&mut self.ctx).await {
                         tracing::debug!(?err, "Failed to process OnTyped");
                     }
+                    self.record_typed(now.elapsed());
+                    let _ = self.ctx.restore_sticky_lnum().await;
                 }
-                ProviderEvent::Key(key_event) => {
-                    if let Err(err) = self.provider.on_key_event(&mut self.ctx, key_event).await {
-                        tracing::error!(?err, "Failed to process key_event");
-                    }
+                event => self.handle_event(event).await,
+            }
+        }
+    }
+
+    /// Handles the event processed the same with or without debounce.
+    async fn handle_event(&mut self, event: ProviderEvent) {
+        match event {
+            ProviderEvent::Key(key_event) => {
+                if let Err(err) = self.provider.on_key_event(&mut self.ctx, key_event).await {
+                    tracing::error!(?err, "Failed to process key_event");
+                }
+            }
+            ProviderEvent::Copy(params) => {
+                if let Err(err) = self.ctx.copy_selection(params).await {
+                    tracing::error!(?err, "Failed to copy the current item");
+                }
+            }
+            ProviderEvent::Pin(params) => {
+                if let Err(err) = self.ctx.pin_item(params).await {
+                    tracing::error!(?err, "Failed to pin the current item");
+                }
+            }
+            ProviderEvent::AcceptAtPreview => {
+                if let Err(err) = self.ctx.accept_at_preview() {
+                    tracing::error!(?err, "Failed to accept at the preview position");
+                }
+            }
+            ProviderEvent::ScrollPreview(params) => {
+                if let Err(err) = self.ctx.scroll_preview_by(params).await {
+                    tracing::error!(?err, "Failed to scroll the preview");
+                }
+            }
+            ProviderEvent::Action(params) => {
+                if let Err(err) = self.provider.on_action(&mut self.ctx, params).await {
+                    tracing::error!(?err, "Failed to perform the provider action");
+                }
+            }
+            ProviderEvent::DisplayMetricsChanged(params) => {
+                if let Err(err) = self.ctx.update_display_metrics(params).await {
+                    tracing::error!(?err, "Failed to update the display metrics");
                 }
             }
+            ProviderEvent::Internal(_)
+            | ProviderEvent::Exit(_)
+            | ProviderEvent::OnMove(_)
+            | ProviderEvent::OnTyped(_) => {
+                // Handled by the event loops themselves.
+            }
         }
     }
 
+    fn record_typed(&self, elapsed: Duration) {
+        crate::datastore::USAGE_STATS_IN_MEMORY
+            .lock()
+            .record_typed(self.ctx.provider_id(), elapsed);
+    }
+
     /// Handles the internal provider event, returns an optional new debounce delay when the
     /// control flow continues.
     async fn handle_internal_event(
@@ -209,8 +273,15 @@ impl ProviderSession {
             }
             InternalProviderEvent::Initialize => {
                 // Primarily initialize the provider source.
-                match self.provider.on_initialize(&mut self.ctx).await {
+                let now = Instant::now();
+                let initialized = self.provider.on_initialize(&mut self.ctx).await;
+                crate::datastore::USAGE_STATS_IN_MEMORY
+                    .lock()
+                    .record_session(self.ctx.provider_id(), now.elapsed());
+                match initialized {
                     Ok(()) => {
+                        self.ctx.watch_files();
+
                         // Try to fulfill the preview window
                         if let Err(err) = self.provider.on_move(&mut self.ctx).await {
                             tracing::debug!(
@@ -229,6 +300,14 @@ impl ProviderSession {
 
                 ControlFlow::Continue(maybe_new_debounce)
             }
+            InternalProviderEvent::FilesChanged(changes) => {
+                if self.ctx.on_files_changed(&changes) {
+                    if let Err(err) = self.provider.on_move(&mut self.ctx).await {
+                        tracing::debug!(?err, "Failed to refresh the outdated preview");
+                    }
+                }
+                ControlFlow::Continue(None)
+            }
             InternalProviderEvent::InitialQuery(initial_query) => {
                 let _ = self
                     .provider
@@ -459,7 +538,7 @@ impl ServiceManager {
 
     pub fn try_exit(&mut self, provider_session_id: ProviderSessionId) {
         if self.exists(provider_session_id) {
-            self.notify_provider_exit(provider_session_id);
+            self.notify_provider_exit(provider_session_id, Params::None);
         }
     }
 
@@ -477,9 +556,9 @@ impl ServiceManager {
     }
 
     /// Stop the session task by sending [`ProviderEvent::Exit`].
-    pub fn notify_provider_exit(&mut self, provider_session_id: ProviderSessionId) {
+    pub fn notify_provider_exit(&mut self, provider_session_id: ProviderSessionId, params: Params) {
         if let Some(sender) = self.providers.remove(&provider_session_id) {
-            sender.send(ProviderEvent::Exit);
+            sender.send(ProviderEvent::Exit(params));
         }
     }
 }
@@ -1,4 +1,4 @@
-use crate::stdio_server::provider::ProviderId;
+use crate::stdio_server::provider::{NoMatchesHints, ProviderId};
 use once_cell::sync::{Lazy, OnceCell};
 use paths::AbsPathBuf;
 use printer::DisplayLines;
@@ -169,12 +169,61 @@ impl PreviewConfig {
 pub struct VimProgressor {
     vim: Vim,
     stopped: Arc<AtomicBool>,
+    /// Whether to show the top match as the ghost text near the input.
+    ghost_text: bool,
+    /// Hints displayed below the no matches message.
+    no_matches_hints: NoMatchesHints,
 }
 
 impl VimProgressor {
     pub fn new(vim: Vim, stopped: Arc<AtomicBool>) -> Self {
-        Self { vim, stopped }
+        Self {
+            vim,
+            stopped,
+            ghost_text: false,
+            no_matches_hints: NoMatchesHints::default(),
+        }
+    }
+
+    pub fn with_no_matches_hints(mut self, no_matches_hints: NoMatchesHints) -> Self {
+        self.no_matches_hints = no_matches_hints;
+        self
+    }
+
+    /// Shows the top match as the ghost text near the input if enabled in the config.
+    pub fn with_top_match_ghost_text(mut self) -> Self {
+        self.ghost_text = crate::config::config().provider.top_match_ghost_text;
+        self
     }
+
+    fn update_ghost_text(&self, display_lines: &DisplayLines) {
+        if self.ghost_text {
+            let _ = self.vim.exec(
+                "clap#state#set_ghost_text",
+                [top_match_ghost_text(display_lines)],
+            );
+        }
+    }
+}
+
+/// Returns the text of top match in the display lines, without the icon.
+///
+/// The display line has been truncated to fit the window width by the printer.
+fn top_match_ghost_text(display_lines: &DisplayLines) -> &str {
+    display_lines
+        .lines
+        .first()
+        .map(|line| {
+            if display_lines.icon_added {
+                line.char_indices()
+                    .nth(icon::ICON_CHAR_LEN)
+                    .map(|(byte_idx, _)| &line[byte_idx..])
+                    .unwrap_or_default()
+            } else {
+                line.as_str()
+            }
+        })
+        .unwrap_or_default()
 }
 
 impl SearchProgressUpdate<DisplayLines> for VimProgressor {
@@ -202,6 +251,7 @@ impl SearchProgressUpdate<DisplayLines> for VimProgressor {
             "clap#state#process_progress_full",
             (display_lines, total_matched, total_processed),
         );
+        self.update_ghost_text(display_lines);
     }
 
     fn on_finished(
@@ -213,10 +263,28 @@ impl SearchProgressUpdate<DisplayLines> for VimProgressor {
         if self.stopped.load(Ordering::Relaxed) {
             return;
         }
-        let _ = self.vim.exec(
-            "clap#state#process_progress_full",
-            (display_lines, total_matched, total_processed),
-        );
+        let no_matches_hints = if total_matched == 0 {
+            self.no_matches_hints.hints()
+        } else {
+            Vec::new()
+        };
+        if !no_matches_hints.is_empty() {
+            let _ = self.vim.exec(
+                "clap#state#process_progress_full",
+                (
+                    &display_lines,
+                    total_matched,
+                    total_processed,
+                    no_matches_hints,
+                ),
+            );
+        } else {
+            let _ = self.vim.exec(
+                "clap#state#process_progress_full",
+                (&display_lines, total_matched, total_processed),
+            );
+        }
+        self.update_ghost_text(&display_lines);
     }
 }
 
@@ -446,6 +514,24 @@ impl Vim {
         }
     }
 
+    /// Returns the lines in `[start, end]` of the display window, with icon stripped.
+    pub async fn display_get_origin_lines(
+        &self,
+        start: usize,
+        end: usize,
+    ) -> VimResult<Vec<String>> {
+        let (lines, icon_added_by_maple): (Vec<String>, bool) =
+            self.call("display_get_origin_lines", [start, end]).await?;
+        if icon_added_by_maple {
+            Ok(lines
+                .into_iter()
+                .map(|line| line.chars().skip(2).collect())
+                .collect())
+        } else {
+            Ok(lines)
+        }
+    }
+
     pub async fn display_getcurlnum(&self) -> VimResult<usize> {
         self.eval("g:clap.display.getcurlnum()").await
     }
@@ -573,4 +659,32 @@ mod tests {
         let v: Value = serde_json::json!({"filer": 10, "files": 5});
         let _config: PreviewConfig = v.into();
     }
+
+    #[test]
+    fn test_top_match_ghost_text() {
+        let display_lines = DisplayLines::new(
+            vec![
+                "\u{e7a8} src/main.rs".to_string(),
+                "\u{e7a8} src/lib.rs".to_string(),
+            ],
+            Vec::new(),
+            Default::default(),
+            true,
+        );
+        assert_eq!(top_match_ghost_text(&display_lines), "src/main.rs");
+
+        let display_lines = DisplayLines::new(
+            vec!["src/main.rs:1:1:fn main() {}".to_string()],
+            Vec::new(),
+            Default::default(),
+            false,
+        );
+        assert_eq!(
+            top_match_ghost_text(&display_lines),
+            "src/main.rs:1:1:fn main() {}"
+        );
+
+        let display_lines = DisplayLines::new(Vec::new(), Vec::new(), Default::default(), true);
+        assert_eq!(top_match_ghost_text(&display_lines), "");
+    }
 }
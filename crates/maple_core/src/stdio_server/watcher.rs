@@ -0,0 +1,610 @@
+//! This module provides the file system watching for invalidating the outdated caches.
+//!
+//! A directory is watched once and shared by the provider sessions, which can
+//! [`subscribe`] to the changes in it.
+//!
+//! Two backends are supported:
+//!
+//! - Native: the recommended watcher of the OS provided by [`notify`].
+//! - Watchman: an external `watchman` process, which scales better on the monorepos
+//!   where the inotify watch limit can be exhausted easily.
+//!
+//! If the preferred backend is not usable, the other one will be used automatically.
+
+use crate::config::WatcherBackend;
+use crate::datastore::CACHE_INFO_IN_MEMORY;
+use crate::UtcTime;
+use dirs::Dirs;
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, thiserror::Error)]
+pub enum WatcherError {
+    #[error("watchman error: {0}")]
+    Watchman(String),
+    #[error(transparent)]
+    Notify(#[from] notify::Error),
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// Change of a file on the disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: PathBuf,
+    /// Whether the file is created, removed or renamed, i.e., the list of files is changed
+    /// rather than the content of the file.
+    pub structural: bool,
+}
+
+/// Callback invoked with the files changed on the disk.
+pub type OnChange = Arc<dyn Fn(Vec<FileChange>) + Send + Sync + 'static>;
+
+/// Active file watcher, the watching stops once dropped.
+pub enum FileWatcher {
+    Native(notify::RecommendedWatcher),
+    Watchman(Child),
+}
+
+impl std::fmt::Debug for FileWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Native(_) => f.write_str("FileWatcher::Native"),
+            Self::Watchman(child) => write!(f, "FileWatcher::Watchman({})", child.id()),
+        }
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        if let Self::Watchman(child) = self {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl FileWatcher {
+    /// Starts watching `dir` recursively using the preferred `backend`, falling back
+    /// to the other backend if the preferred one is unusable.
+    pub fn new(
+        dir: &Path,
+        backend: WatcherBackend,
+        on_change: OnChange,
+    ) -> Result<Self, WatcherError> {
+        match backend {
+            WatcherBackend::Native => match Self::native(dir, on_change.clone()) {
+                Ok(watcher) => Ok(watcher),
+                Err(WatcherError::Notify(err))
+                    if matches!(err.kind, notify::ErrorKind::MaxFilesWatch) && *WATCHMAN_EXISTS =>
+                {
+                    tracing::debug!(?dir, "OS watch limit reached, falling back to watchman");
+                    Self::watchman(dir, on_change)
+                }
+                Err(err) => Err(err),
+            },
+            WatcherBackend::Watchman => {
+                if !*WATCHMAN_EXISTS {
+                    tracing::debug!("watchman is unavailable, falling back to the native watcher");
+                    return Self::native(dir, on_change);
+                }
+                Self::watchman(dir, on_change.clone()).or_else(|err| {
+                    tracing::debug!(
+                        ?err,
+                        "Failed to start watchman, falling back to the native watcher"
+                    );
+                    Self::native(dir, on_change)
+                })
+            }
+        }
+    }
+
+    fn native(dir: &Path, on_change: OnChange) -> Result<Self, WatcherError> {
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+                Ok(event) => {
+                    if !event.kind.is_access() && !event.paths.is_empty() {
+                        let structural = !matches!(
+                            event.kind,
+                            EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Metadata(_))
+                        );
+                        on_change(
+                            event
+                                .paths
+                                .into_iter()
+                                .map(|path| FileChange { path, structural })
+                                .collect(),
+                        );
+                    }
+                }
+                Err(err) => tracing::error!(?err, "Error occurred in the native watcher"),
+            })?;
+        watcher.watch(dir, RecursiveMode::Recursive)?;
+        Ok(Self::Native(watcher))
+    }
+
+    fn watchman(dir: &Path, on_change: OnChange) -> Result<Self, WatcherError> {
+        let output = Command::new("watchman")
+            .arg("watch-project")
+            .arg(dir)
+            .stderr(Stdio::null())
+            .output()?;
+        let watch_project: Value = serde_json::from_slice(&output.stdout)?;
+
+        if let Some(err) = watch_project.get("error").and_then(|e| e.as_str()) {
+            return Err(WatcherError::Watchman(err.to_string()));
+        }
+
+        let Some(watch_root) = watch_project.get("watch").and_then(|w| w.as_str()) else {
+            return Err(WatcherError::Watchman(format!(
+                "unexpected output of watch-project: {watch_project}"
+            )));
+        };
+
+        let mut subscription = json!({ "fields": ["name", "exists", "new"] });
+        if let Some(relative_path) = watch_project.get("relative_path") {
+            subscription["relative_root"] = relative_path.clone();
+        }
+
+        let mut child = Command::new("watchman")
+            .args(["-j", "-p", "--no-pretty"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            let subscribe = json!(["subscribe", watch_root, "vim-clap", subscription]);
+            stdin.write_all(subscribe.to_string().as_bytes())?;
+        }
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| WatcherError::Watchman("stdout of watchman unavailable".to_string()))?;
+
+        let dir = dir.to_path_buf();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if let Some(changed_paths) = parse_watchman_subscription(&line, &dir) {
+                    on_change(changed_paths);
+                }
+            }
+        });
+
+        Ok(Self::Watchman(child))
+    }
+}
+
+static WATCHMAN_EXISTS: Lazy<bool> = Lazy::new(|| {
+    Command::new("watchman")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|exit_status| exit_status.success())
+        .unwrap_or(false)
+});
+
+/// Parses the unilateral subscription response of watchman, returns the changes of the files
+/// with the absolute paths.
+///
+/// Returns `None` if the response is not a change notification.
+fn parse_watchman_subscription(line: &str, dir: &Path) -> Option<Vec<FileChange>> {
+    let value: Value = serde_json::from_str(line).ok()?;
+
+    value.get("subscription")?;
+
+    // The initial response contains all the files in the directory.
+    if value
+        .get("is_fresh_instance")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    {
+        return None;
+    }
+
+    let changes = value
+        .get("files")?
+        .as_array()?
+        .iter()
+        .filter_map(|file| {
+            let name = file.get("name")?.as_str()?;
+            let exists = file.get("exists").and_then(Value::as_bool).unwrap_or(true);
+            let new = file.get("new").and_then(Value::as_bool).unwrap_or(false);
+            Some(FileChange {
+                path: dir.join(name),
+                // A rename is reported as the removal of old name and the creation of new name.
+                structural: !exists || new,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if changes.is_empty() {
+        None
+    } else {
+        Some(changes)
+    }
+}
+
+/// Senders of the changes to the subscribers of a watched directory, keyed by the
+/// subscription id.
+type Subscribers = Arc<Mutex<HashMap<u64, Sender<Vec<FileChange>>>>>;
+
+/// Callback of a subscriber, returns `false` once the changes are no longer wanted,
+/// which ends the subscription.
+pub type OnSubscribedChange = Box<dyn Fn(Vec<FileChange>) -> bool + Send + 'static>;
+
+struct WatchedDir {
+    _watcher: FileWatcher,
+    subscribers: Subscribers,
+    /// Time since which the changes in the directory have been observed.
+    watched_since: UtcTime,
+    /// Whether the provider caches created in the directory rely on the watcher, which
+    /// is kept even if no one subscribes to the changes then.
+    watch_caches: bool,
+}
+
+/// Directories being watched for the provider cache invalidation.
+static CACHE_WATCHERS: Lazy<Mutex<HashMap<PathBuf, WatchedDir>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_SUBSCRIPTION_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Changes within this window are delivered to the subscriber in one batch.
+const SUBSCRIPTION_BATCH_WINDOW: Duration = Duration::from_millis(300);
+
+/// A batch is delivered after this delay at most even if the changes keep coming.
+const SUBSCRIPTION_MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// Returns `true` if the change of `path` has no effect on the provider caches.
+fn is_irrelevant_change(path: &Path) -> bool {
+    path.components().any(|c| c.as_os_str() == ".git")
+        // The cache files of vim-clap can be under the watched directory.
+        || path.starts_with(Dirs::cache_dir())
+        || path.starts_with(Dirs::data_dir())
+}
+
+/// Sends `changes` to the subscribers, the subscribers which have gone are dropped.
+///
+/// Returns `true` if all the subscribers have gone.
+fn notify_subscribers(subscribers: &Subscribers, changes: Vec<FileChange>) -> bool {
+    let mut subscribers = subscribers.lock();
+    if subscribers.is_empty() {
+        return false;
+    }
+    subscribers.retain(|_, sender| sender.send(changes.clone()).is_ok());
+    subscribers.is_empty()
+}
+
+/// Starts watching `dir` if not yet, returns `false` if the watcher is disabled or failed
+/// to start.
+fn ensure_watching(dir: &Path, watch_caches: bool) -> bool {
+    let watcher_config = &crate::config::config().watcher;
+
+    if !watcher_config.enable {
+        return false;
+    }
+
+    start_watching(dir, watcher_config.backend, watch_caches)
+}
+
+fn start_watching(dir: &Path, backend: WatcherBackend, watch_caches: bool) -> bool {
+    if let Some(watched_dir) = CACHE_WATCHERS.lock().get_mut(dir) {
+        watched_dir.watch_caches |= watch_caches;
+        return true;
+    }
+
+    let subscribers: Subscribers = Arc::new(Mutex::new(HashMap::new()));
+
+    let on_change: OnChange = {
+        let subscribers = subscribers.clone();
+        let dir = dir.to_path_buf();
+        Arc::new(move |changes: Vec<FileChange>| {
+            let changes = changes
+                .into_iter()
+                .filter(|change| !is_irrelevant_change(&change.path))
+                .collect::<Vec<_>>();
+
+            if changes.is_empty() {
+                return;
+            }
+
+            for FileChange { path, .. } in &changes {
+                match CACHE_INFO_IN_MEMORY.lock().invalidate_outdated(path) {
+                    Ok(true) => tracing::debug!(?path, "Invalidated the outdated provider cache"),
+                    Ok(false) => {}
+                    Err(err) => tracing::error!(?err, "Failed to invalidate the provider cache"),
+                }
+            }
+
+            if notify_subscribers(&subscribers, changes) {
+                // The watcher can not be stopped in its own callback.
+                let dir = dir.clone();
+                std::thread::spawn(move || unwatch_if_unsubscribed(&dir));
+            }
+        })
+    };
+
+    // The lock is not held while starting the watcher, which may sync with watchman.
+    match FileWatcher::new(dir, backend, on_change) {
+        Ok(watcher) => {
+            let mut watchers = CACHE_WATCHERS.lock();
+            if let Some(watched_dir) = watchers.get_mut(dir) {
+                // Watched by another caller in the meantime, the redundant watcher is
+                // stopped once dropped after the lock is released.
+                watched_dir.watch_caches |= watch_caches;
+                drop(watchers);
+                drop(watcher);
+                return true;
+            }
+            tracing::debug!(
+                ?dir,
+                ?watcher,
+                "Started watching the provider cache directory"
+            );
+            watchers.insert(
+                dir.to_path_buf(),
+                WatchedDir {
+                    _watcher: watcher,
+                    subscribers,
+                    watched_since: chrono::Utc::now(),
+                    watch_caches,
+                },
+            );
+            true
+        }
+        Err(err) => {
+            tracing::error!(?err, ?dir, "Failed to watch the provider cache directory");
+            false
+        }
+    }
+}
+
+/// Stops watching `dir` if neither the subscribers nor the provider caches need it.
+fn unwatch_if_unsubscribed(dir: &Path) {
+    let unwatched = {
+        let mut watchers = CACHE_WATCHERS.lock();
+        match watchers.get(dir) {
+            Some(watched_dir)
+                if !watched_dir.watch_caches && watched_dir.subscribers.lock().is_empty() =>
+            {
+                watchers.remove(dir)
+            }
+            _ => None,
+        }
+    };
+
+    // The watcher is stopped once dropped, which may wait for watchman, outside the lock.
+    if let Some(watched_dir) = unwatched {
+        tracing::debug!(?dir, watcher = ?watched_dir._watcher, "Stopped watching the directory");
+    }
+}
+
+/// Starts watching `dir` if not yet, the provider caches created in `dir` will be
+/// invalidated once any file in it is changed.
+pub fn watch_provider_cache_dir(dir: &Path) {
+    ensure_watching(dir, true);
+}
+
+/// Returns `true` if the changes in `dir` have been observed since `time`, i.e., the
+/// provider caches created in `dir` after `time` are up to date unless invalidated.
+pub fn is_watched_since(dir: &Path, time: UtcTime) -> bool {
+    CACHE_WATCHERS
+        .lock()
+        .get(dir)
+        .map_or(false, |watched_dir| watched_dir.watched_since <= time)
+}
+
+/// Subscription to the changes of a watched directory, unsubscribed once dropped.
+pub struct Subscription {
+    id: u64,
+    dir: PathBuf,
+}
+
+impl std::fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Subscription({})", self.id)
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(watched_dir) = CACHE_WATCHERS.lock().get(&self.dir) {
+            watched_dir.subscribers.lock().remove(&self.id);
+        }
+        unwatch_if_unsubscribed(&self.dir);
+    }
+}
+
+/// Subscribes to the changes of the files in `dir`, which is watched on demand and no
+/// longer once all the subscriptions are gone.
+///
+/// The changes are batched and deduplicated before being passed to `on_change`, which
+/// runs on a dedicated thread.
+pub fn subscribe(dir: &Path, on_change: OnSubscribedChange) -> Option<Subscription> {
+    if !ensure_watching(dir, false) {
+        return None;
+    }
+    add_subscriber(dir, on_change)
+}
+
+/// Adds a subscriber to the watched `dir`, returns `None` if `dir` is not watched.
+fn add_subscriber(dir: &Path, on_change: OnSubscribedChange) -> Option<Subscription> {
+    let (sender, receiver) = std::sync::mpsc::channel::<Vec<FileChange>>();
+
+    let id = NEXT_SUBSCRIPTION_ID.fetch_add(1, Ordering::SeqCst);
+    CACHE_WATCHERS
+        .lock()
+        .get(dir)?
+        .subscribers
+        .lock()
+        .insert(id, sender);
+
+    // The thread exits once the sender is dropped on unsubscribing, or once `on_change`
+    // no longer wants the changes, the sender is dropped on the next change then.
+    std::thread::spawn(move || {
+        while let Ok(first_batch) = receiver.recv() {
+            let deadline = Instant::now() + SUBSCRIPTION_MAX_DELAY;
+            let mut changes = first_batch;
+            loop {
+                let window = deadline
+                    .saturating_duration_since(Instant::now())
+                    .min(SUBSCRIPTION_BATCH_WINDOW);
+                if window.is_zero() {
+                    break;
+                }
+                match receiver.recv_timeout(window) {
+                    Ok(more) => changes.extend(more),
+                    Err(_) => break,
+                }
+            }
+            if !on_change(dedup_changes(changes)) {
+                break;
+            }
+        }
+    });
+
+    Some(Subscription {
+        id,
+        dir: dir.to_path_buf(),
+    })
+}
+
+/// Merges the changes of the same file, which is structural if any of them is.
+fn dedup_changes(mut changes: Vec<FileChange>) -> Vec<FileChange> {
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes.dedup_by(|next, prev| {
+        if next.path == prev.path {
+            prev.structural |= next.structural;
+            true
+        } else {
+            false
+        }
+    });
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_watchman_subscription() {
+        let dir = Path::new("/home/user/project");
+
+        let subscribe_response =
+            r#"{"version":"2023.01.30.00","subscribe":"vim-clap","clock":"c:1:2"}"#;
+        assert_eq!(parse_watchman_subscription(subscribe_response, dir), None);
+
+        let fresh_instance = r#"{"subscription":"vim-clap","root":"/home/user/project","is_fresh_instance":true,"files":["src/main.rs"]}"#;
+        assert_eq!(parse_watchman_subscription(fresh_instance, dir), None);
+
+        let changes = r#"{"subscription":"vim-clap","root":"/home/user/project","is_fresh_instance":false,"files":[{"name":"src/main.rs","exists":true,"new":false},{"name":"README.md","exists":false,"new":false}]}"#;
+        assert_eq!(
+            parse_watchman_subscription(changes, dir),
+            Some(vec![
+                FileChange {
+                    path: PathBuf::from("/home/user/project/src/main.rs"),
+                    structural: false,
+                },
+                FileChange {
+                    path: PathBuf::from("/home/user/project/README.md"),
+                    structural: true,
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_dedup_changes() {
+        let change = |path: &str, structural| FileChange {
+            path: PathBuf::from(path),
+            structural,
+        };
+        assert_eq!(
+            dedup_changes(vec![
+                change("b", false),
+                change("a", false),
+                change("b", true),
+                change("a", false),
+            ]),
+            vec![change("a", false), change("b", true)]
+        );
+    }
+
+    #[test]
+    fn test_notify_subscribers() {
+        let change = FileChange {
+            path: PathBuf::from("a"),
+            structural: false,
+        };
+        let subscribers: Subscribers = Arc::new(Mutex::new(HashMap::new()));
+        assert!(!notify_subscribers(&subscribers, vec![change.clone()]));
+
+        let (alive, alive_receiver) = std::sync::mpsc::channel();
+        let (gone, gone_receiver) = std::sync::mpsc::channel();
+        subscribers.lock().insert(0, alive);
+        subscribers.lock().insert(1, gone);
+        drop(gone_receiver);
+
+        assert!(!notify_subscribers(&subscribers, vec![change.clone()]));
+        assert_eq!(alive_receiver.recv().unwrap(), vec![change.clone()]);
+        assert_eq!(subscribers.lock().keys().collect::<Vec<_>>(), vec![&0]);
+
+        drop(alive_receiver);
+        assert!(notify_subscribers(&subscribers, vec![change]));
+        assert!(subscribers.lock().is_empty());
+    }
+
+    #[test]
+    fn test_is_watched_since() {
+        let dir = std::env::temp_dir().join(format!("clap_watched_since_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let before_watching = chrono::Utc::now() - chrono::Duration::seconds(1);
+        assert!(!is_watched_since(&dir, before_watching));
+
+        assert!(start_watching(&dir, WatcherBackend::Native, true));
+        // The cache created before watching may miss the changes in between.
+        assert!(!is_watched_since(&dir, before_watching));
+        assert!(is_watched_since(&dir, chrono::Utc::now()));
+
+        CACHE_WATCHERS.lock().remove(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unwatch_once_unsubscribed() {
+        let dir = std::env::temp_dir().join(format!("clap_watcher_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        assert!(start_watching(&dir, WatcherBackend::Native, false));
+        let subscription = add_subscriber(&dir, Box::new(|_| true)).unwrap();
+        assert!(CACHE_WATCHERS.lock().contains_key(&dir));
+
+        drop(subscription);
+        assert!(!CACHE_WATCHERS.lock().contains_key(&dir));
+
+        // The watcher of the provider caches is kept.
+        assert!(start_watching(&dir, WatcherBackend::Native, true));
+        drop(add_subscriber(&dir, Box::new(|_| true)).unwrap());
+        assert!(CACHE_WATCHERS.lock().contains_key(&dir));
+
+        CACHE_WATCHERS.lock().remove(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,406 @@
+//! Test double of Vim for testing the plugins and providers headlessly.
+//!
+//! [`MockVim`] plays the Vim side of the RPC channel: it records every call from
+//! the Rust backend and answers the requests with the scripted responses. Enabled in
+//! the unit tests and by the `testing` feature for the downstream crates.
+
+#[cfg(test)]
+use crate::stdio_server::Context;
+use crate::stdio_server::Vim;
+use parking_lot::Mutex;
+use rpc::{Error, ErrorCode, Failure, Params, RpcClient, RpcMessage, RpcResponse, Success};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufReader, Read, Write};
+#[cfg(test)]
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+
+pub use crate::config::initialize_config_for_test as initialize_config;
+
+/// Window id of the display window in the mock Vim.
+pub const DISPLAY_WINID: usize = 1000;
+/// Window id of the preview window in the mock Vim.
+pub const PREVIEW_WINID: usize = 1001;
+
+type Responder = Box<dyn Fn(&[Value]) -> Option<Value> + Send>;
+
+/// A call from the Rust backend to Vim.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VimCall {
+    pub method: String,
+    pub params: Vec<Value>,
+    /// `false` if the call is a notification, i.e., `Vim::exec()`.
+    pub is_request: bool,
+}
+
+#[derive(Default)]
+struct MockVimState {
+    /// Responders of each method, the latest registered one is tried first.
+    responders: HashMap<String, Vec<Responder>>,
+    calls: Vec<VimCall>,
+    /// Bumped whenever the lines of a buffer are set.
+    changedtick: usize,
+}
+
+impl MockVimState {
+    fn respond(&self, method: &str, params: &[Value]) -> Option<Value> {
+        self.responders
+            .get(method)?
+            .iter()
+            .rev()
+            .find_map(|responder| responder(params))
+    }
+}
+
+/// Mock Vim with the scripted responses.
+///
+/// The default responses describe a Vim with an 80x20 display window and a 120x20
+/// preview window and no modified buffers, other requests without a scripted response are answered with
+/// the `MethodNotFound` error.
+#[derive(Clone)]
+pub struct MockVim {
+    state: Arc<Mutex<MockVimState>>,
+}
+
+impl Default for MockVim {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockVim {
+    pub fn new() -> Self {
+        let mock_vim = Self {
+            state: Default::default(),
+        };
+
+        mock_vim
+            .with_winsize(DISPLAY_WINID, 80, 20)
+            .with_winsize(PREVIEW_WINID, 120, 20)
+            .with_response("has", json!(0))
+            .with_response("clap#preview#is_enabled", json!(1))
+            .with_response("clap#preview#direction", json!("UD"))
+            .with_response("clap#api#buffer_content", Value::Null)
+            .with_response("clap#api#buffer_changedtick", Value::Null)
+            .with_eval("g:clap_popup_border", json!("nil"))
+            .with_eval("g:clap.preview.winid", json!(PREVIEW_WINID))
+            .with_input("")
+            .with_display_lines(Vec::new(), 1)
+    }
+
+    /// Answers the requests of `method` using `responder`, `None` falls back to the
+    /// previously registered responders.
+    pub fn with_responder(
+        self,
+        method: impl Into<String>,
+        responder: impl Fn(&[Value]) -> Option<Value> + Send + 'static,
+    ) -> Self {
+        self.state
+            .lock()
+            .responders
+            .entry(method.into())
+            .or_default()
+            .push(Box::new(responder));
+        self
+    }
+
+    /// Answers the requests of `method` with `value` regardless of the params.
+    pub fn with_response(self, method: impl Into<String>, value: Value) -> Self {
+        self.with_responder(method, move |_| Some(value.clone()))
+    }
+
+    /// Answers `eval(expr)` with `value`.
+    pub fn with_eval(self, expr: &str, value: Value) -> Self {
+        let expr = Value::from(expr);
+        self.with_responder("eval", move |params| {
+            (params.first() == Some(&expr)).then(|| value.clone())
+        })
+    }
+
+    /// Sets the size of window `winid`.
+    pub fn with_winsize(self, winid: usize, width: usize, height: usize) -> Self {
+        let is_target = move |params: &[Value]| params.first() == Some(&Value::from(winid));
+        self.with_responder("winwidth", move |params| {
+            is_target(params).then(|| json!(width))
+        })
+        .with_responder("winheight", move |params| {
+            is_target(params).then(|| json!(height))
+        })
+    }
+
+    /// Pretends to be Neovim 0.9+.
+    pub fn nvim(self) -> Self {
+        self.with_response("has", json!(1))
+    }
+
+    /// Sets the content of the input window.
+    pub fn with_input(self, query: &str) -> Self {
+        self.with_eval("g:clap.input.get()", json!(query))
+    }
+
+    /// Sets the lines of the display window and the cursor line number (1-based).
+    pub fn with_display_lines(self, lines: Vec<String>, curlnum: usize) -> Self {
+        let curline = lines
+            .get(curlnum.saturating_sub(1))
+            .cloned()
+            .unwrap_or_default();
+        self.with_eval("g:clap.display.getcurlnum()", json!(curlnum))
+            .with_response("display_getcurline", json!([curline, false]))
+    }
+
+    /// Sets the lines of buffer `bufnr` for `getbufline()` and `getbufoneline()`, the
+    /// changedtick of the buffer is bumped as well.
+    pub fn with_buffer(self, bufnr: usize, lines: Vec<String>) -> Self {
+        let lines = Arc::new(lines);
+        let is_target = move |params: &[Value]| params.first() == Some(&Value::from(bufnr));
+
+        let changedtick = {
+            let mut state = self.state.lock();
+            state.changedtick += 1;
+            state.changedtick
+        };
+
+        let line_number = |value: Option<&Value>, total: usize| match value {
+            Some(Value::String(s)) if s == "$" => Some(total),
+            Some(value) => value.as_u64().map(|n| n as usize),
+            None => None,
+        };
+
+        self.with_responder("getbufvar", move |params| {
+            (is_target(params) && params.get(1) == Some(&json!("changedtick")))
+                .then(|| json!(changedtick))
+        })
+        .with_responder("getbufline", {
+            let lines = lines.clone();
+            move |params| {
+                if !is_target(params) {
+                    return None;
+                }
+                let start = line_number(params.get(1), lines.len())?.max(1);
+                let end = line_number(params.get(2), lines.len())
+                    .unwrap_or(start)
+                    .min(lines.len());
+                Some(json!(lines.get(start - 1..end).unwrap_or_default()))
+            }
+        })
+        .with_responder("getbufoneline", move |params| {
+            if !is_target(params) {
+                return None;
+            }
+            let lnum = line_number(params.get(1), lines.len())?;
+            Some(json!(lnum
+                .checked_sub(1)
+                .and_then(|idx| lines.get(idx))
+                .cloned()
+                .unwrap_or_default()))
+        })
+    }
+
+    /// Returns a [`Vim`] connected to this mock, which must be called in a tokio runtime.
+    pub fn vim(&self) -> Vim {
+        let (sender, receiver) = channel();
+        let reader = MockVimReader {
+            receiver,
+            chunk: Vec::new(),
+            pos: 0,
+        };
+        let writer = MockVimWriter {
+            buf: Vec::new(),
+            sender,
+            state: self.state.clone(),
+        };
+        // No message is initiated from the mock Vim.
+        let (sink, _) = tokio::sync::mpsc::unbounded_channel();
+        Vim::new(Arc::new(RpcClient::new(
+            BufReader::new(reader),
+            writer,
+            sink,
+        )))
+    }
+
+    /// Builds a provider [`Context`] against this mock.
+    ///
+    /// The global config has to be initialized by [`initialize_config`] first. Only
+    /// available in the unit tests as [`Context`] is internal to this crate.
+    #[cfg(test)]
+    pub(crate) async fn context(
+        &self,
+        provider_id: &str,
+        cwd: &Path,
+        start_buffer_path: &Path,
+    ) -> Context {
+        let params = json!({
+            "provider_id": provider_id,
+            "start": { "bufnr": 1, "winid": 999 },
+            "input": { "bufnr": 2, "winid": 998 },
+            "display": { "bufnr": 3, "winid": DISPLAY_WINID },
+            "cwd": cwd,
+            "icon": "",
+            "no_cache": false,
+            "start_buffer_path": start_buffer_path,
+            "source_is_list": false,
+        });
+        let Value::Object(params) = params else {
+            unreachable!("params is a map")
+        };
+
+        Context::new(Params::Map(params), self.vim())
+            .await
+            .expect("Failed to build Context against the mock Vim")
+    }
+
+    /// Returns all the calls received so far.
+    pub fn calls(&self) -> Vec<VimCall> {
+        self.state.lock().calls.clone()
+    }
+
+    /// Returns the params of the notifications of `method` received so far.
+    pub fn notifications(&self, method: &str) -> Vec<Vec<Value>> {
+        self.state
+            .lock()
+            .calls
+            .iter()
+            .filter(|call| !call.is_request && call.method == method)
+            .map(|call| call.params.clone())
+            .collect()
+    }
+}
+
+/// Reads the responses from the mock Vim.
+struct MockVimReader {
+    receiver: Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for MockVimReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pos == self.chunk.len() {
+            self.chunk = match self.receiver.recv() {
+                Ok(chunk) => chunk,
+                // The writer is gone, emit a notification to stop the reader loop as the
+                // message sink has been closed.
+                Err(_) => b"{\"method\":\"exit\",\"params\":[]}\n".to_vec(),
+            };
+            self.pos = 0;
+        }
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Receives the messages sent to Vim, records and answers them.
+struct MockVimWriter {
+    buf: Vec<u8>,
+    sender: Sender<Vec<u8>>,
+    state: Arc<Mutex<MockVimState>>,
+}
+
+impl Write for MockVimWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+
+        while let Some(end) = self.buf.iter().position(|&b| b == b'\n') {
+            let line = self.buf.drain(..=end).collect::<Vec<_>>();
+
+            // Skip the `Content-length` header.
+            let Ok(message) = serde_json::from_slice::<RpcMessage>(&line) else {
+                continue;
+            };
+
+            let into_vec = |params: Params| match params {
+                Params::Array(params) => params,
+                Params::Map(map) => vec![Value::Object(map)],
+                Params::None => Vec::new(),
+            };
+
+            let mut state = self.state.lock();
+
+            match message {
+                RpcMessage::Notification(notification) => {
+                    state.calls.push(VimCall {
+                        method: notification.method,
+                        params: into_vec(notification.params),
+                        is_request: false,
+                    });
+                }
+                RpcMessage::Request(request) => {
+                    let params = into_vec(request.params);
+
+                    let response = match state.respond(&request.method, &params) {
+                        Some(result) => RpcResponse::Success(Success {
+                            result,
+                            id: request.id,
+                        }),
+                        None => RpcResponse::Failure(Failure {
+                            id: request.id,
+                            error: Error {
+                                code: ErrorCode::MethodNotFound,
+                                message: format!(
+                                    "No scripted response for {}({params:?})",
+                                    request.method
+                                ),
+                                data: None,
+                            },
+                        }),
+                    };
+
+                    state.calls.push(VimCall {
+                        method: request.method,
+                        params,
+                        is_request: true,
+                    });
+
+                    let mut response = serde_json::to_vec(&response)?;
+                    response.push(b'\n');
+                    self.sender
+                        .send(response)
+                        .map_err(|_| std::io::ErrorKind::BrokenPipe)?;
+                }
+                RpcMessage::Response(_) => {}
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_vim() {
+        let mock_vim = MockVim::new()
+            .with_winsize(42, 100, 30)
+            .with_buffer(7, vec!["a".into(), "b".into(), "c".into()]);
+        let vim = mock_vim.vim();
+
+        assert_eq!(vim.winwidth(42).await.unwrap(), 100);
+        assert_eq!(vim.winwidth(PREVIEW_WINID).await.unwrap(), 120);
+        assert_eq!(
+            vim.getbufline(7, 2, "$").await.unwrap(),
+            vec!["b".to_string(), "c".to_string()]
+        );
+        assert_eq!(vim.getbufoneline(7, 1).await.unwrap(), "a");
+        assert!(vim.bufname(7).await.is_err());
+
+        vim.exec("clap#state#render_preview", json!({"lines": ["x"]}))
+            .unwrap();
+        vim.bare_call::<String>("input_get").await.ok();
+
+        assert_eq!(
+            mock_vim.notifications("clap#state#render_preview"),
+            vec![vec![json!({"lines": ["x"]})]]
+        );
+        assert_eq!(mock_vim.calls().len(), 7);
+    }
+}
@@ -1,7 +1,8 @@
+use super::TagSignatures;
 use itertools::Itertools;
 use matcher::MatchScope;
 use serde::{Deserialize, Serialize};
-use types::{ClapItem, FuzzyText};
+use types::{ClapItem, FuzzyText, ItemHighlight};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
 #[serde(rename_all = "camelCase")]
@@ -28,11 +29,18 @@ impl BufferTag {
     }
 
     /// Returns the display line for BuiltinHandle, no icon attached.
-    pub fn format_buffer_tag(&self, max_name_len: usize) -> String {
+    ///
+    /// The signature of the function in `signatures` of the buffer takes the place of the
+    /// pattern if any.
+    pub fn format_buffer_tag(&self, max_name_len: usize, signatures: &TagSignatures) -> String {
+        self.format_with_signature(max_name_len, signatures.get(&self.kind, self.line_number))
+    }
+
+    fn format_with_signature(&self, max_name_len: usize, signature: Option<&str>) -> String {
         let name_line = format!("{}:{}", self.name, self.line_number);
 
         let kind = format!("[{}]", self.kind);
-        let pattern = super::trim_pattern(&self.pattern);
+        let pattern = signature.unwrap_or_else(|| super::trim_pattern(&self.pattern));
         format!(
             "{name_group:<name_group_width$} {kind:<kind_width$} {pattern}",
             name_group = name_line,
@@ -42,12 +50,18 @@ impl BufferTag {
         )
     }
 
-    pub fn into_buffer_tag_item(self, max_name_len: usize) -> BufferTagItem {
-        let output_text = self.format_buffer_tag(max_name_len);
+    pub fn into_buffer_tag_item(
+        self,
+        max_name_len: usize,
+        signatures: &TagSignatures,
+    ) -> BufferTagItem {
+        let signature = signatures.get(&self.kind, self.line_number);
+        let output_text = self.format_with_signature(max_name_len, signature);
         BufferTagItem {
             pattern: self.pattern,
             name: self.name,
             output_text,
+            signature_len: signature.map_or(0, |signature| signature.len()),
         }
     }
 
@@ -96,6 +110,35 @@ impl BufferTag {
             None
         }
     }
+
+    /// Returns the scopes enclosing this tag, from the outermost to the innermost.
+    ///
+    /// The nested scopes are joined by `::` or `.` in ctags depending on the language,
+    /// only the kind of innermost scope is known.
+    pub fn scope_chain(&self) -> Vec<String> {
+        let Some(Scope { scope, scope_kind }) = &self.scope else {
+            return Vec::new();
+        };
+
+        let mut chain = scope
+            .split("::")
+            .flat_map(|s| s.split('.'))
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        if let Some(innermost) = chain.last_mut() {
+            let short_kind = match scope_kind.as_str() {
+                "implementation" => "impl",
+                "function" => "fn",
+                "module" => "mod",
+                kind => kind,
+            };
+            *innermost = format!("{short_kind} {innermost}");
+        }
+
+        chain
+    }
 }
 
 #[derive(Debug)]
@@ -103,6 +146,8 @@ pub struct BufferTagItem {
     pub pattern: String,
     pub name: String,
     pub output_text: String,
+    /// Length of the signature at the end of `output_text`, 0 if none.
+    pub signature_len: usize,
 }
 
 impl ClapItem for BufferTagItem {
@@ -121,11 +166,28 @@ impl ClapItem for BufferTagItem {
     fn bonus_text(&self) -> &str {
         &self.pattern
     }
+
+    fn highlights(&self) -> Vec<ItemHighlight> {
+        signature_highlight(&self.output_text, self.signature_len)
+    }
+}
+
+/// Returns the highlight of the signature at the end of the display line.
+pub(super) fn signature_highlight(output_text: &str, signature_len: usize) -> Vec<ItemHighlight> {
+    if signature_len == 0 {
+        return Vec::new();
+    }
+    vec![ItemHighlight {
+        start: output_text.len() - signature_len,
+        length: signature_len,
+        group: "ClapTagSignature",
+    }]
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tree_sitter::Language;
 
     #[test]
     fn test_parse_ctags_raw() {
@@ -145,6 +207,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_scope_chain() {
+        let line = r#"with_dir	crates/maple_core/src/tools/ctags/mod.rs	/^    pub fn with_dir(dir: P) -> Self {$/;"	method	line:150	implementation:TagsGenerator"#;
+        let tag = BufferTag::from_ctags_raw(line).unwrap();
+        assert_eq!(tag.scope_chain(), vec!["impl TagsGenerator".to_string()]);
+
+        let tag = BufferTag {
+            scope: Some(Scope {
+                scope: "ctags::tests::Foo".to_string(),
+                scope_kind: "struct".to_string(),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            tag.scope_chain(),
+            vec![
+                "ctags".to_string(),
+                "tests".to_string(),
+                "struct Foo".to_string()
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_ctags_json() {
         let json_line = r#"
@@ -164,4 +249,36 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_format_buffer_tag_signature() {
+        let line = r#"with_dir	crates/maple_core/src/tools/ctags/mod.rs	/^    pub fn with_dir(dir: P) -> Self {$/;"	method	line:150	implementation:TagsGenerator"#;
+        let tag = BufferTag::from_ctags_raw(line).unwrap();
+
+        let source = format!(
+            "{}impl TagsGenerator {{\n    pub fn with_dir(dir: P) -> Self {{\n    }}\n}}\n",
+            "\n".repeat(148)
+        );
+        let signatures = TagSignatures::new(Language::Rust, source.as_bytes());
+
+        let item = tag.clone().into_buffer_tag_item(8, &signatures);
+        assert_eq!(
+            item.output_text,
+            "with_dir:150   [method]   (dir: P) -> Self"
+        );
+        assert_eq!(
+            item.highlights(),
+            vec![ItemHighlight {
+                start: 26,
+                length: 16,
+                group: "ClapTagSignature"
+            }]
+        );
+
+        // Fallback to the pattern for the unknown language.
+        assert_eq!(
+            tag.format_buffer_tag(8, &TagSignatures::default()),
+            "with_dir:150   [method]   pub fn with_dir(dir: P) -> Self {"
+        );
+    }
 }
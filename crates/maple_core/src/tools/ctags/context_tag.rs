@@ -1,8 +1,7 @@
-use super::BufferTag;
-use crate::tools::ctags::CTAGS_HAS_JSON_FEATURE;
+use super::{BufferTag, TagSignatures};
+use crate::tools::project_tools::project_tools;
 use rayon::prelude::*;
 use std::io::Result;
-use std::ops::Deref;
 use std::path::Path;
 use std::process::Stdio;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -27,15 +26,33 @@ const CONTEXT_SUPERSET: &[&str] = &[
     "macro",
     "implementation",
     "interface",
+    "class",
     "struct",
     "field",
     "typedef",
     "enumerator",
 ];
 
+/// Returns the ctags program to execute for `file`.
+fn ctags_program(file: &Path) -> String {
+    project_tools(file).ctags.program("ctags").to_string()
+}
+
+/// Extra ctags arguments overridden in the project containing `file`.
+fn ctags_args(file: &Path) -> Vec<String> {
+    project_tools(file).ctags.args.clone()
+}
+
+/// Whether the ctags of the project containing `file` supports the JSON output.
+fn ctags_has_json_feature(file: &std::ffi::OsStr) -> bool {
+    project_tools(Path::new(file)).ctags_has_json_feature
+}
+
 fn subprocess_cmd_in_json_format(file: impl AsRef<std::ffi::OsStr>) -> SubprocessCommand {
     // Redirect stderr otherwise the warning message might occur `ctags: Warning: ignoring null tag...`
-    SubprocessCommand::cmd("ctags")
+    let file = file.as_ref();
+    SubprocessCommand::cmd(ctags_program(Path::new(file)))
+        .args(&ctags_args(Path::new(file)))
         .stderr(Redirection::None)
         .arg("--fields=+n")
         .arg("--output-format=json")
@@ -44,7 +61,9 @@ fn subprocess_cmd_in_json_format(file: impl AsRef<std::ffi::OsStr>) -> Subproces
 
 fn subprocess_cmd_in_raw_format(file: impl AsRef<std::ffi::OsStr>) -> SubprocessCommand {
     // Redirect stderr otherwise the warning message might occur `ctags: Warning: ignoring null tag...`
-    SubprocessCommand::cmd("ctags")
+    let file = file.as_ref();
+    SubprocessCommand::cmd(ctags_program(Path::new(file)))
+        .args(&ctags_args(Path::new(file)))
         .stderr(Redirection::None)
         .arg("--fields=+Kn")
         .arg("-f")
@@ -53,8 +72,9 @@ fn subprocess_cmd_in_raw_format(file: impl AsRef<std::ffi::OsStr>) -> Subprocess
 }
 
 fn tokio_cmd_in_json_format(file: &Path) -> TokioCommand {
-    let mut tokio_cmd = TokioCommand::new("ctags");
+    let mut tokio_cmd = TokioCommand::new(ctags_program(file));
     tokio_cmd
+        .args(ctags_args(file))
         .stderr(Stdio::null())
         .arg("--fields=+n")
         .arg("--output-format=json")
@@ -63,8 +83,9 @@ fn tokio_cmd_in_json_format(file: &Path) -> TokioCommand {
 }
 
 fn tokio_cmd_in_raw_format(file: &Path) -> TokioCommand {
-    let mut tokio_cmd = TokioCommand::new("ctags");
+    let mut tokio_cmd = TokioCommand::new(ctags_program(file));
     tokio_cmd
+        .args(ctags_args(file))
         .stderr(Stdio::null())
         .arg("--fields=+Kn")
         .arg("-f")
@@ -93,9 +114,43 @@ fn find_context_tag(superset_tags: Vec<BufferTag>, at: usize) -> Option<BufferTa
     }
 }
 
+/// Returns the context tag of line `at` and its enclosing tags up to `depth` in total,
+/// from the outermost to the innermost.
+///
+/// The parent of a tag is the nearest preceding tag matching its innermost scope.
+fn find_context_tags(superset_tags: Vec<BufferTag>, at: usize, depth: usize) -> Vec<BufferTag> {
+    let Some(innermost) = find_context_tag(superset_tags.clone(), at) else {
+        return Vec::new();
+    };
+
+    let mut context_tags = vec![innermost];
+
+    while context_tags.len() < depth {
+        let child = &context_tags[context_tags.len() - 1];
+        let Some(scope) = &child.scope else {
+            break;
+        };
+        let Some(parent_name) = scope.scope.rsplit(|c| c == ':' || c == '.').next() else {
+            break;
+        };
+        let maybe_parent = superset_tags
+            .iter()
+            .rev()
+            .filter(|tag| tag.line_number < child.line_number)
+            .find(|tag| tag.name == parent_name && tag.kind == scope.scope_kind);
+        match maybe_parent {
+            Some(parent) => context_tags.push(parent.clone()),
+            None => break,
+        }
+    }
+
+    context_tags.reverse();
+    context_tags
+}
+
 /// Async version of [`current_context_tag`].
 pub async fn current_context_tag_async(file: &Path, at: usize) -> Option<BufferTag> {
-    let superset_tags = if *CTAGS_HAS_JSON_FEATURE.deref() {
+    let superset_tags = if ctags_has_json_feature(file.as_ref()) {
         let cmd = tokio_cmd_in_json_format(file);
         collect_superset_context_tags_async(cmd, BufferTag::from_ctags_json, at)
             .await
@@ -110,9 +165,24 @@ pub async fn current_context_tag_async(file: &Path, at: usize) -> Option<BufferT
     find_context_tag(superset_tags, at)
 }
 
+/// Async version of [`find_context_tags`] for the tags in `file`.
+pub async fn context_tags_async(file: &Path, at: usize, depth: usize) -> Vec<BufferTag> {
+    let superset_tags = if ctags_has_json_feature(file.as_ref()) {
+        let cmd = tokio_cmd_in_json_format(file);
+        collect_superset_context_tags_async(cmd, BufferTag::from_ctags_json, at).await
+    } else {
+        let cmd = tokio_cmd_in_raw_format(file);
+        collect_superset_context_tags_async(cmd, BufferTag::from_ctags_raw, at).await
+    };
+
+    superset_tags
+        .map(|tags| find_context_tags(tags, at, depth))
+        .unwrap_or_default()
+}
+
 /// Returns the method/function context associated with line `at`.
 pub fn current_context_tag(file: &Path, at: usize) -> Option<BufferTag> {
-    let superset_tags = if *CTAGS_HAS_JSON_FEATURE.deref() {
+    let superset_tags = if ctags_has_json_feature(file.as_ref()) {
         let cmd = subprocess_cmd_in_json_format(file);
         collect_superset_context_tags(cmd, BufferTag::from_ctags_json, at).ok()?
     } else {
@@ -127,7 +197,9 @@ pub fn buffer_tags_lines(
     file: impl AsRef<std::ffi::OsStr>,
     force_raw: bool,
 ) -> Result<Vec<String>> {
-    let (tags, max_name_len) = if *CTAGS_HAS_JSON_FEATURE.deref() && !force_raw {
+    let signatures = TagSignatures::parse(Path::new(file.as_ref()));
+
+    let (tags, max_name_len) = if ctags_has_json_feature(file.as_ref()) && !force_raw {
         let cmd = subprocess_cmd_in_json_format(file);
         collect_buffer_tags(cmd, BufferTag::from_ctags_json)?
     } else {
@@ -137,12 +209,12 @@ pub fn buffer_tags_lines(
 
     Ok(tags
         .par_iter()
-        .map(|s| s.format_buffer_tag(max_name_len))
+        .map(|s| s.format_buffer_tag(max_name_len, &signatures))
         .collect::<Vec<_>>())
 }
 
 pub fn fetch_buffer_tags(file: impl AsRef<std::ffi::OsStr>) -> Result<Vec<BufferTag>> {
-    let (mut tags, _max_name_len) = if *CTAGS_HAS_JSON_FEATURE.deref() {
+    let (mut tags, _max_name_len) = if ctags_has_json_feature(file.as_ref()) {
         let cmd = subprocess_cmd_in_json_format(file);
         collect_buffer_tags(cmd, BufferTag::from_ctags_json)?
     } else {
@@ -159,7 +231,9 @@ pub fn buffer_tag_items(
     file: impl AsRef<std::ffi::OsStr>,
     force_raw: bool,
 ) -> Result<Vec<Arc<dyn ClapItem>>> {
-    let (tags, max_name_len) = if *CTAGS_HAS_JSON_FEATURE.deref() && !force_raw {
+    let signatures = TagSignatures::parse(Path::new(file.as_ref()));
+
+    let (tags, max_name_len) = if ctags_has_json_feature(file.as_ref()) && !force_raw {
         let cmd = subprocess_cmd_in_json_format(file);
         collect_buffer_tags(cmd, BufferTag::from_ctags_json)?
     } else {
@@ -169,7 +243,9 @@ pub fn buffer_tag_items(
 
     Ok(tags
         .into_par_iter()
-        .map(|tag| Arc::new(tag.into_buffer_tag_item(max_name_len)) as Arc<dyn ClapItem>)
+        .map(|tag| {
+            Arc::new(tag.into_buffer_tag_item(max_name_len, &signatures)) as Arc<dyn ClapItem>
+        })
         .collect::<Vec<_>>())
 }
 
@@ -237,3 +313,38 @@ async fn collect_superset_context_tags_async(
 
     Ok(tags)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::ctags::Scope;
+
+    #[test]
+    fn test_find_context_tags() {
+        let tag = |name: &str, line_number, kind: &str, scope: Option<(&str, &str)>| BufferTag {
+            name: name.into(),
+            pattern: format!("/^{name}$/"),
+            line_number,
+            kind: kind.into(),
+            scope: scope.map(|(scope, scope_kind)| Scope {
+                scope: scope.into(),
+                scope_kind: scope_kind.into(),
+            }),
+        };
+        let superset_tags = vec![
+            tag("outer", 1, "module", None),
+            tag("Foo", 3, "implementation", Some(("outer", "module"))),
+            tag("bar", 5, "method", Some(("outer::Foo", "implementation"))),
+        ];
+
+        let names = |depth| {
+            find_context_tags(superset_tags.clone(), 7, depth)
+                .into_iter()
+                .map(|tag| tag.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(1), ["bar"]);
+        assert_eq!(names(2), ["Foo", "bar"]);
+        assert_eq!(names(5), ["outer", "Foo", "bar"]);
+    }
+}
@@ -2,7 +2,11 @@ mod buffer_tag;
 mod context_tag;
 mod project_tag;
 
-use crate::process::ShellCommand;
+use crate::config::ToolCommandConfig;
+use crate::process::limits;
+use crate::process::{shell_command, ShellCommand};
+use crate::tools::project_tools::project_tools;
+use crate::tools::symbols::MAX_TREE_SITTER_FILE_SIZE;
 use dirs::Dirs;
 use itertools::Itertools;
 use once_cell::sync::Lazy;
@@ -13,12 +17,14 @@ use std::hash::Hash;
 use std::io::{BufRead, BufReader, Error, ErrorKind, Result};
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
-use subprocess::{Exec, NullFile};
+use std::process::Stdio;
+use subprocess::Exec;
+use tree_sitter::Language;
 
 pub use self::buffer_tag::{BufferTag, BufferTagItem, Scope};
 pub use self::context_tag::{
-    buffer_tag_items, buffer_tags_lines, current_context_tag, current_context_tag_async,
-    fetch_buffer_tags,
+    buffer_tag_items, buffer_tags_lines, context_tags_async, current_context_tag,
+    current_context_tag_async, fetch_buffer_tags,
 };
 pub use self::project_tag::{ProjectTag, ProjectTagItem};
 
@@ -33,7 +39,7 @@ pub static DEFAULT_EXCLUDE_OPT: Lazy<String> = Lazy::new(|| {
 
 /// Directory for the `tags` files.
 pub static CTAGS_TAGS_DIR: Lazy<PathBuf> = Lazy::new(|| {
-    let tags_dir = Dirs::project().data_dir().join("tags");
+    let tags_dir = Dirs::data_dir().join("tags");
 
     std::fs::create_dir_all(&tags_dir).expect("Couldn't create tags directory for vim-clap");
 
@@ -56,23 +62,24 @@ pub static CTAGS_EXISTS: Lazy<bool> = Lazy::new(|| {
         .unwrap_or(false)
 });
 
-/// If the ctags executable supports `--output-format=json`.
-pub static CTAGS_HAS_JSON_FEATURE: Lazy<bool> = Lazy::new(|| {
-    fn detect_json_feature() -> std::io::Result<bool> {
-        let output = std::process::Command::new("ctags")
-            .arg("--list-features")
-            .stderr(std::process::Stdio::inherit())
-            .output()?;
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        if stdout.split('\n').any(|x| x.starts_with("json")) {
-            Ok(true)
-        } else {
-            Err(Error::new(ErrorKind::Other, "ctags has no +json feature"))
-        }
-    }
+/// If the default ctags executable supports `--output-format=json`.
+pub static CTAGS_HAS_JSON_FEATURE: Lazy<bool> =
+    Lazy::new(|| detect_json_feature(&ToolCommandConfig::default()));
 
-    detect_json_feature().unwrap_or(false)
-});
+/// Returns `true` if the ctags executable of `ctags` supports `--output-format=json`.
+pub(crate) fn detect_json_feature(ctags: &ToolCommandConfig) -> bool {
+    std::process::Command::new(ctags.program("ctags"))
+        .args(&ctags.args)
+        .arg("--list-features")
+        .stderr(std::process::Stdio::inherit())
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .split('\n')
+                .any(|x| x.starts_with("json"))
+        })
+        .unwrap_or(false)
+}
 
 /// Used to specify the language when working with `readtags`.
 static LANG_MAPS: Lazy<HashMap<String, String>> = Lazy::new(|| {
@@ -197,11 +204,11 @@ impl<'a, P: AsRef<Path> + Hash> TagsGenerator<'a, P> {
             cmd.push_str(&self.files.iter().map(|f| f.display()).join(" "));
         }
 
-        let exit_status = Exec::shell(&cmd)
-            .stderr(NullFile) // ignore the line: ctags: warning...
-            .cwd(self.dir.as_ref())
-            .join()
-            .map_err(|e| Error::new(ErrorKind::Other, e.to_string()))?;
+        let mut child = shell_command(&cmd)
+            .stderr(Stdio::null()) // ignore the line: ctags: warning...
+            .current_dir(self.dir.as_ref())
+            .spawn()?;
+        let exit_status = limits::wait_with_timeout(&mut child, limits::timeout())?;
 
         if !exit_status.success() {
             return Err(Error::new(ErrorKind::Other, "Failed to generate tags file"));
@@ -229,14 +236,20 @@ impl ProjectCtagsCommand {
     }
 
     pub fn with_cwd(cwd: PathBuf) -> Self {
-        let mut std_cmd = std::process::Command::new(Self::TAGS_CMD[0]);
+        let ctags = &project_tools(&cwd).ctags;
+        let mut std_cmd = std::process::Command::new(ctags.program(Self::TAGS_CMD[0]));
+        std_cmd.args(&ctags.args);
         std_cmd.current_dir(&cwd).args(&Self::TAGS_CMD[1..]).args(
             EXCLUDE
                 .split(',')
                 .map(|exclude| format!("--exclude={exclude}")),
         );
         let shell_cmd = ShellCommand::new(
-            format!("{} {}", Self::BASE_TAGS_CMD, DEFAULT_EXCLUDE_OPT.deref()),
+            format!(
+                "{} {}",
+                ctags.shell_cmd(Self::BASE_TAGS_CMD),
+                DEFAULT_EXCLUDE_OPT.deref()
+            ),
             cwd,
         );
         Self::new(std_cmd, shell_cmd)
@@ -244,17 +257,31 @@ impl ProjectCtagsCommand {
 
     /// Parallel version of [`formatted_lines`].
     pub fn par_formatted_lines(&mut self) -> Result<Vec<String>> {
+        self.std_cmd
+            .output()
+            .map(|output| Self::par_format_output(&output.stdout))
+    }
+
+    fn par_format_output(stdout: &[u8]) -> Vec<String> {
+        stdout
+            .par_split(|x| x == &b'\n')
+            .filter_map(|tag| {
+                if let Ok(tag) = serde_json::from_slice::<ProjectTag>(tag) {
+                    Some(tag.format_proj_tag())
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+    }
+
+    /// Returns all the project tags.
+    pub fn tags(&mut self) -> Result<Vec<ProjectTag>> {
         self.std_cmd.output().map(|output| {
             output
                 .stdout
                 .par_split(|x| x == &b'\n')
-                .filter_map(|tag| {
-                    if let Ok(tag) = serde_json::from_slice::<ProjectTag>(tag) {
-                        Some(tag.format_proj_tag())
-                    } else {
-                        None
-                    }
-                })
+                .filter_map(|tag| serde_json::from_slice::<ProjectTag>(tag).ok())
                 .collect::<Vec<_>>()
         })
     }
@@ -298,6 +325,10 @@ impl ProjectCtagsCommand {
         }))
     }
 
+    pub fn shell_cmd(&self) -> &ShellCommand {
+        &self.shell_cmd
+    }
+
     /// Returns a tuple of (total, cache_path) if the cache exists.
     pub fn ctags_cache(&self) -> Option<(usize, PathBuf)> {
         self.shell_cmd
@@ -327,6 +358,21 @@ impl ProjectCtagsCommand {
     pub fn par_create_cache(&mut self) -> Result<(usize, PathBuf)> {
         // TODO: do not store all the output in memory and redirect them to a file directly.
         let lines = self.par_formatted_lines()?;
+        self.write_lines_cache(lines)
+    }
+
+    /// Same as [`Self::par_create_cache`], but ctags is killed once `interrupted` returns
+    /// `true`, the previous cache is kept in that case.
+    pub fn par_create_cache_interruptible(
+        &mut self,
+        interrupted: impl Fn() -> bool,
+    ) -> Result<(usize, PathBuf)> {
+        let output =
+            limits::output_interruptible(&mut self.std_cmd, limits::timeout(), interrupted)?;
+        self.write_lines_cache(Self::par_format_output(&output.stdout))
+    }
+
+    fn write_lines_cache(&self, lines: Vec<String>) -> Result<(usize, PathBuf)> {
         let total = lines.len();
         let lines = lines.into_iter().join("\n");
 
@@ -338,7 +384,8 @@ impl ProjectCtagsCommand {
         Ok((total, cache_path))
     }
 
-    pub async fn execute_and_write_cache(mut self) -> Result<Vec<String>> {
+    /// Runs the command and writes the cache to the disk, returns the formatted lines.
+    pub fn execute_and_write_cache(mut self) -> Result<Vec<String>> {
         let lines = self.par_formatted_lines()?;
 
         {
@@ -365,3 +412,116 @@ pub fn trim_pattern(pattern: &str) -> &str {
 
     pattern.trim()
 }
+
+/// Kinds of the tags displaying the signature instead of the pattern.
+const SIGNATURE_KINDS: &[&str] = &[
+    "function",
+    "method",
+    "func",
+    "member",
+    "constructor",
+    "prototype",
+    "generator",
+];
+
+/// Returns the signature of the function tag of `kind` in the file `path`, parsed from
+/// the tag `pattern` alone.
+///
+/// The project tags span too many files to parse each of them, hence the signature is
+/// only found if it's on the tag line, the pattern is displayed otherwise.
+pub fn pattern_signature(path: &str, kind: &str, pattern: &str) -> Option<String> {
+    if !SIGNATURE_KINDS.contains(&kind) {
+        return None;
+    }
+    let language = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(Language::try_from_extension)?;
+    let line = unescape_pattern(trim_pattern(pattern));
+    tree_sitter::function_signatures(language, line.as_bytes()).remove(&0)
+}
+
+/// Unescapes `\/` and `\\` in the pattern of ctags.
+fn unescape_pattern(pattern: &str) -> String {
+    let mut unescaped = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(next @ ('/' | '\\'))) => {
+                unescaped.push(next);
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Signatures of the function tags in a source file, which is parsed once for all the
+/// tags in it.
+#[derive(Debug, Default)]
+pub struct TagSignatures(HashMap<usize, String>);
+
+impl TagSignatures {
+    pub fn new(language: Language, source: &[u8]) -> Self {
+        Self(tree_sitter::function_signatures(language, source))
+    }
+
+    /// Parses the file `path`, no signatures if the language is unsupported or the file
+    /// is too large.
+    pub fn parse(path: &Path) -> Self {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Language::try_from_extension)
+            .filter(|_| {
+                std::fs::metadata(path).map_or(false, |metadata| {
+                    metadata.len() <= MAX_TREE_SITTER_FILE_SIZE
+                })
+            })
+            .and_then(|language| Some(Self::new(language, &std::fs::read(path).ok()?)))
+            .unwrap_or_default()
+    }
+
+    /// Returns the signature of the tag of `kind` at the 1-based line `lnum`.
+    pub fn get(&self, kind: &str, lnum: usize) -> Option<&str> {
+        if !SIGNATURE_KINDS.contains(&kind) {
+            return None;
+        }
+        self.0.get(&lnum.checked_sub(1)?).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unescape_pattern() {
+        assert_eq!(
+            unescape_pattern(r#"def split(self, sep="\/", esc="\\"):"#),
+            r#"def split(self, sep="/", esc="\"):"#
+        );
+    }
+
+    #[test]
+    fn test_pattern_signature() {
+        assert_eq!(
+            pattern_signature(
+                "src/lib.rs",
+                "function",
+                r"/^pub fn parse<T>(s: &str, sep: char) -> Option<T> {$/"
+            )
+            .as_deref(),
+            Some("<T>(s: &str, sep: char) -> Option<T>")
+        );
+        assert_eq!(
+            pattern_signature("src/lib.rs", "struct", r"/^pub struct Foo {$/"),
+            None
+        );
+        // The parameters span the following lines.
+        assert_eq!(
+            pattern_signature("src/lib.rs", "function", r"/^fn parse(s: &str,$/"),
+            None
+        );
+    }
+}
@@ -1,7 +1,8 @@
+use crate::tools::symbols::{SymbolSource, WorkspaceSymbol};
 use matcher::MatchScope;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use types::{ClapItem, FuzzyText};
+use types::{ClapItem, FuzzyText, ItemHighlight};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct ProjectTag {
@@ -13,11 +14,22 @@ pub struct ProjectTag {
 }
 
 impl ProjectTag {
+    /// Returns the signature of the function parsed from the pattern.
+    fn signature(&self) -> Option<String> {
+        super::pattern_signature(&self.path, &self.kind, &self.pattern)
+    }
+
     /// Builds the line for displaying the tag info.
+    ///
+    /// The signature of the function takes the place of the pattern if any.
     pub fn format_proj_tag(&self) -> String {
+        self.format_with_signature(self.signature().as_deref())
+    }
+
+    fn format_with_signature(&self, signature: Option<&str>) -> String {
         let name_lnum = format!("{}:{}", self.name, self.line);
         let kind = format!("[{}@{}]", self.kind, self.path);
-        let pattern = super::trim_pattern(&self.pattern);
+        let pattern = signature.unwrap_or_else(|| super::trim_pattern(&self.pattern));
         format!(
             "{text:<text_width$} {kind:<kind_width$} {pattern}",
             text = name_lnum,
@@ -28,11 +40,23 @@ impl ProjectTag {
     }
 
     pub fn into_project_tag_item(self) -> ProjectTagItem {
-        let output_text = self.format_proj_tag();
+        let signature = self.signature();
+        let output_text = self.format_with_signature(signature.as_deref());
         ProjectTagItem {
             name: self.name,
             kind: self.kind,
             output_text,
+            signature_len: signature.map_or(0, |signature| signature.len()),
+        }
+    }
+
+    pub fn into_workspace_symbol(self) -> WorkspaceSymbol {
+        WorkspaceSymbol {
+            name: self.name,
+            kind: self.kind,
+            path: self.path,
+            line: self.line,
+            source: SymbolSource::Ctags,
         }
     }
 }
@@ -42,6 +66,8 @@ pub struct ProjectTagItem {
     pub name: String,
     pub kind: String,
     pub output_text: String,
+    /// Length of the signature at the end of `output_text`, 0 if none.
+    pub signature_len: usize,
 }
 
 impl ClapItem for ProjectTagItem {
@@ -60,6 +86,10 @@ impl ClapItem for ProjectTagItem {
     fn icon(&self, _icon: icon::Icon) -> Option<icon::IconType> {
         Some(icon::tags_kind_icon(&self.kind))
     }
+
+    fn highlights(&self) -> Vec<ItemHighlight> {
+        super::buffer_tag::signature_highlight(&self.output_text, self.signature_len)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,213 @@
+//! External tools pretty printing the diff, e.g., delta and difftastic.
+//!
+//! The tools are run with the configured resource limits and killed once they don't
+//! finish in time, a huge commit can not stall the preview.
+
+use crate::config::DiffPager;
+use crate::process::limits::{kill_process_group, LimitResources};
+use crate::tools::git::{check_rev, git_command};
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::process::{Child, Command};
+
+/// Timeout of the diff tools unless `process-limits.timeout` is configured.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum bytes of the pretty printed diff, the tool is killed once the output exceeds
+/// it instead of buffering the entire diff of a huge commit.
+const MAX_OUTPUT_BYTES: u64 = crate::tools::git::MAX_COMMIT_DIFF_BYTES;
+
+fn spawn_limited(cmd: &mut Command) -> Result<Child> {
+    cmd.kill_on_drop(true).limit_resources().killable().spawn()
+}
+
+/// Returns the stdout of `child`, which is killed along with its children on timeout.
+///
+/// At most [`MAX_OUTPUT_BYTES`] of the output are read, the tool still writing is killed
+/// and the truncated output is returned.
+async fn output_with_timeout(mut child: Child) -> Result<Vec<u8>> {
+    let timeout = crate::process::limits::timeout().unwrap_or(DEFAULT_TIMEOUT);
+    let pid = child.id();
+
+    let mut stdout = child.stdout.take().expect("stdout is piped; qed");
+    let mut stderr = child.stderr.take().expect("stderr is piped; qed");
+
+    // Read one more byte to tell whether the output is truncated.
+    let limit = MAX_OUTPUT_BYTES + 1;
+    let mut stdout_buf = Vec::new();
+    let mut stderr_buf = Vec::new();
+
+    let wait_output = async {
+        let read_stdout = async {
+            let n = (&mut stdout)
+                .take(limit)
+                .read_to_end(&mut stdout_buf)
+                .await?;
+            if n as u64 == limit {
+                if let Some(pid) = pid {
+                    kill_process_group(pid);
+                }
+                let _ = child.start_kill();
+            }
+            Ok::<_, Error>(())
+        };
+
+        // Stderr is drained along with stdout, otherwise the tool can be blocked forever
+        // on the full stderr pipe.
+        let read_stderr = async {
+            (&mut stderr)
+                .take(limit)
+                .read_to_end(&mut stderr_buf)
+                .await?;
+            tokio::io::copy(&mut stderr, &mut tokio::io::sink()).await?;
+            Ok::<_, Error>(())
+        };
+
+        tokio::try_join!(read_stdout, read_stderr)?;
+        child.wait().await
+    };
+
+    let status = match tokio::time::timeout(timeout, wait_output).await {
+        Ok(status) => status?,
+        Err(_) => {
+            if let Some(pid) = pid {
+                kill_process_group(pid);
+            }
+            return Err(Error::new(
+                ErrorKind::TimedOut,
+                format!("diff tool killed after timeout of {}s", timeout.as_secs()),
+            ));
+        }
+    };
+
+    if stdout_buf.len() as u64 > MAX_OUTPUT_BYTES {
+        // Keep the complete lines only.
+        stdout_buf.truncate(MAX_OUTPUT_BYTES as usize);
+        if let Some(last_newline) = stdout_buf.iter().rposition(|&b| b == b'\n') {
+            stdout_buf.truncate(last_newline + 1);
+        }
+        return Ok(stdout_buf);
+    }
+
+    if status.success() {
+        Ok(stdout_buf)
+    } else {
+        Err(Error::new(
+            ErrorKind::Other,
+            String::from_utf8_lossy(&stderr_buf).trim().to_string(),
+        ))
+    }
+}
+
+/// Returns the output of `git {git_args}` piped through `program`.
+async fn pipe_git_output(
+    git_args: &[&str],
+    program: &str,
+    args: &[String],
+    cwd: &Path,
+) -> Result<Vec<u8>> {
+    let mut git = spawn_limited(
+        Command::from(git_command(cwd))
+            .args(git_args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null()),
+    )?;
+
+    let git_stdout: Stdio = git
+        .stdout
+        .take()
+        .ok_or_else(|| Error::new(ErrorKind::Other, "stdout of git is unavailable"))?
+        .try_into()?;
+
+    let tool = spawn_limited(
+        Command::new(program)
+            .args(args)
+            .current_dir(cwd)
+            .stdin(git_stdout)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )?;
+
+    let output = output_with_timeout(tool).await;
+
+    // git is blocked on the full pipe if the tool has exited early.
+    if let Some(pid) = git.id() {
+        kill_process_group(pid);
+    }
+    let _ = git.kill().await;
+
+    output
+}
+
+/// Returns the output of `git show {rev}` pretty printed by `pager` in `width` columns.
+///
+/// Returns an error if `rev` is not a commit hash or a ref name, or if the pager is not
+/// installed or fails.
+pub async fn show_commit(
+    pager: &DiffPager,
+    rev: &str,
+    cwd: &Path,
+    width: usize,
+) -> Result<Vec<u8>> {
+    check_rev(rev)?;
+
+    match pager {
+        DiffPager::Delta => {
+            pipe_git_output(
+                &["show", "--color=always", "--end-of-options", rev],
+                "delta",
+                &["--paging=never".to_string(), format!("--width={width}")],
+                cwd,
+            )
+            .await
+        }
+        DiffPager::Difftastic => {
+            let child = spawn_limited(
+                Command::from(git_command(cwd))
+                    .args([
+                        "-c",
+                        "diff.external=difft",
+                        "show",
+                        "--ext-diff",
+                        "--end-of-options",
+                        rev,
+                    ])
+                    .env("DFT_COLOR", "always")
+                    .env("DFT_WIDTH", width.to_string())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped()),
+            )?;
+
+            output_with_timeout(child).await
+        }
+        DiffPager::Command(command) => {
+            let (program, args) = command
+                .split_first()
+                .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "empty diff pager command"))?;
+            pipe_git_output(&["show", "--end-of-options", rev], program, args, cwd).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_output_is_bounded() {
+        let child = spawn_limited(
+            Command::new("yes")
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped()),
+        )
+        .unwrap();
+        let output = output_with_timeout(child).await.unwrap();
+        assert!(!output.is_empty());
+        assert!(output.len() as u64 <= MAX_OUTPUT_BYTES);
+        assert!(output.ends_with(b"y\n"));
+    }
+}
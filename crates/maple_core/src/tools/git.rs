@@ -0,0 +1,766 @@
+//! Git command aware of the git directory outside of the work tree, e.g., the bare repo
+//! managing the dotfiles in the home directory.
+
+use crate::process::limits::LimitResources;
+use crate::tools::project_tools::project_tools;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Returns the pair of (git_dir, work_tree) if `path` is managed by a git directory
+/// outside of the work tree.
+///
+/// The git dir is either specified by `git-dir` in the project tools config or by the
+/// environment variables `GIT_DIR` and `GIT_WORK_TREE`. The nested repo with its own
+/// `.git` is not affected.
+pub fn external_git_dir(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    if paths::find_git_root(path).is_some() {
+        return None;
+    }
+
+    let from_config = project_tools(path).git_dir.clone();
+
+    from_config.or_else(|| {
+        git_dir_from_env(
+            path,
+            std::env::var_os("GIT_DIR"),
+            std::env::var_os("GIT_WORK_TREE"),
+        )
+    })
+}
+
+/// Returns the pair of (git_dir, work_tree) given by `GIT_DIR` and `GIT_WORK_TREE` if
+/// `path` is in the work tree.
+fn git_dir_from_env(
+    path: &Path,
+    git_dir: Option<OsString>,
+    work_tree: Option<OsString>,
+) -> Option<(PathBuf, PathBuf)> {
+    let (git_dir, work_tree) = (PathBuf::from(git_dir?), PathBuf::from(work_tree?));
+    path.starts_with(&work_tree).then_some((git_dir, work_tree))
+}
+
+/// Returns the root of the work tree containing `path`.
+pub fn find_work_tree(path: &Path) -> Option<PathBuf> {
+    paths::find_git_root(path)
+        .map(Path::to_path_buf)
+        .or_else(|| external_git_dir(path).map(|(_, work_tree)| work_tree))
+}
+
+/// Returns the git command executed in `cwd`, the external git dir is specified
+/// explicitly if any.
+pub fn git_command(cwd: &Path) -> Command {
+    let mut cmd = Command::new("git");
+    cmd.current_dir(cwd);
+    if let Some((git_dir, work_tree)) = external_git_dir(cwd) {
+        cmd.arg("--git-dir")
+            .arg(git_dir)
+            .arg("--work-tree")
+            .arg(work_tree);
+    }
+    cmd
+}
+
+/// Returns whether `rev` is a commit hash or a ref name, which can not be taken as an
+/// option of git.
+fn is_valid_rev(rev: &str) -> bool {
+    !rev.is_empty()
+        && !rev.starts_with('-')
+        && rev
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '.' | '_' | '-' | '~' | '^'))
+}
+
+/// Returns an error if `rev` is not a commit hash or a ref name.
+pub fn check_rev(rev: &str) -> std::io::Result<()> {
+    if is_valid_rev(rev) {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid revision: {rev}"),
+        ))
+    }
+}
+
+/// Returns the commits adding or removing `term`, i.e., `git log -S term`, formatted the
+/// same as the source of the `commits` provider.
+///
+/// Only the commits touching `path` are returned if specified. The process is killed once
+/// the returned future is dropped.
+pub async fn pickaxe_log(
+    cwd: &Path,
+    term: &str,
+    path: Option<&Path>,
+) -> std::io::Result<Vec<String>> {
+    let mut cmd = tokio::process::Command::from(git_command(cwd));
+    cmd.args([
+        "log",
+        "--color=never",
+        "--date=short",
+        "--format=%cd %h%d %s (%an)",
+    ])
+    .arg(format!("-S{term}"));
+    if let Some(path) = path {
+        cmd.arg("--follow").arg("--").arg(path);
+    }
+
+    let output = cmd
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .limit_resources()
+        .killable()
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(Into::into)
+        .collect())
+}
+
+/// Last commit touching a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastCommit {
+    pub hash: String,
+    pub author: String,
+    /// Author date in `YYYY-MM-DD`.
+    pub date: String,
+}
+
+/// Returns the last commit touching `path`, `None` if the file is not tracked.
+pub async fn last_commit(path: &Path) -> std::io::Result<Option<LastCommit>> {
+    let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Ok(None);
+    };
+
+    let output = tokio::process::Command::from(git_command(dir))
+        .args([
+            "log",
+            "-1",
+            "--color=never",
+            "--date=short",
+            "--format=%h%x00%an%x00%ad",
+        ])
+        .arg("--")
+        .arg(file_name)
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(parse_last_commit(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_last_commit(output: &str) -> Option<LastCommit> {
+    let mut fields = output.trim_end().splitn(3, '\0');
+    Some(LastCommit {
+        hash: fields.next().filter(|hash| !hash.is_empty())?.to_string(),
+        author: fields.next()?.to_string(),
+        date: fields.next()?.to_string(),
+    })
+}
+
+/// Maximum bytes of the diff read by [`show_commit`], the vendored dependencies or
+/// generated files can make a commit diff of hundreds of megabytes.
+pub const MAX_COMMIT_DIFF_BYTES: u64 = 1024 * 1024;
+
+/// Output of `git show` for previewing a commit.
+#[derive(Debug)]
+pub struct ShownCommit {
+    pub output: Vec<u8>,
+    /// Whether the diff exceeds the cap and only the diffstat is shown.
+    pub stat_only: bool,
+}
+
+/// Returns the output of `git show rev`, falling back to `git show --stat rev` once the
+/// diff exceeds `max_bytes` instead of reading the entire diff.
+///
+/// Returns an error if `rev` is not a commit hash or a ref name.
+pub async fn show_commit(cwd: &Path, rev: &str, max_bytes: u64) -> std::io::Result<ShownCommit> {
+    use tokio::io::AsyncReadExt;
+
+    check_rev(rev)?;
+
+    let mut cmd = tokio::process::Command::from(git_command(cwd));
+    let mut child = cmd
+        .args(["show", "--color=never", "--end-of-options", rev])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let mut output = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        stdout.take(max_bytes + 1).read_to_end(&mut output).await?;
+    }
+
+    if output.len() as u64 <= max_bytes {
+        crate::process::limits::wait_with_timeout_async(
+            &mut child,
+            crate::process::limits::timeout(),
+        )
+        .await?;
+        return Ok(ShownCommit {
+            output,
+            stat_only: false,
+        });
+    }
+
+    // The rest of the diff is not needed, the process is killed on drop.
+    drop(child);
+
+    let stat = tokio::process::Command::from(git_command(cwd))
+        .args(["show", "--color=never", "--stat", "--end-of-options", rev])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .limit_resources()
+        .output();
+    let output = match crate::process::limits::timeout() {
+        Some(timeout) => tokio::time::timeout(timeout, stat)
+            .await
+            .map_err(|_| crate::process::limits::timed_out_error(timeout))??,
+        None => stat.await?,
+    };
+
+    Ok(ShownCommit {
+        output: output.stdout,
+        stat_only: true,
+    })
+}
+
+/// Branch listed by `git for-each-ref`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branch {
+    /// Short name of the ref, e.g., `main` or `origin/main`.
+    pub name: String,
+    pub is_remote: bool,
+    /// Whether the branch is checked out in the work tree.
+    pub is_head: bool,
+    pub hash: String,
+    /// Committer date relative to now, e.g., `2 days ago`.
+    pub date: String,
+    pub subject: String,
+}
+
+/// Runs `git args` in `cwd`, the stderr is returned as the error on failure.
+pub async fn run_git<S: AsRef<std::ffi::OsStr>>(
+    cwd: &Path,
+    args: impl IntoIterator<Item = S>,
+) -> std::io::Result<Vec<u8>> {
+    let output = tokio::process::Command::from(git_command(cwd))
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Returns the local and remote branches, the most recently committed first.
+pub async fn branches(cwd: &Path) -> std::io::Result<Vec<Branch>> {
+    let stdout = run_git(
+        cwd,
+        [
+            "for-each-ref",
+            "--sort=-committerdate",
+            "--format=%(HEAD)%00%(refname)%00%(refname:short)%00%(objectname:short)%00%(committerdate:relative)%00%(subject)",
+            "refs/heads",
+            "refs/remotes",
+        ],
+    )
+    .await?;
+
+    Ok(String::from_utf8_lossy(&stdout)
+        .lines()
+        .filter_map(parse_branch)
+        .collect())
+}
+
+/// Checks out the branch `name`, a remote branch is checked out as a new local branch
+/// tracking it.
+pub async fn checkout(cwd: &Path, name: &str) -> std::io::Result<()> {
+    let is_local = run_git(
+        cwd,
+        [
+            "show-ref",
+            "--verify",
+            "--quiet",
+            &format!("refs/heads/{name}"),
+        ],
+    )
+    .await
+    .is_ok();
+
+    if is_local {
+        run_git(cwd, ["checkout", name]).await?;
+    } else {
+        run_git(cwd, ["checkout", "--track", name]).await?;
+    }
+
+    Ok(())
+}
+
+fn parse_branch(line: &str) -> Option<Branch> {
+    let mut fields = line.splitn(6, '\0');
+    let is_head = fields.next()? == "*";
+    let refname = fields.next()?;
+    // The symbolic ref `refs/remotes/origin/HEAD` is not a branch.
+    if refname.ends_with("/HEAD") {
+        return None;
+    }
+    Some(Branch {
+        is_remote: refname.starts_with("refs/remotes/"),
+        is_head,
+        name: fields.next()?.to_string(),
+        hash: fields.next()?.to_string(),
+        date: fields.next()?.to_string(),
+        subject: fields.next()?.to_string(),
+    })
+}
+
+/// Returns the output of `git log --oneline -n max_count rev`.
+///
+/// Returns an error if `rev` is not a commit hash or a ref name.
+pub async fn log_oneline(cwd: &Path, rev: &str, max_count: usize) -> std::io::Result<Vec<String>> {
+    check_rev(rev)?;
+
+    let output = tokio::process::Command::from(git_command(cwd))
+        .args(["log", "--oneline", "--color=never", "--no-decorate"])
+        .arg(format!("--max-count={max_count}"))
+        .arg("--end-of-options")
+        .arg(rev)
+        .arg("--")
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(Into::into)
+        .collect())
+}
+
+/// Hunk of the uncommitted changes in the work tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// Path relative to the root of the work tree.
+    pub path: String,
+    /// First line of the hunk in the new file, the line before the removed lines if
+    /// the hunk only removes lines.
+    pub lnum: usize,
+    pub added: usize,
+    pub removed: usize,
+    /// First added line, or the first removed line if nothing is added.
+    pub text: String,
+}
+
+/// Maximum bytes of the diff read by [`diff_hunks`], the hunks beyond it are dropped.
+const MAX_DIFF_HUNKS_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Returns the hunks of `git diff -U0 HEAD` in the work tree `work_tree`, the staged
+/// changes are included.
+///
+/// The process is killed once the timeout is reached or the diff exceeds
+/// [`MAX_DIFF_HUNKS_BYTES`].
+pub async fn diff_hunks(work_tree: &Path) -> std::io::Result<Vec<Hunk>> {
+    use tokio::io::AsyncReadExt;
+
+    // There is no HEAD to diff against before the first commit.
+    let has_head = run_git(work_tree, ["rev-parse", "--verify", "--quiet", "HEAD"])
+        .await
+        .is_ok();
+
+    let mut cmd = tokio::process::Command::from(git_command(work_tree));
+    cmd.args([
+        "-c",
+        "core.quotePath=false",
+        "diff",
+        "-U0",
+        "--no-color",
+        "--no-ext-diff",
+        "--no-prefix",
+    ]);
+    if has_head {
+        cmd.arg("HEAD");
+    }
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .limit_resources()
+        .spawn()?;
+
+    let read_diff = async move {
+        let mut diff = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            stdout
+                .take(MAX_DIFF_HUNKS_BYTES + 1)
+                .read_to_end(&mut diff)
+                .await?;
+        }
+
+        if diff.len() as u64 > MAX_DIFF_HUNKS_BYTES {
+            // The partial line at the cap is dropped as well.
+            let end = diff[..MAX_DIFF_HUNKS_BYTES as usize]
+                .iter()
+                .rposition(|&b| b == b'\n')
+                .map_or(0, |newline| newline + 1);
+            diff.truncate(end);
+            return Ok(diff);
+        }
+
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        Ok(diff)
+    };
+
+    let diff = match crate::process::limits::timeout() {
+        Some(timeout) => tokio::time::timeout(timeout, read_diff)
+            .await
+            .map_err(|_| crate::process::limits::timed_out_error(timeout))??,
+        None => read_diff.await?,
+    };
+
+    Ok(parse_diff_hunks(&String::from_utf8_lossy(&diff)))
+}
+
+/// Unquotes the path quoted by git in C style, e.g., `"a\"b"` or `"\303\251.rs"`.
+fn unquote_path(path: &str) -> String {
+    let Some(quoted) = path
+        .strip_prefix('"')
+        .and_then(|path| path.strip_suffix('"'))
+    else {
+        return path.to_string();
+    };
+
+    let mut bytes = Vec::with_capacity(quoted.len());
+    let mut iter = quoted.bytes().peekable();
+    while let Some(byte) = iter.next() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        match iter.next() {
+            Some(b'n') => bytes.push(b'\n'),
+            Some(b't') => bytes.push(b'\t'),
+            Some(b'r') => bytes.push(b'\r'),
+            Some(b'a') => bytes.push(0x07),
+            Some(b'b') => bytes.push(0x08),
+            Some(b'f') => bytes.push(0x0c),
+            Some(b'v') => bytes.push(0x0b),
+            Some(digit @ b'0'..=b'7') => {
+                let mut value = digit - b'0';
+                for _ in 0..2 {
+                    match iter.peek() {
+                        Some(digit @ b'0'..=b'7') => {
+                            value = value.wrapping_mul(8) + (digit - b'0');
+                            iter.next();
+                        }
+                        _ => break,
+                    }
+                }
+                bytes.push(value);
+            }
+            Some(other) => bytes.push(other),
+            None => bytes.push(b'\\'),
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn parse_diff_hunks(diff: &str) -> Vec<Hunk> {
+    // Parses `start[,count]` in the hunk header, the count is 1 if omitted.
+    fn parse_range(range: &str) -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    }
+
+    let mut hunks: Vec<Hunk> = Vec::new();
+    // `None` for the deleted file.
+    let mut path = None;
+    // Lines of the current hunk not consumed yet, the content line like `--- a` must
+    // not be taken as the file header.
+    let mut pending_lines = 0;
+
+    for line in diff.lines() {
+        if line.starts_with('\\') {
+            // `\ No newline at end of file`.
+            continue;
+        } else if pending_lines > 0 {
+            pending_lines -= 1;
+            let Some(hunk) = hunks.last_mut().filter(|hunk| hunk.text.is_empty()) else {
+                continue;
+            };
+            if let Some(added) = line.strip_prefix('+') {
+                hunk.text = added.trim().to_string();
+            } else if let Some(removed) = line.strip_prefix('-').filter(|_| hunk.added == 0) {
+                hunk.text = removed.trim().to_string();
+            }
+        } else if let Some(new_path) = line.strip_prefix("+++ ") {
+            // Git appends a tab to the path containing spaces.
+            let new_path = new_path.strip_suffix('\t').unwrap_or(new_path);
+            path = (new_path != "/dev/null").then(|| unquote_path(new_path));
+        } else if let Some(header) = line.strip_prefix("@@ -") {
+            let mut ranges = header.split(' ');
+            let (Some(Some((_, removed))), Some(Some((start, added)))) = (
+                ranges.next().map(parse_range),
+                ranges
+                    .next()
+                    .and_then(|range| range.strip_prefix('+'))
+                    .map(parse_range),
+            ) else {
+                continue;
+            };
+            pending_lines = added + removed;
+            if let Some(path) = path.clone() {
+                hunks.push(Hunk {
+                    path,
+                    lnum: start.max(1),
+                    added,
+                    removed,
+                    text: String::new(),
+                });
+            }
+        }
+    }
+
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_rev() {
+        assert!(is_valid_rev("3859a4a"));
+        assert!(is_valid_rev("origin/main"));
+        assert!(is_valid_rev("HEAD~2"));
+        assert!(is_valid_rev("v0.49^"));
+        assert!(!is_valid_rev(""));
+        assert!(!is_valid_rev("--output=/tmp/diff"));
+        assert!(!is_valid_rev("-p"));
+        assert!(!is_valid_rev("main; rm -rf ~"));
+    }
+
+    #[test]
+    fn test_git_dir_from_env() {
+        let env = |value: &str| Some(OsString::from(value));
+        assert_eq!(
+            git_dir_from_env(
+                Path::new("/home/user/.config/nvim/init.lua"),
+                env("/home/user/.dotfiles"),
+                env("/home/user")
+            ),
+            Some((
+                PathBuf::from("/home/user/.dotfiles"),
+                PathBuf::from("/home/user")
+            ))
+        );
+        // Outside of the work tree.
+        assert_eq!(
+            git_dir_from_env(
+                Path::new("/tmp/foo"),
+                env("/home/user/.dotfiles"),
+                env("/home/user")
+            ),
+            None
+        );
+        // Both variables are required.
+        assert_eq!(
+            git_dir_from_env(Path::new("/home/user"), env("/home/user/.dotfiles"), None),
+            None
+        );
+        assert_eq!(
+            git_dir_from_env(Path::new("/home/user"), None, env("/home/user")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_work_tree() {
+        let root = std::env::temp_dir().join(format!("clap_work_tree_{}", std::process::id()));
+        let nested = root.join("src").join("nested");
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(&nested).unwrap();
+
+        assert_eq!(find_work_tree(&nested), Some(root.clone()));
+        assert_eq!(external_git_dir(&nested), None);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_show_commit() {
+        let repo = std::env::temp_dir().join(format!("clap_show_commit_{}", std::process::id()));
+        std::fs::create_dir_all(&repo).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=clap", "-c", "user.email=clap@localhost"])
+                .args(args)
+                .current_dir(&repo)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "--quiet"]);
+        std::fs::write(repo.join("large.txt"), "line\n".repeat(1000)).unwrap();
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "Add large.txt"]);
+
+        let shown = show_commit(&repo, "HEAD", MAX_COMMIT_DIFF_BYTES)
+            .await
+            .unwrap();
+        assert!(!shown.stat_only);
+        assert!(String::from_utf8_lossy(&shown.output).contains("+line"));
+
+        // Only the diffstat once the diff exceeds the cap.
+        let shown = show_commit(&repo, "HEAD", 100).await.unwrap();
+        assert!(shown.stat_only);
+        let output = String::from_utf8_lossy(&shown.output);
+        assert!(output.contains("large.txt | 1000 +"));
+        assert!(!output.contains("+line"));
+
+        std::fs::remove_dir_all(&repo).unwrap();
+    }
+
+    #[test]
+    fn test_parse_last_commit() {
+        assert_eq!(
+            parse_last_commit("a1b2c3d\x00Jane Doe\x002023-05-01\n"),
+            Some(LastCommit {
+                hash: "a1b2c3d".into(),
+                author: "Jane Doe".into(),
+                date: "2023-05-01".into(),
+            })
+        );
+        // Untracked file.
+        assert_eq!(parse_last_commit(""), None);
+    }
+
+    #[test]
+    fn test_parse_branch() {
+        assert_eq!(
+            parse_branch("*\x00refs/heads/main\x00main\x00a1b2c3d\x002 days ago\x00Fix the typo"),
+            Some(Branch {
+                name: "main".into(),
+                is_remote: false,
+                is_head: true,
+                hash: "a1b2c3d".into(),
+                date: "2 days ago".into(),
+                subject: "Fix the typo".into(),
+            })
+        );
+        let remote = parse_branch(
+            " \x00refs/remotes/origin/dev\x00origin/dev\x00e4f5a6b\x003 weeks ago\x00WIP",
+        )
+        .unwrap();
+        assert!(remote.is_remote);
+        assert!(!remote.is_head);
+        assert_eq!(remote.name, "origin/dev");
+        assert_eq!(
+            parse_branch(" \x00refs/remotes/origin/HEAD\x00origin\x00a1b2c3d\x002 days ago\x00Fix"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_diff_hunks() {
+        let diff = "\
+diff --git src/lib.rs src/lib.rs
+index 1111111..2222222 100644
+--- src/lib.rs
++++ src/lib.rs
+@@ -3 +3,2 @@ fn main() {
+-    let x = 1;
++    let x = 2;
++    let y = 3;
+@@ -10,2 +10,0 @@ fn main() {
+--- foo();
+-    bar();
+\\ No newline at end of file
+diff --git old.rs old.rs
+deleted file mode 100644
+--- old.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-fn old() {}
+diff --git new.rs new.rs
+new file mode 100644
+--- /dev/null
++++ new.rs
+@@ -0,0 +1 @@
++fn new() {}
+diff --git a b.rs a b.rs
+--- a b.rs\t
++++ a b.rs\t
+@@ -2 +2 @@
+-old
++new
+diff --git \"q\\\"\\303\\251.rs\" \"q\\\"\\303\\251.rs\"
+--- \"q\\\"\\303\\251.rs\"
++++ \"q\\\"\\303\\251.rs\"
+@@ -1 +1 @@
+-old
++new
+";
+        let hunk = |path: &str, lnum, added, removed, text: &str| Hunk {
+            path: path.into(),
+            lnum,
+            added,
+            removed,
+            text: text.into(),
+        };
+        assert_eq!(
+            parse_diff_hunks(diff),
+            vec![
+                hunk("src/lib.rs", 3, 2, 1, "let x = 2;"),
+                hunk("src/lib.rs", 10, 0, 2, "-- foo();"),
+                hunk("new.rs", 1, 1, 0, "fn new() {}"),
+                hunk("a b.rs", 2, 1, 1, "new"),
+                hunk("q\"é.rs", 1, 1, 1, "new"),
+            ]
+        );
+    }
+}
@@ -1,3 +1,8 @@
 pub mod ctags;
+pub mod diff_pager;
+pub mod git;
 pub mod gtags;
+pub mod project_tools;
 pub mod rg;
+pub mod symbols;
+pub mod vcs;
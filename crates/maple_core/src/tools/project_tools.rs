@@ -0,0 +1,96 @@
+//! Tools resolved per project, which may be overridden by `project-tools` in the config.
+//!
+//! The tools of a project are resolved once and kept until the config is replaced, the
+//! overridden tools may differ from the default ones in the features, e.g., whether the
+//! ctags supports the JSON output.
+
+use crate::config::{config_generation, try_config, ToolCommandConfig};
+use crate::tools::ctags;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Debug, Default)]
+pub struct ProjectTools {
+    /// Command of ripgrep, which is the default one if not overridden.
+    pub rg: ToolCommandConfig,
+    /// Command of ctags, which is the default one if not overridden.
+    pub ctags: ToolCommandConfig,
+    /// Whether the ctags of the project supports `--output-format=json`.
+    pub ctags_has_json_feature: bool,
+    /// Pair of (git_dir, work_tree) if the git dir of the project is specified.
+    pub git_dir: Option<(PathBuf, PathBuf)>,
+}
+
+impl ProjectTools {
+    fn default_tools() -> Self {
+        Self {
+            ctags_has_json_feature: *ctags::CTAGS_HAS_JSON_FEATURE,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ResolvedTools {
+    /// Generation of the config the tools are resolved from.
+    generation: usize,
+    /// Tools keyed by the project directory, the paths outside of any project share the
+    /// default tools keyed by the empty path.
+    tools: HashMap<PathBuf, Arc<ProjectTools>>,
+}
+
+static PROJECT_TOOLS: Lazy<Mutex<ResolvedTools>> = Lazy::new(Default::default);
+
+/// Returns the tools of the innermost project containing `path`.
+pub fn project_tools(path: &Path) -> Arc<ProjectTools> {
+    let Some(config) = try_config() else {
+        return Arc::new(ProjectTools::default_tools());
+    };
+    let generation = config_generation();
+
+    let (project_dir, tools_config) = match config.project_tools(path) {
+        Some((project_dir, tools_config)) => (project_dir.to_path_buf(), Some(tools_config)),
+        None => (PathBuf::new(), None),
+    };
+
+    {
+        let mut resolved = PROJECT_TOOLS.lock();
+        if resolved.generation != generation {
+            *resolved = ResolvedTools {
+                generation,
+                tools: HashMap::new(),
+            };
+        }
+        if let Some(tools) = resolved.tools.get(&project_dir) {
+            return tools.clone();
+        }
+    }
+
+    // Resolved without holding the lock as the ctags is executed to detect its features.
+    let tools = match tools_config {
+        Some(tools_config) => {
+            let ctags_has_json_feature = if tools_config.ctags == ToolCommandConfig::default() {
+                *ctags::CTAGS_HAS_JSON_FEATURE
+            } else {
+                ctags::detect_json_feature(&tools_config.ctags)
+            };
+            ProjectTools {
+                rg: tools_config.rg.clone(),
+                ctags: tools_config.ctags.clone(),
+                ctags_has_json_feature,
+                git_dir: config.project_git_dir(&project_dir),
+            }
+        }
+        None => ProjectTools::default_tools(),
+    };
+    let tools = Arc::new(tools);
+
+    let mut resolved = PROJECT_TOOLS.lock();
+    if resolved.generation == generation {
+        resolved.tools.insert(project_dir, tools.clone());
+    }
+    tools
+}
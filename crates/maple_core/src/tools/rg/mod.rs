@@ -3,14 +3,18 @@ mod jsont;
 mod stats;
 
 use crate::cache::Digest;
+use crate::process::limits::{self, LimitResources};
 use crate::process::ShellCommand;
+use crate::tools::project_tools::project_tools;
 use once_cell::sync::Lazy;
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
 use std::ops::Range;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 use utils::display_width;
 
 pub use self::jsont::{Match, Message, SubMatch};
@@ -287,7 +291,7 @@ pub struct RgTokioCommand {
 
 impl RgTokioCommand {
     pub fn new(dir: PathBuf) -> Self {
-        let shell_cmd = ShellCommand::new(RG_EXEC_CMD.into(), dir);
+        let shell_cmd = rg_shell_command(dir);
         Self { shell_cmd }
     }
 
@@ -296,39 +300,82 @@ impl RgTokioCommand {
     }
 
     pub async fn create_cache(self) -> std::io::Result<Digest> {
-        let cache_file = self.shell_cmd.cache_file_path()?;
-
-        let std_cmd = rg_command(&self.shell_cmd.dir);
-        let mut tokio_cmd = tokio::process::Command::from(std_cmd);
-        crate::process::tokio::write_stdout_to_file(&mut tokio_cmd, &cache_file).await?;
-
-        let digest = crate::cache::store_cache_digest(self.shell_cmd.clone(), cache_file)?;
-
-        Ok(digest)
+        let dir = self.shell_cmd.dir;
+        tokio::task::spawn_blocking(move || refresh_cache(dir))
+            .await
+            .map_err(std::io::Error::other)?
     }
 }
 
 pub fn rg_command<P: AsRef<Path>>(dir: P) -> Command {
+    let rg = &project_tools(dir.as_ref()).rg;
     // Can not use StdCommand as it joins the args which does not work somehow.
-    let mut cmd = Command::new(RG_ARGS[0]);
+    let mut cmd = Command::new(rg.program(RG_ARGS[0]));
+    cmd.args(&rg.args);
     // Do not use --vimgrep here.
-    cmd.args(&RG_ARGS[1..]).current_dir(dir);
+    cmd.args(&RG_ARGS[1..])
+        .current_dir(dir)
+        .limit_resources()
+        .killable();
     cmd
 }
 
 pub fn refresh_cache(dir: impl AsRef<Path>) -> std::io::Result<Digest> {
-    let shell_cmd = rg_shell_command(dir.as_ref());
-    let cache_file_path = shell_cmd.cache_file_path()?;
+    refresh_cache_interruptible(dir, || false)
+}
 
-    let mut cmd = rg_command(dir.as_ref());
-    crate::process::write_stdout_to_file(&mut cmd, &cache_file_path)?;
+/// Same as [`refresh_cache`], but rg is killed once `interrupted` returns `true`, the
+/// previous cache is kept in that case.
+pub fn refresh_cache_interruptible(
+    dir: impl AsRef<Path>,
+    interrupted: impl Fn() -> bool,
+) -> std::io::Result<Digest> {
+    let shell_cmd = rg_shell_command(dir.as_ref());
 
-    let digest = crate::cache::store_cache_digest(shell_cmd, cache_file_path)?;
+    crate::cache::create_cache(shell_cmd, |cache_file| {
+        write_interned_cache(dir.as_ref(), cache_file, interrupted)
+    })
+}
 
-    Ok(digest)
+/// Writes the output of ripgrep in `dir` to `cache_file` with the paths interned, see
+/// [`filter::write_interned_grep_lines`].
+fn write_interned_cache(
+    dir: &Path,
+    cache_file: &Path,
+    interrupted: impl Fn() -> bool,
+) -> std::io::Result<()> {
+    let mut cmd = rg_command(dir);
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::null()).spawn()?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| std::io::Error::other("stdout of rg is unavailable"))?;
+    let writer = BufWriter::new(File::create(cache_file)?);
+    // Killing rg on timeout closes the pipe, which ends the writing as well.
+    let writing = std::thread::spawn(move || {
+        filter::write_interned_grep_lines(BufReader::new(stdout), writer)
+    });
+
+    let exit_status = limits::wait_interruptible(&mut child, limits::timeout(), interrupted);
+    writing
+        .join()
+        .map_err(|_| std::io::Error::other("writing the rg cache panicked"))??;
+
+    let exit_status = exit_status?;
+    if exit_status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "Failed to execute the command: {cmd:?}, exit code: {:?}",
+            exit_status.code()
+        )))
+    }
 }
 
 #[inline]
 pub fn rg_shell_command<P: AsRef<Path>>(dir: P) -> ShellCommand {
-    ShellCommand::new(RG_EXEC_CMD.into(), PathBuf::from(dir.as_ref()))
+    let dir = dir.as_ref();
+    let cmd = project_tools(dir).rg.shell_cmd(RG_EXEC_CMD);
+    ShellCommand::new(cmd, PathBuf::from(dir))
 }
@@ -0,0 +1,314 @@
+//! Workspace symbols unioned from the available tools, i.e., LSP, ctags and tree-sitter,
+//! for the `symbols` provider.
+//!
+//! The same symbol found by more than one tool is kept only once, the LSP symbols are
+//! preferred over the ctags ones, which are preferred over the tree-sitter ones.
+
+use crate::tools::ctags::ProjectCtagsCommand;
+use ignore::WalkBuilder;
+use matcher::MatchScope;
+use rayon::prelude::*;
+use serde::Deserialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::Path;
+use types::{ClapItem, FuzzyText, ItemHighlight};
+
+/// Max number of files parsed by tree-sitter.
+const MAX_TREE_SITTER_FILES: usize = 5_000;
+
+/// Files larger than this are skipped by tree-sitter.
+pub(crate) const MAX_TREE_SITTER_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Tool which found the symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolSource {
+    Lsp,
+    Ctags,
+    TreeSitter,
+}
+
+impl SymbolSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Lsp => "lsp",
+            Self::Ctags => "ctags",
+            Self::TreeSitter => "tree-sitter",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceSymbol {
+    pub name: String,
+    pub kind: String,
+    /// Path relative to the workspace root if it's in the workspace.
+    pub path: String,
+    /// 1-based line number.
+    pub line: usize,
+    pub source: SymbolSource,
+}
+
+impl WorkspaceSymbol {
+    /// Builds the line for displaying the symbol, which is compatible with the format of
+    /// `proj_tags` so that the same parser can be used.
+    pub fn format_symbol(&self) -> String {
+        let name_lnum = format!("{}:{}", self.name, self.line);
+        let kind = format!("[{}@{}]", self.kind, self.path);
+        format!("{name_lnum:<30} {kind:<30} ({})", self.source.label())
+    }
+
+    pub fn into_symbol_item(self) -> WorkspaceSymbolItem {
+        let output_text = self.format_symbol();
+        WorkspaceSymbolItem {
+            name: self.name,
+            kind: self.kind,
+            path: self.path,
+            line: self.line,
+            output_text,
+            source: self.source,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct WorkspaceSymbolItem {
+    pub name: String,
+    pub kind: String,
+    /// Path relative to the workspace root if it's in the workspace.
+    pub path: String,
+    /// 1-based line number.
+    pub line: usize,
+    pub output_text: String,
+    pub source: SymbolSource,
+}
+
+impl ClapItem for WorkspaceSymbolItem {
+    fn raw_text(&self) -> &str {
+        &self.output_text
+    }
+
+    fn fuzzy_text(&self, _match_scope: MatchScope) -> Option<FuzzyText> {
+        Some(FuzzyText::new(&self.name, 0))
+    }
+
+    fn output_text(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.output_text)
+    }
+
+    fn icon(&self, _icon: icon::Icon) -> Option<icon::IconType> {
+        Some(icon::tags_kind_icon(&self.kind))
+    }
+
+    fn highlights(&self) -> Vec<ItemHighlight> {
+        // The source label `(lsp)` is at the end.
+        let length = self.source.label().len() + 2;
+        vec![ItemHighlight {
+            start: self.output_text.len() - length,
+            length,
+            group: "ClapSymbolSource",
+        }]
+    }
+}
+
+/// Workspace symbol reported by the language server in Vim.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LspSymbol {
+    pub name: String,
+    /// `SymbolKind` number of LSP or the kind name.
+    pub kind: Value,
+    pub path: String,
+    pub lnum: usize,
+}
+
+/// Returns the lowercase name of LSP `SymbolKind`.
+fn lsp_symbol_kind(kind: &Value) -> String {
+    const SYMBOL_KINDS: &[&str] = &[
+        "file",
+        "module",
+        "namespace",
+        "package",
+        "class",
+        "method",
+        "property",
+        "field",
+        "constructor",
+        "enum",
+        "interface",
+        "function",
+        "variable",
+        "constant",
+        "string",
+        "number",
+        "boolean",
+        "array",
+        "object",
+        "key",
+        "null",
+        "enummember",
+        "struct",
+        "event",
+        "operator",
+        "typeparameter",
+    ];
+
+    match kind {
+        Value::Number(n) => n
+            .as_u64()
+            .and_then(|n| SYMBOL_KINDS.get((n as usize).checked_sub(1)?))
+            .map_or_else(|| "unknown".to_string(), |kind| kind.to_string()),
+        Value::String(s) => s.to_lowercase(),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn relative_path(path: &str, cwd: &Path) -> String {
+    Path::new(path)
+        .strip_prefix(cwd)
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+pub fn lsp_symbols(symbols: Vec<LspSymbol>, cwd: &Path) -> Vec<WorkspaceSymbol> {
+    symbols
+        .into_iter()
+        .map(|symbol| WorkspaceSymbol {
+            kind: lsp_symbol_kind(&symbol.kind),
+            path: relative_path(&symbol.path, cwd),
+            name: symbol.name,
+            line: symbol.lnum,
+            source: SymbolSource::Lsp,
+        })
+        .collect()
+}
+
+/// Returns the project symbols from ctags, empty if ctags is unavailable.
+pub fn ctags_symbols(cwd: &Path) -> Vec<WorkspaceSymbol> {
+    let mut ctags_cmd = ProjectCtagsCommand::with_cwd(cwd.to_path_buf());
+    match ctags_cmd.tags() {
+        Ok(tags) => tags
+            .into_iter()
+            .map(|tag| tag.into_workspace_symbol())
+            .collect(),
+        Err(err) => {
+            tracing::debug!(?err, "Failed to collect the symbols from ctags");
+            Vec::new()
+        }
+    }
+}
+
+/// Returns the top-level symbols in the files under `cwd` that tree-sitter can parse.
+pub fn tree_sitter_symbols(cwd: &Path) -> Vec<WorkspaceSymbol> {
+    let files = WalkBuilder::new(cwd)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.file_type().map_or(false, |ty| ty.is_file())
+                && entry.metadata().map_or(false, |metadata| {
+                    metadata.len() <= MAX_TREE_SITTER_FILE_SIZE
+                })
+        })
+        .filter_map(|entry| {
+            let language = entry
+                .path()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(tree_sitter::Language::try_from_extension)?;
+            Some((entry.into_path(), language))
+        })
+        .take(MAX_TREE_SITTER_FILES)
+        .collect::<Vec<_>>();
+
+    files
+        .par_iter()
+        .flat_map_iter(|(path, language)| {
+            let source = std::fs::read(path).unwrap_or_default();
+            let path = relative_path(&path.to_string_lossy(), cwd);
+            tree_sitter::top_level_symbols(*language, &source)
+                .into_iter()
+                .map(move |symbol| WorkspaceSymbol {
+                    name: symbol.name,
+                    kind: symbol.kind,
+                    path: path.clone(),
+                    line: symbol.line + 1,
+                    source: SymbolSource::TreeSitter,
+                })
+        })
+        .collect()
+}
+
+/// Unions the symbols from different sources, the duplicate ones in the later groups
+/// are dropped.
+pub fn merge_symbols(
+    groups: impl IntoIterator<Item = Vec<WorkspaceSymbol>>,
+) -> Vec<WorkspaceSymbol> {
+    let mut seen = HashSet::new();
+    groups
+        .into_iter()
+        .flatten()
+        .filter(|symbol| seen.insert((symbol.name.clone(), symbol.path.clone(), symbol.line)))
+        .collect()
+}
+
+/// Collects the workspace symbols from all the available sources.
+pub fn collect_workspace_symbols(cwd: &Path, lsp: Vec<LspSymbol>) -> Vec<WorkspaceSymbol> {
+    let (ctags, tree_sitter) = rayon::join(|| ctags_symbols(cwd), || tree_sitter_symbols(cwd));
+    merge_symbols([lsp_symbols(lsp, cwd), ctags, tree_sitter])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol(name: &str, line: usize, source: SymbolSource) -> WorkspaceSymbol {
+        WorkspaceSymbol {
+            name: name.into(),
+            kind: "function".into(),
+            path: "src/lib.rs".into(),
+            line,
+            source,
+        }
+    }
+
+    #[test]
+    fn test_merge_symbols() {
+        let merged = merge_symbols([
+            vec![symbol("foo", 1, SymbolSource::Lsp)],
+            vec![
+                symbol("foo", 1, SymbolSource::Ctags),
+                symbol("bar", 5, SymbolSource::Ctags),
+            ],
+            vec![
+                symbol("bar", 5, SymbolSource::TreeSitter),
+                symbol("baz", 9, SymbolSource::TreeSitter),
+            ],
+        ]);
+        let sources = merged
+            .iter()
+            .map(|s| (s.name.as_str(), s.source))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sources,
+            vec![
+                ("foo", SymbolSource::Lsp),
+                ("bar", SymbolSource::Ctags),
+                ("baz", SymbolSource::TreeSitter)
+            ]
+        );
+
+        let line = merged[0].format_symbol();
+        assert_eq!(pattern::extract_proj_tags(&line), Some((1, "src/lib.rs")));
+        assert!(line.ends_with("(lsp)"));
+
+        let item = merged[0].clone().into_symbol_item();
+        let highlight = item.highlights()[0];
+        assert_eq!(
+            &item.output_text[highlight.start..highlight.start + highlight.length],
+            "(lsp)"
+        );
+
+        assert_eq!(lsp_symbol_kind(&Value::from(12)), "function");
+        assert_eq!(lsp_symbol_kind(&Value::from(100)), "unknown");
+    }
+}
@@ -0,0 +1,288 @@
+//! Version control systems backing the `commits`, `bcommits` and `git_files` providers.
+//!
+//! The commits are listed in the same format for all the VCS, e.g.,
+//! `2023-01-02 1a2b3c4 Fix the typo (Alice)`, so that the revision can be extracted
+//! the same way.
+
+use crate::process::limits::LimitResources;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VcsKind {
+    Git,
+    Mercurial,
+    Svn,
+}
+
+pub trait Vcs: Send + Sync {
+    fn kind(&self) -> VcsKind;
+
+    /// Root of the working copy.
+    fn root(&self) -> &Path;
+
+    /// Returns the commits in the working copy, only the ones touching `file` if specified.
+    fn log(&self, file: Option<&Path>) -> Result<Vec<String>>;
+
+    /// Returns the files tracked in the working copy, relative to the root.
+    fn files(&self) -> Result<Vec<String>>;
+
+    /// Returns the changes of commit `rev` in the unified diff format.
+    fn show(&self, rev: &str) -> Result<Vec<u8>>;
+}
+
+fn check_output(output: Output) -> Result<Vec<u8>> {
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(Error::new(
+            ErrorKind::Other,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+/// Returns the stdout of `cmd` run with the configured resource limits.
+fn limited_output(cmd: &mut Command) -> Result<Vec<u8>> {
+    check_output(cmd.stdin(Stdio::null()).limit_resources().output()?)
+}
+
+fn output_lines(cmd: &mut Command) -> Result<Vec<String>> {
+    let stdout = limited_output(cmd)?;
+    Ok(String::from_utf8_lossy(&stdout)
+        .lines()
+        .map(String::from)
+        .collect())
+}
+
+#[derive(Debug)]
+struct Git {
+    root: PathBuf,
+}
+
+impl Vcs for Git {
+    fn kind(&self) -> VcsKind {
+        VcsKind::Git
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn log(&self, file: Option<&Path>) -> Result<Vec<String>> {
+        let mut cmd = crate::tools::git::git_command(&self.root);
+        cmd.args([
+            "log",
+            "--color=never",
+            "--date=short",
+            "--format=%cd %h%d %s (%an)",
+        ]);
+        match file {
+            Some(file) => cmd.args(["--follow", "--"]).arg(file),
+            None => cmd.arg("--graph"),
+        };
+        output_lines(&mut cmd)
+    }
+
+    fn files(&self) -> Result<Vec<String>> {
+        output_lines(crate::tools::git::git_command(&self.root).arg("ls-files"))
+    }
+
+    fn show(&self, rev: &str) -> Result<Vec<u8>> {
+        limited_output(
+            crate::tools::git::git_command(&self.root)
+                .args(["show", "--end-of-options"])
+                .arg(rev),
+        )
+    }
+}
+
+#[derive(Debug)]
+struct Mercurial {
+    root: PathBuf,
+}
+
+impl Mercurial {
+    const LOG_TEMPLATE: &'static str =
+        "{date|shortdate} {node|short} {desc|firstline} ({author|person})\\n";
+
+    fn hg_command(&self) -> Command {
+        let mut cmd = Command::new("hg");
+        // Do not let the user config change the output format.
+        cmd.current_dir(&self.root).env("HGPLAIN", "1");
+        cmd
+    }
+}
+
+impl Vcs for Mercurial {
+    fn kind(&self) -> VcsKind {
+        VcsKind::Mercurial
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn log(&self, file: Option<&Path>) -> Result<Vec<String>> {
+        let mut cmd = self.hg_command();
+        cmd.args(["log", "--template", Self::LOG_TEMPLATE]);
+        if let Some(file) = file {
+            cmd.args(["--follow", "--"]).arg(file);
+        }
+        output_lines(&mut cmd)
+    }
+
+    fn files(&self) -> Result<Vec<String>> {
+        output_lines(self.hg_command().arg("files"))
+    }
+
+    fn show(&self, rev: &str) -> Result<Vec<u8>> {
+        limited_output(self.hg_command().args(["log", "--patch", "-r", rev]))
+    }
+}
+
+#[derive(Debug)]
+struct Svn {
+    root: PathBuf,
+}
+
+impl Svn {
+    fn svn_command(&self) -> Command {
+        let mut cmd = Command::new("svn");
+        cmd.current_dir(&self.root).arg("--non-interactive");
+        cmd
+    }
+}
+
+/// Converts the output of `svn log` to the commit lines.
+///
+/// ```text
+/// ------------------------------------------------------------------------
+/// r2 | alice | 2023-01-02 10:00:00 +0000 (Mon, 02 Jan 2023) | 1 line
+///
+/// Fix the typo
+/// ```
+fn parse_svn_log(output: &str) -> Vec<String> {
+    let mut commits = Vec::new();
+    let mut lines = output.lines();
+    while let Some(line) = lines.next() {
+        let mut fields = line.split(" | ");
+        let (Some(rev), Some(author), Some(date)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let Some(day) = date.get(..10).filter(|_| rev.starts_with('r')) else {
+            continue;
+        };
+        let summary = lines
+            .by_ref()
+            .take_while(|line| !line.starts_with("-----"))
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or_default();
+        commits.push(format!("{day} {rev} {summary} ({author})"));
+    }
+    commits
+}
+
+impl Vcs for Svn {
+    fn kind(&self) -> VcsKind {
+        VcsKind::Svn
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn log(&self, file: Option<&Path>) -> Result<Vec<String>> {
+        let mut cmd = self.svn_command();
+        cmd.arg("log");
+        if let Some(file) = file {
+            cmd.arg(file);
+        }
+        let stdout = limited_output(&mut cmd)?;
+        Ok(parse_svn_log(&String::from_utf8_lossy(&stdout)))
+    }
+
+    fn files(&self) -> Result<Vec<String>> {
+        let mut files = output_lines(self.svn_command().args(["list", "--recursive"]))?;
+        files.retain(|file| !file.ends_with('/'));
+        Ok(files)
+    }
+
+    fn show(&self, rev: &str) -> Result<Vec<u8>> {
+        let rev = rev.trim_start_matches('r');
+        let log = limited_output(self.svn_command().args(["log", "-v", "-c", rev]))?;
+        let diff = limited_output(self.svn_command().args(["diff", "-c", rev]))?;
+        Ok([log, diff].concat())
+    }
+}
+
+/// Detects the VCS of the working copy containing `path`, the nearest one wins.
+pub fn detect(path: &Path) -> Option<Box<dyn Vcs>> {
+    for dir in path.ancestors() {
+        if dir.join(".git").exists() {
+            return Some(Box::new(Git {
+                root: dir.to_path_buf(),
+            }));
+        } else if dir.join(".hg").is_dir() {
+            return Some(Box::new(Mercurial {
+                root: dir.to_path_buf(),
+            }));
+        } else if dir.join(".svn").is_dir() {
+            return Some(Box::new(Svn {
+                root: dir.to_path_buf(),
+            }));
+        }
+    }
+
+    // The git dir outside of the work tree.
+    crate::tools::git::find_work_tree(path).map(|root| Box::new(Git { root }) as Box<dyn Vcs>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_svn_log() {
+        let output = "\
+------------------------------------------------------------------------
+r2 | alice | 2023-01-02 10:00:00 +0000 (Mon, 02 Jan 2023) | 2 lines
+
+Fix the typo
+in the readme
+------------------------------------------------------------------------
+r1 | bob | 2023-01-01 09:00:00 +0000 (Sun, 01 Jan 2023) | 1 line
+
+Initial import
+------------------------------------------------------------------------
+";
+        let commits = parse_svn_log(output);
+        assert_eq!(
+            commits,
+            vec![
+                "2023-01-02 r2 Fix the typo (alice)",
+                "2023-01-01 r1 Initial import (bob)"
+            ]
+        );
+        assert_eq!(pattern::extract_commit_rev(&commits[0]), Some("r2"));
+
+        // The malformed date is skipped instead of panicking.
+        assert!(parse_svn_log("r3 | carol | 2023-01-0中 | 1 line\n\nBroken\n").is_empty());
+    }
+
+    #[test]
+    fn test_detect() {
+        let dir = std::env::temp_dir().join(format!("clap_test_vcs_detect_{}", std::process::id()));
+        let nested = dir.join("src");
+        std::fs::create_dir_all(dir.join(".hg")).unwrap();
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let vcs = detect(&nested).unwrap();
+        assert_eq!(vcs.kind(), VcsKind::Mercurial);
+        assert_eq!(vcs.root(), dir);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+//! Local usage statistics of the providers.
+//!
+//! The statistics are only stored in the data directory and never leave the machine, they are
+//! displayed by the `stats` provider to help tune the config.
+
+use crate::cache::Digest;
+use crate::recent_files::FrecentEntry;
+use crate::UtcTime;
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProviderStats {
+    /// Number of times the provider was opened.
+    pub sessions: u64,
+    /// Total time of initializing the provider in milliseconds.
+    pub initialization_ms: u64,
+    /// Number of times the results were updated on typing.
+    pub typed: u64,
+    /// Total time of updating the results on typing in milliseconds.
+    pub typed_ms: u64,
+    /// Time of last use.
+    pub last_used: Option<UtcTime>,
+}
+
+impl ProviderStats {
+    pub fn avg_initialization_ms(&self) -> u64 {
+        self.initialization_ms
+            .checked_div(self.sessions)
+            .unwrap_or(0)
+    }
+
+    pub fn avg_typed_ms(&self) -> u64 {
+        self.typed_ms.checked_div(self.typed).unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageStats {
+    providers: HashMap<String, ProviderStats>,
+}
+
+impl UsageStats {
+    /// Records a new session of `provider_id` initialized in `elapsed`.
+    pub fn record_session(&mut self, provider_id: &str, elapsed: Duration) {
+        let stats = self.providers.entry(provider_id.to_string()).or_default();
+        stats.sessions += 1;
+        stats.initialization_ms += elapsed.as_millis() as u64;
+        stats.last_used.replace(Utc::now());
+    }
+
+    /// Records an update of the results on typing in `elapsed`.
+    pub fn record_typed(&mut self, provider_id: &str, elapsed: Duration) {
+        let stats = self.providers.entry(provider_id.to_string()).or_default();
+        stats.typed += 1;
+        stats.typed_ms += elapsed.as_millis() as u64;
+    }
+
+    /// Returns the provider stats sorted by the number of sessions in descending order.
+    pub fn providers(&self) -> Vec<(&str, &ProviderStats)> {
+        let mut providers = self
+            .providers
+            .iter()
+            .map(|(id, stats)| (id.as_str(), stats))
+            .collect::<Vec<_>>();
+        providers.sort_unstable_by(|a, b| b.1.sessions.cmp(&a.1.sessions).then(a.0.cmp(b.0)));
+        providers
+    }
+}
+
+fn readable_size(size: u64) -> String {
+    if size > 1024 * 1024 {
+        format!("{}MB", size / 1024 / 1024)
+    } else if size > 1024 {
+        format!("{}KB", size / 1024)
+    } else {
+        format!("{size}B")
+    }
+}
+
+/// Maximum number of the most opened files displayed.
+const MAX_FILES: usize = 20;
+
+/// Returns the lines displayed in the `stats` provider.
+pub fn stats_lines(
+    usage_stats: &UsageStats,
+    digests: &[Digest],
+    recent_files: &[FrecentEntry],
+) -> Vec<String> {
+    let providers = usage_stats.providers();
+    let max_id_len = providers.iter().map(|(id, _)| id.len()).max().unwrap_or(0);
+
+    let provider_lines = providers.iter().map(|(id, stats)| {
+        let last_used = stats
+            .last_used
+            .map(|time| chrono_humanize::HumanTime::from(time).to_string())
+            .unwrap_or_default();
+        format!(
+            "[provider] {id:<max_id_len$}  sessions: {:<5} init: {:>5}ms  typed: {:>5}ms  {last_used}",
+            stats.sessions,
+            stats.avg_initialization_ms(),
+            stats.avg_typed_ms(),
+        )
+    });
+
+    let mut digests = digests
+        .iter()
+        .map(|digest| {
+            let size = std::fs::metadata(&digest.cached_path).map_or(0, |m| m.len());
+            (size, digest)
+        })
+        .collect::<Vec<_>>();
+    digests.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+
+    let total_size = digests.iter().map(|(size, _)| size).sum::<u64>();
+    let cache_summary = format!(
+        "[cache] {} entries, {} in total",
+        digests.len(),
+        readable_size(total_size)
+    );
+    let cache_lines = digests.iter().map(|(size, digest)| {
+        format!(
+            "[cache] {:>6}  {} lines  {} ({})",
+            readable_size(*size),
+            digest.total,
+            digest.shell_cmd.command,
+            digest.shell_cmd.dir.display()
+        )
+    });
+
+    let mut recent_files = recent_files.iter().collect::<Vec<_>>();
+    recent_files.sort_unstable_by(|a, b| b.visits.cmp(&a.visits));
+    let file_lines = recent_files
+        .into_iter()
+        .take(MAX_FILES)
+        .map(|entry| format!("[file] {:>5} visits  {}", entry.visits, entry.fpath));
+
+    provider_lines
+        .chain(std::iter::once(cache_summary))
+        .chain(cache_lines)
+        .chain(file_lines)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_usage_stats() {
+        let mut usage_stats = UsageStats::default();
+        usage_stats.record_session("files", Duration::from_millis(30));
+        usage_stats.record_session("files", Duration::from_millis(10));
+        usage_stats.record_typed("files", Duration::from_millis(5));
+        usage_stats.record_session("grep", Duration::from_millis(100));
+
+        let providers = usage_stats.providers();
+        assert_eq!(providers[0].0, "files");
+        assert_eq!(providers[0].1.sessions, 2);
+        assert_eq!(providers[0].1.avg_initialization_ms(), 20);
+        assert_eq!(providers[0].1.avg_typed_ms(), 5);
+        assert_eq!(providers[1].1.avg_typed_ms(), 0);
+    }
+}
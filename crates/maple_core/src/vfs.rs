@@ -0,0 +1,611 @@
+//! Virtual filesystem the previewer reads the file contents from.
+//!
+//! The contents can come from the disk, from the buffers in Vim which are modified or
+//! not backed by a file, or from the handlers of custom schemes like `fugitive://`.
+
+use crate::previewer::encoding;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use utils::bytelines::ByteLines;
+
+/// Maximum size of the file read as a whole for previewing.
+const MAX_FILE_SIZE: u64 = 32 * 1_048_576;
+
+/// Files larger than this are read through the line index.
+const LARGE_FILE_SIZE: u64 = 8 * 1_048_576;
+
+/// Maximum number of the line indexes kept in memory.
+const MAX_LINE_INDEXES: usize = 4;
+
+pub trait Vfs: std::fmt::Debug + Send + Sync {
+    /// Returns the whole content of the file at `path`.
+    fn read(&self, path: &Path) -> Result<Vec<u8>>;
+
+    /// Returns the whole content of the file at `path` as UTF-8 text to be displayed.
+    fn read_text(&self, path: &Path) -> Result<Vec<u8>> {
+        self.read(path)
+    }
+
+    /// Returns `true` if `path` is a file in this filesystem.
+    fn is_file(&self, path: &Path) -> bool;
+
+    /// Returns the first `n` lines of the file at `path`.
+    fn read_first_lines(&self, path: &Path, n: usize) -> Result<Vec<String>> {
+        let content = self.read_text(path)?;
+        Ok(ByteLines::new(&content)
+            .take(n)
+            .map(|line| line.trim_end().to_string())
+            .collect())
+    }
+
+    /// Returns the total number of lines of the file at `path`.
+    fn count_lines(&self, path: &Path) -> Result<usize> {
+        utils::count_lines(self.read_text(path)?.as_slice())
+    }
+
+    /// Returns the lines in `[start, end)` of the file at `path` and the total number of
+    /// lines.
+    fn read_lines(&self, path: &Path, start: usize, end: usize) -> Result<(Vec<String>, usize)> {
+        lines_in_range(&self.read_text(path)?, start, end)
+    }
+}
+
+/// Returns the lines in `[start, end)` of `content` and the total number of lines.
+fn lines_in_range(content: &[u8], start: usize, end: usize) -> Result<(Vec<String>, usize)> {
+    let total = utils::count_lines(content)?;
+    let lines = ByteLines::new(content)
+        .skip(start)
+        .take(end.saturating_sub(start))
+        // trim_end() to get rid of ^M on Windows.
+        .map(|line| line.trim_end().to_string())
+        .collect();
+    Ok((lines, total))
+}
+
+/// Number of the last indexed bytes compared to tell whether the file is only appended.
+const TAIL_LEN: u64 = 64;
+
+/// Offsets of the lines of a large file, the lines are read from the file on demand.
+///
+/// The file is not memory-mapped as it may be truncated in place, e.g., a rotated log,
+/// which raises SIGBUS on accessing the mapped memory.
+#[derive(Debug, Clone)]
+struct LineIndex {
+    path: PathBuf,
+    /// Legacy encoding the lines are transcoded from.
+    encoding: Option<&'static Encoding>,
+    /// Number of the indexed bytes.
+    len: u64,
+    modified: Option<SystemTime>,
+    /// Start offset of each line.
+    line_starts: Vec<u64>,
+    /// Whether the indexed part ends with a newline.
+    ends_with_newline: bool,
+    /// Last bytes of the indexed part.
+    tail: Vec<u8>,
+}
+
+impl LineIndex {
+    fn new(path: PathBuf, encoding: Option<&'static Encoding>) -> Self {
+        Self {
+            path,
+            encoding,
+            len: 0,
+            modified: None,
+            line_starts: Vec::new(),
+            ends_with_newline: true,
+            tail: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if the indexed part is unchanged in the file of `new_len` bytes.
+    fn is_prefix_of(&self, file: &mut File, new_len: u64) -> Result<bool> {
+        if new_len < self.len {
+            return Ok(false);
+        }
+        let mut tail = vec![0; self.tail.len()];
+        file.seek(SeekFrom::Start(self.len - self.tail.len() as u64))?;
+        file.read_exact(&mut tail)?;
+        Ok(tail == self.tail)
+    }
+
+    /// Indexes the bytes appended after the indexed part up to `new_len`.
+    fn extend(&mut self, file: &mut File, new_len: u64) -> Result<()> {
+        // The line after a trailing newline starts in the appended part.
+        if self.len < new_len && self.ends_with_newline {
+            self.line_starts.push(self.len);
+        }
+
+        file.seek(SeekFrom::Start(self.len))?;
+        let mut reader = file.take(new_len - self.len);
+        // The chunks are all of the same even size but the last one, so that no UTF-16 code
+        // unit is split across two chunks.
+        let mut chunk = vec![0; 1 << 16];
+        let mut offset = self.len;
+        loop {
+            let n = read_chunk(&mut reader, &mut chunk)?;
+            if n == 0 {
+                break;
+            }
+            push_line_ends(&chunk[..n], offset, self.encoding, &mut self.line_starts);
+            offset += n as u64;
+        }
+
+        // Less is read if the file is truncated meanwhile.
+        if offset > self.len {
+            self.ends_with_newline = self.line_starts.last() == Some(&offset);
+            if self.ends_with_newline {
+                self.line_starts.pop();
+            }
+        }
+        self.len = offset;
+        let tail_len = TAIL_LEN.min(offset);
+        let file = reader.into_inner();
+        file.seek(SeekFrom::Start(offset - tail_len))?;
+        self.tail.resize(tail_len as usize, 0);
+        file.read_exact(&mut self.tail)?;
+
+        Ok(())
+    }
+
+    fn total(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the lines in `[start, end)`.
+    fn read_lines(&self, start: usize, end: usize) -> Result<Vec<String>> {
+        let total = self.total();
+        let (start, end) = (start.min(total), end.min(total));
+        if start >= end {
+            return Ok(Vec::new());
+        }
+
+        let from = self.line_starts[start];
+        let to = self.line_starts.get(end).copied().unwrap_or(self.len);
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(from))?;
+        let mut content = Vec::with_capacity((to - from) as usize);
+        file.take(to - from).read_to_end(&mut content)?;
+
+        let lines = match self.encoding {
+            Some(encoding) => encoding::transcode(&content, encoding)
+                .lines()
+                .take(end - start)
+                .map(|line| line.trim_end().to_string())
+                .collect(),
+            None => ByteLines::new(&content)
+                .take(end - start)
+                // trim_end() to get rid of ^M on Windows.
+                .map(|line| line.trim_end().to_string())
+                .collect(),
+        };
+
+        Ok(lines)
+    }
+}
+
+/// Fills `buf` from `reader` unless the end is reached, returns the number of bytes read.
+fn read_chunk(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Pushes the offsets right after the newlines in `chunk` which starts at `offset` of the
+/// file in `encoding`.
+///
+/// The newline of UTF-16 is a code unit of two bytes, the byte `\n` in the other encodings
+/// supported is never a part of a multi-byte character.
+fn push_line_ends(
+    chunk: &[u8],
+    offset: u64,
+    encoding: Option<&'static Encoding>,
+    line_ends: &mut Vec<u64>,
+) {
+    let newlines = memchr::memchr_iter(b'\n', chunk);
+    if encoding == Some(UTF_16LE) {
+        line_ends.extend(
+            newlines
+                .filter(|&pos| (offset + pos as u64) % 2 == 0 && chunk.get(pos + 1) == Some(&0))
+                .map(|pos| offset + pos as u64 + 2),
+        );
+    } else if encoding == Some(UTF_16BE) {
+        line_ends.extend(
+            newlines
+                .filter(|&pos| (offset + pos as u64) % 2 == 1 && pos > 0 && chunk[pos - 1] == 0)
+                .map(|pos| offset + pos as u64 + 1),
+        );
+    } else {
+        line_ends.extend(newlines.map(|pos| offset + pos as u64 + 1));
+    }
+}
+
+/// Line indexes of the large files, the most recently used one comes first.
+static LINE_INDEXES: Lazy<Mutex<Vec<Arc<LineIndex>>>> = Lazy::new(Default::default);
+
+/// Returns the line index of the large file `path`, which is built on the first access
+/// and updated once the file is modified.
+///
+/// Returns `None` if the file is not large enough to be worth indexing.
+///
+/// The large files are typically the logs which are only appended, the index of which
+/// is extended with the appended lines instead of being rebuilt.
+fn large_file_lines(path: &Path) -> Result<Option<Arc<LineIndex>>> {
+    let mut file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let len = metadata.len();
+
+    if len <= LARGE_FILE_SIZE {
+        return Ok(None);
+    }
+
+    let modified = metadata.modified().ok();
+
+    // The lock is not held while indexing, which may take a while for a huge file.
+    let outdated = {
+        let mut line_indexes = LINE_INDEXES.lock();
+        match line_indexes.iter().position(|index| index.path == path) {
+            Some(pos) if line_indexes[pos].len == len && line_indexes[pos].modified == modified => {
+                let index = line_indexes.remove(pos);
+                line_indexes.insert(0, index.clone());
+                return Ok(Some(index));
+            }
+            Some(pos) => Some(line_indexes.remove(pos)),
+            None => None,
+        }
+    };
+
+    let mut index = match outdated {
+        Some(outdated) if outdated.is_prefix_of(&mut file, len)? => {
+            Arc::try_unwrap(outdated).unwrap_or_else(|outdated| outdated.as_ref().clone())
+        }
+        _ => LineIndex::new(path.to_path_buf(), encoding::detect_file(path)?),
+    };
+    index.extend(&mut file, len)?;
+    index.modified = modified;
+    let index = Arc::new(index);
+
+    let mut line_indexes = LINE_INDEXES.lock();
+    line_indexes.retain(|line_index| line_index.path != path);
+    line_indexes.insert(0, index.clone());
+    line_indexes.truncate(MAX_LINE_INDEXES);
+
+    Ok(Some(index))
+}
+
+/// Files on disk.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiskFs;
+
+impl Vfs for DiskFs {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        let mut file = File::open(path)?;
+        let file_size = file.metadata()?.len();
+        if file_size > MAX_FILE_SIZE {
+            return Err(Error::new(
+                ErrorKind::Other,
+                "maximum preview file buffer size reached",
+            ));
+        }
+        let mut content = Vec::with_capacity(file_size as usize + 1);
+        file.read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    // The files in the legacy encodings are transcoded before being split into lines.
+    fn read_text(&self, path: &Path) -> Result<Vec<u8>> {
+        let content = self.read(path)?;
+        match encoding::detect(&content) {
+            Some(encoding) => Ok(encoding::transcode(&content, encoding).into_bytes()),
+            None => Ok(content),
+        }
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    // Only the first lines are read for the possibly huge file.
+    fn read_first_lines(&self, path: &Path, n: usize) -> Result<Vec<String>> {
+        if encoding::detect_file(path)?.is_some() {
+            let content = self.read_text(path)?;
+            return Ok(ByteLines::new(&content)
+                .take(n)
+                .map(|line| line.trim_end().to_string())
+                .collect());
+        }
+        Ok(utils::read_first_lines(path, n)?.collect())
+    }
+
+    fn count_lines(&self, path: &Path) -> Result<usize> {
+        match large_file_lines(path)? {
+            Some(index) => Ok(index.total()),
+            None if encoding::detect_file(path)?.is_some() => {
+                utils::count_lines(self.read_text(path)?.as_slice())
+            }
+            None => utils::count_lines(File::open(path)?),
+        }
+    }
+
+    // Only the requested lines are read from the large file, e.g., a log of gigabytes.
+    fn read_lines(&self, path: &Path, start: usize, end: usize) -> Result<(Vec<String>, usize)> {
+        let Some(index) = large_file_lines(path)? else {
+            return lines_in_range(&self.read_text(path)?, start, end);
+        };
+
+        Ok((index.read_lines(start, end)?, index.total()))
+    }
+}
+
+/// Contents of the buffers in Vim keyed by the buffer name.
+#[derive(Debug, Clone, Default)]
+pub struct BufferFs {
+    buffers: Arc<RwLock<HashMap<PathBuf, Arc<str>>>>,
+}
+
+impl BufferFs {
+    pub fn insert(&self, path: PathBuf, lines: Vec<String>) {
+        let mut content = lines.join("\n");
+        content.push('\n');
+        self.buffers.write().insert(path, content.into());
+    }
+
+    pub fn remove(&self, path: &Path) {
+        self.buffers.write().remove(path);
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.buffers.read().contains_key(path)
+    }
+
+    /// Returns the content of the buffer for `path`, shared instead of copied.
+    pub fn content(&self, path: &Path) -> Result<Arc<str>> {
+        self.buffers
+            .read()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "buffer not found"))
+    }
+}
+
+impl Vfs for BufferFs {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        self.content(path)
+            .map(|content| content.as_bytes().to_vec())
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.contains(path)
+    }
+
+    fn read_first_lines(&self, path: &Path, n: usize) -> Result<Vec<String>> {
+        Ok(self
+            .content(path)?
+            .lines()
+            .take(n)
+            .map(|line| line.trim_end().to_string())
+            .collect())
+    }
+
+    fn count_lines(&self, path: &Path) -> Result<usize> {
+        utils::count_lines(self.content(path)?.as_bytes())
+    }
+
+    fn read_lines(&self, path: &Path, start: usize, end: usize) -> Result<(Vec<String>, usize)> {
+        lines_in_range(self.content(path)?.as_bytes(), start, end)
+    }
+}
+
+/// Returns the scheme of the virtual path, e.g., `fugitive` of
+/// `fugitive:///project/.git//0/src/lib.rs`.
+///
+/// The scheme has at least two chars so that the drive letter on Windows is not a
+/// scheme.
+pub fn uri_scheme(path: &Path) -> Option<&str> {
+    let path = path.to_str()?;
+    let (scheme, _) = path.split_once("://")?;
+    let mut chars = scheme.chars();
+    let is_scheme = scheme.len() > 1
+        && chars.next().map_or(false, |c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    is_scheme.then_some(scheme)
+}
+
+/// Filesystem overlaying the buffer contents and the custom schemes on the disk.
+///
+/// The contents of the buffers take precedence, then the path with a scheme is read
+/// by the handler of the scheme, the others are read from the disk.
+#[derive(Debug, Clone, Default)]
+pub struct OverlayFs {
+    buffers: BufferFs,
+    schemes: HashMap<String, Arc<dyn Vfs>>,
+}
+
+impl OverlayFs {
+    pub fn buffers(&self) -> &BufferFs {
+        &self.buffers
+    }
+
+    /// Registers the filesystem for the paths of `scheme`.
+    pub fn register_scheme(&mut self, scheme: impl Into<String>, vfs: Arc<dyn Vfs>) {
+        self.schemes.insert(scheme.into(), vfs);
+    }
+
+    fn with_fs<T>(&self, path: &Path, f: impl FnOnce(&dyn Vfs) -> T) -> Option<T> {
+        if self.buffers.contains(path) {
+            return Some(f(&self.buffers));
+        }
+        match uri_scheme(path) {
+            Some(scheme) => self.schemes.get(scheme).map(|vfs| f(vfs.as_ref())),
+            None => Some(f(&DiskFs)),
+        }
+    }
+
+    fn unsupported_scheme(path: &Path) -> Error {
+        Error::new(
+            ErrorKind::Unsupported,
+            format!("no filesystem for the scheme of {}", path.display()),
+        )
+    }
+}
+
+impl Vfs for OverlayFs {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        self.with_fs(path, |vfs| vfs.read(path))
+            .unwrap_or_else(|| Err(Self::unsupported_scheme(path)))
+    }
+
+    fn read_text(&self, path: &Path) -> Result<Vec<u8>> {
+        self.with_fs(path, |vfs| vfs.read_text(path))
+            .unwrap_or_else(|| Err(Self::unsupported_scheme(path)))
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.with_fs(path, |vfs| vfs.is_file(path)).unwrap_or(false)
+    }
+
+    fn read_first_lines(&self, path: &Path, n: usize) -> Result<Vec<String>> {
+        self.with_fs(path, |vfs| vfs.read_first_lines(path, n))
+            .unwrap_or_else(|| Err(Self::unsupported_scheme(path)))
+    }
+
+    fn count_lines(&self, path: &Path) -> Result<usize> {
+        self.with_fs(path, |vfs| vfs.count_lines(path))
+            .unwrap_or_else(|| Err(Self::unsupported_scheme(path)))
+    }
+
+    fn read_lines(&self, path: &Path, start: usize, end: usize) -> Result<(Vec<String>, usize)> {
+        self.with_fs(path, |vfs| vfs.read_lines(path, start, end))
+            .unwrap_or_else(|| Err(Self::unsupported_scheme(path)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay_fs() {
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+
+        let mut fs = OverlayFs::default();
+        assert!(fs.is_file(&manifest));
+        let disk_lines = fs.read_first_lines(&manifest, 1).unwrap();
+
+        // The unsaved buffer shadows the file on disk.
+        fs.buffers()
+            .insert(manifest.clone(), vec!["modified".into(), "lines".into()]);
+        assert_eq!(
+            fs.read_first_lines(&manifest, 5).unwrap(),
+            ["modified", "lines"]
+        );
+        assert_eq!(fs.count_lines(&manifest).unwrap(), 2);
+        fs.buffers().remove(&manifest);
+        assert_eq!(fs.read_first_lines(&manifest, 1).unwrap(), disk_lines);
+
+        let virtual_path = Path::new("fugitive:///project/.git//0/src/lib.rs");
+        assert_eq!(uri_scheme(virtual_path), Some("fugitive"));
+        assert_eq!(uri_scheme(Path::new("C://foo")), None);
+        assert!(!fs.is_file(virtual_path));
+        assert_eq!(
+            fs.read(virtual_path).unwrap_err().kind(),
+            ErrorKind::Unsupported
+        );
+
+        let fugitive = BufferFs::default();
+        fugitive.insert(virtual_path.to_path_buf(), vec!["fn main() {}".into()]);
+        fs.register_scheme("fugitive", Arc::new(fugitive));
+        assert_eq!(fs.read(virtual_path).unwrap(), b"fn main() {}\n");
+    }
+
+    #[test]
+    fn test_large_file_lines() {
+        let path = std::env::temp_dir().join(format!("clap_large_file_{}", std::process::id()));
+        let line = "x".repeat(1023);
+        let total = (LARGE_FILE_SIZE / 1024) as usize + 1;
+        let mut content = String::with_capacity(total * 1032);
+        for idx in 0..total {
+            content.push_str(&format!("{idx:08}{line}\n"));
+        }
+        std::fs::write(&path, content).unwrap();
+
+        let (lines, count) = DiskFs.read_lines(&path, total - 2, total + 5).unwrap();
+        assert_eq!(count, total);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with(&format!("{:08}", total - 1)));
+        assert_eq!(DiskFs.count_lines(&path).unwrap(), total);
+
+        // The index is extended once the file is appended.
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| std::io::Write::write_all(&mut file, b"appended\n"))
+            .unwrap();
+        let (lines, count) = DiskFs.read_lines(&path, total, total + 1).unwrap();
+        assert_eq!(count, total + 1);
+        assert_eq!(lines, ["appended"]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_line_index() {
+        let path = std::env::temp_dir().join(format!("clap_line_index_{}", std::process::id()));
+        let index_of = |content: &str| {
+            std::fs::write(&path, content).unwrap();
+            let mut index = LineIndex::new(path.clone(), None);
+            let mut file = File::open(&path).unwrap();
+            index.extend(&mut file, content.len() as u64).unwrap();
+            index
+        };
+
+        let mut index = index_of("foo\r\nbar");
+        assert_eq!(index.total(), 2);
+
+        // The last line without the newline is continued by the appended part.
+        std::fs::write(&path, "foo\r\nbar baz\nqux\n").unwrap();
+        let mut file = File::open(&path).unwrap();
+        assert!(index.is_prefix_of(&mut file, 17).unwrap());
+        index.extend(&mut file, 17).unwrap();
+        assert_eq!(index.read_lines(0, 5).unwrap(), ["foo", "bar baz", "qux"]);
+
+        // Rewritten in place.
+        let index = index_of("foo\nbar\n");
+        std::fs::write(&path, "fox\nbar\nbaz\n").unwrap();
+        assert!(!index
+            .is_prefix_of(&mut File::open(&path).unwrap(), 12)
+            .unwrap());
+
+        // Truncated after being indexed.
+        std::fs::write(&path, "fo").unwrap();
+        assert_eq!(index.read_lines(0, 2).unwrap(), ["fo"]);
+
+        // The lines of UTF-16 are split at the newline code units and transcoded.
+        let utf16le = "中\n\u{a0a}文\r\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        std::fs::write(&path, &utf16le).unwrap();
+        let mut index = LineIndex::new(path.clone(), Some(UTF_16LE));
+        let mut file = File::open(&path).unwrap();
+        index.extend(&mut file, utf16le.len() as u64).unwrap();
+        assert_eq!(index.total(), 2);
+        assert_eq!(index.read_lines(0, 2).unwrap(), ["中", "\u{a0a}文"]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Error, Expr, ExprLit, Fields, Lit, Meta};
+
+/// Returns the text of the doc comments in `attrs`, one line per doc comment.
+fn doc_text(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(name_value) => match &name_value.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(doc), ..
+                }) => Some(doc.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|line| match line.strip_prefix(' ') {
+            Some(line) => line.to_string(),
+            None => line,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn config_schema_derive_impl(input: &DeriveInput) -> TokenStream {
+    let named_fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Error::new_spanned(input, "ConfigSchema requires the named fields")
+                    .to_compile_error()
+                    .into()
+            }
+        },
+        _ => {
+            return Error::new_spanned(input, "ConfigSchema can only be derived for structs")
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    let fields = named_fields.iter().map(|field| {
+        let name = field
+            .ident
+            .as_ref()
+            .expect("Named field must have an ident")
+            .to_string();
+        let doc = doc_text(&field.attrs);
+        let ty = &field.ty;
+
+        // The kind of the field is resolved by the type through the autoref-based
+        // specialization, see `crate::config_editor::schema_probe`.
+        quote! {
+            crate::config_editor::SchemaField {
+                name: #name,
+                doc: #doc,
+                kind: {
+                    #[allow(unused_imports)]
+                    use crate::config_editor::schema_probe::{
+                        MapKind, StructKind, ValueKind, ValueMapKind,
+                    };
+                    (&&crate::config_editor::schema_probe::Probe::<#ty>::new()).field_kind()
+                },
+            }
+        }
+    });
+
+    let ident = &input.ident;
+
+    quote! {
+        impl crate::config_editor::ConfigSchema for #ident {
+            fn schema() -> Vec<crate::config_editor::SchemaField> {
+                vec![#(#fields),*]
+            }
+        }
+    }
+    .into()
+}
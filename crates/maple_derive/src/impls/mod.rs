@@ -1,2 +1,3 @@
 pub mod clap_plugin;
+pub mod config_schema;
 pub mod subscriptions;
@@ -10,6 +10,16 @@ pub fn clap_plugin_derive(input: TokenStream) -> TokenStream {
     }
 }
 
+/// Implements `ConfigSchema` listing the fields of the config struct with their doc
+/// comments, used by the `config` provider.
+#[proc_macro_derive(ConfigSchema)]
+pub fn config_schema_derive(input: TokenStream) -> TokenStream {
+    match syn::parse(input) {
+        Ok(ast) => impls::config_schema::config_schema_derive_impl(&ast),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 #[proc_macro_attribute]
 pub fn subscriptions(_attr: TokenStream, item: TokenStream) -> TokenStream {
     impls::subscriptions::subscriptions_impl(item)
@@ -1,4 +1,5 @@
 pub mod fzy;
+pub mod prefilter;
 pub mod skim;
 pub mod substring;
 
@@ -0,0 +1,91 @@
+//! Cheap rejection of the texts which can never match the fuzzy query, before
+//! running the expensive scoring algorithm on them.
+//!
+//! The scoring algorithms are at least O(query * text) and allocate for the case
+//! folding, while most of the items in a large source are rejected by the
+//! necessary conditions checked here in a single pass over the bytes.
+//!
+//! The checks are conservative: a text is only rejected if it can't match under
+//! any [`types::CaseMatching`], the non-ASCII texts are always passed through.
+
+/// Bitmask of the ASCII characters in a text, case-insensitively.
+///
+/// Similar to the character set check of fzf, a text can't contain the query as a
+/// subsequence if the query has any character missing in the text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CharBag(u64);
+
+/// Bit of each byte, the letters are case-folded and the bytes out of the
+/// alphanumeric range share the remaining bits. Non-ASCII bytes set all the bits.
+const BYTE_BITS: [u64; 256] = {
+    let mut table = [0u64; 256];
+    let mut byte = 0usize;
+    while byte < 256 {
+        let b = byte as u8;
+        table[byte] = if b.is_ascii_lowercase() {
+            1 << (b - b'a')
+        } else if b.is_ascii_uppercase() {
+            1 << (b - b'A')
+        } else if b.is_ascii_digit() {
+            1 << (26 + b - b'0')
+        } else if b.is_ascii() {
+            1 << (36 + b % 28)
+        } else {
+            u64::MAX
+        };
+        byte += 1;
+    }
+    table
+};
+
+impl CharBag {
+    /// Returns the characters of `text`.
+    ///
+    /// The loop is a branch-free OR-reduction which the compiler can vectorize.
+    #[inline]
+    pub fn from_text(text: &str) -> Self {
+        Self(
+            text.bytes()
+                .fold(0u64, |bag, byte| bag | BYTE_BITS[byte as usize]),
+        )
+    }
+
+    /// Returns the characters of `query`, the non-ASCII characters are ignored as
+    /// their case folding can't be handled bytewise.
+    pub fn from_query(query: &str) -> Self {
+        Self(
+            query
+                .bytes()
+                .filter(u8::is_ascii)
+                .fold(0u64, |bag, byte| bag | BYTE_BITS[byte as usize]),
+        )
+    }
+
+    /// Returns `true` if all the characters of `other` are in `self`.
+    #[inline]
+    pub fn contains(self, other: Self) -> bool {
+        other.0 & !self.0 == 0
+    }
+}
+
+impl std::ops::BitOr for CharBag {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Returns `false` if `query` is definitely not a subsequence of `text` ignoring
+/// the ASCII case, which is required by all the fuzzy algorithms.
+#[inline]
+pub fn may_contain_subsequence(query: &str, text: &str) -> bool {
+    if !query.is_ascii() || !text.is_ascii() {
+        return true;
+    }
+
+    let mut text_bytes = text.bytes();
+    query
+        .bytes()
+        .all(|q| text_bytes.any(|t| t.eq_ignore_ascii_case(&q)))
+}
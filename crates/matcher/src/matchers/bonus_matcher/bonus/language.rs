@@ -3,15 +3,25 @@
 //! Ref: https://github.com/jacktasia/dumb-jump/blob/master/dumb-jump.el
 
 use crate::Score;
+use dumb_analyzer::CommentSyntax;
+use std::sync::Arc;
 
 pub type FileExtension = String;
 
 #[derive(Debug, Clone)]
-pub struct Language(FileExtension);
+pub struct Language {
+    ext: FileExtension,
+    /// Resolved once per search, the lookup takes the lock of the user comment syntax.
+    comment_syntax: Arc<CommentSyntax>,
+}
 
 impl<T: AsRef<str>> From<T> for Language {
     fn from(s: T) -> Self {
-        Self(s.as_ref().into())
+        let ext = s.as_ref();
+        Self {
+            ext: ext.into(),
+            comment_syntax: dumb_analyzer::get_comment_syntax(ext),
+        }
     }
 }
 
@@ -19,10 +29,10 @@ impl Language {
     pub fn calc_bonus(&self, bonus_text: &str, base_score: Score) -> Score {
         let trimmed = bonus_text.trim_start();
 
-        if dumb_analyzer::is_comment(trimmed, &self.0) {
+        if self.comment_syntax.is_comment_line(trimmed) {
             -(base_score / 5)
         } else {
-            match dumb_analyzer::calculate_pattern_priority(trimmed, &self.0) {
+            match dumb_analyzer::calculate_pattern_priority(trimmed, &self.ext) {
                 Some(priority) => base_score / priority.as_i32(),
                 None => 0,
             }
@@ -0,0 +1,21 @@
+use crate::Score;
+
+/// Items pinned by the user in a provider.
+#[derive(Debug, Clone)]
+pub struct Pinned(Vec<String>);
+
+impl From<Vec<String>> for Pinned {
+    fn from(inner: Vec<String>) -> Self {
+        Self(inner)
+    }
+}
+
+impl Pinned {
+    pub fn calc_bonus(&self, bonus_text: &str, base_score: Score) -> Score {
+        if self.0.iter().any(|s| s == bonus_text) {
+            base_score / 2
+        } else {
+            0
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::algo::prefilter::{may_contain_subsequence, CharBag};
 use crate::algo::FuzzyAlgorithm;
 use std::sync::Arc;
 use types::{CaseMatching, ClapItem, FuzzyTerm, FuzzyText, MatchResult, MatchScope, Score};
@@ -8,6 +9,8 @@ pub struct FuzzyMatcher {
     pub fuzzy_algo: FuzzyAlgorithm,
     pub fuzzy_terms: Vec<FuzzyTerm>,
     pub case_matching: CaseMatching,
+    /// Characters of all the fuzzy terms, computed once per query.
+    query_bag: CharBag,
 }
 
 impl FuzzyMatcher {
@@ -17,11 +20,15 @@ impl FuzzyMatcher {
         fuzzy_terms: Vec<FuzzyTerm>,
         case_matching: CaseMatching,
     ) -> Self {
+        let query_bag = fuzzy_terms.iter().fold(CharBag::default(), |bag, term| {
+            bag | CharBag::from_query(&term.text)
+        });
         Self {
             match_scope,
             fuzzy_algo,
             fuzzy_terms,
             case_matching,
+            query_bag,
         }
     }
 
@@ -36,6 +43,11 @@ impl FuzzyMatcher {
     }
 
     pub fn match_fuzzy_text(&self, fuzzy_text: &FuzzyText) -> Option<(Score, Vec<usize>)> {
+        // Reject the text early if it misses any character of the query.
+        if !CharBag::from_text(fuzzy_text.text).contains(self.query_bag) {
+            return None;
+        }
+
         let fuzzy_len = self.fuzzy_terms.iter().map(|f| f.len()).sum();
 
         // Try the fuzzy terms against the matched text.
@@ -44,6 +56,9 @@ impl FuzzyMatcher {
 
         for term in self.fuzzy_terms.iter() {
             let query = &term.text;
+            if !may_contain_subsequence(query, fuzzy_text.text) {
+                return None;
+            }
             if let Some(MatchResult { score, indices }) =
                 self.fuzzy_algo
                     .fuzzy_match(query, fuzzy_text, self.case_matching)
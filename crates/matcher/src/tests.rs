@@ -245,3 +245,38 @@ fn test_rank() {
         println!("{matched_item:?}");
     }
 }
+
+#[test]
+fn test_prefilter() {
+    use crate::algo::prefilter::{may_contain_subsequence, CharBag};
+
+    let text = "crates/maple_core/src/lib.rs";
+    let bag = CharBag::from_text(text);
+    assert!(bag.contains(CharBag::from_query("MapleLib")));
+    assert!(!bag.contains(CharBag::from_query("zap")));
+    // Non-ASCII texts are never rejected.
+    assert!(CharBag::from_text("café").contains(CharBag::from_query("xyz")));
+
+    assert!(may_contain_subsequence("MCORE", text));
+    assert!(!may_contain_subsequence("libmaple", text));
+
+    // The prefilter must not reject any text matched by the algorithms.
+    for (query, line) in [
+        ("srlib", text),
+        (
+            "rules",
+            "crates/maple_cli/src/lib.rs:2:1:macro_rules! println_json {",
+        ),
+        ("Lib", "crates/extracted_fzy/src/lib.rs"),
+    ] {
+        for algo in [FuzzyAlgorithm::Fzy, FuzzyAlgorithm::Skim] {
+            let fuzzy_text = FuzzyText::new(line, 0);
+            let matched = algo.fuzzy_match(query, &fuzzy_text, CaseMatching::Ignore);
+            assert_eq!(
+                matched.is_some(),
+                may_contain_subsequence(query, line)
+                    && CharBag::from_text(line).contains(CharBag::from_query(query))
+            );
+        }
+    }
+}
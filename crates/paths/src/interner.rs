@@ -0,0 +1,90 @@
+//! Interning of the directories shared by lots of paths, e.g., the files containing the
+//! grep matches in a monorepo, so that each directory is stored only once in memory.
+
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Path of which the parent directory is shared with the other paths interned by the
+/// same [`PathInterner`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InternedPath {
+    dir: Arc<Path>,
+    file_name: Box<OsStr>,
+}
+
+impl InternedPath {
+    /// Expands the path, meant to be called only when the path is displayed.
+    pub fn to_path_buf(&self) -> PathBuf {
+        self.dir.join(&*self.file_name)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn file_name(&self) -> &OsStr {
+        &self.file_name
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PathInterner {
+    dirs: Mutex<HashSet<Arc<Path>>>,
+}
+
+impl PathInterner {
+    pub fn intern(&self, path: &Path) -> InternedPath {
+        let (dir, file_name) = match (path.parent(), path.file_name()) {
+            (Some(dir), Some(file_name)) => (dir, file_name),
+            _ => (Path::new(""), path.as_os_str()),
+        };
+
+        let mut dirs = self.dirs.lock().unwrap_or_else(|err| err.into_inner());
+        let dir = match dirs.get(dir) {
+            Some(interned) => interned.clone(),
+            None => {
+                let interned: Arc<Path> = Arc::from(dir);
+                dirs.insert(interned.clone());
+                interned
+            }
+        };
+
+        InternedPath {
+            dir,
+            file_name: file_name.into(),
+        }
+    }
+
+    /// Returns the number of the interned directories.
+    pub fn len(&self) -> usize {
+        self.dirs
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_interner() {
+        let interner = PathInterner::default();
+        let a = interner.intern(Path::new("/repo/crates/foo/src/lib.rs"));
+        let b = interner.intern(Path::new("/repo/crates/foo/src/main.rs"));
+        let c = interner.intern(Path::new("README.md"));
+
+        assert_eq!(interner.len(), 2);
+        assert!(Arc::ptr_eq(&a.dir, &b.dir));
+        assert_eq!(a.to_path_buf(), Path::new("/repo/crates/foo/src/lib.rs"));
+        assert_eq!(b.file_name(), "main.rs");
+        assert_eq!(c.to_path_buf(), Path::new("README.md"));
+    }
+}
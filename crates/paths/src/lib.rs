@@ -1,3 +1,7 @@
+mod interner;
+
+pub use self::interner::{InternedPath, PathInterner};
+
 use dirs::Dirs;
 use itertools::Itertools;
 use serde::de::Error as DeserializeError;
@@ -121,6 +125,36 @@ pub fn expand_tilde(path: impl AsRef<str>) -> PathBuf {
     }
 }
 
+/// Whether the paths are compared case-insensitively on the current platform, which
+/// is the default of the filesystems on macOS and Windows.
+pub const CASE_INSENSITIVE_PATHS: bool = cfg!(any(target_os = "macos", target_os = "windows"));
+
+/// Returns `path` folded to the lowercase if `case_insensitive`.
+fn fold_path_case(path: &str, case_insensitive: bool) -> Cow<'_, str> {
+    if case_insensitive && path.chars().any(char::is_uppercase) {
+        path.to_lowercase().into()
+    } else {
+        path.into()
+    }
+}
+
+/// Returns the key of `path` for the deduplication and caching, `Foo.rs` and `foo.rs`
+/// have the same key on the case-insensitive platforms.
+pub fn path_case_key(path: &str) -> Cow<'_, str> {
+    fold_path_case(path, CASE_INSENSITIVE_PATHS)
+}
+
+/// Same as [`path_case_key`] but for [`Path`].
+pub fn path_buf_case_key(path: &Path) -> Cow<'_, Path> {
+    if !CASE_INSENSITIVE_PATHS {
+        return path.into();
+    }
+    match path.to_str().map(path_case_key) {
+        Some(Cow::Owned(folded)) => PathBuf::from(folded).into(),
+        _ => path.into(),
+    }
+}
+
 // /home/xlc/.rustup/toolchains/stable-x86_64-unknown-linux-gnu/lib/rustlib/src/rust/library/alloc/src/string.rs
 pub fn truncate_absolute_path(abs_path: &str, max_len: usize) -> Cow<'_, str> {
     if abs_path.len() > max_len {
@@ -222,6 +256,20 @@ where
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_fold_path_case() {
+        assert_eq!(fold_path_case("/src/Foo.rs", true), "/src/foo.rs");
+        assert_eq!(fold_path_case("/src/Foo.rs", false), "/src/Foo.rs");
+        assert!(matches!(
+            fold_path_case("/src/foo.rs", true),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(
+            path_case_key("Foo.rs") == path_case_key("foo.rs"),
+            CASE_INSENSITIVE_PATHS
+        );
+    }
+
     #[test]
     #[ignore = "Not sure why the behavior is differnt in CI"]
     fn test_truncate_absolute_path() {
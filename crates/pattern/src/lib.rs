@@ -5,8 +5,11 @@ use regex::Regex;
 
 static GREP_POS: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(.*?):(\d+):(\d+):(.*)").unwrap());
 
+// Match the grep line without column, e.g., the output of `grep -n`.
+static GREP_POS_NO_COLUMN: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(.+?):(\d+):(.*)").unwrap());
+
 static DUMB_JUMP_LINE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"^\[(.*)\](.*?):(\d+):(\d+):").unwrap());
+    Lazy::new(|| Regex::new(r"^\[([^\]]*)\](.*?):(\d+):(\d+):").unwrap());
 
 // Match the file path and line number of grep line.
 static GREP_STRIP_FPATH: Lazy<Regex> = Lazy::new(|| Regex::new(r"^.*?:\d+:\d+:").unwrap());
@@ -21,6 +24,10 @@ static BUFFER_TAGS: Lazy<Regex> = Lazy::new(|| Regex::new(r"^.*:(\d+).*\[(.*)\]"
 static COMMIT_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^.*\d{4}-\d{2}-\d{2}\s+([0-9a-z]+)\s+").unwrap());
 
+// Match the entry of quickfix list formatted by `clap#provider#quickfix#into_qf_line()`.
+static QUICKFIX_POS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(.*?)\|(\d+) col (\d+)\| ?(.*)").unwrap());
+
 static GTAGS: Lazy<Regex> = Lazy::new(|| Regex::new(r"(.*)\s+(\d+)\s+(.*)").unwrap());
 
 pub fn parse_gtags(line: &str) -> Option<(usize, &str, &str)> {
@@ -56,14 +63,53 @@ pub fn extract_grep_pattern(line: &str) -> Option<(&str, usize)> {
 }
 
 /// Returns a tuple of (fpath, lnum, col, line_content).
+///
+/// The leading whitespaces are ignored, the column defaults to 1 if the line has
+/// only the line number, e.g., the output of `grep -n`.
 pub fn extract_grep_position(line: &str) -> Option<(&str, usize, usize, &str)> {
-    let cap = GREP_POS.captures(line)?;
+    let line = line.trim_start();
+
+    if let Some(cap) = GREP_POS.captures(line) {
+        let fpath = cap.get(1).map(|x| x.as_str())?;
+        let str2nr = |idx: usize| cap.get(idx).map(|x| x.as_str()).and_then(parse_lnum);
+        let lnum = str2nr(2)?;
+        let col = str2nr(3)?;
+        let line_content = cap.get(4).map(|x| x.as_str())?;
+        return Some((fpath, lnum, col, line_content));
+    }
+
+    let cap = GREP_POS_NO_COLUMN.captures(line)?;
+    let fpath = cap.get(1).map(|x| x.as_str())?;
+    let lnum = cap.get(2).map(|x| x.as_str()).and_then(parse_lnum)?;
+    let line_content = cap.get(3).map(|x| x.as_str())?;
+    Some((fpath, lnum, 1, line_content))
+}
+
+/// Returns a tuple of (fpath, lnum, col, text) of the quickfix entry line, e.g.,
+/// `src/lib.rs|12 col 5| unused variable`.
+///
+/// The line number and column are 0 if the entry has no position.
+pub fn extract_quickfix_position(line: &str) -> Option<(&str, usize, usize, &str)> {
+    let cap = QUICKFIX_POS.captures(line)?;
     let fpath = cap.get(1).map(|x| x.as_str())?;
     let str2nr = |idx: usize| cap.get(idx).map(|x| x.as_str()).and_then(parse_lnum);
     let lnum = str2nr(2)?;
     let col = str2nr(3)?;
-    let line_content = cap.get(4).map(|x| x.as_str())?;
-    Some((fpath, lnum, col, line_content))
+    let text = cap.get(4).map(|x| x.as_str())?;
+    Some((fpath, lnum, col, text))
+}
+
+/// Returns the file path of a grep line carrying no line number, e.g., the message
+/// `path: binary file matches (found "\0" byte around offset 42)` of ripgrep.
+///
+/// The result is a best-effort guess, the caller is expected to check its existence.
+pub fn extract_grep_path_lossy(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let fpath = match line.find(": ") {
+        Some(idx) => &line[..idx],
+        None => line,
+    };
+    (!fpath.is_empty()).then_some(fpath)
 }
 
 /// Returns a tuple of (end_of_path, start_of_line).
@@ -84,9 +130,9 @@ pub fn parse_grep_item(line: &str) -> Option<(usize, usize)> {
     })
 }
 
-/// Returns a tuple of (fpath, lnum, col).
+/// Returns a tuple of (def_kind, fpath, lnum, col).
 pub fn extract_jump_line_info(line: &str) -> Option<(&str, &str, usize, usize)> {
-    let cap = DUMB_JUMP_LINE.captures(line)?;
+    let cap = DUMB_JUMP_LINE.captures(line.trim_start())?;
     let def_kind = cap.get(1).map(|x| x.as_str())?;
     let fpath = cap.get(2).map(|x| x.as_str())?;
     let str2nr = |idx: usize| cap.get(idx).map(|x| x.as_str()).and_then(parse_lnum);
@@ -182,6 +228,111 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tolerant_grep_position() {
+        // Colons in the line content.
+        assert_eq!(
+            extract_grep_position("src/lib.rs:3:7:let url = \"http://a:80\";"),
+            Some(("src/lib.rs", 3, 7, "let url = \"http://a:80\";"))
+        );
+        // Windows drive letter.
+        assert_eq!(
+            extract_grep_position(r"C:\Users\foo\main.rs:10:2:fn main() {"),
+            Some((r"C:\Users\foo\main.rs", 10, 2, "fn main() {"))
+        );
+        // Leading whitespaces.
+        assert_eq!(
+            extract_grep_position("   install.sh:1:5:#!/usr/bin/env bash"),
+            Some(("install.sh", 1, 5, "#!/usr/bin/env bash"))
+        );
+        // No column.
+        assert_eq!(
+            extract_grep_position("install.sh:12:echo 1:2:3"),
+            Some(("install.sh", 12, 1, "echo 1:2:3"))
+        );
+        // Binary marker.
+        let line = "target/debug/maple: binary file matches (found \"\\0\" byte around offset 7)";
+        assert_eq!(extract_grep_position(line), None);
+        assert_eq!(extract_grep_path_lossy(line), Some("target/debug/maple"));
+
+        assert_eq!(
+            extract_jump_line_info("[variable]src/a.rs:3:9:    let x = v[0]; // [tag]"),
+            Some(("variable", "src/a.rs", 3, 9))
+        );
+    }
+
+    #[test]
+    fn test_quickfix_position() {
+        assert_eq!(
+            extract_quickfix_position("src/lib.rs|12 col 5| unused variable: `x`"),
+            Some(("src/lib.rs", 12, 5, "unused variable: `x`"))
+        );
+        let line = "test/bench/python/test_fuzzy_filter.vim|0 col 0| Modified 2月,13 2021 10:58:27 rw-rw-r--";
+        assert_eq!(
+            extract_quickfix_position(line),
+            Some((
+                "test/bench/python/test_fuzzy_filter.vim",
+                0,
+                0,
+                "Modified 2月,13 2021 10:58:27 rw-rw-r--"
+            ))
+        );
+        assert_eq!(extract_quickfix_position("src/lib.rs:12:5:foo"), None);
+    }
+
+    #[test]
+    fn test_fuzz_grep_line_parsers() {
+        const ALPHABET: &[&str] = &[
+            ":",
+            ":",
+            "1",
+            "23",
+            " ",
+            "\t",
+            "\\",
+            "/",
+            "[",
+            "]",
+            "a",
+            "z.rs",
+            "C:",
+            "\0",
+            "中",
+            "👨\u{200d}👩",
+            "e\u{301}",
+            "\r",
+        ];
+
+        // xorshift64 for deterministic runs.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..20_000 {
+            let len = (next() % 24) as usize;
+            let line = (0..len)
+                .map(|_| ALPHABET[(next() % ALPHABET.len() as u64) as usize])
+                .collect::<String>();
+
+            if let Some((fpath, _lnum, _col, line_content)) = extract_grep_position(&line) {
+                assert!(line.trim_start().starts_with(fpath), "{line:?}");
+                assert!(line.ends_with(line_content), "{line:?}");
+            }
+            if let Some((_kind, fpath, _lnum, _col)) = extract_jump_line_info(&line) {
+                assert!(line.contains(fpath), "{line:?}");
+            }
+            if let Some((end_of_path, start_of_line)) = parse_grep_item(&line) {
+                assert!(end_of_path < start_of_line && start_of_line <= line.len());
+            }
+            let _ = extract_grep_path_lossy(&line);
+            let _ = extract_grep_pattern(&line);
+        }
+    }
+
     #[test]
     fn test_dumb_jump_line() {
         let line = "[variable]crates/maple_cli/src/stdio_server/session/context.rs:36:8:        let cwd = msg.get_cwd().into();";
@@ -0,0 +1,242 @@
+use crate::path_suffix::PathSuffixIndex;
+use crate::trimmer::UnicodeDots;
+use crate::truncation::LinesTruncatedMap;
+use crate::GrepResult;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use types::MatchedItem;
+
+/// Layout of the display lines adapted to the width of the display window.
+///
+/// The narrower layout formats the items more compactly before resorting to the
+/// truncation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// Items are displayed as is.
+    #[default]
+    Full,
+    /// Icons and the secondary columns of the items are hidden.
+    Compact,
+    /// Same as [`Self::Compact`], and the paths which can not fit into the window are
+    /// shortened to the unique suffix among the displayed paths.
+    Narrow,
+}
+
+impl Layout {
+    /// Negotiates the layout given the display `line_width`, `compact_width` and
+    /// `narrow_width` are the widths below which the corresponding layout is used.
+    pub fn negotiate(line_width: usize, compact_width: usize, narrow_width: usize) -> Self {
+        if line_width < narrow_width {
+            Self::Narrow
+        } else if line_width < compact_width {
+            Self::Compact
+        } else {
+            Self::Full
+        }
+    }
+
+    pub fn hide_icon(&self) -> bool {
+        !matches!(self, Self::Full)
+    }
+
+    /// Whether to drop the secondary columns of the items, e.g., the file size of the
+    /// buffers, which are formatted by the providers.
+    pub fn drop_columns(&self) -> bool {
+        !matches!(self, Self::Full)
+    }
+
+    pub fn shorten_paths(&self) -> bool {
+        matches!(self, Self::Narrow)
+    }
+}
+
+/// Returns the byte offset of the shortest unique suffix of each path, the suffix is
+/// always made of the whole path components.
+///
+/// The offset is 0 if the whole path is needed to tell it from the others.
+pub fn unique_suffix_starts<'a>(paths: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+    let paths = paths.into_iter().collect::<Vec<_>>();
+    let index = PathSuffixIndex::new(paths.iter().copied());
+    paths
+        .into_iter()
+        .map(|path| index.unique_suffix_start(path))
+        .collect()
+}
+
+/// Replaces `text[..suffix_start]` with the dots, returns the new text and the number
+/// of chars removed before the dots.
+fn shorten_text(text: &str, suffix_start: usize) -> (String, usize) {
+    // Keep the separator before the suffix.
+    let (prefix, suffix) = text.split_at(suffix_start - 1);
+    (
+        format!("{}{suffix}", UnicodeDots::DOTS),
+        prefix.chars().count(),
+    )
+}
+
+/// Drops the indices of the removed leading chars and shifts the rest, `inserted_chars`
+/// is the number of chars inserted in place of the removed ones.
+fn shift_indices(indices: &mut Vec<usize>, removed_chars: usize, inserted_chars: usize) {
+    indices.retain(|&idx| idx >= removed_chars);
+    indices
+        .iter_mut()
+        .for_each(|idx| *idx = *idx - removed_chars + inserted_chars);
+}
+
+/// Displays the items which are file paths as their unique suffixes in `index`.
+///
+/// Returns the map of the shortened lines to the original lines.
+pub(crate) fn shorten_to_unique_suffix(
+    matched_items: &mut [MatchedItem],
+    index: &PathSuffixIndex,
+) -> LinesTruncatedMap {
+    let mut shortened_map = HashMap::new();
+    for (idx, matched_item) in matched_items.iter_mut().enumerate() {
+        let output_text = matched_item.output_text().into_owned();
+        let start = index.unique_suffix_start(&output_text);
+        if start == 0 {
+            continue;
+        }
+        shift_indices(
+            &mut matched_item.indices,
+            output_text[..start].chars().count(),
+            0,
+        );
+        matched_item
+            .output_text
+            .replace(output_text[start..].to_string());
+        shortened_map.insert(idx + 1, output_text);
+    }
+    shortened_map
+}
+
+/// Shortens the items which are file paths too long to fit into `line_width`.
+///
+/// Returns the map of the shortened lines to the original lines.
+pub(crate) fn shorten_item_paths(
+    matched_items: &mut [MatchedItem],
+    line_width: usize,
+) -> LinesTruncatedMap {
+    let output_texts = matched_items
+        .iter()
+        .map(|matched_item| matched_item.output_text().into_owned())
+        .collect::<Vec<_>>();
+    let starts = unique_suffix_starts(output_texts.iter().map(String::as_str));
+
+    let mut shortened_map = HashMap::new();
+    for (idx, ((matched_item, output_text), start)) in matched_items
+        .iter_mut()
+        .zip(output_texts)
+        .zip(starts)
+        .enumerate()
+    {
+        if start == 0 || output_text.chars().count() <= line_width {
+            continue;
+        }
+        let (shortened, removed_chars) = shorten_text(&output_text, start);
+        shift_indices(
+            &mut matched_item.indices,
+            removed_chars,
+            UnicodeDots::CHAR_LEN,
+        );
+        matched_item.output_text.replace(shortened);
+        shortened_map.insert(idx + 1, output_text);
+    }
+    shortened_map
+}
+
+/// Shortens the file paths of grep results which are too long to fit into `line_width`.
+///
+/// Returns the map of the shortened lines to the original lines.
+pub(crate) fn shorten_grep_paths(
+    grep_results: &mut [GrepResult],
+    line_width: usize,
+) -> LinesTruncatedMap {
+    let paths = grep_results
+        .iter()
+        .map(|grep_result| grep_result.path.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let starts = unique_suffix_starts(paths.iter().map(String::as_str));
+
+    let mut shortened_map = HashMap::new();
+    for (idx, ((grep_result, path), start)) in
+        grep_results.iter_mut().zip(paths).zip(starts).enumerate()
+    {
+        let output_text = grep_result.matched_item.output_text().into_owned();
+        if start == 0
+            || !output_text.starts_with(&path)
+            || output_text.chars().count() <= line_width
+        {
+            continue;
+        }
+        let (shortened, removed_chars) = shorten_text(&output_text, start);
+        let removed_bytes = output_text.len() - shortened.len();
+        shift_indices(
+            &mut grep_result.matched_item.indices,
+            removed_chars,
+            UnicodeDots::CHAR_LEN,
+        );
+        grep_result.matched_item.output_text.replace(shortened);
+        grep_result.path = PathBuf::from(shorten_text(&path, start).0);
+        grep_result.column_end -= removed_bytes;
+        shortened_map.insert(idx + 1, output_text);
+    }
+    shortened_map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_layout_negotiate() {
+        assert_eq!(Layout::negotiate(100, 60, 40), Layout::Full);
+        assert_eq!(Layout::negotiate(50, 60, 40), Layout::Compact);
+        assert_eq!(Layout::negotiate(30, 60, 40), Layout::Narrow);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_unique_suffix_starts() {
+        let paths = [
+            "crates/maple_core/src/lib.rs",
+            "crates/printer/src/lib.rs",
+            "crates/printer/src/layout.rs",
+            "README.md",
+            "crates/printer/src/layout.rs",
+        ];
+        let suffixes = paths
+            .iter()
+            .zip(unique_suffix_starts(paths))
+            .map(|(path, start)| &path[start..])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            suffixes,
+            vec![
+                "maple_core/src/lib.rs",
+                "printer/src/lib.rs",
+                "layout.rs",
+                "README.md",
+                "layout.rs",
+            ]
+        );
+
+        let mut items = paths
+            .iter()
+            .map(|path| {
+                MatchedItem::new(Arc::new(path.to_string()), Default::default(), vec![0, 25])
+            })
+            .collect::<Vec<_>>();
+        let shortened_map = shorten_item_paths(&mut items, 20);
+        assert_eq!(items[0].output_text(), "…/maple_core/src/lib.rs");
+        assert_eq!(items[0].indices, vec![20]);
+        assert_eq!(items[2].output_text(), "…/layout.rs");
+        assert_eq!(items[3].output_text(), "README.md");
+        assert_eq!(
+            shortened_map.get(&1).map(String::as_str),
+            Some("crates/maple_core/src/lib.rs")
+        );
+        assert!(!shortened_map.contains_key(&4));
+    }
+}
@@ -1,6 +1,8 @@
 //! This crate provides the feature of displaying the information of filtered lines
 //! by printing them to stdout in JSON format.
 
+mod layout;
+mod path_suffix;
 mod trimmer;
 mod truncation;
 
@@ -8,10 +10,13 @@ use self::truncation::LinesTruncatedMap;
 use icon::{Icon, ICON_CHAR_LEN};
 use serde::Serialize;
 use std::path::PathBuf;
+use std::sync::Arc;
 use truncation::truncate_grep_results;
 use types::MatchedItem;
 use utils::char_indices_to_byte_indices;
 
+pub use self::layout::{unique_suffix_starts, Layout};
+pub use self::path_suffix::PathSuffixIndex;
 pub use self::trimmer::v1::{trim_text, TrimInfo, TrimmedText};
 pub use self::truncation::{
     truncate_grep_lines, truncate_item_output_text, truncate_item_output_text_v0,
@@ -60,6 +65,16 @@ pub struct DisplayLines {
     ///
     /// The icon is added after the truncation.
     pub icon_added: bool,
+    /// Highlights provided by the items, `(start, length, highlight_group)` in bytes.
+    ///
+    /// The highlights of the truncated lines are dropped as the highlighted text may
+    /// have been trimmed.
+    #[serde(skip_serializing_if = "has_no_highlights")]
+    pub highlights: Vec<Vec<(usize, usize, &'static str)>>,
+}
+
+fn has_no_highlights(highlights: &[Vec<(usize, usize, &'static str)>]) -> bool {
+    highlights.iter().all(Vec::is_empty)
 }
 
 impl DisplayLines {
@@ -74,6 +89,7 @@ impl DisplayLines {
             indices,
             truncated_map,
             icon_added,
+            highlights: Vec::new(),
         }
     }
 
@@ -83,54 +99,90 @@ impl DisplayLines {
             indices,
             truncated_map,
             icon_added,
+            ..
         } = self;
 
         println_json!(lines, indices, truncated_map, icon_added, total);
     }
 }
 
+/// Returns the item highlights on the display line of `matched_item`, `icon_len` is the
+/// byte length of the icon prepended to the line.
+fn item_highlights(
+    matched_item: &MatchedItem,
+    icon_len: usize,
+) -> Vec<(usize, usize, &'static str)> {
+    matched_item
+        .item
+        .highlights()
+        .into_iter()
+        .map(|hl| (hl.start + icon_len, hl.length, hl.group))
+        .collect()
+}
+
 fn convert_truncated_matched_items_to_display_lines(
     matched_items: impl IntoIterator<Item = MatchedItem>,
     icon: Icon,
     mut truncated_map: LinesTruncatedMap,
 ) -> DisplayLines {
+    let mut lines = Vec::new();
+    let mut indices = Vec::new();
+    let mut highlights = Vec::new();
+
     if let Some(icon_kind) = icon.icon_kind() {
-        let (lines, indices): (Vec<_>, Vec<Vec<usize>>) = matched_items
-            .into_iter()
-            .enumerate()
-            .map(|(idx, matched_item)| {
-                let display_text = matched_item.display_text();
-                let iconized = if let Some(output_text) = truncated_map.get_mut(&(idx + 1)) {
-                    let icon = matched_item
-                        .item
-                        .icon(icon)
-                        .expect("Icon must be provided if specified");
-                    *output_text = format!("{icon} {output_text}");
-                    format!("{icon} {display_text}")
-                } else {
-                    icon_kind.add_icon_to_text(&display_text)
-                };
-                let (line, indices) = (iconized, matched_item.shifted_indices(ICON_CHAR_LEN));
-                let indices = char_indices_to_byte_indices(&line, &indices);
-                (line, indices)
-            })
-            .unzip();
-
-        DisplayLines::new(lines, indices, truncated_map, true)
+        for (idx, matched_item) in matched_items.into_iter().enumerate() {
+            let display_text = matched_item.display_text();
+            let (iconized, truncated) = if let Some(output_text) = truncated_map.get_mut(&(idx + 1))
+            {
+                let icon = matched_item
+                    .item
+                    .icon(icon)
+                    .expect("Icon must be provided if specified");
+                *output_text = format!("{icon} {output_text}");
+                (format!("{icon} {display_text}"), true)
+            } else {
+                // The item may tell the icon from its structure rather than the text.
+                let icon = matched_item
+                    .item
+                    .icon(icon)
+                    .unwrap_or_else(|| icon_kind.icon(&display_text));
+                (format!("{icon} {display_text}"), false)
+            };
+            let line_highlights = if truncated {
+                Vec::new()
+            } else {
+                item_highlights(&matched_item, iconized.len() - display_text.len())
+            };
+            let line_indices = char_indices_to_byte_indices(
+                &iconized,
+                &matched_item.shifted_indices(ICON_CHAR_LEN),
+            );
+            lines.push(iconized);
+            indices.push(line_indices);
+            highlights.push(line_highlights);
+        }
+
+        DisplayLines {
+            highlights,
+            ..DisplayLines::new(lines, indices, truncated_map, true)
+        }
     } else {
-        let (lines, indices): (Vec<_>, Vec<_>) = matched_items
-            .into_iter()
-            .map(|matched_item| {
-                let (line, indices) = (
-                    matched_item.display_text().to_string(),
-                    matched_item.indices,
-                );
-                let indices = char_indices_to_byte_indices(&line, &indices);
-                (line, indices)
-            })
-            .unzip();
-
-        DisplayLines::new(lines, indices, truncated_map, false)
+        for (idx, matched_item) in matched_items.into_iter().enumerate() {
+            let line = matched_item.display_text().to_string();
+            let line_highlights = if truncated_map.contains_key(&(idx + 1)) {
+                Vec::new()
+            } else {
+                item_highlights(&matched_item, 0)
+            };
+            indices.push(char_indices_to_byte_indices(&line, &matched_item.indices));
+            lines.push(line);
+            highlights.push(line_highlights);
+        }
+
+        DisplayLines {
+            highlights,
+            ..DisplayLines::new(lines, indices, truncated_map, false)
+        }
     }
 }
 
@@ -139,6 +191,10 @@ pub struct Printer {
     pub line_width: usize,
     pub icon: Icon,
     pub truncate_text: bool,
+    /// Layout of the items, the paths are shortened only if the items are file paths.
+    pub layout: Layout,
+    /// Displays the file paths as their unique suffixes among the paths in the index.
+    pub path_suffix: Option<Arc<PathSuffixIndex>>,
 }
 
 impl Printer {
@@ -148,21 +204,47 @@ impl Printer {
             line_width,
             icon,
             truncate_text: true,
+            layout: Layout::Full,
+            path_suffix: None,
         }
     }
 
+    /// Sets the layout negotiated with the display window.
+    pub fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Sets the index for displaying the file paths as their unique suffixes.
+    pub fn with_path_suffix(mut self, path_suffix: Option<Arc<PathSuffixIndex>>) -> Self {
+        self.path_suffix = path_suffix;
+        self
+    }
+
     pub fn to_display_lines(&self, mut matched_items: Vec<MatchedItem>) -> DisplayLines {
         let Self {
             line_width,
             icon,
             truncate_text,
+            layout,
+            path_suffix,
         } = self;
 
-        let truncated_map = if *truncate_text {
+        let shortened_map = if let Some(index) = path_suffix {
+            layout::shorten_to_unique_suffix(&mut matched_items, index)
+        } else if layout.shorten_paths() {
+            layout::shorten_item_paths(&mut matched_items, *line_width)
+        } else {
+            Default::default()
+        };
+
+        let mut truncated_map = if *truncate_text {
             truncate_item_output_text(matched_items.iter_mut(), *line_width, None)
         } else {
             Default::default()
         };
+        // The original lines take precedence over the shortened ones.
+        truncated_map.extend(shortened_map);
 
         convert_truncated_matched_items_to_display_lines(matched_items, *icon, truncated_map)
     }
@@ -182,8 +264,16 @@ pub fn grep_results_to_display_lines(
     mut grep_results: Vec<GrepResult>,
     line_width: usize,
     icon: Icon,
+    layout: Layout,
 ) -> DisplayLines {
-    let truncated_map = truncate_grep_results(grep_results.iter_mut(), line_width, None);
+    let shortened_map = if layout.shorten_paths() {
+        layout::shorten_grep_paths(&mut grep_results, line_width)
+    } else {
+        Default::default()
+    };
+    let mut truncated_map = truncate_grep_results(grep_results.iter_mut(), line_width, None);
+    // The original lines take precedence over the shortened ones.
+    truncated_map.extend(shortened_map);
     convert_truncated_matched_items_to_display_lines(
         grep_results.into_iter().map(|i| i.matched_item),
         icon,
@@ -240,6 +330,93 @@ pub(crate) mod tests {
         winwidth: usize,
     }
 
+    #[test]
+    fn test_item_highlights() {
+        #[derive(Debug)]
+        struct HighlightedItem(String);
+
+        impl ClapItem for HighlightedItem {
+            fn raw_text(&self) -> &str {
+                &self.0
+            }
+
+            fn highlights(&self) -> Vec<types::ItemHighlight> {
+                vec![types::ItemHighlight {
+                    start: 0,
+                    length: 5,
+                    group: "Error",
+                }]
+            }
+        }
+
+        let item = |text: String, indices: Vec<usize>| {
+            MatchedItem::new(Arc::new(HighlightedItem(text)), Default::default(), indices)
+        };
+        // The matched elements at the end force the long line to be truncated.
+        let items = vec![
+            item("error: short".into(), vec![0]),
+            item("error: ".repeat(20), vec![130]),
+        ];
+
+        let display_lines = Printer::new(30, Icon::Null).to_display_lines(items.clone());
+        // The highlights of the truncated line are dropped.
+        assert_eq!(
+            display_lines.highlights,
+            vec![vec![(0, 5, "Error")], vec![]]
+        );
+
+        let display_lines =
+            Printer::new(100, Icon::Enabled(icon::IconKind::File)).to_display_lines(items);
+        let icon_len = display_lines.lines[0].len() - "error: short".len();
+        assert_eq!(display_lines.highlights[0], vec![(icon_len, 5, "Error")]);
+    }
+
+    #[test]
+    fn test_item_icon() {
+        #[derive(Debug)]
+        struct MarkItem(String);
+
+        impl ClapItem for MarkItem {
+            fn raw_text(&self) -> &str {
+                &self.0
+            }
+
+            fn icon(&self, _icon: Icon) -> Option<icon::IconType> {
+                Some(icon::file_icon("main.rs"))
+            }
+        }
+
+        let icon = Icon::Enabled(icon::IconKind::File);
+
+        // The icon of the plain item is derived from the text as before.
+        let items = vec![MatchedItem::new(
+            Arc::new(SourceItem::from("src/lib.rs".to_string())),
+            Default::default(),
+            vec![0],
+        )];
+        let display_lines = Printer::new(100, icon).to_display_lines(items);
+        assert_eq!(
+            display_lines.lines,
+            vec![icon::IconKind::File.add_icon_to_text("src/lib.rs")]
+        );
+
+        // The icon told by the item is used no matter the line is truncated or not.
+        let item = |text: String, indices: Vec<usize>| {
+            MatchedItem::new(Arc::new(MarkItem(text)), Default::default(), indices)
+        };
+        let items = vec![
+            item("a  12  short".into(), vec![0]),
+            item(format!("b  34  {}", "long ".repeat(20)), vec![105]),
+        ];
+        let display_lines = Printer::new(30, icon).to_display_lines(items);
+        let expected_icon = icon::file_icon("main.rs");
+        assert!(display_lines.truncated_map.contains_key(&2));
+        assert!(display_lines
+            .lines
+            .iter()
+            .all(|line| line.starts_with(expected_icon)));
+    }
+
     pub(crate) fn filter_single_line(
         line: impl Into<SourceItem>,
         query: impl Into<Query>,
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::path::MAIN_SEPARATOR;
+
+#[derive(Debug, Clone, Default)]
+struct SuffixNode {
+    /// Number of the distinct paths ending with the components from the root to this node.
+    count: usize,
+    /// Whether a path ends exactly at this node.
+    is_path: bool,
+    children: HashMap<Box<str>, SuffixNode>,
+}
+
+/// Trie of the reversed path components, used for finding the shortest suffix which
+/// tells a path from all the other paths in the set, e.g., `src/lib.rs` for
+/// `crates/foo/src/lib.rs` if `crates/bar/lib.rs` is also in the set.
+///
+/// Both the insertion and the lookup are `O(depth)`, so the index can be maintained
+/// over the whole result set incrementally.
+#[derive(Debug, Clone, Default)]
+pub struct PathSuffixIndex {
+    root: SuffixNode,
+}
+
+/// Returns the reversed components of `path` along with their byte offsets.
+fn reversed_components(path: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut end = path.len();
+    path.rmatch_indices(MAIN_SEPARATOR)
+        .map(|(idx, sep)| idx + sep.len())
+        .chain(std::iter::once(0))
+        .map(move |start| {
+            let component = &path[start..end];
+            end = start.saturating_sub(MAIN_SEPARATOR.len_utf8());
+            (start, component)
+        })
+}
+
+impl PathSuffixIndex {
+    pub fn new<'a>(paths: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut index = Self::default();
+        paths.into_iter().for_each(|path| index.insert(path));
+        index
+    }
+
+    /// Adds `path` to the index, the duplicate path is ignored.
+    pub fn insert(&mut self, path: &str) {
+        let mut node = &mut self.root;
+        for (_, component) in reversed_components(path) {
+            node = node.children.entry(component.into()).or_default();
+        }
+        if node.is_path {
+            return;
+        }
+        node.is_path = true;
+
+        let mut node = &mut self.root;
+        for (_, component) in reversed_components(path) {
+            node = node
+                .children
+                .get_mut(component)
+                .expect("Nodes have been created; qed");
+            node.count += 1;
+        }
+    }
+
+    /// Returns the byte offset of the shortest unique suffix of `path`, 0 if the whole
+    /// path is needed or the path is not in the index.
+    pub fn unique_suffix_start(&self, path: &str) -> usize {
+        let mut node = &self.root;
+        for (start, component) in reversed_components(path) {
+            match node.children.get(component) {
+                Some(child) if child.count == 1 => return start,
+                Some(child) => node = child,
+                None => return 0,
+            }
+        }
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_path_suffix_index() {
+        let mut index = PathSuffixIndex::new([
+            "crates/foo/src/lib.rs",
+            "crates/bar/src/lib.rs",
+            "crates/bar/src/main.rs",
+            "/abs/lib.rs",
+        ]);
+        let suffix =
+            |index: &PathSuffixIndex, path: &'static str| &path[index.unique_suffix_start(path)..];
+
+        assert_eq!(suffix(&index, "crates/foo/src/lib.rs"), "foo/src/lib.rs");
+        assert_eq!(suffix(&index, "crates/bar/src/main.rs"), "main.rs");
+        assert_eq!(suffix(&index, "/abs/lib.rs"), "abs/lib.rs");
+        assert_eq!(suffix(&index, "not/indexed.rs"), "not/indexed.rs");
+
+        // Duplicate paths do not change the counts.
+        index.insert("crates/bar/src/main.rs");
+        assert_eq!(suffix(&index, "crates/bar/src/main.rs"), "main.rs");
+
+        index.insert("main.rs");
+        assert_eq!(suffix(&index, "crates/bar/src/main.rs"), "src/main.rs");
+        assert_eq!(suffix(&index, "main.rs"), "main.rs");
+    }
+}
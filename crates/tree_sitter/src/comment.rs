@@ -0,0 +1,48 @@
+use tree_sitter_core::{Point, Tree};
+
+/// Returns `true` if `line`, the 0-based line `row` of the source of `tree`, starts in a
+/// comment node, which covers the lines in the middle of a block comment as well.
+pub fn is_comment_line(tree: &Tree, row: usize, line: &str) -> bool {
+    let Some(column) = line.bytes().position(|b| !b.is_ascii_whitespace()) else {
+        return false;
+    };
+
+    let point = Point::new(row, column);
+    let mut maybe_node = tree.root_node().descendant_for_point_range(point, point);
+    while let Some(node) = maybe_node {
+        // `comment`, `line_comment`, `block_comment`, etc.
+        if node.kind().ends_with("comment") {
+            return true;
+        }
+        maybe_node = node.parent();
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Language;
+
+    #[test]
+    fn test_is_comment_line() {
+        let source = r#"
+/* Block comment
+   without the leading star. */
+fn foo() {
+    // Line comment.
+    let x = 1; // Trailing comment.
+}
+"#;
+        let tree = crate::parse(Language::Rust, source.as_bytes()).unwrap();
+        let lines = source.split('\n').collect::<Vec<_>>();
+        let is_comment = |row: usize| is_comment_line(&tree, row, lines[row]);
+        assert!(is_comment(1));
+        assert!(is_comment(2));
+        assert!(!is_comment(3));
+        assert!(is_comment(4));
+        assert!(!is_comment(5));
+        assert!(!is_comment(0));
+    }
+}
@@ -0,0 +1,171 @@
+use tree_sitter_core::{Node, Point, Tree};
+
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Returns the key path of the value at the 0-based line `row` in the JSON/TOML
+/// `source` parsed into `tree`, e.g., `services.web.environment[3]`.
+///
+/// Returns `None` if the line is not inside any value.
+pub fn key_path(tree: &Tree, source: &[u8], row: usize) -> Option<String> {
+    let line = source.split(|b| *b == b'\n').nth(row)?;
+    let column = line.iter().take_while(|b| b.is_ascii_whitespace()).count();
+    let point = Point::new(row, column);
+    let node = tree
+        .root_node()
+        .named_descendant_for_point_range(point, point)?;
+
+    let mut segments = Vec::new();
+    // The child of `parent` on the way from the node to the root.
+    let mut child: Option<Node> = None;
+    let mut ancestor = Some(node);
+    while let Some(parent) = ancestor {
+        match parent.kind() {
+            "array" => {
+                if let Some(child) = child {
+                    let mut cursor = parent.walk();
+                    let index = parent
+                        .named_children(&mut cursor)
+                        .filter(|node| node.kind() != "comment")
+                        .position(|node| node.id() == child.id());
+                    if let Some(index) = index {
+                        segments.push(Segment::Index(index));
+                    }
+                }
+            }
+            "pair" | "table" => {
+                if let Some(key) = key_text(parent, source) {
+                    segments.push(Segment::Key(key));
+                }
+            }
+            "table_array_element" => {
+                if let Some(key) = key_text(parent, source) {
+                    let mut index = 0;
+                    let mut sibling = parent.prev_named_sibling();
+                    while let Some(prev) = sibling {
+                        if prev.kind() == parent.kind()
+                            && key_text(prev, source).as_ref() == Some(&key)
+                        {
+                            index += 1;
+                        }
+                        sibling = prev.prev_named_sibling();
+                    }
+                    segments.push(Segment::Index(index));
+                    segments.push(Segment::Key(key));
+                }
+            }
+            _ => {}
+        }
+        child = Some(parent);
+        ancestor = parent.parent();
+    }
+
+    if segments.is_empty() {
+        return None;
+    }
+
+    let mut key_path = String::new();
+    for segment in segments.into_iter().rev() {
+        match segment {
+            Segment::Key(key) => {
+                if !key_path.is_empty() {
+                    key_path.push('.');
+                }
+                key_path.push_str(&key);
+            }
+            Segment::Index(index) => key_path.push_str(&format!("[{index}]")),
+        }
+    }
+
+    Some(key_path)
+}
+
+/// Returns the key of the JSON pair or TOML pair/table with the quotes stripped.
+fn key_text(node: Node, source: &[u8]) -> Option<String> {
+    let key = node
+        .child_by_field_name("key")
+        .or_else(|| node.named_child(0))?;
+    let text = key.utf8_text(source).ok()?;
+    let text = match key.kind() {
+        "dotted_key" => text
+            .split('.')
+            .map(|part| part.trim().trim_matches(|c| c == '"' || c == '\''))
+            .collect::<Vec<_>>()
+            .join("."),
+        _ => text.trim_matches(|c| c == '"' || c == '\'').to_string(),
+    };
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Language;
+
+    fn parse_key_path(language: Language, source: &str, row: usize) -> Option<String> {
+        let tree = crate::parse(language, source.as_bytes())?;
+        key_path(&tree, source.as_bytes(), row)
+    }
+
+    #[test]
+    fn test_key_path() {
+        let json = r#"{
+  "services": {
+    "web": {
+      "environment": [
+        "A=1",
+        {"name": "B"},
+        "C=3"
+      ]
+    }
+  }
+}"#;
+        let cases = [
+            (1, Some("services")),
+            (3, Some("services.web.environment")),
+            (4, Some("services.web.environment[0]")),
+            (5, Some("services.web.environment[1]")),
+            (6, Some("services.web.environment[2]")),
+            (0, None),
+        ];
+        for (row, expected) in cases {
+            assert_eq!(
+                parse_key_path(Language::Json, json, row).as_deref(),
+                expected,
+                "row: {row}"
+            );
+        }
+
+        let toml = r#"[package]
+name = "maple"
+
+[[bin]]
+name = "a"
+
+[[bin]]
+name = "b"
+features = [
+  "x",
+  "y",
+]
+
+[dependencies.serde]
+"#;
+        let cases = [
+            (1, Some("package.name")),
+            (4, Some("bin[0].name")),
+            (7, Some("bin[1].name")),
+            (10, Some("bin[1].features[1]")),
+            (13, Some("dependencies.serde")),
+        ];
+        for (row, expected) in cases {
+            assert_eq!(
+                parse_key_path(Language::Toml, toml, row).as_deref(),
+                expected,
+                "row: {row}"
+            );
+        }
+    }
+}
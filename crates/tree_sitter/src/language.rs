@@ -98,6 +98,38 @@ pub enum Language {
 }
 
 impl Language {
+    /// All the supported languages.
+    pub const ALL: [Self; 11] = [
+        Self::Bash,
+        Self::C,
+        Self::Cpp,
+        Self::Go,
+        Self::Javascript,
+        Self::Json,
+        Self::Markdown,
+        Self::Python,
+        Self::Rust,
+        Self::Toml,
+        Self::Viml,
+    ];
+
+    /// Returns the name of language.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Bash => "bash",
+            Self::C => "c",
+            Self::Cpp => "cpp",
+            Self::Go => "go",
+            Self::Javascript => "javascript",
+            Self::Json => "json",
+            Self::Markdown => "markdown",
+            Self::Python => "python",
+            Self::Rust => "rust",
+            Self::Toml => "toml",
+            Self::Viml => "viml",
+        }
+    }
+
     /// Constructs a new instance of [`Language`] from the file extension if any.
     pub fn try_from_extension(extension: &str) -> Option<Self> {
         let language = match extension {
@@ -162,6 +194,23 @@ impl Language {
         }
     }
 
+    /// Returns the tree-sitter grammar of this language.
+    pub fn grammar(&self) -> tree_sitter_core::Language {
+        match self {
+            Self::Bash => tree_sitter_bash::language(),
+            Self::C => tree_sitter_c::language(),
+            Self::Cpp => tree_sitter_cpp::language(),
+            Self::Go => tree_sitter_go::language(),
+            Self::Javascript => tree_sitter_javascript::language(),
+            Self::Json => tree_sitter_json::language(),
+            Self::Markdown => tree_sitter_md::language(),
+            Self::Python => tree_sitter_python::language(),
+            Self::Rust => tree_sitter_rust::language(),
+            Self::Toml => tree_sitter_toml::language(),
+            Self::Viml => tree_sitter_vim::language(),
+        }
+    }
+
     pub fn highlight_query(&self) -> &str {
         match self {
             Self::Bash => tree_sitter_bash::HIGHLIGHT_QUERY,
@@ -1,13 +1,22 @@
+mod comment;
+mod key_path;
 mod language;
+mod signature;
+mod symbols;
 mod utf8_char_indices;
 
 use std::cell::RefCell;
 use std::collections::{BTreeMap, HashSet};
-use tree_sitter_core::{Node, Point, TreeCursor};
+use tree_sitter_core::{Node, Parser, Point, TreeCursor};
 use tree_sitter_highlight::{Highlight, HighlightConfiguration, HighlightEvent, Highlighter};
 
+pub use self::comment::is_comment_line;
+pub use self::key_path::key_path;
 pub use self::language::Language;
+pub use self::signature::function_signatures;
+pub use self::symbols::{top_level_symbols, Symbol};
 pub use self::utf8_char_indices::{UncheckedUtf8CharIndices, Utf8CharIndices};
+pub use tree_sitter_core::Tree;
 pub use tree_sitter_highlight::Error as HighlightError;
 
 /// Parse .scm file for a list of node names.
@@ -54,6 +63,15 @@ pub struct HighlightItem {
     pub highlight: Highlight,
 }
 
+/// Parses `source` into the syntax tree.
+///
+/// Returns `None` if the grammar is incompatible or the parsing is cancelled.
+pub fn parse(language: Language, source: &[u8]) -> Option<Tree> {
+    let mut parser = Parser::new();
+    parser.set_language(language.grammar()).ok()?;
+    parser.parse(source, None)
+}
+
 thread_local! {
     static HIGHLIGHTER: RefCell<Highlighter> = RefCell::new(Highlighter::new());
 }
@@ -0,0 +1,134 @@
+use crate::Language;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use tree_sitter_core::{Node, Parser};
+
+thread_local! {
+    static PARSER: RefCell<Parser> = RefCell::new(Parser::new());
+}
+
+/// Returns the signatures of the functions defined in `source`, i.e., the type
+/// parameters, the parameters and the return type, e.g., `(x: i32, y: i32) -> Self` of
+/// `pub fn new(x: i32, y: i32) -> Self {`, keyed by the 0-based line of the function name.
+///
+/// The whole source is parsed once, the functions with the incomplete parameters are
+/// skipped.
+pub fn function_signatures(language: Language, source: &[u8]) -> HashMap<usize, String> {
+    let Some(tree) = PARSER.with(|parser| {
+        let mut parser = parser.borrow_mut();
+        parser.set_language(language.grammar()).ok()?;
+        parser.parse(source, None)
+    }) else {
+        return HashMap::new();
+    };
+
+    let mut signatures = HashMap::new();
+    let mut cursor = tree.walk();
+    'walk: loop {
+        let node = cursor.node();
+        if let Some(signature) = signature(node, source) {
+            // The outer function wins if a closure is defined in the same line.
+            signatures.entry(name_line(node)).or_insert(signature);
+        }
+
+        if cursor.goto_first_child() || cursor.goto_next_sibling() {
+            continue;
+        }
+        loop {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+            if cursor.goto_next_sibling() {
+                break;
+            }
+        }
+    }
+
+    signatures
+}
+
+/// Returns the signature of `function` if it's a node with the parameters.
+fn signature(function: Node, source: &[u8]) -> Option<String> {
+    let parameters = function.child_by_field_name("parameters")?;
+    if parameters.has_error() {
+        return None;
+    }
+
+    let start = function
+        .child_by_field_name("type_parameters")
+        .filter(|node| !node.has_error())
+        .map_or(parameters.start_byte(), |node| node.start_byte());
+    let end = ["return_type", "result"]
+        .iter()
+        .find_map(|field| function.child_by_field_name(field))
+        .filter(|node| !node.has_error() && !node.is_missing())
+        .map_or(parameters.end_byte(), |node| node.end_byte());
+
+    let signature = std::str::from_utf8(source.get(start..end)?)
+        .ok()?
+        .split_whitespace()
+        .collect::<Vec<_>>();
+
+    Some(signature.join(" "))
+}
+
+/// Returns the line of the function name, which is the line of the ctags tag.
+fn name_line(function: Node) -> usize {
+    // The C function declarator has no name field.
+    function
+        .child_by_field_name("name")
+        .or_else(|| function.child_by_field_name("declarator"))
+        .unwrap_or(function)
+        .start_position()
+        .row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_function_signatures() {
+        let cases = [
+            (
+                Language::Rust,
+                "impl Point {\n    pub fn new(x: i32,\n        y: i32) -> Self {\n        let f = |a: i32| a;\n        Self { x, y }\n    }\n}\n\nfn parse<T: FromStr>(s: &str) -> Option<T> {\n}\n\npub struct Line {\n}\n",
+                vec![
+                    (1, "(x: i32, y: i32) -> Self"),
+                    (3, "|a: i32|"),
+                    (8, "<T: FromStr>(s: &str) -> Option<T>"),
+                ],
+            ),
+            (
+                Language::Python,
+                "@cache\ndef foo(a, b=1) -> int:\n    return a\n",
+                vec![(1, "(a, b=1) -> int")],
+            ),
+            (
+                Language::Go,
+                "package main\n\nfunc (p *Point) Dist(q Point) (float64, error) {\n}\n",
+                vec![(2, "(q Point) (float64, error)")],
+            ),
+            (
+                Language::C,
+                "static int\nadd(int a, int b) {\n  return a + b;\n}\n",
+                vec![(1, "(int a, int b)")],
+            ),
+            (
+                Language::Javascript,
+                "function   render(props,\tstate) {\n}\n",
+                vec![(0, "(props, state)")],
+            ),
+        ];
+
+        for (language, source, expected) in cases {
+            let signatures = function_signatures(language, source.as_bytes());
+            let mut signatures = signatures
+                .iter()
+                .map(|(line, signature)| (*line, signature.as_str()))
+                .collect::<Vec<_>>();
+            signatures.sort();
+            assert_eq!(signatures, expected, "{language:?}: {source}");
+        }
+    }
+}
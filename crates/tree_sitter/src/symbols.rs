@@ -0,0 +1,133 @@
+use crate::Language;
+use tree_sitter_core::{Node, Parser};
+
+/// Symbol defined at the top level of a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    /// Node kind without the syntax suffix, e.g., `function` for `function_item`.
+    pub kind: String,
+    /// 0-based line number of the symbol name.
+    pub line: usize,
+}
+
+/// Returns the symbols defined at the top level of `source`, e.g., the functions,
+/// types and modules, in the order of appearance.
+pub fn top_level_symbols(language: Language, source: &[u8]) -> Vec<Symbol> {
+    let mut parser = Parser::new();
+    if parser.set_language(language.grammar()).is_err() {
+        return Vec::new();
+    }
+
+    let Some(tree) = parser.parse(source, None) else {
+        return Vec::new();
+    };
+
+    let root = tree.root_node();
+    let mut cursor = root.walk();
+    let symbols = root
+        .named_children(&mut cursor)
+        .filter_map(|node| {
+            let (definition, name_node) = symbol_nodes(node)?;
+            let name = name_node.utf8_text(source).ok()?.trim();
+            if name.is_empty() || name.contains('\n') {
+                return None;
+            }
+            Some(Symbol {
+                name: name.to_string(),
+                kind: symbol_kind(definition.kind()).to_string(),
+                line: name_node.start_position().row,
+            })
+        })
+        .collect();
+    symbols
+}
+
+/// Returns the definition node and its name node.
+fn symbol_nodes(node: Node) -> Option<(Node, Node)> {
+    if let Some(name) = node.child_by_field_name("name") {
+        return Some((node, name));
+    }
+
+    match node.kind() {
+        // Python
+        "decorated_definition" => symbol_nodes(node.child_by_field_name("definition")?),
+        // Rust
+        "impl_item" => Some((node, node.child_by_field_name("type")?)),
+        // C/C++
+        "function_definition" | "declaration" => {
+            let mut declarator = node.child_by_field_name("declarator")?;
+            while let Some(inner) = declarator.child_by_field_name("declarator") {
+                declarator = inner;
+            }
+            declarator
+                .kind()
+                .ends_with("identifier")
+                .then_some((node, declarator))
+        }
+        // Go, JavaScript
+        "type_declaration" | "const_declaration" | "lexical_declaration" => {
+            let mut cursor = node.walk();
+            let spec = node.named_children(&mut cursor).next()?;
+            Some((node, spec.child_by_field_name("name")?))
+        }
+        _ => None,
+    }
+}
+
+/// Strips the syntax suffix of the node kind.
+fn symbol_kind(node_kind: &str) -> &str {
+    ["_item", "_definition", "_declaration", "_specifier"]
+        .iter()
+        .find_map(|suffix| node_kind.strip_suffix(suffix))
+        .unwrap_or(node_kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_level_symbols() {
+        let source = br#"
+use std::fmt;
+
+pub struct Point {
+    x: i32,
+}
+
+impl Point {
+    fn new() -> Self {
+        Self { x: 0 }
+    }
+}
+
+pub fn distance() {}
+"#;
+        let symbols = top_level_symbols(Language::Rust, source)
+            .into_iter()
+            .map(|s| (s.name, s.kind, s.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            symbols,
+            vec![
+                ("Point".into(), "struct".into(), 3),
+                ("Point".into(), "impl".into(), 7),
+                ("distance".into(), "function".into(), 13),
+            ]
+        );
+
+        let source = b"@decorator\ndef foo():\n    pass\n\nclass Bar:\n    pass\n";
+        let symbols = top_level_symbols(Language::Python, source)
+            .into_iter()
+            .map(|s| (s.name, s.kind, s.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            symbols,
+            vec![
+                ("foo".into(), "function".into(), 1),
+                ("Bar".into(), "class".into(), 4),
+            ]
+        );
+    }
+}
@@ -10,8 +10,8 @@ pub use self::search_term::{
     TermType, WordTerm,
 };
 pub use self::source_item::{
-    extract_fuzzy_text, AsAny, ClapItem, FileNameItem, FuzzyText, GrepItem, MatchScope,
-    MatchedItem, SourceItem,
+    extract_fuzzy_text, AsAny, ClapItem, FileNameItem, FuzzyText, GrepItem, ItemHighlight,
+    MatchScope, MatchedItem, SourceItem,
 };
 
 #[derive(Clone, Copy, Debug, Default)]
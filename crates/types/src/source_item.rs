@@ -75,6 +75,18 @@ impl<T: AsRef<str>> From<T> for MatchScope {
     }
 }
 
+/// Highlight of a part of the item text carrying the semantic info, e.g., the severity
+/// of a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemHighlight {
+    /// Byte offset in [`ClapItem::output_text`].
+    pub start: usize,
+    /// Length in bytes.
+    pub length: usize,
+    /// Highlight group in Vim.
+    pub group: &'static str,
+}
+
 /// This trait represents the items used in the entire filter pipeline.
 pub trait ClapItem: AsAny + std::fmt::Debug + Send + Sync {
     /// Initial raw text.
@@ -130,6 +142,14 @@ pub trait ClapItem: AsAny + std::fmt::Debug + Send + Sync {
     fn truncation_offset(&self) -> Option<usize> {
         None
     }
+
+    /// Returns the highlights of [`ClapItem::output_text`] beyond the matched elements.
+    ///
+    /// These highlights are added to the display window with a lower priority than
+    /// the highlights of the matched elements.
+    fn highlights(&self) -> Vec<ItemHighlight> {
+        Vec::new()
+    }
 }
 
 // Impl [`ClapItem`] for raw String.
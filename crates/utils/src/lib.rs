@@ -5,6 +5,7 @@ use std::ffi::OsStr;
 use std::hash::{Hash, Hasher};
 use std::path::Path;
 use std::process::{Command, Output};
+use unicode_segmentation::UnicodeSegmentation;
 
 pub mod bytelines;
 mod io;
@@ -85,3 +86,45 @@ pub fn char_indices_to_byte_indices(s: &str, char_indices: &[usize]) -> Vec<usiz
         })
         .collect::<Vec<_>>()
 }
+
+/// Returns the longest prefix of `s` in at most `max_len` bytes ending at a grapheme
+/// cluster boundary, the emoji and combining sequences are never split.
+pub fn truncate_at_grapheme_boundary(s: &str, max_len: usize) -> &str {
+    if s.len() <= max_len {
+        return s;
+    }
+
+    let end = s
+        .grapheme_indices(true)
+        .map(|(idx, grapheme)| idx + grapheme.len())
+        .take_while(|&end| end <= max_len)
+        .last()
+        .unwrap_or_default();
+
+    &s[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_at_grapheme_boundary() {
+        assert_eq!(truncate_at_grapheme_boundary("hello", 10), "hello");
+        assert_eq!(truncate_at_grapheme_boundary("hello", 3), "hel");
+        // CJK characters are 3 bytes each.
+        assert_eq!(truncate_at_grapheme_boundary("中文字符", 7), "中文");
+        // Family emoji joined by ZWJ is a single grapheme of 18 bytes.
+        let family = "👨\u{200d}👩\u{200d}👧";
+        assert_eq!(
+            truncate_at_grapheme_boundary(&format!("a{family}b"), 10),
+            "a"
+        );
+        assert_eq!(
+            truncate_at_grapheme_boundary(&format!("a{family}b"), 19),
+            format!("a{family}")
+        );
+        // `e` followed by the combining acute accent.
+        assert_eq!(truncate_at_grapheme_boundary("ae\u{301}", 2), "a");
+    }
+}
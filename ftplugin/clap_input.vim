@@ -135,3 +135,12 @@ nnoremap <silent> <buffer> <S-ScrollWheelDown> :<c-u>call clap#client#notify_pro
 
 inoremap <silent> <buffer> <S-ScrollWheelUp>   <C-R>=<SID>Notify('shift-up')<CR>
 inoremap <silent> <buffer> <S-ScrollWheelDown> <C-R>=<SID>Notify('shift-down')<CR>
+
+" Accept at the position of the scrolled preview
+nnoremap <silent> <buffer> <S-CR> :<c-u>call clap#client#notify_provider('accept_at_preview')<CR>
+
+inoremap <silent> <buffer> <S-CR> <C-R>=<SID>Notify('accept_at_preview')<CR>
+
+" Preview toggle
+nnoremap <silent> <buffer> <A-p> :<c-u>call clap#client#notify_provider('alt-p')<CR>
+inoremap <silent> <buffer> <A-p> <C-R>=<SID>Notify('alt-p')<CR>
@@ -59,26 +59,73 @@ with open('rg_pcre2_regex.json', 'w') as f:
 
 print(rules.keys())
 
+# Line comment prefixes and block comment delimiters of each file extension, `*`
+# is used for the unknown extensions.
+def comment_syntax(line, block=[]):
+    return {'line': line, 'block': block}
+
+c_like = comment_syntax(['//'], [['/*', '*/']])
+hash_line = comment_syntax(['#'])
+
 comments_map = {
-    '*': ['//'],
-    'lua': ['--'],
-    'erl': ['%'],
-    'hrl': ['%'],
-    'tex': ['%'],
-    'r': ['//'],
-    'go': ['//'],
-    'rs': ['//', '//!', '///'],
-    'cpp': ['//'],
-    'javascript': ['//'],
-    'typescript': ['//'],
-    'php': ['//', '#'],
-    'el': [';'],
-    'clj': [';'],
-    'exs': ['#'],
-    'perl': ['#'],
-    'py': ['#'],
-    'nim': ['#'],
-    'rb': ['#'],
+    '*': c_like,
+    'c': c_like,
+    'h': c_like,
+    'cc': c_like,
+    'cpp': c_like,
+    'hpp': c_like,
+    'cs': c_like,
+    'java': c_like,
+    'kt': c_like,
+    'scala': c_like,
+    'swift': c_like,
+    'dart': c_like,
+    'go': c_like,
+    'zig': comment_syntax(['//']),
+    'rs': comment_syntax(['//', '//!', '///'], [['/*', '*/']]),
+    'js': c_like,
+    'jsx': c_like,
+    'ts': c_like,
+    'tsx': c_like,
+    'javascript': c_like,
+    'typescript': c_like,
+    'css': comment_syntax([], [['/*', '*/']]),
+    'scss': c_like,
+    'less': c_like,
+    'php': comment_syntax(['//', '#'], [['/*', '*/']]),
+    'r': hash_line,
+    'lua': comment_syntax(['--'], [['--[[', ']]']]),
+    'sql': comment_syntax(['--'], [['/*', '*/']]),
+    'hs': comment_syntax(['--'], [['{-', '-}']]),
+    'elm': comment_syntax(['--'], [['{-', '-}']]),
+    'ml': comment_syntax([], [['(*', '*)']]),
+    'erl': comment_syntax(['%']),
+    'hrl': comment_syntax(['%']),
+    'tex': comment_syntax(['%']),
+    'el': comment_syntax([';']),
+    'clj': comment_syntax([';']),
+    'lisp': comment_syntax([';']),
+    'scm': comment_syntax([';']),
+    'ex': hash_line,
+    'exs': hash_line,
+    'pl': hash_line,
+    'perl': hash_line,
+    'py': hash_line,
+    'nim': comment_syntax(['#'], [['#[', ']#']]),
+    'rb': comment_syntax(['#'], [['=begin', '=end']]),
+    'sh': hash_line,
+    'bash': hash_line,
+    'zsh': hash_line,
+    'fish': hash_line,
+    'toml': hash_line,
+    'yaml': hash_line,
+    'yml': hash_line,
+    'cmake': hash_line,
+    'mk': hash_line,
+    'vim': comment_syntax(['"', '#']),
+    'html': comment_syntax([], [['<!--', '-->']]),
+    'xml': comment_syntax([], [['<!--', '-->']]),
+    'vue': comment_syntax(['//'], [['/*', '*/'], ['<!--', '-->']]),
 }
 
 with open('comments_map.json', 'w') as f:
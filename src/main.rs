@@ -1,5 +1,5 @@
 use clap::Parser;
-use cli::{Args, RunCmd};
+use cli::{Args, RunCmd, StartupConfig};
 
 const BUILD_TIME: &str = include!(concat!(env!("OUT_DIR"), "/compiled_at.txt"));
 
@@ -39,10 +39,21 @@ pub struct Maple {
     pub cmd: Cmd,
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     let maple = Maple::parse();
 
+    // The config file is parsed once, the thread pools are configured before the runtime.
+    let startup_config = maple.args.load_config();
+
+    maple
+        .args
+        .build_runtime(&startup_config)?
+        .block_on(run(maple, startup_config));
+
+    Ok(())
+}
+
+async fn run(maple: Maple, startup_config: StartupConfig) {
     match maple.cmd {
         Cmd::Version => {
             println!(
@@ -68,12 +79,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
         Cmd::Run(run_cmd) => {
-            if let Err(e) = run_cmd.run(maple.args).await {
+            cli::set_backend_version(format!(
+                "{}{}",
+                built_info::PKG_VERSION,
+                built_info::GIT_VERSION.map_or_else(|| "".to_owned(), |v| format!(" (git {v})"))
+            ));
+            if let Err(e) = run_cmd.run(maple.args, startup_config).await {
                 eprintln!("error: {e:?}");
                 std::process::exit(1);
             }
         }
     }
-
-    Ok(())
 }
@@ -0,0 +1,7 @@
+syntax match ClapDiagnosticsPath /^\(\S\s\)\?\zs.\{-}\ze:\d\+:\d\+ / nextgroup=ClapDiagnosticsPosition
+syntax match ClapDiagnosticsPosition /:\d\+:\d\+/ contained
+syntax match ClapDiagnosticsCode /\[[^\[\]]*\]$/
+
+highlight default link ClapDiagnosticsPath Directory
+highlight default link ClapDiagnosticsPosition LineNr
+highlight default link ClapDiagnosticsCode Title
@@ -0,0 +1,9 @@
+syntax match ClapGitBranchesHead /^\*/
+syntax match ClapGitBranchesName /^. \zs\S\+/ nextgroup=ClapGitBranchesHash skipwhite
+syntax match ClapGitBranchesHash /\x\{7,}/ contained
+syntax match ClapGitBranchesDate /([^()]*)$/
+
+highlight default link ClapGitBranchesHead Special
+highlight default link ClapGitBranchesName Identifier
+highlight default link ClapGitBranchesHash Number
+highlight default link ClapGitBranchesDate Comment
@@ -0,0 +1,9 @@
+syntax match ClapGitHunksPath /^.\{-}\ze:\d\+ +\d\+ -\d\+/ nextgroup=ClapGitHunksLnum
+syntax match ClapGitHunksLnum /:\d\+/ contained nextgroup=ClapGitHunksAdded skipwhite
+syntax match ClapGitHunksAdded /+\d\+/ contained nextgroup=ClapGitHunksRemoved skipwhite
+syntax match ClapGitHunksRemoved /-\d\+/ contained
+
+highlight default link ClapGitHunksPath Directory
+highlight default link ClapGitHunksLnum LineNr
+highlight default link ClapGitHunksAdded DiffAdd
+highlight default link ClapGitHunksRemoved DiffDelete
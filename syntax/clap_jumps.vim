@@ -1,7 +1,7 @@
-syntax match ClapJump /^\s\+\d\+/ nextgroup=ClapJumpLineCol
-syntax match ClapJumpsHeader /jump line  col file\/text/
-syntax match ClapJumpLineCol /\s\+\zs\d\+\ze\s\+/
+syntax match ClapJump /^\%(\S\s\)\?\zs[-+]\?\d\+/
+syntax match ClapJumpLineCol /\s\+\zs\d\+\s\+\d\+\ze\s\s/
+syntax match ClapJumpFileText /\d\+\s\+\d\+\s\s\zs.*$/
 
-hi default link ClapJump        Function
-hi default link ClapJumpsHeader Title
-hi default link ClapJumpLineCol Number
+hi default link ClapJump         Function
+hi default link ClapJumpLineCol  Number
+hi default link ClapJumpFileText String
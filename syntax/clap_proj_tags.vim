@@ -4,7 +4,9 @@ syntax match ClapProjTagKindPathSeperator /@/ contained
 syntax match ClapProjTagBrackets /\[\|\]/ contained
 syntax match ClapProjTagKind   /\[\zs.*\ze@\f*\]/ contained
 syntax match ClapProjTagPath /\[.*@\f*\]/ contains=ClapProjTagKind,ClapProjTagKindPathSeperator
-syntax match ClapProjTagPattern /^.*$/ contains=ClapTagName,ClapProjTagKind,ClapProjTagPath,ClapProjTagLnum
+" The signature of the function starts with the parameters or type parameters.
+syntax match ClapTagSignature /\(\]\s\+\)\@<=[(<].*$/ contained
+syntax match ClapProjTagPattern /^.*$/ contains=ClapTagName,ClapProjTagKind,ClapProjTagPath,ClapProjTagLnum,ClapTagSignature
 
 hi default link ClapProjTagName Type
 hi default link ClapProjTagKind Function
@@ -13,3 +15,4 @@ hi default link ClapProjTagPattern Identifier
 hi default link ClapProjTagKindPathSeperator String
 hi default link ClapProjTagLnum Number
 hi default link ClapProjTagBrackets Comment
+hi default link ClapTagSignature Comment
@@ -0,0 +1,26 @@
+#!/usr/bin/env bash
+
+cd "$(dirname "${BASH_SOURCE[0]}")"
+
+cd ..
+
+errors_file=$(mktemp)
+status=0
+
+for entry in test/vim/*_test.vim
+do
+  vim -Nu NONE -i NONE -es --cmd 'set rtp^=.' \
+    -c "source $entry" \
+    -c "call writefile(v:errors, '$errors_file')" \
+    -c 'qall!'
+  if [ -s "$errors_file" ]; then
+    echo "[ERROR] $entry"
+    cat "$errors_file"
+    status=1
+  else
+    echo "[PASS] $entry"
+  fi
+done
+
+rm -f "$errors_file"
+exit $status
@@ -0,0 +1,31 @@
+use std::collections::HashMap;
+
+fn main() {
+    let mut scores = HashMap::new();
+    scores.insert("中文字符", 1);
+    scores.insert("emoji 👨‍👩‍👧", 2);
+
+    for (name, score) in &scores {
+        println!("{name}: {score}");
+    }
+
+    let long_line = "这是一行非常长的文本，用于测试预览窗口中的截断行为，确保不会在多字节字符的中间截断，也不会拆分表情符号 👨‍👩‍👧 👍🏽 的字素簇，以及组合字符 é 的序列";
+    println!("{long_line}");
+}
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Point {
+    fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    fn distance(&self, other: &Point) -> f64 {
+        let dx = (self.x - other.x) as f64;
+        let dy = (self.y - other.y) as f64;
+        (dx * dx + dy * dy).sqrt()
+    }
+}
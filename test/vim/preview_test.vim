@@ -0,0 +1,12 @@
+" Tests of autoload/clap/preview.vim, run by test/run_vim_tests.sh.
+
+call assert_equal('\c\V\(foo\|bar\)', clap#preview#query_terms_pattern("'foo ^bar !baz"))
+call assert_equal('\c\V\(foo\|bar\)', clap#preview#query_terms_pattern('foo$ bar'))
+" Smart case as the matcher.
+call assert_equal('\C\V\(Foo\)', clap#preview#query_terms_pattern('Foo !bar'))
+" The backslash is literal in the very nomagic pattern.
+call assert_equal('\c\V\(a\\b\)', clap#preview#query_terms_pattern('a\b'))
+" No term the matched items must contain.
+call assert_equal('', clap#preview#query_terms_pattern('!foo'))
+call assert_equal('', clap#preview#query_terms_pattern("' ^"))
+call assert_equal('', clap#preview#query_terms_pattern(''))